@@ -1,5 +1,6 @@
 use candid::{CandidType, Deserialize, Principal};
 use ic_cdk::api::{time, caller};
+use ic_cdk::api::stable::{stable_grow, stable_read, stable_size, stable_write};
 use ic_cdk_macros::*;
 
 use std::cell::RefCell;
@@ -34,6 +35,13 @@ thread_local! {
         checkin_time_index: BTreeMap::new(),
         consecutive_days_index: BTreeMap::new(),
         total_points_index: BTreeMap::new(),
+        user_timezones: HashMap::new(),
+        streak_freezes: HashMap::new(),
+        streak_item_history: HashMap::new(),
+        outage_windows: Vec::new(),
+        next_outage_window_id: 0,
+        archived_points_history: HashMap::new(),
+        points_archive_length: 0,
     });
 }
 
@@ -45,7 +53,7 @@ struct Storage {
     consecutive_days: HashMap<Principal, u64>,
     // User points (simplified from the main system)
     user_points: HashMap<Principal, u64>,
-    // Points history
+    // Recent points history (older entries are archived to stable memory)
     points_history: HashMap<Principal, Vec<PointsTransaction>>,
     // Task configuration
     task_config: TaskConfig,
@@ -58,6 +66,78 @@ struct Storage {
     consecutive_days_index: BTreeMap<u64, Vec<Principal>>,
     // BTreeMap for total points (key: points, value: list of users)
     total_points_index: BTreeMap<u64, Vec<Principal>>,
+    // Per-user timezone used for local day boundaries
+    #[serde(default)]
+    user_timezones: HashMap<Principal, UserTimezone>,
+    // Streak-freeze tokens held by each user
+    #[serde(default)]
+    streak_freezes: HashMap<Principal, u64>,
+    // Streak item history (earned, purchased, used)
+    #[serde(default)]
+    streak_item_history: HashMap<Principal, Vec<StreakItemEvent>>,
+    // Admin-declared outage windows during which missed check-ins can be claimed later
+    #[serde(default)]
+    outage_windows: Vec<OutageWindow>,
+    #[serde(default)]
+    next_outage_window_id: u64,
+    // Stable memory offsets of each user's archived points transactions, oldest first
+    #[serde(default)]
+    archived_points_history: HashMap<Principal, Vec<u64>>,
+    // Bytes used in the stable memory points archive
+    #[serde(default)]
+    points_archive_length: u64,
+}
+
+// Page of a user's points history, newest first
+#[derive(CandidType, Deserialize, Clone)]
+struct PointsHistoryPage {
+    pub entries: Vec<PointsTransaction>,
+    pub total_count: u64,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+// Period during which the canister was unavailable
+#[derive(CandidType, Deserialize, Clone)]
+struct OutageWindow {
+    pub id: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub reason: String,
+    pub declared_by: Principal,
+    pub declared_at: u64,
+}
+
+// User timezone as a fixed offset from UTC
+#[derive(CandidType, Deserialize, Clone)]
+struct UserTimezone {
+    pub offset_minutes: i32,
+    pub updated_at: u64,
+}
+
+// Streak item history entry
+#[derive(CandidType, Deserialize, Clone)]
+enum StreakItemAction {
+    Earned,
+    Purchased,
+    Used,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+struct StreakItemEvent {
+    pub action: StreakItemAction,
+    pub timestamp: u64,
+    // Streak length at the time of the event
+    pub consecutive_days: u64,
+}
+
+// Streak items held by a user
+#[derive(CandidType, Deserialize, Clone)]
+struct StreakItemsResponse {
+    pub streak_freezes: u64,
+    pub max_streak_freezes: u64,
+    pub purchase_cost_points: u64,
+    pub history: Vec<StreakItemEvent>,
 }
 
 // Points transaction record
@@ -81,6 +161,24 @@ struct TaskConfig {
     pub enabled: bool,
 }
 
+// Task type, matching the main canister's TaskType
+#[derive(CandidType, Deserialize, Clone)]
+enum TaskType {
+    Daily,
+}
+
+// Task definition advertised to the main canister's task registry
+#[derive(CandidType, Deserialize, Clone)]
+struct TaskDefinition {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub points: u64,
+    pub task_type: TaskType,
+    pub completion_criteria: String,
+    pub expiration_time: Option<u64>,
+}
+
 // Task verification request from the main canister
 #[derive(CandidType, Deserialize, Clone)]
 struct TaskVerificationRequest {
@@ -116,6 +214,7 @@ struct DailyCheckInResponse {
     pub bonus_points: u64,
     pub total_points: u64,
     pub next_claim_available_at: u64,
+    pub streak_freeze_used: bool,
 }
 
 // User check-in detail record
@@ -138,19 +237,87 @@ struct PaginatedCheckInDetails {
 }
 
 // Constants
-const SECONDS_IN_DAY: u64 = 86400000;
+const MILLIS_IN_DAY: u64 = 86_400_000;
+const MILLIS_IN_MINUTE: i64 = 60_000;
+// UTC-12:00 to UTC+14:00
+const MIN_TIMEZONE_OFFSET_MINUTES: i32 = -12 * 60;
+const MAX_TIMEZONE_OFFSET_MINUTES: i32 = 14 * 60;
+// Prevents hopping timezones to claim the same day twice
+const TIMEZONE_CHANGE_COOLDOWN: u64 = 7 * MILLIS_IN_DAY;
+// Streak freezes cover a single missed day and are earned on completing a full bonus cycle
+const MAX_STREAK_FREEZES: u64 = 3;
+const STREAK_FREEZE_COST_POINTS: u64 = 50;
+const MAX_STREAK_ITEM_HISTORY: usize = 100;
+// Points history kept on the heap per user; older entries move to the stable memory archive
+const MAX_IN_MEMORY_POINTS_HISTORY: usize = 50;
+const MAX_POINTS_HISTORY_PAGE_SIZE: u64 = 100;
+// The archive lives above the region written by stable_save during upgrades
+const POINTS_ARCHIVE_REGION_START: u64 = 1024 * 1024 * 1024;
+const WASM_PAGE_SIZE: u64 = 65536;
 const DAILY_CHECK_IN_POINTS: u64 = 10;
 const MAX_CONSECUTIVE_BONUS_DAYS: u64 = 7;
 const CONSECUTIVE_DAYS_BONUS_MULTIPLIER: u64 = 2;
 
+// Start of the user's local day containing `timestamp`, expressed in UTC milliseconds
+fn local_day_start(timestamp: u64, offset_minutes: i32) -> u64 {
+    let offset = offset_minutes as i64 * MILLIS_IN_MINUTE;
+    let local = timestamp as i64 + offset;
+    (local - local.rem_euclid(MILLIS_IN_DAY as i64) - offset).max(0) as u64
+}
+
+fn user_timezone_offset(storage: &Storage, user: &Principal) -> i32 {
+    storage.user_timezones.get(user).map(|timezone| timezone.offset_minutes).unwrap_or(0)
+}
+
+// Start of the current local day for a user
+fn user_today_start(user: &Principal, now: u64) -> u64 {
+    STORAGE.with(|storage| local_day_start(now, user_timezone_offset(&storage.borrow(), user)))
+}
+
+// Set the caller's timezone offset from UTC in minutes (e.g. 480 for UTC+8)
+#[update]
+fn set_timezone_offset(offset_minutes: i32) -> Result<(), String> {
+    if !(MIN_TIMEZONE_OFFSET_MINUTES..=MAX_TIMEZONE_OFFSET_MINUTES).contains(&offset_minutes) {
+        return Err(format!(
+            "Timezone offset must be between {} and {} minutes",
+            MIN_TIMEZONE_OFFSET_MINUTES, MAX_TIMEZONE_OFFSET_MINUTES
+        ));
+    }
+    
+    let user = caller();
+    let now = time() / 1_000_000;
+    
+    STORAGE.with(|storage| {
+        let mut storage = storage.borrow_mut();
+        
+        if let Some(timezone) = storage.user_timezones.get(&user) {
+            if timezone.offset_minutes == offset_minutes {
+                return Ok(());
+            }
+            if now < timezone.updated_at + TIMEZONE_CHANGE_COOLDOWN {
+                return Err("Timezone can only be changed once every 7 days".to_string());
+            }
+        }
+        
+        storage.user_timezones.insert(user, UserTimezone { offset_minutes, updated_at: now });
+        Ok(())
+    })
+}
+
+// Get a user's timezone offset in minutes (0 = UTC)
+#[query]
+fn get_timezone_offset(user: Principal) -> i32 {
+    STORAGE.with(|storage| user_timezone_offset(&storage.borrow(), &user))
+}
+
 // Direct daily check-in API for users
 #[update]
 fn claim_daily_check_in() -> Result<DailyCheckInResponse, String> {
     let caller = caller();
     let now = time() / 1_000_000;
     
-    // Calculate the start of the current day in UTC time
-    let today_start = (now / SECONDS_IN_DAY) * SECONDS_IN_DAY;
+    // Calculate the start of the current day in the user's timezone
+    let today_start = user_today_start(&caller, now);
     
     // Check if already claimed today
     let already_checked_in = STORAGE.with(|storage| {
@@ -158,7 +325,7 @@ fn claim_daily_check_in() -> Result<DailyCheckInResponse, String> {
         
         if let Some(last_checkin) = storage.user_checkins.get(&caller) {
             // Check if last check-in time is within today's range
-            let last_checkin_day_start = *last_checkin - (*last_checkin % SECONDS_IN_DAY);
+            let last_checkin_day_start = local_day_start(*last_checkin, user_timezone_offset(&storage, &caller));
             let checked_in_today = last_checkin_day_start == today_start;
             
             checked_in_today
@@ -181,7 +348,8 @@ fn claim_daily_check_in() -> Result<DailyCheckInResponse, String> {
         consecutive_days: result.0,
         bonus_points: result.1,
         total_points: DAILY_CHECK_IN_POINTS + result.1,
-        next_claim_available_at: today_start + SECONDS_IN_DAY,
+        next_claim_available_at: today_start + MILLIS_IN_DAY,
+        streak_freeze_used: result.2,
     })
 }
 
@@ -191,8 +359,8 @@ fn verify_task(request: TaskVerificationRequest) -> Result<TaskVerificationRespo
     let user = request.user;
     let now = time() / 1_000_000;
     
-    // Calculate the start of the current day in UTC time
-    let today_start = (now / SECONDS_IN_DAY) * SECONDS_IN_DAY;
+    // Calculate the start of the current day in the user's timezone
+    let today_start = user_today_start(&user, now);
     
     // Check if the user has already checked in today
     let already_checked_in = STORAGE.with(|storage| {
@@ -201,7 +369,7 @@ fn verify_task(request: TaskVerificationRequest) -> Result<TaskVerificationRespo
         if let Some(last_checkin) = storage.user_checkins.get(&user) {
             // Check if the last check-in time is within today's range
             // Calculate the start of the last check-in day
-            let last_checkin_day_start = *last_checkin - (*last_checkin % SECONDS_IN_DAY);
+            let last_checkin_day_start = local_day_start(*last_checkin, user_timezone_offset(&storage, &user));
             let checked_in_today = last_checkin_day_start == today_start;
             
             checked_in_today
@@ -215,7 +383,7 @@ fn verify_task(request: TaskVerificationRequest) -> Result<TaskVerificationRespo
     }
     
     // Process the check-in
-    let (consecutive_days, bonus_points) = process_daily_checkin(user, now, today_start);
+    let (consecutive_days, bonus_points, streak_freeze_used) = process_daily_checkin(user, now, today_start);
     
     // Calculate total points
     let total_points = DAILY_CHECK_IN_POINTS + bonus_points;
@@ -224,7 +392,8 @@ fn verify_task(request: TaskVerificationRequest) -> Result<TaskVerificationRespo
     let mut metadata = HashMap::new();
     metadata.insert("consecutive_days".to_string(), consecutive_days.to_string());
     metadata.insert("bonus_points".to_string(), bonus_points.to_string());
-    metadata.insert("next_claim_available_at".to_string(), (today_start + SECONDS_IN_DAY).to_string());
+    metadata.insert("streak_freeze_used".to_string(), streak_freeze_used.to_string());
+    metadata.insert("next_claim_available_at".to_string(), (today_start + MILLIS_IN_DAY).to_string());
     
     // Create verification data
     let verification_data = VerificationData {
@@ -242,8 +411,86 @@ fn verify_task(request: TaskVerificationRequest) -> Result<TaskVerificationRespo
     })
 }
 
+// Record a streak item event, keeping only the most recent entries
+fn record_streak_item_event(storage: &mut Storage, user: Principal, action: StreakItemAction, timestamp: u64, consecutive_days: u64) {
+    let history = storage.streak_item_history.entry(user).or_default();
+    history.push(StreakItemEvent { action, timestamp, consecutive_days });
+    if history.len() > MAX_STREAK_ITEM_HISTORY {
+        let excess = history.len() - MAX_STREAK_ITEM_HISTORY;
+        history.drain(..excess);
+    }
+}
+
+// Move a user between total points index buckets
+fn reindex_total_points(storage: &mut Storage, user: Principal, old_points: Option<u64>, new_points: u64) {
+    if let Some(points) = old_points {
+        let users = storage.total_points_index.entry(points).or_default();
+        users.retain(|p| p != &user);
+        if users.is_empty() {
+            storage.total_points_index.remove(&points);
+        }
+    }
+    storage.total_points_index.entry(new_points).or_default().push(user);
+}
+
+// Append a length-prefixed transaction to the stable memory archive
+fn archive_points_transaction(storage: &mut Storage, user: Principal, transaction: &PointsTransaction) -> Result<(), String> {
+    let bytes = candid::encode_one(transaction).map_err(|e| format!("Failed to encode points transaction: {}", e))?;
+    let offset = POINTS_ARCHIVE_REGION_START + storage.points_archive_length;
+    let end = offset + 4 + bytes.len() as u64;
+    
+    let required_pages = end.div_ceil(WASM_PAGE_SIZE);
+    let current_pages = stable_size();
+    if required_pages > current_pages {
+        stable_grow(required_pages - current_pages)
+            .map_err(|e| format!("Failed to grow stable memory for points archive: {:?}", e))?;
+    }
+    
+    stable_write(offset, &(bytes.len() as u32).to_le_bytes());
+    stable_write(offset + 4, &bytes);
+    storage.points_archive_length = end - POINTS_ARCHIVE_REGION_START;
+    storage.archived_points_history.entry(user).or_default().push(offset);
+    Ok(())
+}
+
+fn read_archived_points_transaction(offset: u64) -> Option<PointsTransaction> {
+    let mut length = [0u8; 4];
+    stable_read(offset, &mut length);
+    let mut bytes = vec![0u8; u32::from_le_bytes(length) as usize];
+    stable_read(offset + 4, &mut bytes);
+    candid::decode_one(&bytes).ok()
+}
+
+// Move a user's oldest in-memory transactions to the archive once over the cap
+fn prune_points_history(storage: &mut Storage, user: Principal) {
+    let overflow: Vec<PointsTransaction> = match storage.points_history.get_mut(&user) {
+        Some(history) if history.len() > MAX_IN_MEMORY_POINTS_HISTORY => {
+            let excess = history.len() - MAX_IN_MEMORY_POINTS_HISTORY;
+            history.drain(..excess).collect()
+        },
+        _ => return,
+    };
+    
+    let mut overflow = overflow.into_iter();
+    while let Some(transaction) = overflow.next() {
+        if let Err(error) = archive_points_transaction(storage, user, &transaction) {
+            // Whatever could not be archived stays in memory, oldest first, and is retried next time
+            ic_cdk::println!("Keeping points history of {} in memory: {}", user, error);
+            let unarchived: Vec<PointsTransaction> = std::iter::once(transaction).chain(overflow).collect();
+            storage.points_history.entry(user).or_default().splice(0..0, unarchived);
+            return;
+        }
+    }
+}
+
+fn push_points_transaction(storage: &mut Storage, user: Principal, transaction: PointsTransaction) {
+    storage.points_history.entry(user).or_default().push(transaction);
+    prune_points_history(storage, user);
+}
+
 // Common function to process a daily check-in
-fn process_daily_checkin(user: Principal, now: u64, today_start: u64) -> (u64, u64) {
+// Returns (consecutive days, bonus points, whether a streak freeze was used)
+fn process_daily_checkin(user: Principal, now: u64, today_start: u64) -> (u64, u64, bool) {
     STORAGE.with(|storage| {
         let mut storage = storage.borrow_mut();
         
@@ -265,13 +512,23 @@ fn process_daily_checkin(user: Principal, now: u64, today_start: u64) -> (u64, u
         let current_consecutive_days = *storage.consecutive_days.get(&user).unwrap_or(&0);
         let mut new_consecutive_days = 1; // Default to 1 (first check-in)
         let mut bonus_points = 0;
+        let mut streak_freeze_used = false;
         
         // Check if this is a consecutive day
         if let Some(last_checkin) = storage.user_checkins.get(&user) {
-            let last_checkin_day = *last_checkin - (*last_checkin % SECONDS_IN_DAY);
-            let yesterday_start = today_start - SECONDS_IN_DAY;
+            let last_checkin_day = local_day_start(*last_checkin, user_timezone_offset(&storage, &user));
+            let yesterday_start = today_start - MILLIS_IN_DAY;
+            
+            // A single missed day is covered by a streak freeze, if the user holds one
+            let missed_one_day = last_checkin_day == yesterday_start - MILLIS_IN_DAY;
+            let freezes = *storage.streak_freezes.get(&user).unwrap_or(&0);
+            if missed_one_day && freezes > 0 && current_consecutive_days > 0 {
+                storage.streak_freezes.insert(user, freezes - 1);
+                record_streak_item_event(&mut storage, user, StreakItemAction::Used, now, current_consecutive_days);
+                streak_freeze_used = true;
+            }
             
-            if last_checkin_day == yesterday_start {
+            if last_checkin_day == yesterday_start || streak_freeze_used {
                 // This is a consecutive day
                 // If current consecutive days reach the maximum, reset to 1 and no bonus points
                 if current_consecutive_days >= max_consecutive_days {
@@ -334,6 +591,13 @@ fn process_daily_checkin(user: Principal, now: u64, today_start: u64) -> (u64, u
         storage.user_checkins.insert(user, today_start);
         storage.consecutive_days.insert(user, new_consecutive_days);
         
+        // Completing a full bonus cycle earns a streak freeze
+        let freezes = *storage.streak_freezes.get(&user).unwrap_or(&0);
+        if new_consecutive_days == max_consecutive_days && freezes < MAX_STREAK_FREEZES {
+            storage.streak_freezes.insert(user, freezes + 1);
+            record_streak_item_event(&mut storage, user, StreakItemAction::Earned, now, new_consecutive_days);
+        }
+        
         // Update points (for direct API calls)
         let total_points = DAILY_CHECK_IN_POINTS + bonus_points;
         let current_points = *storage.user_points.get(&user).unwrap_or(&0);
@@ -365,13 +629,179 @@ fn process_daily_checkin(user: Principal, now: u64, today_start: u64) -> (u64, u
             points: total_points,
         };
         
-        let user_history = storage.points_history.entry(user).or_insert_with(Vec::new);
-        user_history.push(transaction);
+        push_points_transaction(&mut storage, user, transaction);
+        
+        (new_consecutive_days, bonus_points, streak_freeze_used)
+    })
+}
+
+// Declare an outage window (admin function)
+#[update]
+fn declare_outage_window(start_time: u64, end_time: u64, reason: String) -> Result<OutageWindow, String> {
+    let caller = caller();
+    let now = time() / 1_000_000;
+    
+    STORAGE.with(|storage| {
+        let mut storage = storage.borrow_mut();
+        
+        if !storage.admins.contains(&caller) {
+            return Err("Unauthorized: Only admins can declare outage windows".to_string());
+        }
+        if start_time >= end_time {
+            return Err("Outage start_time must be before end_time".to_string());
+        }
+        if end_time > now {
+            return Err("Outage windows can only be declared once they have ended".to_string());
+        }
+        
+        let window = OutageWindow {
+            id: storage.next_outage_window_id,
+            start_time,
+            end_time,
+            reason,
+            declared_by: caller,
+            declared_at: now,
+        };
+        storage.next_outage_window_id += 1;
+        storage.outage_windows.push(window.clone());
+        Ok(window)
+    })
+}
+
+// Remove a declared outage window (admin function)
+#[update]
+fn remove_outage_window(id: u64) -> Result<(), String> {
+    STORAGE.with(|storage| {
+        let mut storage = storage.borrow_mut();
+        
+        if !storage.admins.contains(&caller()) {
+            return Err("Unauthorized: Only admins can remove outage windows".to_string());
+        }
+        
+        let count = storage.outage_windows.len();
+        storage.outage_windows.retain(|window| window.id != id);
+        if storage.outage_windows.len() == count {
+            return Err(format!("Outage window {} not found", id));
+        }
+        Ok(())
+    })
+}
+
+#[query]
+fn get_outage_windows() -> Vec<OutageWindow> {
+    STORAGE.with(|storage| storage.borrow().outage_windows.clone())
+}
+
+// Claim a check-in missed during a declared outage. `missed_day` is any timestamp within the
+// missed local day. Claims are accepted until the end of the day after the outage ended,
+// and must be made (oldest day first) before checking in again.
+#[update]
+fn claim_retroactive_check_in(missed_day: u64) -> Result<DailyCheckInResponse, String> {
+    let user = caller();
+    let now = time() / 1_000_000;
+    
+    let (missed_day_start, today_start) = STORAGE.with(|storage| {
+        let storage = storage.borrow();
+        let offset_minutes = user_timezone_offset(&storage, &user);
+        (local_day_start(missed_day, offset_minutes), local_day_start(now, offset_minutes))
+    });
+    
+    if missed_day_start >= today_start {
+        return Err("Only past days can be claimed retroactively".to_string());
+    }
+    
+    STORAGE.with(|storage| {
+        let storage = storage.borrow();
+        
+        let covered = storage.outage_windows.iter().any(|window| {
+            let overlaps = window.start_time < missed_day_start + MILLIS_IN_DAY && window.end_time > missed_day_start;
+            let claim_deadline = local_day_start(window.end_time, user_timezone_offset(&storage, &user)) + 2 * MILLIS_IN_DAY;
+            overlaps && now < claim_deadline
+        });
+        if !covered {
+            return Err("No declared outage window covers that day, or the claim period has passed".to_string());
+        }
+        
+        let offset_minutes = user_timezone_offset(&storage, &user);
+        let checked_in_since = storage.user_checkins
+            .get(&user)
+            .is_some_and(|last_checkin| local_day_start(*last_checkin, offset_minutes) >= missed_day_start);
+        if checked_in_since {
+            return Err("Already checked in on or after that day".to_string());
+        }
+        
+        Ok(())
+    })?;
+    
+    let (consecutive_days, bonus_points, streak_freeze_used) = process_daily_checkin(user, now, missed_day_start);
+    
+    Ok(DailyCheckInResponse {
+        success: true,
+        points_earned: DAILY_CHECK_IN_POINTS,
+        consecutive_days,
+        bonus_points,
+        total_points: DAILY_CHECK_IN_POINTS + bonus_points,
+        next_claim_available_at: today_start,
+        streak_freeze_used,
+    })
+}
+
+// Buy a streak freeze with check-in points
+#[update]
+fn purchase_streak_freeze() -> Result<StreakItemsResponse, String> {
+    let user = caller();
+    let now = time() / 1_000_000;
+    
+    STORAGE.with(|storage| {
+        let mut storage = storage.borrow_mut();
+        
+        let freezes = *storage.streak_freezes.get(&user).unwrap_or(&0);
+        if freezes >= MAX_STREAK_FREEZES {
+            return Err(format!("Cannot hold more than {} streak freezes", MAX_STREAK_FREEZES));
+        }
+        
+        let current_points = storage.user_points.get(&user).cloned();
+        let balance = current_points.unwrap_or(0);
+        if balance < STREAK_FREEZE_COST_POINTS {
+            return Err(format!("Insufficient points: {} required, {} available", STREAK_FREEZE_COST_POINTS, balance));
+        }
+        
+        let new_total_points = balance - STREAK_FREEZE_COST_POINTS;
+        storage.user_points.insert(user, new_total_points);
+        reindex_total_points(&mut storage, user, current_points, new_total_points);
+        
+        let transaction = PointsTransaction {
+            amount: -(STREAK_FREEZE_COST_POINTS as i64),
+            reason: "Purchased streak freeze".to_string(),
+            timestamp: now,
+            reference_id: Some("streak_freeze".to_string()),
+            points: STREAK_FREEZE_COST_POINTS,
+        };
+        push_points_transaction(&mut storage, user, transaction);
+        
+        storage.streak_freezes.insert(user, freezes + 1);
+        let consecutive_days = *storage.consecutive_days.get(&user).unwrap_or(&0);
+        record_streak_item_event(&mut storage, user, StreakItemAction::Purchased, now, consecutive_days);
         
-        (new_consecutive_days, bonus_points)
+        Ok(streak_items_for(&storage, &user))
     })
 }
 
+fn streak_items_for(storage: &Storage, user: &Principal) -> StreakItemsResponse {
+    StreakItemsResponse {
+        streak_freezes: *storage.streak_freezes.get(user).unwrap_or(&0),
+        max_streak_freezes: MAX_STREAK_FREEZES,
+        purchase_cost_points: STREAK_FREEZE_COST_POINTS,
+        history: storage.streak_item_history.get(user).cloned().unwrap_or_default(),
+    }
+}
+
+// Get a user's streak items and their usage history
+#[query]
+fn get_streak_items(user: Principal) -> StreakItemsResponse {
+    STORAGE.with(|storage| streak_items_for(&storage.borrow(), &user))
+}
+
 // Get the current check-in status for the caller
 #[query]
 fn get_my_checkin_status() -> HashMap<String, String> {
@@ -384,8 +814,8 @@ fn get_my_checkin_status() -> HashMap<String, String> {
 fn get_checkin_status(user: Principal) -> HashMap<String, String> {
     let now = time() / 1_000_000;
     
-    // Calculate the start of the current day in UTC time
-    let today_start = (now / SECONDS_IN_DAY) * SECONDS_IN_DAY;
+    // Calculate the start of the current day in the user's timezone
+    let today_start = user_today_start(&user, now);
     
     STORAGE.with(|storage| {
         let storage = storage.borrow();
@@ -393,7 +823,7 @@ fn get_checkin_status(user: Principal) -> HashMap<String, String> {
         
         // Check if user exists in check-in records
         if let Some(last_checkin) = storage.user_checkins.get(&user) {
-            let last_checkin_day = *last_checkin - (*last_checkin % SECONDS_IN_DAY);
+            let last_checkin_day = local_day_start(*last_checkin, user_timezone_offset(&storage, &user));
             let has_checked_in_today = last_checkin_day == today_start;
             
             // Get consecutive days and total points
@@ -409,7 +839,7 @@ fn get_checkin_status(user: Principal) -> HashMap<String, String> {
             status.insert("today_start".to_string(), today_start.to_string()); // Add today start time for debugging
             status.insert("next_claim_available_at".to_string(), 
                 if has_checked_in_today {
-                    (today_start + SECONDS_IN_DAY).to_string()
+                    (today_start + MILLIS_IN_DAY).to_string()
                 } else {
                     "0".to_string() // Can claim now
                 }
@@ -453,14 +883,15 @@ fn get_user_rewards(user: Principal) -> HashMap<String, Value> {
             
             // Calculate if user can check in today
             let now = time() / 1_000_000;
-            let today_start = now - (now % SECONDS_IN_DAY);
-            let last_checkin_day = last_checkin - (last_checkin % SECONDS_IN_DAY);
+            let offset_minutes = user_timezone_offset(&storage, &user);
+            let today_start = local_day_start(now, offset_minutes);
+            let last_checkin_day = local_day_start(*last_checkin, offset_minutes);
             let has_checked_in_today = last_checkin_day == today_start;
             
             result.insert("can_checkin_today".to_string(), Value::Bool(!has_checked_in_today));
             
             if has_checked_in_today {
-                let next_claim_time = today_start + SECONDS_IN_DAY;
+                let next_claim_time = today_start + MILLIS_IN_DAY;
                 result.insert("next_claim_available_at".to_string(), Value::Nat(next_claim_time));
             }
         } else {
@@ -468,9 +899,10 @@ fn get_user_rewards(user: Principal) -> HashMap<String, Value> {
             result.insert("can_checkin_today".to_string(), Value::Bool(true));
         }
         
-        // Get points history
+        // Get recent points history; use get_points_history for the full, paginated history
         if let Some(history) = storage.points_history.get(&user) {
-            result.insert("points_history_count".to_string(), Value::Nat(history.len() as u64));
+            let archived_count = storage.archived_points_history.get(&user).map_or(0, Vec::len);
+            result.insert("points_history_count".to_string(), Value::Nat((history.len() + archived_count) as u64));
             
             // Add latest transaction if available
             if !history.is_empty() {
@@ -480,7 +912,7 @@ fn get_user_rewards(user: Principal) -> HashMap<String, Value> {
                 result.insert("latest_transaction_timestamp".to_string(), Value::Nat(latest.timestamp));
             }
             
-            // Add full points history
+            // Add recent points history
             let history_entries: Vec<Value> = history.iter().map(|transaction| {
                 let mut entry = Vec::new();
                 entry.push(("amount".to_string(), Value::Int(transaction.amount)));
@@ -502,6 +934,39 @@ fn get_user_rewards(user: Principal) -> HashMap<String, Value> {
     result
 }
 
+// Get the caller's points history, newest first, including archived entries
+#[query]
+fn get_points_history(offset: u64, limit: u64) -> Result<PointsHistoryPage, String> {
+    if limit == 0 || limit > MAX_POINTS_HISTORY_PAGE_SIZE {
+        return Err(format!("Limit must be between 1 and {}", MAX_POINTS_HISTORY_PAGE_SIZE));
+    }
+    
+    let user = caller();
+    STORAGE.with(|storage| {
+        let storage = storage.borrow();
+        let recent = storage.points_history.get(&user).map_or(&[][..], Vec::as_slice);
+        let archived = storage.archived_points_history.get(&user).map_or(&[][..], Vec::as_slice);
+        
+        // Skip over archive offsets before reading, so only the requested page touches stable memory
+        let entries = recent.iter().rev().map(|transaction| Ok(transaction.clone()))
+            .chain(archived.iter().rev().map(|archive_offset| Err(*archive_offset)))
+            .skip(offset as usize)
+            .take(limit as usize)
+            .filter_map(|entry| match entry {
+                Ok(transaction) => Some(transaction),
+                Err(archive_offset) => read_archived_points_transaction(archive_offset),
+            })
+            .collect();
+        
+        Ok(PointsHistoryPage {
+            entries,
+            total_count: (recent.len() + archived.len()) as u64,
+            offset,
+            limit,
+        })
+    })
+}
+
 // Reset a user's check-in streak (admin function)
 #[update]
 fn reset_user_streak(user: Principal) {
@@ -550,8 +1015,7 @@ fn award_points(user: Principal, points: u64, reason: String) {
             points,
         };
         
-        let user_history = storage.points_history.entry(user).or_insert_with(Vec::new);
-        user_history.push(transaction);
+        push_points_transaction(&mut storage, user, transaction);
     });
 }
 
@@ -625,6 +1089,27 @@ fn update_task_config(config: TaskConfig) {
     });
 }
 
+// Task definitions synced by the main canister's task registry
+#[query]
+fn get_task_definitions() -> Vec<TaskDefinition> {
+    STORAGE.with(|storage| {
+        let config = &storage.borrow().task_config;
+        if !config.enabled {
+            return Vec::new();
+        }
+        
+        vec![TaskDefinition {
+            id: "daily_checkin".to_string(),
+            title: if config.title.is_empty() { "Daily Check-in".to_string() } else { config.title.clone() },
+            description: config.description.clone(),
+            points: if config.base_points > 0 { config.base_points } else { DAILY_CHECK_IN_POINTS },
+            task_type: TaskType::Daily,
+            completion_criteria: "Check in once per day".to_string(),
+            expiration_time: None,
+        }]
+    })
+}
+
 // Get task configuration
 #[query]
 fn get_task_config() -> TaskConfig {
@@ -963,6 +1448,15 @@ fn restore_state_after_upgrade() {
                 *storage.borrow_mut() = storage_data;
             });
             
+            // Archive any history accumulated before the in-memory cap existed
+            STORAGE.with(|storage| {
+                let mut storage = storage.borrow_mut();
+                let users: Vec<Principal> = storage.points_history.keys().cloned().collect();
+                for user in users {
+                    prune_points_history(&mut storage, user);
+                }
+            });
+            
             // Log some stats about the restored data
             STORAGE.with(|storage| {
                 let storage = storage.borrow();
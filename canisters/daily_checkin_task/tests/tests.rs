@@ -6,6 +6,8 @@ use ic_cdk::api::time;
 use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+const SECONDS_IN_DAY: u64 = 86_400;
+
 // Helper function to create a test principal
 fn test_principal(id: u8) -> Principal {
     Principal::from_slice(&[id; 29])
@@ -0,0 +1,55 @@
+#!/usr/bin/env python3
+"""Mark millisecond timestamps in a generated .did file with the TimestampMillis alias.
+
+Candid export inlines Rust type aliases, so `TimestampMillis` fields come out as plain `nat64`.
+This reads which struct fields are declared as `TimestampMillis` in the Rust sources, rewrites
+those fields of the matching Candid records, and declares `type TimestampMillis = nat64;`.
+
+Usage: scripts/annotate_did_timestamps.py <src dir> <did file>
+"""
+import pathlib
+import re
+import sys
+
+ALIAS = "TimestampMillis"
+STRUCT = re.compile(r"pub struct (\w+)\s*\{(.*?)\n\}", re.S)
+FIELD = re.compile(r"pub (\w+): (Option<|Vec<)?" + ALIAS + r"\b")
+RECORD = re.compile(r"^type (\w+?)(?:_\d+)? = record \{(.*?)\};$", re.S | re.M)
+CANDID_FIELD = {"": "nat64", "Option<": "opt nat64", "Vec<": "vec nat64"}
+
+
+def timestamp_fields(src_dir):
+    fields = {}
+    for path in pathlib.Path(src_dir).rglob("*.rs"):
+        for name, body in STRUCT.findall(path.read_text()):
+            for field, wrapper in FIELD.findall(body):
+                fields.setdefault(name, {})[field] = wrapper
+    return fields
+
+
+def annotate(did, fields):
+    def rewrite(record):
+        wanted = fields.get(record.group(1))
+        if not wanted:
+            return record.group(0)
+        text = record.group(0)
+        for field, wrapper in wanted.items():
+            plain = CANDID_FIELD[wrapper]
+            aliased = plain.replace("nat64", ALIAS)
+            text = re.sub(r"\b(" + field + r" : )" + plain + r"\b", r"\g<1>" + aliased, text)
+        return text
+
+    did = RECORD.sub(rewrite, did)
+    declaration = "type {} = nat64;\n".format(ALIAS)
+    if declaration not in did:
+        did = declaration + did
+    return did
+
+
+def main():
+    src_dir, did_path = sys.argv[1], pathlib.Path(sys.argv[2])
+    did_path.write_text(annotate(did_path.read_text(), timestamp_fields(src_dir)))
+
+
+if __name__ == "__main__":
+    main()
@@ -64,6 +64,7 @@ canister_name="ic_news_square"
 cargo clean
 cargo build --target wasm32-unknown-unknown --release
 candid-extractor "target/wasm32-unknown-unknown/release/ic_news_square.wasm" > "src/$canister_name.did"
+python3 scripts/annotate_did_timestamps.py src "src/$canister_name.did"
 
 if [ -f ".dfx/$NETWORK/canister_ids.json" ] && [ "$(jq -r ".$canister_name.$NETWORK" ".dfx/$NETWORK/canister_ids.json")" != "null" ]; then
     echo "Updating existing canister with --mode reinstall..."
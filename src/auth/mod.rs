@@ -2,6 +2,7 @@ use candid::Principal;
 use ic_cdk::caller;
 use crate::models::error::{SquareError, SquareResult};
 use crate::storage::STORAGE;
+use crate::utils::middleware::{ApiResponse, with_error_handling};
 
 pub fn init_admin() {
     STORAGE.with(|storage| {
@@ -53,7 +54,21 @@ pub fn is_manager_or_admin() -> Result<(), String> {
 }
 
 #[ic_cdk_macros::update]
-pub fn add_manager(manager: Principal) -> Result<(), String> {
+pub fn add_manager_v2(manager: Principal) -> ApiResponse<()> {
+    with_error_handling(|| Ok(add_manager_checked(manager)?))()
+}
+
+#[ic_cdk_macros::update]
+pub fn remove_manager_v2(manager: Principal) -> ApiResponse<()> {
+    with_error_handling(|| Ok(remove_manager_checked(manager)?))()
+}
+
+#[ic_cdk_macros::query]
+pub fn list_managers_v2() -> ApiResponse<Vec<Principal>> {
+    with_error_handling(|| Ok(list_managers_checked()?))()
+}
+
+pub fn add_manager_checked(manager: Principal) -> Result<(), String> {
     is_admin()?;
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
@@ -70,8 +85,7 @@ pub fn add_manager(manager: Principal) -> Result<(), String> {
     })
 }
 
-#[ic_cdk_macros::update]
-pub fn remove_manager(manager: Principal) -> Result<(), String> {
+pub fn remove_manager_checked(manager: Principal) -> Result<(), String> {
     is_admin()?;
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
@@ -83,8 +97,7 @@ pub fn remove_manager(manager: Principal) -> Result<(), String> {
     })
 }
 
-#[ic_cdk_macros::query]
-pub fn list_managers() -> Result<Vec<Principal>, String> {
+pub fn list_managers_checked() -> Result<Vec<Principal>, String> {
     is_admin()?;
     Ok(STORAGE.with(|storage| {
         let store = storage.borrow();
@@ -0,0 +1,203 @@
+// Instruction benchmarks for the feed queries, run by `canbench` (see canbench.yml). Each bench
+// seeds the heap with synthetic authors, posts and likes, measures a single call and traps when the
+// call goes over its budget, so a regression fails the run before it reaches mainnet.
+use candid::Principal;
+use canbench_rs::{bench, bench_fn, BenchResult};
+
+use crate::models::content::{ContentFormat, ContentStatus, ContentVisibility, PaginationParams, Post};
+use crate::models::discovery::{DiscoverContentRequest, PersonalizedRecommendationsRequest, SearchRequest, SortOption};
+use crate::models::user::RegisterUserRequest;
+use crate::services::discovery::{discover_content, get_personalized_recommendations, search_content};
+use crate::services::user::register_user;
+use crate::storage::STORAGE;
+use crate::utils::instruction_budget::{DEGRADE_AT_PERCENT, QUERY_INSTRUCTION_LIMIT};
+use crate::utils::time_utils::now_millis;
+
+const AUTHORS: u64 = 200;
+const TOPICS: [&str; 5] = ["bitcoin", "defi", "nft", "icp", "gaming"];
+// Every fiftieth post is liked by a handful of authors
+const LIKED_EVERY: u64 = 50;
+const LIKERS_PER_POST: u64 = 5;
+
+// A feed query over 10k posts has to finish without degrading to cheaper ranking; over 100k posts
+// it only has to fit in a query
+fn budget(posts: u64) -> u64 {
+    if posts <= 10_000 {
+        QUERY_INSTRUCTION_LIMIT / 100 * DEGRADE_AT_PERCENT
+    } else {
+        QUERY_INSTRUCTION_LIMIT
+    }
+}
+
+fn within_budget(name: &str, posts: u64, result: BenchResult) -> BenchResult {
+    let budget = budget(posts);
+    if result.total.instructions > budget {
+        ic_cdk::trap(&format!(
+            "{} over {} posts took {} instructions, over its budget of {}",
+            name, posts, result.total.instructions, budget
+        ));
+    }
+    result
+}
+
+fn author(index: u64) -> Principal {
+    let mut bytes = [0u8; 9];
+    bytes[..8].copy_from_slice(&index.to_be_bytes());
+    bytes[8] = 0xBE;
+    Principal::from_slice(&bytes)
+}
+
+fn seed(posts: u64) {
+    for index in 0..AUTHORS {
+        register_user(RegisterUserRequest {
+            username: format!("author{}", index),
+            handle: format!("author{}", index),
+            bio: String::new(),
+            avatar: String::new(),
+            social_links: None,
+            interests: Some(vec![TOPICS[index as usize % TOPICS.len()].to_string()]),
+            invite_code: None,
+        }, author(index)).expect("seeding authors");
+    }
+
+    let now = now_millis();
+    let caller = ic_cdk::caller();
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        for index in 0..posts {
+            let id = format!("bench_post_{}", index);
+            let topic = TOPICS[index as usize % TOPICS.len()];
+            let post_author = author(index % AUTHORS);
+            store.posts.insert(id.clone(), Post {
+                id: id.clone(),
+                author: post_author,
+                content: format!("Post {} about {} and the market today", index, topic),
+                media_urls: Vec::new(),
+                hashtags: vec![format!("#{}", topic)],
+                token_mentions: Vec::new(),
+                tags: vec![topic.to_string()],
+                created_at: now.saturating_sub(index * 60_000),
+                updated_at: now.saturating_sub(index * 60_000),
+                status: ContentStatus::Active,
+                visibility: ContentVisibility::Public,
+                news_reference: None,
+                posted_by: None,
+                title: None,
+                is_premium: false,
+                license: None,
+                token_gate: None,
+                format: ContentFormat::default(),
+                content_hash: None,
+                cross_post_of: None,
+                cover_image: None,
+                thumbnail: None,
+            });
+            store.user_posts.entry(post_author).or_default().push(id.clone());
+
+            if index % LIKED_EVERY == 0 {
+                // The caller likes some of the liked posts, so collaborative filtering has neighbours
+                let mut likers: Vec<Principal> = (0..LIKERS_PER_POST).map(|offset| author((index / LIKED_EVERY + offset) % AUTHORS)).collect();
+                if index % (LIKED_EVERY * 4) == 0 {
+                    likers.push(caller);
+                }
+                for liker in likers {
+                    let liker_id = store.principal_ids.intern(liker);
+                    store.like_sets.entry(id.clone()).or_default().insert(liker_id);
+                }
+            }
+        }
+    });
+}
+
+fn page() -> PaginationParams {
+    PaginationParams { offset: None, limit: Some(20) }
+}
+
+fn bench_discover_content(name: &str, posts: u64, sort_by: Option<SortOption>) -> BenchResult {
+    seed(posts);
+    let result = bench_fn(|| {
+        discover_content(DiscoverContentRequest {
+            content_types: None,
+            tags: None,
+            pagination: page(),
+            sort_by: sort_by.clone(),
+            filter: None,
+            exclude_seen: None,
+        })
+        .expect("discover_content")
+    });
+    within_budget(name, posts, result)
+}
+
+fn bench_search_content(posts: u64) -> BenchResult {
+    seed(posts);
+    let result = bench_fn(|| {
+        search_content(SearchRequest {
+            // Matches the text of every fifth post
+            query: "about defi".to_string(),
+            content_types: None,
+            pagination: page(),
+        })
+        .expect("search_content")
+    });
+    within_budget("search_content", posts, result)
+}
+
+fn bench_recommendations(posts: u64) -> BenchResult {
+    seed(posts);
+    let result = bench_fn(|| {
+        get_personalized_recommendations(PersonalizedRecommendationsRequest {
+            content_types: None,
+            pagination: page(),
+            include_followed_users: None,
+            include_followed_topics: None,
+            include_trending: None,
+            include_similar_to_liked: None,
+            diversity_factor: None,
+            recency_weight: None,
+            exclude_seen: None,
+        })
+        .expect("get_personalized_recommendations")
+    });
+    within_budget("get_personalized_recommendations", posts, result)
+}
+
+#[bench(raw)]
+fn discover_content_10k() -> BenchResult {
+    bench_discover_content("discover_content", 10_000, None)
+}
+
+#[bench(raw)]
+fn discover_content_100k() -> BenchResult {
+    bench_discover_content("discover_content", 100_000, None)
+}
+
+#[bench(raw)]
+fn discover_trending_10k() -> BenchResult {
+    bench_discover_content("discover_content (trending)", 10_000, Some(SortOption::Trending))
+}
+
+#[bench(raw)]
+fn discover_trending_100k() -> BenchResult {
+    bench_discover_content("discover_content (trending)", 100_000, Some(SortOption::Trending))
+}
+
+#[bench(raw)]
+fn search_content_10k() -> BenchResult {
+    bench_search_content(10_000)
+}
+
+#[bench(raw)]
+fn search_content_100k() -> BenchResult {
+    bench_search_content(100_000)
+}
+
+#[bench(raw)]
+fn recommendations_10k() -> BenchResult {
+    bench_recommendations(10_000)
+}
+
+#[bench(raw)]
+fn recommendations_100k() -> BenchResult {
+    bench_recommendations(100_000)
+}
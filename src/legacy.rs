@@ -0,0 +1,279 @@
+// Legacy endpoints
+// The ApiResponse envelope is served under a `_v2` suffix (e.g. `get_post_v2`). Until the removal
+// version, the original method names keep their SquareResult/plain signatures so deployed clients
+// go on working while they move to the `_v2` methods; remove this module afterwards.
+// Each method here is listed in the deprecation registry (models::api_version). Update methods record
+// a warning in the error monitor when called; query methods can only log the call.
+use ic_cdk_macros::*;
+
+use super::*;
+
+// Public read endpoints here share the anonymous read policy with their `_v2` methods
+fn legacy_read<T>(endpoint: &str, handler: impl FnOnce() -> SquareResult<T>) -> SquareResult<T> {
+    services::api_version::log_deprecated_query(endpoint);
+    services::access_policy::check_anonymous_read(endpoint)?;
+    handler()
+}
+
+#[query]
+fn get_post(post_id: String) -> SquareResult<PostResponse> {
+    legacy_read("get_post", || {
+        services::content::get_post(post_id)
+    })
+}
+
+#[query]
+fn get_posts(pagination: PaginationParams) -> SquareResult<PostsResponse> {
+    legacy_read("get_posts", || {
+        services::content::get_posts(pagination)
+    })
+}
+
+#[update]
+fn delete_post(post_id: String) -> SquareResult<()> {
+    services::api_version::record_deprecated_call("delete_post");
+    services::maintenance::check_maintenance_mode()?;
+    services::content::delete_post(post_id, caller())
+}
+
+#[update]
+fn create_comment(request: CreateCommentRequest) -> SquareResult<CommentResponse> {
+    services::api_version::record_deprecated_call("create_comment");
+    services::maintenance::check_maintenance_mode()?;
+    services::content::create_comment(request, caller())
+}
+
+#[update]
+fn update_comment(request: UpdateCommentRequest) -> SquareResult<CommentResponse> {
+    services::api_version::record_deprecated_call("update_comment");
+    services::maintenance::check_maintenance_mode()?;
+    services::content::update_comment(request, caller())
+}
+
+#[query]
+fn get_comment(comment_id: String) -> SquareResult<CommentResponse> {
+    legacy_read("get_comment", || {
+        services::content::get_comment(comment_id, Some(caller()))
+    })
+}
+
+#[update]
+fn delete_comment(comment_id: String) -> SquareResult<()> {
+    services::api_version::record_deprecated_call("delete_comment");
+    services::maintenance::check_maintenance_mode()?;
+    services::content::delete_comment(comment_id, caller())
+}
+
+#[query]
+fn get_comments(parent_id: String, parent_type: String, pagination: PaginationParams) -> SquareResult<CommentsResponse> {
+    legacy_read("get_comments", || {
+        services::content::get_comments(parent_id, parent_type, pagination, Some(caller()))
+    })
+}
+
+#[query]
+fn get_user_content(user_identifier: Option<String>, content_type: Option<ContentType>, pagination: PaginationParams) -> SquareResult<FeedResponse> {
+    legacy_read("get_user_content", || {
+        let identifier = match user_identifier {
+            Some(id) => id,
+            None => caller().to_text()
+        };
+        services::content::get_user_content(identifier, content_type, crate::models::content::PaginationParams { offset: pagination.offset, limit: pagination.limit })
+    })
+}
+
+#[update]
+fn like_content(request: LikeContentRequest) -> SquareResult<()> {
+    services::api_version::record_deprecated_call("like_content");
+    services::maintenance::check_maintenance_mode()?;
+    services::interaction::like_content(request.content_id, request.content_type, caller()).map(|_| ())
+}
+
+#[update]
+fn unlike_content(request: LikeContentRequest) -> SquareResult<()> {
+    services::api_version::record_deprecated_call("unlike_content");
+    services::maintenance::check_maintenance_mode()?;
+    services::interaction::unlike_content(request.content_id, request.content_type, caller()).map(|_| ())
+}
+
+#[query]
+fn get_likes(content_id: String, content_type: ContentType) -> SquareResult<LikesResponse> {
+    legacy_read("get_likes", || {
+        services::interaction::get_likes(content_id, content_type)
+    })
+}
+
+#[update]
+fn report_content(request: ReportContentRequest) -> SquareResult<()> {
+    services::api_version::record_deprecated_call("report_content");
+    services::maintenance::check_maintenance_mode()?;
+    services::interaction::report_content(request, caller()).map(|_| ())
+}
+
+#[query]
+fn discover_content(request: DiscoverContentRequest) -> SquareResult<FeedResponse> {
+    legacy_read("discover_content", || {
+        services::discovery::discover_content(request)
+    })
+}
+
+#[query]
+fn search_content(request: SearchRequest) -> SquareResult<Vec<SearchResultResponse>> {
+    legacy_read("search_content", || {
+        services::discovery::search_content(request)
+    })
+}
+
+#[query]
+fn get_trending_topics(request: GetTrendingTopicsRequest) -> SquareResult<Vec<TrendingTopicResponse>> {
+    legacy_read("get_trending_topics", || {
+        services::discovery::get_trending_topics(request)
+    })
+}
+
+#[query]
+fn get_hot_tags(request: DiscoveryGetHotTagsRequest) -> SquareResult<DiscoveryHotTagsResponse> {
+    legacy_read("get_hot_tags", || {
+        services::discovery::get_hot_tags(request)
+    })
+}
+
+#[query]
+fn get_personalized_recommendations(request: PersonalizedRecommendationsRequest) -> SquareResult<FeedResponse> {
+    legacy_read("get_personalized_recommendations", || {
+        services::discovery::get_personalized_recommendations(request)
+    })
+}
+
+#[update(name = "complete_task")]
+async fn complete_task_async(request: CompleteTaskRequest) -> SquareResult<TaskCompletionResponse> {
+    services::api_version::record_deprecated_call("complete_task");
+    services::maintenance::check_maintenance_mode()?;
+    services::reward::complete_task(request, caller()).await
+}
+
+#[query]
+fn get_user_rewards() -> SquareResult<UserRewardsResponse> {
+    services::api_version::log_deprecated_query("get_user_rewards");
+    services::reward::get_user_rewards(caller())
+}
+
+#[query]
+fn get_available_tasks() -> SquareResult<Vec<TaskResponse>> {
+    services::api_version::log_deprecated_query("get_available_tasks");
+    services::reward::get_available_tasks(caller())
+}
+
+#[update]
+fn moderate_content(request: ModerateContentRequest) -> SquareResult<()> {
+    services::api_version::record_deprecated_call("moderate_content");
+    services::maintenance::check_maintenance_mode()?;
+    services::content::moderate_content(request, caller()).map(|_| ())
+}
+
+#[update]
+fn award_points(request: AwardPointsRequest) -> SquareResult<()> {
+    services::api_version::record_deprecated_call("award_points");
+    services::maintenance::check_maintenance_mode()?;
+    services::treasury::award_points(request, caller())
+}
+
+#[update(name = "create_task")]
+fn create_task(request: CreateTaskRequest) -> SquareResult<String> {
+    services::api_version::record_deprecated_call("create_task");
+    services::maintenance::check_maintenance_mode()?;
+    services::reward::create_task(request)
+}
+
+#[update]
+fn update_task(request: UpdateTaskRequest) -> SquareResult<()> {
+    services::api_version::record_deprecated_call("update_task");
+    services::maintenance::check_maintenance_mode()?;
+    services::reward::update_task(request)
+}
+
+#[update]
+fn delete_task(task_id: String) -> SquareResult<()> {
+    services::api_version::record_deprecated_call("delete_task");
+    services::maintenance::check_maintenance_mode()?;
+    services::reward::delete_task(task_id)
+}
+
+#[query]
+fn get_cycles_balance() -> SquareResult<CyclesBalanceResponse> {
+    services::api_version::log_deprecated_query("get_cycles_balance");
+    services::cycles::get_cycles_balance()
+}
+
+#[query]
+fn get_cycles_consumption_history() -> SquareResult<CyclesConsumptionResponse> {
+    services::api_version::log_deprecated_query("get_cycles_consumption_history");
+    // Only authenticated users can view consumption history
+    auth::get_authenticated_caller()?;
+    services::cycles::get_cycles_consumption_history()
+}
+
+#[update]
+fn acknowledge_notification(timestamp: u64) -> SquareResult<()> {
+    services::api_version::record_deprecated_call("acknowledge_notification");
+    services::maintenance::check_maintenance_mode()?;
+    // Only admin can acknowledge notifications
+    services::cycles::acknowledge_notification(timestamp, caller())
+}
+
+#[update]
+fn update_notification_settings(enabled: Option<bool>) -> SquareResult<()> {
+    services::api_version::record_deprecated_call("update_notification_settings");
+    services::maintenance::check_maintenance_mode()?;
+    // Only admin can update notification settings
+    services::cycles::update_notification_settings(enabled, caller())
+}
+
+#[query]
+fn get_notification_settings() -> SquareResult<bool> {
+    services::api_version::log_deprecated_query("get_notification_settings");
+    // Only admin can view notification settings
+    services::cycles::get_notification_settings(caller())
+}
+
+#[query]
+fn get_logs() -> Vec<utils::logger::LogEntry> {
+    services::api_version::log_deprecated_query("get_logs");
+    utils::logger::get_all_logs()
+}
+
+#[query]
+fn get_recent_logs(count: usize) -> Vec<utils::logger::LogEntry> {
+    services::api_version::log_deprecated_query("get_recent_logs");
+    utils::logger::get_recent_logs(count)
+}
+
+#[update]
+fn clear_logs() -> bool {
+    services::api_version::record_deprecated_call("clear_logs");
+    match auth::is_admin() {
+        Ok(_) => {
+            utils::logger::clear_logs();
+            true
+        },
+        Err(_) => false
+    }
+}
+
+#[update]
+fn add_manager(manager: Principal) -> Result<(), String> {
+    services::api_version::record_deprecated_call("add_manager");
+    auth::add_manager_checked(manager)
+}
+
+#[update]
+fn remove_manager(manager: Principal) -> Result<(), String> {
+    services::api_version::record_deprecated_call("remove_manager");
+    auth::remove_manager_checked(manager)
+}
+
+#[query]
+fn list_managers() -> Result<Vec<Principal>, String> {
+    services::api_version::log_deprecated_query("list_managers");
+    auth::list_managers_checked()
+}
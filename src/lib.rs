@@ -1,7 +1,7 @@
 use candid::Principal;
 use ic_cdk::api::{caller, time, performance_counter, canister_balance};
 use ic_cdk_macros::*;
-use ic_cdk::api::stable::{stable_size, stable_grow, stable_write, stable_read};
+use ic_cdk::api::stable::{stable_size, stable_read};
 
 // Import modules
 mod auth;
@@ -9,18 +9,64 @@ mod models;
 mod services;
 mod storage;
 mod utils;
+mod legacy;
+#[cfg(feature = "canbench-rs")]
+mod benches;
 
 // Import specific types
-use models::content::{ContentType, PaginationParams, CreatePostRequest, UpdatePostRequest, PostResponse, PostsResponse, CreateCommentRequest, UpdateCommentRequest, CommentResponse, CommentsResponse, ModerateContentRequest};
-use models::user::{RegisterUserRequest, UpdateProfileRequest, UserProfileResponse, UserSocialResponse, UserLeaderboardResponse, FollowUserRequest};
-use models::interaction::{LikeContentRequest, LikesResponse, ReportContentRequest};
-use models::reward::{CompleteTaskRequest, TaskCompletionResponse, TaskResponse, AwardPointsRequest, CreateTaskRequest, UpdateTaskRequest, UserRewardsResponse, Value};
-use models::discovery::{DiscoverContentRequest, SearchRequest, SearchResultResponse, GetTrendingTopicsRequest, TrendingTopicResponse, PersonalizedRecommendationsRequest};
-use models::display::{FeedResponse};
+use models::content::{ContentType, ModeratedContent, PaginationParams, CreatePostRequest, UpdatePostRequest, PostResponse, PostsResponse, CrossPostRequest, SetContentScheduleRequest, ScheduledPostResponse, CreateCommentRequest, UpdateCommentRequest, CommentResponse, CommentsResponse, ModerateContentRequest, ContentLicense, ArticleSummary};
+use models::user::{RegisterUserRequest, UpdateProfileRequest, UserProfileResponse, UserSocialResponse, UserLeaderboardResponse, UserLeaderboardItem, FollowUserRequest, FollowState, FollowTopicRequest};
+use models::user::{UserStatus, UserRole, DeactivateAccountRequest, AdminUserListRequest, DebugUserListRequest, AdminUserSummary, UserModerationHistoryResponse, BulkUserStatusUpdateRequest, BulkUserStatusUpdateResponse, UserContentCounts, FollowCounterRepairResponse};
+use models::notification::{NotificationRetentionSettings, NotificationsResponse, NotificationsSinceResponse, PostAlertState};
+use models::subscription::ContentSubscriptionResponse;
+use models::anchoring::ContentIntegrityResponse;
+use models::api_version::ApiVersionResponse;
+use models::access_policy::{EndpointAccess, SetAnonymousReadAccessRequest};
+use models::legal_hold::{LegalHold, LegalHoldExport, PlaceLegalHoldRequest};
+use models::interaction::{LikeContentRequest, LikeState, LikesResponse, ReportContentRequest, ReportResult, ViewContentRequest, ContentReport, ResolveReportRequest, InteractionResponse};
+use models::reward::{CompleteTaskRequest, TaskCompletionResponse, TaskResponse, AwardPointsRequest, CreateTaskRequest, UpdateTaskRequest, UserRewardsResponse, Value, PointsExpirySettings, PointsExpiryState};
+use models::discovery::{DiscoverContentRequest, SearchRequest, SearchResultResponse, GetTrendingTopicsRequest, TrendingTopicResponse, TrendingMaintenanceStats, PersonalizedRecommendationsRequest, SimilarityRefreshStatus, FeedComposition, NewcomerBoostSettings, RisingCreator};
+use models::display::{FeedResponse, PaginatedResponse};
 use models::error::{SquareError, SquareResult, ErrorCode};
-use models::tag::{};
-use models::cycles::{CyclesBalanceResponse, CyclesConsumptionResponse, UpdateCyclesThresholdRequest, CyclesThresholdConfig, CyclesNotificationsResponse, NotificationSettings, UpdateHeartbeatIntervalRequest, HeartbeatIntervalResponse};
-use utils::middleware::{ApiResponse, with_error_handling};
+use models::tag::{TagResponse, UpdateTagRequest, TopicPageResponse, PinTopicPostRequest};
+use models::delegation::{PostingDelegation, DelegationAuditResponse};
+use models::creator::*;
+use models::task_registry::{TaskCanisterRegistration, RegisterTaskCanisterRequest, UpdateTaskCanisterRequest, TaskCanisterSyncResult, TaskWebhookDelivery};
+use models::task_template::{CreateTaskTemplateRequest, TaskTemplate, UpdateTaskTemplateRequest};
+use models::treasury::{AdjustPointsRequest, LedgerEntry, MintBudget, MintBudgetResponse, PointsReconciliationReport, SetMintBudgetRequest};
+use models::invite::{InviteCodeResponse, MyInvitesResponse, MintInviteCodesRequest, InviteSettings, UpdateInviteSettingsRequest};
+use models::anomaly::{AnomalyStatus, EngagementAnomaly, ReviewAnomalyRequest, AnomalyDetectionSettings, UpdateAnomalySettingsRequest};
+use models::account_quality::AccountQuality;
+use models::trust::{TrustLevelResponse, TrustSettings};
+use models::name_policy::{NamePolicySettings, UpdateNamePolicyRequest};
+use models::companion::{CompanionCanister, RegisterCompanionCanisterRequest, RegisterFromRequest};
+use models::timeline::TimelineSettings;
+use models::impression::{ImpressionSettings, ImpressionStatsResponse, RecordImpressionsRequest};
+use models::event::{DailyTaskProgress, EventConsumer, InteractionEventLogStatus};
+use models::index_rebuild::{IndexRebuildProgress, IndexScope};
+use models::integrity::IntegrityAuditStatus;
+use models::quota::{StorageQuotaSettings, StorageUsageResponse};
+use models::http::{HttpGatewayRequest, HttpGatewayResponse, StreamingCallbackHttpResponse, StreamingCallbackToken};
+use models::token_gate::{GateTarget, SetTokenGateRequest, TokenGateAccessResponse};
+use models::showcase::{AddShowcaseNftRequest, ShowcaseNft};
+use models::profile_visit::ProfileVisitorsResponse;
+use models::activity::ActivityCalendarResponse;
+use models::follow_graph::FollowGraphPage;
+use models::approval::{ContentApprovalSettings, PendingPostResponse, ReviewPendingPostRequest};
+use models::identity::{AccountLinkChallenge, LinkedAccount, RequestAccountLinkRequest, TokenHoldings};
+use models::feature_flag::{FeatureFlagResponse, SetFeatureFlagRequest};
+use models::maintenance::{SetMaintenanceModeRequest, SystemBanner};
+use models::classifier::{ClassifierSettingsResponse, ClassifierVerdict, UpdateClassifierSettingsRequest};
+use models::sanitizer::HtmlSanitizerSettings;
+use models::emoji::{AddCustomEmojiRequest, CustomEmoji, ReactToContentRequest};
+use models::translation::{TranslationResponse, TranslationSettingsResponse, UpdateTranslationSettingsRequest};
+use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
+use models::syndication::{RegisterSyndicationPartnerRequest, SyndicationFeedResponse, SyndicationPartner, SyndicationPartnerCredentials, UpdateSyndicationPartnerRequest};
+use models::openchat::{MirroredMessage, OpenChatBridgeSettings, OpenChatTopicRoute, SetOpenChatTopicRouteRequest, UpdateOpenChatSettingsRequest};
+use models::shard::{ContentShard, RegisterContentShardRequest, ShardedPostsResponse, ShardedSearchResponse};
+use models::quiz::{CreateQuizRequest, SubmitQuizAnswersRequest, DailyQuizResponse, QuizResultResponse, QuizSubmission};
+use models::cycles::{CyclesBalanceResponse, CyclesConsumptionResponse, CyclesRunwayForecast, FreezeGuardStatus, UpdateCyclesThresholdRequest, CyclesThresholdConfig, CyclesNotificationsResponse, NotificationSettings, UpdateHeartbeatIntervalRequest, HeartbeatIntervalResponse};
+use utils::middleware::{ApiResponse, with_error_handling, with_read_access, with_update_handling};
 
 use models::discovery::GetHotTagsRequest as DiscoveryGetHotTagsRequest;
 use models::discovery::HotTagsResponse as DiscoveryHotTagsResponse;
@@ -31,239 +77,1533 @@ fn init() {
     // Register global error handler
     utils::error_interceptor::register_global_error_handler();
     
+    storage::attach_stable_collections();
     auth::init_admin();
     services::cycles::init_cycles_monitoring();
 
     
-    // Initialize default tasks
-    services::reward::init_default_tasks_all_enabled();
+    // Seed the built-in task templates and their first instances
+    services::task_template::init_default_task_templates();
 }
 
 // User API
 #[update]
 fn register_user(request: RegisterUserRequest) -> ApiResponse<()> {
-    with_error_handling(|| services::user::register_user(request, caller()))()
+    with_update_handling(|| services::user::register_user(request, caller()))()
+}
+
+#[update]
+async fn register_from(request: RegisterFromRequest) -> ApiResponse<()> {
+    let result = match services::maintenance::check_maintenance_mode().and_then(|()| auth::get_authenticated_caller()) {
+        Ok(caller) => services::user::register_from(request, caller).await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+#[query]
+fn get_companion_canisters() -> ApiResponse<Vec<CompanionCanister>> {
+    with_read_access("get_companion_canisters", services::user::get_companion_canisters)()
+}
+
+#[update]
+fn register_companion_canister(request: RegisterCompanionCanisterRequest) -> ApiResponse<CompanionCanister> {
+    with_update_handling(|| services::user::register_companion_canister(request, caller()))()
+}
+
+#[update]
+fn remove_companion_canister(canister_id: Principal) -> ApiResponse<()> {
+    with_update_handling(|| services::user::remove_companion_canister(canister_id))()
 }
 
 #[update]
 fn update_user_profile(request: UpdateProfileRequest) -> ApiResponse<String> {
-    with_error_handling(|| services::user::update_user_profile(request, caller()))()
+    with_update_handling(|| services::user::update_user_profile(request, caller()))()
+}
+
+#[update]
+fn login() -> ApiResponse<UserStatus> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::record_login(caller)
+    })()
+}
+
+#[update]
+fn set_timezone_offset(offset_minutes: i32) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::set_timezone_offset(offset_minutes, caller)
+    })()
 }
 
+// Account quality
 #[query]
-fn get_user_profile(user_identifier: Option<String>) -> ApiResponse<UserProfileResponse> {
+fn get_my_account_quality() -> ApiResponse<AccountQuality> {
     with_error_handling(|| {
-        let identifier = match user_identifier {
-            Some(id) => id,
-            None => caller().to_text()
-        };
-        services::user::get_user_profile(identifier)
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_my_account_quality(caller)
+    })()
+}
+
+#[query]
+fn get_my_trust_level() -> ApiResponse<TrustLevelResponse> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_my_trust_level(caller)
     })()
 }
 
+#[query]
+fn get_trust_settings() -> ApiResponse<TrustSettings> {
+    with_error_handling(services::user::get_trust_settings)()
+}
+
+#[update]
+fn update_trust_settings(settings: TrustSettings) -> ApiResponse<TrustSettings> {
+    with_update_handling(|| services::user::update_trust_settings(settings))()
+}
+
+#[query]
+fn get_content_approval_settings() -> ApiResponse<ContentApprovalSettings> {
+    with_error_handling(services::content::get_content_approval_settings)()
+}
+
+#[update]
+fn update_content_approval_settings(settings: ContentApprovalSettings) -> ApiResponse<ContentApprovalSettings> {
+    with_update_handling(|| services::content::update_content_approval_settings(settings))()
+}
+
+#[query]
+fn get_approval_queue(pagination: PaginationParams) -> ApiResponse<PaginatedResponse<PendingPostResponse>> {
+    with_error_handling(|| services::content::get_approval_queue(pagination))()
+}
+
 #[update]
-fn follow_user(principal: Principal) -> ApiResponse<()> {
+fn review_pending_post(request: ReviewPendingPostRequest) -> ApiResponse<ModeratedContent> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::content::review_pending_post(request, caller)
+    })()
+}
+
+#[query]
+fn get_my_storage_usage() -> ApiResponse<StorageUsageResponse> {
     with_error_handling(|| {
-        let request = FollowUserRequest {
-            user_to_follow: principal
-        };
-        services::user::follow_user(request, caller())
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_my_storage_usage(caller)
+    })()
+}
+
+#[query]
+fn get_storage_quota_settings() -> ApiResponse<StorageQuotaSettings> {
+    with_read_access("get_storage_quota_settings", services::user::get_storage_quota_settings)()
+}
+
+#[update]
+fn update_storage_quota_settings(settings: StorageQuotaSettings) -> ApiResponse<StorageQuotaSettings> {
+    with_update_handling(|| services::user::update_storage_quota_settings(settings))()
+}
+
+#[update]
+fn set_storage_quota_override(user: Principal, quota_bytes: Option<u64>) -> ApiResponse<StorageUsageResponse> {
+    with_update_handling(|| services::user::set_storage_quota_override(user, quota_bytes))()
+}
+
+#[update]
+fn run_follow_counter_repair() -> ApiResponse<FollowCounterRepairResponse> {
+    with_update_handling(services::user::run_follow_counter_repair)()
+}
+
+#[query]
+fn get_name_policy() -> ApiResponse<NamePolicySettings> {
+    with_error_handling(services::user::get_name_policy)()
+}
+
+#[update]
+fn update_name_policy(request: UpdateNamePolicyRequest) -> ApiResponse<NamePolicySettings> {
+    with_update_handling(|| services::user::update_name_policy(request, caller()))()
+}
+
+// Linked wallets and neurons
+#[update]
+fn request_account_link(request: RequestAccountLinkRequest) -> ApiResponse<AccountLinkChallenge> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::request_account_link(request, caller)
+    })()
+}
+
+#[update]
+fn confirm_account_link(challenge: String) -> ApiResponse<LinkedAccount> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::confirm_account_link(challenge, caller)
     })()
 }
 
 #[update]
-fn unfollow_user(principal: Principal) -> ApiResponse<()> {
+fn unlink_account(account: Principal) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::unlink_account(account, caller)
+    })()
+}
+
+#[query]
+fn get_my_linked_accounts() -> ApiResponse<Vec<LinkedAccount>> {
     with_error_handling(|| {
-        let request = FollowUserRequest {
-            user_to_follow: principal
-        };
-        services::user::unfollow_user(request, caller())
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_my_linked_accounts(caller)
+    })()
+}
+
+// NFT showcase
+#[update]
+async fn add_showcase_nft(request: AddShowcaseNftRequest) -> ApiResponse<ShowcaseNft> {
+    let result = match services::maintenance::check_maintenance_mode().and_then(|()| auth::get_authenticated_caller()) {
+        Ok(caller) => services::user::add_showcase_nft(request, caller).await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+#[update]
+fn remove_showcase_nft(canister_id: Principal, token_id: String) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::remove_showcase_nft(canister_id, token_id, caller)
+    })()
+}
+
+// Profile visitors
+#[update]
+fn record_profile_visit(user_identifier: String) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        let profile_owner = services::user::resolve_user_identifier(&user_identifier)?;
+        services::user::record_profile_visit(profile_owner, caller)
     })()
 }
 
 #[query]
-fn get_followers(user_identifier: Option<String>) -> ApiResponse<Vec<UserSocialResponse>> {
+fn get_profile_visitors(pagination: PaginationParams) -> ApiResponse<ProfileVisitorsResponse> {
     with_error_handling(|| {
-        let current_caller = auth::get_authenticated_caller()?;
-        let identifier = match user_identifier {
-            Some(id) => id,
-            None => caller().to_text()
-        };
-        services::user::get_followers(identifier, Some(current_caller))
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_profile_visitors(pagination, caller)
     })()
 }
 
+// Per-day posts and comments for the caller's contribution graph, with posting streaks
 #[query]
-fn get_following(user_identifier: Option<String>) -> ApiResponse<Vec<UserSocialResponse>> {
+fn get_my_activity_calendar(year: u64) -> ApiResponse<ActivityCalendarResponse> {
     with_error_handling(|| {
-        let current_caller = auth::get_authenticated_caller()?;
-        let identifier = match user_identifier {
-            Some(id) => id,
-            None => caller().to_text()
-        };
-        services::user::get_following(identifier, Some(current_caller))
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_my_activity_calendar(year, caller)
     })()
 }
 
+// Notifications
 #[query]
-fn get_user_leaderboard(pagination: PaginationParams) -> ApiResponse<UserLeaderboardResponse> {
+fn get_notifications(pagination: PaginationParams) -> ApiResponse<NotificationsResponse> {
     with_error_handling(|| {
-        // Only verify caller is not anonymous
-        auth::get_authenticated_caller()?;
-        services::user::get_user_leaderboard(pagination)
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_user_notifications(caller, pagination)
+    })()
+}
+
+// Incremental sync for devices that keep a local copy: pass the previous `latest_seq`
+#[query]
+fn get_notifications_since(since: u64, limit: Option<u64>) -> ApiResponse<NotificationsSinceResponse> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_notifications_since(caller, since, limit)
     })()
 }
 
-// Content API
 #[update]
-fn create_post(request: CreatePostRequest) -> ApiResponse<PostResponse> {
-    with_error_handling(|| services::content::create_post(request, caller()))()
+fn mark_notification_as_read(notification_id: String) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::mark_notification_as_read(caller, notification_id)
+    })()
 }
 
 #[update]
-fn update_post(request: UpdatePostRequest) -> ApiResponse<PostResponse> {
-    with_error_handling(|| services::content::update_post(request, caller()))()
+fn mark_all_notifications_as_read() -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::mark_all_notifications_as_read(caller)
+    })()
 }
 
-#[query]
-fn get_post(post_id: String) -> SquareResult<PostResponse> {
-    services::content::get_post(post_id)
+// The "notify me when they post" bell for a followed user
+#[update]
+fn set_post_alerts(user_identifier: String, enabled: bool) -> ApiResponse<PostAlertState> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        let author = services::user::resolve_user_identifier(&user_identifier)?;
+        services::user::set_post_alerts(author, enabled, caller)
+    })()
 }
 
 #[query]
-fn get_posts(pagination: PaginationParams) -> SquareResult<PostsResponse> {
-    services::content::get_posts(pagination)
+fn get_post_alerts() -> ApiResponse<Vec<Principal>> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_post_alerts(caller)
+    })()
+}
+
+// Following a post's comment thread: subscribers get a digest of new comments, muting silences
+// a thread the caller would otherwise be subscribed to
+#[update]
+fn subscribe_to_content(content_id: String) -> ApiResponse<ContentSubscriptionResponse> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::content::subscribe_to_content(content_id, caller)
+    })()
 }
 
 #[update]
-fn delete_post(post_id: String) -> SquareResult<()> {
-    services::content::delete_post(post_id, caller())
+fn mute_content(content_id: String) -> ApiResponse<ContentSubscriptionResponse> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::content::mute_content(content_id, caller)
+    })()
 }
 
 #[update]
-fn create_comment(request: CreateCommentRequest) -> SquareResult<CommentResponse> {
-    services::content::create_comment(request, caller())
+fn unsubscribe_from_content(content_id: String) -> ApiResponse<ContentSubscriptionResponse> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::content::unsubscribe_from_content(content_id, caller)
+    })()
 }
 
+#[query]
+fn get_content_subscription(content_id: String) -> ApiResponse<ContentSubscriptionResponse> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::content::get_content_subscription(content_id, caller)
+    })()
+}
+
+// Removes the caller's notifications created before `older_than`, or all of them; returns how many
 #[update]
-fn update_comment(request: UpdateCommentRequest) -> SquareResult<CommentResponse> {
-    services::content::update_comment(request, caller())
+fn clear_notifications(older_than: Option<u64>) -> ApiResponse<u64> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::clear_notifications(caller, older_than)
+    })()
 }
 
 #[query]
-fn get_comment(comment_id: String) -> SquareResult<CommentResponse> {
-    services::content::get_comment(comment_id, Some(caller()))
+fn get_notification_retention() -> ApiResponse<NotificationRetentionSettings> {
+    with_error_handling(services::user::get_notification_retention)()
+}
+
+#[update]
+fn update_notification_retention(settings: NotificationRetentionSettings) -> ApiResponse<NotificationRetentionSettings> {
+    with_update_handling(|| services::user::update_notification_retention(settings))()
+}
+
+// Token-gated communities and posts
+#[update]
+fn set_token_gate(request: SetTokenGateRequest) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::token_gate::set_token_gate(request, caller)
+    })()
 }
 
 #[update]
-fn delete_comment(comment_id: String) -> SquareResult<()> {
-    services::content::delete_comment(comment_id, caller())
+async fn verify_token_gate(target: GateTarget) -> ApiResponse<TokenGateAccessResponse> {
+    let result = match services::maintenance::check_maintenance_mode().and_then(|()| auth::get_authenticated_caller()) {
+        Ok(caller) => services::token_gate::verify_token_gate(target, caller).await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
 }
 
 #[query]
-fn get_comments(parent_id: String, parent_type: String, pagination: PaginationParams) -> SquareResult<CommentsResponse> {
-    services::content::get_comments(parent_id, parent_type, pagination, Some(caller()))
+fn get_token_gate_status(target: GateTarget) -> ApiResponse<TokenGateAccessResponse> {
+    with_read_access("get_token_gate_status", || services::token_gate::get_token_gate_status(target, caller()))()
 }
 
 #[query]
-fn get_user_content(user_identifier: Option<String>, content_type: Option<ContentType>, pagination: PaginationParams) -> SquareResult<FeedResponse> {
-    let identifier = match user_identifier {
-        Some(id) => id,
-        None => caller().to_text()
+fn get_gated_post(post_id: String) -> ApiResponse<PostResponse> {
+    with_read_access("get_gated_post", || services::token_gate::get_gated_post(post_id, caller()))()
+}
+
+#[query(composite = true)]
+async fn get_my_token_holdings(ledger_canister_id: Principal) -> ApiResponse<TokenHoldings> {
+    let result = match auth::get_authenticated_caller() {
+        Ok(caller) => services::user::token_holdings(caller, ledger_canister_id).await,
+        Err(error) => Err(error),
     };
-    services::content::get_user_content(identifier, content_type, crate::models::content::PaginationParams { offset: pagination.offset, limit: pagination.limit })
+    with_error_handling(|| result)()
+}
+
+#[query]
+fn get_account_quality(user_identifier: String) -> ApiResponse<AccountQuality> {
+    with_read_access("get_account_quality", || {
+        let user = services::user::resolve_user_identifier(&user_identifier)?;
+        services::user::get_account_quality(user)
+    })()
+}
+
+// Invites
+#[update]
+async fn create_invite_code() -> ApiResponse<InviteCodeResponse> {
+    let result = match services::maintenance::check_maintenance_mode().and_then(|()| auth::get_authenticated_caller()) {
+        Ok(caller) => services::user::create_invite_code(caller).await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+#[query]
+fn get_my_invites() -> ApiResponse<MyInvitesResponse> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_my_invites(caller)
+    })()
 }
 
-// Interaction API
 #[update]
-fn like_content(request: LikeContentRequest) -> SquareResult<()> {
-    services::interaction::like_content(request.content_id, request.content_type, caller())
+async fn mint_invite_codes(request: MintInviteCodesRequest) -> ApiResponse<Vec<String>> {
+    let result = match services::maintenance::check_maintenance_mode().and_then(|()| auth::get_authenticated_caller()) {
+        Ok(caller) => services::user::mint_invite_codes(request, caller).await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+#[query]
+fn get_invite_settings() -> ApiResponse<InviteSettings> {
+    with_error_handling(services::user::get_invite_settings)()
 }
 
 #[update]
-fn unlike_content(request: LikeContentRequest) -> SquareResult<()> {
-    services::interaction::unlike_content(request.content_id, request.content_type, caller())
+fn update_invite_settings(request: UpdateInviteSettingsRequest) -> ApiResponse<InviteSettings> {
+    with_update_handling(|| services::user::update_invite_settings(request))()
 }
 
 #[query]
-fn get_likes(content_id: String, content_type: ContentType) -> SquareResult<LikesResponse> {
-    services::interaction::get_likes(content_id, content_type)
+fn get_timezone_offset(user_identifier: String) -> ApiResponse<i32> {
+    with_read_access("get_timezone_offset", || {
+        let user = services::user::resolve_user_identifier(&user_identifier)?;
+        Ok(services::user::user_timezone_offset(&user))
+    })()
 }
 
 #[update]
-fn report_content(request: ReportContentRequest) -> SquareResult<()> {
-    services::interaction::report_content(request, caller())
+fn deactivate_account(request: DeactivateAccountRequest) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::deactivate_account(request, caller)
+    })()
+}
+
+#[update]
+fn reactivate_account() -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::reactivate_account(caller)
+    })()
 }
 
-// Discovery API
 #[query]
-fn discover_content(request: DiscoverContentRequest) -> SquareResult<FeedResponse> {
-    services::discovery::discover_content(request)
+fn get_user_profile(user_identifier: Option<String>) -> ApiResponse<UserProfileResponse> {
+    with_read_access("get_user_profile", || {
+        let identifier = match user_identifier {
+            Some(id) => id,
+            None => caller().to_text()
+        };
+        services::user::get_user_profile(identifier)
+    })()
+}
+
+#[update]
+fn follow_user(principal: Principal) -> ApiResponse<FollowState> {
+    with_update_handling(|| {
+        let request = FollowUserRequest {
+            user_to_follow: principal
+        };
+        services::user::follow_user(request, caller())
+    })()
+}
+
+#[update]
+fn unfollow_user(principal: Principal) -> ApiResponse<FollowState> {
+    with_update_handling(|| {
+        let request = FollowUserRequest {
+            user_to_follow: principal
+        };
+        services::user::unfollow_user(request, caller())
+    })()
+}
+
+// follow_user and unfollow_user keep their principal argument for existing clients; these take a
+// principal or @handle
+#[update]
+fn follow_user_by_identifier(user_identifier: String) -> ApiResponse<FollowState> {
+    with_update_handling(|| {
+        let request = FollowUserRequest {
+            user_to_follow: services::user::resolve_user_identifier(&user_identifier)?
+        };
+        services::user::follow_user(request, caller())
+    })()
+}
+
+#[update]
+fn unfollow_user_by_identifier(user_identifier: String) -> ApiResponse<FollowState> {
+    with_update_handling(|| {
+        let request = FollowUserRequest {
+            user_to_follow: services::user::resolve_user_identifier(&user_identifier)?
+        };
+        services::user::unfollow_user(request, caller())
+    })()
 }
 
 #[query]
-fn search_content(request: SearchRequest) -> SquareResult<Vec<SearchResultResponse>> {
-    services::discovery::search_content(request)
+fn get_followers(user_identifier: Option<String>) -> ApiResponse<Vec<UserSocialResponse>> {
+    with_read_access("get_followers", || {
+        // Anonymous callers have to name the user
+        let identifier = match user_identifier {
+            Some(id) => id,
+            None => auth::get_authenticated_caller()?.to_text()
+        };
+        let current_caller = Some(caller()).filter(|caller| *caller != Principal::anonymous());
+        services::user::get_followers(identifier, current_caller)
+    })()
 }
 
 #[query]
-fn get_trending_topics(request: GetTrendingTopicsRequest) -> SquareResult<Vec<TrendingTopicResponse>> {
-    services::discovery::get_trending_topics(request)
+fn get_following(user_identifier: Option<String>) -> ApiResponse<Vec<UserSocialResponse>> {
+    with_read_access("get_following", || {
+        // Anonymous callers have to name the user
+        let identifier = match user_identifier {
+            Some(id) => id,
+            None => auth::get_authenticated_caller()?.to_text()
+        };
+        let current_caller = Some(caller()).filter(|caller| *caller != Principal::anonymous());
+        services::user::get_following(identifier, current_caller)
+    })()
+}
+
+// Delegated posting API
+#[update]
+fn grant_posting_delegate(delegate: String) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let current_caller = auth::get_authenticated_caller()?;
+        let delegate = services::user::resolve_user_identifier(&delegate)?;
+        services::user::grant_posting_delegate(delegate, current_caller)
+    })()
+}
+
+#[update]
+fn revoke_posting_delegate(delegate: String) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let current_caller = auth::get_authenticated_caller()?;
+        let delegate = services::user::resolve_user_identifier(&delegate)?;
+        services::user::revoke_posting_delegate(delegate, current_caller)
+    })()
 }
 
 #[query]
-fn get_hot_tags(request: DiscoveryGetHotTagsRequest) -> SquareResult<DiscoveryHotTagsResponse> {
-    services::discovery::get_hot_tags(request)
+fn get_posting_delegates(user_identifier: Option<String>) -> ApiResponse<Vec<PostingDelegation>> {
+    with_read_access("get_posting_delegates", || {
+        let org = match user_identifier {
+            Some(id) => services::user::resolve_user_identifier(&id)?,
+            None => auth::get_authenticated_caller()?,
+        };
+        services::user::get_posting_delegates(org)
+    })()
 }
 
 #[query]
-fn get_personalized_recommendations(request: PersonalizedRecommendationsRequest) -> SquareResult<FeedResponse> {
-    services::discovery::get_personalized_recommendations(request)
+fn get_delegation_audit_log(user_identifier: Option<String>, pagination: PaginationParams) -> ApiResponse<DelegationAuditResponse> {
+    with_error_handling(|| {
+        let current_caller = auth::get_authenticated_caller()?;
+        let org = match user_identifier {
+            Some(id) => services::user::resolve_user_identifier(&id)?,
+            None => current_caller,
+        };
+        services::user::get_delegation_audit_log(org, current_caller, pagination)
+    })()
 }
 
-// Rewards API
-// Note: claim_daily_check_in has been moved to the daily_checkin_task canister
-// Users should call that canister directly for check-ins
+// Creator program API
+#[update]
+fn apply_for_creator(request: CreatorApplicationRequest) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::apply_for_creator(request, caller)
+    })()
+}
+
+#[query]
+fn get_my_creator_application() -> ApiResponse<CreatorApplication> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_creator_application(caller)
+    })()
+}
 
-#[update(name = "complete_task")]
-fn complete_task_async(request: CompleteTaskRequest) -> SquareResult<TaskCompletionResponse> {
-    services::reward::complete_task(request, caller())
+#[update]
+fn review_creator_application(request: ReviewCreatorApplicationRequest) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::review_creator_application(request, caller)
+    })()
 }
 
 #[query]
-fn get_user_rewards() -> SquareResult<UserRewardsResponse> {
-    services::reward::get_user_rewards(caller())
+fn get_creator_applications(status: Option<CreatorApplicationStatus>, pagination: PaginationParams) -> ApiResponse<PaginatedResponse<CreatorApplication>> {
+    with_error_handling(|| {
+        auth::get_authenticated_caller()?;
+        services::user::get_creator_applications(status, pagination)
+    })()
+}
+
+#[query]
+fn get_creators(pagination: PaginationParams) -> ApiResponse<PaginatedResponse<UserSocialResponse>> {
+    with_read_access("get_creators", || {
+        services::user::get_creators(pagination)
+    })()
+}
+
+#[query]
+fn get_creator_analytics() -> ApiResponse<CreatorAnalyticsResponse> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_creator_analytics(caller)
+    })()
+}
+
+#[query]
+fn get_creator_thresholds() -> ApiResponse<CreatorPromotionThresholds> {
+    with_read_access("get_creator_thresholds", || {
+        services::user::get_creator_thresholds()
+    })()
+}
+
+#[update]
+fn update_creator_thresholds(request: UpdateCreatorThresholdsRequest) -> ApiResponse<CreatorPromotionThresholds> {
+    with_update_handling(|| {
+        auth::get_authenticated_caller()?;
+        services::user::update_creator_thresholds(request)
+    })()
+}
+
+#[query]
+fn get_user_leaderboard(pagination: PaginationParams) -> ApiResponse<UserLeaderboardResponse> {
+    with_read_access("get_user_leaderboard", || services::user::get_user_leaderboard(pagination))()
+}
+
+// The caller's own rank and points, also for users hidden from the public leaderboard
+#[query]
+fn get_my_leaderboard_rank() -> ApiResponse<UserLeaderboardItem> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::get_my_leaderboard_rank(caller)
+    })()
+}
+
+// Content API
+#[update]
+fn create_post(request: CreatePostRequest) -> ApiResponse<PostResponse> {
+    with_update_handling(|| services::content::create_post(request, caller()))()
+}
+
+#[update]
+fn update_post(request: UpdatePostRequest) -> ApiResponse<PostResponse> {
+    with_update_handling(|| services::content::update_post(request, caller()))()
+}
+
+#[query]
+fn get_post_v2(post_id: String) -> ApiResponse<PostResponse> {
+    with_read_access("get_post", || services::content::get_post(post_id))()
+}
+
+#[query]
+fn verify_content_integrity(content_id: String) -> ApiResponse<ContentIntegrityResponse> {
+    with_read_access("verify_content_integrity", || services::content::verify_content_integrity(content_id))()
+}
+
+#[query]
+fn get_posts_v2(pagination: PaginationParams) -> ApiResponse<PostsResponse> {
+    with_read_access("get_posts", || services::content::get_posts(pagination))()
+}
+
+#[query]
+fn get_content_by_license(license: ContentLicense, since: Option<u64>, pagination: PaginationParams) -> ApiResponse<PaginatedResponse<PostResponse>> {
+    with_read_access("get_content_by_license", || services::content::get_content_by_license(license, since, pagination))()
+}
+
+#[query]
+fn get_article_summary(article_id: String) -> ApiResponse<ArticleSummary> {
+    with_read_access("get_article_summary", || services::content::get_article_summary(article_id))()
+}
+
+#[update]
+fn delete_post_v2(post_id: String) -> ApiResponse<()> {
+    with_update_handling(|| services::content::delete_post(post_id, caller()))()
+}
+
+// Shares a post into another tag community; the cross-post links back to it and shares its likes and comments
+#[update]
+fn cross_post(request: CrossPostRequest) -> ApiResponse<PostResponse> {
+    with_update_handling(|| services::content::cross_post(request, caller()))()
+}
+
+#[query]
+fn get_cross_posts(post_id: String) -> ApiResponse<Vec<PostResponse>> {
+    with_read_access("get_cross_posts", || services::content::get_cross_posts(post_id))()
+}
+
+// Sets or clears a post's embargo and sunset times; embargoes only apply to posts not yet published
+#[update]
+fn set_content_schedule(request: SetContentScheduleRequest) -> ApiResponse<ScheduledPostResponse> {
+    with_update_handling(|| services::content::set_content_schedule(request, caller()))()
+}
+
+#[query]
+fn get_my_scheduled_posts() -> ApiResponse<Vec<ScheduledPostResponse>> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::content::get_my_scheduled_posts(caller)
+    })()
+}
+
+#[update]
+fn create_comment_v2(request: CreateCommentRequest) -> ApiResponse<CommentResponse> {
+    with_update_handling(|| services::content::create_comment(request, caller()))()
+}
+
+#[update]
+fn update_comment_v2(request: UpdateCommentRequest) -> ApiResponse<CommentResponse> {
+    with_update_handling(|| services::content::update_comment(request, caller()))()
+}
+
+#[query]
+fn get_comment_v2(comment_id: String) -> ApiResponse<CommentResponse> {
+    with_read_access("get_comment", || services::content::get_comment(comment_id, Some(caller())))()
+}
+
+#[update]
+fn delete_comment_v2(comment_id: String) -> ApiResponse<()> {
+    with_update_handling(|| services::content::delete_comment(comment_id, caller()))()
+}
+
+#[query]
+fn get_comments_v2(parent_id: String, parent_type: String, pagination: PaginationParams) -> ApiResponse<CommentsResponse> {
+    with_read_access("get_comments", || services::content::get_comments(parent_id, parent_type, pagination, Some(caller())))()
+}
+
+#[query]
+fn get_user_content_v2(user_identifier: Option<String>, content_type: Option<ContentType>, pagination: PaginationParams) -> ApiResponse<FeedResponse> {
+    with_read_access("get_user_content", || {
+        let identifier = match user_identifier {
+            Some(id) => id,
+            None => caller().to_text()
+        };
+        services::content::get_user_content(identifier, content_type, crate::models::content::PaginationParams { offset: pagination.offset, limit: pagination.limit })
+    })()
+}
+
+#[query]
+fn get_following_feed(pagination: PaginationParams) -> ApiResponse<FeedResponse> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::content::get_following_feed(pagination, caller)
+    })()
+}
+
+#[query]
+fn get_timeline_settings() -> ApiResponse<TimelineSettings> {
+    with_error_handling(services::content::timeline::get_timeline_settings)()
+}
+
+#[update]
+fn update_timeline_settings(settings: TimelineSettings) -> ApiResponse<TimelineSettings> {
+    with_update_handling(|| services::content::timeline::update_timeline_settings(settings))()
+}
+
+#[query]
+fn get_home_feed(pagination: PaginationParams) -> ApiResponse<FeedResponse> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::discovery::get_home_feed(pagination, caller)
+    })()
+}
+
+#[query]
+fn get_explore_feed(pagination: PaginationParams) -> ApiResponse<FeedResponse> {
+    with_read_access("get_explore_feed", || services::discovery::get_explore_feed(pagination))()
+}
+
+#[query]
+fn get_feed_composition() -> ApiResponse<FeedComposition> {
+    with_error_handling(services::discovery::get_feed_composition)()
+}
+
+#[update]
+fn update_feed_composition(composition: FeedComposition) -> ApiResponse<FeedComposition> {
+    with_update_handling(|| services::discovery::update_feed_composition(composition))()
+}
+
+#[query]
+fn get_rising_creators(limit: Option<usize>) -> ApiResponse<Vec<RisingCreator>> {
+    with_read_access("get_rising_creators", || services::discovery::get_rising_creators(limit))()
+}
+
+#[query]
+fn get_newcomer_boost() -> ApiResponse<NewcomerBoostSettings> {
+    with_error_handling(services::discovery::get_newcomer_boost)()
+}
+
+#[update]
+fn update_newcomer_boost(settings: NewcomerBoostSettings) -> ApiResponse<NewcomerBoostSettings> {
+    with_update_handling(|| services::discovery::update_newcomer_boost(settings))()
+}
+
+#[update]
+fn record_impressions(request: RecordImpressionsRequest) -> ApiResponse<u64> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::impression::record_impressions(request, caller)
+    })()
+}
+
+#[query]
+fn get_impression_stats() -> ApiResponse<ImpressionStatsResponse> {
+    with_error_handling(services::impression::get_impression_stats)()
+}
+
+#[query]
+fn get_impression_settings() -> ApiResponse<ImpressionSettings> {
+    with_error_handling(services::impression::get_impression_settings)()
+}
+
+#[update]
+fn update_impression_settings(settings: ImpressionSettings) -> ApiResponse<ImpressionSettings> {
+    with_update_handling(|| services::impression::update_impression_settings(settings))()
+}
+
+// Interaction API
+#[update]
+fn like_content_v2(request: LikeContentRequest) -> ApiResponse<LikeState> {
+    with_update_handling(|| services::interaction::like_content(request.content_id, request.content_type, caller()))()
+}
+
+#[update]
+fn unlike_content_v2(request: LikeContentRequest) -> ApiResponse<LikeState> {
+    with_update_handling(|| services::interaction::unlike_content(request.content_id, request.content_type, caller()))()
+}
+
+#[update]
+fn toggle_like(request: LikeContentRequest) -> ApiResponse<LikeState> {
+    with_update_handling(|| services::interaction::toggle_like(request, caller()))()
+}
+
+#[query]
+fn get_likes_v2(content_id: String, content_type: ContentType) -> ApiResponse<LikesResponse> {
+    with_read_access("get_likes", || services::interaction::get_likes(content_id, content_type))()
+}
+
+#[update]
+fn react_to_content(request: ReactToContentRequest) -> ApiResponse<InteractionResponse> {
+    with_update_handling(|| services::emoji::react_to_content(request, caller()))()
+}
+
+#[update]
+fn record_view(request: ViewContentRequest) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::interaction::view_content(request, caller)
+    })()
+}
+
+#[update]
+fn report_content_v2(request: ReportContentRequest) -> ApiResponse<ReportResult> {
+    with_update_handling(|| services::interaction::report_content(request, caller()))()
+}
+
+// Discovery API
+#[query]
+fn discover_content_v2(request: DiscoverContentRequest) -> ApiResponse<FeedResponse> {
+    with_read_access("discover_content", || services::discovery::discover_content(request))()
+}
+
+#[query]
+fn search_content_v2(request: SearchRequest) -> ApiResponse<Vec<SearchResultResponse>> {
+    with_read_access("search_content", || services::discovery::search_content(request))()
+}
+
+#[query]
+fn get_trending_topics_v2(request: GetTrendingTopicsRequest) -> ApiResponse<Vec<TrendingTopicResponse>> {
+    with_read_access("get_trending_topics", || services::discovery::get_trending_topics(request))()
+}
+
+#[query]
+fn get_trending_maintenance_stats() -> ApiResponse<TrendingMaintenanceStats> {
+    with_error_handling(services::discovery::get_trending_maintenance_stats)()
+}
+
+#[query]
+fn get_similarity_refresh_status() -> ApiResponse<SimilarityRefreshStatus> {
+    with_error_handling(services::discovery::get_similarity_refresh_status)()
+}
+
+#[update]
+fn start_similarity_refresh() -> ApiResponse<SimilarityRefreshStatus> {
+    with_update_handling(services::discovery::start_similarity_refresh)()
+}
+
+// Interaction event log
+#[query]
+fn get_event_log_status() -> ApiResponse<InteractionEventLogStatus> {
+    with_error_handling(services::events::get_event_log_status)()
+}
+
+#[update]
+fn rebuild_derived_state(consumer: EventConsumer) -> ApiResponse<InteractionEventLogStatus> {
+    with_update_handling(|| services::events::rebuild_derived_state(consumer))()
+}
+
+#[update]
+fn rebuild_indexes(scope: IndexScope) -> ApiResponse<IndexRebuildProgress> {
+    with_update_handling(|| services::index_rebuild::rebuild_indexes(scope))()
+}
+
+// Integrity audits
+#[query]
+fn get_integrity_report() -> ApiResponse<IntegrityAuditStatus> {
+    with_error_handling(services::integrity::get_integrity_report)()
+}
+
+#[update]
+fn start_integrity_audit() -> ApiResponse<IntegrityAuditStatus> {
+    with_update_handling(services::integrity::start_integrity_audit)()
+}
+
+#[query]
+fn get_hot_tags_v2(request: DiscoveryGetHotTagsRequest) -> ApiResponse<DiscoveryHotTagsResponse> {
+    with_read_access("get_hot_tags", || services::discovery::get_hot_tags(request))()
+}
+
+#[query]
+fn get_tag(name: String) -> ApiResponse<TopicPageResponse> {
+    with_read_access("get_tag", || services::discovery::get_tag(name))()
+}
+
+#[update]
+fn update_tag(request: UpdateTagRequest) -> ApiResponse<TagResponse> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::discovery::update_tag(request, caller)
+    })()
+}
+
+#[update]
+fn pin_topic_post(request: PinTopicPostRequest) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::discovery::pin_topic_post(request, caller)
+    })()
+}
+
+#[update]
+fn unpin_topic_post(tag: String) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::discovery::unpin_topic_post(tag, caller)
+    })()
+}
+
+#[update]
+fn follow_topic(request: FollowTopicRequest) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::follow_topic(request, caller)
+    })()
+}
+
+#[update]
+fn unfollow_topic(request: FollowTopicRequest) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::user::unfollow_topic(request, caller)
+    })()
+}
+
+#[update]
+fn merge_tags(from: String, to: String) -> ApiResponse<u64> {
+    with_update_handling(|| services::discovery::merge_tags(from, to))()
+}
+
+#[query]
+fn get_personalized_recommendations_v2(request: PersonalizedRecommendationsRequest) -> ApiResponse<FeedResponse> {
+    with_read_access("get_personalized_recommendations", || services::discovery::get_personalized_recommendations(request))()
+}
+
+// Rewards API
+// Note: claim_daily_check_in has been moved to the daily_checkin_task canister
+// Users should call that canister directly for check-ins
+
+#[update(name = "complete_task_v2")]
+async fn complete_task_async(request: CompleteTaskRequest) -> ApiResponse<TaskCompletionResponse> {
+    let result = match services::maintenance::check_maintenance_mode() {
+        Ok(()) => services::reward::complete_task(request, caller()).await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+#[query]
+fn get_user_rewards_v2() -> ApiResponse<UserRewardsResponse> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::reward::get_user_rewards(caller)
+    })()
+}
+
+#[query]
+fn get_available_tasks_v2() -> ApiResponse<Vec<TaskResponse>> {
+    with_read_access("get_available_tasks", || services::reward::get_available_tasks(caller()))()
+}
+
+#[query]
+fn get_task_progress() -> ApiResponse<DailyTaskProgress> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::events::get_task_progress(caller)
+    })()
+}
+
+#[update]
+fn moderate_content_v2(request: ModerateContentRequest) -> ApiResponse<ModeratedContent> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::content::moderate_content(request, caller)
+    })()
+}
+
+#[query]
+fn get_reports_queue(tag: Option<String>, pagination: PaginationParams) -> ApiResponse<PaginatedResponse<ContentReport>> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::interaction::get_reports_queue(tag, pagination, caller)
+    })()
+}
+
+#[update]
+fn resolve_report(request: ResolveReportRequest) -> ApiResponse<InteractionResponse> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::interaction::resolve_report(request, caller)
+    })()
+}
+
+// Legal holds
+#[update]
+fn place_legal_hold(request: PlaceLegalHoldRequest) -> ApiResponse<LegalHold> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::content::place_legal_hold(request, caller)
+    })()
+}
+
+#[update]
+fn release_legal_hold(content_id: String) -> ApiResponse<LegalHold> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::content::release_legal_hold(content_id, caller)
+    })()
+}
+
+#[query]
+fn get_legal_holds(pagination: PaginationParams) -> ApiResponse<PaginatedResponse<LegalHold>> {
+    with_error_handling(|| services::content::get_legal_holds(pagination))()
+}
+
+#[query]
+fn export_legal_hold(content_id: String) -> ApiResponse<LegalHoldExport> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::content::export_legal_hold(content_id, caller)
+    })()
+}
+
+// Engagement anomaly alerts
+#[query]
+fn get_engagement_anomalies(status: Option<AnomalyStatus>, pagination: PaginationParams) -> ApiResponse<PaginatedResponse<EngagementAnomaly>> {
+    with_error_handling(|| services::anomaly::get_engagement_anomalies(status, pagination))()
+}
+
+#[update]
+fn review_engagement_anomaly(request: ReviewAnomalyRequest) -> ApiResponse<EngagementAnomaly> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::anomaly::review_engagement_anomaly(request, caller)
+    })()
+}
+
+#[query]
+fn get_anomaly_settings() -> ApiResponse<AnomalyDetectionSettings> {
+    with_error_handling(services::anomaly::get_anomaly_settings)()
+}
+
+#[update]
+fn update_anomaly_settings(request: UpdateAnomalySettingsRequest) -> ApiResponse<AnomalyDetectionSettings> {
+    with_update_handling(|| services::anomaly::update_anomaly_settings(request))()
+}
+
+// Interface version and deprecated methods
+#[query]
+fn get_api_version() -> ApiResponse<ApiVersionResponse> {
+    with_read_access("get_api_version", services::api_version::get_api_version)()
+}
+
+// Anonymous read policy
+#[query]
+fn get_anonymous_read_policy() -> ApiResponse<Vec<EndpointAccess>> {
+    with_read_access("get_anonymous_read_policy", services::access_policy::get_anonymous_read_policy)()
+}
+
+#[update]
+fn set_anonymous_read_access(request: SetAnonymousReadAccessRequest) -> ApiResponse<EndpointAccess> {
+    with_update_handling(|| services::access_policy::set_anonymous_read_access(request))()
+}
+
+// Feature flags
+#[query]
+fn get_feature_flags() -> ApiResponse<Vec<FeatureFlagResponse>> {
+    with_read_access("get_feature_flags", services::feature_flags::get_feature_flags)()
+}
+
+#[update]
+fn set_feature_flag(request: SetFeatureFlagRequest) -> ApiResponse<FeatureFlagResponse> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::feature_flags::set_feature_flag(request, caller)
+    })()
+}
+
+// Maintenance mode
+#[update]
+fn set_maintenance_mode(request: SetMaintenanceModeRequest) -> ApiResponse<Option<SystemBanner>> {
+    with_error_handling(|| services::maintenance::set_maintenance_mode(request, caller()))()
+}
+
+#[query]
+fn get_system_banner() -> ApiResponse<Option<SystemBanner>> {
+    with_read_access("get_system_banner", services::maintenance::get_system_banner)()
+}
+
+// Tag moderators
+#[update]
+fn add_tag_moderator(tag: String, moderator: Principal) -> ApiResponse<()> {
+    with_update_handling(|| services::discovery::add_tag_moderator(tag, moderator))()
+}
+
+#[update]
+fn remove_tag_moderator(tag: String, moderator: Principal) -> ApiResponse<()> {
+    with_update_handling(|| services::discovery::remove_tag_moderator(tag, moderator))()
+}
+
+#[update]
+fn set_tag_posting_roles(tag: String, roles: Vec<UserRole>) -> ApiResponse<TagResponse> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::discovery::set_tag_posting_roles(tag, roles, caller)
+    })()
+}
+
+#[query]
+fn get_my_moderated_tags() -> ApiResponse<Vec<String>> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::discovery::get_my_moderated_tags(caller)
+    })()
+}
+
+#[update]
+fn award_points_v2(request: AwardPointsRequest) -> ApiResponse<()> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::treasury::award_points(request, caller)
+    })()
+}
+
+#[update]
+fn adjust_points(request: AdjustPointsRequest) -> ApiResponse<u64> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::treasury::adjust_points(request, caller)
+    })()
+}
+
+#[update]
+fn set_mint_budget(request: SetMintBudgetRequest) -> ApiResponse<MintBudget> {
+    with_update_handling(|| services::treasury::set_mint_budget(request))()
+}
+
+#[query]
+fn get_mint_budgets() -> ApiResponse<Vec<MintBudgetResponse>> {
+    with_error_handling(services::treasury::get_mint_budgets)()
+}
+
+#[query]
+fn get_points_ledger(pagination: PaginationParams) -> ApiResponse<Vec<LedgerEntry>> {
+    with_error_handling(|| services::treasury::get_points_ledger(pagination))()
+}
+
+#[query]
+fn get_points_reconciliation() -> ApiResponse<PointsReconciliationReport> {
+    with_error_handling(services::treasury::get_points_reconciliation)()
+}
+
+#[update(name = "create_task_v2")]
+fn create_task_v2(request: CreateTaskRequest) -> ApiResponse<String> {
+    with_update_handling(|| services::reward::create_task(request))()
+}
+
+#[update]
+fn update_task_v2(request: UpdateTaskRequest) -> ApiResponse<()> {
+    with_update_handling(|| services::reward::update_task(request))()
+}
+
+#[update]
+fn delete_task_v2(task_id: String) -> ApiResponse<()> {
+    with_update_handling(|| services::reward::delete_task(task_id))()
+}
+
+// Recurring task templates
+#[update]
+fn create_task_template(request: CreateTaskTemplateRequest) -> ApiResponse<TaskTemplate> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::task_template::create_task_template(request, caller)
+    })()
+}
+
+#[update]
+fn update_task_template(request: UpdateTaskTemplateRequest) -> ApiResponse<TaskTemplate> {
+    with_update_handling(|| services::task_template::update_task_template(request))()
+}
+
+#[update]
+fn delete_task_template(template_id: String) -> ApiResponse<()> {
+    with_update_handling(|| services::task_template::delete_task_template(template_id))()
+}
+
+#[query]
+fn get_task_templates() -> ApiResponse<Vec<TaskTemplate>> {
+    with_error_handling(services::task_template::get_task_templates)()
+}
+
+// Task canister registry
+#[update]
+fn register_task_canister(request: RegisterTaskCanisterRequest) -> ApiResponse<TaskCanisterRegistration> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::task_registry::register_task_canister(request, caller)
+    })()
+}
+
+#[update]
+fn update_task_canister(request: UpdateTaskCanisterRequest) -> ApiResponse<TaskCanisterRegistration> {
+    with_update_handling(|| services::task_registry::update_task_canister(request))()
+}
+
+#[update]
+fn unregister_task_canister(canister_id: Principal) -> ApiResponse<()> {
+    with_update_handling(|| services::task_registry::unregister_task_canister(canister_id))()
+}
+
+#[query]
+fn get_task_canisters() -> ApiResponse<Vec<TaskCanisterRegistration>> {
+    with_read_access("get_task_canisters", services::task_registry::get_task_canisters)()
+}
+
+// HTML sanitizer
+#[query]
+fn get_html_sanitizer_settings() -> ApiResponse<HtmlSanitizerSettings> {
+    with_error_handling(services::content::sanitizer::get_html_sanitizer_settings)()
+}
+
+#[update]
+fn update_allowed_html_tags(allowed_tags: Vec<String>) -> ApiResponse<HtmlSanitizerSettings> {
+    with_update_handling(|| services::content::sanitizer::update_allowed_html_tags(allowed_tags, caller()))()
+}
+
+// Custom emojis
+#[query]
+fn list_custom_emojis() -> ApiResponse<Vec<CustomEmoji>> {
+    with_read_access("list_custom_emojis", services::emoji::list_custom_emojis)()
+}
+
+#[update]
+fn add_custom_emoji(request: AddCustomEmojiRequest) -> ApiResponse<CustomEmoji> {
+    with_update_handling(|| services::emoji::add_custom_emoji(request, caller()))()
+}
+
+#[update]
+fn remove_custom_emoji(shortcode: String) -> ApiResponse<()> {
+    with_update_handling(|| services::emoji::remove_custom_emoji(shortcode))()
+}
+
+// Content translation
+#[update]
+async fn translate_content(content_id: String, target_lang: String) -> ApiResponse<TranslationResponse> {
+    let result = match services::maintenance::check_maintenance_mode().and_then(|()| auth::get_authenticated_caller()) {
+        Ok(caller) => services::translation::translate_content(content_id, target_lang, caller).await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+#[query]
+fn get_translation_settings() -> ApiResponse<TranslationSettingsResponse> {
+    with_error_handling(services::translation::get_translation_settings)()
+}
+
+#[update]
+fn update_translation_settings(request: UpdateTranslationSettingsRequest) -> ApiResponse<TranslationSettingsResponse> {
+    with_update_handling(|| services::translation::update_translation_settings(request))()
+}
+
+// Moderation classifier
+#[query]
+fn transform_classifier_response(args: TransformArgs) -> HttpResponse {
+    services::classifier::transform_classifier_response(args)
+}
+
+#[query]
+fn get_classifier_settings() -> ApiResponse<ClassifierSettingsResponse> {
+    with_error_handling(services::classifier::get_classifier_settings)()
+}
+
+#[update]
+fn update_classifier_settings(request: UpdateClassifierSettingsRequest) -> ApiResponse<ClassifierSettingsResponse> {
+    with_update_handling(|| services::classifier::update_classifier_settings(request))()
+}
+
+#[query]
+fn get_classifier_verdicts(flagged_only: bool, pagination: PaginationParams) -> ApiResponse<PaginatedResponse<ClassifierVerdict>> {
+    with_error_handling(|| services::classifier::get_classifier_verdicts(flagged_only, pagination))()
+}
+
+#[query]
+fn get_classifier_verdict(content_id: String) -> ApiResponse<ClassifierVerdict> {
+    with_error_handling(|| services::classifier::get_classifier_verdict(content_id))()
+}
+
+// Content shards
+#[update]
+fn register_content_shard(request: RegisterContentShardRequest) -> ApiResponse<ContentShard> {
+    with_update_handling(|| services::shards::register_content_shard(request, caller()))()
+}
+
+#[update]
+fn set_content_shard_enabled(canister_id: Principal, enabled: bool) -> ApiResponse<ContentShard> {
+    with_update_handling(|| services::shards::set_content_shard_enabled(canister_id, enabled))()
+}
+
+#[update]
+fn unregister_content_shard(canister_id: Principal) -> ApiResponse<()> {
+    with_update_handling(|| services::shards::unregister_content_shard(canister_id))()
+}
+
+#[query]
+fn get_content_shards() -> ApiResponse<Vec<ContentShard>> {
+    with_read_access("get_content_shards", services::shards::get_content_shards)()
+}
+
+#[query(composite = true)]
+async fn get_posts_across_shards(pagination: PaginationParams) -> ApiResponse<ShardedPostsResponse> {
+    let result = match services::access_policy::check_anonymous_read("get_posts_across_shards") {
+        Ok(()) => services::shards::get_posts_across_shards(pagination).await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+#[query(composite = true)]
+async fn search_content_across_shards(request: SearchRequest) -> ApiResponse<ShardedSearchResponse> {
+    let result = match services::access_policy::check_anonymous_read("search_content_across_shards") {
+        Ok(()) => services::shards::search_content_across_shards(request).await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+// Syndication partners
+#[update]
+async fn register_syndication_partner(request: RegisterSyndicationPartnerRequest) -> ApiResponse<SyndicationPartnerCredentials> {
+    let result = match services::maintenance::check_maintenance_mode() {
+        Ok(()) => services::syndication::register_syndication_partner(request, caller()).await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+#[update]
+fn update_syndication_partner(request: UpdateSyndicationPartnerRequest) -> ApiResponse<SyndicationPartner> {
+    with_update_handling(|| services::syndication::update_syndication_partner(request))()
+}
+
+#[update]
+fn remove_syndication_partner(partner_id: String) -> ApiResponse<()> {
+    with_update_handling(|| services::syndication::remove_syndication_partner(partner_id))()
+}
+
+#[query]
+fn get_syndication_partners() -> ApiResponse<Vec<SyndicationPartner>> {
+    with_error_handling(services::syndication::get_syndication_partners)()
+}
+
+// An update call so each request counts against the partner's rate limit; stays readable during maintenance
+#[update]
+fn get_syndication_feed(partner_token: String, since: Option<u64>, limit: Option<usize>, since_id: Option<String>) -> ApiResponse<SyndicationFeedResponse> {
+    with_error_handling(|| services::syndication::get_syndication_feed(partner_token, since, since_id, limit, caller()))()
+}
+
+// OpenChat bridge
+#[query]
+fn get_openchat_settings() -> ApiResponse<OpenChatBridgeSettings> {
+    with_error_handling(services::openchat::get_openchat_settings)()
+}
+
+#[update]
+fn update_openchat_settings(request: UpdateOpenChatSettingsRequest) -> ApiResponse<OpenChatBridgeSettings> {
+    with_update_handling(|| services::openchat::update_openchat_settings(request))()
+}
+
+#[update]
+fn set_openchat_topic_route(request: SetOpenChatTopicRouteRequest) -> ApiResponse<OpenChatTopicRoute> {
+    with_update_handling(|| services::openchat::set_openchat_topic_route(request, caller()))()
+}
+
+#[update]
+fn remove_openchat_topic_route(tag: String) -> ApiResponse<()> {
+    with_update_handling(|| services::openchat::remove_openchat_topic_route(tag))()
+}
+
+#[query]
+fn get_openchat_topic_routes() -> ApiResponse<Vec<OpenChatTopicRoute>> {
+    with_error_handling(services::openchat::get_openchat_topic_routes)()
+}
+
+#[query]
+fn get_mirrored_messages(pagination: PaginationParams) -> ApiResponse<PaginatedResponse<MirroredMessage>> {
+    with_error_handling(|| services::openchat::get_mirrored_messages(pagination))()
+}
+
+#[update]
+async fn run_openchat_mirror() -> ApiResponse<Vec<MirroredMessage>> {
+    let result = match services::maintenance::check_maintenance_mode() {
+        Ok(()) => services::openchat::run_openchat_mirror().await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+#[update]
+async fn sync_task_canisters() -> ApiResponse<Vec<TaskCanisterSyncResult>> {
+    let result = match services::maintenance::check_maintenance_mode() {
+        Ok(()) => services::task_registry::sync_task_canisters().await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+#[update]
+async fn enable_task_webhook(canister_id: Principal) -> ApiResponse<String> {
+    let result = match services::maintenance::check_maintenance_mode() {
+        Ok(()) => services::task_webhook::enable_task_webhook(canister_id).await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
+}
+
+#[update]
+fn disable_task_webhook(canister_id: Principal) -> ApiResponse<()> {
+    with_update_handling(|| services::task_webhook::disable_task_webhook(canister_id))()
+}
+
+#[query]
+fn get_task_webhook_deliveries() -> ApiResponse<Vec<TaskWebhookDelivery>> {
+    with_error_handling(services::task_webhook::get_task_webhook_deliveries)()
+}
+
+// Points expiry
+#[query]
+fn get_points_expiry_settings() -> ApiResponse<PointsExpirySettings> {
+    with_error_handling(services::points_expiry::get_points_expiry_settings)()
+}
+
+#[update]
+fn update_points_expiry_settings(settings: PointsExpirySettings) -> ApiResponse<PointsExpirySettings> {
+    with_update_handling(|| services::points_expiry::update_points_expiry_settings(settings))()
+}
+
+#[query]
+fn get_points_expiry_status() -> ApiResponse<PointsExpiryState> {
+    with_error_handling(services::points_expiry::get_points_expiry_status)()
+}
+
+// Daily quiz
+#[update]
+fn create_quiz(request: CreateQuizRequest) -> ApiResponse<String> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::quiz::create_quiz(request, caller)
+    })()
 }
 
-#[query]
-fn get_available_tasks() -> SquareResult<Vec<TaskResponse>> {
-    services::reward::get_available_tasks(caller())
+#[update]
+fn set_quiz_active(quiz_id: String, is_active: bool) -> ApiResponse<()> {
+    with_update_handling(|| services::quiz::set_quiz_active(quiz_id, is_active))()
 }
 
-#[update]
-fn moderate_content(request: ModerateContentRequest) -> SquareResult<()> {
-    auth::is_manager_or_admin()?;
-    services::content::moderate_content(request)
+#[query]
+fn get_daily_quiz() -> ApiResponse<DailyQuizResponse> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::quiz::get_daily_quiz(caller)
+    })()
 }
 
 #[update]
-fn award_points(request: AwardPointsRequest) -> SquareResult<()> {
-    services::reward::award_points(request)
+fn submit_quiz_answers(request: SubmitQuizAnswersRequest) -> ApiResponse<QuizResultResponse> {
+    with_update_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::quiz::submit_quiz_answers(request, caller)
+    })()
 }
 
-#[update(name = "create_task")]
-fn create_task(request: CreateTaskRequest) -> SquareResult<String> {
-    services::reward::create_task(request)
+#[query]
+fn get_my_quiz_submissions() -> ApiResponse<Vec<QuizSubmission>> {
+    with_error_handling(|| {
+        let caller = auth::get_authenticated_caller()?;
+        services::quiz::get_my_quiz_submissions(caller)
+    })()
 }
 
-#[update]
-fn update_task(request: UpdateTaskRequest) -> SquareResult<()> {
-    services::reward::update_task(request)
+// HTTP gateway: article pages, inline media and post exports, streamed in chunks when large
+#[query]
+fn http_request(request: HttpGatewayRequest) -> HttpGatewayResponse {
+    services::http::http_request(request)
 }
 
-#[update]
-fn delete_task(task_id: String) -> SquareResult<()> {
-    services::reward::delete_task(task_id)
+#[query]
+fn http_request_streaming_callback(token: StreamingCallbackToken) -> StreamingCallbackHttpResponse {
+    services::http::http_request_streaming_callback(token)
 }
 
 // System functions
@@ -287,9 +1627,9 @@ fn heartbeat() {
             store.heartbeat_interval_hours
         });
         
-        let interval_seconds = interval_hours * 60 * 60; // Convert hours to seconds
+        let interval_millis = interval_hours * utils::time_utils::MILLIS_PER_HOUR;
         
-        if current_time - last >= interval_seconds {
+        if current_time - last >= interval_millis {
             *last_time.borrow_mut() = current_time;
             true
         } else {
@@ -297,29 +1637,142 @@ fn heartbeat() {
         }
     });
     
+    // Expensive jobs pause while the cycles balance is projected to reach the freezing threshold
+    let freeze_guarded = services::cycles::freeze_guard_active();
+    
     if should_run_full {
+        // Re-read the freezing threshold the freeze guard works from
+        ic_cdk::spawn(async {
+            services::cycles::refresh_freezing_threshold().await;
+        });
+        
         // Only update trending content on full runs (expensive operation)
-        let _ = services::discovery::update_trending_content(Vec::new());
+        if !freeze_guarded {
+            let _ = services::discovery::update_trending_content(Vec::new());
+        }
+        services::discovery::decay_trending_topics();
+        
+        // Promote users who meet the creator engagement thresholds
+        if !freeze_guarded {
+            services::user::creator::run_creator_auto_promotion();
+        }
+        
+        // Publish held posts whose authors have reached the auto-approval trust level
+        services::content::run_auto_approvals();
+        
+        // Reset follow counters that drifted from the follow sets, a batch of profiles per run
+        services::user::repair_follow_counters();
+        
+        // Drop notifications past the retention count and age
+        services::user::prune_notifications();
+        
+        // Recompute the similar users behind collaborative recommendations, a batch per heartbeat
+        if !freeze_guarded {
+            services::discovery::schedule_similarity_refresh();
+        }
+        
+        // Audit derived data against the primary records once a day, a batch per heartbeat
+        if !freeze_guarded {
+            services::integrity::schedule_integrity_audit();
+        }
+        
+        // Expire points past the admin's expiry policy once a day, a batch per heartbeat
+        services::points_expiry::schedule_points_expiry();
+        
+        // Drop lapsed engagement windows, throttles, rate-limit history and served-post buffers
+        services::anomaly::prune_engagement_tracking();
+        services::user::quality::prune_recent_actions();
+        services::user::visitors::prune_recent_profile_visits();
+        services::impression::prune_recently_served();
+        
+        // Anchor the day's post hashes in certified data
+        services::content::anchoring::anchor_content_hashes();
+        
+        // Retry task completion receipts that partner canisters did not accept
+        services::task_webhook::retry_task_webhooks();
+        
+        // Refresh the task definitions cached from registered task canisters
+        ic_cdk::spawn(async {
+            services::task_registry::sync_all_task_canisters().await;
+        });
+        
+        // Drop showcased NFTs their owners no longer hold
+        if !freeze_guarded {
+            ic_cdk::spawn(async {
+                services::user::showcase::reverify_showcases().await;
+            });
+        }
         
-        // Initialize default tasks if they don't exist
-        services::reward::init_default_tasks_all_enabled();
+        // Mirror newly trending posts into their OpenChat topic channels
+        if !freeze_guarded {
+            ic_cdk::spawn(async {
+                services::openchat::mirror_trending_posts().await;
+            });
+        }
+    }
+    
+    if !freeze_guarded {
+        // Continue a running similar-users refresh, or start one after many like changes
+        services::discovery::run_similarity_refresh();
         
+        // Continue a running integrity audit
+        services::integrity::run_integrity_audit();
     }
     
+    // Continue a running points expiry sweep
+    services::points_expiry::run_points_expiry();
+    
+    // Send queued new-post alerts, a batch per heartbeat
+    services::user::run_post_alert_fanout();
+    
+    // Rehash posts after the content hash recipe changed, a batch per heartbeat
+    services::content::anchoring::run_content_hash_backfill();
+    
+    // Re-render content after the allowed HTML tags changed, a batch per heartbeat
+    services::content::sanitizer::run_rendered_content_refresh();
+    
+    // Lift due embargoes and hide posts past their sunset
+    services::content::run_content_schedules();
+    
+    // Send due comment digests to thread subscribers, a batch per heartbeat
+    services::content::run_thread_digests();
+    
+    // Start a new period for task templates that are due
+    services::task_template::generate_task_instances();
+    
+    // Check user records after an upgrade, a batch per heartbeat
+    services::user::run_user_data_sync();
+    
+    // Read the stable collections into their heap caches after an upgrade, a batch per heartbeat
+    storage::warm_stable_collections();
+    
     // Always record cycles consumption (lightweight operation)
     services::cycles::record_cycles_consumption();
+    
+    // Recompute stale account quality scores; queries read them but cannot cache them
+    if !freeze_guarded {
+        services::user::quality::refresh_account_quality();
+    }
+    
+    // Score posts for trending order once the last scores expire, so queries read rather than compute them
+    if !freeze_guarded {
+        services::discovery::refresh_trending_scores();
+    }
+    
+    // Refresh expired query cache entries; queries cannot persist them themselves
+    services::query_cache::warm_query_cache();
 }
 
 // Cycles Monitoring API
 #[query]
-fn get_cycles_balance() -> SquareResult<CyclesBalanceResponse> {
-    services::cycles::get_cycles_balance()
+fn get_cycles_balance_v2() -> ApiResponse<CyclesBalanceResponse> {
+    with_read_access("get_cycles_balance", services::cycles::get_cycles_balance)()
 }
 
 // Admin API - Heartbeat Configuration
 #[update]
 fn update_heartbeat_interval(request: UpdateHeartbeatIntervalRequest) -> ApiResponse<HeartbeatIntervalResponse> {
-    with_error_handling(|| {
+    with_update_handling(|| {
         services::admin::update_heartbeat_interval(request)
     })()
 }
@@ -331,84 +1784,151 @@ fn get_heartbeat_interval() -> ApiResponse<HeartbeatIntervalResponse> {
     })()
 }
 
+// Admin API - User Management
+#[query]
+fn admin_list_users(request: AdminUserListRequest) -> ApiResponse<PaginatedResponse<AdminUserSummary>> {
+    with_error_handling(|| services::user::list_users_admin(request))()
+}
+
+#[query]
+fn get_user_moderation_history(user_identifier: String) -> ApiResponse<UserModerationHistoryResponse> {
+    with_error_handling(|| {
+        let principal = services::user::resolve_user_identifier(&user_identifier)?;
+        services::user::get_user_moderation_history(principal)
+    })()
+}
+
+#[update]
+fn bulk_update_user_status(request: BulkUserStatusUpdateRequest) -> ApiResponse<BulkUserStatusUpdateResponse> {
+    with_update_handling(|| services::user::bulk_update_user_status(request))()
+}
+
+#[query]
+fn get_user_content_counts(user_identifier: String) -> ApiResponse<UserContentCounts> {
+    with_error_handling(|| {
+        let principal = services::user::resolve_user_identifier(&user_identifier)?;
+        services::user::get_user_content_counts(principal)
+    })()
+}
+
+// Follow edges for off-chain analysis; pass `next_cursor` back until it is empty
+#[query]
+fn export_follow_graph(cursor: Option<String>) -> ApiResponse<FollowGraphPage> {
+    with_error_handling(|| services::user::export_follow_graph(cursor))()
+}
+
 // Admin API - Storage Management
 #[update]
 fn migrate_storage() -> ApiResponse<String> {
-    with_error_handling(|| {
+    with_update_handling(|| {
         auth::require_admin()?;
         services::admin::migrate_storage()
     })()
 }
 
 #[query]
-fn get_cycles_consumption_history() -> SquareResult<CyclesConsumptionResponse> {
-    // Only authenticated users can view consumption history
-    auth::get_authenticated_caller()?;
-    services::cycles::get_cycles_consumption_history()
+fn get_cycles_consumption_history_v2() -> ApiResponse<CyclesConsumptionResponse> {
+    with_error_handling(|| {
+        // Only authenticated users can view consumption history
+        auth::get_authenticated_caller()?;
+        services::cycles::get_cycles_consumption_history()
+    })()
+}
+
+#[query]
+fn get_cycles_runway_forecast() -> ApiResponse<CyclesRunwayForecast> {
+    with_error_handling(|| {
+        auth::get_authenticated_caller()?;
+        services::cycles::get_cycles_runway_forecast()
+    })()
+}
+
+#[query]
+fn get_freeze_guard_status() -> ApiResponse<FreezeGuardStatus> {
+    with_error_handling(|| {
+        auth::get_authenticated_caller()?;
+        services::cycles::get_freeze_guard_status()
+    })()
+}
+
+#[update]
+async fn refresh_freeze_guard() -> ApiResponse<FreezeGuardStatus> {
+    let result = match services::maintenance::check_maintenance_mode() {
+        Ok(()) => services::cycles::refresh_freeze_guard().await,
+        Err(error) => Err(error),
+    };
+    with_error_handling(|| result)()
 }
 
 #[update]
 fn update_cycles_threshold(request: UpdateCyclesThresholdRequest) -> ApiResponse<CyclesThresholdConfig> {
-    with_error_handling(|| {
+    with_update_handling(|| {
         services::admin::update_cycles_threshold(request)
     })()
 }
 
 #[query]
 fn get_cycles_threshold() -> ApiResponse<CyclesThresholdConfig> {
-    with_error_handling(|| {
+    with_read_access("get_cycles_threshold", || {
         services::admin::get_cycles_threshold()
     })()
 }
 
 #[query]
 fn get_cycles_notifications() -> ApiResponse<CyclesNotificationsResponse> {
-    with_error_handling(|| {
+    with_read_access("get_cycles_notifications", || {
         services::admin::get_cycles_notifications()
     })()
 }
 
 #[update]
-fn acknowledge_notification(timestamp: u64) -> SquareResult<()> {
-    // Only admin can acknowledge notifications
-    services::cycles::acknowledge_notification(timestamp, caller())
+fn acknowledge_notification_v2(timestamp: u64) -> ApiResponse<()> {
+    with_update_handling(|| {
+        // Only admin can acknowledge notifications
+        services::cycles::acknowledge_notification(timestamp, caller())
+    })()
 }
 
 #[update]
-fn update_notification_settings(enabled: Option<bool>) -> SquareResult<()> {
-    // Only admin can update notification settings
-    services::cycles::update_notification_settings(enabled, caller())
+fn update_notification_settings_v2(enabled: Option<bool>) -> ApiResponse<()> {
+    with_update_handling(|| {
+        // Only admin can update notification settings
+        services::cycles::update_notification_settings(enabled, caller())
+    })()
 }
 
 #[query]
-fn get_notification_settings() -> SquareResult<bool> {
-    // Only admin can view notification settings
-    services::cycles::get_notification_settings(caller())
+fn get_notification_settings_v2() -> ApiResponse<bool> {
+    with_error_handling(|| {
+        // Only admin can view notification settings
+        services::cycles::get_notification_settings(caller())
+    })()
 }
 
 // Error Monitoring API
 #[query]
 fn get_error_history() -> ApiResponse<Vec<String>> {
-    with_error_handling(|| {
+    with_read_access("get_error_history", || {
         Ok(utils::error_monitor::get_error_history())
     })()
 }
 
 #[query]
 fn get_error_stats() -> ApiResponse<Vec<(ErrorCode, u64, u64, u64)>> {
-    with_error_handling(|| {
+    with_read_access("get_error_stats", || {
         Ok(utils::error_monitor::get_error_stats())
     })()
 }
 
 #[query]
 fn get_most_common_errors(limit: usize) -> ApiResponse<Vec<(ErrorCode, u64)>> {
-    with_error_handling(|| {
+    with_read_access("get_most_common_errors", || {
         Ok(utils::error_monitor::get_most_common_errors(limit))
     })()
 }
 
-// State management for canister upgrades
+// State management for canister upgrades. Posts, comments, users, likes and rewards are written to
+// their stable memories as they change, so only the rest of Storage is serialized here.
 #[pre_upgrade]
 fn pre_upgrade() {
     utils::logger::log("========== STARTING PRE-UPGRADE HOOK ==========");
@@ -429,116 +1949,22 @@ fn pre_upgrade() {
     
     storage::STORAGE.with(|storage| {
         let store = storage.borrow();
-        utils::logger::log(&format!("Main storage users count: {}", store.users.len()));
-        utils::logger::log(&format!("Main storage posts count: {}", store.posts.len()));
-        utils::logger::log(&format!("Main storage comments count: {}", store.comments.len()));
-        utils::logger::log(&format!("Main storage user rewards count: {}", store.user_rewards.len()));
-    });
-    
-    utils::logger::log("Saving main storage data...");
-    storage::migration::save_state_for_upgrade();
-    
-    let stable_size_after_data = stable_size();
-    utils::logger::log(&format!("Stable storage size after saving data: {} pages ({} bytes)", 
-        stable_size_after_data, 
-        stable_size_after_data * 65536));
-    utils::logger::log(&format!("Data size: {} bytes", 
-        (stable_size_after_data - stable_size_before) * 65536));
-    
-    // Main storage is the only storage mechanism used
-    utils::logger::log("Using main storage for all data");
-    
-    // Log main storage statistics
-    utils::logger::log("Main storage statistics:");
-    storage::STORAGE.with(|storage| {
-        let store = storage.borrow();
+        utils::logger::log("Main storage statistics:");
         utils::logger::log(&format!("- Users: {}", store.users.len()));
-    });
-    
-    storage::STORAGE.with(|storage| {
-        let store = storage.borrow();
-        
-        // Log counts from main storage
         utils::logger::log(&format!("- User profiles: {}", store.user_profiles.as_ref().map_or(0, |profiles| profiles.len())));
         utils::logger::log(&format!("- User stats: {}", store.user_stats.as_ref().map_or(0, |stats| stats.len())));
         utils::logger::log(&format!("- Posts: {}", store.posts.len()));
         utils::logger::log(&format!("- Comments: {}", store.comments.len()));
-        utils::logger::log(&format!("- Likes: {}", store.likes.len()));
-    });
-    
-    // Continue logging main storage statistics
-    storage::STORAGE.with(|storage| {
-        let store = storage.borrow();
+        utils::logger::log(&format!("- Likes: {}", store.like_sets.len()));
         utils::logger::log(&format!("- User rewards: {}", store.user_rewards.len()));
         utils::logger::log(&format!("- User tasks: {}", store.user_tasks.len()));
     });
     
-    // Main storage is now the primary storage
-    utils::logger::log("✅ Successfully saved main storage to stable storage");
-    
-    /*
-    storage::STORAGE.with(|storage| {
-        let storage_ref = storage.borrow();
-        match ic_cdk::storage::stable_save((storage_ref.clone(),)) {
-            Ok(_) => utils::logger::log("✅ Successfully saved main storage to stable storage"),
-            Err(e) => utils::logger::log(&format!("❌ ERROR saving main storage: {:?}", e)),
-        }
-    });
-    */
-    
-    let stable_size_after_main = stable_size();
-    utils::logger::log(&format!("Stable storage size after saving main storage: {} pages ({} bytes)", 
-        stable_size_after_main, 
-        stable_size_after_main * 65536));
-    utils::logger::log(&format!("Main storage data size: {} bytes", 
-        (stable_size_after_main - stable_size_after_data) * 65536));
-    
-    utils::logger::log("Setting backup flag...");
-    
-    let current_pages = stable_size();
-    let max_pages = 4294967295;
-    
-    utils::logger::log(&format!("Current stable pages: {}, Max pages: {}", current_pages, max_pages));
-    
-    if current_pages < max_pages {
-        match stable_grow(1) {
-            Ok(new_pages) => {
-                if new_pages > 0 {
-                    let backup_flag: u64 = 0x1234567890ABCDEF;
-                    let offset = (stable_size() - 1) * 65536;
-                    stable_write(offset, &backup_flag.to_le_bytes());
-                    utils::logger::log(&format!("✅ Set backup flag at offset {}", offset));
-                } else {
-                    utils::logger::log("⚠️ WARNING: Could not grow stable memory, but no error returned");
-                    
-                    if current_pages > 0 {
-                        let backup_flag: u64 = 0x1234567890ABCDEF;
-                        let offset = (current_pages * 65536) - 8; 
-                        stable_write(offset, &backup_flag.to_le_bytes());
-                        utils::logger::log(&format!("Wrote backup flag to existing page at offset {}", offset));
-                    }
-                }
-            },
-            Err(e) => {
-                utils::logger::log(&format!("ERROR: Failed to grow stable memory: {:?}", e));
-                
-                if current_pages > 0 {
-                    let backup_flag: u64 = 0x1234567890ABCDEF;
-                    let offset = (current_pages * 65536) - 8; 
-                    stable_write(offset, &backup_flag.to_le_bytes());
-                    utils::logger::log(&format!("Wrote backup flag to existing page at offset {}", offset));
-                }
-            },
-        }
-    } else {
-        utils::logger::log("WARNING: Stable storage at maximum capacity");
-        
-        if current_pages > 0 {
-            let backup_flag: u64 = 0x1234567890ABCDEF;
-            let offset = (current_pages * 65536) - 8; 
-            stable_write(offset, &backup_flag.to_le_bytes());
-            utils::logger::log(&format!("Wrote backup flag to existing page at offset {}", offset));
-        }
+    utils::logger::log("Saving the rest of main storage to the state cell...");
+    match storage::STORAGE.with(|storage| storage::memory::save_state(&storage.borrow())) {
+        Ok(size) => utils::logger::log(&format!("✅ Saved main storage to stable memory, size: {} bytes", size)),
+        // Trapping aborts the upgrade, which keeps the running canister and its state
+        Err(e) => ic_cdk::trap(&format!("❌ ERROR saving main storage: {}", e)),
     }
     
     let final_stable_size = stable_size();
@@ -559,6 +1985,150 @@ fn post_upgrade() {
         stable_size, 
         stable_size * 65536));
     
+    let mut state_cell_empty = false;
+    if storage::memory::uses_memory_manager() {
+        match storage::memory::load_state() {
+            Ok(Some(restored_storage)) => {
+                storage::STORAGE.with(|storage| {
+                    *storage.borrow_mut() = restored_storage;
+                });
+                utils::logger::log("✅ Restored main storage from the state cell");
+            },
+            // Checked once the collections are attached; an empty cell is only expected before any data
+            Ok(None) => {
+                utils::logger::log("⚠️ WARNING: The state cell is empty");
+                state_cell_empty = true;
+            },
+            // Trapping aborts the upgrade, which keeps the previous release and its state
+            Err(e) => ic_cdk::trap(&format!("❌ ERROR restoring main storage: {}", e)),
+        }
+    } else {
+        // Upgrading from a release that serialized all of Storage
+        restore_legacy_storage(stable_size);
+    }
+    
+    // Back the collections with their stable memories, or move them there after a legacy restore
+    storage::attach_stable_collections();
+    if state_cell_empty && storage::stable_collections_hold_entries() {
+        ic_cdk::trap("❌ ERROR: The stable collections hold entries but the state cell is empty");
+    }
+    
+    // Only main storage is used.
+    
+    let main_storage_empty = storage::STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.users.is_empty() && store.posts.is_empty()
+    });
+    
+    utils::logger::log(&format!("Main storage state: empty={}", main_storage_empty));
+    
+    if main_storage_empty {
+        utils::logger::log("⚠️ WARNING: Main storage is empty! This may indicate a problem with data loading.");
+    } else {
+        utils::logger::log("Main storage contains data, proceeding with normal operation.");
+    }
+    
+    // Perform any necessary post-upgrade synchronization
+    storage::migration::synchronize_storage_after_upgrade();
+    
+    if storage::migration::migrate_timestamps_to_millis() {
+        utils::logger::log("Normalized stored timestamps to milliseconds");
+    }
+    if storage::migration::migrate_hashtags_to_normalized() {
+        utils::logger::log("Normalized stored hashtags");
+    }
+    if storage::migration::migrate_article_summaries() {
+        utils::logger::log("Built summaries for existing articles");
+    }
+    if storage::migration::migrate_handles_case_insensitive() {
+        utils::logger::log("Made user handles unique regardless of case");
+    }
+    if storage::migration::migrate_user_cards() {
+        utils::logger::log("Built the user card index");
+    }
+    if storage::migration::migrate_like_sets() {
+        utils::logger::log("Compacted like sets");
+    }
+    if storage::migration::migrate_event_log() {
+        utils::logger::log("Seeded the interaction event log checkpoint");
+    }
+    if storage::migration::migrate_storage_usage() {
+        utils::logger::log("Counted storage usage per user");
+    }
+    if storage::migration::migrate_task_templates() {
+        utils::logger::log("Turned the built-in daily tasks into task templates");
+    }
+    if storage::migration::migrate_points_ledger() {
+        utils::logger::log("Recorded existing points balances in the points ledger");
+    }
+    if storage::migration::migrate_report_reporters() {
+        utils::logger::log("Merged duplicate pending reports and recorded their reporters");
+    }
+    if storage::migration::migrate_notification_seqs() {
+        utils::logger::log("Numbered existing notifications for incremental sync");
+    }
+    if storage::migration::migrate_notifications_oldest_first() {
+        utils::logger::log("Reordered notifications oldest first");
+    }
+    if storage::migration::migrate_follow_times() {
+        utils::logger::log("Dated existing follows from the event log");
+    }
+    if storage::migration::migrate_article_outlines() {
+        utils::logger::log("Built tables of contents for existing articles");
+    }
+    if storage::migration::migrate_schedule_queue() {
+        utils::logger::log("Queued content schedules by due time");
+    }
+    if storage::migration::migrate_post_comments() {
+        utils::logger::log("Indexed comments by post");
+    }
+    if storage::migration::migrate_tag_follower_counts() {
+        utils::logger::log("Counted tag followers from followed topics");
+    }
+    if storage::migration::migrate_open_reports() {
+        utils::logger::log("Indexed pending reports by content");
+    }
+    if storage::migration::migrate_event_checkpoint_counters() {
+        utils::logger::log("Compacted the event log checkpoint to stats counters");
+    }
+    if storage::migration::migrate_linked_account_owners() {
+        utils::logger::log("Indexed linked accounts by user");
+    }
+    
+    storage::STORAGE.with(|storage| {
+        let store = storage.borrow();
+        utils::logger::log(&format!("After synchronization, main storage has:"));
+        utils::logger::log(&format!("- Users: {}", store.users.len()));
+        utils::logger::log(&format!("- Posts: {}", store.posts.len()));
+        utils::logger::log(&format!("- Comments: {}", store.comments.len()));
+        utils::logger::log(&format!("- User rewards: {}", store.user_rewards.len()));
+        utils::logger::log(&format!("- User tasks: {}", store.user_tasks.len()));
+    });
+    
+    // Check every user's records for consistency; the heartbeat does it a batch at a time
+    services::user::schedule_user_data_sync();
+    
+    // Start the current period of every task template
+    utils::logger::log("Generating task template instances...");
+    services::task_template::generate_task_instances();
+    
+    // Initialize admin if needed
+    utils::logger::log("Initializing admin if needed...");
+    auth::init_admin_if_empty();
+    
+    // Initialize cycles monitoring
+    utils::logger::log("Initializing cycles monitoring...");
+    services::cycles::init_cycles_monitoring();
+    
+    // Certify the latest content anchor again
+    services::content::anchoring::restore_certified_anchor();
+    
+    utils::logger::log("========== POST-UPGRADE HOOK COMPLETED ==========");
+}
+
+// Restore Storage from the raw backup regions written by releases before the memory manager. It
+// must run before the memory manager is first used, which lays out stable memory anew.
+fn restore_legacy_storage(stable_size: u64) {
     let mut has_valid_backup = false;
     let mut backup_flag_value = 0u64;
     
@@ -596,7 +2166,7 @@ fn post_upgrade() {
                 utils::logger::log(&format!("- User stats: {}", restored_storage.user_stats.as_ref().map_or(0, |stats| stats.len())));
                 utils::logger::log(&format!("- Posts: {}", restored_storage.posts.len()));
                 utils::logger::log(&format!("- Comments: {}", restored_storage.comments.len()));
-                utils::logger::log(&format!("- Likes: {}", restored_storage.likes.len()));
+                utils::logger::log(&format!("- Likes: {}", restored_storage.like_sets.len()));
                 utils::logger::log(&format!("- User rewards: {}", restored_storage.user_rewards.len()));
                 utils::logger::log(&format!("- User tasks: {}", restored_storage.user_tasks.len()));
                 
@@ -638,100 +2208,57 @@ fn post_upgrade() {
             utils::logger::log(&format!("- User stats: {}", store.user_stats.as_ref().map_or(0, |stats| stats.len())));
             utils::logger::log(&format!("- Posts: {}", store.posts.len()));
             utils::logger::log(&format!("- Comments: {}", store.comments.len()));
-            utils::logger::log(&format!("- Likes: {}", store.likes.len()));
+            utils::logger::log(&format!("- Likes: {}", store.like_sets.len()));
             utils::logger::log(&format!("- User rewards: {}", store.user_rewards.len()));
             utils::logger::log(&format!("- User tasks: {}", store.user_tasks.len()));
         });
     }
-    
-    // Only main storage is used.
-    
-    let main_storage_empty = storage::STORAGE.with(|storage| {
-        let store = storage.borrow();
-        store.users.is_empty() && store.posts.is_empty()
-    });
-    
-    utils::logger::log(&format!("Main storage state: empty={}", main_storage_empty));
-    
-    if main_storage_empty {
-        utils::logger::log("⚠️ WARNING: Main storage is empty! This may indicate a problem with data loading.");
-    } else {
-        utils::logger::log("Main storage contains data, proceeding with normal operation.");
-    }
-    
-    // Perform any necessary post-upgrade synchronization
-    storage::migration::synchronize_storage_after_upgrade();
-    
-    storage::STORAGE.with(|storage| {
-        let store = storage.borrow();
-        utils::logger::log(&format!("After synchronization, main storage has:"));
-        utils::logger::log(&format!("- Users: {}", store.users.len()));
-        utils::logger::log(&format!("- Posts: {}", store.posts.len()));
-        utils::logger::log(&format!("- Comments: {}", store.comments.len()));
-        utils::logger::log(&format!("- User rewards: {}", store.user_rewards.len()));
-        utils::logger::log(&format!("- User tasks: {}", store.user_tasks.len()));
-    });
-    
-    // Synchronize user data to ensure consistency across all storage locations
-    utils::logger::log("Synchronizing user data to ensure consistency...");
-    match services::user::synchronize_all_user_data() {
-        Ok(_) => utils::logger::log("User data synchronization completed successfully"),
-        Err(e) => utils::logger::log(&format!("Error during user data synchronization: {:?}", e))
-    }
-    
-    // Initialize default tasks if needed
-    utils::logger::log("Initializing default tasks if needed...");
-    services::reward::init_default_tasks_if_empty();
-    
-    // Initialize admin if needed
-    utils::logger::log("Initializing admin if needed...");
-    auth::init_admin_if_empty();
-    
-    // Initialize cycles monitoring
-    utils::logger::log("Initializing cycles monitoring...");
-    services::cycles::init_cycles_monitoring();
-    
-    utils::logger::log("========== POST-UPGRADE HOOK COMPLETED ==========");
 }
 
 // Logging API
 
 #[query]
-fn get_logs() -> Vec<utils::logger::LogEntry> {
-    utils::logger::get_all_logs()
+fn get_logs_v2() -> ApiResponse<Vec<utils::logger::LogEntry>> {
+    with_read_access("get_logs", || Ok(utils::logger::get_all_logs()))()
 }
 
 #[query]
-fn get_recent_logs(count: usize) -> Vec<utils::logger::LogEntry> {
-    utils::logger::get_recent_logs(count)
+fn get_recent_logs_v2(count: usize) -> ApiResponse<Vec<utils::logger::LogEntry>> {
+    with_read_access("get_recent_logs", || Ok(utils::logger::get_recent_logs(count)))()
 }
 
 #[update]
-fn clear_logs() -> bool {
-    match auth::is_admin() {
-        Ok(_) => {
-            utils::logger::clear_logs();
-            true
-        },
-        Err(_) => false
-    }
+fn clear_logs_v2() -> ApiResponse<bool> {
+    with_update_handling(|| {
+        auth::require_admin()?;
+        utils::logger::clear_logs();
+        Ok(true)
+    })()
 }
 
 // Debug API
 
-#[query]
-fn debug_list_all_users() -> ApiResponse<Vec<(String, String)>> {
-    with_error_handling(|| services::user::debug_list_all_users())()
+// Updates so the shared hourly budget can count every call
+#[update]
+fn debug_list_all_users(request: DebugUserListRequest) -> ApiResponse<PaginatedResponse<(String, String)>> {
+    with_update_handling(|| services::user::debug_list_all_users(request))()
 }
 
 #[update]
 fn debug_fix_user_data(principal_str: String) -> ApiResponse<bool> {
-    with_error_handling(|| services::user::debug_fix_user_data(principal_str))()
+    with_update_handling(|| services::user::debug_fix_user_data(principal_str))()
 }
 
 #[update]
 fn debug_fix_user_profile(principal_str: String) -> ApiResponse<String> {
-    with_error_handling(|| services::user::debug_fix_user_profile(principal_str))()
+    with_update_handling(|| services::user::debug_fix_user_profile(principal_str))()
+}
+
+// Local and staging builds only: cargo build --features demo-data
+#[cfg(feature = "demo-data")]
+#[update]
+fn seed_demo_data(users: u32, posts: u32, comments: u32) -> ApiResponse<models::demo_data::DemoDataSummary> {
+    with_update_handling(|| services::demo_data::seed_demo_data(users, posts, comments))()
 }
 
 ic_cdk::export_candid!();
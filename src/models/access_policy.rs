@@ -0,0 +1,74 @@
+use candid::{CandidType, Deserialize};
+
+// Public read endpoints and whether anonymous callers may use them unless an admin overrides it.
+// Endpoints that act on the caller's own data always need a signed-in caller and are not listed,
+// nor are those only admins or managers may call. `http_request` covers the article pages, media
+// and exports served over HTTP.
+pub const PUBLIC_READ_ENDPOINTS: &[(&str, bool)] = &[
+    ("get_user_profile", true),
+    ("get_followers", true),
+    ("get_following", true),
+    ("get_posting_delegates", true),
+    ("get_timezone_offset", true),
+    ("get_account_quality", false),
+    ("get_token_gate_status", true),
+    ("get_gated_post", false),
+    ("get_creators", true),
+    ("get_user_leaderboard", false),
+    ("get_post", true),
+    ("get_cross_posts", true),
+    ("get_posts", true),
+    ("verify_content_integrity", true),
+    ("get_content_by_license", true),
+    ("get_article_summary", true),
+    ("get_comment", true),
+    ("get_comments", true),
+    ("get_user_content", true),
+    ("get_explore_feed", true),
+    ("get_rising_creators", true),
+    ("get_likes", true),
+    ("discover_content", true),
+    ("search_content", true),
+    ("get_trending_topics", true),
+    ("get_hot_tags", true),
+    ("get_tag", true),
+    ("get_personalized_recommendations", true),
+    ("list_custom_emojis", true),
+    ("get_posts_across_shards", true),
+    ("search_content_across_shards", true),
+    ("get_available_tasks", true),
+    ("get_storage_quota_settings", true),
+    ("get_creator_thresholds", true),
+    ("get_companion_canisters", true),
+    ("get_content_shards", true),
+    ("get_task_canisters", true),
+    ("get_cycles_balance", true),
+    ("get_cycles_threshold", true),
+    ("get_cycles_notifications", true),
+    ("get_api_version", true),
+    ("get_feature_flags", true),
+    ("get_system_banner", true),
+    ("get_anonymous_read_policy", true),
+    ("get_error_history", true),
+    ("get_error_stats", true),
+    ("get_most_common_errors", true),
+    ("get_logs", true),
+    ("get_recent_logs", true),
+    ("http_request", true),
+];
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct SetAnonymousReadAccessRequest {
+    pub endpoint: String,
+    pub allow_anonymous: bool,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EndpointAccess {
+    pub endpoint: String,
+    pub allow_anonymous: bool,
+    // Whether an admin override replaces the default
+    pub overridden: bool,
+}
@@ -0,0 +1,80 @@
+use candid::{CandidType, Deserialize};
+use crate::utils::time_utils::TimestampMillis;
+
+// Account quality scoring constants
+pub const MAX_ACCOUNT_QUALITY_SCORE: u32 = 100;
+pub const LOW_QUALITY_SCORE_THRESHOLD: u32 = 30;
+pub const HIGH_QUALITY_SCORE_THRESHOLD: u32 = 70;
+// Cached scores are recomputed after this long
+pub const ACCOUNT_QUALITY_CACHE_MS: u64 = 60 * 60 * 1000;
+// Stale cached scores the heartbeat recomputes per run
+pub const ACCOUNT_QUALITY_REFRESH_BATCH: usize = 200;
+
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum AccountQualityTier {
+    Low,
+    Medium,
+    High,
+}
+
+// Sybil-resistance score built from on-chain signals only (no IP data)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AccountQuality {
+    pub score: u32,
+    pub tier: AccountQualityTier,
+    // Up to 30 points for accounts up to 30 days old
+    pub age_points: u32,
+    // Up to 20 points for distinct active days (check-ins, tasks, quizzes) in the last 30 days
+    pub activity_points: u32,
+    // Up to 20 points for active posts and comments, minus moderation penalties
+    pub content_points: u32,
+    // Up to 30 points for followers who are themselves established accounts
+    pub follower_points: u32,
+    pub computed_at: TimestampMillis,
+}
+
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RateLimitedAction {
+    Post,
+    Comment,
+    Like,
+    Report,
+    Translate,
+    View,
+    ProfileVisit,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RecentAction {
+    pub action: RateLimitedAction,
+    pub timestamp: TimestampMillis,
+}
+
+// Hourly action limits for an account quality tier
+pub fn hourly_action_limit(tier: AccountQualityTier, action: RateLimitedAction) -> usize {
+    match (tier, action) {
+        (AccountQualityTier::Low, RateLimitedAction::Post) => 3,
+        (AccountQualityTier::Low, RateLimitedAction::Comment) => 10,
+        (AccountQualityTier::Low, RateLimitedAction::Like) => 30,
+        (AccountQualityTier::Low, RateLimitedAction::Report) => 5,
+        (AccountQualityTier::Low, RateLimitedAction::Translate) => 5,
+        (AccountQualityTier::Low, RateLimitedAction::View) => 60,
+        (AccountQualityTier::Low, RateLimitedAction::ProfileVisit) => 30,
+        (_, RateLimitedAction::Post) => 20,
+        (_, RateLimitedAction::Comment) => 100,
+        (_, RateLimitedAction::Like) => 500,
+        (_, RateLimitedAction::Report) => 30,
+        (_, RateLimitedAction::Translate) => 30,
+        (_, RateLimitedAction::View) => 600,
+        (_, RateLimitedAction::ProfileVisit) => 200,
+    }
+}
+
+// Weight of an account's engagement in trending scores
+pub fn engagement_weight(tier: AccountQualityTier) -> f64 {
+    match tier {
+        AccountQualityTier::Low => 0.2,
+        AccountQualityTier::Medium => 0.7,
+        AccountQualityTier::High => 1.0,
+    }
+}
@@ -0,0 +1,27 @@
+use candid::{CandidType, Deserialize};
+
+// Earliest year an activity calendar can be asked for
+pub const MIN_ACTIVITY_YEAR: u64 = 2020;
+
+// Posts and comments written on one local day
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ActivityDay {
+    pub date: String,  // YYYY-MM-DD in the user's timezone
+    pub posts: u64,
+    pub comments: u64,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ActivityCalendarResponse {
+    pub year: u64,
+    // Days with activity only, in date order
+    pub days: Vec<ActivityDay>,
+    pub total_posts: u64,
+    pub total_comments: u64,
+    // Consecutive days with at least one post: the longest within the year, and the run ending today,
+    // or yesterday when nothing was posted yet today
+    pub longest_streak: u64,
+    pub current_streak: u64,
+    pub timezone_offset_minutes: i32,
+}
@@ -0,0 +1,69 @@
+use candid::{CandidType, Deserialize};
+use crate::utils::time_utils::TimestampMillis;
+
+// Content anchoring constants
+// Daily anchors kept; older ones are dropped from the chain history
+pub const MAX_CONTENT_ANCHORS: usize = 366;
+// The newest anchors keep their leaves; older ones keep only their root and chain value and can no
+// longer produce proofs
+pub const MAX_ANCHORS_WITH_LEAVES: usize = 30;
+// Posts rehashed per heartbeat after the hash recipe changes
+pub const CONTENT_HASH_BATCH_SIZE: usize = 500;
+// Version of the recipe in `post_content_hash`; raising it rehashes every post
+pub const CONTENT_HASH_RECIPE: u32 = 2;
+
+// A post's hash as of one create or edit, waiting for or included in a daily anchor
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ContentHashRecord {
+    pub content_id: String,
+    pub content_hash: String,
+    pub recorded_at: TimestampMillis,
+}
+
+// One day's hash records under a Merkle root. Each root is chained onto the previous anchor's
+// chain value, and the latest chain value is the canister's certified data. `leaves` is emptied
+// once MAX_ANCHORS_WITH_LEAVES newer anchors exist.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ContentAnchor {
+    pub root: String,
+    pub previous_chain: String,
+    pub chain: String,
+    pub anchored_at: TimestampMillis,
+    pub leaves: Vec<ContentHashRecord>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MerkleProofStep {
+    pub sibling: String,
+    // The sibling is hashed before the running value rather than after it
+    pub sibling_on_left: bool,
+}
+
+// Response DTOs
+// How to get from a post's hash to the certified chain value:
+// leaf = sha256(0x00 || content_id || 0x00 || content_hash), node = sha256(0x01 || left || right)
+// up the proof to `root`, then chain = sha256(previous_chain || root) and once more with each of
+// `later_roots` in order. The result equals the certified data in `certificate`.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ContentAnchorProof {
+    pub anchored_at: TimestampMillis,
+    pub root: String,
+    pub previous_chain: String,
+    pub leaf_index: u64,
+    pub proof: Vec<MerkleProofStep>,
+    pub later_roots: Vec<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ContentIntegrityResponse {
+    pub content_id: String,
+    // The hash recorded when the post was last written
+    pub content_hash: Option<String>,
+    // The hash of the post as stored now; differs from `content_hash` if it changed without an edit
+    pub computed_hash: String,
+    pub intact: bool,
+    // None until the next daily anchor includes the latest edit
+    pub anchor: Option<ContentAnchorProof>,
+    pub certified_chain: Option<String>,
+    pub certificate: Option<Vec<u8>>,
+}
@@ -0,0 +1,90 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::models::content::ContentType;
+use crate::utils::time_utils::TimestampMillis;
+
+// Anomaly detection constants
+pub const MAX_ENGAGEMENT_EVENTS_PER_CONTENT: usize = 500;
+
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum EngagementKind {
+    Like,
+    Report,
+}
+
+// Recent like/report on a piece of content, kept only for the detection window
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EngagementEvent {
+    pub principal: Principal,
+    pub kind: EngagementKind,
+    pub timestamp: TimestampMillis,
+}
+
+// Thresholds for flagging engagement driven by newly registered accounts
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AnomalyDetectionSettings {
+    pub enabled: bool,
+    pub window_minutes: u64,
+    // Accounts registered more recently than this count as new
+    pub new_account_age_hours: u64,
+    // Minimum likes/reports from new accounts within the window
+    pub min_new_account_events: u64,
+    // Minimum share of the window's events coming from new accounts
+    pub min_new_account_percent: u64,
+    // How long new accounts are blocked from interacting with flagged content
+    pub throttle_minutes: u64,
+}
+
+impl Default for AnomalyDetectionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_minutes: 10,
+            new_account_age_hours: 72,
+            min_new_account_events: 10,
+            min_new_account_percent: 60,
+            throttle_minutes: 60,
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
+pub enum AnomalyStatus {
+    Open,
+    Confirmed,
+    Dismissed,
+}
+
+// Moderation alert raised when content receives abnormal engagement
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EngagementAnomaly {
+    pub id: String,
+    pub content_id: String,
+    pub content_type: ContentType,
+    pub detected_at: TimestampMillis,
+    pub window_likes: u64,
+    pub window_reports: u64,
+    pub new_account_events: u64,
+    pub contributing_principals: Vec<Principal>,
+    pub throttled_until: TimestampMillis,
+    pub status: AnomalyStatus,
+    pub reviewed_by: Option<Principal>,
+    pub reviewed_at: Option<TimestampMillis>,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct ReviewAnomalyRequest {
+    pub anomaly_id: String,
+    // Confirmed keeps the throttle running; dismissed lifts it
+    pub confirm: bool,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UpdateAnomalySettingsRequest {
+    pub enabled: Option<bool>,
+    pub window_minutes: Option<u64>,
+    pub new_account_age_hours: Option<u64>,
+    pub min_new_account_events: Option<u64>,
+    pub min_new_account_percent: Option<u64>,
+    pub throttle_minutes: Option<u64>,
+}
@@ -0,0 +1,67 @@
+use candid::{CandidType, Deserialize};
+
+// Version of the Candid interface. The minor version goes up when methods or optional fields are
+// added; the major version goes up when methods are removed or change shape.
+pub const API_VERSION: &str = "2.2.0";
+
+// The original method names keep their 1.x signatures from before the ApiResponse envelope, which
+// is served under a `_v2` suffix
+pub const LEGACY_DEPRECATED_SINCE: &str = "2.0.0";
+pub const LEGACY_REMOVAL_VERSION: &str = "3.0.0";
+
+// Deprecated methods and the method that replaces each, if any
+pub const DEPRECATED_METHODS: [(&str, Option<&str>); 37] = [
+    ("get_post", Some("get_post_v2")),
+    ("get_posts", Some("get_posts_v2")),
+    ("delete_post", Some("delete_post_v2")),
+    ("create_comment", Some("create_comment_v2")),
+    ("update_comment", Some("update_comment_v2")),
+    ("get_comment", Some("get_comment_v2")),
+    ("delete_comment", Some("delete_comment_v2")),
+    ("get_comments", Some("get_comments_v2")),
+    ("get_user_content", Some("get_user_content_v2")),
+    ("like_content", Some("like_content_v2")),
+    ("unlike_content", Some("unlike_content_v2")),
+    ("get_likes", Some("get_likes_v2")),
+    ("report_content", Some("report_content_v2")),
+    ("discover_content", Some("discover_content_v2")),
+    ("search_content", Some("search_content_v2")),
+    ("get_trending_topics", Some("get_trending_topics_v2")),
+    ("get_hot_tags", Some("get_hot_tags_v2")),
+    ("get_personalized_recommendations", Some("get_personalized_recommendations_v2")),
+    ("complete_task", Some("complete_task_v2")),
+    ("get_user_rewards", Some("get_user_rewards_v2")),
+    ("get_available_tasks", Some("get_available_tasks_v2")),
+    ("moderate_content", Some("moderate_content_v2")),
+    ("award_points", Some("award_points_v2")),
+    ("create_task", Some("create_task_v2")),
+    ("update_task", Some("update_task_v2")),
+    ("delete_task", Some("delete_task_v2")),
+    ("get_cycles_balance", Some("get_cycles_balance_v2")),
+    ("get_cycles_consumption_history", Some("get_cycles_consumption_history_v2")),
+    ("acknowledge_notification", Some("acknowledge_notification_v2")),
+    ("update_notification_settings", Some("update_notification_settings_v2")),
+    ("get_notification_settings", Some("get_notification_settings_v2")),
+    ("get_logs", Some("get_logs_v2")),
+    ("get_recent_logs", Some("get_recent_logs_v2")),
+    ("clear_logs", Some("clear_logs_v2")),
+    ("add_manager", Some("add_manager_v2")),
+    ("remove_manager", Some("remove_manager_v2")),
+    ("list_managers", Some("list_managers_v2")),
+];
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct DeprecatedMethod {
+    pub method: String,
+    pub replacement: Option<String>,
+    pub deprecated_since: String,
+    // The interface version that drops the method
+    pub removal_version: String,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ApiVersionResponse {
+    pub version: String,
+    pub deprecated_methods: Vec<DeprecatedMethod>,
+}
@@ -0,0 +1,54 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::models::content::PostResponse;
+use crate::models::trust::TrustLevel;
+use crate::utils::time_utils::TimestampMillis;
+
+// Admin-configurable approval mode: the first posts of new accounts wait for a moderator
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ContentApprovalSettings {
+    pub enabled: bool,
+    // Accounts younger than this have their posts held
+    pub new_account_days: u64,
+    // Posts are held until the account has this many active posts
+    pub held_posts: u64,
+    // Held posts are approved automatically once their author reaches this trust level, and
+    // accounts at it are never held
+    pub auto_approve_level: TrustLevel,
+}
+
+impl Default for ContentApprovalSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            new_account_days: 3,
+            held_posts: 3,
+            auto_approve_level: TrustLevel::Basic,
+        }
+    }
+}
+
+// A held post waiting in the approval queue
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PendingApproval {
+    pub post_id: String,
+    pub author: Principal,
+    pub submitted_at: TimestampMillis,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReviewPendingPostRequest {
+    pub post_id: String,
+    // Approving publishes the post; rejecting removes it
+    pub approve: bool,
+    pub reason: Option<String>,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct PendingPostResponse {
+    pub post: PostResponse,
+    pub submitted_at: TimestampMillis,
+    pub author_trust_level: TrustLevel,
+    pub account_age_days: u64,
+}
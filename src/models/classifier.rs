@@ -0,0 +1,72 @@
+use candid::{CandidType, Deserialize};
+use crate::models::content::ContentType;
+use crate::utils::time_utils::TimestampMillis;
+
+// Moderation classifier constants
+pub const MAX_CLASSIFIER_URL_LENGTH: usize = 500;
+// Text beyond this many bytes is not sent to the classifier (the hash still covers all of it)
+pub const MAX_CLASSIFIER_TEXT_BYTES: usize = 8 * 1024;
+pub const CLASSIFIER_MAX_RESPONSE_BYTES: u64 = 2 * 1024;
+// Label the classifier returns for content that needs no review
+pub const CLASSIFIER_SAFE_LABEL: &str = "safe";
+
+// External moderation API called via HTTPS outcall for every new post and comment.
+// The API receives `{"content_id", "content_type", "content_hash", "text"}` as JSON and
+// answers with `{"label": string, "confidence": number between 0 and 1}`.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ClassifierSettings {
+    pub enabled: bool,
+    pub endpoint_url: String,
+    // Sent as the Authorization header; never returned by queries
+    pub auth_token: Option<String>,
+    // Non-safe labels at or above this confidence put the content UnderReview
+    pub confidence_threshold: f64,
+    // Cycles attached to each outcall
+    pub cycles_per_call: u128,
+}
+
+impl Default for ClassifierSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint_url: String::new(),
+            auth_token: None,
+            confidence_threshold: 0.8,
+            cycles_per_call: 2_000_000_000,
+        }
+    }
+}
+
+// Classifier result kept for moderators
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ClassifierVerdict {
+    pub content_id: String,
+    pub content_type: ContentType,
+    pub content_hash: String,
+    pub label: String,
+    pub confidence: f64,
+    // Whether the verdict moved the content to UnderReview
+    pub flagged: bool,
+    pub classified_at: TimestampMillis,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UpdateClassifierSettingsRequest {
+    pub enabled: Option<bool>,
+    pub endpoint_url: Option<String>,
+    // Some("") clears the token
+    pub auth_token: Option<String>,
+    pub confidence_threshold: Option<f64>,
+    pub cycles_per_call: Option<u128>,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ClassifierSettingsResponse {
+    pub enabled: bool,
+    pub endpoint_url: String,
+    pub has_auth_token: bool,
+    pub confidence_threshold: f64,
+    pub cycles_per_call: u128,
+}
@@ -0,0 +1,43 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// Companion canister constants
+pub const MAX_COMPANION_CANISTERS: usize = 10;
+pub const MAX_COMPANION_NAME_LENGTH: usize = 100;
+pub const DEFAULT_PROFILE_IMPORT_METHOD: &str = "get_profile_for_import";
+
+// Another IC News product that new users may import their profile from
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CompanionCanister {
+    pub canister_id: Principal,
+    pub name: String,
+    // Query taking the user's principal and returning `opt ImportedProfile`
+    pub profile_method: String,
+    pub registered_by: Principal,
+    pub registered_at: TimestampMillis,
+}
+
+// Profile as served by a companion canister
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ImportedProfile {
+    pub username: String,
+    pub handle: Option<String>,
+    pub avatar: Option<String>,
+    pub bio: Option<String>,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct RegisterCompanionCanisterRequest {
+    pub canister_id: Principal,
+    pub name: String,
+    pub profile_method: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct RegisterFromRequest {
+    pub canister_id: Principal,
+    // Overrides the imported handle, or supplies one when the companion has none
+    pub handle: Option<String>,
+    pub invite_code: Option<String>,
+}
@@ -1,4 +1,6 @@
 use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+use crate::models::token_gate::TokenGate;
 
 // News reference response for returning news references in responses
 #[derive(CandidType, Deserialize, Clone, Debug)]
@@ -18,6 +20,20 @@ pub struct CreatePostRequest {
     pub is_nsfw: Option<bool>,
     pub visibility: Option<ContentVisibility>,
     pub news_reference: Option<NewsReference>,  // Optional reference to news
+    pub on_behalf_of: Option<Principal>,  // Organization account the caller is a delegate of
+    pub title: Option<String>,  // Articles carry a title (creators only)
+    pub is_premium: Option<bool>,  // Premium posts (creators only)
+    pub license: Option<ContentLicense>,  // Articles and posts with media only
+    pub token_gate: Option<TokenGate>,  // Only holders of the token can read the full post
+    pub format: Option<ContentFormat>,  // Defaults to Html
+    #[serde(default)]
+    pub embargo_until: Option<TimestampMillis>,  // Hidden from everyone but the author until then
+    #[serde(default)]
+    pub sunset_at: Option<TimestampMillis>,  // Hidden automatically from then on
+    #[serde(default)]
+    pub cover_image: Option<String>,  // https:// image shown above the post and in link previews
+    #[serde(default)]
+    pub thumbnail: Option<String>,  // https:// image for compact views such as search results
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -26,6 +42,9 @@ pub struct CreateCommentRequest {
     pub content: String,
     pub parent_id: String,
     pub parent_type: ParentType,
+    pub format: Option<ContentFormat>,  // Defaults to Html
+    pub subscribe: Option<bool>,  // Subscribe to the thread's comment digests; ignored if the caller muted it
+    pub quoted_post_id: Option<String>,  // A public, active post to quote inside the comment
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -38,15 +57,64 @@ pub struct UpdatePostRequest {
     pub tags: Option<Vec<String>>,  // Max 5 tags
     pub visibility: Option<ContentVisibility>,
     pub news_reference: Option<NewsReference>,  // Optional reference to news
+    pub title: Option<String>,  // Articles carry a title (creators only)
+    pub license: Option<ContentLicense>,  // Articles and posts with media only
+    pub format: Option<ContentFormat>,  // Keeps the current format when omitted
+    #[serde(default)]
+    pub cover_image: Option<String>,  // Keeps the current image when omitted; an empty string removes it
+    #[serde(default)]
+    pub thumbnail: Option<String>,  // Keeps the current image when omitted; an empty string removes it
+}
+
+// Publication window of a post, kept until both times have passed
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ContentSchedule {
+    // The post stays hidden until this time, then it is published
+    pub embargo_until: Option<TimestampMillis>,
+    // The post is hidden from this time on
+    pub sunset_at: Option<TimestampMillis>,
+}
+
+// Replaces the whole schedule; leave a time out to clear it
+#[derive(CandidType, Deserialize, Clone)]
+pub struct SetContentScheduleRequest {
+    pub post_id: String,
+    pub embargo_until: Option<TimestampMillis>,
+    pub sunset_at: Option<TimestampMillis>,
+}
+
+// Share an existing post into another tag community
+#[derive(CandidType, Deserialize, Clone)]
+pub struct CrossPostRequest {
+    pub post_id: String,
+    pub hashtag: String,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
 pub struct UpdateCommentRequest {
     pub id: String,
     pub content: String,
+    pub format: Option<ContentFormat>,  // Keeps the current format when omitted
 }
 
 // Content types
+// How `content` was written; Markdown and Plain sources are rendered to sanitized HTML for readers
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum ContentFormat {
+    #[default]
+    Html,
+    Markdown,
+    Plain,
+}
+
+// Reuse terms for articles and media posts, exposed to syndication partners
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ContentLicense {
+    CcBy,
+    Cc0,
+    AllRightsReserved,
+}
+
 #[derive(CandidType, Deserialize, Clone)]
 pub struct Post {
     pub id: String,
@@ -56,14 +124,43 @@ pub struct Post {
     pub hashtags: Vec<String>,
     pub token_mentions: Vec<String>,
     pub tags: Vec<String>,  // Max 5 tags
-    pub created_at: u64,
-    pub updated_at: u64,
+    pub created_at: TimestampMillis,
+    pub updated_at: TimestampMillis,
     pub status: ContentStatus,
     pub visibility: ContentVisibility,
     pub news_reference: Option<NewsReference>,
+    #[serde(default)]
+    pub posted_by: Option<Principal>,  // Delegate who posted on behalf of the author
+    #[serde(default)]
+    pub title: Option<String>,  // Set for creator articles
+    #[serde(default)]
+    pub is_premium: bool,
+    #[serde(default)]
+    pub license: Option<ContentLicense>,  // Set for articles and posts with media
+    #[serde(default)]
+    pub token_gate: Option<TokenGate>,
+    #[serde(default)]
+    pub format: ContentFormat,
+    // SHA-256 of the post as last written; see `verify_content_integrity`
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    // Set on a cross-post: the original post, which holds the likes and comments of both
+    #[serde(default)]
+    pub cross_post_of: Option<String>,
+    // Chosen by the author rather than taken from `media_urls`
+    #[serde(default)]
+    pub cover_image: Option<String>,
+    #[serde(default)]
+    pub thumbnail: Option<String>,
 }
 
 // Response DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct ScheduledPostResponse {
+    pub post: PostResponse,
+    pub schedule: ContentSchedule,
+}
+
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct PostResponse {
     pub id: String,
@@ -73,14 +170,40 @@ pub struct PostResponse {
     pub hashtags: Vec<String>,
     pub token_mentions: Vec<String>,
     pub tags: Vec<String>,  // Max 5 tags
-    pub created_at: u64,
-    pub updated_at: u64,
+    pub created_at: TimestampMillis,
+    pub updated_at: TimestampMillis,
     pub status: ContentStatus,
     pub visibility: ContentVisibility,
     pub likes_count: u64,
     pub comments_count: u64,
     pub author_info: crate::models::user::UserSocialResponse,
     pub news_reference: Option<NewsReference>,
+    pub posted_by: Option<Principal>,
+    pub title: Option<String>,
+    pub is_premium: bool,
+    pub license: Option<ContentLicense>,
+    // Set when the post is token-gated; content and media are then withheld from list and detail views
+    pub token_gate: Option<TokenGate>,
+    // Up to three of the caller's followed users who liked the post
+    pub liked_by_followed: Vec<crate::models::user::UserSocialResponse>,
+    // Extractive preview of long articles
+    pub summary: Option<String>,
+    // `content` is always HTML; Markdown and Plain posts also return their source for editing
+    pub format: ContentFormat,
+    pub source: Option<String>,
+    // Emoji reactions, and how to draw each :shortcode: used in the content or its reactions
+    pub reactions: Vec<crate::models::emoji::ReactionCount>,
+    pub emojis: Vec<crate::models::emoji::EmojiMetadata>,
+    pub content_hash: Option<String>,
+    pub cross_post_of: Option<String>,
+    // Headings of an article with their anchors; only filled in on single-post views
+    #[serde(default)]
+    pub table_of_contents: Vec<TocEntry>,
+    // Shown even for token-gated posts, like the title
+    #[serde(default)]
+    pub cover_image: Option<String>,
+    #[serde(default)]
+    pub thumbnail: Option<String>,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -90,11 +213,15 @@ pub struct Comment {
     pub content: String,
     pub parent_id: String, // ID of the post or comment this is replying to
     pub parent_type: ParentType,
-    pub created_at: u64,
-    pub updated_at: u64,
+    pub created_at: TimestampMillis,
+    pub updated_at: TimestampMillis,
     pub status: ContentStatus,
     pub child_comments: Vec<String>, // IDs of child comments
     pub likes_count: u64,
+    #[serde(default)]
+    pub format: ContentFormat,
+    #[serde(default)]
+    pub quoted_post_id: Option<String>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Debug)]
@@ -104,8 +231,8 @@ pub struct CommentResponse {
     pub content: String,
     pub parent_id: String,
     pub parent_type: ParentType,
-    pub created_at: u64,
-    pub updated_at: u64,
+    pub created_at: TimestampMillis,
+    pub updated_at: TimestampMillis,
     pub status: ContentStatus,
     pub likes_count: u64,
     pub comments_count: u64,
@@ -113,6 +240,16 @@ pub struct CommentResponse {
     pub child_comments: Vec<Box<CommentResponse>>,
     pub author_info: crate::models::user::UserSocialResponse,
     pub is_liked: bool,
+    // `content` is always HTML; Markdown and Plain comments also return their source for editing
+    pub format: ContentFormat,
+    pub source: Option<String>,
+    // Emoji reactions, and how to draw each :shortcode: used in the content or its reactions
+    pub reactions: Vec<crate::models::emoji::ReactionCount>,
+    pub emojis: Vec<crate::models::emoji::EmojiMetadata>,
+    // The quoted post in compact form; None when the comment quotes nothing or the post is no longer
+    // public and active, which `quoted_post_id` tells apart
+    pub quoted_post_id: Option<String>,
+    pub quoted_post: Option<PostResponse>,
 }
 
 impl From<Comment> for CommentResponse {
@@ -132,6 +269,12 @@ impl From<Comment> for CommentResponse {
             child_comments: Vec::new(), // Child comments need to be populated separately
             author_info: crate::models::user::UserSocialResponse::default(), // Need to be populated separately
             is_liked: false, // Need to be populated separately
+            format: comment.format,
+            source: None, // Filled in with the rendered content
+            reactions: Vec::new(), // Filled in with the emoji metadata
+            emojis: Vec::new(),
+            quoted_post_id: comment.quoted_post_id,
+            quoted_post: None, // Filled in with the quoted posts
         }
     }
 }
@@ -142,6 +285,9 @@ pub struct CommentsResponse {
     pub total: u64,
     pub has_more: bool,
     pub next_offset: usize,
+    // Set when the page ended early to keep the reply under the size limit
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -149,6 +295,40 @@ pub struct PostsResponse {
     pub posts: Vec<PostResponse>,
     pub total: u64,
     pub next_offset: usize,
+    // Set when the page ended early to keep the reply under the size limit
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+// Cached summary of a long article; rebuilt when the article text changes
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArticleSummary {
+    pub post_id: String,
+    pub summary: String,
+    pub source_hash: String,
+    pub generated_at: TimestampMillis,
+}
+
+// A heading of an article and the anchor that links to its section
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TocEntry {
+    pub level: u8,
+    pub text: String,
+    pub anchor: String,
+}
+
+// Cached table of contents of an article; rebuilt when its rendered HTML changes
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArticleOutline {
+    pub source_hash: String,
+    pub entries: Vec<TocEntry>,
+}
+
+// Cached HTML rendering of Markdown or Plain content; rebuilt when the source changes
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RenderedContent {
+    pub source_hash: String,
+    pub html: String,
 }
 
 // Query parameters
@@ -157,8 +337,8 @@ pub struct ContentFilter {
     pub author: Option<Principal>,
     pub hashtag: Option<String>,
     pub token_mention: Option<String>,
-    pub created_after: Option<u64>,
-    pub created_before: Option<u64>,
+    pub created_after: Option<TimestampMillis>,
+    pub created_before: Option<TimestampMillis>,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -176,6 +356,15 @@ pub struct ContentModerationRequest {
     pub reason: String,
 }
 
+// A moderated post or comment as it now stands
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ModeratedContent {
+    pub content_id: String,
+    pub content_type: ContentType,
+    pub status: ContentStatus,
+    pub updated_at: u64,
+}
+
 #[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
 pub enum ContentStatus {
     Active,
@@ -183,6 +372,10 @@ pub enum ContentStatus {
     Removed,
     Hidden,
     Deleted,
+    // Kept for a legal request: hidden from everyone and frozen until an admin releases the hold
+    LegalHold,
+    // Waiting for its embargo to lift; only its author and moderators see it
+    Scheduled,
 }
 
 #[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
@@ -223,3 +416,17 @@ pub const MAX_TITLE_LENGTH: usize = 200;
 pub const MAX_HASHTAGS: usize = 10;
 pub const MAX_TOKEN_MENTIONS: usize = 10;
 pub const MAX_MEDIA_URLS: usize = 5;
+pub const MAX_IMAGE_URL_LENGTH: usize = 2048;
+// Articles longer than a regular post get a summary
+pub const SUMMARY_MIN_ARTICLE_LENGTH: usize = MAX_POST_LENGTH;
+pub const SUMMARY_MAX_SENTENCES: usize = 3;
+pub const SUMMARY_MAX_CHARS: usize = 400;
+// Articles with at least this many headings get a table of contents, of at most TOC_MAX_ENTRIES
+pub const TOC_MIN_HEADINGS: usize = 2;
+pub const TOC_MAX_ENTRIES: usize = 100;
+// Communities a post can be cross-posted into, besides its own hashtags
+pub const MAX_CROSS_POSTS: usize = 5;
+// Characters of a quoted post's text embedded in a comment
+pub const QUOTED_POST_EXCERPT_LENGTH: usize = 280;
+// How far ahead an embargo or sunset can be scheduled
+pub const MAX_SCHEDULE_DAYS: u64 = 365;
@@ -0,0 +1,85 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// Creator program constants
+pub const MAX_CREATOR_PITCH_LENGTH: usize = 1000;
+pub const MAX_PORTFOLIO_LINKS: usize = 5;
+pub const MAX_ARTICLE_LENGTH: usize = 20000;
+// Users checked against the promotion thresholds per full heartbeat run
+pub const CREATOR_PROMOTION_BATCH: usize = 500;
+
+// Creator application submitted by a user
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CreatorApplication {
+    pub applicant: Principal,
+    pub pitch: String,
+    pub portfolio_links: Vec<String>,
+    pub status: CreatorApplicationStatus,
+    pub submitted_at: TimestampMillis,
+    pub reviewed_at: Option<TimestampMillis>,
+    pub reviewer: Option<Principal>,
+    pub review_notes: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
+pub enum CreatorApplicationStatus {
+    Pending,
+    Approved,
+    Rejected,
+    AutoPromoted,
+}
+
+// Engagement thresholds for automatic promotion to Creator
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CreatorPromotionThresholds {
+    pub enabled: bool,
+    pub min_followers: u64,
+    pub min_posts: u64,
+    pub min_likes_received: u64,
+}
+
+impl Default for CreatorPromotionThresholds {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_followers: 100,
+            min_posts: 20,
+            min_likes_received: 500,
+        }
+    }
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct CreatorApplicationRequest {
+    pub pitch: String,
+    pub portfolio_links: Option<Vec<String>>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct ReviewCreatorApplicationRequest {
+    pub applicant: Principal,
+    pub approve: bool,
+    pub notes: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UpdateCreatorThresholdsRequest {
+    pub enabled: Option<bool>,
+    pub min_followers: Option<u64>,
+    pub min_posts: Option<u64>,
+    pub min_likes_received: Option<u64>,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct CreatorAnalyticsResponse {
+    pub total_posts: u64,
+    pub total_articles: u64,
+    pub total_premium_posts: u64,
+    pub total_comments_received: u64,
+    pub total_likes_received: u64,
+    pub followers_count: u64,
+    pub engagement_rate: f64,
+    pub top_posts: Vec<(String, u64)>, // post id -> likes
+}
@@ -1,4 +1,11 @@
 use candid::{CandidType, Deserialize};
+use crate::utils::time_utils::TimestampMillis;
+
+// Days-of-runway tiers that raise a runway alert, loosest first; the last one is critical
+pub const RUNWAY_ALERT_DAYS: [u64; 3] = [30, 14, 7];
+// Days ahead the projected balance is checked against the freezing threshold reserve
+pub const FREEZE_WARNING_DAYS: u64 = 14;
+pub const FREEZE_GUARD_DAYS: u64 = 7;
 
 // Response for cycles balance query
 #[derive(CandidType, Deserialize, Clone)]
@@ -44,12 +51,67 @@ pub struct UpdateCyclesThresholdRequest {
 // Cycles warning notification
 #[derive(CandidType, Deserialize, Clone)]
 pub struct CyclesWarningNotification {
-    pub timestamp: u64,
+    pub timestamp: TimestampMillis,
     pub balance: u64,
     pub threshold: u64,
     pub severity: CyclesWarningSeverity,
     pub message: String,
     pub is_acknowledged: bool,
+    pub runway_days: Option<u64>,  // Runway tier for forecast alerts, None for balance threshold alerts
+    pub kind: CyclesAlertKind,
+}
+
+// What raised a cycles notification
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum CyclesAlertKind {
+    Balance,
+    Runway,
+    FreezeThreshold,
+}
+
+// Burn forecast over the consumption history
+#[derive(CandidType, Deserialize, Clone)]
+pub struct CyclesRunwayForecast {
+    pub balance: u64,
+    pub daily_burn: u64,  // Weighted daily burn, recent days counting most
+    pub runway_days: Option<u64>,  // None until some consumption has been recorded
+    pub history_days: u64,  // Days of history behind the forecast
+    pub alert_tier: Option<u64>,  // Tightest tier in RUNWAY_ALERT_DAYS the runway is below
+}
+
+// How close the projected balance is to the freezing threshold reserve
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum FreezeGuardLevel {
+    #[default]
+    Normal,
+    Warning,  // Crosses the reserve within FREEZE_WARNING_DAYS
+    Guarded,  // Crosses it within FREEZE_GUARD_DAYS; expensive jobs and media uploads are paused
+}
+
+// Freezing threshold settings from the last canister_status call, the guard level they drive and
+// the last runway alert tier. Kept in storage, so an upgrade neither lifts the guard nor repeats
+// alerts until the next reading.
+#[derive(CandidType, Deserialize, Clone, Default)]
+pub struct FreezeGuardState {
+    pub freezing_threshold_seconds: Option<u64>,
+    pub idle_cycles_burned_per_day: Option<u64>,
+    pub checked_at: Option<TimestampMillis>,
+    pub last_error: Option<String>,
+    pub level: FreezeGuardLevel,
+    pub last_runway_tier: Option<u64>,
+}
+
+// Freezing threshold as last read from the management canister, and the guard it drives
+#[derive(CandidType, Deserialize, Clone)]
+pub struct FreezeGuardStatus {
+    pub balance: u64,
+    pub freezing_threshold_seconds: Option<u64>,
+    pub idle_cycles_burned_per_day: Option<u64>,
+    pub freeze_reserve: Option<u64>,  // Balance below which the canister freezes
+    pub projected_balance: u64,  // Balance FREEZE_GUARD_DAYS from now at the forecast burn
+    pub level: FreezeGuardLevel,
+    pub checked_at: Option<TimestampMillis>,
+    pub last_error: Option<String>,  // Why the last status read failed, if it did
 }
 
 // Warning severity level
@@ -0,0 +1,41 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// Maximum number of audit records kept per organization account
+pub const MAX_DELEGATION_AUDIT_RECORDS: usize = 500;
+
+// A principal authorized to post on behalf of an organization account
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PostingDelegation {
+    pub org: Principal,
+    pub delegate: Principal,
+    pub granted_at: TimestampMillis,
+}
+
+// Audit trail entry for grants, revocations and delegated content actions
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct DelegatedActionRecord {
+    pub org: Principal,
+    pub delegate: Principal,
+    pub action: DelegatedAction,
+    pub content_id: Option<String>,
+    pub timestamp: TimestampMillis,
+}
+
+#[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
+pub enum DelegatedAction {
+    Granted,
+    Revoked,
+    PostCreated,
+    PostUpdated,
+    PostDeleted,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct DelegationAuditResponse {
+    pub records: Vec<DelegatedActionRecord>,
+    pub total: u64,
+    pub has_more: bool,
+    pub next_offset: usize,
+}
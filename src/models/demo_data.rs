@@ -0,0 +1,21 @@
+use candid::{CandidType, Deserialize};
+
+// Demo data constants
+// Most one call may create, to stay within a single message's instruction limit
+pub const MAX_DEMO_USERS: u32 = 500;
+pub const MAX_DEMO_POSTS: u32 = 5_000;
+pub const MAX_DEMO_COMMENTS: u32 = 10_000;
+// New demo users follow up to this many earlier demo users
+pub const MAX_DEMO_FOLLOWS_PER_USER: usize = 8;
+// Posts spread back over this many days
+pub const DEMO_CONTENT_DAYS: u64 = 30;
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct DemoDataSummary {
+    pub users_created: u32,
+    pub posts_created: u32,
+    pub comments_created: u32,
+    pub likes_created: u32,
+    pub follows_created: u32,
+}
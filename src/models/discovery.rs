@@ -1,6 +1,75 @@
 use candid::{CandidType, Deserialize, Principal};
 use crate::models::content::{ContentType, PaginationParams};
-use crate::models::tag::TagType;
+use crate::models::tag::{TagType, TagResponse};
+use crate::utils::time_utils::TimestampMillis;
+
+// Trending topic maintenance constants
+pub const MAX_TRENDING_TOPICS: usize = 1000;
+// Topics whose decayed count falls below this are dropped
+pub const TRENDING_TOPIC_FLOOR_COUNT: u64 = 2;
+// Share of a topic's count kept on each decay pass
+pub const TRENDING_DECAY_PERCENT: u64 = 50;
+
+// Feed composition constants
+pub const MAX_FEED_COMPOSITION_WEIGHT: u32 = 100;
+// Largest offset + limit the home and explore feeds serve
+pub const MAX_COMPOSED_FEED_WINDOW: usize = 500;
+// Accounts registered this recently count as fresh creators
+pub const FRESH_CREATOR_DAYS: u64 = 30;
+// Only posts this recent are scored for the trending share
+pub const COMPOSED_TRENDING_DAYS: u64 = 7;
+// How long the heartbeat's trending scores are used before it computes them again
+pub const TRENDING_SCORES_TTL_MS: u64 = 5 * 60 * 1000;
+
+// Relative share of each source in the home and explore feeds. Sources take turns in proportion
+// to their weight, and one that runs out leaves its turns to the others. Explore has no followed share.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct FeedComposition {
+    pub followed: u32,
+    pub trending: u32,
+    pub fresh_creators: u32,
+    // Posts carrying a news reference
+    pub news: u32,
+}
+
+impl Default for FeedComposition {
+    fn default() -> Self {
+        Self {
+            followed: 40,
+            trending: 30,
+            fresh_creators: 15,
+            news: 15,
+        }
+    }
+}
+
+// Creator spotlight constants
+pub const MAX_NEWCOMER_BOOST_PERCENT: u64 = 300;
+// Rising creators compare engagement over the latest window with the one before it
+pub const RISING_CREATORS_WINDOW_DAYS: u64 = 7;
+pub const MAX_RISING_CREATORS: usize = 50;
+// Fewest likes and new followers in the latest window to be listed
+pub const MIN_RISING_ENGAGEMENT: u64 = 3;
+
+// Raises discovery ranking scores of posts by accounts with fewer than `max_followers` followers,
+// so newcomers get found
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NewcomerBoostSettings {
+    pub enabled: bool,
+    pub max_followers: u64,
+    // Added to the score, as a percentage of it
+    pub boost_percent: u64,
+}
+
+impl Default for NewcomerBoostSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_followers: 100,
+            boost_percent: 50,
+        }
+    }
+}
 
 // Request DTOs
 #[derive(CandidType, Deserialize, Clone)]
@@ -9,15 +78,17 @@ pub struct DiscoverContentRequest {
     pub tags: Option<Vec<String>>,
     pub pagination: PaginationParams,
     pub sort_by: Option<SortOption>,
-    pub filter: Option<ContentFilter>
+    pub filter: Option<ContentFilter>,
+    // Skip posts reported through `record_impressions` in the caller's current session
+    pub exclude_seen: Option<bool>,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
 pub struct ContentFilter {
     pub hashtag: Option<String>,
     pub token_mention: Option<String>,
-    pub created_after: Option<u64>,
-    pub created_before: Option<u64>,
+    pub created_after: Option<TimestampMillis>,
+    pub created_before: Option<TimestampMillis>,
     pub author: Option<Principal>
 }
 
@@ -57,7 +128,9 @@ pub struct PersonalizedRecommendationsRequest {
     pub include_trending: Option<bool>,
     pub include_similar_to_liked: Option<bool>,
     pub diversity_factor: Option<f64>,
-    pub recency_weight: Option<f64>
+    pub recency_weight: Option<f64>,
+    // Skip posts reported through `record_impressions` in the caller's current session
+    pub exclude_seen: Option<bool>,
 }
 
 // Response DTOs
@@ -68,8 +141,20 @@ pub struct SearchResultResponse {
     pub title: Option<String>,
     pub snippet: String,
     pub author: crate::models::user::UserSocialResponse,
-    pub created_at: u64,
-    pub relevance_score: f64
+    pub created_at: TimestampMillis,
+    pub relevance_score: f64,
+    // The post's thumbnail, or its cover image when it has no thumbnail
+    #[serde(default)]
+    pub thumbnail: Option<String>,
+}
+
+// Likes and new followers from other accounts, in the latest window and the one before it
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RisingCreator {
+    pub user: crate::models::user::UserSocialResponse,
+    pub recent_engagement: u64,
+    pub previous_engagement: u64,
+    pub growth: i64,
 }
 
 #[derive(CandidType, Deserialize, Clone, Debug)]
@@ -81,15 +166,52 @@ pub struct TrendingTopicResponse {
 
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct HotTagsResponse {
-    pub tags: Vec<HotTagInfo>,
-    pub updated_at: u64
+    pub tags: Vec<TagResponse>,
+    pub updated_at: TimestampMillis
+}
+
+// Counters from the periodic decay and pruning of trending topics
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct TrendingMaintenanceStats {
+    pub last_run_at: TimestampMillis,
+    pub last_pruned_count: u64,
+    pub total_pruned_count: u64,
+    pub runs: u64,
+    pub trending_topics_count: u64,
+    pub previous_trending_topics_count: u64,
+}
+
+// Collaborative filtering precomputation constants
+pub const SIMILAR_USERS_PER_USER: usize = 20;
+// Users processed per heartbeat while a refresh is running
+pub const SIMILARITY_REFRESH_BATCH_SIZE: usize = 50;
+// Likes and unlikes that start a refresh before the next full heartbeat run
+pub const SIMILARITY_REFRESH_LIKE_CHANGES: u64 = 500;
+// Users who logged in within this many days get a precomputed neighbour list
+pub const SIMILARITY_ACTIVE_DAYS: u64 = 30;
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SimilarUser {
+    pub user: Principal,
+    pub similarity: f64,
+}
+
+// Progress of the periodic similar-users refresh
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct SimilarityRefreshState {
+    // Users still to process in the running refresh; empty when idle
+    pub pending: Vec<Principal>,
+    pub like_changes: u64,
+    pub last_completed_at: Option<TimestampMillis>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Debug)]
-pub struct HotTagInfo {
-    pub name: String,
-    pub count: u64,
-    pub tag_type: TagType
+pub struct SimilarityRefreshStatus {
+    pub running: bool,
+    pub pending_users: u64,
+    pub cached_users: u64,
+    pub like_changes_since_refresh: u64,
+    pub last_completed_at: Option<TimestampMillis>,
 }
 
 // Enums
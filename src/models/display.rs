@@ -2,6 +2,7 @@ use candid::{CandidType, Deserialize};
 use crate::models::content::{PostResponse, CommentResponse};
 use crate::models::user::UserSocialResponse;
 use crate::models::discovery::TrendingTopicResponse;
+use crate::utils::time_utils::TimestampMillis;
 
 // Feed display models
 #[derive(CandidType, Deserialize, Clone, Debug)]
@@ -11,6 +12,12 @@ pub struct FeedResponse {
     pub has_more: bool,
     pub next_offset: usize,
     pub total: u64,
+    // Set when ranking fell back to a cheaper ordering to stay within the instruction limit
+    #[serde(default)]
+    pub degraded: bool,
+    // Set when the page ended early to keep the reply under the size limit
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 #[derive(CandidType, Deserialize, Clone, Debug)]
@@ -73,7 +80,7 @@ pub struct NotificationResponse {
     pub actor: Option<UserSocialResponse>,
     pub content_id: Option<String>,
     pub content_snippet: Option<String>,
-    pub created_at: u64,
+    pub created_at: TimestampMillis,
     pub is_read: bool,
 }
 
@@ -0,0 +1,61 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// Emoji constants
+pub const MAX_CUSTOM_EMOJIS: usize = 500;
+pub const MAX_EMOJI_SHORTCODE_LENGTH: usize = 32;
+pub const MAX_EMOJI_IMAGE_URL_LENGTH: usize = 500;
+pub const MAX_EMOJI_CATEGORY_LENGTH: usize = 30;
+pub const MAX_REACTIONS_PER_USER: usize = 5;  // Distinct emojis one user can react with on one post or comment
+
+// Unicode emojis every client resolves the same way; custom emojis cannot reuse these shortcodes
+pub const STANDARD_EMOJIS: [(&str, &str); 40] = [
+    ("+1", "👍"), ("-1", "👎"), ("100", "💯"), ("clap", "👏"), ("cry", "😢"), ("eyes", "👀"),
+    ("fire", "🔥"), ("gem", "💎"), ("grin", "😁"), ("heart", "❤️"), ("heart_eyes", "😍"), ("joy", "😂"),
+    ("laughing", "😆"), ("moneybag", "💰"), ("muscle", "💪"), ("ok_hand", "👌"), ("open_mouth", "😮"),
+    ("party", "🥳"), ("pray", "🙏"), ("raised_hands", "🙌"), ("rocket", "🚀"), ("rofl", "🤣"), ("scream", "😱"),
+    ("see_no_evil", "🙈"), ("smile", "😄"), ("smiley", "😃"), ("sob", "😭"), ("sparkles", "✨"), ("star", "⭐"),
+    ("sunglasses", "😎"), ("tada", "🎉"), ("thinking", "🤔"), ("thumbsdown", "👎"), ("thumbsup", "👍"),
+    ("trophy", "🏆"), ("warning", "⚠️"), ("wave", "👋"), ("white_check_mark", "✅"), ("wink", "😉"), ("x", "❌"),
+];
+
+// Admin-registered image emoji
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CustomEmoji {
+    pub shortcode: String,
+    pub image_url: String,
+    pub category: Option<String>,
+    pub added_by: Principal,
+    pub added_at: TimestampMillis,
+}
+
+// How clients draw a :shortcode:; exactly one of `unicode` and `image_url` is set
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct EmojiMetadata {
+    pub shortcode: String,
+    pub unicode: Option<String>,
+    pub image_url: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReactionCount {
+    pub shortcode: String,
+    pub count: u64,
+    pub reacted_by_caller: bool,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct AddCustomEmojiRequest {
+    pub shortcode: String,
+    pub image_url: String,
+    pub category: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct ReactToContentRequest {
+    pub content_id: String,
+    pub content_type: crate::models::content::ContentType,
+    // With or without the surrounding colons
+    pub shortcode: String,
+}
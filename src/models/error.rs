@@ -21,6 +21,8 @@ pub enum ErrorCode {
     RateLimitExceeded = 1011,
     PermissionDenied = 1012,
     QuotaExceeded = 1013,
+    // A deprecated method was called; recorded as a warning, never returned
+    DeprecatedMethod = 1014,
     UnexpectedError = 1099,
     // 2000-2999: Authentication and authorization errors
     AuthUnauthorized = 2000,
@@ -52,6 +54,31 @@ pub enum ErrorCode {
     ServiceTimeout = 7001,
 }
 
+impl ErrorCode {
+    /// Whether a request failing with this code may succeed if retried unchanged
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorCode::ServiceUnavailable
+                | ErrorCode::DependencyFailed
+                | ErrorCode::ResourceUnavailable
+                | ErrorCode::RateLimitExceeded
+                | ErrorCode::ResourceNotAvailable
+                | ErrorCode::OperationTimeout
+                | ErrorCode::ServiceTimeout
+        )
+    }
+}
+
+/// Field-level validation error
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    /// Request field that failed validation (e.g. "username")
+    pub field: String,
+    /// Human readable reason
+    pub message: String,
+}
+
 /// Error context information
 #[derive(CandidType, Deserialize, Debug, Clone)]
 pub struct ErrorContext {
@@ -95,6 +122,12 @@ pub struct SquareErrorEnhanced {
     pub recoverable: bool,
     /// Suggested recovery action
     pub recovery_hint: Option<String>,
+    /// Whether the same request may succeed if retried
+    #[serde(default)]
+    pub retryable: bool,
+    /// Field-level validation details
+    #[serde(default)]
+    pub field_errors: Vec<FieldError>,
 }
 
 /// Main error type
@@ -139,6 +172,8 @@ impl SquareError {
             },
             recoverable: false,
             recovery_hint: None,
+            retryable: code.is_retryable(),
+            field_errors: Vec::new(),
         })
     }
     
@@ -167,6 +202,41 @@ impl SquareError {
         self
     }
     
+    /// Override the retryable flag derived from the error code
+    pub fn with_retryable(mut self, retryable: bool) -> Self {
+        if let SquareError::Enhanced(ref mut enhanced) = self {
+            enhanced.retryable = retryable;
+        }
+        self
+    }
+    
+    /// Add a field-level validation detail
+    pub fn with_field_error(mut self, field: impl Into<String>, message: impl Into<String>) -> Self {
+        if let SquareError::Enhanced(ref mut enhanced) = self {
+            enhanced.field_errors.push(FieldError {
+                field: field.into(),
+                message: message.into(),
+            });
+        }
+        self
+    }
+    
+    /// Whether the same request may succeed if retried
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SquareError::Enhanced(enhanced) => enhanced.retryable,
+            _ => self.code().is_retryable(),
+        }
+    }
+    
+    /// Field-level validation details, if any
+    pub fn field_errors(&self) -> Vec<FieldError> {
+        match self {
+            SquareError::Enhanced(enhanced) => enhanced.field_errors.clone(),
+            _ => Vec::new(),
+        }
+    }
+    
     /// Get error code
     pub fn code(&self) -> ErrorCode {
         match self {
@@ -0,0 +1,160 @@
+use candid::{CandidType, Deserialize, Principal};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::user::UserStats;
+use crate::utils::time_utils::TimestampMillis;
+
+// Interaction event log constants
+pub const MAX_RETAINED_EVENTS: usize = 20_000;
+// Events folded into the checkpoint at a time once the log is over its cap
+pub const EVENT_TRIM_BATCH: usize = 1_000;
+
+// Something a user did that derived state (stats, trending, task progress) depends on
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum InteractionEventKind {
+    PostCreated { post_id: String, author: Principal, hashtags: Vec<String> },
+    // A post that stops counting: deleted, or moved out of Active by moderation. Events logged before
+    // hashtags and likes were recorded leave trending and likes received alone.
+    PostDeleted {
+        post_id: String,
+        author: Principal,
+        #[serde(default)]
+        hashtags: Vec<String>,
+        #[serde(default)]
+        likes: u64,
+    },
+    // A moderated post made Active again
+    PostRestored { post_id: String, author: Principal, hashtags: Vec<String>, likes: u64 },
+    CommentCreated {
+        comment_id: String,
+        author: Principal,
+        // A comment in the author's own thread or repeating one of theirs there; kept out of task progress
+        #[serde(default)]
+        excluded: bool,
+    },
+    // Like PostDeleted, for comments
+    CommentDeleted {
+        comment_id: String,
+        author: Principal,
+        #[serde(default)]
+        likes: u64,
+    },
+    CommentRestored { comment_id: String, author: Principal, likes: u64 },
+    Liked { content_id: String, author: Option<Principal> },
+    Unliked { content_id: String, author: Option<Principal> },
+    Viewed { content_id: String, author: Principal },
+    Followed { followed: Principal },
+    Unfollowed { followed: Principal },
+    TopicsMentioned { topics: Vec<String> },
+    TopicsMerged { from: String, into: String },
+    // Daily roll-over of trending counts, logged so replays decay at the same points
+    TrendingDecayed,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct InteractionEvent {
+    pub seq: u64,
+    pub actor: Principal,
+    pub timestamp: TimestampMillis,
+    pub kind: InteractionEventKind,
+    // UTC offset of the user whose task progress the event counts toward, as set when it was logged.
+    // None for events logged before the offset was recorded.
+    #[serde(default)]
+    pub timezone_offset: Option<i32>,
+}
+
+// Processes interaction events into one piece of derived state
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EventConsumer {
+    // post_count, comment_count, like_count and reputation in `user_stats`
+    UserStats,
+    // trending_topics and previous_trending_topics
+    Trending,
+    // task_progress
+    TaskProgress,
+}
+
+pub const EVENT_CONSUMERS: [EventConsumer; 3] = [EventConsumer::UserStats, EventConsumer::Trending, EventConsumer::TaskProgress];
+
+// What a user did during their current local day, checked against task requirements
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct DailyTaskProgress {
+    pub day_start: TimestampMillis,
+    pub posts: u64,
+    pub comments: u64,
+    pub likes_given: u64,
+    pub follows: u64,
+}
+
+// The counters of a user's stats that events drive
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StatsCounters {
+    pub post_count: u64,
+    pub comment_count: u64,
+    pub like_count: u64,
+    pub reputation: u64,
+}
+
+impl From<&UserStats> for StatsCounters {
+    fn from(stats: &UserStats) -> Self {
+        Self {
+            post_count: stats.post_count,
+            comment_count: stats.comment_count,
+            like_count: stats.like_count,
+            reputation: stats.reputation,
+        }
+    }
+}
+
+impl StatsCounters {
+    // Set the event-driven counters of `stats`, leaving points alone
+    pub fn write_to(&self, stats: &mut UserStats) {
+        stats.post_count = self.post_count;
+        stats.comment_count = self.comment_count;
+        stats.like_count = self.like_count;
+        stats.reputation = self.reputation;
+    }
+}
+
+// Derived state as of `seq`, built from the events trimmed from the log. Rebuilding a consumer
+// starts from here and replays the retained events.
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct EventCheckpoint {
+    pub seq: u64,
+    // Users whose counters are all zero are left out
+    #[serde(default)]
+    pub user_counters: HashMap<Principal, StatsCounters>,
+    // Full stats held by checkpoints written before `user_counters`; migrate_event_checkpoint_counters
+    // moves them over
+    #[serde(default)]
+    pub user_stats: HashMap<Principal, UserStats>,
+    pub trending_topics: BTreeMap<String, u64>,
+    pub previous_trending_topics: BTreeMap<String, u64>,
+    pub task_progress: HashMap<Principal, DailyTaskProgress>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct InteractionEventLog {
+    // Oldest first
+    pub events: Vec<InteractionEvent>,
+    pub next_seq: u64,
+    // Next event each consumer will process
+    pub cursors: BTreeMap<EventConsumer, u64>,
+    pub checkpoint: EventCheckpoint,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EventConsumerStatus {
+    pub consumer: EventConsumer,
+    pub next_seq: u64,
+    pub pending_events: u64,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct InteractionEventLogStatus {
+    pub next_seq: u64,
+    pub checkpoint_seq: u64,
+    pub retained_events: u64,
+    pub consumers: Vec<EventConsumerStatus>,
+}
@@ -0,0 +1,44 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// Runtime switches operators can flip without an upgrade; all are off by default
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FeatureFlag {
+    // Personalized recommendations return an error instead of computing a feed
+    DisableRecommendations,
+    // New accounts cannot register
+    PauseRegistrations,
+    // Update calls from anyone but the admin are rejected, as in maintenance mode but without a banner
+    ReadOnlyMode,
+}
+
+impl FeatureFlag {
+    pub const ALL: [FeatureFlag; 3] = [
+        FeatureFlag::DisableRecommendations,
+        FeatureFlag::PauseRegistrations,
+        FeatureFlag::ReadOnlyMode,
+    ];
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct FeatureFlagState {
+    pub enabled: bool,
+    pub updated_at: TimestampMillis,
+    pub updated_by: Principal,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct SetFeatureFlagRequest {
+    pub flag: FeatureFlag,
+    pub enabled: bool,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct FeatureFlagResponse {
+    pub flag: FeatureFlag,
+    pub enabled: bool,
+    pub updated_at: Option<TimestampMillis>,
+    pub updated_by: Option<Principal>,
+}
@@ -0,0 +1,20 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+pub const FOLLOW_GRAPH_PAGE_SIZE: usize = 1000;
+
+// One follow; `followed_at` is unknown for follows made before follow times were recorded
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct FollowEdge {
+    pub follower: Principal,
+    pub followee: Principal,
+    pub followed_at: Option<TimestampMillis>,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct FollowGraphPage {
+    pub edges: Vec<FollowEdge>,
+    // Pass back to get the next page; None on the last page
+    pub next_cursor: Option<String>,
+}
@@ -0,0 +1,51 @@
+use candid::{define_function, CandidType, Deserialize};
+
+// HTTP gateway constants
+// Bodies larger than this are sent in chunks of this size, below the reply size limit
+pub const HTTP_CHUNK_SIZE: usize = 1_000_000;
+
+pub type HeaderField = (String, String);
+
+// Request from the HTTP gateway
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct HttpGatewayRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<HeaderField>,
+    pub body: Vec<u8>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct HttpGatewayResponse {
+    pub status_code: u16,
+    pub headers: Vec<HeaderField>,
+    pub body: Vec<u8>,
+    pub streaming_strategy: Option<StreamingStrategy>,
+}
+
+// Identifies the next chunk of a streamed body
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct StreamingCallbackToken {
+    // Path of the resource being streamed
+    pub path: String,
+    pub chunk_index: u64,
+    // Version of the resource when streaming began; the stream stops if it changes
+    pub version: u64,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct StreamingCallbackHttpResponse {
+    pub body: Vec<u8>,
+    // None once the last chunk has been sent
+    pub token: Option<StreamingCallbackToken>,
+}
+
+define_function!(pub StreamingCallback : (StreamingCallbackToken) -> (StreamingCallbackHttpResponse) query);
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum StreamingStrategy {
+    Callback {
+        callback: StreamingCallback,
+        token: StreamingCallbackToken,
+    },
+}
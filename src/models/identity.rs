@@ -0,0 +1,63 @@
+use candid::{CandidType, Deserialize, Nat, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// Account linking constants
+pub const MAX_LINKED_ACCOUNTS: usize = 5;
+pub const ACCOUNT_LINK_CHALLENGE_TTL_MS: u64 = 10 * 60 * 1000;
+// Outstanding challenges per user; asking again for the same account replaces its challenge
+pub const MAX_PENDING_ACCOUNT_LINKS: usize = 5;
+
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum LinkedAccountKind {
+    Wallet,
+    // Principal controlling an NNS neuron
+    NeuronController,
+}
+
+// Principal proven to belong to a user by calling `confirm_account_link` with its own identity
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LinkedAccount {
+    pub principal: Principal,
+    pub kind: LinkedAccountKind,
+    pub verified_at: TimestampMillis,
+}
+
+// Outstanding link waiting for the linked principal to sign the challenge
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PendingAccountLink {
+    pub user: Principal,
+    pub account: Principal,
+    pub kind: LinkedAccountKind,
+    pub expires_at: TimestampMillis,
+}
+
+// ICRC-1 account passed to `icrc1_balance_of`
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Icrc1Account {
+    pub owner: Principal,
+    pub subaccount: Option<Vec<u8>>,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct RequestAccountLinkRequest {
+    pub account: Principal,
+    pub kind: LinkedAccountKind,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AccountLinkChallenge {
+    pub challenge: String,
+    pub account: Principal,
+    pub kind: LinkedAccountKind,
+    pub expires_at: TimestampMillis,
+}
+
+// Balance of one ICRC-1 token across a user's own principal and linked accounts
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TokenHoldings {
+    pub ledger_canister_id: Principal,
+    pub total: Nat,
+    pub balances: Vec<(Principal, Nat)>,
+}
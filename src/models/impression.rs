@@ -0,0 +1,109 @@
+use candid::{CandidType, Deserialize};
+use crate::utils::time_utils::{TimestampMillis, MILLIS_PER_DAY, MILLIS_PER_MINUTE};
+
+// Impression logging constants
+pub const MAX_IMPRESSIONS_PER_USER: usize = 200;
+// Users with an impression log; the one idle longest makes room for a new one
+pub const MAX_IMPRESSION_USERS: usize = 10_000;
+pub const MAX_IMPRESSIONS_PER_CALL: usize = 50;
+// A view this soon after an impression counts as a click on it
+pub const IMPRESSION_CLICK_WINDOW: TimestampMillis = MILLIS_PER_DAY;
+
+// Recommendations weigh posts by click-through once they have this many impressions, up to
+// CLICK_WEIGHT_RANGE times more or less than the click-through of all posts
+pub const MIN_IMPRESSIONS_FOR_CLICK_WEIGHT: u64 = 20;
+pub const CLICK_WEIGHT_RANGE: f64 = 2.0;
+
+// Posts served to each user in their current session, for feeds asked to skip them
+pub const RECENTLY_SERVED_TTL: TimestampMillis = 30 * MILLIS_PER_MINUTE;
+pub const MAX_RECENTLY_SERVED: usize = 300;
+
+// Where a post was shown
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FeedSurface {
+    Home,
+    Explore,
+    Following,
+    Recommendations,
+    Discover,
+    Search,
+}
+
+// Impressions are logged for a fixed sample of users, chosen by principal, so each sampled
+// user's clicks can be matched against everything they were served
+// Arms of the click-through ranking experiment, assigned by principal alongside the sample.
+// Recommendations weigh posts by click-through for the ClickWeighted arm only.
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RankingArm {
+    Baseline,
+    ClickWeighted,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ImpressionSettings {
+    pub enabled: bool,
+    pub sample_percent: u64,
+}
+
+impl Default for ImpressionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sample_percent: 10,
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Impression {
+    pub content_id: String,
+    pub surface: FeedSurface,
+    pub served_at: TimestampMillis,
+    pub clicked_at: Option<TimestampMillis>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ServedPost {
+    pub content_id: String,
+    pub served_at: TimestampMillis,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct ImpressionCounts {
+    pub impressions: u64,
+    pub clicks: u64,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct RecordImpressionsRequest {
+    pub surface: FeedSurface,
+    // Post ids in the order they were shown
+    pub content_ids: Vec<String>,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SurfaceImpressionStats {
+    pub surface: FeedSurface,
+    pub impressions: u64,
+    pub clicks: u64,
+    // Clicks per impression, 0 to 1
+    pub click_through_rate: f64,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArmImpressionStats {
+    pub arm: RankingArm,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub click_through_rate: f64,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ImpressionStatsResponse {
+    pub settings: ImpressionSettings,
+    pub sampled_users: u64,
+    pub surfaces: Vec<SurfaceImpressionStats>,
+    pub arms: Vec<ArmImpressionStats>,
+}
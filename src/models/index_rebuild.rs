@@ -0,0 +1,101 @@
+use candid::{CandidType, Deserialize};
+
+use crate::utils::time_utils::TimestampMillis;
+
+// Index rebuild constants
+// Records checked per `rebuild_indexes` call
+pub const INDEX_REBUILD_BATCH_SIZE: usize = 500;
+// Discrepancies listed in the progress report; the rest are only counted
+pub const MAX_REPORTED_DISCREPANCIES: usize = 100;
+
+// What `rebuild_indexes` recomputes from the primary records
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexScope {
+    All,
+    // user_posts
+    UserPosts,
+    // user_comments, post_comments and each comment's child_comments
+    Comments,
+    // likes_count stored on comments
+    LikeCounts,
+    // post, comment and like counts in user_stats; reads user_posts and user_comments, so
+    // rebuild those first when they may be off
+    UserStats,
+    // trending topics, replayed from the event log, and trending_content
+    Trending,
+}
+
+// One pass over a set of records; a scope runs its passes in order
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexRebuildPass {
+    // Every post is listed under its author
+    PostAuthors,
+    // Every user_posts entry is a post by that user
+    UserPostEntries,
+    // Every comment is listed under its author and its parent post or comment, and lists only its
+    // own replies
+    CommentLinks,
+    // Every user_comments entry is a comment by that user
+    UserCommentEntries,
+    CommentLikeCounts,
+    UserStats,
+    Trending,
+}
+
+impl IndexScope {
+    pub fn passes(self) -> Vec<IndexRebuildPass> {
+        match self {
+            IndexScope::All => vec![
+                IndexRebuildPass::PostAuthors,
+                IndexRebuildPass::UserPostEntries,
+                IndexRebuildPass::CommentLinks,
+                IndexRebuildPass::UserCommentEntries,
+                IndexRebuildPass::CommentLikeCounts,
+                IndexRebuildPass::UserStats,
+                IndexRebuildPass::Trending,
+            ],
+            IndexScope::UserPosts => vec![IndexRebuildPass::PostAuthors, IndexRebuildPass::UserPostEntries],
+            IndexScope::Comments => vec![IndexRebuildPass::CommentLinks, IndexRebuildPass::UserCommentEntries],
+            IndexScope::LikeCounts => vec![IndexRebuildPass::CommentLikeCounts],
+            IndexScope::UserStats => vec![IndexRebuildPass::UserStats],
+            IndexScope::Trending => vec![IndexRebuildPass::Trending],
+        }
+    }
+}
+
+// A derived value that disagreed with the primary records. All are corrected except comments
+// whose parent comment is gone, which are only reported.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct IndexDiscrepancy {
+    pub pass: IndexRebuildPass,
+    // Content ID, principal or topic the value belongs to
+    pub key: String,
+    pub detail: String,
+}
+
+// A rebuild in progress, carried across `rebuild_indexes` calls
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct IndexRebuildState {
+    pub scope: IndexScope,
+    // Passes still to run, current first
+    pub passes: Vec<IndexRebuildPass>,
+    // Last key the current pass checked
+    pub cursor: Option<String>,
+    pub records_checked: u64,
+    pub discrepancies_found: u64,
+    pub discrepancies: Vec<IndexDiscrepancy>,
+    pub started_at: TimestampMillis,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct IndexRebuildProgress {
+    pub scope: IndexScope,
+    // Pass the next call continues with; None once the rebuild is complete
+    pub next_pass: Option<IndexRebuildPass>,
+    pub records_checked: u64,
+    pub discrepancies_found: u64,
+    pub discrepancies: Vec<IndexDiscrepancy>,
+    pub started_at: TimestampMillis,
+    pub complete: bool,
+}
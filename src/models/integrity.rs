@@ -0,0 +1,81 @@
+use candid::{CandidType, Deserialize};
+
+use crate::utils::time_utils::{TimestampMillis, MILLIS_PER_DAY};
+
+// Integrity audit constants
+// A full heartbeat run starts an audit once this long has passed since the last one started
+pub const INTEGRITY_AUDIT_INTERVAL: u64 = MILLIS_PER_DAY;
+// Records checked per heartbeat while an audit runs
+pub const INTEGRITY_AUDIT_BATCH_SIZE: usize = 500;
+// Findings kept in a report; the rest are only counted
+pub const MAX_INTEGRITY_FINDINGS: usize = 200;
+
+// Something the audit looks for. The audit only reports; `rebuild_indexes` repairs.
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegrityCheck {
+    // Comments whose parent post or comment no longer exists
+    OrphanedComments,
+    // Like sets for content that no longer exists
+    DanglingLikes,
+    // user_stats counters that disagree with the indexed posts, comments and likes
+    StatsDrift,
+    // user_posts entries for posts that are missing or by someone else
+    DanglingUserPosts,
+}
+
+pub const INTEGRITY_CHECKS: [IntegrityCheck; 4] = [
+    IntegrityCheck::OrphanedComments,
+    IntegrityCheck::DanglingLikes,
+    IntegrityCheck::StatsDrift,
+    IntegrityCheck::DanglingUserPosts,
+];
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct IntegrityFinding {
+    pub check: IntegrityCheck,
+    // Content ID or principal the finding is about
+    pub key: String,
+    pub detail: String,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct IntegrityCheckSummary {
+    pub check: IntegrityCheck,
+    pub records_checked: u64,
+    pub findings: u64,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct IntegrityReport {
+    pub started_at: TimestampMillis,
+    // None while the audit is running
+    pub completed_at: Option<TimestampMillis>,
+    // Checks run so far, in order
+    pub checks: Vec<IntegrityCheckSummary>,
+    pub findings: Vec<IntegrityFinding>,
+}
+
+// An audit in progress, advanced a batch per heartbeat
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct IntegrityAuditRun {
+    // Checks still to run, current first
+    pub remaining: Vec<IntegrityCheck>,
+    // Last key the current check looked at
+    pub cursor: Option<String>,
+    pub report: IntegrityReport,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct IntegrityAuditState {
+    pub running: Option<IntegrityAuditRun>,
+    pub last_report: Option<IntegrityReport>,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct IntegrityAuditStatus {
+    // Partial report of the audit in progress
+    pub running: Option<IntegrityReport>,
+    pub next_check: Option<IntegrityCheck>,
+    pub last_report: Option<IntegrityReport>,
+}
@@ -1,5 +1,8 @@
 use candid::{CandidType, Deserialize, Principal};
+use std::collections::HashMap;
 use crate::models::content::ContentType;
+use crate::utils::stable_map::{DefaultMemory, StableMap};
+use crate::utils::time_utils::TimestampMillis;
 
 // Request DTOs
 #[derive(CandidType, Deserialize, Clone)]
@@ -8,6 +11,13 @@ pub struct LikeContentRequest {
     pub content_type: ContentType,
 }
 
+// The caller's like on a piece of content after a like, unlike or toggle
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LikeState {
+    pub liked: bool,
+    pub like_count: u64,
+}
+
 #[derive(CandidType, Deserialize, Clone)]
 pub struct ReportContentRequest {
     pub content_id: String,
@@ -34,7 +44,7 @@ pub struct InteractionCountsResponse {
 pub struct UserLikeInfo {
     pub principal: Principal,
     pub username: String,
-    pub timestamp: u64,
+    pub timestamp: TimestampMillis,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -74,22 +84,42 @@ pub struct ViewContentRequest {
     pub content_type: ContentType,
 }
 
-// Content report management
+// Reporters kept on one report; later reports only add to its count
+pub const MAX_LISTED_REPORTERS: usize = 100;
+
+// Content report management. A piece of content has at most one pending report; later reporters
+// are added to it rather than opening another.
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct ContentReport {
     pub id: String,
     pub content_id: String,
     pub content_type: ContentType,
+    // First reporter; their reason and description are the ones kept
     pub reporter: Principal,
+    // The first MAX_LISTED_REPORTERS who reported the content while the report was pending, first
+    // reporter included; `report_count` counts everyone
+    #[serde(default)]
+    pub reporters: Vec<Principal>,
+    #[serde(default)]
+    pub report_count: u64,
     pub reason: ReportReason,
     pub description: Option<String>,
     pub status: ReportStatus,
-    pub created_at: u64,
-    pub resolved_at: Option<u64>,
+    pub created_at: TimestampMillis,
+    pub resolved_at: Option<TimestampMillis>,
     pub resolver: Option<Principal>,
     pub resolution_notes: Option<String>,
 }
 
+// Returned to the reporter; `already_reported` is set when they had already reported the content
+// and nothing changed
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReportResult {
+    pub report_id: String,
+    pub already_reported: bool,
+    pub report_count: u64,
+}
+
 #[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
 pub enum ReportStatus {
     Pending,
@@ -103,3 +133,56 @@ pub struct ResolveReportRequest {
     pub status: ReportStatus,
     pub notes: Option<String>,
 }
+
+// Small numeric ids for principals, so sets of principals can be stored as compressed `IdSet`s.
+// Ids are handed out in order and never reused. Both directions are kept in stable memory of their
+// own, so the like sets that hold these ids stay readable even if the state cell is not saved.
+#[derive(CandidType, Deserialize, Default)]
+pub struct PrincipalTable {
+    // The table as stored in the state cell before it had its own memory; moved out by `attach`
+    #[serde(default)]
+    principals: Vec<Principal>,
+    #[serde(default)]
+    by_id: StableMap<u32, Principal>,
+    #[serde(default)]
+    ids: StableMap<Principal, u32>,
+}
+
+impl PrincipalTable {
+    // Back the table with its memories, first moving in the ids kept in the state cell by releases
+    // before it had them
+    pub fn attach(&mut self, by_id: DefaultMemory, ids: DefaultMemory) {
+        for (id, principal) in std::mem::take(&mut self.principals).into_iter().enumerate() {
+            self.by_id.insert(id as u32, principal);
+            self.ids.insert(principal, id as u32);
+        }
+        self.by_id.attach(by_id);
+        self.ids.attach(ids);
+    }
+
+    pub fn intern(&mut self, principal: Principal) -> u32 {
+        if let Some(id) = self.ids.get(&principal) {
+            return *id;
+        }
+        let id = self.by_id.len() as u32;
+        self.by_id.insert(id, principal);
+        self.ids.insert(principal, id);
+        id
+    }
+
+    pub fn id(&self, principal: &Principal) -> Option<u32> {
+        self.ids.get(principal).copied()
+    }
+
+    pub fn principal(&self, id: u32) -> Option<Principal> {
+        self.by_id.get(&id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+}
@@ -0,0 +1,80 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// Invite constants
+pub const INVITE_CODE_LENGTH: usize = 8;
+pub const MAX_INVITE_MINT_BATCH: u32 = 500;
+
+// Platform-wide invite settings
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct InviteSettings {
+    // When enabled, registration requires an unused invite code
+    pub invite_only: bool,
+    // Codes each active user may generate per rolling week
+    pub invites_per_week: u32,
+}
+
+impl Default for InviteSettings {
+    fn default() -> Self {
+        Self {
+            invite_only: false,
+            invites_per_week: 3,
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
+pub enum InviteSource {
+    User,
+    Admin,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct InviteCode {
+    pub code: String,
+    pub created_by: Principal,
+    pub source: InviteSource,
+    pub created_at: TimestampMillis,
+    pub expires_at: Option<TimestampMillis>,
+    pub used_by: Option<Principal>,
+    pub used_at: Option<TimestampMillis>,
+}
+
+#[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
+pub enum InviteStatus {
+    Available,
+    Used,
+    Expired,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UpdateInviteSettingsRequest {
+    pub invite_only: Option<bool>,
+    pub invites_per_week: Option<u32>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct MintInviteCodesRequest {
+    pub count: u32,
+    pub expires_in_days: Option<u64>,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct InviteCodeResponse {
+    pub code: String,
+    pub status: InviteStatus,
+    pub created_at: TimestampMillis,
+    pub expires_at: Option<TimestampMillis>,
+    pub used_by: Option<Principal>,
+    pub used_at: Option<TimestampMillis>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct MyInvitesResponse {
+    pub invites: Vec<InviteCodeResponse>,
+    pub remaining_this_week: u32,
+    // When the next code becomes available once the weekly allowance is used up
+    pub next_available_at: Option<TimestampMillis>,
+}
@@ -0,0 +1,56 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::models::content::{Comment, ContentStatus, ContentType, Post};
+use crate::models::interaction::ContentReport;
+use crate::utils::time_utils::TimestampMillis;
+
+// Legal hold constants
+pub const MAX_LEGAL_HOLD_REFERENCE_LENGTH: usize = 200;
+pub const MAX_LEGAL_HOLD_NOTES_LENGTH: usize = 2000;
+
+#[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
+pub enum LegalHoldReason {
+    CourtOrder,
+    LawEnforcementRequest,
+    Litigation,
+    RegulatoryRequest,
+    Other,
+}
+
+// Content kept unchanged for a legal request. While held, nobody can edit, delete or moderate it,
+// and it is left out of every read except the admin export.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LegalHold {
+    pub content_id: String,
+    pub content_type: ContentType,
+    pub reason: LegalHoldReason,
+    // Case or request number the hold answers
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+    pub placed_by: Principal,
+    pub placed_at: TimestampMillis,
+    // Restored when the hold is released
+    pub previous_status: ContentStatus,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct PlaceLegalHoldRequest {
+    pub content_id: String,
+    pub content_type: ContentType,
+    pub reason: LegalHoldReason,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+}
+
+// Response DTOs
+// Everything kept about held content, for answering the request behind the hold
+#[derive(CandidType, Deserialize, Clone)]
+pub struct LegalHoldExport {
+    pub hold: LegalHold,
+    pub post: Option<Post>,
+    pub comment: Option<Comment>,
+    pub reports: Vec<ContentReport>,
+    pub like_count: u64,
+    pub exported_by: Principal,
+    pub exported_at: TimestampMillis,
+}
@@ -0,0 +1,37 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+pub const DEFAULT_MAINTENANCE_MESSAGE: &str = "The service is undergoing maintenance. Updates are temporarily disabled.";
+pub const MAX_MAINTENANCE_MESSAGE_LENGTH: usize = 500;
+
+// While enabled, every update call except the admin's is rejected
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MaintenanceMode {
+    pub enabled: bool,
+    pub message: String,
+    pub started_at: TimestampMillis,
+    pub started_by: Principal,
+    pub expected_end: Option<TimestampMillis>,
+}
+
+#[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
+pub enum BannerKind {
+    Maintenance,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct SetMaintenanceModeRequest {
+    pub enabled: bool,
+    pub message: Option<String>,
+    pub expected_end: Option<TimestampMillis>,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SystemBanner {
+    pub kind: BannerKind,
+    pub message: String,
+    pub since: TimestampMillis,
+    pub expected_end: Option<TimestampMillis>,
+}
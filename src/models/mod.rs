@@ -8,4 +8,46 @@ pub mod error;
 pub mod tag;
 pub mod cycles;
 pub mod storage;
-pub mod notification;
\ No newline at end of file
+pub mod notification;
+pub mod delegation;
+pub mod creator;
+pub mod task_registry;
+pub mod quiz;
+pub mod invite;
+pub mod anomaly;
+pub mod account_quality;
+pub mod feature_flag;
+pub mod maintenance;
+pub mod shard;
+pub mod classifier;
+pub mod trust;
+pub mod syndication;
+pub mod openchat;
+pub mod identity;
+pub mod token_gate;
+pub mod showcase;
+pub mod profile_visit;
+pub mod translation;
+pub mod sanitizer;
+pub mod emoji;
+pub mod name_policy;
+pub mod companion;
+pub mod timeline;
+pub mod event;
+pub mod index_rebuild;
+pub mod integrity;
+pub mod task_template;
+pub mod treasury;
+pub mod quota;
+pub mod http;
+pub mod impression;
+pub mod legal_hold;
+pub mod anchoring;
+pub mod access_policy;
+pub mod api_version;
+pub mod subscription;
+pub mod activity;
+pub mod follow_graph;
+pub mod approval;
+#[cfg(feature = "demo-data")]
+pub mod demo_data;
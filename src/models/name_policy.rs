@@ -0,0 +1,46 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// Name policy constants
+pub const MAX_DENIED_NAME_TERMS: usize = 500;
+pub const MAX_RESERVED_HANDLES: usize = 500;
+pub const MAX_NAME_TERM_LENGTH: usize = 30;
+
+// Handles that always belong to the platform, compared by their look-alike skeleton
+pub const SYSTEM_RESERVED_HANDLES: [&str; 24] = [
+    "admin", "administrator", "root", "system", "sysadmin", "support", "help", "helpdesk", "moderator", "mod",
+    "staff", "team", "official", "security", "icnews", "square", "icnewssquare", "dfinity", "api", "bot",
+    "null", "undefined", "anonymous", "everyone",
+];
+
+// Starting deny-list; matched anywhere in a username or handle
+pub const DEFAULT_DENIED_NAME_TERMS: [&str; 7] = [
+    "fuck", "shit", "bitch", "asshole", "whore", "slut", "pedophile",
+];
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NamePolicySettings {
+    pub denied_terms: Vec<String>,
+    // Reserved in addition to the system handles
+    pub reserved_handles: Vec<String>,
+    pub updated_by: Option<Principal>,
+    pub updated_at: TimestampMillis,
+}
+
+impl Default for NamePolicySettings {
+    fn default() -> Self {
+        Self {
+            denied_terms: DEFAULT_DENIED_NAME_TERMS.iter().map(|term| term.to_string()).collect(),
+            reserved_handles: Vec::new(),
+            updated_by: None,
+            updated_at: 0,
+        }
+    }
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UpdateNamePolicyRequest {
+    pub denied_terms: Option<Vec<String>>,
+    pub reserved_handles: Option<Vec<String>>,
+}
@@ -1,4 +1,5 @@
 use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
 
 // Notification structures
 #[derive(CandidType, Deserialize, Clone)]
@@ -9,8 +10,40 @@ pub struct UserNotification {
     pub content: String,
     pub related_user: Option<Principal>,
     pub related_content_id: Option<String>,
-    pub created_at: u64,
+    pub created_at: TimestampMillis,
     pub read: bool,
+    // Position in the user's notification sequence; moves to the end again when the notification is read
+    #[serde(default)]
+    pub seq: u64,
+}
+
+// Most notifications returned by one get_notifications_since call
+pub const MAX_NOTIFICATIONS_SINCE: usize = 100;
+pub const DEFAULT_MAX_NOTIFICATIONS_PER_USER: u64 = 100;
+pub const MAX_NOTIFICATIONS_PER_USER_LIMIT: u64 = 1_000;
+
+// How many notifications each user keeps, and for how long; the oldest go first
+#[derive(CandidType, Deserialize, Clone)]
+pub struct NotificationRetentionSettings {
+    pub max_per_user: u64,
+    pub max_age_days: Option<u64>,  // None keeps notifications until the count pushes them out
+}
+
+impl Default for NotificationRetentionSettings {
+    fn default() -> Self {
+        Self {
+            max_per_user: DEFAULT_MAX_NOTIFICATIONS_PER_USER,
+            max_age_days: None,
+        }
+    }
+}
+
+// Per-user notification sequence
+#[derive(CandidType, Deserialize, Clone, Default)]
+pub struct NotificationSyncState {
+    pub last_seq: u64,
+    // Highest sequence number of a notification dropped from the retained list
+    pub pruned_through: u64,
 }
 
 #[derive(CandidType, Deserialize, Clone, PartialEq)]
@@ -23,6 +56,7 @@ pub enum NotificationType {
     System,
     Achievement,
     Custom,
+    NewPost,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -32,8 +66,9 @@ pub struct NotificationResponse {
     pub content: String,
     pub related_user: Option<Principal>,
     pub related_content_id: Option<String>,
-    pub created_at: u64,
+    pub created_at: TimestampMillis,
     pub read: bool,
+    pub seq: u64,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -44,3 +79,32 @@ pub struct NotificationsResponse {
     pub has_more: bool,
     pub next_cursor: Option<String>,
 }
+
+// Notifications created or changed after a sequence number, oldest change first
+#[derive(CandidType, Deserialize, Clone)]
+pub struct NotificationsSinceResponse {
+    pub notifications: Vec<NotificationResponse>,
+    pub latest_seq: u64,  // Pass back as `since` to continue
+    pub has_more: bool,
+    pub resync_required: bool,  // Notifications the caller may hold were dropped; refetch the full list
+    pub unread_count: u64,
+}
+
+// New-post alerts sent per heartbeat, across all queued posts
+pub const POST_ALERT_BATCH_SIZE: usize = 1_000;
+// Characters of the post quoted in a new-post alert
+pub const POST_ALERT_EXCERPT_LENGTH: usize = 80;
+
+// New-post alerts still to send for a post, resuming after the last subscriber alerted
+#[derive(CandidType, Deserialize, Clone)]
+pub struct PostAlertJob {
+    pub post_id: String,
+    pub author: Principal,
+    pub resume_after: Option<Principal>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct PostAlertState {
+    pub author: Principal,
+    pub enabled: bool,
+}
@@ -0,0 +1,88 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// OpenChat bridge constants
+pub const MAX_OPENCHAT_TOPIC_ROUTES: usize = 50;
+pub const MAX_OPENCHAT_MESSAGE_CHARS: usize = 1_000;
+// Community canister method the bot posts through
+pub const OPENCHAT_SEND_MESSAGE_METHOD: &str = "bot_send_message";
+
+// Mirrors trending posts into an OpenChat community through its bot API
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct OpenChatBridgeSettings {
+    pub enabled: bool,
+    pub community_canister_id: Option<Principal>,
+    // Posts below this trending score are not mirrored
+    pub min_trending_score: f64,
+    // Most posts mirrored per topic on each run
+    pub max_posts_per_topic: u32,
+    // Only posts created within this window are considered
+    pub lookback_hours: u64,
+}
+
+impl Default for OpenChatBridgeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            community_canister_id: None,
+            min_trending_score: 10.0,
+            max_posts_per_topic: 3,
+            lookback_hours: 24,
+        }
+    }
+}
+
+// Community channel that receives the trending posts of one tag
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct OpenChatTopicRoute {
+    pub tag: String,
+    pub channel_id: u128,
+    pub added_by: Principal,
+    pub added_at: TimestampMillis,
+}
+
+// Post already mirrored into OpenChat, keyed by post ID so it is sent only once
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MirroredMessage {
+    pub post_id: String,
+    pub tag: String,
+    pub channel_id: u128,
+    pub message_id: u128,
+    pub mirrored_at: TimestampMillis,
+}
+
+// Bot API payloads
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct OpenChatSendMessageArgs {
+    pub channel_id: u128,
+    // Derived from the post and channel, so a retried send cannot create a duplicate
+    pub message_id: u128,
+    pub text: String,
+    pub block_level_markdown: bool,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum OpenChatSendMessageResponse {
+    Success,
+    NotAuthorized,
+    ChannelNotFound,
+    Frozen,
+    ThrottledMessage,
+    InternalError(String),
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UpdateOpenChatSettingsRequest {
+    pub enabled: Option<bool>,
+    pub community_canister_id: Option<Principal>,
+    pub min_trending_score: Option<f64>,
+    pub max_posts_per_topic: Option<u32>,
+    pub lookback_hours: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct SetOpenChatTopicRouteRequest {
+    pub tag: String,
+    pub channel_id: u128,
+}
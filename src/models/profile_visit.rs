@@ -0,0 +1,35 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::models::display::PaginatedResponse;
+use crate::models::user::UserSocialResponse;
+use crate::utils::time_utils::{TimestampMillis, MILLIS_PER_DAY};
+
+// Profile visit constants
+pub const PROFILE_VISIT_WINDOW_MS: u64 = 30 * MILLIS_PER_DAY;
+pub const MAX_PROFILE_VISITS_PER_USER: usize = 1000;
+// A visitor counts once per profile in this window
+pub const PROFILE_VISIT_DEDUP_MS: u64 = MILLIS_PER_DAY;
+
+// A recorded profile view; `visitor` is only kept when both users share profile visits
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ProfileVisit {
+    pub visitor: Option<Principal>,
+    pub visited_at: TimestampMillis,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct ProfileVisitor {
+    pub user: UserSocialResponse,
+    pub visited_at: TimestampMillis,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct ProfileVisitorsResponse {
+    // Whether the caller shares profile visits; when off only the counts are returned
+    pub sharing_enabled: bool,
+    // Every visit in the window, including visitors who do not share theirs
+    pub total_visits: u64,
+    pub anonymous_visits: u64,
+    pub window_days: u64,
+    pub visitors: PaginatedResponse<ProfileVisitor>,
+}
@@ -0,0 +1,95 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// Quiz constants
+pub const MAX_QUIZ_QUESTIONS: usize = 20;
+pub const MIN_QUIZ_OPTIONS: usize = 2;
+pub const MAX_QUIZ_OPTIONS: usize = 6;
+pub const MAX_QUIZ_TEXT_LENGTH: usize = 500;
+pub const MAX_QUIZ_POINTS_PER_ANSWER: u64 = 100;
+pub const MAX_QUIZ_SUBMISSIONS_PER_USER: usize = 100;
+// Task ID recorded in user tasks when the daily quiz is submitted
+pub const DAILY_QUIZ_TASK_ID: &str = "daily_quiz";
+
+// Stored question; the correct option is only kept as a salted hash
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct QuizQuestion {
+    pub question: String,
+    pub options: Vec<String>,
+    pub correct_option_hash: String,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Quiz {
+    pub id: String,
+    pub title: String,
+    pub questions: Vec<QuizQuestion>,
+    pub points_per_correct_answer: u64,
+    pub min_correct_to_pass: u32,
+    pub salt: String,
+    pub is_active: bool,
+    pub created_by: Principal,
+    pub created_at: TimestampMillis,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct QuizSubmission {
+    pub quiz_id: String,
+    // Start of the user's local day the quiz was taken for
+    pub day_start: TimestampMillis,
+    pub answers: Vec<u32>,
+    pub correct_count: u32,
+    pub points_earned: u64,
+    pub submitted_at: TimestampMillis,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct QuizQuestionInput {
+    pub question: String,
+    pub options: Vec<String>,
+    pub correct_option: u32,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct CreateQuizRequest {
+    pub title: String,
+    pub questions: Vec<QuizQuestionInput>,
+    pub points_per_correct_answer: u64,
+    pub min_correct_to_pass: u32,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct SubmitQuizAnswersRequest {
+    pub quiz_id: String,
+    // Selected option index per question, in question order
+    pub answers: Vec<u32>,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct QuizQuestionResponse {
+    pub question: String,
+    pub options: Vec<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct DailyQuizResponse {
+    pub quiz_id: String,
+    pub title: String,
+    pub questions: Vec<QuizQuestionResponse>,
+    pub points_per_correct_answer: u64,
+    pub min_correct_to_pass: u32,
+    pub day_start: TimestampMillis,
+    pub already_submitted: bool,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct QuizResultResponse {
+    pub quiz_id: String,
+    pub correct_count: u32,
+    pub total_questions: u32,
+    pub passed: bool,
+    pub points_earned: u64,
+    pub total_points: u64,
+}
@@ -0,0 +1,50 @@
+use candid::{CandidType, Deserialize};
+
+use crate::models::trust::TrustLevel;
+
+// Storage quota constants
+// Counted for every stored post or comment on top of its text, for ids, timestamps and other metadata
+pub const STORED_ITEM_OVERHEAD_BYTES: u64 = 256;
+const KIB: u64 = 1_024;
+const MIB: u64 = 1_024 * KIB;
+
+// Approximate bytes each trust level may keep stored across its posts and comments
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct StorageQuotaSettings {
+    pub new: u64,
+    pub basic: u64,
+    pub member: u64,
+    pub regular: u64,
+}
+
+impl Default for StorageQuotaSettings {
+    fn default() -> Self {
+        Self {
+            new: 512 * KIB,
+            basic: 2 * MIB,
+            member: 10 * MIB,
+            regular: 25 * MIB,
+        }
+    }
+}
+
+impl StorageQuotaSettings {
+    pub fn quota(&self, level: TrustLevel) -> u64 {
+        match level {
+            TrustLevel::New => self.new,
+            TrustLevel::Basic => self.basic,
+            TrustLevel::Member => self.member,
+            TrustLevel::Regular => self.regular,
+        }
+    }
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct StorageUsageResponse {
+    pub bytes_used: u64,
+    pub quota_bytes: u64,
+    pub trust_level: TrustLevel,
+    // True when an admin override replaces the trust level's quota
+    pub overridden: bool,
+}
@@ -1,5 +1,6 @@
 use candid::{CandidType, Deserialize, Principal};
 use std::collections::HashMap;
+use crate::utils::time_utils::TimestampMillis;
 
 // User rewards and tasks
 #[derive(CandidType, Deserialize, Clone)]
@@ -7,20 +8,20 @@ pub struct UserRewards {
     pub principal: Principal,
     pub points: u64,
     pub points_history: Vec<PointsTransaction>,
-    pub last_claim_date: Option<u64>,
+    pub last_claim_date: Option<TimestampMillis>,
     // Note: consecutive_daily_logins field has been moved to daily_checkin_task canister
     // and is now tracked as consecutive_days there
     pub transactions: Vec<PointsTransaction>,
-    pub last_updated: u64,
+    pub last_updated: TimestampMillis,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
 pub struct UserTasks {
     pub principal: Principal,
     pub completed_tasks: HashMap<String, u64>, // task_id -> completion timestamp
-    pub daily_tasks_reset: u64, // Timestamp when daily tasks were last reset
-    pub last_check_in: Option<u64>, // Last daily check-in timestamp
-    pub last_updated: u64,
+    pub daily_tasks_reset: TimestampMillis, // Timestamp when daily tasks were last reset
+    pub last_check_in: Option<TimestampMillis>, // Last daily check-in timestamp
+    pub last_updated: TimestampMillis,
 }
 
 // Task configuration
@@ -39,7 +40,7 @@ pub struct TaskConfig {
 pub struct TaskVerificationRequest {
     pub user: Principal,
     pub task_id: String,
-    pub timestamp: u64,
+    pub timestamp: TimestampMillis,
     pub proof: Option<String>,
 }
 
@@ -48,7 +49,7 @@ pub struct TaskVerificationRequest {
 pub struct VerificationData {
     pub task_id: String,
     pub points_earned: u64,
-    pub completion_timestamp: u64,
+    pub completion_timestamp: TimestampMillis,
     pub metadata: HashMap<String, String>,
 }
 
@@ -69,12 +70,24 @@ pub struct TaskDefinition {
     pub points: u64,
     pub task_type: TaskType,
     pub completion_criteria: String,
-    pub expiration_time: Option<u64>,
-    pub created_at: u64,
-    pub updated_at: u64,
+    pub expiration_time: Option<TimestampMillis>,
+    pub created_at: TimestampMillis,
+    pub updated_at: TimestampMillis,
     pub is_active: bool,
     pub requirements: Option<TaskRequirements>,
     pub canister_id: Principal,
+    // Template this task was generated from, if any
+    #[serde(default)]
+    pub template_id: Option<String>,
+    // Dates the period of a template instance starts and ends on, as UTC midnights. A user can
+    // complete the instance while their local date is in the period.
+    #[serde(default)]
+    pub period_start: Option<TimestampMillis>,
+    #[serde(default)]
+    pub period_end: Option<TimestampMillis>,
+    // Segments the task is shown to; empty for everyone
+    #[serde(default)]
+    pub audience: Vec<TaskAudience>,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -100,8 +113,10 @@ pub struct TaskResponse {
     pub task_type: TaskType,
     pub completion_criteria: String,
     pub is_completed: bool,
-    pub expiration_time: Option<u64>,
-    pub created_at: u64,
+    pub expiration_time: Option<TimestampMillis>,
+    pub created_at: TimestampMillis,
+    // Canister that verifies the task (this canister for built-in tasks)
+    pub canister_id: Principal,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -120,10 +135,12 @@ pub struct CreateTaskRequest {
     pub points_reward: u64,
     pub task_type: TaskType,
     pub canister_id: Principal,
-    pub start_time: Option<u64>,
-    pub end_time: Option<u64>,
+    pub start_time: Option<TimestampMillis>,
+    pub end_time: Option<TimestampMillis>,
     pub completion_criteria: String,
     pub requirements: Option<TaskRequirements>,
+    // None or empty shows the task to everyone
+    pub audience: Option<Vec<TaskAudience>>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Debug)]
@@ -172,9 +189,11 @@ pub struct UpdateTaskRequest {
     pub task_type: TaskType,
     pub completion_criteria: String,
     pub canister_id: Principal,
-    pub start_time: Option<u64>,
-    pub end_time: Option<u64>,
+    pub start_time: Option<TimestampMillis>,
+    pub end_time: Option<TimestampMillis>,
     pub requirements: Option<TaskRequirements>,
+    // None keeps the current audience
+    pub audience: Option<Vec<TaskAudience>>,
 }
 
 
@@ -240,17 +259,67 @@ pub enum TaskType {
     Special
 }
 
+// User segment a task can be targeted at. A user sees a targeted task when they belong to any of its segments.
+#[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
+pub enum TaskAudience {
+    // Registered within the last NEW_USER_TASK_DAYS days
+    NewUsers,
+    Creators,
+    // Neither logged in nor active for at least INACTIVE_USER_DAYS days, including for that long
+    // again after they come back
+    InactiveUsers,
+    // Following the tag; given with or without '#' and stored with it, like followed topics
+    TagFollowers(String),
+}
+
+// Points expiry policy; off until an admin turns it on
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PointsExpirySettings {
+    pub enabled: bool,
+    // Points expire this many days after they were earned; spending uses up the oldest points first
+    pub expiry_days: u64,
+    // Users are notified this many days before points expire
+    pub warning_days: u64,
+}
+
+impl Default for PointsExpirySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            expiry_days: 365,
+            warning_days: 30,
+        }
+    }
+}
+
+// Progress of the daily expiry sweep over every user's points
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct PointsExpiryState {
+    pub running: bool,
+    // Last user swept by the running pass
+    pub cursor: Option<Principal>,
+    pub last_started_at: TimestampMillis,
+    // Totals of the running or last pass
+    pub points_expired: u64,
+    pub users_warned: u64,
+}
+
 #[derive(CandidType, Deserialize, Clone)]
 pub struct PointsTransaction {
     pub amount: i64,
     pub reason: String,
-    pub timestamp: u64,
+    pub timestamp: TimestampMillis,
     pub reference_id: Option<String>, // Content ID or task ID
     pub points: u64,
 }
 
 // Constants
-pub const SECONDS_IN_DAY: u64 = 86400;
 pub const DAILY_CHECK_IN_POINTS: u64 = 10;
 pub const MAX_CONSECUTIVE_BONUS_DAYS: u64 = 7;
 pub const CONSECUTIVE_DAYS_BONUS_MULTIPLIER: u64 = 2;
+pub const NEW_USER_TASK_DAYS: u64 = 7;
+pub const INACTIVE_USER_DAYS: u64 = 7;
+// Longest expiry an admin can set, ten years
+pub const MAX_POINTS_EXPIRY_DAYS: u64 = 3650;
+pub const POINTS_EXPIRY_BATCH_SIZE: usize = 500;
+pub const POINTS_EXPIRY_REASON: &str = "Points expired";
@@ -0,0 +1,37 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// HTML sanitizer constants
+pub const MAX_ALLOWED_HTML_TAGS: usize = 60;
+// Posts and comments re-rendered per heartbeat after the allowed tags change
+pub const RERENDER_BATCH_SIZE: usize = 200;
+
+// Tags kept in post and comment HTML unless admins configure otherwise
+pub const DEFAULT_ALLOWED_HTML_TAGS: [&str; 43] = [
+    "a", "abbr", "b", "blockquote", "br", "caption", "code", "del", "div", "em", "figcaption", "figure",
+    "h1", "h2", "h3", "h4", "h5", "h6", "hr", "i", "img", "ins", "li", "mark", "ol", "p", "pre", "s",
+    "small", "source", "span", "strong", "sub", "sup", "table", "tbody", "td", "th", "thead", "tr", "u", "ul", "video",
+];
+
+// Tags that can run script, load foreign documents or take input; never allowed
+pub const FORBIDDEN_HTML_TAGS: [&str; 22] = [
+    "script", "style", "iframe", "frame", "frameset", "object", "embed", "applet", "form", "input", "button",
+    "textarea", "select", "option", "link", "meta", "base", "svg", "math", "template", "noscript", "title",
+];
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct HtmlSanitizerSettings {
+    pub allowed_tags: Vec<String>,
+    pub updated_by: Option<Principal>,
+    pub updated_at: TimestampMillis,
+}
+
+impl Default for HtmlSanitizerSettings {
+    fn default() -> Self {
+        Self {
+            allowed_tags: DEFAULT_ALLOWED_HTML_TAGS.iter().map(|tag| tag.to_string()).collect(),
+            updated_by: None,
+            updated_at: 0,
+        }
+    }
+}
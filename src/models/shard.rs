@@ -0,0 +1,44 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::models::content::PostResponse;
+use crate::models::discovery::SearchResultResponse;
+use crate::utils::time_utils::TimestampMillis;
+
+// Content shard constants
+pub const MAX_CONTENT_SHARDS: usize = 16;
+pub const MAX_CONTENT_SHARD_NAME_LENGTH: usize = 100;
+// Largest offset + limit a fan-out read will request from each shard
+pub const MAX_SHARDED_READ_WINDOW: usize = 200;
+
+// Peer canister running this same code and holding a slice of the content
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ContentShard {
+    pub canister_id: Principal,
+    pub name: String,
+    pub enabled: bool,
+    pub registered_by: Principal,
+    pub registered_at: TimestampMillis,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct RegisterContentShardRequest {
+    pub canister_id: Principal,
+    pub name: String,
+}
+
+// Response DTOs
+// Globally sorted page merged from this canister and every enabled shard
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ShardedPostsResponse {
+    pub posts: Vec<PostResponse>,
+    pub total: u64,
+    pub next_offset: usize,
+    // Shards that failed to answer; their content is missing from this page
+    pub unavailable_shards: Vec<Principal>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ShardedSearchResponse {
+    pub results: Vec<SearchResultResponse>,
+    pub unavailable_shards: Vec<Principal>,
+}
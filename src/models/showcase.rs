@@ -0,0 +1,54 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::models::identity::Icrc1Account;
+use crate::utils::time_utils::{TimestampMillis, MILLIS_PER_DAY};
+
+// NFT showcase constants
+pub const MAX_SHOWCASE_NFTS: usize = 12;
+pub const MAX_NFT_TOKEN_ID_LENGTH: usize = 100;
+// Showcased NFTs are checked with their collection again once a day, a batch per full heartbeat run
+pub const SHOWCASE_REVERIFY_AFTER: TimestampMillis = MILLIS_PER_DAY;
+pub const SHOWCASE_REVERIFY_BATCH: usize = 20;
+
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum NftStandard {
+    Ext,
+    Icrc7,
+}
+
+// NFT shown on a profile after its ownership was checked with the collection canister
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ShowcaseNft {
+    pub canister_id: Principal,
+    pub standard: NftStandard,
+    // EXT token identifier text, or the ICRC-7 token ID as a decimal string
+    pub token_id: String,
+    // Principal that held the token: the user or one of their linked accounts
+    pub owner: Principal,
+    pub verified_at: TimestampMillis,
+}
+
+// EXT `bearer` result
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum ExtCommonError {
+    InvalidToken(String),
+    Other(String),
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum ExtBearerResponse {
+    #[serde(rename = "ok")]
+    Ok(String),
+    #[serde(rename = "err")]
+    Err(ExtCommonError),
+}
+
+// ICRC-7 `icrc7_owner_of` returns one optional account per requested token
+pub type Icrc7OwnerOfResponse = Vec<Option<Icrc1Account>>;
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct AddShowcaseNftRequest {
+    pub canister_id: Principal,
+    pub standard: NftStandard,
+    pub token_id: String,
+}
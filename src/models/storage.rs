@@ -1,15 +1,56 @@
 use std::cell::RefCell;
-use std::collections::{HashMap, HashSet, BTreeMap};
+use std::collections::{HashMap, HashSet, BTreeMap, BTreeSet, VecDeque};
 use candid::{CandidType, Deserialize, Principal};
 
-use crate::models::reward::TaskDefinition;
-use crate::models::interaction::ContentReport;
-use crate::models::content::{NewsReference, Post, Comment};
-use crate::models::user::{User, UserProfile, UserStats};
+use crate::models::treasury::{MintBudget, PointsTreasury};
+use crate::models::reward::{PointsExpirySettings, PointsExpiryState, TaskDefinition};
+use crate::models::interaction::{ContentReport, PrincipalTable};
+use crate::models::content::{ArticleOutline, ArticleSummary, ContentSchedule, NewsReference, Post, Comment, RenderedContent};
+use crate::models::user::{User, UserCard, UserProfile, UserStats, UserModerationRecord, AccountDeactivation, UserTimezone};
 use crate::models::reward::{UserRewards, UserTasks};
-use crate::models::notification::UserNotification;
+use crate::models::subscription::{ThreadActivity, ThreadDigestJob, ThreadSubscriptionState};
+use crate::models::notification::{NotificationRetentionSettings, NotificationSyncState, PostAlertJob, UserNotification};
+use crate::models::delegation::{PostingDelegation, DelegatedActionRecord};
+use crate::models::creator::{CreatorApplication, CreatorPromotionThresholds};
+use crate::models::task_registry::{TaskCanisterRegistration, TaskWebhookDelivery};
+use crate::models::quiz::{Quiz, QuizSubmission};
+use crate::models::invite::{InviteCode, InviteSettings};
+use crate::models::tag::Tag;
+use crate::models::account_quality::{AccountQuality, RecentAction};
+use crate::utils::time_utils::TimestampMillis;
+use crate::utils::id_set::IdSet;
+use crate::utils::stable_map::StableMap;
+use crate::models::anomaly::{AnomalyDetectionSettings, EngagementAnomaly, EngagementEvent};
+use crate::models::discovery::{FeedComposition, NewcomerBoostSettings, SimilarUser, SimilarityRefreshState, TrendingMaintenanceStats};
+use crate::models::feature_flag::{FeatureFlag, FeatureFlagState};
+use crate::models::maintenance::MaintenanceMode;
+use crate::models::shard::ContentShard;
+use crate::models::classifier::{ClassifierSettings, ClassifierVerdict};
+use crate::models::trust::TrustSettings;
+use crate::models::approval::{ContentApprovalSettings, PendingApproval};
+use crate::models::quota::StorageQuotaSettings;
+use crate::models::cycles::FreezeGuardState;
+use crate::models::syndication::SyndicationPartner;
+use crate::models::identity::{LinkedAccount, PendingAccountLink};
+use crate::models::token_gate::CachedTokenBalance;
+use crate::models::showcase::ShowcaseNft;
+use crate::models::profile_visit::ProfileVisit;
+use crate::models::translation::{CachedTranslation, TranslationSettings};
+use crate::models::sanitizer::HtmlSanitizerSettings;
+use crate::models::emoji::CustomEmoji;
+use crate::models::name_policy::NamePolicySettings;
+use crate::models::timeline::TimelineSettings;
+use crate::models::legal_hold::LegalHold;
+use crate::models::anchoring::{ContentAnchor, ContentHashRecord};
+use crate::models::impression::{FeedSurface, Impression, ImpressionCounts, ImpressionSettings, RankingArm, ServedPost};
+use crate::models::event::{DailyTaskProgress, InteractionEventLog};
+use crate::models::index_rebuild::IndexRebuildState;
+use crate::models::integrity::IntegrityAuditState;
+use crate::models::task_template::TaskTemplate;
+use crate::models::companion::CompanionCanister;
+use crate::models::openchat::{MirroredMessage, OpenChatBridgeSettings, OpenChatTopicRoute};
 
-#[derive(CandidType, Deserialize, Clone)]
+#[derive(CandidType, Deserialize)]
 pub struct Storage {
     // Admin and managers
     pub admin: Option<Principal>,
@@ -17,35 +58,122 @@ pub struct Storage {
     pub managers: Option<HashSet<Principal>>,
     pub bark_api_key: String,
     
-    // Content storage
-    pub posts: HashMap<String, Post>,
-    pub comments: HashMap<String, Comment>,
+    // Content storage; these, users, user_profiles, like_sets and user_rewards are kept in stable memory and
+    // serialize as empty once attached, see storage::memory
+    pub posts: StableMap<String, Post>,
+    pub comments: StableMap<String, Comment>,
+    // Original post id -> ids of its cross-posts
+    #[serde(default)]
+    pub cross_posts: HashMap<String, Vec<String>>,
+    // Embargo and sunset times by post id, applied by the heartbeat
+    #[serde(default)]
+    pub content_schedules: HashMap<String, ContentSchedule>,
+    // (due time, post id) for every embargo and sunset in content_schedules, soonest first
+    #[serde(default)]
+    pub schedule_queue: BTreeSet<(TimestampMillis, String)>,
     
     // User data
-    pub users: HashMap<Principal, User>,
+    pub users: StableMap<Principal, User>,
     #[serde(default)]
-    pub user_profiles: Option<HashMap<Principal, UserProfile>>,
+    pub user_profiles: Option<StableMap<Principal, UserProfile>>,
     #[serde(default)]
     pub user_stats: Option<HashMap<Principal, UserStats>>,
+    // Follower -> followee -> when the follow was made, for follows made since this was recorded
+    #[serde(default)]
+    pub follow_times: HashMap<Principal, HashMap<Principal, TimestampMillis>>,
+    // Last profile whose follow counters were checked; the next run resumes after it
+    #[serde(default)]
+    pub follow_repair_cursor: Option<Principal>,
     
     // Content indexing
-    pub user_posts: HashMap<Principal, Vec<String>>,
-    pub user_comments: HashMap<Principal, Vec<String>>,
+    pub user_posts: BTreeMap<Principal, Vec<String>>,
+    pub user_comments: BTreeMap<Principal, Vec<String>>,
+    // Post id -> its top-level comments, oldest first
+    #[serde(default)]
+    pub post_comments: HashMap<String, Vec<String>>,
     
     // Interactions
-    pub likes: HashMap<String, HashSet<Principal>>,
+    // Likes as stored before like sets were compacted; emptied by `migrate_like_sets`
+    #[serde(default, rename = "likes")]
+    pub legacy_likes: HashMap<String, HashSet<Principal>>,
+    // Content id -> ids of the principals who liked it, from `principal_ids`
+    #[serde(default)]
+    pub like_sets: StableMap<String, IdSet>,
+    #[serde(default)]
+    pub principal_ids: PrincipalTable,
+
+    // Precomputed most similar users for collaborative recommendations
+    #[serde(default)]
+    pub similar_users: HashMap<Principal, Vec<SimilarUser>>,
+    #[serde(default)]
+    pub similarity_refresh: SimilarityRefreshState,
+
+    // Interaction events and the consumers' progress through them
+    #[serde(default)]
+    pub interaction_events: InteractionEventLog,
+    // Derived from the event log by the TaskProgress consumer
+    #[serde(default)]
+    pub task_progress: HashMap<Principal, DailyTaskProgress>,
+
+    // Admin rebuild of indexes and counters from the primary records, run across several calls
+    #[serde(default)]
+    pub index_rebuild: Option<IndexRebuildState>,
+
+    // Scheduled integrity audit in progress and the last completed report
+    #[serde(default)]
+    pub integrity_audit: IntegrityAuditState,
     pub reports: HashMap<String, ContentReport>,
+    // Pending report id by content id
+    #[serde(default)]
+    pub open_reports: HashMap<String, String>,
     
     // Discovery
     pub trending_topics: BTreeMap<String, u64>, // hashtag -> count
     pub previous_trending_topics: BTreeMap<String, u64>, // previous period hashtag -> count
     pub trending_content: Vec<String>, // content IDs
+    #[serde(default)]
+    pub trending_maintenance: Option<TrendingMaintenanceStats>,
     
     // Rewards and tasks
-    pub user_rewards: HashMap<Principal, UserRewards>,
+    pub user_rewards: StableMap<Principal, UserRewards>,
     pub user_tasks: HashMap<Principal, UserTasks>,
     #[serde(default)]
     pub tasks: Option<HashMap<String, TaskDefinition>>,
+    #[serde(default)]
+    pub points_expiry_settings: Option<PointsExpirySettings>,
+    #[serde(default)]
+    pub points_expiry: PointsExpiryState,
+    // When each user was last warned about expiring points
+    #[serde(default)]
+    pub points_expiry_warnings: HashMap<Principal, TimestampMillis>,
+    #[serde(default)]
+    pub points_treasury: PointsTreasury,
+    #[serde(default)]
+    pub mint_budgets: HashMap<Principal, MintBudget>,
+    // Replies hidden along with the post or comment they belong to, by its id; restored with it
+    #[serde(default)]
+    pub cascade_hidden_comments: HashMap<String, Vec<String>>,
+    // Posts and comments on legal hold, by content id
+    #[serde(default)]
+    pub legal_holds: HashMap<String, LegalHold>,
+    // Post hashes recorded since the last daily anchor, oldest first
+    #[serde(default)]
+    pub pending_content_hashes: Vec<ContentHashRecord>,
+    // Daily anchors, oldest first
+    #[serde(default)]
+    pub content_anchors: Vec<ContentAnchor>,
+    // Hash recipe every post has been hashed with; the heartbeat rehashes posts while it is behind
+    // CONTENT_HASH_RECIPE, resuming after `content_hash_cursor`
+    #[serde(default)]
+    pub content_hash_recipe: u32,
+    #[serde(default)]
+    pub content_hash_cursor: Option<String>,
+    // Set on upgrade until the heartbeat has synchronized every user's records, resuming after
+    // `user_sync_cursor`
+    #[serde(default)]
+    pub user_sync_pending: bool,
+    #[serde(default)]
+    pub user_sync_cursor: Option<Principal>,
     
     // System data
     #[serde(default)]
@@ -57,7 +185,343 @@ pub struct Storage {
     #[serde(default)]
     pub terms_of_service: Option<String>,
     pub heartbeat_interval_hours: u64, // Configurable heartbeat interval in hours
+    #[serde(default)]
+    pub freeze_guard: FreezeGuardState,
     
     // Notifications
-    pub user_notifications: HashMap<Principal, Vec<UserNotification>>,
+    // Per-user ring buffer, oldest first
+    pub user_notifications: HashMap<Principal, VecDeque<UserNotification>>,
+    #[serde(default)]
+    pub notification_sync: HashMap<Principal, NotificationSyncState>,
+    #[serde(default)]
+    pub notification_retention: Option<NotificationRetentionSettings>,
+    // Followers who turned on new-post alerts, by author, and the alerts still to send
+    #[serde(default)]
+    pub post_alert_subscribers: HashMap<Principal, BTreeSet<Principal>>,
+    #[serde(default)]
+    pub post_alert_queue: VecDeque<PostAlertJob>,
+    // Thread subscribers by post, comments waiting for the next digest, and the digests due
+    #[serde(default)]
+    pub content_subscriptions: HashMap<String, BTreeMap<Principal, ThreadSubscriptionState>>,
+    #[serde(default)]
+    pub thread_activity: HashMap<String, ThreadActivity>,
+    #[serde(default)]
+    pub thread_digest_queue: VecDeque<ThreadDigestJob>,
+    
+    // Delegated posting (org -> delegate -> grant) and per-org audit trail
+    #[serde(default)]
+    pub posting_delegations: HashMap<Principal, HashMap<Principal, PostingDelegation>>,
+    #[serde(default)]
+    pub delegation_audit_log: HashMap<Principal, Vec<DelegatedActionRecord>>,
+
+    // Creator program
+    #[serde(default)]
+    pub creator_applications: HashMap<Principal, CreatorApplication>,
+    #[serde(default)]
+    pub creator_promotion_thresholds: Option<CreatorPromotionThresholds>,
+    // Last user checked for auto-promotion; the next run resumes after it
+    #[serde(default)]
+    pub creator_promotion_cursor: Option<Principal>,
+
+    // Per-user moderation history (status/role changes and moderated content)
+    #[serde(default)]
+    pub user_moderation_log: HashMap<Principal, Vec<UserModerationRecord>>,
+
+    // Self-deactivated accounts
+    #[serde(default)]
+    pub account_deactivations: HashMap<Principal, AccountDeactivation>,
+
+    // Per-user timezone offsets for local day boundaries
+    #[serde(default)]
+    pub user_timezones: HashMap<Principal, UserTimezone>,
+
+    // External task canisters and the task definitions last synced from them
+    #[serde(default)]
+    pub task_canisters: HashMap<Principal, TaskCanisterRegistration>,
+    #[serde(default)]
+    pub external_tasks: HashMap<Principal, Vec<TaskDefinition>>,
+    // Secrets signing the completion receipts sent to task canisters, and receipts awaiting delivery
+    #[serde(default)]
+    pub task_webhook_secrets: HashMap<Principal, String>,
+    #[serde(default)]
+    pub task_webhook_deliveries: Vec<TaskWebhookDelivery>,
+
+    // Recurring task templates, keyed by template ID
+    #[serde(default)]
+    pub task_templates: HashMap<String, TaskTemplate>,
+    // When users last came back after being away long enough to count as inactive
+    #[serde(default)]
+    pub returning_users: HashMap<Principal, TimestampMillis>,
+    // When users last did something that went into the interaction event log
+    #[serde(default)]
+    pub last_active: HashMap<Principal, TimestampMillis>,
+
+    // Quiz definitions and per-user quiz submissions
+    #[serde(default)]
+    pub quizzes: HashMap<String, Quiz>,
+    #[serde(default)]
+    pub quiz_submissions: HashMap<Principal, Vec<QuizSubmission>>,
+
+    // Invite-gated registration
+    #[serde(default)]
+    pub invite_settings: Option<InviteSettings>,
+    #[serde(default)]
+    pub invite_codes: HashMap<String, InviteCode>,
+
+    // Tag metadata and follower counts, keyed by normalized hashtag
+    #[serde(default)]
+    pub tags: HashMap<String, Tag>,
+    // Tag moderators, who can moderate content only within their tags
+    #[serde(default)]
+    pub tag_moderators: HashMap<String, HashSet<Principal>>,
+
+    // Engagement anomaly detection: recent likes/reports per content, raised alerts and active throttles
+    #[serde(default)]
+    pub anomaly_settings: Option<AnomalyDetectionSettings>,
+    #[serde(default)]
+    pub recent_engagement: HashMap<String, Vec<EngagementEvent>>,
+    #[serde(default)]
+    pub engagement_anomalies: HashMap<String, EngagementAnomaly>,
+    #[serde(default)]
+    pub throttled_content: HashMap<String, TimestampMillis>,
+
+    // Sampled record of the posts served to each user, with clicks matched from views, and the
+    // running totals per surface, per post and per ranking arm
+    #[serde(default)]
+    pub impression_settings: Option<ImpressionSettings>,
+    #[serde(default)]
+    pub impressions: HashMap<Principal, Vec<Impression>>,
+    #[serde(default)]
+    pub impression_counts: HashMap<FeedSurface, ImpressionCounts>,
+    #[serde(default)]
+    pub post_impression_counts: HashMap<String, ImpressionCounts>,
+    #[serde(default)]
+    pub arm_impression_counts: HashMap<RankingArm, ImpressionCounts>,
+    // Posts each user viewed on the current UTC day, so a post's views count once per viewer and day
+    #[serde(default)]
+    pub daily_views: HashMap<Principal, HashSet<String>>,
+    #[serde(default)]
+    pub daily_views_day: u64,
+    // Every user's posts served in the last session, oldest first; not sampled
+    #[serde(default)]
+    pub recently_served: HashMap<Principal, Vec<ServedPost>>,
+
+    // Cached account quality scores and the last hour of rate-limited actions per account
+    #[serde(default)]
+    pub account_quality_scores: HashMap<Principal, AccountQuality>,
+    #[serde(default)]
+    pub recent_actions: HashMap<Principal, Vec<RecentAction>>,
+    // Debug endpoint calls in the last hour, from any admin
+    #[serde(default)]
+    pub debug_calls: Vec<TimestampMillis>,
+
+    // Operator-controlled runtime feature flags
+    #[serde(default)]
+    pub feature_flags: HashMap<FeatureFlag, FeatureFlagState>,
+    // Admin overrides of which public read endpoints anonymous callers may use, by endpoint name
+    #[serde(default)]
+    pub anonymous_read_overrides: HashMap<String, bool>,
+    #[serde(default)]
+    pub maintenance_mode: Option<MaintenanceMode>,
+
+    // Peer canisters holding other slices of the content, read through composite-query fan-out
+    #[serde(default)]
+    pub content_shards: HashMap<Principal, ContentShard>,
+
+    // External moderation classifier and its verdicts, keyed by content ID
+    #[serde(default)]
+    pub classifier_settings: Option<ClassifierSettings>,
+    #[serde(default)]
+    pub classifier_verdicts: HashMap<String, ClassifierVerdict>,
+
+    // Trust level requirements and posting privileges
+    #[serde(default)]
+    pub trust_settings: Option<TrustSettings>,
+
+    // Approval mode for new accounts, and their held posts by post id
+    #[serde(default)]
+    pub content_approval: Option<ContentApprovalSettings>,
+    #[serde(default)]
+    pub pending_approvals: BTreeMap<String, PendingApproval>,
+
+    // Approximate bytes of posts and comments stored per author, checked against per-trust-level
+    // quotas or an admin override
+    #[serde(default)]
+    pub storage_usage: HashMap<Principal, u64>,
+    #[serde(default)]
+    pub storage_quota_settings: Option<StorageQuotaSettings>,
+    #[serde(default)]
+    pub storage_quota_overrides: HashMap<Principal, u64>,
+
+    // Syndication partners and the last hour of feed requests per partner
+    #[serde(default)]
+    pub syndication_partners: HashMap<String, SyndicationPartner>,
+    #[serde(default)]
+    pub syndication_requests: HashMap<String, Vec<TimestampMillis>>,
+
+    // OpenChat bridge: per-topic channel routes and mirrored posts keyed by post ID
+    #[serde(default)]
+    pub openchat_settings: Option<OpenChatBridgeSettings>,
+    #[serde(default)]
+    pub openchat_routes: HashMap<String, OpenChatTopicRoute>,
+    #[serde(default)]
+    pub openchat_mirrored: HashMap<String, MirroredMessage>,
+
+    // Wallets and neuron controllers proven to belong to a user, and challenges awaiting confirmation
+    #[serde(default)]
+    pub linked_accounts: HashMap<Principal, Vec<LinkedAccount>>,
+    // Linked account -> the user it is linked to
+    #[serde(default)]
+    pub linked_account_owners: HashMap<Principal, Principal>,
+    #[serde(default)]
+    pub pending_account_links: HashMap<String, PendingAccountLink>,
+
+    // Last verified token balances per user, used by token-gated tags and posts
+    #[serde(default)]
+    pub token_balances: HashMap<Principal, Vec<CachedTokenBalance>>,
+
+    // Verified NFTs users show on their profiles
+    #[serde(default)]
+    pub nft_showcases: HashMap<Principal, Vec<ShowcaseNft>>,
+
+    // Recent visits to each user's profile
+    #[serde(default)]
+    pub profile_visits: HashMap<Principal, Vec<ProfileVisit>>,
+    // Profiles each visitor was last counted on, kept for a day so repeat visits are not counted again
+    #[serde(default)]
+    pub recent_profile_visits: HashMap<Principal, HashMap<Principal, TimestampMillis>>,
+
+    // Translation API configuration
+    #[serde(default)]
+    pub translation_settings: Option<TranslationSettings>,
+
+    // Cached translations keyed by "<content_id>:<lang>"
+    #[serde(default)]
+    pub translations: HashMap<String, CachedTranslation>,
+
+    // Extractive summaries of long articles, keyed by post ID
+    #[serde(default)]
+    pub article_summaries: HashMap<String, ArticleSummary>,
+
+    // Tables of contents of articles with enough headings, keyed by post ID
+    #[serde(default)]
+    pub article_outlines: HashMap<String, ArticleOutline>,
+
+    // Allowed HTML tags for stored content; defaults apply until admins change them
+    #[serde(default)]
+    pub html_sanitizer_settings: Option<HtmlSanitizerSettings>,
+
+    // HTML renderings of Markdown and Plain posts and comments, keyed by content ID
+    #[serde(default)]
+    pub rendered_content: HashMap<String, RenderedContent>,
+    // Content rendered before the last allowed tag change; the heartbeat re-renders it a batch at a time
+    #[serde(default)]
+    pub rendered_content_refresh: Vec<String>,
+
+    // Admin-registered image emojis, keyed by shortcode
+    #[serde(default)]
+    pub custom_emojis: BTreeMap<String, CustomEmoji>,
+
+    // Emoji reactions: content ID -> shortcode -> users who reacted
+    #[serde(default)]
+    pub reactions: HashMap<String, BTreeMap<String, HashSet<Principal>>>,
+
+    // Username and handle deny-list and reserved handles; defaults apply until admins change them
+    #[serde(default)]
+    pub name_policy: Option<NamePolicySettings>,
+
+    // Other IC News products that users can import their profile from
+    #[serde(default)]
+    pub companion_canisters: HashMap<Principal, CompanionCanister>,
+
+    // Display fields of every profile, for hydrating authors in feeds
+    #[serde(default)]
+    pub user_cards: HashMap<Principal, UserCard>,
+
+    // Fan-out threshold for following timelines; defaults apply until admins change it
+    #[serde(default)]
+    pub timeline_settings: Option<TimelineSettings>,
+    // Source mix of the home and explore feeds and the newcomer ranking boost; defaults apply until admins change them
+    #[serde(default)]
+    pub feed_composition: Option<FeedComposition>,
+    #[serde(default)]
+    pub newcomer_boost: Option<NewcomerBoostSettings>,
+
+    // Post ids pushed to each follower by smaller accounts, oldest first
+    #[serde(default)]
+    pub timelines: HashMap<Principal, Vec<String>>,
+
+    // Set once every stored timestamp has been normalized to milliseconds
+    #[serde(default)]
+    pub timestamps_in_millis: bool,
+
+    // Set once every stored hashtag has been normalized
+    #[serde(default)]
+    pub hashtags_normalized: bool,
+
+    // Set once summaries have been built for articles written before summaries existed
+    #[serde(default)]
+    pub article_summaries_built: bool,
+
+    // Set once handles that differed only in case have been made unique
+    #[serde(default)]
+    pub handles_case_insensitive: bool,
+
+    // Set once the user card index has been built for existing profiles
+    #[serde(default)]
+    pub user_cards_built: bool,
+
+    // Set once likes have been moved into compressed like sets
+    #[serde(default)]
+    pub like_sets_built: bool,
+
+    // Set once stats counters and the event log checkpoint have been seeded from existing content
+    #[serde(default)]
+    pub event_log_initialized: bool,
+
+    // Set once storage usage has been counted for content stored before quotas existed
+    #[serde(default)]
+    pub storage_usage_built: bool,
+
+    // Set once the built-in daily tasks have been turned into task templates
+    #[serde(default)]
+    pub task_templates_migrated: bool,
+
+    // Set once balances from before the points ledger have been recorded as opening entries
+    #[serde(default)]
+    pub points_ledger_migrated: bool,
+
+    // Set once reports from before deduplication list their reporters and pending duplicates are merged
+    #[serde(default)]
+    pub report_reporters_migrated: bool,
+    // Set once notifications from before sync tokens carry a sequence number
+    #[serde(default)]
+    pub notification_seqs_migrated: bool,
+    // Set once notification lists run oldest first, as ring buffers
+    #[serde(default)]
+    pub notifications_oldest_first: bool,
+    // Set once follows from before follow times were recorded are dated from the event log
+    #[serde(default)]
+    pub follow_times_migrated: bool,
+    // Set once tables of contents have been built for articles written before they existed
+    #[serde(default)]
+    pub article_outlines_built: bool,
+    // Set once embargoed posts are Scheduled rather than Hidden and schedules are queued by due time
+    #[serde(default)]
+    pub schedule_queue_built: bool,
+    // Set once post_comments lists the comments made before it existed
+    #[serde(default)]
+    pub post_comments_indexed: bool,
+    // Set once tag follower counts include follows made before tags counted them
+    #[serde(default)]
+    pub tag_follower_counts_built: bool,
+    // Set once open_reports lists the reports pending before it existed
+    #[serde(default)]
+    pub open_reports_indexed: bool,
+    // Set once the event log checkpoint keeps counters instead of full user stats
+    #[serde(default)]
+    pub event_checkpoint_compacted: bool,
+    // Set once linked_account_owners lists the accounts linked before it existed
+    #[serde(default)]
+    pub linked_account_owners_indexed: bool,
 }
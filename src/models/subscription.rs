@@ -0,0 +1,38 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::utils::time_utils::{TimestampMillis, MILLIS_PER_MINUTE};
+
+// Comments on a thread within this window reach its subscribers as one digest notification
+pub const THREAD_DIGEST_WINDOW: u64 = 15 * MILLIS_PER_MINUTE;
+// Digest notifications sent per heartbeat, across all threads
+pub const THREAD_DIGEST_BATCH_SIZE: usize = 1_000;
+// Distinct commenters remembered per digest, enough to tell a subscriber's own comment apart
+pub const THREAD_DIGEST_COMMENTERS: usize = 2;
+
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ThreadSubscriptionState {
+    Subscribed,
+    Muted,  // No digests, and commenting does not subscribe again
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct ContentSubscriptionResponse {
+    pub content_id: String,
+    pub state: Option<ThreadSubscriptionState>,
+}
+
+// New comments on a thread since its last digest
+#[derive(CandidType, Deserialize, Clone)]
+pub struct ThreadActivity {
+    pub new_comments: u64,
+    pub commenters: Vec<Principal>,  // Up to THREAD_DIGEST_COMMENTERS, first come first
+    pub last_commenter: Principal,
+}
+
+// A digest due for a thread; `activity` is taken over from the thread when the digest starts
+#[derive(CandidType, Deserialize, Clone)]
+pub struct ThreadDigestJob {
+    pub post_id: String,
+    pub due_at: TimestampMillis,
+    pub activity: Option<ThreadActivity>,
+    pub resume_after: Option<Principal>,
+}
@@ -0,0 +1,81 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::models::content::ContentLicense;
+use crate::utils::time_utils::TimestampMillis;
+
+// Syndication constants
+pub const MAX_SYNDICATION_PARTNERS: usize = 50;
+pub const MAX_SYNDICATION_PARTNER_NAME_LENGTH: usize = 100;
+pub const MAX_ATTRIBUTION_TEXT_LENGTH: usize = 200;
+pub const DEFAULT_SYNDICATION_REQUESTS_PER_HOUR: u32 = 60;
+pub const MAX_SYNDICATION_FEED_LIMIT: usize = 100;
+
+// Partner dapp allowed to pull Square content into its own UI
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SyndicationPartner {
+    pub id: String,
+    pub name: String,
+    // When set, only this canister may use the partner's token
+    pub canister_id: Option<Principal>,
+    // SHA-256 hex digest of the access token; the token itself is never stored
+    pub token_hash: String,
+    // Text partners must display next to embedded content
+    pub attribution_text: String,
+    pub requests_per_hour: u32,
+    pub enabled: bool,
+    pub created_by: Principal,
+    pub created_at: TimestampMillis,
+    pub last_request_at: Option<TimestampMillis>,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct RegisterSyndicationPartnerRequest {
+    pub name: String,
+    pub canister_id: Option<Principal>,
+    pub attribution_text: String,
+    pub requests_per_hour: Option<u32>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UpdateSyndicationPartnerRequest {
+    pub id: String,
+    pub enabled: Option<bool>,
+    pub attribution_text: Option<String>,
+    pub requests_per_hour: Option<u32>,
+}
+
+// Response DTOs
+// Returned once at registration; only the token hash is kept
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SyndicationPartnerCredentials {
+    pub partner: SyndicationPartner,
+    pub token: String,
+}
+
+// Normalized content object, independent of the internal post layout
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SyndicatedContent {
+    pub id: String,
+    pub title: Option<String>,
+    pub body: String,
+    pub media_urls: Vec<String>,
+    pub hashtags: Vec<String>,
+    pub author: Principal,
+    pub author_name: String,
+    pub author_handle: String,
+    pub license: Option<ContentLicense>,
+    pub published_at: TimestampMillis,
+    pub updated_at: TimestampMillis,
+    // Attribution line the partner must render with this item
+    pub attribution: String,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SyndicationFeedResponse {
+    pub items: Vec<SyndicatedContent>,
+    // Pass as `since` and `since_id` on the next call to continue from the last item
+    pub next_since: Option<TimestampMillis>,
+    pub next_since_id: Option<String>,
+    pub has_more: bool,
+    pub requests_remaining: u32,
+}
@@ -1,4 +1,15 @@
-use candid::{CandidType, Deserialize};
+use candid::{CandidType, Deserialize, Principal};
+use crate::models::content::PostResponse;
+use crate::models::token_gate::TokenGate;
+use crate::models::user::UserRole;
+use crate::utils::time_utils::TimestampMillis;
+
+// Tag constants
+pub const MAX_TAG_DESCRIPTION_LENGTH: usize = 500;
+pub const MAX_TAG_COVER_IMAGE_LENGTH: usize = 1000;
+pub const MAX_TAG_RULES: usize = 10;
+pub const MAX_TAG_RULE_LENGTH: usize = 300;
+pub const MAX_TOPIC_PIN_HOURS: u64 = 24 * 30;
 
 #[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
 pub enum TagType {
@@ -25,5 +36,84 @@ pub struct GetHotTagsRequest {
 #[derive(CandidType, Deserialize, Clone)]
 pub struct HotTagsResponse {
     pub tags: Vec<TagStats>,
-    pub updated_at: u64,
+    pub updated_at: TimestampMillis,
+}
+
+// Tag entity keyed by its normalized hashtag; metadata is managed by moderators
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Tag {
+    pub name: String,
+    pub description: String,
+    pub cover_image: Option<String>,
+    // Posting rules shown on the topic page and enforced by the tag's moderators
+    #[serde(default)]
+    pub rules: Vec<String>,
+    // Explainer post pinned to the top of the topic feed
+    #[serde(default)]
+    pub pinned: Option<PinnedContent>,
+    // Posting and commenting in the community require holding this token
+    #[serde(default)]
+    pub token_gate: Option<TokenGate>,
+    // Only authors with one of these roles may post with the tag; empty means anyone may
+    #[serde(default)]
+    pub posting_roles: Vec<UserRole>,
+    pub follower_count: u64,
+    pub created_at: TimestampMillis,
+    pub updated_at: TimestampMillis,
+    pub updated_by: Option<Principal>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PinnedContent {
+    pub post_id: String,
+    pub pinned_by: Principal,
+    pub pinned_at: TimestampMillis,
+    pub expires_at: Option<TimestampMillis>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UpdateTagRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub cover_image: Option<String>,
+    pub rules: Option<Vec<String>>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct PinTopicPostRequest {
+    pub tag: String,
+    pub post_id: String,
+    // Pin indefinitely when omitted
+    pub expires_in_hours: Option<u64>,
+}
+
+// Tag as shown on hot tag lists and topic pages
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TagResponse {
+    pub name: String,
+    pub tag_type: TagType,
+    pub description: String,
+    pub cover_image: Option<String>,
+    pub rules: Vec<String>,
+    pub moderators: Vec<Principal>,
+    pub token_gate: Option<TokenGate>,
+    #[serde(default)]
+    pub posting_roles: Vec<UserRole>,
+    pub follower_count: u64,
+    pub post_count: u64,
+    pub created_at: Option<TimestampMillis>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct PinnedContentResponse {
+    pub post: PostResponse,
+    pub pinned_by: Principal,
+    pub pinned_at: TimestampMillis,
+    pub expires_at: Option<TimestampMillis>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct TopicPageResponse {
+    pub tag: TagResponse,
+    pub pinned_content: Option<PinnedContentResponse>,
 }
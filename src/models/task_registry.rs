@@ -0,0 +1,112 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::models::reward::TaskType;
+use crate::utils::time_utils::TimestampMillis;
+
+// Task canister registry constants
+pub const MAX_TASK_CANISTER_NAME_LENGTH: usize = 100;
+pub const MAX_TASK_CANISTER_DESCRIPTION_LENGTH: usize = 500;
+pub const MAX_TASKS_PER_CANISTER: usize = 50;
+// Deliveries are retried on full heartbeat runs until they succeed or reach this many attempts
+pub const MAX_TASK_WEBHOOK_ATTEMPTS: u32 = 5;
+// Failed and pending deliveries kept for retries and for admins to inspect; the oldest are dropped first
+pub const MAX_TASK_WEBHOOK_DELIVERIES: usize = 500;
+// Receipts awaiting an answer from one task canister at a time. Calls wait for an answer without a
+// timeout, so this bounds how many a canister that never answers can hold open; further receipts
+// wait in the queue for a retry without using up an attempt.
+pub const MAX_TASK_WEBHOOKS_IN_FLIGHT: usize = 10;
+
+// Kind of external task canister
+#[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
+pub enum TaskCanisterKind {
+    CheckIn,
+    Quiz,
+    Referral,
+    Other(String),
+}
+
+// External task canister registered by an admin
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TaskCanisterRegistration {
+    pub canister_id: Principal,
+    pub name: String,
+    pub description: String,
+    pub kind: TaskCanisterKind,
+    // Tasks offering points outside this range are ignored during sync
+    pub min_points: u64,
+    pub max_points: u64,
+    pub enabled: bool,
+    pub registered_by: Principal,
+    pub registered_at: TimestampMillis,
+    pub last_synced_at: Option<TimestampMillis>,
+    pub last_sync_error: Option<String>,
+    // Whether the canister is sent a receipt through `on_task_completed` when one of its tasks is completed
+    #[serde(default)]
+    pub webhook_enabled: bool,
+}
+
+// Task definition exposed by a task canister through `get_task_definitions`
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ExternalTaskDefinition {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub points: u64,
+    pub task_type: TaskType,
+    pub completion_criteria: String,
+    pub expiration_time: Option<TimestampMillis>,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct RegisterTaskCanisterRequest {
+    pub canister_id: Principal,
+    pub name: String,
+    pub description: String,
+    pub kind: TaskCanisterKind,
+    pub min_points: u64,
+    pub max_points: u64,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UpdateTaskCanisterRequest {
+    pub canister_id: Principal,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub min_points: Option<u64>,
+    pub max_points: Option<u64>,
+    pub enabled: Option<bool>,
+}
+
+// Result of syncing one task canister
+#[derive(CandidType, Deserialize, Clone)]
+pub struct TaskCanisterSyncResult {
+    pub canister_id: Principal,
+    pub tasks_synced: u64,
+    pub tasks_rejected: u64,
+    pub error: Option<String>,
+}
+
+// Sent to a task canister's `on_task_completed` when a user completes one of its tasks. The signature
+// is a hex HMAC-SHA256, keyed with the canister's webhook secret as of each send, of
+// "receipt_id|task_id|user|points_awarded|completed_at|issuer".
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TaskCompletionReceipt {
+    pub receipt_id: String,
+    pub task_id: String,
+    pub user: Principal,
+    pub points_awarded: u64,
+    pub completed_at: TimestampMillis,
+    // This canister
+    pub issuer: Principal,
+    pub signature: String,
+}
+
+// A receipt that has not been delivered yet
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TaskWebhookDelivery {
+    pub canister_id: Principal,
+    pub receipt: TaskCompletionReceipt,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub last_attempt_at: TimestampMillis,
+}
@@ -0,0 +1,86 @@
+use candid::{CandidType, Deserialize, Principal};
+use crate::models::reward::{TaskRequirements, TaskType};
+use crate::utils::time_utils::TimestampMillis;
+
+// Task template constants
+pub const MAX_TASK_TEMPLATES: usize = 50;
+pub const MAX_TASK_TEMPLATE_ID_LENGTH: usize = 50;
+pub const MAX_TASK_TEMPLATE_TITLE_LENGTH: usize = 100;
+pub const MAX_TASK_TEMPLATE_DESCRIPTION_LENGTH: usize = 500;
+// How far to look for the start of the current or next period; every schedule matches within a year
+pub const RECURRENCE_SEARCH_DAYS: u64 = 366;
+
+// When a template starts a new period. Periods begin at local midnight on a matching day, in each
+// user's timezone, and last until the next one.
+#[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
+pub enum TaskRecurrence {
+    Daily,
+    // 0 is Monday, 6 is Sunday
+    Weekly { weekday: u8 },
+    // Cron day fields: a day matches when its weekday or day of the month is listed.
+    // An empty list matches nothing unless the other is empty too, which matches every day.
+    Cron { weekdays: Vec<u8>, month_days: Vec<u8> },
+}
+
+impl TaskRecurrence {
+    pub fn task_type(&self) -> TaskType {
+        match self {
+            TaskRecurrence::Daily => TaskType::Daily,
+            TaskRecurrence::Weekly { .. } => TaskType::Weekly,
+            TaskRecurrence::Cron { .. } => TaskType::Special,
+        }
+    }
+}
+
+// A task defined once and instantiated afresh for every period of its recurrence
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TaskTemplate {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub points: u64,
+    pub completion_criteria: String,
+    pub requirements: Option<TaskRequirements>,
+    pub recurrence: TaskRecurrence,
+    // Whether completing an instance needs a proof
+    pub proof_required: bool,
+    pub enabled: bool,
+    pub created_by: Principal,
+    pub created_at: TimestampMillis,
+    pub updated_at: TimestampMillis,
+    // Task generated for the current period, and the dates that period starts and ends on
+    pub current_instance: Option<String>,
+    pub current_period_start: Option<TimestampMillis>,
+    pub next_period_start: Option<TimestampMillis>,
+    // Instances of earlier periods, kept until their period has ended in every timezone
+    #[serde(default)]
+    pub earlier_instances: Vec<String>,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct CreateTaskTemplateRequest {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub points: u64,
+    pub completion_criteria: String,
+    pub requirements: Option<TaskRequirements>,
+    pub recurrence: TaskRecurrence,
+    pub proof_required: bool,
+}
+
+// Changes apply from the next period and the current instance keeps its terms, except that a new
+// recurrence starts right away
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UpdateTaskTemplateRequest {
+    pub id: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub points: Option<u64>,
+    pub completion_criteria: Option<String>,
+    pub requirements: Option<TaskRequirements>,
+    pub recurrence: Option<TaskRecurrence>,
+    pub proof_required: Option<bool>,
+    pub enabled: Option<bool>,
+}
@@ -0,0 +1,24 @@
+use candid::{CandidType, Deserialize};
+
+// Timeline constants
+pub const DEFAULT_FANOUT_FOLLOWER_THRESHOLD: u64 = 1_000;
+pub const MAX_TIMELINE_ITEMS: usize = 500;
+// Recent posts copied into a timeline when its owner follows a fanned-out account
+pub const TIMELINE_BACKFILL_POSTS: usize = 20;
+// Largest offset + limit the following feed serves
+pub const MAX_FOLLOWING_FEED_WINDOW: usize = 500;
+
+// Accounts with at most `fanout_follower_threshold` followers push new posts into their followers'
+// timelines; posts by larger accounts are gathered when the feed is read
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TimelineSettings {
+    pub fanout_follower_threshold: u64,
+}
+
+impl Default for TimelineSettings {
+    fn default() -> Self {
+        Self {
+            fanout_follower_threshold: DEFAULT_FANOUT_FOLLOWER_THRESHOLD,
+        }
+    }
+}
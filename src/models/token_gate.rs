@@ -0,0 +1,53 @@
+use candid::{CandidType, Deserialize, Nat, Principal};
+use crate::utils::time_utils::TimestampMillis;
+
+// Cached balances are trusted for this long before holders must re-verify
+pub const TOKEN_GATE_REVERIFY_MS: u64 = 60 * 60 * 1000;
+
+// Holding requirement on a tag community or a single post
+#[derive(CandidType, Deserialize, Clone, PartialEq, Debug)]
+pub struct TokenGate {
+    pub ledger_canister_id: Principal,
+    pub min_balance: Nat,
+}
+
+// Last verified balance of one token across a user's principal and linked accounts
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CachedTokenBalance {
+    pub ledger_canister_id: Principal,
+    pub balance: Nat,
+    pub verified_at: TimestampMillis,
+}
+
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum TokenGateStatus {
+    // No balance on record, or the cached one is older than the re-verification interval
+    Unverified,
+    Passed,
+    Failed,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum GateTarget {
+    Tag(String),
+    Post(String),
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct SetTokenGateRequest {
+    pub target: GateTarget,
+    // None removes the gate
+    pub gate: Option<TokenGate>,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TokenGateAccessResponse {
+    pub gate: TokenGate,
+    pub status: TokenGateStatus,
+    pub balance: Option<Nat>,
+    pub verified_at: Option<TimestampMillis>,
+    // Re-verify after this time to keep access
+    pub expires_at: Option<TimestampMillis>,
+}
@@ -0,0 +1,84 @@
+use candid::{CandidType, Deserialize};
+use crate::models::content::ContentType;
+use crate::utils::time_utils::TimestampMillis;
+
+// Content translation constants
+pub const MAX_TRANSLATION_URL_LENGTH: usize = 500;
+pub const MAX_TRANSLATION_TEXT_BYTES: usize = 16 * 1024;
+pub const TRANSLATION_MAX_RESPONSE_BYTES: u64 = 64 * 1024;
+pub const MAX_SUPPORTED_LANGUAGES: usize = 20;
+
+// External translation API called via HTTPS outcall when a reader asks for a translation.
+// The API receives `{"content_id", "target_lang", "title", "text"}` as JSON, with `title` null for
+// untitled content, and answers with `{"title": string or null, "text": string}`.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TranslationSettings {
+    pub enabled: bool,
+    pub endpoint_url: String,
+    // Sent as the Authorization header; never returned by queries
+    pub auth_token: Option<String>,
+    // Lowercase language codes readers may request, such as "en" or "zh"
+    pub supported_languages: Vec<String>,
+    // Cycles attached to each outcall
+    pub cycles_per_call: u128,
+}
+
+impl Default for TranslationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint_url: String::new(),
+            auth_token: None,
+            supported_languages: vec!["en".to_string(), "zh".to_string()],
+            cycles_per_call: 4_000_000_000,
+        }
+    }
+}
+
+// Translation kept per content and language; reused until the original text changes
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CachedTranslation {
+    pub content_id: String,
+    pub content_type: ContentType,
+    pub target_lang: String,
+    // Hash of the original title and text the translation was made from
+    pub source_hash: String,
+    pub title: Option<String>,
+    pub text: String,
+    pub translated_at: TimestampMillis,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UpdateTranslationSettingsRequest {
+    pub enabled: Option<bool>,
+    pub endpoint_url: Option<String>,
+    // Some("") clears the token
+    pub auth_token: Option<String>,
+    pub supported_languages: Option<Vec<String>>,
+    pub cycles_per_call: Option<u128>,
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TranslationSettingsResponse {
+    pub enabled: bool,
+    pub endpoint_url: String,
+    pub has_auth_token: bool,
+    pub supported_languages: Vec<String>,
+    pub cycles_per_call: u128,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TranslationResponse {
+    pub content_id: String,
+    pub content_type: ContentType,
+    pub target_lang: String,
+    pub original_title: Option<String>,
+    pub original_text: String,
+    pub translated_title: Option<String>,
+    pub translated_text: String,
+    // Whether the translation came from the cache rather than a new outcall
+    pub cached: bool,
+    pub translated_at: TimestampMillis,
+}
@@ -0,0 +1,90 @@
+use candid::{CandidType, Deserialize, Principal};
+use std::collections::VecDeque;
+use crate::utils::time_utils::TimestampMillis;
+
+// Treasury constants
+pub const MAX_LEDGER_ENTRIES: usize = 10_000;
+pub const DEFAULT_MINT_BUDGET: u64 = 10_000;
+pub const DEFAULT_MINT_BUDGET_PERIOD_DAYS: u64 = 30;
+pub const MAX_MINT_BUDGET_PERIOD_DAYS: u64 = 366;
+pub const MAX_ADJUSTMENT_REASON_LENGTH: usize = 200;
+pub const MAX_RECONCILIATION_MISMATCHES: usize = 100;
+pub const OPENING_BALANCE_REASON: &str = "Opening balance";
+
+// Points move between accounts. The mint issues every point and takes back every point removed,
+// so user balances always add up to what it has issued less what it has taken back.
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum LedgerAccount {
+    Mint,
+    User(Principal),
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LedgerEntry {
+    pub id: u64,
+    pub debit: LedgerAccount,
+    pub credit: LedgerAccount,
+    pub amount: u64,
+    pub reason: String,
+    pub reference_id: Option<String>,
+    // Manager or admin behind a manual entry
+    pub authorized_by: Option<Principal>,
+    pub timestamp: TimestampMillis,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct PointsTreasury {
+    // Totals over every entry ever made, including those dropped from `entries`
+    pub minted: u64,
+    pub burned: u64,
+    pub next_entry_id: u64,
+    // The latest MAX_LEDGER_ENTRIES entries, oldest first
+    pub entries: VecDeque<LedgerEntry>,
+}
+
+// How many points a manager may award per period. Admins are not limited.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MintBudget {
+    pub limit: u64,
+    pub period_days: u64,
+    pub period_start: TimestampMillis,
+    pub minted_in_period: u64,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MintBudgetResponse {
+    pub manager: Principal,
+    pub budget: MintBudget,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PointsReconciliationReport {
+    pub generated_at: TimestampMillis,
+    pub minted: u64,
+    pub burned: u64,
+    // What user balances should add up to
+    pub expected_circulation: u64,
+    pub total_user_balances: u64,
+    // Balances minus expected circulation; anything but 0 means points moved outside the ledger
+    pub discrepancy: i64,
+    pub users_checked: u64,
+    // Users whose balance differs from the sum of their points history
+    pub mismatched_users: Vec<Principal>,
+}
+
+// Request DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct SetMintBudgetRequest {
+    pub manager: Principal,
+    pub limit: u64,
+    pub period_days: u64,
+}
+
+// Takes points back from a user, e.g. to correct a mistaken award
+#[derive(CandidType, Deserialize, Clone)]
+pub struct AdjustPointsRequest {
+    pub principal: Principal,
+    pub points: u64,
+    pub reason: String,
+    pub reference_id: Option<String>,
+}
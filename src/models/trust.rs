@@ -0,0 +1,88 @@
+use candid::{CandidType, Deserialize};
+use crate::models::content::MAX_MEDIA_URLS;
+
+#[derive(CandidType, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TrustLevel {
+    New,
+    Basic,
+    Member,
+    Regular,
+}
+
+// What an account at a trust level may do when posting
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TrustPrivileges {
+    pub can_post_links: bool,
+    pub max_media_urls: u32,
+    pub max_posts_per_hour: u32,
+}
+
+// Requirements for reaching a trust level, and what it unlocks
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TrustLevelConfig {
+    pub min_account_age_days: u64,
+    pub min_active_posts: u64,
+    pub privileges: TrustPrivileges,
+}
+
+// Admin-configurable trust ladder
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TrustSettings {
+    pub new: TrustPrivileges,
+    pub basic: TrustLevelConfig,
+    pub member: TrustLevelConfig,
+    pub regular: TrustLevelConfig,
+    // Removed content or a suspension/ban within this many days holds an account at New
+    pub clean_history_days: u64,
+}
+
+impl Default for TrustSettings {
+    fn default() -> Self {
+        Self {
+            new: TrustPrivileges { can_post_links: false, max_media_urls: 1, max_posts_per_hour: 2 },
+            basic: TrustLevelConfig {
+                min_account_age_days: 1,
+                min_active_posts: 0,
+                privileges: TrustPrivileges { can_post_links: true, max_media_urls: 2, max_posts_per_hour: 5 },
+            },
+            member: TrustLevelConfig {
+                min_account_age_days: 7,
+                min_active_posts: 3,
+                privileges: TrustPrivileges { can_post_links: true, max_media_urls: 4, max_posts_per_hour: 10 },
+            },
+            regular: TrustLevelConfig {
+                min_account_age_days: 30,
+                min_active_posts: 10,
+                privileges: TrustPrivileges {
+                    can_post_links: true,
+                    max_media_urls: MAX_MEDIA_URLS as u32,
+                    max_posts_per_hour: 30,
+                },
+            },
+            clean_history_days: 30,
+        }
+    }
+}
+
+impl TrustSettings {
+    pub fn privileges(&self, level: TrustLevel) -> &TrustPrivileges {
+        match level {
+            TrustLevel::New => &self.new,
+            TrustLevel::Basic => &self.basic.privileges,
+            TrustLevel::Member => &self.member.privileges,
+            TrustLevel::Regular => &self.regular.privileges,
+        }
+    }
+}
+
+// Response DTOs
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TrustLevelResponse {
+    pub level: TrustLevel,
+    pub privileges: TrustPrivileges,
+    pub account_age_days: u64,
+    pub active_posts: u64,
+    // False when recent removals or suspensions hold the account at New
+    pub clean_history: bool,
+    pub next_level: Option<TrustLevel>,
+}
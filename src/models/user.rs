@@ -1,19 +1,26 @@
 use candid::{CandidType, Deserialize, Principal};
 use std::collections::{HashSet, HashMap};
 use crate::models::notification::NotificationType;
+use crate::models::content::{ContentStatus, PaginationParams};
+use crate::models::interaction::ContentReport;
+use crate::utils::time_utils::TimestampMillis;
 
 // Constants for validation
 pub const MIN_USERNAME_LENGTH: usize = 3;
 pub const MAX_USERNAME_LENGTH: usize = 30;
 pub const MAX_BIO_LENGTH: usize = 500;
 pub const HANDLE_PATTERN: &str = r"^[a-zA-Z0-9_]{3,30}$";
+pub const MAX_BULK_STATUS_UPDATES: usize = 100;
+pub const MAX_MODERATION_RECORDS_PER_USER: usize = 200;
+// Profiles whose follow counters are checked per repair run
+pub const FOLLOW_REPAIR_BATCH: usize = 500;
 
 // User data structures
 #[derive(CandidType, Deserialize, Clone)]
 pub struct User {
     pub principal: Principal,
-    pub registered_at: u64,
-    pub last_login: u64,
+    pub registered_at: TimestampMillis,
+    pub last_login: TimestampMillis,
     pub interests: Vec<String>,
     pub status: UserStatus,
     pub role: UserRole,
@@ -35,12 +42,12 @@ pub struct UserProfile {
     pub followed_topics: HashSet<String>,
     pub followers_count: u64,
     pub following_count: u64,
-    pub created_at: u64,
-    pub updated_at: u64,
+    pub created_at: TimestampMillis,
+    pub updated_at: TimestampMillis,
     pub privacy_settings: Option<UserPrivacySettings>,
 }
 
-#[derive(CandidType, Deserialize, Clone)]
+#[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct UserStats {
     pub principal: Principal,
     pub post_count: u64,
@@ -66,6 +73,8 @@ pub struct RegisterUserRequest {
     pub avatar: String,
     pub social_links: Option<Vec<(String, String)>>,
     pub interests: Option<Vec<String>>,
+    // Required while registration is invite-only
+    pub invite_code: Option<String>,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -118,6 +127,12 @@ pub struct InteractionPreferences {
     pub allow_mentions: bool,
     pub allow_follows: bool,
     pub show_likes: bool,
+    // Opt in to who-viewed-my-profile: your visits are shown to others who opted in, and theirs to you
+    #[serde(default)]
+    pub share_profile_visits: Option<bool>,
+    // Leave me out of the public leaderboard and creator suggestions; my own rank stays visible to me
+    #[serde(default)]
+    pub hide_from_rankings: Option<bool>,
 }
 
 impl Default for InteractionPreferences {
@@ -127,6 +142,8 @@ impl Default for InteractionPreferences {
             allow_mentions: true,
             allow_follows: true,
             show_likes: true,
+            share_profile_visits: None,
+            hide_from_rankings: None,
         }
     }
 }
@@ -157,6 +174,19 @@ pub struct FollowUserRequest {
     pub user_to_follow: Principal,
 }
 
+// Follow relationship and counts after a follow or unfollow
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct FollowState {
+    pub following: bool,
+    // The followed user's followers
+    pub followers_count: u64,
+    // How many users the caller follows
+    pub following_count: u64,
+    // When the caller followed the user, if it was recorded
+    #[serde(default)]
+    pub followed_at: Option<TimestampMillis>,
+}
+
 #[derive(CandidType, Deserialize, Clone)]
 pub struct FollowTopicRequest {
     pub topic: String,
@@ -174,7 +204,110 @@ pub struct UserRoleUpdateRequest {
     pub role: UserRole,
 }
 
+// Self-service account deactivation
+#[derive(CandidType, Deserialize, Clone)]
+pub struct DeactivateAccountRequest {
+    pub reactivate_on_login: Option<bool>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct AccountDeactivation {
+    pub deactivated_at: TimestampMillis,
+    pub reactivate_on_login: bool,
+}
+
+// Per-user timezone used for daily task boundaries (UTC-12:00 to UTC+14:00)
+pub const MIN_TIMEZONE_OFFSET_MINUTES: i32 = -12 * 60;
+pub const MAX_TIMEZONE_OFFSET_MINUTES: i32 = 14 * 60;
+// Minimum time between timezone changes, so a day cannot be claimed twice by hopping zones
+pub const TIMEZONE_CHANGE_COOLDOWN_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UserTimezone {
+    pub offset_minutes: i32,
+    pub updated_at: TimestampMillis,
+}
+
+// Admin user-management DTOs
+// Debug endpoints are admin only and share one hourly budget across all admins
+pub const MAX_DEBUG_CALLS_PER_HOUR: usize = 60;
+pub const MAX_DEBUG_PAGE_SIZE: usize = 500;
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct DebugUserListRequest {
+    pub pagination: PaginationParams,
+    // Instead of a page, about this many users spread evenly over the whole list
+    pub sample_size: Option<usize>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct AdminUserListRequest {
+    pub status: Option<UserStatus>,
+    pub role: Option<UserRole>,
+    pub registered_after: Option<TimestampMillis>,
+    pub registered_before: Option<TimestampMillis>,
+    pub pagination: PaginationParams,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct BulkUserStatusUpdateRequest {
+    pub principals: Vec<Principal>,
+    pub status: UserStatus,
+    pub reason: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct BulkUserStatusUpdateResponse {
+    pub updated: Vec<Principal>,
+    pub not_found: Vec<Principal>,
+}
+
+// Moderation history entry kept per user
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UserModerationRecord {
+    pub action: UserModerationAction,
+    pub moderator: Principal,
+    pub reason: Option<String>,
+    pub content_id: Option<String>,
+    pub timestamp: TimestampMillis,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub enum UserModerationAction {
+    StatusChanged(UserStatus),
+    RoleChanged(UserRole),
+    ContentModerated(ContentStatus),
+}
+
 // Response DTOs
+#[derive(CandidType, Deserialize, Clone)]
+pub struct AdminUserSummary {
+    pub principal: Principal,
+    pub username: String,
+    pub handle: String,
+    pub status: UserStatus,
+    pub role: UserRole,
+    pub registered_at: TimestampMillis,
+    pub last_login: TimestampMillis,
+    pub content_counts: UserContentCounts,
+}
+
+#[derive(CandidType, Deserialize, Clone, Default)]
+pub struct UserContentCounts {
+    pub posts: u64,
+    pub comments: u64,
+    pub removed_or_hidden: u64,
+    pub reports_received: u64,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct UserModerationHistoryResponse {
+    pub principal: Principal,
+    pub status: UserStatus,
+    pub records: Vec<UserModerationRecord>,
+    pub reports_against: Vec<ContentReport>,
+}
+
 #[derive(CandidType, Deserialize, Clone)]
 pub struct UserProfileResponse {
     pub principal: Principal,
@@ -185,15 +318,35 @@ pub struct UserProfileResponse {
     pub social_links: Vec<(String, String)>,
     pub followers_count: u64,
     pub following_count: u64,
-    pub registered_at: u64,
-    pub last_login: u64,
+    pub registered_at: TimestampMillis,
+    pub last_login: TimestampMillis,
     pub status: UserStatus,
     pub role: UserRole,
     pub is_following: bool,
     pub interests: Vec<String>,
-    pub created_at: u64,
-    pub updated_at: u64,
+    pub created_at: TimestampMillis,
+    pub updated_at: TimestampMillis,
     pub privacy_settings: Option<UserPrivacySettings>,
+    pub linked_accounts: Vec<crate::models::identity::LinkedAccount>,
+    pub showcase: Vec<crate::models::showcase::ShowcaseNft>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct FollowCounterRepairResponse {
+    pub profiles_checked: u64,
+    pub profiles_repaired: u64,
+}
+
+// Display fields of a profile, kept alongside it so feeds can show authors without reading the whole profile
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UserCard {
+    pub username: String,
+    pub handle: String,
+    pub avatar: String,
+    pub bio: String,
+    pub interests: Vec<String>,
+    pub followers_count: u64,
+    pub following_count: u64,
 }
 
 #[derive(CandidType, Deserialize, Clone, Debug)]
@@ -208,6 +361,10 @@ pub struct UserSocialResponse {
     pub following_count: u64,
     pub is_following: bool,
     pub is_followed_by_caller: bool,
+    pub has_linked_wallet: bool,  // Badge for users with a verified linked wallet or neuron
+    // In get_followers and get_following: when the follow was made, if it was recorded
+    #[serde(default)]
+    pub followed_at: Option<TimestampMillis>,
 }
 
 impl Default for UserSocialResponse {
@@ -223,6 +380,8 @@ impl Default for UserSocialResponse {
             following_count: 0,
             is_following: false,
             is_followed_by_caller: false,
+            has_linked_wallet: false,
+            followed_at: None,
         }
     }
 }
@@ -247,8 +406,8 @@ pub struct UserResponse {
     pub interests: Vec<String>,
     pub followers_count: u64,
     pub following_count: u64,
-    pub created_at: u64,
-    pub updated_at: u64,
+    pub created_at: TimestampMillis,
+    pub updated_at: TimestampMillis,
     pub stats: UserStatsResponse,
     pub status: UserStatus,
     pub role: UserRole,
@@ -262,13 +421,16 @@ pub struct UserLeaderboardItem {
     pub handle: String,
     pub avatar: String,
     pub rank: u64,
-    pub last_claim_date: u64,
+    pub last_claim_date: TimestampMillis,
     pub consecutive_daily_logins: u64,
     pub followers_count: u64,
     pub post_count: u64,
     pub comment_count: u64,
     pub like_count: u64,
     pub reputation: u64,
+    // Current points balance, after any expiry
+    #[serde(default)]
+    pub points: u64,
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -286,9 +448,10 @@ pub enum UserStatus {
     Suspended,
     Banned,
     Restricted,
+    Deactivated, // Set by the user themselves; content is preserved
 }
 
-#[derive(CandidType, Deserialize, Clone, PartialEq, Default)]
+#[derive(CandidType, Deserialize, Clone, PartialEq, Default, Debug)]
 pub enum UserRole {
     #[default]
     User,
@@ -0,0 +1,84 @@
+use candid::Principal;
+use ic_cdk::api::caller;
+
+use crate::auth::is_admin;
+use crate::models::access_policy::*;
+use crate::models::error::SquareResult;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+
+const MODULE: &str = "services::access_policy";
+
+fn default_access(endpoint: &str) -> Option<bool> {
+    PUBLIC_READ_ENDPOINTS
+        .iter()
+        .find(|(name, _)| *name == endpoint)
+        .map(|(_, allow_anonymous)| *allow_anonymous)
+}
+
+fn endpoint_access(endpoint: &str) -> bool {
+    let overridden = STORAGE.with(|storage| storage.borrow().anonymous_read_overrides.get(endpoint).copied());
+    overridden.or(default_access(endpoint)).unwrap_or(false)
+}
+
+// Reject anonymous callers of a public read endpoint that the policy closes to them
+pub fn check_anonymous_read(endpoint: &str) -> SquareResult<()> {
+    if caller() != Principal::anonymous() || endpoint_access(endpoint) {
+        return Ok(());
+    }
+    log_and_return(unauthorized_error(
+        &format!("Sign in to call {}", endpoint),
+        MODULE,
+        "check_anonymous_read"
+    ))
+}
+
+pub fn get_anonymous_read_policy() -> SquareResult<Vec<EndpointAccess>> {
+    Ok(STORAGE.with(|storage| {
+        let store = storage.borrow();
+        PUBLIC_READ_ENDPOINTS
+            .iter()
+            .map(|(endpoint, default)| {
+                let overridden = store.anonymous_read_overrides.get(*endpoint);
+                EndpointAccess {
+                    endpoint: endpoint.to_string(),
+                    allow_anonymous: overridden.copied().unwrap_or(*default),
+                    overridden: overridden.is_some(),
+                }
+            })
+            .collect()
+    }))
+}
+
+// Setting an endpoint back to its default drops the override
+pub fn set_anonymous_read_access(request: SetAnonymousReadAccessRequest) -> SquareResult<EndpointAccess> {
+    const FUNCTION: &str = "set_anonymous_read_access";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "set_anonymous_read_access",
+            "Only admins can change the anonymous read policy",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    let Some(default) = default_access(&request.endpoint) else {
+        return log_and_return(not_found_error("Public read endpoint", &request.endpoint, MODULE, FUNCTION));
+    };
+
+    let overridden = request.allow_anonymous != default;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if overridden {
+            store.anonymous_read_overrides.insert(request.endpoint.clone(), request.allow_anonymous);
+        } else {
+            store.anonymous_read_overrides.remove(&request.endpoint);
+        }
+    });
+
+    Ok(EndpointAccess {
+        endpoint: request.endpoint,
+        allow_anonymous: request.allow_anonymous,
+        overridden,
+    })
+}
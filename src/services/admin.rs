@@ -7,6 +7,11 @@ use crate::storage::{STORAGE, ContentStatus, UserStatus};
 use crate::models::interaction::ReportStatus;
 use crate::models::notification::NotificationType;
 use crate::services::user::{create_notification};
+use crate::services::user::admin::record_user_moderation;
+use crate::models::user::UserModerationAction;
+use crate::services::query_cache::{invalidate, POST_QUERIES};
+use crate::services::content::transitions::{set_comment_status, set_post_status};
+use crate::services::content::legal_hold::ensure_not_on_legal_hold;
 use crate::models::cycles::{
     UpdateHeartbeatIntervalRequest, HeartbeatIntervalResponse,
     UpdateCyclesThresholdRequest, CyclesThresholdConfig,
@@ -59,119 +64,31 @@ pub fn moderate_content(content_id: String, action: String) -> SquareResult<()>
     const MODULE: &str = "services::admin";
     const FUNCTION: &str = "moderate_content";
     
+    invalidate(POST_QUERIES);
+    
     // Check if caller is admin
     ensure_admin()?;
     
-    // Check if content exists in main storage
-    let mut post_exists = false;
-    let mut comment_exists = false;
-    
-    // Check if post exists
-    let mut post_to_update = None;
-    STORAGE.with(|storage| {
-        let store = storage.borrow();
-        if let Some(post) = store.posts.get(&content_id) {
-            post_exists = true;
-            post_to_update = Some(post.clone());
-        }
-    });
-    
-    // Check if comment exists
-    let mut comment_to_update = None;
-    STORAGE.with(|storage| {
-        let store = storage.borrow();
-        if let Some(comment) = store.comments.get(&content_id) {
-            comment_exists = true;
-            comment_to_update = Some(comment.clone());
-        }
-    });
+    let status = match action.as_str() {
+        "remove" => ContentStatus::Removed,
+        "hide" => ContentStatus::Hidden,
+        "review" => ContentStatus::UnderReview,
+        "restore" => ContentStatus::Active,
+        _ => return Err(SquareError::ValidationFailed(format!("Invalid action: {}", action))),
+    };
     
-    // If neither post nor comment exists, return error
-    if !post_exists && !comment_exists {
+    // The id may name a post, a comment or both
+    let found = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        ensure_not_on_legal_hold(&store, &content_id, "moderate_content", MODULE, FUNCTION)?;
+        let post = set_post_status(&mut store, caller(), &content_id, status.clone());
+        let comment = set_comment_status(&mut store, caller(), &content_id, status);
+        Ok::<bool, SquareError>(post.is_some() || comment.is_some())
+    })?;
+    if !found {
         return Err(SquareError::NotFound(format!("Content with ID {} not found", content_id)));
     }
     
-    // Determine action to take
-    match action.as_str() {
-        "remove" => {
-            // Handle post moderation
-            if let Some(mut post) = post_to_update {
-                post.status = ContentStatus::Removed;
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    store.posts.insert(content_id.clone(), post);
-                });
-            }
-            
-            // Handle comment moderation
-            if let Some(mut comment) = comment_to_update {
-                comment.status = ContentStatus::Removed;
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    store.comments.insert(content_id.clone(), comment);
-                });
-            }
-        },
-        "hide" => {
-            // Handle post moderation
-            if let Some(mut post) = post_to_update {
-                post.status = ContentStatus::Hidden;
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    store.posts.insert(content_id.clone(), post);
-                });
-            }
-            
-            // Handle comment moderation
-            if let Some(mut comment) = comment_to_update {
-                comment.status = ContentStatus::Hidden;
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    store.comments.insert(content_id.clone(), comment);
-                });
-            }
-        },
-        "review" => {
-            // Handle post moderation
-            if let Some(mut post) = post_to_update {
-                post.status = ContentStatus::UnderReview;
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    store.posts.insert(content_id.clone(), post);
-                });
-            }
-            
-            // Handle comment moderation
-            if let Some(mut comment) = comment_to_update {
-                comment.status = ContentStatus::UnderReview;
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    store.comments.insert(content_id.clone(), comment);
-                });
-            }
-        },
-        "restore" => {
-            // Handle post moderation
-            if let Some(mut post) = post_to_update {
-                post.status = ContentStatus::Active;
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    store.posts.insert(content_id.clone(), post);
-                });
-            }
-            
-            // Handle comment moderation
-            if let Some(mut comment) = comment_to_update {
-                comment.status = ContentStatus::Active;
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    store.comments.insert(content_id.clone(), comment);
-                });
-            }
-        },
-        _ => return Err(SquareError::ValidationFailed(format!("Invalid action: {}", action))),
-    }
-    
     // Update reports in main storage
     // For now, we'll just log the action
     ic_cdk::println!(
@@ -245,20 +162,19 @@ pub fn ban_user(user_id: Principal, reason: String) -> SquareResult<()> {
         });
         
         // Set all user posts to hidden
-        for post_id in user_posts {
-            STORAGE.with(|storage| {
-                let mut store = storage.borrow_mut();
-                if let Some(mut post) = store.posts.get(&post_id).cloned() {
-                    post.status = ContentStatus::Hidden;
-                    store.posts.insert(post_id.clone(), post);
-                }
-            });
-        }
+        STORAGE.with(|storage| {
+            let mut store = storage.borrow_mut();
+            for post_id in &user_posts {
+                set_post_status(&mut store, caller(), post_id, ContentStatus::Hidden);
+            }
+        });
         
         // Get user comments (this would need to be implemented in content service)
         // For now, we'll just log that we can't update comments yet
         ic_cdk::println!("Note: User comments status update not implemented yet");
         
+        record_user_moderation(user_id, UserModerationAction::StatusChanged(UserStatus::Banned), Some(reason.clone()), None);
+        
         // Log the ban action
         ic_cdk::println!("User {} banned by {}. Reason: {}", user_id, caller(), reason);
         
@@ -369,6 +285,73 @@ pub fn migrate_storage() -> SquareResult<String> {
     ensure_admin()?;
     
     // Perform any necessary storage migrations or optimizations
+    let mut migrated = Vec::new();
+    if crate::storage::migration::migrate_timestamps_to_millis() {
+        migrated.push("Storage timestamps normalized to milliseconds");
+    }
+    if crate::storage::migration::migrate_hashtags_to_normalized() {
+        migrated.push("Stored hashtags normalized");
+    }
+    if crate::storage::migration::migrate_article_summaries() {
+        migrated.push("Article summaries built");
+    }
+    if crate::storage::migration::migrate_handles_case_insensitive() {
+        migrated.push("Handle case collisions resolved");
+    }
+    if crate::storage::migration::migrate_user_cards() {
+        migrated.push("User card index built");
+    }
+    if crate::storage::migration::migrate_like_sets() {
+        migrated.push("Like sets compacted");
+    }
+    if crate::storage::migration::migrate_event_log() {
+        migrated.push("Event log checkpoint seeded");
+    }
+    if crate::storage::migration::migrate_storage_usage() {
+        migrated.push("Storage usage counted");
+    }
+    if crate::storage::migration::migrate_task_templates() {
+        migrated.push("Built-in tasks turned into templates");
+    }
+    if crate::storage::migration::migrate_points_ledger() {
+        migrated.push("Points balances recorded in the ledger");
+    }
+    if crate::storage::migration::migrate_report_reporters() {
+        migrated.push("Duplicate pending reports merged");
+    }
+    if crate::storage::migration::migrate_notification_seqs() {
+        migrated.push("Existing notifications numbered");
+    }
+    if crate::storage::migration::migrate_notifications_oldest_first() {
+        migrated.push("Notifications reordered oldest first");
+    }
+    if crate::storage::migration::migrate_follow_times() {
+        migrated.push("Existing follows dated");
+    }
+    if crate::storage::migration::migrate_article_outlines() {
+        migrated.push("Article tables of contents built");
+    }
+    if crate::storage::migration::migrate_schedule_queue() {
+        migrated.push("Content schedules queued");
+    }
+    if crate::storage::migration::migrate_post_comments() {
+        migrated.push("Comments indexed by post");
+    }
+    if crate::storage::migration::migrate_tag_follower_counts() {
+        migrated.push("Tag followers counted");
+    }
+    if crate::storage::migration::migrate_open_reports() {
+        migrated.push("Pending reports indexed by content");
+    }
+    if crate::storage::migration::migrate_event_checkpoint_counters() {
+        migrated.push("Event log checkpoint compacted");
+    }
+    if crate::storage::migration::migrate_linked_account_owners() {
+        migrated.push("Linked accounts indexed by user");
+    }
+    if !migrated.is_empty() {
+        return Ok(migrated.join("; "));
+    }
     
     Ok("Storage optimization completed successfully".to_string())
 }
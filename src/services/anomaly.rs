@@ -0,0 +1,278 @@
+use candid::Principal;
+use ic_cdk::api::time;
+use std::collections::HashSet;
+
+use crate::auth::{is_admin, is_manager_or_admin};
+use crate::models::anomaly::*;
+use crate::models::content::{ContentType, PaginationParams};
+use crate::models::display::PaginatedResponse;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::notification::NotificationType;
+use crate::models::storage::Storage;
+use crate::services::user::create_notification;
+use crate::services::user::creator::paginate;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::{MILLIS_PER_HOUR, MILLIS_PER_MINUTE};
+
+const MODULE: &str = "services::anomaly";
+
+// Anti-brigading: detect bursts of likes/reports on one piece of content driven by new accounts
+fn is_new_account(store: &Storage, principal: &Principal, settings: &AnomalyDetectionSettings, now: u64) -> bool {
+    store.users
+        .get(principal)
+        .is_none_or(|user| user.registered_at + settings.new_account_age_hours * MILLIS_PER_HOUR > now)
+}
+
+// Reject likes/reports from new accounts while the content is throttled
+pub fn check_engagement_throttle(content_id: &str, caller: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "check_engagement_throttle";
+
+    let now = time() / 1_000_000;
+    let throttled = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let settings = store.anomaly_settings.clone().unwrap_or_default();
+        store.throttled_content.get(content_id).is_some_and(|until| *until > now)
+            && is_new_account(&store, &caller, &settings, now)
+    });
+
+    if throttled {
+        return log_and_return(rate_limit_error("engage_with_flagged_content", 0, MODULE, FUNCTION)
+            .with_details("Interactions from new accounts are temporarily paused on this content"));
+    }
+    Ok(())
+}
+
+// Record a like/report and raise an alert when the window's engagement looks coordinated
+pub fn record_engagement(content_id: &str, content_type: ContentType, caller: Principal, kind: EngagementKind) {
+    let now = time() / 1_000_000;
+
+    let anomaly = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let settings = store.anomaly_settings.clone().unwrap_or_default();
+        if !settings.enabled {
+            return None;
+        }
+
+        let window_start = now.saturating_sub(settings.window_minutes * MILLIS_PER_MINUTE);
+        let events = store.recent_engagement.entry(content_id.to_string()).or_default();
+        events.retain(|event| event.timestamp >= window_start);
+        events.push(EngagementEvent { principal: caller, kind, timestamp: now });
+        if events.len() > MAX_ENGAGEMENT_EVENTS_PER_CONTENT {
+            let excess = events.len() - MAX_ENGAGEMENT_EVENTS_PER_CONTENT;
+            events.drain(..excess);
+        }
+        let events = events.clone();
+
+        // Content already under an active throttle has an open alert
+        if store.throttled_content.get(content_id).is_some_and(|until| *until > now) {
+            return None;
+        }
+
+        let new_account_events: Vec<&EngagementEvent> = events
+            .iter()
+            .filter(|event| is_new_account(&store, &event.principal, &settings, now))
+            .collect();
+        let new_count = new_account_events.len() as u64;
+        let total = events.len() as u64;
+        if new_count < settings.min_new_account_events || new_count * 100 < settings.min_new_account_percent * total {
+            return None;
+        }
+
+        let mut contributing_principals: Vec<Principal> = new_account_events
+            .iter()
+            .map(|event| event.principal)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        contributing_principals.sort();
+
+        let throttled_until = now + settings.throttle_minutes * MILLIS_PER_MINUTE;
+        let anomaly = EngagementAnomaly {
+            id: format!("anomaly_{}_{}", now, store.engagement_anomalies.len() + 1),
+            content_id: content_id.to_string(),
+            content_type,
+            detected_at: now,
+            window_likes: events.iter().filter(|event| event.kind == EngagementKind::Like).count() as u64,
+            window_reports: events.iter().filter(|event| event.kind == EngagementKind::Report).count() as u64,
+            new_account_events: new_count,
+            contributing_principals,
+            throttled_until,
+            status: AnomalyStatus::Open,
+            reviewed_by: None,
+            reviewed_at: None,
+        };
+        store.throttled_content.insert(content_id.to_string(), throttled_until);
+        store.engagement_anomalies.insert(anomaly.id.clone(), anomaly.clone());
+        Some(anomaly)
+    });
+
+    if let Some(anomaly) = anomaly {
+        notify_moderators(&anomaly);
+    }
+}
+
+fn notify_moderators(anomaly: &EngagementAnomaly) {
+    let moderators: HashSet<Principal> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.admin
+            .iter()
+            .chain(store.managers.iter().flatten())
+            .cloned()
+            .collect()
+    });
+
+    let message = format!(
+        "Abnormal engagement on {} from {} new accounts ({} likes, {} reports)",
+        anomaly.content_id,
+        anomaly.contributing_principals.len(),
+        anomaly.window_likes,
+        anomaly.window_reports
+    );
+    for moderator in moderators {
+        let _ = create_notification(
+            moderator,
+            NotificationType::System,
+            message.clone(),
+            Some(anomaly.id.clone()),
+            None
+        );
+    }
+}
+
+// Drop engagement windows and throttles that have lapsed; run from the heartbeat
+pub fn prune_engagement_tracking() {
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let window = store.anomaly_settings.clone().unwrap_or_default().window_minutes * MILLIS_PER_MINUTE;
+        store.recent_engagement.retain(|_, events| events.iter().any(|event| event.timestamp + window >= now));
+        store.throttled_content.retain(|_, until| *until > now);
+    });
+}
+
+// Moderation alerts
+pub fn get_engagement_anomalies(status: Option<AnomalyStatus>, pagination: PaginationParams) -> SquareResult<PaginatedResponse<EngagementAnomaly>> {
+    const FUNCTION: &str = "get_engagement_anomalies";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_engagement_anomalies",
+            "Only managers or admins can view engagement anomalies",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let mut anomalies: Vec<EngagementAnomaly> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.engagement_anomalies
+            .values()
+            .filter(|anomaly| status.as_ref().is_none_or(|s| anomaly.status == *s))
+            .cloned()
+            .collect()
+    });
+
+    // Newest first
+    anomalies.sort_by_key(|anomaly| std::cmp::Reverse(anomaly.detected_at));
+    Ok(paginate(anomalies, pagination))
+}
+
+pub fn review_engagement_anomaly(request: ReviewAnomalyRequest, caller: Principal) -> SquareResult<EngagementAnomaly> {
+    const FUNCTION: &str = "review_engagement_anomaly";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "review_engagement_anomaly",
+            "Only managers or admins can review engagement anomalies",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let Some(anomaly) = store.engagement_anomalies.get_mut(&request.anomaly_id) else {
+            return log_and_return(not_found_error("EngagementAnomaly", &request.anomaly_id, MODULE, FUNCTION));
+        };
+        if anomaly.status != AnomalyStatus::Open {
+            return log_and_return(invalid_operation_error(
+                "review_engagement_anomaly",
+                "Anomaly has already been reviewed",
+                MODULE,
+                FUNCTION
+            ));
+        }
+
+        anomaly.status = if request.confirm { AnomalyStatus::Confirmed } else { AnomalyStatus::Dismissed };
+        anomaly.reviewed_by = Some(caller);
+        anomaly.reviewed_at = Some(now);
+        let anomaly = anomaly.clone();
+
+        if !request.confirm {
+            store.throttled_content.remove(&anomaly.content_id);
+        }
+        Ok(anomaly)
+    })
+}
+
+pub fn get_anomaly_settings() -> SquareResult<AnomalyDetectionSettings> {
+    Ok(STORAGE.with(|storage| storage.borrow().anomaly_settings.clone().unwrap_or_default()))
+}
+
+pub fn update_anomaly_settings(request: UpdateAnomalySettingsRequest) -> SquareResult<AnomalyDetectionSettings> {
+    const FUNCTION: &str = "update_anomaly_settings";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_anomaly_settings",
+            "Only admins can update anomaly detection settings",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if request.min_new_account_percent.is_some_and(|percent| percent > 100) {
+        return log_and_return(field_validation_error(
+            "min_new_account_percent",
+            "must be between 0 and 100",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if request.window_minutes.is_some_and(|minutes| minutes == 0) {
+        return log_and_return(field_validation_error(
+            "window_minutes",
+            "must be greater than 0",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let mut settings = store.anomaly_settings.clone().unwrap_or_default();
+
+        if let Some(enabled) = request.enabled {
+            settings.enabled = enabled;
+        }
+        if let Some(window_minutes) = request.window_minutes {
+            settings.window_minutes = window_minutes;
+        }
+        if let Some(new_account_age_hours) = request.new_account_age_hours {
+            settings.new_account_age_hours = new_account_age_hours;
+        }
+        if let Some(min_new_account_events) = request.min_new_account_events {
+            settings.min_new_account_events = min_new_account_events;
+        }
+        if let Some(min_new_account_percent) = request.min_new_account_percent {
+            settings.min_new_account_percent = min_new_account_percent;
+        }
+        if let Some(throttle_minutes) = request.throttle_minutes {
+            settings.throttle_minutes = throttle_minutes;
+        }
+
+        store.anomaly_settings = Some(settings.clone());
+        Ok(settings)
+    })
+}
@@ -0,0 +1,42 @@
+use crate::models::api_version::*;
+use crate::models::error::{ErrorCode, ErrorSeverity, SquareError, SquareResult};
+use crate::utils::error_monitor;
+
+const MODULE: &str = "services::api_version";
+
+pub fn get_api_version() -> SquareResult<ApiVersionResponse> {
+    Ok(ApiVersionResponse {
+        version: API_VERSION.to_string(),
+        deprecated_methods: DEPRECATED_METHODS
+            .iter()
+            .map(|(method, replacement)| DeprecatedMethod {
+                method: method.to_string(),
+                replacement: replacement.map(str::to_string),
+                deprecated_since: LEGACY_DEPRECATED_SINCE.to_string(),
+                removal_version: LEGACY_REMOVAL_VERSION.to_string(),
+            })
+            .collect(),
+    })
+}
+
+fn deprecation_message(method: &str) -> String {
+    let replacement = DEPRECATED_METHODS
+        .iter()
+        .find(|(name, _)| *name == method)
+        .and_then(|(_, replacement)| *replacement);
+    match replacement {
+        Some(replacement) => format!("Deprecated method {} called; use {} before {}", method, replacement, LEGACY_REMOVAL_VERSION),
+        None => format!("Deprecated method {} called; it is removed in {}", method, LEGACY_REMOVAL_VERSION),
+    }
+}
+
+// Record a call to a deprecated update method as a warning in the error monitor, so operators can
+// see which old methods are still in use before they are removed
+pub fn record_deprecated_call(method: &str) {
+    error_monitor::record_error(&SquareError::new(ErrorCode::DeprecatedMethod, deprecation_message(method), MODULE, method, ErrorSeverity::Warning));
+}
+
+// Queries cannot keep what they write, so calls to deprecated query methods only go to the canister log
+pub fn log_deprecated_query(method: &str) {
+    ic_cdk::println!("[{}] {}", MODULE, deprecation_message(method));
+}
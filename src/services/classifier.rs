@@ -0,0 +1,298 @@
+use candid::Principal;
+use ic_cdk::api::management_canister::http_request::{
+    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs, TransformContext,
+};
+use ic_cdk::api::time;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::auth::{is_admin, is_manager_or_admin};
+use crate::models::classifier::*;
+use crate::models::content::{ContentStatus, ContentType, PaginationParams};
+use crate::models::display::PaginatedResponse;
+use crate::models::error::{SquareError, SquareResult};
+use crate::services::content::transitions::set_content_status;
+use crate::services::query_cache::{invalidate, POST_QUERIES};
+use crate::services::user::creator::paginate;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::logger;
+
+const MODULE: &str = "services::classifier";
+
+#[derive(Deserialize)]
+struct ClassifierResult {
+    label: String,
+    confidence: f64,
+}
+
+pub fn content_hash(text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(text.as_bytes());
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Cut text to at most `max_bytes` without splitting a character
+pub(crate) fn truncate_to_bytes(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+// POST a JSON body to an HTTPS API by outcall and parse its JSON answer; shared with translation
+pub(crate) async fn post_json<T: DeserializeOwned>(
+    url: &str,
+    auth_token: Option<&String>,
+    body: serde_json::Value,
+    max_response_bytes: u64,
+    cycles: u128,
+    service: &str
+) -> Result<T, String> {
+    let mut headers = vec![HttpHeader { name: "Content-Type".to_string(), value: "application/json".to_string() }];
+    if let Some(token) = auth_token {
+        headers.push(HttpHeader { name: "Authorization".to_string(), value: token.clone() });
+    }
+    let request = CanisterHttpRequestArgument {
+        url: url.to_string(),
+        method: HttpMethod::POST,
+        body: Some(body.to_string().into_bytes()),
+        max_response_bytes: Some(max_response_bytes),
+        transform: Some(TransformContext::from_name("transform_classifier_response".to_string(), vec![])),
+        headers,
+    };
+
+    let (response,) = http_request(request, cycles)
+        .await
+        .map_err(|(code, message)| format!("HTTP request failed with code {:?}: {}", code, message))?;
+    let status = response.status.to_string().parse::<u16>().unwrap_or(0);
+    if !(200..300).contains(&status) {
+        return Err(format!("{} returned status {}", service, status));
+    }
+    serde_json::from_slice(&response.body).map_err(|error| format!("Invalid {} response: {}", service.to_lowercase(), error))
+}
+
+// Queue new content for classification when the classifier is enabled; never blocks the write
+pub fn submit_for_classification(content_id: String, content_type: ContentType, text: String) {
+    let settings = STORAGE.with(|storage| storage.borrow().classifier_settings.clone().unwrap_or_default());
+    if !settings.enabled || settings.endpoint_url.is_empty() {
+        return;
+    }
+
+    ic_cdk::spawn(async move {
+        if let Err(error) = classify_content(settings, content_id.clone(), content_type, text).await {
+            logger::log(&format!("[{}] Classification of {} failed: {}", MODULE, content_id, error));
+        }
+    });
+}
+
+async fn classify_content(
+    settings: ClassifierSettings,
+    content_id: String,
+    content_type: ContentType,
+    text: String
+) -> Result<(), String> {
+    let hash = content_hash(&text);
+    let body = serde_json::json!({
+        "content_id": content_id,
+        "content_type": format!("{:?}", content_type),
+        "content_hash": hash,
+        "text": truncate_to_bytes(&text, MAX_CLASSIFIER_TEXT_BYTES),
+    });
+
+    let result: ClassifierResult = post_json(
+        &settings.endpoint_url,
+        settings.auth_token.as_ref(),
+        body,
+        CLASSIFIER_MAX_RESPONSE_BYTES,
+        settings.cycles_per_call,
+        "Classifier"
+    ).await?;
+
+    record_verdict(&settings, content_id, content_type, hash, result);
+    Ok(())
+}
+
+fn record_verdict(
+    settings: &ClassifierSettings,
+    content_id: String,
+    content_type: ContentType,
+    content_hash: String,
+    result: ClassifierResult
+) {
+    let now = time() / 1_000_000;
+    let over_threshold = !result.label.eq_ignore_ascii_case(CLASSIFIER_SAFE_LABEL)
+        && result.confidence >= settings.confidence_threshold;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+
+        // Only still-active content is flagged, and posts held for approval, which are already
+        // UnderReview and must not be auto-approved; moderators may already have acted on the rest
+        let held = store.pending_approvals.contains_key(&content_id);
+        let status = match content_type {
+            ContentType::Post => store.posts.get(&content_id).map(|post| post.status.clone()),
+            ContentType::Comment => store.comments.get(&content_id).map(|comment| comment.status.clone()),
+        };
+        let flagged = over_threshold
+            && (status == Some(ContentStatus::Active) || (held && status == Some(ContentStatus::UnderReview)));
+        // Leaving Active is a status change like any other, so stats, trending and replies follow
+        if flagged && status == Some(ContentStatus::Active) {
+            set_content_status(&mut store, ic_cdk::id(), &content_type, &content_id, ContentStatus::UnderReview);
+            invalidate(POST_QUERIES);
+        }
+
+        store.classifier_verdicts.insert(content_id.clone(), ClassifierVerdict {
+            content_id,
+            content_type,
+            content_hash,
+            label: result.label,
+            confidence: result.confidence,
+            flagged,
+            classified_at: now,
+        });
+    });
+}
+
+// Strip headers so replicas agree on the response; used by every `post_json` outcall
+pub fn transform_classifier_response(args: TransformArgs) -> HttpResponse {
+    HttpResponse {
+        status: args.response.status,
+        headers: vec![],
+        body: args.response.body,
+    }
+}
+
+fn settings_response(settings: ClassifierSettings) -> ClassifierSettingsResponse {
+    ClassifierSettingsResponse {
+        enabled: settings.enabled,
+        endpoint_url: settings.endpoint_url,
+        has_auth_token: settings.auth_token.is_some(),
+        confidence_threshold: settings.confidence_threshold,
+        cycles_per_call: settings.cycles_per_call,
+    }
+}
+
+pub fn get_classifier_settings() -> SquareResult<ClassifierSettingsResponse> {
+    const FUNCTION: &str = "get_classifier_settings";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_classifier_settings",
+            "Only admins can view classifier settings",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    Ok(settings_response(STORAGE.with(|storage| storage.borrow().classifier_settings.clone().unwrap_or_default())))
+}
+
+pub fn update_classifier_settings(request: UpdateClassifierSettingsRequest) -> SquareResult<ClassifierSettingsResponse> {
+    const FUNCTION: &str = "update_classifier_settings";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_classifier_settings",
+            "Only admins can update classifier settings",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if request.endpoint_url.as_ref().is_some_and(|url| !url.starts_with("https://") || url.len() > MAX_CLASSIFIER_URL_LENGTH) {
+        return log_and_return(field_validation_error(
+            "endpoint_url",
+            &format!("must be an https:// URL of at most {} characters", MAX_CLASSIFIER_URL_LENGTH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if request.confidence_threshold.is_some_and(|threshold| !(0.0..=1.0).contains(&threshold)) {
+        return log_and_return(field_validation_error(
+            "confidence_threshold",
+            "must be between 0 and 1",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let mut settings = store.classifier_settings.clone().unwrap_or_default();
+
+        if let Some(endpoint_url) = request.endpoint_url {
+            settings.endpoint_url = endpoint_url;
+        }
+        if let Some(auth_token) = request.auth_token {
+            settings.auth_token = Some(auth_token).filter(|token| !token.is_empty());
+        }
+        if let Some(confidence_threshold) = request.confidence_threshold {
+            settings.confidence_threshold = confidence_threshold;
+        }
+        if let Some(cycles_per_call) = request.cycles_per_call {
+            settings.cycles_per_call = cycles_per_call;
+        }
+        if let Some(enabled) = request.enabled {
+            settings.enabled = enabled;
+        }
+        if settings.enabled && settings.endpoint_url.is_empty() {
+            return log_and_return(field_validation_error(
+                "endpoint_url",
+                "must be set before enabling the classifier",
+                MODULE,
+                FUNCTION
+            ));
+        }
+
+        store.classifier_settings = Some(settings.clone());
+        Ok(settings_response(settings))
+    })
+}
+
+// Classifier verdicts for moderators, newest first
+pub fn get_classifier_verdicts(flagged_only: bool, pagination: PaginationParams) -> SquareResult<PaginatedResponse<ClassifierVerdict>> {
+    const FUNCTION: &str = "get_classifier_verdicts";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_classifier_verdicts",
+            "Only managers or admins can view classifier verdicts",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let mut verdicts: Vec<ClassifierVerdict> = STORAGE.with(|storage| {
+        storage.borrow()
+            .classifier_verdicts
+            .values()
+            .filter(|verdict| !flagged_only || verdict.flagged)
+            .cloned()
+            .collect()
+    });
+    verdicts.sort_by_key(|verdict| std::cmp::Reverse(verdict.classified_at));
+    Ok(paginate(verdicts, pagination))
+}
+
+pub fn get_classifier_verdict(content_id: String) -> SquareResult<ClassifierVerdict> {
+    const FUNCTION: &str = "get_classifier_verdict";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_classifier_verdict",
+            "Only managers or admins can view classifier verdicts",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    STORAGE.with(|storage| {
+        match storage.borrow().classifier_verdicts.get(&content_id) {
+            Some(verdict) => Ok(verdict.clone()),
+            None => log_and_return(not_found_error("ClassifierVerdict", &content_id, MODULE, FUNCTION)),
+        }
+    })
+}
@@ -0,0 +1,203 @@
+use sha2::{Digest, Sha256};
+
+use crate::models::anchoring::*;
+use crate::models::content::{ContentFormat, ContentStatus, Post};
+use crate::models::error::SquareResult;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::logger;
+use crate::utils::merkle::{hash_from_hex, leaf_hash, merkle_proof, merkle_root, to_hex, Hash};
+use crate::utils::time_utils::{day_start, now_millis, TimestampMillis};
+
+const MODULE: &str = "services::content::anchoring";
+
+fn format_name(format: &ContentFormat) -> &'static str {
+    match format {
+        ContentFormat::Html => "html",
+        ContentFormat::Markdown => "markdown",
+        ContentFormat::Plain => "plain",
+    }
+}
+
+// SHA-256 over the fields an author writes, as a client can recompute it from a PostResponse. Each
+// field is its UTF-8 bytes, or the principal's raw bytes for the author, preceded by its length as
+// a little-endian u64, in this order: id, author, format ("html", "markdown" or "plain"), title,
+// content, cover_image, thumbnail, then each media URL. Missing optional fields are empty.
+// Changing the recipe means raising CONTENT_HASH_RECIPE.
+pub(crate) fn post_content_hash(post: &Post) -> String {
+    let mut hasher = Sha256::new();
+    let mut field = |bytes: &[u8]| {
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
+    };
+    field(post.id.as_bytes());
+    field(post.author.as_slice());
+    field(format_name(&post.format).as_bytes());
+    field(post.title.as_deref().unwrap_or_default().as_bytes());
+    field(post.content.as_bytes());
+    field(post.cover_image.as_deref().unwrap_or_default().as_bytes());
+    field(post.thumbnail.as_deref().unwrap_or_default().as_bytes());
+    for url in &post.media_urls {
+        field(url.as_bytes());
+    }
+    to_hex(&hasher.finalize())
+}
+
+// Record a post's hash after it is created or edited; edits that leave the hashed fields alone
+// record nothing
+pub(crate) fn stamp_content_hash(post: &mut Post, pending: &mut Vec<ContentHashRecord>, now: TimestampMillis) {
+    let content_hash = post_content_hash(post);
+    if post.content_hash.as_ref() == Some(&content_hash) {
+        return;
+    }
+    post.content_hash = Some(content_hash.clone());
+    pending.push(ContentHashRecord {
+        content_id: post.id.clone(),
+        content_hash,
+        recorded_at: now,
+    });
+}
+
+// Rehash posts whose hash predates CONTENT_HASH_RECIPE, a batch per heartbeat, so the next daily
+// anchor includes them
+pub fn run_content_hash_backfill() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        if store.content_hash_recipe >= CONTENT_HASH_RECIPE {
+            return;
+        }
+
+        let ids = store.posts.keys_after(store.content_hash_cursor.as_ref(), CONTENT_HASH_BATCH_SIZE);
+        for id in &ids {
+            let stale = store.posts.get(id).is_some_and(|post| post.content_hash.as_ref() != Some(&post_content_hash(post)));
+            if let Some(mut post) = store.posts.get_mut(id).filter(|_| stale) {
+                let written_at = post.updated_at;
+                stamp_content_hash(&mut post, &mut store.pending_content_hashes, written_at);
+            }
+        }
+        store.content_hash_cursor = ids.last().cloned();
+        if ids.len() < CONTENT_HASH_BATCH_SIZE {
+            store.content_hash_recipe = CONTENT_HASH_RECIPE;
+            store.content_hash_cursor = None;
+            logger::log(&format!("[{}] Rehashed posts with content hash recipe {}", MODULE, CONTENT_HASH_RECIPE));
+        }
+    });
+}
+
+fn record_leaf(record: &ContentHashRecord) -> Hash {
+    leaf_hash(format!("{}\0{}", record.content_id, record.content_hash).as_bytes())
+}
+
+fn chain_hash(previous_chain: &Hash, root: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(previous_chain);
+    hasher.update(root);
+    hasher.finalize().into()
+}
+
+// Anchor the hashes recorded since the last anchor, at most once a UTC day. Run on full heartbeat runs.
+pub fn anchor_content_hashes() {
+    let now = now_millis();
+    let chain = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let anchored_today = store.content_anchors
+            .last()
+            .is_some_and(|anchor| anchor.anchored_at >= day_start(now));
+        if anchored_today || store.pending_content_hashes.is_empty() {
+            return None;
+        }
+
+        let leaves: Vec<ContentHashRecord> = std::mem::take(&mut store.pending_content_hashes);
+        let root = merkle_root(&leaves.iter().map(record_leaf).collect::<Vec<_>>())?;
+        let previous_chain = store.content_anchors
+            .last()
+            .and_then(|anchor| hash_from_hex(&anchor.chain))
+            .unwrap_or([0; 32]);
+        let chain = chain_hash(&previous_chain, &root);
+        store.content_anchors.push(ContentAnchor {
+            root: to_hex(&root),
+            previous_chain: to_hex(&previous_chain),
+            chain: to_hex(&chain),
+            anchored_at: now,
+            leaves,
+        });
+        if store.content_anchors.len() > MAX_CONTENT_ANCHORS {
+            let excess = store.content_anchors.len() - MAX_CONTENT_ANCHORS;
+            store.content_anchors.drain(..excess);
+        }
+        // Older anchors keep their root for the chain but give up their leaves
+        let with_leaves_from = store.content_anchors.len().saturating_sub(MAX_ANCHORS_WITH_LEAVES);
+        for anchor in &mut store.content_anchors[..with_leaves_from] {
+            anchor.leaves = Vec::new();
+        }
+        Some(chain)
+    });
+
+    if let Some(chain) = chain {
+        ic_cdk::api::set_certified_data(&chain);
+        logger::log(&format!("[{}] Anchored content hashes, chain {}", MODULE, to_hex(&chain)));
+    }
+}
+
+// Certified data does not survive an upgrade; set it again from the latest anchor
+pub fn restore_certified_anchor() {
+    let chain = STORAGE.with(|storage| {
+        storage.borrow().content_anchors.last().and_then(|anchor| hash_from_hex(&anchor.chain))
+    });
+    if let Some(chain) = chain {
+        ic_cdk::api::set_certified_data(&chain);
+    }
+}
+
+// Check a post against the hash recorded when it was last written, and prove that hash was
+// anchored in the canister's certified data
+pub fn verify_content_integrity(content_id: String) -> SquareResult<ContentIntegrityResponse> {
+    const FUNCTION: &str = "verify_content_integrity";
+
+    let mut response = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let Some(post) = store.posts.get(&content_id).filter(|post| post.status != ContentStatus::LegalHold) else {
+            return log_and_return(not_found_error("Post", &content_id, MODULE, FUNCTION));
+        };
+        let computed_hash = post_content_hash(post);
+
+        // The newest anchor holding the recorded hash, and the roots chained after it
+        let anchor = post.content_hash.as_ref().and_then(|content_hash| {
+            store.content_anchors.iter().enumerate().rev().find_map(|(position, anchor)| {
+                let leaf_index = anchor.leaves
+                    .iter()
+                    .rposition(|record| record.content_id == content_id && &record.content_hash == content_hash)?;
+                let leaves: Vec<Hash> = anchor.leaves.iter().map(record_leaf).collect();
+                Some(ContentAnchorProof {
+                    anchored_at: anchor.anchored_at,
+                    root: anchor.root.clone(),
+                    previous_chain: anchor.previous_chain.clone(),
+                    leaf_index: leaf_index as u64,
+                    proof: merkle_proof(&leaves, leaf_index)
+                        .into_iter()
+                        .map(|(sibling, sibling_on_left)| MerkleProofStep {
+                            sibling: to_hex(&sibling),
+                            sibling_on_left,
+                        })
+                        .collect(),
+                    later_roots: store.content_anchors[position + 1..].iter().map(|later| later.root.clone()).collect(),
+                })
+            })
+        });
+
+        Ok(ContentIntegrityResponse {
+            content_id: content_id.clone(),
+            intact: post.content_hash.as_ref() == Some(&computed_hash),
+            content_hash: post.content_hash.clone(),
+            computed_hash,
+            anchor,
+            certified_chain: store.content_anchors.last().map(|anchor| anchor.chain.clone()),
+            certificate: None,
+        })
+    })?;
+
+    response.certificate = ic_cdk::api::data_certificate();
+    Ok(response)
+}
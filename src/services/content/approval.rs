@@ -0,0 +1,268 @@
+// Approval mode: while it is enabled, posts from accounts that are young, have few active posts and
+// have not reached the auto-approval trust level are stored UnderReview and queued for moderators.
+// A held post is published when it is approved, so it is counted, fanned out and alerted on only
+// then. The heartbeat approves held posts whose author has since reached the trust level, except
+// those the classifier flagged.
+use candid::Principal;
+use ic_cdk::api::time;
+use std::collections::HashMap;
+
+use crate::auth::{is_admin, is_manager_or_admin};
+use crate::models::approval::*;
+use crate::models::content::{ContentStatus, ContentType, ModeratedContent, PaginationParams};
+use crate::models::display::PaginatedResponse;
+use crate::models::error::SquareResult;
+use crate::models::event::InteractionEventKind;
+use crate::models::notification::NotificationType;
+use crate::models::storage::Storage;
+use crate::models::user::UserModerationAction;
+use crate::services::content::posts::post_response;
+use crate::services::content::schedule::embargoed;
+use crate::services::content::timeline::fan_out_post;
+use crate::services::content::transitions::set_post_status;
+use crate::services::events::log_event;
+use crate::services::query_cache::{invalidate, POST_QUERIES};
+use crate::services::user::admin::record_user_moderation;
+use crate::services::user::creator::paginate;
+use crate::services::user::notification::push_notification;
+use crate::services::user::post_alerts::enqueue_post_alerts;
+use crate::services::user::trust::{active_posts, compute_trust_level};
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::MILLIS_PER_DAY;
+
+const MODULE: &str = "services::content::approval";
+
+fn approval_settings(store: &Storage) -> ContentApprovalSettings {
+    store.content_approval.clone().unwrap_or_default()
+}
+
+// Held posts the classifier flagged wait for a moderator even once their author is trusted
+fn flagged_by_classifier(store: &Storage, post_id: &str) -> bool {
+    store.classifier_verdicts.get(post_id).is_some_and(|verdict| verdict.flagged)
+}
+
+// Whether a new post by `author` waits for approval; callers exempt admins and managers
+pub(crate) fn requires_approval(store: &Storage, author: Principal, now: u64) -> bool {
+    let settings = approval_settings(store);
+    if !settings.enabled {
+        return false;
+    }
+    let account_age_days = store.users
+        .get(&author)
+        .map_or(0, |user| now.saturating_sub(user.registered_at) / MILLIS_PER_DAY);
+    account_age_days < settings.new_account_days
+        && active_posts(store, &author) < settings.held_posts
+        && compute_trust_level(store, author, now).level < settings.auto_approve_level
+}
+
+pub(crate) fn hold_post(store: &mut Storage, post_id: &str, author: Principal, now: u64) {
+    store.pending_approvals.insert(post_id.to_string(), PendingApproval {
+        post_id: post_id.to_string(),
+        author,
+        submitted_at: now,
+    });
+}
+
+// Make a stored post public: count it and send it to followers' timelines and post alerts
+pub(crate) fn publish_post(store: &mut Storage, actor: Principal, post_id: &str) {
+    let Some(post) = store.posts.get(post_id) else {
+        return;
+    };
+    let (author, hashtags) = (post.author, post.hashtags.clone());
+    fan_out_post(store, post_id);
+    enqueue_post_alerts(store, post_id);
+    log_event(store, actor, InteractionEventKind::PostCreated {
+        post_id: post_id.to_string(),
+        author,
+        hashtags,
+    });
+}
+
+// Publish a held post. Returns false when it is no longer held, for example because a moderator
+// already restored or removed it.
+fn approve_post(store: &mut Storage, actor: Principal, post_id: &str, now: u64) -> bool {
+    store.pending_approvals.remove(post_id);
+    // An approved post that is still embargoed is published when the embargo lifts
+    let embargoed = embargoed(store, post_id, now);
+    let Some(mut post) = store.posts.get_mut(post_id).filter(|post| post.status == ContentStatus::UnderReview) else {
+        return false;
+    };
+    post.status = if embargoed { ContentStatus::Scheduled } else { ContentStatus::Active };
+    post.updated_at = now;
+    let author = post.author;
+    drop(post);
+    let content = if embargoed {
+        "Your post was approved and will be published when its embargo lifts"
+    } else {
+        publish_post(store, actor, post_id);
+        "Your post was approved and is now public"
+    };
+    push_notification(store, author, NotificationType::System, content.to_string(), Some(post_id.to_string()), None, now);
+    true
+}
+
+// Held posts still waiting, oldest first; moderators may have acted on some through moderate_content
+fn pending_posts(store: &Storage) -> Vec<PendingApproval> {
+    let mut pending: Vec<PendingApproval> = store.pending_approvals
+        .values()
+        .filter(|pending| store.posts.get(&pending.post_id).is_some_and(|post| post.status == ContentStatus::UnderReview))
+        .cloned()
+        .collect();
+    pending.sort_by(|a, b| a.submitted_at.cmp(&b.submitted_at).then_with(|| a.post_id.cmp(&b.post_id)));
+    pending
+}
+
+pub fn get_approval_queue(pagination: PaginationParams) -> SquareResult<PaginatedResponse<PendingPostResponse>> {
+    const FUNCTION: &str = "get_approval_queue";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_approval_queue",
+            "Only managers and admins can review held posts",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let now = time() / 1_000_000;
+    let page = STORAGE.with(|storage| paginate(pending_posts(&storage.borrow()), pagination));
+    let items = page.items
+        .into_iter()
+        .map(|pending| {
+            let trust = STORAGE.with(|storage| compute_trust_level(&storage.borrow(), pending.author, now));
+            Ok(PendingPostResponse {
+                post: post_response(pending.post_id, true)?,
+                submitted_at: pending.submitted_at,
+                author_trust_level: trust.level,
+                account_age_days: trust.account_age_days,
+            })
+        })
+        .collect::<SquareResult<Vec<_>>>()?;
+
+    Ok(PaginatedResponse {
+        items,
+        total: page.total,
+        has_more: page.has_more,
+        next_offset: page.next_offset,
+    })
+}
+
+// Approve a held post, publishing it, or reject it, which removes it
+pub fn review_pending_post(request: ReviewPendingPostRequest, caller: Principal) -> SquareResult<ModeratedContent> {
+    const FUNCTION: &str = "review_pending_post";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "review_pending_post",
+            "Only managers and admins can review held posts",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    invalidate(POST_QUERIES);
+
+    let now = time() / 1_000_000;
+    let (author, status) = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let held = store.pending_approvals
+            .get(&request.post_id)
+            .map(|pending| pending.author)
+            .filter(|_| store.posts.get(&request.post_id).is_some_and(|post| post.status == ContentStatus::UnderReview));
+        let Some(author) = held else {
+            return log_and_return(not_found_error("Held post", &request.post_id, MODULE, FUNCTION));
+        };
+
+        if request.approve {
+            approve_post(store, caller, &request.post_id, now);
+            return Ok((author, ContentStatus::Active));
+        }
+        store.pending_approvals.remove(&request.post_id);
+        set_post_status(store, caller, &request.post_id, ContentStatus::Removed);
+        if let Some(mut post) = store.posts.get_mut(&request.post_id) {
+            post.updated_at = now;
+        }
+        let content = match &request.reason {
+            Some(reason) => format!("Your post was not approved: {}", reason),
+            None => "Your post was not approved".to_string(),
+        };
+        push_notification(store, author, NotificationType::System, content, Some(request.post_id.clone()), None, now);
+        Ok((author, ContentStatus::Removed))
+    })?;
+
+    // A rejection counts against the author's trust like any removal
+    if status == ContentStatus::Removed {
+        record_user_moderation(
+            author,
+            UserModerationAction::ContentModerated(ContentStatus::Removed),
+            request.reason,
+            Some(request.post_id.clone())
+        );
+    }
+
+    Ok(ModeratedContent {
+        content_id: request.post_id,
+        content_type: ContentType::Post,
+        status,
+        updated_at: now,
+    })
+}
+
+// Approve held posts whose author has reached the auto-approval trust level, unless the classifier
+// flagged them, and forget posts moderators have already acted on
+pub fn run_auto_approvals() {
+    let now = time() / 1_000_000;
+    let approved = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        if store.pending_approvals.is_empty() {
+            return 0;
+        }
+        let level = approval_settings(store).auto_approve_level;
+        let held: Vec<PendingApproval> = store.pending_approvals.values().cloned().collect();
+        // Trust is checked once per author; approving one of their posts does not change it
+        let mut trusted: HashMap<Principal, bool> = HashMap::new();
+        let mut approved = 0;
+        for pending in held {
+            if !store.posts.get(&pending.post_id).is_some_and(|post| post.status == ContentStatus::UnderReview) {
+                store.pending_approvals.remove(&pending.post_id);
+                continue;
+            }
+            if flagged_by_classifier(store, &pending.post_id) {
+                continue;
+            }
+            let is_trusted = *trusted
+                .entry(pending.author)
+                .or_insert_with(|| compute_trust_level(store, pending.author, now).level >= level);
+            if is_trusted {
+                approved += approve_post(store, pending.author, &pending.post_id, now) as usize;
+            }
+        }
+        approved
+    });
+    if approved > 0 {
+        invalidate(POST_QUERIES);
+    }
+}
+
+pub fn get_content_approval_settings() -> SquareResult<ContentApprovalSettings> {
+    Ok(STORAGE.with(|storage| approval_settings(&storage.borrow())))
+}
+
+// Takes effect for new posts; posts already held stay in the queue
+pub fn update_content_approval_settings(settings: ContentApprovalSettings) -> SquareResult<ContentApprovalSettings> {
+    const FUNCTION: &str = "update_content_approval_settings";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_content_approval_settings",
+            "Only admins can update approval mode",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| storage.borrow_mut().content_approval = Some(settings.clone()));
+    Ok(settings)
+}
@@ -5,32 +5,148 @@ use std::collections::{HashMap, HashSet};
 use crate::auth::is_admin;
 use crate::models::content::{
     CreateCommentRequest, UpdateCommentRequest, ContentStatus, ParentType,
-    ContentVisibility, ContentType, CommentResponse, CommentsResponse,
-    PaginationParams, MAX_COMMENT_LENGTH,
+    ContentVisibility, ContentType, ContentFormat, CommentResponse, CommentsResponse,
+    PaginationParams, PostResponse, MAX_COMMENT_LENGTH, QUOTED_POST_EXCERPT_LENGTH,
 };
 use crate::models::storage::Storage;
-use crate::utils::content_utils::calculate_content_length_excluding_base64_and_html;
+use crate::utils::content_utils::{calculate_content_length_excluding_base64_and_html, strip_html_tags};
+use crate::utils::validators::validate_comment_content;
 use crate::{SquareError, SquareResult};
-use crate::storage::{Comment, STORAGE};
+use crate::storage::{Comment, Post, STORAGE};
 use crate::utils::error_handler::*;
 use crate::services::user::social::get_user_social_info;
+use crate::services::user::{check_action_rate_limit, check_content_privileges};
+use crate::models::account_quality::RateLimitedAction;
+use crate::services::classifier::submit_for_classification;
+use crate::services::query_cache::{invalidate, ENGAGEMENT_QUERIES};
+use crate::services::token_gate::{check_content_gates, withhold_gated_content};
+use crate::services::translation::remove_translations;
+use crate::services::content::sanitizer::sanitize_content;
+use crate::services::content::rendering::{attach_rendered_comments, refresh_rendered_content};
+use crate::services::emoji::attach_comment_emojis;
+use crate::services::events::log_event;
+use crate::services::user::quota::{charge_storage, comment_size, release_storage, storage_quota};
+use crate::models::event::InteractionEventKind;
+use crate::utils::response_size::{next_offset_after_truncation, truncate_comments_to_budget, RESPONSE_SIZE_BUDGET};
+use crate::utils::pagination::page_bounds;
+use crate::services::discovery::tags::root_post;
+use crate::services::content::subscriptions::{auto_subscribe, record_thread_comment};
+use crate::services::content::cross_posts::canonical_post_id;
+use crate::services::content::transitions::{index_comment, remove_comment, thread_on_legal_hold};
+use crate::services::content::legal_hold::ensure_not_on_legal_hold;
 
+// Comparable form of a comment, so repeats differing only in case or spacing match
+fn normalized_comment(content: &str) -> String {
+    content.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+// Whether a new comment is self-engagement: it is in the author's own thread, or repeats a comment
+// the author already left in that thread. Such comments are kept but earn no task progress. Only the
+// author's own comments are compared, read from user_comments.
+pub(crate) fn excluded_from_engagement(store: &Storage, comment: &Comment, parent_content_type: &ContentType) -> bool {
+    let Some(root) = root_post(store, parent_content_type, &comment.parent_id) else {
+        return false;
+    };
+    if root.author == comment.author {
+        return true;
+    }
+    let content = normalized_comment(&comment.content);
+    store.user_comments
+        .get(&comment.author)
+        .into_iter()
+        .flatten()
+        .filter_map(|id| store.comments.get(id))
+        .filter(|previous| previous.author == comment.author && previous.status == ContentStatus::Active)
+        .filter(|previous| normalized_comment(&previous.content) == content)
+        .any(|previous| root_post(store, &ContentType::Comment, &previous.id).is_some_and(|post| post.id == root.id))
+}
+
+// Only posts anyone can read may be quoted, since the comment may be read by anyone
+fn quotable(post: &Post) -> bool {
+    post.status == ContentStatus::Active && post.visibility == ContentVisibility::Public
+}
 
-pub fn create_comment(request: CreateCommentRequest, caller: Principal) -> SquareResult<CommentResponse> {
+// A quoted post as shown inside a comment: its title or the start of its text and its thumbnail,
+// without other media, tags or engagement details. Token-gated posts keep their gate and withhold the
+// excerpt, and premium posts are quoted without one.
+fn quoted_post_response(post_id: &str) -> Option<PostResponse> {
+    let post = STORAGE.with(|storage| storage.borrow().posts.get(post_id).filter(|post| quotable(post)).cloned())?;
+    let author_info = get_user_social_info(post.author.to_string(), None).ok()?;
+    let excerpt = if post.is_premium {
+        String::new()
+    } else {
+        strip_html_tags(&post.content).chars().take(QUOTED_POST_EXCERPT_LENGTH).collect()
+    };
+    Some(withhold_gated_content(PostResponse {
+        content: excerpt,
+        id: post.id,
+        author: post.author,
+        media_urls: Vec::new(),
+        hashtags: Vec::new(),
+        token_mentions: Vec::new(),
+        tags: Vec::new(),
+        created_at: post.created_at,
+        updated_at: post.updated_at,
+        status: post.status,
+        visibility: post.visibility,
+        likes_count: 0,
+        comments_count: 0,
+        author_info,
+        news_reference: None,
+        posted_by: post.posted_by,
+        title: post.title,
+        is_premium: post.is_premium,
+        license: post.license,
+        token_gate: post.token_gate,
+        liked_by_followed: Vec::new(),
+        summary: None,
+        format: ContentFormat::Plain,
+        source: None,
+        reactions: Vec::new(),
+        emojis: Vec::new(),
+        content_hash: post.content_hash,
+        cross_post_of: post.cross_post_of,
+        table_of_contents: Vec::new(),
+        cover_image: None,
+        thumbnail: post.thumbnail,
+    }))
+}
+
+// Embed the post each comment quotes, replies included
+fn attach_quoted_posts(comments: &mut [CommentResponse]) {
+    for comment in comments.iter_mut() {
+        comment.quoted_post = comment.quoted_post_id.as_deref().and_then(quoted_post_response);
+        for child in comment.child_comments.iter_mut() {
+            attach_quoted_posts(std::slice::from_mut(&mut **child));
+        }
+    }
+}
+
+pub fn create_comment(mut request: CreateCommentRequest, caller: Principal) -> SquareResult<CommentResponse> {
     const MODULE: &str = "services::content::comments";
     const FUNCTION: &str = "create_comment";
     
+    invalidate(ENGAGEMENT_QUERIES);
+    
+    // Markdown and Plain sources are sanitized when rendered instead
+    let format = request.format.unwrap_or_default();
+    if format == ContentFormat::Html {
+        request.content = sanitize_content(&request.content);
+    }
     // Validate content length
-    let content_length = calculate_content_length_excluding_base64_and_html(&request.content);
-    if content_length > MAX_COMMENT_LENGTH {
-        return log_and_return(content_too_long_error(
-            "Comment",
-            MAX_COMMENT_LENGTH,
-            content_length,
-            MODULE,
-            FUNCTION
-        ));
+    validate_comment_content(&request.content).finish(MODULE, FUNCTION)?;
+    check_action_rate_limit(caller, RateLimitedAction::Comment)?;
+    // Links in comments are gated by the author's trust level like in posts
+    check_content_privileges(caller, &request.content, 0)?;
+    // Comments on a cross-post join the thread of its original
+    if request.parent_type == ParentType::Post {
+        request.parent_id = STORAGE.with(|storage| canonical_post_id(&storage.borrow(), &request.parent_id));
     }
+    let parent_content_type = match request.parent_type {
+        ParentType::Post => ContentType::Post,
+        ParentType::Comment => ContentType::Comment,
+    };
+    check_content_gates(caller, &parent_content_type, &request.parent_id)?;
     
     let now = time() / 1_000_000;
     let comment_id = format!("comment_{}", now);
@@ -46,13 +162,16 @@ pub fn create_comment(request: CreateCommentRequest, caller: Principal) -> Squar
         status: ContentStatus::Active,
         likes_count: 0,
         child_comments: Vec::new(),
+        format,
+        quoted_post_id: request.quoted_post_id.clone(),
     };
     
-    // Store comment in main storage
-    STORAGE.with(|storage| {
+    // Store comment in main storage, within the author's storage quota
+    let quota = storage_quota(caller);
+    let mut response = STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         
-        // Add comment to parent's child_comments list
+        // The parent must be active; a reply is added to its child_comments once everything else checks out
         match request.parent_type {
             ParentType::Post => {
                 if let Some(post) = store.posts.get(&request.parent_id) {
@@ -73,7 +192,7 @@ pub fn create_comment(request: CreateCommentRequest, caller: Principal) -> Squar
                 }
             }
             ParentType::Comment => {
-                if let Some(parent_comment) = store.comments.get_mut(&request.parent_id) {
+                if let Some(parent_comment) = store.comments.get(&request.parent_id) {
                     if parent_comment.status != ContentStatus::Active {
                         return log_and_return(validation_error(
                             "Cannot reply to an inactive comment",
@@ -81,7 +200,6 @@ pub fn create_comment(request: CreateCommentRequest, caller: Principal) -> Squar
                             FUNCTION
                         ));
                     }
-                    parent_comment.child_comments.push(comment_id.clone());
                 } else {
                     return log_and_return(not_found_error(
                         "Parent comment",
@@ -93,7 +211,39 @@ pub fn create_comment(request: CreateCommentRequest, caller: Principal) -> Squar
             }
         }
         
+        if let Some(quoted_post_id) = &comment.quoted_post_id {
+            match store.posts.get(quoted_post_id).filter(|post| post.status != ContentStatus::LegalHold) {
+                Some(post) if quotable(post) => {}
+                Some(_) => return log_and_return(invalid_operation_error(
+                    "create_comment",
+                    "Only public, active posts can be quoted",
+                    MODULE,
+                    FUNCTION
+                )),
+                None => return log_and_return(not_found_error(
+                    "Quoted post",
+                    quoted_post_id,
+                    MODULE,
+                    FUNCTION
+                )),
+            }
+        }
+        
+        charge_storage(&mut store.storage_usage, caller, 0, comment_size(&comment), quota)?;
+        if let Some(mut parent_comment) = store.comments.get_mut(&request.parent_id).filter(|_| request.parent_type == ParentType::Comment) {
+            parent_comment.child_comments.push(comment_id.clone());
+        }
+        let excluded = excluded_from_engagement(&store, &comment, &parent_content_type);
         store.comments.insert(comment_id.clone(), comment.clone());
+        index_comment(&mut store, &comment);
+        refresh_rendered_content(&mut store, &comment_id);
+        if let Some(thread) = root_post(&store, &parent_content_type, &comment.parent_id).map(|post| post.id.clone()) {
+            if request.subscribe == Some(true) {
+                auto_subscribe(&mut store, &thread, caller);
+            }
+            record_thread_comment(&mut store, &thread, caller, now);
+        }
+        log_event(&mut store, caller, InteractionEventKind::CommentCreated { comment_id: comment_id.clone(), author: comment.author, excluded });
         Ok(CommentResponse {
             comments_count: 0,
             is_liked: false,
@@ -108,19 +258,31 @@ pub fn create_comment(request: CreateCommentRequest, caller: Principal) -> Squar
             visibility: ContentVisibility::Public,
             likes_count: comment.likes_count,
             child_comments: Vec::new(),
-            author_info: get_user_social_info(comment.author.to_string(), None)?
+            author_info: get_user_social_info(comment.author.to_string(), None)?,
+            format: comment.format,
+            source: None,
+            reactions: Vec::new(),
+            emojis: Vec::new(),
+            quoted_post_id: comment.quoted_post_id,
+            quoted_post: None,
         })
-    })
+    })?;
+    
+    submit_for_classification(response.id.clone(), ContentType::Comment, response.content.clone());
+    attach_rendered_comments(std::slice::from_mut(&mut response));
+    attach_comment_emojis(std::slice::from_mut(&mut response));
+    attach_quoted_posts(std::slice::from_mut(&mut response));
+    Ok(response)
 }
 
 pub fn get_comment(id: String, caller: Option<Principal>) -> SquareResult<CommentResponse> {
     const MODULE: &str = "services::content::comments";
     const FUNCTION: &str = "get_comment";
     
-    STORAGE.with(|storage| {
+    let mut response: CommentResponse = STORAGE.with(|storage| -> SquareResult<CommentResponse> {
         let store = storage.borrow();
         
-        let comment = store.comments.get(&id).ok_or_else(|| {
+        let comment = store.comments.get(&id).filter(|comment| comment.status != ContentStatus::LegalHold).ok_or_else(|| {
             not_found_error("Comment", &id, MODULE, FUNCTION)
         })?;
         
@@ -134,17 +296,29 @@ pub fn get_comment(id: String, caller: Option<Principal>) -> SquareResult<Commen
         }
         
         Ok(comment.clone().into())
-    })
+    })?;
+    
+    attach_rendered_comments(std::slice::from_mut(&mut response));
+    attach_comment_emojis(std::slice::from_mut(&mut response));
+    attach_quoted_posts(std::slice::from_mut(&mut response));
+    Ok(response)
 }
 
 pub fn update_comment(request: UpdateCommentRequest, caller: Principal) -> SquareResult<CommentResponse> {
     const MODULE: &str = "services::content::comments";
     const FUNCTION: &str = "update_comment";
     
-    STORAGE.with(|storage| {
+    let current = STORAGE.with(|storage| storage.borrow().comments.get(&request.id).map(|comment| (comment.format, comment.author)));
+    let format = request.format.or(current.map(|(format, _)| format)).unwrap_or_default();
+    let quota = current.and_then(|(_, author)| storage_quota(author));
+    let content = if format == ContentFormat::Html { sanitize_content(&request.content) } else { request.content };
+    
+    let mut response = STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
+        let store = &mut *store;
         
-        let comment = store.comments.get_mut(&request.id).ok_or_else(|| {
+        ensure_not_on_legal_hold(store, &request.id, "update_comment", MODULE, FUNCTION)?;
+        let mut comment = store.comments.get_mut(&request.id).ok_or_else(|| {
             not_found_error("Comment", &request.id, MODULE, FUNCTION)
         })?;
         
@@ -157,20 +331,13 @@ pub fn update_comment(request: UpdateCommentRequest, caller: Principal) -> Squar
             ));
         }
         
-        // Update content
-        let content = &request.content;
-        // Validate content length
-        let content_length = calculate_content_length_excluding_base64_and_html(&content);
-        if content_length > MAX_COMMENT_LENGTH {
-            return log_and_return(content_too_long_error(
-                "Comment",
-                MAX_COMMENT_LENGTH,
-                content_length,
-                MODULE,
-                FUNCTION
-            ));
-        }
-        comment.content = content.clone();
+        // Update content; growth counts against the author's storage quota
+        validate_comment_content(&content).finish(MODULE, FUNCTION)?;
+        let old_size = comment_size(&comment);
+        let new_size = old_size - comment.content.len() as u64 + content.len() as u64;
+        charge_storage(&mut store.storage_usage, comment.author, old_size, new_size, quota)?;
+        comment.content = content;
+        comment.format = format;
         
         comment.updated_at = time() / 1_000_000;
         
@@ -188,15 +355,29 @@ pub fn update_comment(request: UpdateCommentRequest, caller: Principal) -> Squar
             status: comment.status.clone(),
             likes_count: comment.likes_count,
             child_comments: get_child_comments(&comment.child_comments, Some(caller))?,
-            author_info: get_user_social_info(comment.author.to_string(), None)?
+            author_info: get_user_social_info(comment.author.to_string(), None)?,
+            format: comment.format,
+            source: None,
+            reactions: Vec::new(),
+            emojis: Vec::new(),
+            quoted_post_id: comment.quoted_post_id.clone(),
+            quoted_post: None,
         })
-    })
+    })?;
+    
+    STORAGE.with(|storage| refresh_rendered_content(&mut storage.borrow_mut(), &request.id));
+    attach_rendered_comments(std::slice::from_mut(&mut response));
+    attach_comment_emojis(std::slice::from_mut(&mut response));
+    attach_quoted_posts(std::slice::from_mut(&mut response));
+    Ok(response)
 }
 
 pub fn delete_comment(id: String, caller: Principal) -> SquareResult<()> {
     const MODULE: &str = "services::content::comments";
     const FUNCTION: &str = "delete_comment";
     
+    invalidate(ENGAGEMENT_QUERIES);
+    
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         
@@ -216,13 +397,22 @@ pub fn delete_comment(id: String, caller: Principal) -> SquareResult<()> {
         let parent_id = comment.parent_id.clone();
         let parent_type = comment.parent_type;
         
+        if thread_on_legal_hold(&store, &ContentType::Comment, &id) {
+            return log_and_return(invalid_operation_error(
+                "delete_comment",
+                "The comment or one of its replies is on legal hold",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        
         // Remove comment from storage first
-        store.comments.remove(&id);
+        remove_comment(&mut store, caller, &id);
         
         // Then update parent's child_comments list
         match parent_type {
             ParentType::Comment => {
-                if let Some(parent_comment) = store.comments.get_mut(&parent_id) {
+                if let Some(mut parent_comment) = store.comments.get_mut(&parent_id) {
                     parent_comment.child_comments.retain(|c| c != &id);
                 }
             }
@@ -240,7 +430,7 @@ pub fn get_child_comments(comment_ids: &Vec<String>, caller: Option<Principal>)
         let mut child_comments: Vec<Box<CommentResponse>> = Vec::new();
         
         for child_id in comment_ids {
-            if let Some(comment) = store.comments.get(child_id) {
+            if let Some(comment) = store.comments.get(child_id).filter(|comment| comment.status != ContentStatus::LegalHold) {
                 // Skip private comments if caller is not the author
                 if caller.is_none() || caller != Some(comment.author) {
                     continue;
@@ -260,7 +450,13 @@ pub fn get_child_comments(comment_ids: &Vec<String>, caller: Option<Principal>)
                     status: comment.status.clone(),
                     likes_count: comment.likes_count,
                     child_comments: get_child_comments(&comment.child_comments, caller)?,
-                    author_info: get_user_social_info(comment.author.to_string(), None)?
+                    author_info: get_user_social_info(comment.author.to_string(), None)?,
+                    format: comment.format,
+                    source: None,
+                    reactions: Vec::new(),
+                    emojis: Vec::new(),
+                    quoted_post_id: comment.quoted_post_id.clone(),
+                    quoted_post: None,
                 };
                 child_comments.push(Box::new(child_response));
             }
@@ -270,7 +466,7 @@ pub fn get_child_comments(comment_ids: &Vec<String>, caller: Option<Principal>)
     })
 }
 
-pub fn get_comments(parent_id: String, parent_type_str: String, pagination: PaginationParams, caller: Option<Principal>) -> SquareResult<CommentsResponse> {
+pub fn get_comments(mut parent_id: String, parent_type_str: String, pagination: PaginationParams, caller: Option<Principal>) -> SquareResult<CommentsResponse> {
     const MODULE: &str = "services::content::comments";
     const FUNCTION: &str = "get_comments";
     
@@ -283,9 +479,9 @@ pub fn get_comments(parent_id: String, parent_type_str: String, pagination: Pagi
             FUNCTION
         )),
     };
-    
-    let limit = pagination.limit;
-    let offset = pagination.offset;
+    if parent_type == ParentType::Post {
+        parent_id = STORAGE.with(|storage| canonical_post_id(&storage.borrow(), &parent_id));
+    }
     
     // Get comments from storage
     let mut comments = STORAGE.with(|storage| {
@@ -293,6 +489,7 @@ pub fn get_comments(parent_id: String, parent_type_str: String, pagination: Pagi
         store.comments
             .values()
             .filter(|c| c.parent_id == parent_id && c.parent_type == parent_type)
+            .filter(|c| c.status == ContentStatus::Active || (Some(c.author) == caller && c.status != ContentStatus::LegalHold))
             .cloned()
             .collect::<Vec<Comment>>()
     });
@@ -302,9 +499,7 @@ pub fn get_comments(parent_id: String, parent_type_str: String, pagination: Pagi
     
     // Apply pagination
     let total = comments.len() as u64;
-    let start = offset.unwrap_or(0);
-    let limit = limit.unwrap_or(10);
-    let end = (start + limit).min(comments.len());
+    let (start, end) = page_bounds(comments.len(), &pagination, 10);
     let comments = comments[start..end].to_vec();
     
     // Convert to response format and get child comments
@@ -314,11 +509,18 @@ pub fn get_comments(parent_id: String, parent_type_str: String, pagination: Pagi
         response.child_comments = get_child_comments(&c.child_comments, caller)?;
         comments_result.push(response);
     }
+    attach_rendered_comments(&mut comments_result);
+    attach_comment_emojis(&mut comments_result);
+    attach_quoted_posts(&mut comments_result);
+    // A comment whose replies were cut short is kept, and the next page starts after it
+    let mut budget = RESPONSE_SIZE_BUDGET;
+    let truncated = truncate_comments_to_budget(&mut comments_result, &mut budget);
     
     Ok(CommentsResponse {
+        has_more: truncated || (end as u64) < total,
+        next_offset: if truncated { next_offset_after_truncation(start, comments_result.len()) } else { end },
         comments: comments_result,
         total,
-        has_more: (start as u64 + limit as u64) < total,
-        next_offset: start + limit,
+        truncated,
     })
 }
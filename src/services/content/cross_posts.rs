@@ -0,0 +1,231 @@
+// Cross-posts: an existing post shared into another tag community. A cross-post is a post of its own
+// in that community that points back to the original through `cross_post_of`. It mirrors the
+// original's content and status, and likes and comments on it go to the original, so both show the
+// same engagement. Feeds that reach both show only one of them.
+use candid::Principal;
+use ic_cdk::api::time;
+use std::borrow::Borrow;
+use std::collections::HashSet;
+
+use crate::models::account_quality::RateLimitedAction;
+use crate::models::content::{ContentStatus, ContentVisibility, CrossPostRequest, PostResponse, MAX_CROSS_POSTS};
+use crate::models::error::SquareResult;
+use crate::models::storage::Storage;
+use crate::services::content::anchoring::stamp_content_hash;
+use crate::services::content::outline::refresh_article_outline;
+use crate::services::content::posts::get_post;
+use crate::services::content::rendering::refresh_rendered_content;
+use crate::services::content::summary::refresh_article_summary;
+use crate::services::content::transitions::{index_post, on_legal_hold};
+use crate::services::discovery::tags::check_tag_roles;
+use crate::services::query_cache::{invalidate, POST_QUERIES};
+use crate::services::token_gate::check_tag_gates;
+use crate::services::user::{check_action_rate_limit, check_posting_privileges};
+use crate::services::user::delegation::is_posting_delegate;
+use crate::services::user::quota::{charge_storage, post_size, storage_quota};
+use crate::storage::{Post, STORAGE};
+use crate::utils::content_utils::normalize_hashtag;
+use crate::utils::error_handler::*;
+
+const MODULE: &str = "services::content::cross_posts";
+
+// The post that holds the likes and comments of `post_id`: its original when it is a cross-post
+pub fn canonical_post_id(store: &Storage, post_id: &str) -> String {
+    store.posts
+        .get(post_id)
+        .and_then(|post| post.cross_post_of.clone())
+        .unwrap_or_else(|| post_id.to_string())
+}
+
+// Keep one post per original: the original itself when it is in the list, otherwise the first of
+// its cross-posts
+pub fn dedup_cross_posts<T: Borrow<Post>>(posts: &mut Vec<T>) {
+    let originals: HashSet<String> = posts
+        .iter()
+        .map(|post| post.borrow())
+        .filter(|post| post.cross_post_of.is_none())
+        .map(|post| post.id.clone())
+        .collect();
+    let mut seen = HashSet::new();
+    posts.retain(|post| match &post.borrow().cross_post_of {
+        Some(original) => !originals.contains(original) && seen.insert(original.clone()),
+        None => true,
+    });
+}
+
+// Ids are taken from the time, with a suffix for further cross-posts in the same millisecond
+fn unused_cross_post_id(store: &Storage, now: u64) -> String {
+    let mut id = format!("crosspost_{}", now);
+    let mut suffix = 1;
+    while store.posts.contains_key(&id) {
+        suffix += 1;
+        id = format!("crosspost_{}_{}", now, suffix);
+    }
+    id
+}
+
+// Share a post into the community of `hashtag`. Cross-posting a cross-post shares its original.
+pub fn cross_post(request: CrossPostRequest, caller: Principal) -> SquareResult<PostResponse> {
+    const FUNCTION: &str = "cross_post";
+
+    invalidate(POST_QUERIES);
+
+    let Some(hashtag) = normalize_hashtag(&request.hashtag) else {
+        return log_and_return(field_validation_error(
+            "hashtag",
+            "must contain at least one letter or digit",
+            MODULE,
+            FUNCTION
+        ));
+    };
+    let (original_id, author, content, media_count) = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let original_id = canonical_post_id(&store, &request.post_id);
+        store.posts.get(&original_id).map(|post| (original_id, post.author, post.content.clone(), post.media_urls.len()))
+    }).ok_or_else(|| not_found_error("Post", &request.post_id, MODULE, FUNCTION))?;
+    if author != caller && !is_posting_delegate(author, caller) {
+        return log_and_return(permission_denied_error(
+            "cross_post",
+            "Only the author can cross-post this post",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    check_action_rate_limit(caller, RateLimitedAction::Post)?;
+    // A cross-post is a new post of the author's, so their trust level gates it like one
+    check_posting_privileges(author, &content, media_count)?;
+    // Gated tag communities only accept posts from verified holders, and restricted tags from their roles
+    check_tag_gates(author, std::slice::from_ref(&hashtag))?;
+    check_tag_roles(author, std::slice::from_ref(&hashtag), "cross_post")?;
+
+    let quota = storage_quota(author);
+    let now = time() / 1_000_000;
+    let post_id = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let original = store.posts
+            .get(&original_id)
+            .ok_or_else(|| not_found_error("Post", &original_id, MODULE, FUNCTION))?;
+        if original.status != ContentStatus::Active || original.visibility == ContentVisibility::Private {
+            return log_and_return(invalid_operation_error(
+                "cross_post",
+                "Only active posts that are not private can be cross-posted",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        let cross_posts = store.cross_posts.get(&original_id).cloned().unwrap_or_default();
+        let in_community = original.hashtags.contains(&hashtag)
+            || cross_posts.iter().any(|id| store.posts.get(id).is_some_and(|post| post.hashtags.contains(&hashtag)));
+        if in_community {
+            return log_and_return(invalid_operation_error(
+                "cross_post",
+                "The post is already in this community",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        if cross_posts.len() >= MAX_CROSS_POSTS {
+            return log_and_return(invalid_operation_error(
+                "cross_post",
+                &format!("A post can be cross-posted into at most {} communities", MAX_CROSS_POSTS),
+                MODULE,
+                FUNCTION
+            ));
+        }
+
+        let mut post = Post {
+            id: unused_cross_post_id(store, now),
+            author,
+            content: original.content.clone(),
+            media_urls: original.media_urls.clone(),
+            hashtags: vec![hashtag],
+            token_mentions: original.token_mentions.clone(),
+            tags: original.tags.clone(),
+            created_at: now,
+            updated_at: now,
+            status: ContentStatus::Active,
+            visibility: original.visibility.clone(),
+            news_reference: original.news_reference.clone(),
+            posted_by: (caller != author).then_some(caller),
+            title: original.title.clone(),
+            is_premium: original.is_premium,
+            license: original.license,
+            token_gate: original.token_gate.clone(),
+            format: original.format,
+            content_hash: None,
+            cross_post_of: Some(original_id.clone()),
+            cover_image: original.cover_image.clone(),
+            thumbnail: original.thumbnail.clone(),
+        };
+        charge_storage(&mut store.storage_usage, author, 0, post_size(&post), quota)?;
+        stamp_content_hash(&mut post, &mut store.pending_content_hashes, now);
+        let post_id = post.id.clone();
+        store.posts.insert(post_id.clone(), post);
+        store.cross_posts.entry(original_id.clone()).or_default().push(post_id.clone());
+        index_post(store, author, &post_id);
+        refresh_article_summary(store, &post_id);
+        refresh_rendered_content(store, &post_id);
+        refresh_article_outline(store, &post_id);
+        Ok(post_id)
+    })?;
+
+    get_post(post_id)
+}
+
+// The cross-posts of a post, or of the original of a cross-post, that are still active
+pub fn get_cross_posts(post_id: String) -> SquareResult<Vec<PostResponse>> {
+    const FUNCTION: &str = "get_cross_posts";
+
+    let ids = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let original_id = canonical_post_id(&store, &post_id);
+        if !store.posts.contains_key(&original_id) {
+            return log_and_return(not_found_error("Post", &post_id, MODULE, FUNCTION));
+        }
+        Ok(store.cross_posts
+            .get(&original_id)
+            .into_iter()
+            .flatten()
+            .filter(|id| store.posts.get(*id).is_some_and(|post| post.status == ContentStatus::Active))
+            .cloned()
+            .collect::<Vec<String>>())
+    })?;
+    ids.into_iter().map(get_post).collect()
+}
+
+// Carry an edit of the original over to its cross-posts. Their size follows the original's, so the
+// quota was already checked on the original. Cross-posts on legal hold keep the content they were
+// held with.
+pub(crate) fn sync_cross_posts(store: &mut Storage, original_id: &str) {
+    let Some(original) = store.posts.get(original_id).cloned() else {
+        return;
+    };
+    for id in store.cross_posts.get(original_id).cloned().unwrap_or_default() {
+        if on_legal_hold(store, &id) {
+            continue;
+        }
+        let Some(mut post) = store.posts.get_mut(&id) else {
+            continue;
+        };
+        let old_size = post_size(&post);
+        post.content = original.content.clone();
+        post.media_urls = original.media_urls.clone();
+        post.token_mentions = original.token_mentions.clone();
+        post.tags = original.tags.clone();
+        post.visibility = original.visibility.clone();
+        post.news_reference = original.news_reference.clone();
+        post.title = original.title.clone();
+        post.license = original.license;
+        post.format = original.format;
+        post.cover_image = original.cover_image.clone();
+        post.thumbnail = original.thumbnail.clone();
+        post.updated_at = original.updated_at;
+        let _ = charge_storage(&mut store.storage_usage, post.author, old_size, post_size(&post), None);
+        stamp_content_hash(&mut post, &mut store.pending_content_hashes, original.updated_at);
+        drop(post);
+        refresh_article_summary(store, &id);
+        refresh_rendered_content(store, &id);
+        refresh_article_outline(store, &id);
+    }
+}
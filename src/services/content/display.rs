@@ -8,38 +8,39 @@ use crate::models::error::{SquareResult, SquareError};
 use crate::storage::{STORAGE, Post};
 use std::borrow::Borrow;
 use crate::utils::error_handler::*;
-use crate::services::user::social::get_user_social_info;
+use crate::services::user::social::{attach_liked_by_followed, get_user_social_info};
+use crate::services::content::summary::attach_article_summaries;
+use crate::services::content::outline::attach_table_of_contents;
+use crate::services::content::rendering::attach_rendered_posts;
+use crate::services::emoji::attach_post_emojis;
+use crate::services::user::resolve_user_identifier;
+use crate::services::token_gate::withhold_gated_content;
+use crate::services::interaction::{has_liked, like_count};
+use crate::utils::response_size::truncate_feed;
+use crate::utils::pagination::page_bounds;
 
 pub fn get_user_content(user_identifier: String, content_type: Option<ContentType>, pagination: PaginationParams) -> SquareResult<FeedResponse> {
     const MODULE: &str = "services::content::display";
     const FUNCTION: &str = "get_user_content";
     
-    let limit = pagination.limit;
-    let offset = pagination.offset;
     let content_type = content_type.unwrap_or(ContentType::Post);
     
+    // Try to find user by principal or handle
+    let user_principal = resolve_user_identifier(&user_identifier)?;
+    
     // Get user's content from storage
     let mut feed_items = STORAGE.with(|storage| -> SquareResult<Vec<PostResponse>> {
         let store = storage.borrow();
         
-        // Try to find user by principal or handle
-        let user_principal = if let Ok(principal) = Principal::from_text(&user_identifier) {
-            principal
-        } else {
-            // If not a valid principal, try to find by handle
-            store.user_profiles.as_ref().and_then(|profiles| profiles.values().find(|profile| profile.handle == user_identifier)).map(|profile| profile.principal).ok_or_else(|| not_found_error("User", &user_identifier, MODULE, FUNCTION))?
-
-        };
-        
         // Get user's content
         match content_type {
             ContentType::Post => {
                 let posts: Vec<SquareResult<PostResponse>> = store.posts
                     .values()
-                    .filter(|post| post.author == user_principal)
+                    .filter(|post| post.author == user_principal && post.status != ContentStatus::LegalHold)
                     .map(|post| -> SquareResult<PostResponse> {
                         let author_info = get_user_social_info(post.author.to_string(), None)?;
-                        Ok(PostResponse {
+                        Ok(withhold_gated_content(PostResponse {
                             hashtags: post.hashtags.clone(),
                             status: post.status.clone(),
                             tags: post.tags.clone(),
@@ -55,7 +56,23 @@ pub fn get_user_content(user_identifier: String, content_type: Option<ContentTyp
                             comments_count: 0, // TODO: Get from comments storage
                             author_info,
                             news_reference: post.news_reference.clone(),
-                        })
+                            posted_by: post.posted_by,
+                            title: post.title.clone(),
+                            is_premium: post.is_premium,
+                            license: post.license,
+                            token_gate: post.token_gate.clone(),
+                            liked_by_followed: Vec::new(),
+                            summary: None,
+                            format: post.format,
+                            source: None,
+                            reactions: Vec::new(),
+                            emojis: Vec::new(),
+                            content_hash: post.content_hash.clone(),
+                            cross_post_of: post.cross_post_of.clone(),
+                            table_of_contents: Vec::new(),
+                            cover_image: post.cover_image.clone(),
+                            thumbnail: post.thumbnail.clone(),
+                        }))
                     })
                     .collect();
                 
@@ -66,7 +83,7 @@ pub fn get_user_content(user_identifier: String, content_type: Option<ContentTyp
             ContentType::Comment => {
                 let comments: Vec<SquareResult<PostResponse>> = store.comments
                     .values()
-                    .filter(|comment| comment.author == user_principal)
+                    .filter(|comment| comment.author == user_principal && comment.status == ContentStatus::Active)
                     .map(|comment| -> SquareResult<PostResponse> {
                         let author_info = get_user_social_info(comment.author.to_string(), None)?;
                         Ok(PostResponse {
@@ -85,6 +102,22 @@ pub fn get_user_content(user_identifier: String, content_type: Option<ContentTyp
                             comments_count: 0, // TODO: Get from comments storage
                             author_info,
                             news_reference: None,
+                            posted_by: None,
+                            title: None,
+                            is_premium: false,
+                            license: None,
+                            token_gate: None,
+                            liked_by_followed: Vec::new(),
+                            summary: None,
+                            format: comment.format,
+                            source: None,
+                            reactions: Vec::new(),
+                            emojis: Vec::new(),
+                            content_hash: None,
+                            cross_post_of: None,
+                            table_of_contents: Vec::new(),
+                            cover_image: None,
+                            thumbnail: None,
                         })
                     })
                     .collect();
@@ -101,37 +134,41 @@ pub fn get_user_content(user_identifier: String, content_type: Option<ContentTyp
     
     // Apply pagination
     let total = feed_items.len() as u64;
-    let start = offset.unwrap_or(0);
-    let limit = limit.unwrap_or(10);
-    let end = (start + limit).min(feed_items.len());
-    let feed_items = feed_items[start..end].to_vec();
+    let (start, end) = page_bounds(feed_items.len(), &pagination, 10);
+    let mut feed_items = feed_items[start..end].to_vec();
+    attach_liked_by_followed(&mut feed_items);
+    attach_article_summaries(&mut feed_items);
+    attach_rendered_posts(&mut feed_items);
+    attach_post_emojis(&mut feed_items);
     
-    Ok(FeedResponse {
+    let mut response = FeedResponse {
         posts: feed_items,
         comments: vec![],
-        has_more: (start as u64 + limit as u64) < total,
-        next_offset: start + limit,
+        has_more: (end as u64) < total,
+        next_offset: end,
         total,
-    })
+        degraded: false,
+        truncated: false,
+    };
+    truncate_feed(&mut response, start);
+    Ok(response)
 }
 
 pub fn get_content_detail(content_id: String, content_type: ContentType, caller: Option<Principal>) -> SquareResult<ContentDetailResponse> {
     const MODULE: &str = "services::content::display";
     const FUNCTION: &str = "get_content_detail";
     
-    STORAGE.with(|storage| {
+    let mut detail = STORAGE.with(|storage| -> SquareResult<ContentDetailResponse> {
         let store = storage.borrow();
         
         match content_type {
             ContentType::Post => {
-                let post = store.posts.get(&content_id).ok_or_else(|| {
+                let post = store.posts.get(&content_id).filter(|post| post.status != ContentStatus::LegalHold).ok_or_else(|| {
                     not_found_error("Post", &content_id, MODULE, FUNCTION)
                 })?;
                 
                 // Get likes count
-                let _likes_count = store.likes
-                    .get(&content_id)
-                    .map_or(0, |likes| likes.len() as u64);
+                let _likes_count = like_count(&store, &content_id);
                 
                 // Get comments count
                 let _comments_count = store.comments
@@ -141,15 +178,13 @@ pub fn get_content_detail(content_id: String, content_type: ContentType, caller:
                 
                 // Check if caller has liked the content
                 let _has_liked = if let Some(caller) = caller {
-                    store.likes
-                        .get(&content_id)
-                        .map_or(false, |likes| likes.contains(&caller))
+                    has_liked(&store, &content_id, &caller)
                 } else {
                     false
                 };
                 
                 Ok(ContentDetailResponse {
-                    post: Some(PostResponse {
+                    post: Some(withhold_gated_content(PostResponse {
                         hashtags: post.hashtags.clone(),
                         status: post.status.clone(),
                         tags: post.tags.clone(),
@@ -165,30 +200,42 @@ pub fn get_content_detail(content_id: String, content_type: ContentType, caller:
                         comments_count: 0, // TODO: Get from comments storage
                         author_info: get_user_social_info(post.author.to_string(), None)?,
                         news_reference: post.news_reference.clone(),
-                    }),
+                        posted_by: post.posted_by,
+                        title: post.title.clone(),
+                        is_premium: post.is_premium,
+                        license: post.license,
+                        token_gate: post.token_gate.clone(),
+                        liked_by_followed: Vec::new(),
+                        summary: None,
+                        format: post.format,
+                        source: None,
+                        reactions: Vec::new(),
+                        emojis: Vec::new(),
+                        content_hash: post.content_hash.clone(),
+                        cross_post_of: post.cross_post_of.clone(),
+                        table_of_contents: Vec::new(),
+                        cover_image: post.cover_image.clone(),
+                        thumbnail: post.thumbnail.clone(),
+                    })),
                     comments: vec![],
                     has_more_comments: false,
                     next_comment_offset: 0,
                 })
             }
             ContentType::Comment => {
-                let comment = store.comments.get(&content_id).ok_or_else(|| {
+                let comment = store.comments.get(&content_id).filter(|comment| comment.status != ContentStatus::LegalHold).ok_or_else(|| {
                     not_found_error("Comment", &content_id, MODULE, FUNCTION)
                 })?;
                 
                 // Get likes count
-                let _likes_count = store.likes
-                    .get(&content_id)
-                    .map_or(0, |likes| likes.len() as u64);
+                let _likes_count = like_count(&store, &content_id);
                 
                 // Get replies count
                 let _comments_count = comment.child_comments.len() as u64;
                 
                 // Check if caller has liked the content
                 let _has_liked = if let Some(caller) = caller {
-                    store.likes
-                        .get(&content_id)
-                        .map_or(false, |likes| likes.contains(&caller))
+                    has_liked(&store, &content_id, &caller)
                 } else {
                     false
                 };
@@ -210,6 +257,22 @@ pub fn get_content_detail(content_id: String, content_type: ContentType, caller:
                         comments_count: 0, // TODO: Get from comments storage
                         author_info: get_user_social_info(comment.author.to_string(), None)?,
                         news_reference: None,
+                        posted_by: None,
+                        title: None,
+                        is_premium: false,
+                        license: None,
+                        token_gate: None,
+                        liked_by_followed: Vec::new(),
+                        summary: None,
+                        format: comment.format,
+                        source: None,
+                        reactions: Vec::new(),
+                        emojis: Vec::new(),
+                        content_hash: None,
+                        cross_post_of: None,
+                        table_of_contents: Vec::new(),
+                        cover_image: None,
+                        thumbnail: None,
                     }),
                     comments: vec![],
                     has_more_comments: false,
@@ -217,5 +280,14 @@ pub fn get_content_detail(content_id: String, content_type: ContentType, caller:
                 })
             }
         }
-    })
+    })?;
+
+    if let Some(post) = detail.post.as_mut() {
+        attach_liked_by_followed(std::slice::from_mut(post));
+        attach_article_summaries(std::slice::from_mut(post));
+        attach_table_of_contents(std::slice::from_mut(post));
+        attach_rendered_posts(std::slice::from_mut(post));
+        attach_post_emojis(std::slice::from_mut(post));
+    }
+    Ok(detail)
 }
@@ -0,0 +1,150 @@
+use candid::Principal;
+
+use crate::auth::is_admin;
+use crate::models::content::{ContentStatus, ContentType, PaginationParams};
+use crate::models::display::PaginatedResponse;
+use crate::models::error::SquareResult;
+use crate::models::legal_hold::*;
+use crate::models::storage::Storage;
+use crate::services::content::transitions::set_content_status;
+use crate::services::interaction::like_count;
+use crate::services::query_cache::{invalidate, POST_QUERIES};
+use crate::services::user::creator::paginate;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::logger;
+use crate::utils::time_utils::now_millis;
+use crate::utils::validators::validate_place_legal_hold;
+
+const MODULE: &str = "services::content::legal_hold";
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage legal holds",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+fn content_status(store: &Storage, content_type: &ContentType, content_id: &str) -> Option<ContentStatus> {
+    match content_type {
+        ContentType::Post => store.posts.get(content_id).map(|post| post.status.clone()),
+        ContentType::Comment => store.comments.get(content_id).map(|comment| comment.status.clone()),
+    }
+}
+
+// Refuse to change content on legal hold; for edits, deletes and moderation
+pub(crate) fn ensure_not_on_legal_hold(store: &Storage, content_id: &str, operation: &str, module: &str, function: &str) -> SquareResult<()> {
+    if store.legal_holds.contains_key(content_id) {
+        return log_and_return(invalid_operation_error(
+            operation,
+            "Content is on legal hold",
+            module,
+            function
+        ));
+    }
+    Ok(())
+}
+
+// Freeze a post or comment and hide it from everyone. Its status before the hold is kept and comes
+// back on release.
+pub fn place_legal_hold(request: PlaceLegalHoldRequest, caller: Principal) -> SquareResult<LegalHold> {
+    const FUNCTION: &str = "place_legal_hold";
+
+    require_admin("place_legal_hold", FUNCTION)?;
+    validate_place_legal_hold(&request).finish(MODULE, FUNCTION)?;
+    invalidate(POST_QUERIES);
+
+    let hold = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let Some(previous_status) = content_status(&store, &request.content_type, &request.content_id) else {
+            return log_and_return(not_found_error("Content", &request.content_id, MODULE, FUNCTION));
+        };
+        if store.legal_holds.contains_key(&request.content_id) {
+            return log_and_return(already_exists_error("LegalHold", &request.content_id, MODULE, FUNCTION));
+        }
+
+        let hold = LegalHold {
+            content_id: request.content_id.clone(),
+            content_type: request.content_type.clone(),
+            reason: request.reason,
+            reference: request.reference,
+            notes: request.notes,
+            placed_by: caller,
+            placed_at: now_millis(),
+            previous_status,
+        };
+        // Status first: once the hold is recorded the status is frozen
+        set_content_status(&mut store, caller, &request.content_type, &request.content_id, ContentStatus::LegalHold);
+        store.legal_holds.insert(request.content_id.clone(), hold.clone());
+        Ok(hold)
+    })?;
+
+    logger::log(&format!("[{}] {} placed a legal hold on {}", MODULE, caller, hold.content_id));
+    Ok(hold)
+}
+
+// Lift a hold and put the content back in the status it had before
+pub fn release_legal_hold(content_id: String, caller: Principal) -> SquareResult<LegalHold> {
+    const FUNCTION: &str = "release_legal_hold";
+
+    require_admin("release_legal_hold", FUNCTION)?;
+    invalidate(POST_QUERIES);
+
+    let hold = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let Some(hold) = store.legal_holds.remove(&content_id) else {
+            return log_and_return(not_found_error("LegalHold", &content_id, MODULE, FUNCTION));
+        };
+        set_content_status(&mut store, caller, &hold.content_type, &hold.content_id, hold.previous_status.clone());
+        Ok(hold)
+    })?;
+
+    logger::log(&format!("[{}] {} released the legal hold on {}", MODULE, caller, hold.content_id));
+    Ok(hold)
+}
+
+// Holds in force, newest first
+pub fn get_legal_holds(pagination: PaginationParams) -> SquareResult<PaginatedResponse<LegalHold>> {
+    const FUNCTION: &str = "get_legal_holds";
+
+    require_admin("get_legal_holds", FUNCTION)?;
+    let mut holds: Vec<LegalHold> = STORAGE.with(|storage| storage.borrow().legal_holds.values().cloned().collect());
+    holds.sort_by(|a, b| b.placed_at.cmp(&a.placed_at).then_with(|| a.content_id.cmp(&b.content_id)));
+    Ok(paginate(holds, pagination))
+}
+
+// The held record as stored, with the reports filed against it, for a compliance request
+pub fn export_legal_hold(content_id: String, caller: Principal) -> SquareResult<LegalHoldExport> {
+    const FUNCTION: &str = "export_legal_hold";
+
+    require_admin("export_legal_hold", FUNCTION)?;
+    let export = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let Some(hold) = store.legal_holds.get(&content_id).cloned() else {
+            return log_and_return(not_found_error("LegalHold", &content_id, MODULE, FUNCTION));
+        };
+        let mut reports: Vec<_> = store.reports
+            .values()
+            .filter(|report| report.content_id == content_id)
+            .cloned()
+            .collect();
+        reports.sort_by_key(|report| report.created_at);
+        Ok(LegalHoldExport {
+            post: store.posts.get(&content_id).filter(|_| hold.content_type == ContentType::Post).cloned(),
+            comment: store.comments.get(&content_id).filter(|_| hold.content_type == ContentType::Comment).cloned(),
+            reports,
+            like_count: like_count(&store, &content_id),
+            exported_by: caller,
+            exported_at: now_millis(),
+            hold,
+        })
+    })?;
+
+    logger::log(&format!("[{}] {} exported the legal hold on {}", MODULE, caller, content_id));
+    Ok(export)
+}
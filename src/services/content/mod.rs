@@ -8,12 +8,25 @@ pub mod posts;
 pub mod comments;
 pub mod moderation;
 pub mod display;
+pub mod summary;
+pub mod outline;
+pub mod sanitizer;
+pub mod rendering;
+pub mod timeline;
+pub mod transitions;
+pub mod legal_hold;
+pub mod anchoring;
+pub mod subscriptions;
+pub mod cross_posts;
+pub mod approval;
+pub mod schedule;
 
 // Re-export commonly used functions
 pub use posts::{
     create_post,
     get_post,
     get_posts,
+    get_content_by_license,
     update_post,
     delete_post,
 };
@@ -30,7 +43,53 @@ pub use moderation::{
     moderate_content,
 };
 
+pub use legal_hold::{
+    place_legal_hold,
+    release_legal_hold,
+    get_legal_holds,
+    export_legal_hold,
+};
+
+pub use anchoring::{
+    verify_content_integrity,
+};
+
+pub use approval::{
+    get_approval_queue,
+    review_pending_post,
+    run_auto_approvals,
+    get_content_approval_settings,
+    update_content_approval_settings,
+};
+
+pub use schedule::{
+    set_content_schedule,
+    get_my_scheduled_posts,
+    run_content_schedules,
+};
+
+pub use cross_posts::{
+    cross_post,
+    get_cross_posts,
+};
+
+pub use subscriptions::{
+    subscribe_to_content,
+    mute_content,
+    unsubscribe_from_content,
+    get_content_subscription,
+    run_thread_digests,
+};
+
 pub use display::{
     get_user_content,
     get_content_detail,
 };
+
+pub use summary::{
+    get_article_summary,
+};
+
+pub use timeline::{
+    get_following_feed,
+};
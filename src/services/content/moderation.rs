@@ -2,47 +2,83 @@ use candid::Principal;
 use ic_cdk::api::time;
 
 use crate::auth::is_admin;
-use crate::models::content::{ContentModerationRequest, ContentStatus, ContentType};
+use crate::models::content::{ContentModerationRequest, ContentStatus, ContentType, ModeratedContent};
 use crate::models::storage::Storage;
 use crate::{SquareError, SquareResult};
 use crate::storage::STORAGE;
 use crate::utils::error_handler::*;
+use crate::models::user::UserModerationAction;
+use crate::services::user::admin::record_user_moderation;
+use crate::services::discovery::tags::can_moderate_in_tags;
+use crate::services::query_cache::{invalidate, POST_QUERIES};
+use crate::services::content::transitions::set_content_status;
+use crate::services::content::legal_hold::ensure_not_on_legal_hold;
 
-pub fn moderate_content(request: ContentModerationRequest) -> SquareResult<()> {
+pub fn moderate_content(request: ContentModerationRequest, caller: Principal) -> SquareResult<ModeratedContent> {
     const MODULE: &str = "services::content::moderation";
     const FUNCTION: &str = "moderate_content";
     
-    // Only admin can moderate content
-    if is_admin().is_err() {
+    invalidate(POST_QUERIES);
+    
+    // Admins moderate everything; tag moderators only content within their tags
+    let is_tag_moderator = STORAGE.with(|storage| {
+        can_moderate_in_tags(&storage.borrow(), caller, &request.content_type, &request.content_id)
+    });
+    if is_admin().is_err() && !is_tag_moderator {
         return log_and_return(unauthorized_error(
-            "Only admin can moderate content",
+            "Only admins or moderators of the content's tags can moderate content",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    
+    if request.status == ContentStatus::LegalHold {
+        return log_and_return(invalid_operation_error(
+            "moderate_content",
+            "Legal holds are placed with place_legal_hold",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if request.status == ContentStatus::Scheduled {
+        return log_and_return(invalid_operation_error(
+            "moderate_content",
+            "Posts are scheduled with set_content_schedule",
             MODULE,
             FUNCTION
         ));
     }
     
-    STORAGE.with(|storage| {
+    let now = time() / 1_000_000;
+    let author = STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
-        
+        ensure_not_on_legal_hold(&store, &request.content_id, "moderate_content", MODULE, FUNCTION)?;
+        let Some(author) = set_content_status(&mut store, caller, &request.content_type, &request.content_id, request.status.clone()) else {
+            let resource = match request.content_type {
+                ContentType::Post => "Post",
+                ContentType::Comment => "Comment",
+            };
+            return Err(not_found_error(resource, &request.content_id, MODULE, FUNCTION));
+        };
         match request.content_type {
-            ContentType::Post => {
-                let post = store.posts.get_mut(&request.content_id).ok_or_else(|| {
-                    not_found_error("Post", &request.content_id, MODULE, FUNCTION)
-                })?;
-                
-                post.status = request.status;
-                post.updated_at = time() / 1_000_000;
-            }
-            ContentType::Comment => {
-                let comment = store.comments.get_mut(&request.content_id).ok_or_else(|| {
-                    not_found_error("Comment", &request.content_id, MODULE, FUNCTION)
-                })?;
-                
-                comment.status = request.status;
-                comment.updated_at = time() / 1_000_000;
-            }
-        }
-        
-        Ok(())
+            ContentType::Post => store.posts.get_mut(&request.content_id).map(|mut post| post.updated_at = now),
+            ContentType::Comment => store.comments.get_mut(&request.content_id).map(|mut comment| comment.updated_at = now),
+        };
+        Ok::<Principal, SquareError>(author)
+    })?;
+    
+    // Keep the author's moderation history in sync
+    record_user_moderation(
+        author,
+        UserModerationAction::ContentModerated(request.status.clone()),
+        Some(request.reason),
+        Some(request.content_id.clone())
+    );
+    
+    Ok(ModeratedContent {
+        content_id: request.content_id,
+        content_type: request.content_type,
+        status: request.status,
+        updated_at: now,
     })
 }
@@ -0,0 +1,46 @@
+// Tables of contents of articles: the headings of the rendered HTML and their anchors, built when
+// an article is written. Single-post views return them and the article page links each entry to
+// its section.
+use crate::models::content::{ArticleOutline, PostResponse, TocEntry, TOC_MAX_ENTRIES, TOC_MIN_HEADINGS};
+use crate::models::storage::Storage;
+use crate::services::classifier::content_hash;
+use crate::services::content::rendering::rendered_html;
+use crate::storage::STORAGE;
+use crate::utils::content_utils::heading_outline;
+
+// Rebuild a post's cached outline after it is written; only articles with enough headings keep one
+pub fn refresh_article_outline(store: &mut Storage, post_id: &str) {
+    let Some(post) = store.posts.get(post_id).filter(|post| post.title.is_some()) else {
+        store.article_outlines.remove(post_id);
+        return;
+    };
+    let html = rendered_html(store, &post.id, post.format, &post.content);
+    let source_hash = content_hash(&html);
+    if store.article_outlines.get(post_id).is_some_and(|cached| cached.source_hash == source_hash) {
+        return;
+    }
+    let mut entries = heading_outline(&html);
+    if entries.len() < TOC_MIN_HEADINGS {
+        store.article_outlines.remove(post_id);
+        return;
+    }
+    entries.truncate(TOC_MAX_ENTRIES);
+    store.article_outlines.insert(post_id.to_string(), ArticleOutline { source_hash, entries });
+}
+
+pub fn article_toc(store: &Storage, post_id: &str) -> Vec<TocEntry> {
+    store.article_outlines
+        .get(post_id)
+        .map(|outline| outline.entries.clone())
+        .unwrap_or_default()
+}
+
+// Gated articles keep theirs withheld with the content
+pub fn attach_table_of_contents(posts: &mut [PostResponse]) {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        for post in posts.iter_mut().filter(|post| post.token_gate.is_none()) {
+            post.table_of_contents = article_toc(&store, &post.id);
+        }
+    });
+}
@@ -2,91 +2,194 @@ use candid::Principal;
 use ic_cdk::api::time;
 use std::collections::{HashMap, HashSet};
 
-use crate::auth::is_admin;
+use crate::auth::{is_admin, is_manager_or_admin};
 use crate::models::content::{
-    CreatePostRequest, UpdatePostRequest, ContentStatus, ContentVisibility,
-    ContentType, PostResponse, PostsResponse, PaginationParams,
+    CreatePostRequest, UpdatePostRequest, ContentStatus, ContentVisibility, ContentFormat,
+    ContentType, ContentLicense, ContentSchedule, PostResponse, PostsResponse, PaginationParams,
     MAX_MEDIA_URLS, MAX_TOKEN_MENTIONS, MAX_HASHTAGS, MAX_POST_LENGTH,
 };
 use crate::models::storage::Storage;
-use crate::utils::content_utils::calculate_content_length_excluding_base64_and_html;
+use crate::models::display::PaginatedResponse;
+use crate::utils::content_utils::{calculate_content_length_excluding_base64_and_html, has_inline_media, normalize_hashtags};
 use crate::{SquareError, SquareResult};
 use crate::storage::{Post, STORAGE};
 use crate::utils::error_handler::*;
-use crate::services::user::social::get_user_social_info;
+use crate::services::user::social::{attach_liked_by_followed, get_user_social_info};
+use crate::services::user::delegation::{is_posting_delegate, record_delegated_action};
+use crate::models::delegation::DelegatedAction;
+use crate::utils::validators::{validate_create_post, validate_update_post};
+use crate::services::user::creator::{paginate, validate_creator_post};
+use crate::services::user::{check_action_rate_limit, check_content_privileges, check_posting_privileges};
+use crate::services::user::quota::{charge_storage, post_size, release_storage, storage_quota};
+use crate::models::account_quality::RateLimitedAction;
+use crate::services::cycles::ensure_media_uploads_allowed;
+use crate::services::query_cache::{cached, invalidate, CachedQuery, POST_QUERIES};
+use crate::services::classifier::submit_for_classification;
+use crate::services::token_gate::{check_tag_gates, validate_token_gate, withhold_gated_content};
+use crate::services::discovery::tags::check_tag_roles;
+use crate::services::translation::remove_translations;
+use crate::services::content::summary::{attach_article_summaries, refresh_article_summary};
+use crate::services::content::outline::{attach_table_of_contents, refresh_article_outline};
+use crate::services::content::approval::{hold_post, publish_post, requires_approval};
+use crate::services::content::schedule::{embargoed, store_schedule, validate_schedule};
+use crate::services::content::subscriptions::auto_subscribe;
+use crate::services::content::cross_posts::sync_cross_posts;
+use crate::services::content::transitions::{index_post, remove_post, thread_on_legal_hold};
+use crate::services::content::legal_hold::ensure_not_on_legal_hold;
+use crate::services::content::anchoring::stamp_content_hash;
+use crate::services::content::sanitizer::sanitize_content;
+use crate::services::content::rendering::{attach_rendered_posts, refresh_rendered_content};
+use crate::services::emoji::attach_post_emojis;
+use crate::utils::response_size::{next_offset_after_truncation, truncate_to_budget, RESPONSE_SIZE_BUDGET};
+use crate::utils::pagination::page_bounds;
 
-
-pub fn create_post(request: CreatePostRequest, caller: Principal) -> SquareResult<PostResponse> {
+// Licenses apply to articles and posts with media, which default to all rights reserved
+fn resolve_license(
+    requested: Option<ContentLicense>,
+    current: Option<ContentLicense>,
+    licensable: bool,
+) -> SquareResult<Option<ContentLicense>> {
     const MODULE: &str = "services::content::posts";
-    const FUNCTION: &str = "create_post";
-    
-    // Validate content length (excluding HTML tags and base64 images/videos)
-    let content_length = calculate_content_length_excluding_base64_and_html(&request.content);
-    if content_length > MAX_POST_LENGTH {
-        return log_and_return(content_too_long_error(
-            "Post", 
-            MAX_POST_LENGTH, 
-            content_length, 
-            MODULE, 
-            FUNCTION
-        ));
-    }
-    
-    // Validate hashtags count
-    if request.hashtags.len() > MAX_HASHTAGS {
-        return log_and_return(validation_error(
-            &format!("Too many hashtags. Maximum allowed is {}", MAX_HASHTAGS),
-            MODULE,
-            FUNCTION
-        ));
-    }
-    
-    // Validate token mentions count
-    if let Some(token_mentions) = &request.token_mentions {
-        if token_mentions.len() > MAX_TOKEN_MENTIONS {
-            return log_and_return(validation_error(
-                &format!("Too many token mentions. Maximum allowed is {}", MAX_TOKEN_MENTIONS),
+    const FUNCTION: &str = "resolve_license";
+
+    if !licensable {
+        if requested.is_some() {
+            return log_and_return(field_validation_error(
+                "license",
+                "Only articles and posts with media can carry a license",
                 MODULE,
                 FUNCTION
             ));
         }
+        return Ok(None);
     }
+    Ok(requested.or(current).or(Some(ContentLicense::AllRightsReserved)))
+}
+
+pub fn create_post(mut request: CreatePostRequest, caller: Principal) -> SquareResult<PostResponse> {
+    const MODULE: &str = "services::content::posts";
+    const FUNCTION: &str = "create_post";
     
-    // Validate media URLs count
-    let media_urls = request.media_urls;
-    if media_urls.len() > MAX_MEDIA_URLS {
-        return log_and_return(validation_error(
-            &format!("Too many media URLs. Maximum allowed is {}", MAX_MEDIA_URLS),
-            MODULE,
-            FUNCTION
-        ));
+    invalidate(POST_QUERIES);
+    
+    // Markdown and Plain sources are sanitized when rendered instead
+    let format = request.format.unwrap_or_default();
+    if format == ContentFormat::Html {
+        request.content = sanitize_content(&request.content);
     }
+    // Validate all request fields at once (content length excludes HTML tags and base64 media)
+    validate_create_post(&request).finish(MODULE, FUNCTION)?;
+    let has_media = has_inline_media(&request.content) || request.media_urls.iter().any(|url| has_inline_media(url));
+    ensure_media_uploads_allowed(has_media, "create_post", MODULE, FUNCTION)?;
+    check_action_rate_limit(caller, RateLimitedAction::Post)?;
+    let media_urls = request.media_urls;
+    
+    // Resolve the author when posting on behalf of an organization account
+    let (author, posted_by) = match request.on_behalf_of {
+        Some(org) if org != caller => {
+            if !is_posting_delegate(org, caller) {
+                return log_and_return(permission_denied_error(
+                    "create_post",
+                    "Caller is not authorized to post on behalf of this account",
+                    MODULE,
+                    FUNCTION
+                ));
+            }
+            (org, Some(caller))
+        },
+        _ => (caller, None),
+    };
+    
+    // Articles and premium posts are creator-only
+    let is_premium = request.is_premium.unwrap_or(false);
+    validate_creator_post(author, &request.title, is_premium)?;
+    
+    // Links, media and posting frequency are gated by the author's trust level
+    check_posting_privileges(author, &request.content, media_urls.len())?;
+    let license = resolve_license(request.license, None, request.title.is_some() || !media_urls.is_empty())?;
+    
+    // Gated tag communities only accept posts from verified holders, and restricted tags from their roles
+    let hashtags = normalize_hashtags(request.hashtags);
+    check_tag_gates(author, &hashtags)?;
+    check_tag_roles(author, &hashtags, "create_post")?;
+    validate_token_gate(&request.token_gate, FUNCTION)?;
     
     let now = time() / 1_000_000;
     let post_id = format!("post_{}", now);
+    let schedule = ContentSchedule {
+        embargo_until: request.embargo_until,
+        sunset_at: request.sunset_at,
+    };
+    validate_schedule(&schedule, now, MODULE, FUNCTION)?;
+    
+    // While approval mode is on, the first posts of new accounts wait for a moderator
+    let held = is_manager_or_admin().is_err() && STORAGE.with(|storage| requires_approval(&storage.borrow(), author, now));
+    // Embargoed posts stay hidden until the heartbeat publishes them
+    let under_embargo = schedule.embargo_until.is_some();
     
-    let post = Post {
+    let mut post = Post {
         id: post_id.clone(),
-        author: caller,
+        author,
         content: request.content,
-        hashtags: request.hashtags,
+        hashtags,
         token_mentions: request.token_mentions.unwrap_or_default(),
         media_urls: media_urls,
         tags: request.tags.unwrap_or_default(),
         created_at: now,
         updated_at: now,
-        status: ContentStatus::Active,
+        status: if held {
+            ContentStatus::UnderReview
+        } else if under_embargo {
+            ContentStatus::Scheduled
+        } else {
+            ContentStatus::Active
+        },
         visibility: request.visibility.unwrap_or(ContentVisibility::Public),
         news_reference: request.news_reference,
+        posted_by,
+        title: request.title,
+        is_premium,
+        license,
+        token_gate: request.token_gate,
+        format,
+        content_hash: None,
+        cross_post_of: None,
+        cover_image: request.cover_image,
+        thumbnail: request.thumbnail,
     };
     
-    // Store post in main storage
-    STORAGE.with(|storage| {
+    // Store post in main storage, within the author's storage quota
+    let quota = storage_quota(author);
+    STORAGE.with(|storage| -> SquareResult<()> {
         let mut store = storage.borrow_mut();
+        charge_storage(&mut store.storage_usage, author, 0, post_size(&post), quota)?;
+        stamp_content_hash(&mut post, &mut store.pending_content_hashes, now);
         store.posts.insert(post_id.clone(), post.clone());
-    });
+        index_post(&mut store, author, &post_id);
+        refresh_article_summary(&mut store, &post_id);
+        refresh_rendered_content(&mut store, &post_id);
+        refresh_article_outline(&mut store, &post_id);
+        auto_subscribe(&mut store, &post_id, author);
+        store_schedule(&mut store, &post_id, schedule);
+        if held {
+            hold_post(&mut store, &post_id, author, now);
+        } else if !under_embargo {
+            publish_post(&mut store, caller, &post_id);
+        }
+        Ok(())
+    })?;
+    
+    if let Some(delegate) = posted_by {
+        record_delegated_action(author, delegate, DelegatedAction::PostCreated, Some(post_id.clone()));
+    }
     
-    Ok(PostResponse {
+    let classifier_text = match &post.title {
+        Some(title) => format!("{}\n\n{}", title, post.content),
+        None => post.content.clone(),
+    };
+    submit_for_classification(post_id.clone(), ContentType::Post, classifier_text);
+    
+    let mut response = PostResponse {
         id: post.id,
         author: post.author,
         content: post.content,
@@ -102,21 +205,64 @@ pub fn create_post(request: CreatePostRequest, caller: Principal) -> SquareResul
         comments_count: 0, // TODO: Get from comments storage
         author_info: get_user_social_info(post.author.to_string(), None)?,
         news_reference: post.news_reference,
-    })
+        posted_by: post.posted_by,
+        title: post.title.clone(),
+        is_premium: post.is_premium,
+        license: post.license,
+        token_gate: post.token_gate.clone(),
+        liked_by_followed: Vec::new(),
+        summary: None,
+        format: post.format,
+        source: None,
+        reactions: Vec::new(),
+        emojis: Vec::new(),
+        content_hash: post.content_hash,
+        cross_post_of: post.cross_post_of,
+        table_of_contents: Vec::new(),
+        cover_image: post.cover_image,
+        thumbnail: post.thumbnail,
+    };
+    attach_article_summaries(std::slice::from_mut(&mut response));
+    attach_table_of_contents(std::slice::from_mut(&mut response));
+    attach_rendered_posts(std::slice::from_mut(&mut response));
+    attach_post_emojis(std::slice::from_mut(&mut response));
+    Ok(response)
+}
+
+// Who may read a post: anyone when it is public, the author's followers when it is followers-only,
+// and its author always
+pub(crate) fn can_view_post(store: &Storage, post: &Post, viewer: Principal) -> bool {
+    match post.visibility {
+        ContentVisibility::Public => true,
+        _ if post.author == viewer => true,
+        ContentVisibility::FollowersOnly => store.user_profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(&post.author))
+            .is_some_and(|profile| profile.followers.contains(&viewer)),
+        ContentVisibility::Private => false,
+    }
 }
 
 pub fn get_post(id: String) -> SquareResult<PostResponse> {
+    post_response(id, false)
+}
+
+// Embargoed posts are only built for their author and moderators
+pub(crate) fn post_response(id: String, include_embargoed: bool) -> SquareResult<PostResponse> {
     const MODULE: &str = "services::content::posts";
     const FUNCTION: &str = "get_post";
     
-    STORAGE.with(|storage| {
+    let now = time() / 1_000_000;
+    let mut response = STORAGE.with(|storage| {
         let store = storage.borrow();
         let post = store.posts
             .get(&id)
+            .filter(|post| post.status != ContentStatus::LegalHold)
+            .filter(|post| include_embargoed || !embargoed(&store, &post.id, now))
             .cloned()
             .ok_or_else(|| not_found_error("Post", &id, MODULE, FUNCTION))?;
         
-        Ok(PostResponse {
+        Ok::<_, SquareError>(withhold_gated_content(PostResponse {
             id: post.id.clone(),
             author: post.author,
             content: post.content.clone(),
@@ -132,37 +278,83 @@ pub fn get_post(id: String) -> SquareResult<PostResponse> {
             comments_count: 0, // TODO: Get from comments storage
             author_info: get_user_social_info(post.author.to_string(), None)?,
             news_reference: post.news_reference.clone(),
-        })
-    })
+            posted_by: post.posted_by,
+            title: post.title.clone(),
+            is_premium: post.is_premium,
+            license: post.license,
+            token_gate: post.token_gate.clone(),
+            liked_by_followed: Vec::new(),
+            summary: None,
+            format: post.format,
+            source: None,
+            reactions: Vec::new(),
+            emojis: Vec::new(),
+            content_hash: post.content_hash.clone(),
+            cross_post_of: post.cross_post_of.clone(),
+            table_of_contents: Vec::new(),
+            cover_image: post.cover_image.clone(),
+            thumbnail: post.thumbnail.clone(),
+        }))
+    })?;
+    
+    attach_liked_by_followed(std::slice::from_mut(&mut response));
+    
+    attach_article_summaries(std::slice::from_mut(&mut response));
+    attach_table_of_contents(std::slice::from_mut(&mut response));
+    
+    attach_rendered_posts(std::slice::from_mut(&mut response));
+    attach_post_emojis(std::slice::from_mut(&mut response));
+    Ok(response)
 }
 
 pub fn get_posts(pagination: PaginationParams) -> SquareResult<PostsResponse> {
+    let offset = pagination.offset.unwrap_or(0);
+    // Only the first page is hot enough to cache
+    let mut response = if offset != 0 {
+        load_posts(pagination)?
+    } else {
+        let limit = pagination.limit.unwrap_or(10);
+        cached(CachedQuery::LatestPosts, limit, 0, || load_posts(pagination))?
+    };
+    // The social-proof preview is per caller, so it is filled in after the shared cache
+    attach_liked_by_followed(&mut response.posts);
+    attach_article_summaries(&mut response.posts);
+    attach_rendered_posts(&mut response.posts);
+    attach_post_emojis(&mut response.posts);
+    let mut budget = RESPONSE_SIZE_BUDGET;
+    if truncate_to_budget(&mut response.posts, &mut budget) {
+        response.next_offset = next_offset_after_truncation(offset, response.posts.len());
+        response.truncated = true;
+    }
+    Ok(response)
+}
+
+fn load_posts(pagination: PaginationParams) -> SquareResult<PostsResponse> {
     const MODULE: &str = "services::content::posts";
     const FUNCTION: &str = "get_posts";
     
-    let limit = pagination.limit;
-    let offset = pagination.offset;
-    
     // Get posts from storage
     let mut posts = STORAGE.with(|storage| {
         let store = storage.borrow();
-        store.posts.values().cloned().collect::<Vec<Post>>()
+        // Only published posts; embargoed and held ones are not Active yet. Deactivated accounts are hidden.
+        store.posts
+            .values()
+            .filter(|post| post.status == ContentStatus::Active && !store.account_deactivations.contains_key(&post.author))
+            .cloned()
+            .collect::<Vec<Post>>()
     });
     
     // Sort by creation time (newest first)
     posts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
     
     // Apply pagination
-    let _total = posts.len() as u64;
-    let start = offset.unwrap_or(0);
-    let limit = limit.unwrap_or(10);
-    let end = (start + limit).min(posts.len());
+    let (start, end) = page_bounds(posts.len(), &pagination, 10);
     let posts_slice = posts[start..end].to_vec();
     let posts_len = posts.len();
     
     Ok(PostsResponse {
         posts: posts_slice.into_iter().map(|p| -> Result<PostResponse, SquareError> {
-            Ok(PostResponse {
+            Ok(withhold_gated_content(PostResponse {
             id: p.id,
             author: p.author,
             content: p.content,
@@ -178,26 +370,129 @@ pub fn get_posts(pagination: PaginationParams) -> SquareResult<PostsResponse> {
             comments_count: 0, // TODO: Get from comments storage
             author_info: get_user_social_info(p.author.to_string(), None)?,
             news_reference: p.news_reference,
-        })
+            posted_by: p.posted_by,
+            title: p.title.clone(),
+            is_premium: p.is_premium,
+            license: p.license,
+            token_gate: p.token_gate.clone(),
+            liked_by_followed: Vec::new(),
+            summary: None,
+            format: p.format,
+            source: None,
+            reactions: Vec::new(),
+            emojis: Vec::new(),
+            content_hash: p.content_hash,
+            cross_post_of: p.cross_post_of,
+            table_of_contents: Vec::new(),
+            cover_image: p.cover_image,
+            thumbnail: p.thumbnail,
+        }))
         }).collect::<Result<Vec<_>, _>>()?,
-        next_offset: if (start + limit) < posts_len { start + limit } else { posts_len },
+        next_offset: end,
         total: posts_len as u64,
+        truncated: false,
     })
 }
 
-pub fn update_post(request: UpdatePostRequest, caller: Principal) -> SquareResult<PostResponse> {
+// Public, active licensed content for syndication partners, newest first
+pub fn get_content_by_license(
+    license: ContentLicense,
+    since: Option<u64>,
+    pagination: PaginationParams,
+) -> SquareResult<PaginatedResponse<PostResponse>> {
+    let mut posts: Vec<(String, u64)> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.posts
+            .values()
+            .filter(|post| post.license == Some(license)
+                && post.status == ContentStatus::Active
+                && post.visibility == ContentVisibility::Public
+                && !post.is_premium
+                && post.token_gate.is_none()
+                && since.is_none_or(|since| post.created_at >= since))
+            .map(|post| (post.id.clone(), post.created_at))
+            .collect()
+    });
+    posts.sort_by_key(|(_, created_at)| std::cmp::Reverse(*created_at));
+
+    let page = paginate(posts, pagination);
+    Ok(PaginatedResponse {
+        items: page.items.into_iter().map(|(id, _)| get_post(id)).collect::<SquareResult<Vec<_>>>()?,
+        total: page.total,
+        has_more: page.has_more,
+        next_offset: page.next_offset,
+    })
+}
+
+pub fn update_post(mut request: UpdatePostRequest, caller: Principal) -> SquareResult<PostResponse> {
     const MODULE: &str = "services::content::posts";
     const FUNCTION: &str = "update_post";
     
-    STORAGE.with(|storage| {
+    invalidate(POST_QUERIES);
+    
+    let has_media = has_inline_media(&request.content)
+        || request.media_urls.iter().flatten().any(|url| has_inline_media(url));
+    ensure_media_uploads_allowed(has_media, "update_post", MODULE, FUNCTION)?;
+    
+    // Delegates of the author's organization account may also edit its posts
+    let delegated_author = delegated_author_of(&request.id, caller);
+    
+    let existing = STORAGE.with(|storage| {
+        storage.borrow().posts.get(&request.id).map(|post| (post.author, post.title.is_some(), post.format, post.media_urls.len()))
+    });
+    let format = request.format.or(existing.map(|(_, _, format, _)| format)).unwrap_or_default();
+    if format == ContentFormat::Html {
+        request.content = sanitize_content(&request.content);
+    }
+    
+    if let Some((post_author, is_article, _, media_count)) = existing {
+        validate_update_post(&request, is_article).finish(MODULE, FUNCTION)?;
+        
+        // Edits may not add links or media past the author's trust level
+        let media_count = request.media_urls.as_ref().map_or(media_count, |urls| urls.len());
+        check_content_privileges(post_author, &request.content, media_count)?;
+        
+        // Turning a post into an article is creator-only
+        if request.title.is_some() {
+            validate_creator_post(post_author, &request.title, false)?;
+        }
+        
+        // Restricted tags the edit adds must allow the author's role; tags the post already has stay
+        if let Some(hashtags) = &request.hashtags {
+            let current = STORAGE.with(|storage| {
+                storage.borrow().posts.get(&request.id).map(|post| post.hashtags.clone()).unwrap_or_default()
+            });
+            let added: Vec<String> = normalize_hashtags(hashtags.clone())
+                .into_iter()
+                .filter(|tag| !current.contains(tag))
+                .collect();
+            check_tag_roles(post_author, &added, "update_post")?;
+        }
+    }
+    
+    // Edits that grow the post count against the author's storage quota
+    let quota = existing.and_then(|(post_author, _, _, _)| storage_quota(post_author));
+    
+    let mut result = STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
+        let store = &mut *store;
         
-        let post = store.posts.get_mut(&request.id).ok_or_else(|| {
+        ensure_not_on_legal_hold(store, &request.id, "update_post", MODULE, FUNCTION)?;
+        let mut post = store.posts.get_mut(&request.id).ok_or_else(|| {
             not_found_error("Post", &request.id, MODULE, FUNCTION)
         })?;
         
-        // Check if caller is the author or admin
-        if post.author != caller {
+        if post.cross_post_of.is_some() {
+            return log_and_return(invalid_operation_error(
+                "update_post",
+                "Cross-posts follow their original post; edit the original instead",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        
+        // Check if caller is the author, a delegate or admin
+        if post.author != caller && delegated_author.is_none() {
             match is_admin() {
                 Ok(_) => {},
                 Err(_) => {
@@ -210,49 +505,27 @@ pub fn update_post(request: UpdatePostRequest, caller: Principal) -> SquareResul
             }
         }
         
-        // Validate content length
-        let content_length = calculate_content_length_excluding_base64_and_html(&request.content);
-        if content_length > MAX_POST_LENGTH {
-            return log_and_return(content_too_long_error(
-                "Post",
-                MAX_POST_LENGTH,
-                content_length,
-                MODULE,
-                FUNCTION
-            ));
+        let previous = post.clone();
+        let licensable = request.title.is_some()
+            || post.title.is_some()
+            || request.media_urls.as_ref().map_or(!post.media_urls.is_empty(), |urls| !urls.is_empty());
+        post.license = resolve_license(request.license, post.license, licensable)?;
+        
+        if let Some(title) = request.title {
+            post.title = Some(title);
         }
         post.content = request.content;
+        post.format = format;
         
         if let Some(hashtags) = request.hashtags {
-            if hashtags.len() > MAX_HASHTAGS {
-                return log_and_return(validation_error(
-                    &format!("Too many hashtags. Maximum allowed is {}", MAX_HASHTAGS),
-                    MODULE,
-                    FUNCTION
-                ));
-            }
-            post.hashtags = hashtags;
+            post.hashtags = normalize_hashtags(hashtags);
         }
         
         if let Some(token_mentions) = request.token_mentions {
-            if token_mentions.len() > MAX_TOKEN_MENTIONS {
-                return log_and_return(validation_error(
-                    &format!("Too many token mentions. Maximum allowed is {}", MAX_TOKEN_MENTIONS),
-                    MODULE,
-                    FUNCTION
-                ));
-            }
             post.token_mentions = token_mentions;
         }
         
         if let Some(media_urls) = request.media_urls {
-            if media_urls.len() > MAX_MEDIA_URLS {
-                return log_and_return(validation_error(
-                    &format!("Too many media URLs. Maximum allowed is {}", MAX_MEDIA_URLS),
-                    MODULE,
-                    FUNCTION
-                ));
-            }
             post.media_urls = media_urls;
         }
         
@@ -268,8 +541,23 @@ pub fn update_post(request: UpdatePostRequest, caller: Principal) -> SquareResul
             post.news_reference = Some(news_reference);
         }
         
+        if let Some(cover_image) = request.cover_image {
+            post.cover_image = Some(cover_image).filter(|url| !url.is_empty());
+        }
+        
+        if let Some(thumbnail) = request.thumbnail {
+            post.thumbnail = Some(thumbnail).filter(|url| !url.is_empty());
+        }
+        
         post.updated_at = time() / 1_000_000;
         
+        if let Err(error) = charge_storage(&mut store.storage_usage, post.author, post_size(&previous), post_size(&post), quota) {
+            *post = previous;
+            return Err(error);
+        }
+        let updated_at = post.updated_at;
+        stamp_content_hash(&mut post, &mut store.pending_content_hashes, updated_at);
+        
         Ok(PostResponse {
             id: post.id.clone(),
             author: post.author,
@@ -286,14 +574,52 @@ pub fn update_post(request: UpdatePostRequest, caller: Principal) -> SquareResul
             comments_count: 0, // TODO: Get from comments storage
             author_info: get_user_social_info(post.author.to_string(), None)?,
             news_reference: post.news_reference.clone(),
+            posted_by: post.posted_by,
+            title: post.title.clone(),
+            is_premium: post.is_premium,
+            license: post.license,
+            token_gate: post.token_gate.clone(),
+            liked_by_followed: Vec::new(),
+            summary: None,
+            format: post.format,
+            source: None,
+            reactions: Vec::new(),
+            emojis: Vec::new(),
+            content_hash: post.content_hash.clone(),
+            cross_post_of: post.cross_post_of.clone(),
+            table_of_contents: Vec::new(),
+            cover_image: post.cover_image.clone(),
+            thumbnail: post.thumbnail.clone(),
         })
-    })
+    })?;
+    
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        refresh_article_summary(&mut store, &request.id);
+        refresh_rendered_content(&mut store, &request.id);
+        refresh_article_outline(&mut store, &request.id);
+        sync_cross_posts(&mut store, &request.id);
+    });
+    attach_article_summaries(std::slice::from_mut(&mut result));
+    attach_table_of_contents(std::slice::from_mut(&mut result));
+    attach_rendered_posts(std::slice::from_mut(&mut result));
+    attach_post_emojis(std::slice::from_mut(&mut result));
+    
+    if let Some(org) = delegated_author {
+        record_delegated_action(org, caller, DelegatedAction::PostUpdated, Some(request.id.clone()));
+    }
+    
+    Ok(result)
 }
 
 pub fn delete_post(id: String, caller: Principal) -> SquareResult<()> {
     const MODULE: &str = "services::content::posts";
     const FUNCTION: &str = "delete_post";
     
+    invalidate(POST_QUERIES);
+    
+    let delegated_author = delegated_author_of(&id, caller);
+    
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         
@@ -301,8 +627,8 @@ pub fn delete_post(id: String, caller: Principal) -> SquareResult<()> {
             not_found_error("Post", &id, MODULE, FUNCTION)
         })?;
         
-        // Check if caller is the author or admin
-        if post.author != caller && is_admin().is_err() {
+        // Check if caller is the author, a delegate or admin
+        if post.author != caller && delegated_author.is_none() && is_admin().is_err() {
             return log_and_return(unauthorized_error(
                 "Only the author or admin can delete this post",
                 MODULE,
@@ -310,9 +636,37 @@ pub fn delete_post(id: String, caller: Principal) -> SquareResult<()> {
             ));
         }
         
-        // Remove post from storage
-        store.posts.remove(&id);
+        if thread_on_legal_hold(&store, &ContentType::Post, &id) {
+            return log_and_return(invalid_operation_error(
+                "delete_post",
+                "The post or one of its comments is on legal hold",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        
+        remove_post(&mut store, caller, &id);
         
         Ok(())
-    })
+    })?;
+    
+    if let Some(org) = delegated_author {
+        record_delegated_action(org, caller, DelegatedAction::PostDeleted, Some(id));
+    }
+    
+    Ok(())
+}
+
+// Returns the post's author if the caller is acting as one of its posting delegates
+fn delegated_author_of(post_id: &str, caller: Principal) -> Option<Principal> {
+    let author = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.posts.get(post_id).map(|post| post.author)
+    })?;
+    
+    if author != caller && is_posting_delegate(author, caller) {
+        Some(author)
+    } else {
+        None
+    }
 }
@@ -0,0 +1,96 @@
+use crate::models::content::{CommentResponse, ContentFormat, PostResponse, RenderedContent};
+use crate::models::storage::Storage;
+use crate::services::classifier::content_hash;
+use crate::services::content::sanitizer::allowed_html_tags;
+use crate::services::user::creator::can_read_premium;
+use crate::storage::STORAGE;
+use crate::utils::content_utils::render_content_html;
+
+fn source_hash(format: ContentFormat, source: &str) -> String {
+    content_hash(&format!("{:?}:{}", format, source))
+}
+
+// Render a Markdown or Plain post or comment once it is written; HTML content is stored already sanitized
+pub fn refresh_rendered_content(store: &mut Storage, content_id: &str) {
+    let stored = store.posts
+        .get(content_id)
+        .map(|post| (post.format, &post.content))
+        .or_else(|| store.comments.get(content_id).map(|comment| (comment.format, &comment.content)))
+        .filter(|(format, _)| *format != ContentFormat::Html);
+    let Some((format, source)) = stored else {
+        store.rendered_content.remove(content_id);
+        return;
+    };
+    let source_hash = source_hash(format, source);
+    if store.rendered_content.get(content_id).is_some_and(|cached| cached.source_hash == source_hash) {
+        return;
+    }
+    let html = render_content_html(format, source, &allowed_html_tags(store));
+    store.rendered_content.insert(content_id.to_string(), RenderedContent { source_hash, html });
+}
+
+// HTML for stored content, from the cache when it matches the source
+pub fn rendered_html(store: &Storage, content_id: &str, format: ContentFormat, source: &str) -> String {
+    if format == ContentFormat::Html {
+        return source.to_string();
+    }
+    let source_hash = source_hash(format, source);
+    match store.rendered_content.get(content_id).filter(|cached| cached.source_hash == source_hash) {
+        Some(cached) => cached.html.clone(),
+        None => render_content_html(format, source, &allowed_html_tags(store)),
+    }
+}
+
+// Swap Markdown and Plain sources for their HTML; gated posts keep their content withheld, and
+// premium posts lose theirs unless the caller may read them
+pub fn attach_rendered_posts(posts: &mut [PostResponse]) {
+    let caller = ic_cdk::caller();
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        for post in posts.iter_mut() {
+            if post.is_premium && !can_read_premium(&store, &post.author, caller) {
+                post.content = String::new();
+                post.media_urls.clear();
+                post.table_of_contents.clear();
+                post.source = None;
+                continue;
+            }
+            // Comment-type views reuse PostResponse, so look in both maps
+            let stored = store.posts
+                .get(&post.id)
+                .map(|stored| (stored.format, &stored.content))
+                .or_else(|| store.comments.get(&post.id).map(|stored| (stored.format, &stored.content)));
+            let Some((format, source)) = stored else {
+                continue;
+            };
+            post.format = format;
+            if format == ContentFormat::Html || post.token_gate.is_some() {
+                continue;
+            }
+            post.content = rendered_html(&store, &post.id, format, source);
+            post.source = Some(source.clone());
+        }
+    });
+}
+
+fn render_comment(store: &Storage, comment: &mut CommentResponse) {
+    if let Some(stored) = store.comments.get(&comment.id) {
+        comment.format = stored.format;
+        if stored.format != ContentFormat::Html {
+            comment.content = rendered_html(store, &comment.id, stored.format, &stored.content);
+            comment.source = Some(stored.content.clone());
+        }
+    }
+    for child in comment.child_comments.iter_mut() {
+        render_comment(store, child);
+    }
+}
+
+pub fn attach_rendered_comments(comments: &mut [CommentResponse]) {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        for comment in comments.iter_mut() {
+            render_comment(&store, comment);
+        }
+    });
+}
@@ -0,0 +1,117 @@
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::auth::is_admin;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::sanitizer::*;
+use crate::models::storage::Storage;
+use crate::services::content::outline::refresh_article_outline;
+use crate::services::content::rendering::refresh_rendered_content;
+use crate::storage::STORAGE;
+use crate::utils::content_utils::sanitize_html;
+use crate::utils::error_handler::*;
+use crate::utils::logger;
+
+const MODULE: &str = "services::content::sanitizer";
+
+pub fn allowed_html_tags(store: &Storage) -> Vec<String> {
+    match &store.html_sanitizer_settings {
+        Some(settings) => settings.allowed_tags.clone(),
+        None => HtmlSanitizerSettings::default().allowed_tags,
+    }
+}
+
+// Sanitize post or comment HTML with the configured tag list before it is stored
+pub fn sanitize_content(content: &str) -> String {
+    let allowed_tags = STORAGE.with(|storage| allowed_html_tags(&storage.borrow()));
+    sanitize_html(content, &allowed_tags)
+}
+
+pub fn get_html_sanitizer_settings() -> SquareResult<HtmlSanitizerSettings> {
+    const FUNCTION: &str = "get_html_sanitizer_settings";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_html_sanitizer_settings",
+            "Only admins can view sanitizer settings",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    Ok(STORAGE.with(|storage| storage.borrow().html_sanitizer_settings.clone().unwrap_or_default()))
+}
+
+// Replace the list of HTML tags kept in new and edited content
+pub fn update_allowed_html_tags(allowed_tags: Vec<String>, caller: Principal) -> SquareResult<HtmlSanitizerSettings> {
+    const FUNCTION: &str = "update_allowed_html_tags";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_allowed_html_tags",
+            "Only admins can update sanitizer settings",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let mut tags: Vec<String> = allowed_tags.iter().map(|tag| tag.trim().to_ascii_lowercase()).collect();
+    tags.sort();
+    tags.dedup();
+    if tags.is_empty() || tags.len() > MAX_ALLOWED_HTML_TAGS {
+        return log_and_return(field_validation_error(
+            "allowed_tags",
+            &format!("must list between 1 and {} tags", MAX_ALLOWED_HTML_TAGS),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if let Some(tag) = tags.iter().find(|tag| tag.is_empty() || !tag.chars().all(|c| c.is_ascii_alphanumeric())) {
+        return log_and_return(field_validation_error(
+            "allowed_tags",
+            &format!("\"{}\" is not a valid tag name", tag),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if let Some(tag) = tags.iter().find(|tag| FORBIDDEN_HTML_TAGS.contains(&tag.as_str())) {
+        return log_and_return(field_validation_error(
+            "allowed_tags",
+            &format!("<{}> can never be allowed", tag),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let settings = HtmlSanitizerSettings {
+        allowed_tags: tags,
+        updated_by: Some(caller),
+        updated_at: time() / 1_000_000,
+    };
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        store.html_sanitizer_settings = Some(settings.clone());
+        // Drop the old renders so reads render against the new list, and queue them for the heartbeat
+        store.rendered_content_refresh.extend(store.rendered_content.drain().map(|(content_id, _)| content_id));
+    });
+    Ok(settings)
+}
+
+// Re-render content queued by the last allowed tag change, a batch per heartbeat
+pub fn run_rendered_content_refresh() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.rendered_content_refresh.is_empty() {
+            return;
+        }
+        let split = store.rendered_content_refresh.len().saturating_sub(RERENDER_BATCH_SIZE);
+        let batch = store.rendered_content_refresh.split_off(split);
+        for content_id in &batch {
+            refresh_rendered_content(&mut store, content_id);
+            refresh_article_outline(&mut store, content_id);
+        }
+        if store.rendered_content_refresh.is_empty() {
+            logger::log(&format!("[{}] Re-rendered content against the allowed HTML tags", MODULE));
+        }
+    });
+}
@@ -0,0 +1,213 @@
+// Embargo and sunset times for posts. An embargoed post is stored Scheduled and is not counted, sent
+// to timelines or alerted on; only its author sees it. When the embargo lifts it is published as if
+// it had just been created, unless a moderator or a ban took it down in the meantime. From its sunset
+// time a post is hidden like any moderated post. The heartbeat applies both, reading due times from
+// the schedule queue.
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::models::content::{ContentSchedule, ContentStatus, ScheduledPostResponse, SetContentScheduleRequest, MAX_SCHEDULE_DAYS};
+use crate::models::error::SquareResult;
+use crate::models::storage::Storage;
+use crate::services::content::approval::publish_post;
+use crate::services::content::legal_hold::ensure_not_on_legal_hold;
+use crate::services::content::posts::post_response;
+use crate::services::content::transitions::set_post_status;
+use crate::services::query_cache::{invalidate, POST_QUERIES};
+use crate::services::user::delegation::is_posting_delegate;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::MILLIS_PER_DAY;
+
+const MODULE: &str = "services::content::schedule";
+
+pub(crate) fn embargoed(store: &Storage, post_id: &str, now: u64) -> bool {
+    store.content_schedules
+        .get(post_id)
+        .and_then(|schedule| schedule.embargo_until)
+        .is_some_and(|embargo_until| embargo_until > now)
+}
+
+// Both times must be ahead, within MAX_SCHEDULE_DAYS, and the sunset after the embargo
+pub(crate) fn validate_schedule(schedule: &ContentSchedule, now: u64, module: &str, function: &str) -> SquareResult<()> {
+    let horizon = now + MAX_SCHEDULE_DAYS * MILLIS_PER_DAY;
+    for (field, at) in [("embargo_until", schedule.embargo_until), ("sunset_at", schedule.sunset_at)] {
+        let Some(at) = at else {
+            continue;
+        };
+        if at <= now {
+            return log_and_return(field_validation_error(field, "must be in the future", module, function));
+        }
+        if at > horizon {
+            return log_and_return(field_validation_error(
+                field,
+                &format!("must be at most {} days ahead", MAX_SCHEDULE_DAYS),
+                module,
+                function
+            ));
+        }
+    }
+    let sunset_first = schedule.embargo_until
+        .zip(schedule.sunset_at)
+        .is_some_and(|(embargo_until, sunset_at)| sunset_at <= embargo_until);
+    if sunset_first {
+        return log_and_return(field_validation_error("sunset_at", "must be after embargo_until", module, function));
+    }
+    Ok(())
+}
+
+fn due_times(schedule: &ContentSchedule) -> impl Iterator<Item = u64> {
+    [schedule.embargo_until, schedule.sunset_at].into_iter().flatten()
+}
+
+// Replace a post's schedule and its entries in the schedule queue; the default schedule removes it
+pub(crate) fn store_schedule(store: &mut Storage, post_id: &str, schedule: ContentSchedule) {
+    if let Some(previous) = store.content_schedules.remove(post_id) {
+        for at in due_times(&previous) {
+            store.schedule_queue.remove(&(at, post_id.to_string()));
+        }
+    }
+    if schedule != ContentSchedule::default() {
+        for at in due_times(&schedule) {
+            store.schedule_queue.insert((at, post_id.to_string()));
+        }
+        store.content_schedules.insert(post_id.to_string(), schedule);
+    }
+}
+
+// Publish an embargoed post. Posts still held for approval are published when approved instead.
+fn lift_embargo(store: &mut Storage, post_id: &str, now: u64) {
+    let Some(mut post) = store.posts.get_mut(post_id).filter(|post| post.status == ContentStatus::Scheduled) else {
+        return;
+    };
+    post.status = ContentStatus::Active;
+    post.created_at = now;
+    post.updated_at = now;
+    let author = post.author;
+    drop(post);
+    publish_post(store, author, post_id);
+}
+
+fn sunset_post(store: &mut Storage, post_id: &str) {
+    let held = store.pending_approvals.remove(post_id).is_some();
+    let Some((author, status)) = store.posts.get(post_id).map(|post| (post.author, post.status.clone())) else {
+        return;
+    };
+    if status == ContentStatus::Active {
+        set_post_status(store, author, post_id, ContentStatus::Hidden);
+    } else if let Some(mut post) = store.posts
+        .get_mut(post_id)
+        .filter(|_| status == ContentStatus::Scheduled || (held && status == ContentStatus::UnderReview))
+    {
+        // Never published, so there is nothing to take back
+        post.status = ContentStatus::Hidden;
+    }
+}
+
+// Replace a post's schedule. Embargoes can only be set or moved before the post is published, that
+// is while it is embargoed or held for approval; clearing an embargo publishes the post now.
+pub fn set_content_schedule(request: SetContentScheduleRequest, caller: Principal) -> SquareResult<ScheduledPostResponse> {
+    const FUNCTION: &str = "set_content_schedule";
+
+    invalidate(POST_QUERIES);
+
+    let now = time() / 1_000_000;
+    let schedule = ContentSchedule {
+        embargo_until: request.embargo_until,
+        sunset_at: request.sunset_at,
+    };
+    validate_schedule(&schedule, now, MODULE, FUNCTION)?;
+
+    let author = STORAGE.with(|storage| storage.borrow().posts.get(&request.post_id).map(|post| post.author))
+        .ok_or_else(|| not_found_error("Post", &request.post_id, MODULE, FUNCTION))?;
+    if author != caller && !is_posting_delegate(author, caller) {
+        return log_and_return(permission_denied_error(
+            "set_content_schedule",
+            "Only the author can schedule this post",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        ensure_not_on_legal_hold(store, &request.post_id, "set_content_schedule", MODULE, FUNCTION)?;
+        let was_embargoed = embargoed(store, &request.post_id, now);
+        let unpublished = was_embargoed || store.pending_approvals.contains_key(&request.post_id);
+        if schedule.embargo_until.is_some() && !unpublished {
+            return log_and_return(invalid_operation_error(
+                "set_content_schedule",
+                "Published posts cannot be embargoed",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        store_schedule(store, &request.post_id, schedule.clone());
+        if was_embargoed && schedule.embargo_until.is_none() {
+            lift_embargo(store, &request.post_id, now);
+        }
+        Ok(())
+    })?;
+
+    Ok(ScheduledPostResponse {
+        post: post_response(request.post_id, true)?,
+        schedule,
+    })
+}
+
+// The caller's posts with an embargo or sunset still ahead, soonest first
+pub fn get_my_scheduled_posts(caller: Principal) -> SquareResult<Vec<ScheduledPostResponse>> {
+    let mut scheduled: Vec<(String, ContentSchedule)> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.content_schedules
+            .iter()
+            .filter(|(post_id, _)| store.posts.get(*post_id).is_some_and(|post| post.author == caller))
+            .map(|(post_id, schedule)| (post_id.clone(), schedule.clone()))
+            .collect()
+    });
+    scheduled.sort_by_key(|(post_id, schedule)| (schedule.embargo_until.or(schedule.sunset_at), post_id.clone()));
+
+    scheduled
+        .into_iter()
+        .map(|(post_id, schedule)| Ok(ScheduledPostResponse {
+            post: post_response(post_id, true)?,
+            schedule,
+        }))
+        .collect()
+}
+
+// Lift embargoes and apply sunsets that are due; runs on every heartbeat
+pub fn run_content_schedules() {
+    let now = time() / 1_000_000;
+    let applied = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        // Take the due entries off the queue; schedules with a time still ahead are queued again
+        let later = store.schedule_queue.split_off(&(now + 1, String::new()));
+        let mut due: Vec<String> = std::mem::replace(&mut store.schedule_queue, later)
+            .into_iter()
+            .map(|(_, post_id)| post_id)
+            .collect();
+        due.sort();
+        due.dedup();
+        for post_id in &due {
+            let Some(mut schedule) = store.content_schedules.get(post_id).cloned() else {
+                continue;
+            };
+            if schedule.embargo_until.is_some_and(|at| at <= now) {
+                schedule.embargo_until = None;
+                lift_embargo(store, post_id, now);
+            }
+            if schedule.sunset_at.is_some_and(|at| at <= now) {
+                schedule.sunset_at = None;
+                sunset_post(store, post_id);
+            }
+            store_schedule(store, post_id, schedule);
+        }
+        !due.is_empty()
+    });
+    if applied {
+        invalidate(POST_QUERIES);
+    }
+}
@@ -0,0 +1,185 @@
+// Thread subscriptions: users follow a post to hear about new comments on it. Comments are
+// gathered per thread for THREAD_DIGEST_WINDOW and then sent to each subscriber as one digest
+// notification, in batches by the heartbeat.
+use candid::Principal;
+use std::ops::Bound;
+
+use crate::models::content::ContentStatus;
+use crate::models::error::SquareResult;
+use crate::models::notification::NotificationType;
+use crate::models::storage::Storage;
+use crate::models::subscription::*;
+use crate::services::content::posts::can_view_post;
+use crate::services::content::summary::post_excerpt;
+use crate::services::user::notification::push_notification;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::instruction_budget::approaching_instruction_limit;
+use crate::utils::time_utils::{now_millis, TimestampMillis};
+
+const MODULE: &str = "services::content::subscriptions";
+// Characters of the post quoted in a digest
+const DIGEST_EXCERPT_LENGTH: usize = 80;
+
+// Subscribing and muting need a post the caller can read; unsubscribing is always allowed
+fn set_state(content_id: String, state: Option<ThreadSubscriptionState>, caller: Principal, function: &str) -> SquareResult<ContentSubscriptionResponse> {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let active = store.posts
+            .get(&content_id)
+            .filter(|post| post.status == ContentStatus::Active)
+            .is_some_and(|post| state.is_none() || can_view_post(&store, post, caller));
+        if !active {
+            return log_and_return(not_found_error("Post", &content_id, MODULE, function));
+        }
+        match state {
+            Some(state) => {
+                store.content_subscriptions.entry(content_id.clone()).or_default().insert(caller, state);
+            }
+            None => remove_subscription(&mut store, &content_id, caller),
+        }
+        Ok(ContentSubscriptionResponse { content_id, state })
+    })
+}
+
+fn remove_subscription(store: &mut Storage, post_id: &str, user: Principal) {
+    if let Some(subscribers) = store.content_subscriptions.get_mut(post_id) {
+        subscribers.remove(&user);
+        if subscribers.is_empty() {
+            store.content_subscriptions.remove(post_id);
+        }
+    }
+}
+
+pub fn subscribe_to_content(content_id: String, caller: Principal) -> SquareResult<ContentSubscriptionResponse> {
+    set_state(content_id, Some(ThreadSubscriptionState::Subscribed), caller, "subscribe_to_content")
+}
+
+// Muting also keeps commenting from subscribing the caller again
+pub fn mute_content(content_id: String, caller: Principal) -> SquareResult<ContentSubscriptionResponse> {
+    set_state(content_id, Some(ThreadSubscriptionState::Muted), caller, "mute_content")
+}
+
+pub fn unsubscribe_from_content(content_id: String, caller: Principal) -> SquareResult<ContentSubscriptionResponse> {
+    set_state(content_id, None, caller, "unsubscribe_from_content")
+}
+
+pub fn get_content_subscription(content_id: String, caller: Principal) -> SquareResult<ContentSubscriptionResponse> {
+    let state = STORAGE.with(|storage| {
+        storage.borrow().content_subscriptions.get(&content_id).and_then(|subscribers| subscribers.get(&caller).copied())
+    });
+    Ok(ContentSubscriptionResponse { content_id, state })
+}
+
+// Subscribe a post's author, or a commenter who asked to be, unless they muted the thread
+pub fn auto_subscribe(store: &mut Storage, post_id: &str, user: Principal) {
+    store.content_subscriptions.entry(post_id.to_string()).or_default().entry(user).or_insert(ThreadSubscriptionState::Subscribed);
+}
+
+// Count a new comment towards the thread's next digest
+pub fn record_thread_comment(store: &mut Storage, post_id: &str, commenter: Principal, now: TimestampMillis) {
+    if !store.content_subscriptions.contains_key(post_id) {
+        return;
+    }
+    match store.thread_activity.get_mut(post_id) {
+        Some(activity) => {
+            activity.new_comments += 1;
+            activity.last_commenter = commenter;
+            if activity.commenters.len() < THREAD_DIGEST_COMMENTERS && !activity.commenters.contains(&commenter) {
+                activity.commenters.push(commenter);
+            }
+        }
+        None => {
+            store.thread_activity.insert(post_id.to_string(), ThreadActivity {
+                new_comments: 1,
+                commenters: vec![commenter],
+                last_commenter: commenter,
+            });
+            store.thread_digest_queue.push_back(ThreadDigestJob {
+                post_id: post_id.to_string(),
+                due_at: now + THREAD_DIGEST_WINDOW,
+                activity: None,
+                resume_after: None,
+            });
+        }
+    }
+}
+
+pub fn remove_thread_subscriptions(store: &mut Storage, post_id: &str) {
+    store.content_subscriptions.remove(post_id);
+    store.thread_activity.remove(post_id);
+}
+
+fn digest_content(store: &Storage, post_id: &str, activity: &ThreadActivity) -> Option<String> {
+    let post = store.posts.get(post_id).filter(|post| post.status == ContentStatus::Active)?;
+    let thread = post_excerpt(post, DIGEST_EXCERPT_LENGTH)
+        .map_or_else(|| "a members-only post".to_string(), |excerpt| format!("\"{}\"", excerpt));
+    Some(if activity.new_comments == 1 {
+        format!("New comment on {}", thread)
+    } else {
+        format!("{} new comments on {}", activity.new_comments, thread)
+    })
+}
+
+// Send due digests, up to THREAD_DIGEST_BATCH_SIZE notifications per heartbeat. A thread with
+// more subscribers than that continues on the next heartbeat where it left off.
+pub fn run_thread_digests() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let now = now_millis();
+        let mut sent = 0;
+
+        while sent < THREAD_DIGEST_BATCH_SIZE && !approaching_instruction_limit() {
+            let Some(job) = store.thread_digest_queue.front().filter(|job| job.due_at <= now).cloned() else {
+                break;
+            };
+            // Comments from now on count towards the thread's next digest
+            let activity = match job.activity {
+                Some(activity) => activity,
+                None => match store.thread_activity.remove(&job.post_id) {
+                    Some(activity) => activity,
+                    None => {
+                        store.thread_digest_queue.pop_front();
+                        continue;
+                    }
+                },
+            };
+            let Some(content) = digest_content(store, &job.post_id, &activity) else {
+                store.thread_digest_queue.pop_front();
+                continue;
+            };
+
+            // Subscribers whose own comment is the only news are skipped
+            let only_commenter = (activity.commenters.len() == 1).then(|| activity.commenters[0]);
+            let lower = job.resume_after.map_or(Bound::Unbounded, Bound::Excluded);
+            let batch: Vec<(Principal, ThreadSubscriptionState)> = store.content_subscriptions
+                .get(&job.post_id)
+                .map(|subscribers| subscribers.range((lower, Bound::Unbounded)).take(THREAD_DIGEST_BATCH_SIZE - sent).map(|(user, state)| (*user, *state)).collect())
+                .unwrap_or_default();
+
+            for (subscriber, state) in &batch {
+                if *state == ThreadSubscriptionState::Muted || only_commenter == Some(*subscriber) {
+                    continue;
+                }
+                push_notification(
+                    store,
+                    *subscriber,
+                    NotificationType::Comment,
+                    content.clone(),
+                    Some(job.post_id.clone()),
+                    Some(activity.last_commenter),
+                    now
+                );
+            }
+            sent += batch.len();
+
+            if sent < THREAD_DIGEST_BATCH_SIZE {
+                store.thread_digest_queue.pop_front();
+            } else if let Some(front) = store.thread_digest_queue.front_mut() {
+                front.activity = Some(activity);
+                front.resume_after = batch.last().map(|(user, _)| *user);
+            }
+        }
+    });
+}
@@ -0,0 +1,91 @@
+use ic_cdk::api::time;
+
+use crate::models::content::*;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::storage::Storage;
+use crate::services::classifier::content_hash;
+use crate::storage::STORAGE;
+use crate::utils::content_utils::{calculate_content_length_excluding_base64_and_html, extractive_summary, strip_html_tags};
+use crate::utils::error_handler::*;
+
+const MODULE: &str = "services::content::summary";
+
+fn build_summary(post: &Post, source_hash: String) -> ArticleSummary {
+    ArticleSummary {
+        post_id: post.id.clone(),
+        summary: extractive_summary(&post.content, SUMMARY_MAX_SENTENCES, SUMMARY_MAX_CHARS),
+        source_hash,
+        generated_at: time() / 1_000_000,
+    }
+}
+
+// A post's title, or the start of its text, for quoting it in notifications. Token-gated and premium
+// posts are quoted by title only, since not every recipient may read them; None when they have none.
+pub fn post_excerpt(post: &Post, max_chars: usize) -> Option<String> {
+    match &post.title {
+        Some(title) => Some(title.clone()),
+        None if post.token_gate.is_some() || post.is_premium => None,
+        None => Some(strip_html_tags(&post.content).chars().take(max_chars).collect()),
+    }
+}
+
+fn is_long_article(post: &Post) -> bool {
+    post.title.is_some() && calculate_content_length_excluding_base64_and_html(&post.content) > SUMMARY_MIN_ARTICLE_LENGTH
+}
+
+// Rebuild a post's cached summary after it is written; only long articles keep one
+pub fn refresh_article_summary(store: &mut Storage, post_id: &str) {
+    let Some(post) = store.posts.get(post_id).filter(|post| is_long_article(post)) else {
+        store.article_summaries.remove(post_id);
+        return;
+    };
+    let source_hash = content_hash(&post.content);
+    if store.article_summaries.get(post_id).is_some_and(|cached| cached.source_hash == source_hash) {
+        return;
+    }
+    let summary = build_summary(post, source_hash);
+    store.article_summaries.insert(post_id.to_string(), summary);
+}
+
+// Feed and detail views carry cached summaries; gated articles keep theirs withheld with the content
+pub fn attach_article_summaries(posts: &mut [PostResponse]) {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        for post in posts.iter_mut().filter(|post| post.token_gate.is_none()) {
+            post.summary = store.article_summaries.get(&post.id).map(|cached| cached.summary.clone());
+        }
+    });
+}
+
+pub fn get_article_summary(article_id: String) -> SquareResult<ArticleSummary> {
+    const FUNCTION: &str = "get_article_summary";
+
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let Some(post) = store.posts.get(&article_id).filter(|post| post.status == ContentStatus::Active) else {
+            return log_and_return(not_found_error("Post", &article_id, MODULE, FUNCTION));
+        };
+        if post.title.is_none() {
+            return log_and_return(invalid_operation_error(
+                "get_article_summary",
+                "Only articles have summaries",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        if post.token_gate.is_some() {
+            return log_and_return(permission_denied_error(
+                "get_article_summary",
+                "Summaries of token-gated articles are withheld; holders read them through get_gated_post",
+                MODULE,
+                FUNCTION
+            ));
+        }
+
+        // Articles too short for a cached summary still get one on request
+        match store.article_summaries.get(&article_id) {
+            Some(cached) => Ok(cached.clone()),
+            None => Ok(build_summary(post, content_hash(&post.content))),
+        }
+    })
+}
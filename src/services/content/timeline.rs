@@ -0,0 +1,220 @@
+use candid::Principal;
+use std::collections::HashSet;
+
+use crate::models::content::{ContentStatus, ContentVisibility, PaginationParams, Post, PostResponse};
+use crate::models::display::FeedResponse;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::storage::Storage;
+use crate::models::timeline::*;
+use crate::services::content::cross_posts::dedup_cross_posts;
+use crate::services::content::rendering::attach_rendered_posts;
+use crate::services::content::summary::attach_article_summaries;
+use crate::services::emoji::attach_post_emojis;
+use crate::services::token_gate::withhold_gated_content;
+use crate::services::user::deactivated_users;
+use crate::services::user::social::{attach_liked_by_followed, get_user_social_info};
+use crate::auth::is_admin;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::response_size::truncate_feed;
+
+const MODULE: &str = "services::content::timeline";
+
+fn fanout_threshold(store: &Storage) -> u64 {
+    store.timeline_settings.as_ref().map_or(DEFAULT_FANOUT_FOLLOWER_THRESHOLD, |settings| settings.fanout_follower_threshold)
+}
+
+fn followers_count(store: &Storage, user: &Principal) -> u64 {
+    store.user_profiles.as_ref().and_then(|profiles| profiles.get(user)).map_or(0, |profile| profile.followers_count)
+}
+
+fn shown_to_followers(post: &Post) -> bool {
+    post.visibility != ContentVisibility::Private
+}
+
+// Keep the newest entries, ordered oldest first
+fn trim_timeline(timeline: &mut Vec<String>) {
+    if timeline.len() > MAX_TIMELINE_ITEMS {
+        let excess = timeline.len() - MAX_TIMELINE_ITEMS;
+        timeline.drain(..excess);
+    }
+}
+
+// Push a new post into its author's followers' timelines; large accounts are read on demand instead
+pub fn fan_out_post(store: &mut Storage, post_id: &str) {
+    let Some(post) = store.posts.get(post_id).filter(|post| shown_to_followers(post)) else {
+        return;
+    };
+    let author = post.author;
+    if followers_count(store, &author) > fanout_threshold(store) {
+        return;
+    }
+    let followers: Vec<Principal> = store.user_profiles
+        .as_ref()
+        .and_then(|profiles| profiles.get(&author))
+        .map(|profile| profile.followers.iter().copied().collect())
+        .unwrap_or_default();
+
+    for follower in followers {
+        let timeline = store.timelines.entry(follower).or_default();
+        timeline.push(post_id.to_string());
+        trim_timeline(timeline);
+    }
+}
+
+// Copy a newly followed account's recent posts into the follower's timeline
+pub fn backfill_timeline(store: &mut Storage, follower: Principal, followed: Principal) {
+    if followers_count(store, &followed) > fanout_threshold(store) {
+        return;
+    }
+    let mut recent: Vec<&Post> = store.user_posts
+        .get(&followed)
+        .into_iter()
+        .flatten()
+        .filter_map(|post_id| store.posts.get(post_id))
+        .filter(|post| shown_to_followers(post))
+        .collect();
+    recent.sort_by_key(|post| std::cmp::Reverse(post.created_at));
+
+    let mut timeline = store.timelines.remove(&follower).unwrap_or_default();
+    for post in recent.into_iter().take(TIMELINE_BACKFILL_POSTS) {
+        if !timeline.contains(&post.id) {
+            timeline.push(post.id.clone());
+        }
+    }
+    timeline.sort_by_key(|post_id| store.posts.get(post_id).map_or(0, |post| post.created_at));
+    trim_timeline(&mut timeline);
+    store.timelines.insert(follower, timeline);
+}
+
+pub(crate) fn feed_item(post: &Post) -> SquareResult<PostResponse> {
+    Ok(withhold_gated_content(PostResponse {
+        id: post.id.clone(),
+        author: post.author,
+        content: post.content.clone(),
+        media_urls: post.media_urls.clone(),
+        hashtags: post.hashtags.clone(),
+        token_mentions: post.token_mentions.clone(),
+        tags: post.tags.clone(),
+        created_at: post.created_at,
+        updated_at: post.updated_at,
+        status: post.status.clone(),
+        visibility: post.visibility.clone(),
+        likes_count: 0, // TODO: Get from likes storage
+        comments_count: 0, // TODO: Get from comments storage
+        author_info: get_user_social_info(post.author.to_string(), None)?,
+        news_reference: post.news_reference.clone(),
+        posted_by: post.posted_by,
+        title: post.title.clone(),
+        is_premium: post.is_premium,
+        license: post.license,
+        token_gate: post.token_gate.clone(),
+        liked_by_followed: Vec::new(),
+        summary: None,
+        format: post.format,
+        source: None,
+        reactions: Vec::new(),
+        emojis: Vec::new(),
+        content_hash: post.content_hash.clone(),
+        cross_post_of: post.cross_post_of.clone(),
+        table_of_contents: Vec::new(),
+        cover_image: post.cover_image.clone(),
+        thumbnail: post.thumbnail.clone(),
+    }))
+}
+
+// Posts by accounts the caller follows, newest first: the caller's timeline merged with
+// posts pulled from followed accounts above the fan-out threshold
+pub fn get_following_feed(pagination: PaginationParams, caller: Principal) -> SquareResult<FeedResponse> {
+    const FUNCTION: &str = "get_following_feed";
+
+    let offset = pagination.offset.unwrap_or(0);
+    let limit = pagination.limit.unwrap_or(10);
+    if offset.saturating_add(limit) > MAX_FOLLOWING_FEED_WINDOW {
+        return log_and_return(field_validation_error(
+            "pagination",
+            &format!("offset + limit must be at most {}", MAX_FOLLOWING_FEED_WINDOW),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    let deactivated = deactivated_users();
+
+    let (mut posts, total) = STORAGE.with(|storage| -> SquareResult<(Vec<PostResponse>, usize)> {
+        let store = storage.borrow();
+        let Some(followed) = store.user_profiles.as_ref().and_then(|profiles| profiles.get(&caller)).map(|profile| &profile.followed_users) else {
+            return Ok((Vec::new(), 0));
+        };
+
+        let mut seen = HashSet::new();
+        // Pushed on write; unfollowed authors are skipped here rather than purged
+        let mut posts: Vec<&Post> = store.timelines
+            .get(&caller)
+            .into_iter()
+            .flatten()
+            .filter_map(|post_id| store.posts.get(post_id))
+            .filter(|post| followed.contains(&post.author) && seen.insert(post.id.as_str()))
+            .collect();
+
+        // Pulled on read; also covers accounts that crossed the threshold after their posts were pushed
+        let threshold = fanout_threshold(&store);
+        let large: HashSet<&Principal> = followed.iter().filter(|user| followers_count(&store, user) > threshold).collect();
+        for user in large {
+            posts.extend(store.user_posts
+                .get(user)
+                .into_iter()
+                .flatten()
+                .filter_map(|post_id| store.posts.get(post_id))
+                .filter(|post| seen.insert(post.id.as_str())));
+        }
+
+        posts.retain(|post| post.status == ContentStatus::Active && shown_to_followers(post) && !deactivated.contains(&post.author));
+        dedup_cross_posts(&mut posts);
+        posts.sort_by_key(|post| std::cmp::Reverse(post.created_at));
+        let total = posts.len();
+        let page = posts
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(feed_item)
+            .collect::<SquareResult<Vec<_>>>()?;
+        Ok((page, total))
+    })?;
+
+    attach_liked_by_followed(&mut posts);
+    attach_article_summaries(&mut posts);
+    attach_rendered_posts(&mut posts);
+    attach_post_emojis(&mut posts);
+
+    let mut response = FeedResponse {
+        posts,
+        comments: vec![],
+        has_more: offset + limit < total,
+        next_offset: offset + limit,
+        total: total as u64,
+        degraded: false,
+        truncated: false,
+    };
+    truncate_feed(&mut response, offset);
+    Ok(response)
+}
+
+pub fn get_timeline_settings() -> SquareResult<TimelineSettings> {
+    Ok(STORAGE.with(|storage| storage.borrow().timeline_settings.clone().unwrap_or_default()))
+}
+
+// Changing the threshold affects new posts and follows; existing timelines are kept
+pub fn update_timeline_settings(settings: TimelineSettings) -> SquareResult<TimelineSettings> {
+    const FUNCTION: &str = "update_timeline_settings";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_timeline_settings",
+            "Only admins can update timeline settings",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    STORAGE.with(|storage| storage.borrow_mut().timeline_settings = Some(settings.clone()));
+    Ok(settings)
+}
@@ -0,0 +1,312 @@
+// Every change to whether a post or comment exists or is Active goes through here, so that what is
+// derived from it moves in step: user stats, trending topics, trending content, likes and the
+// user_posts, user_comments and post_comments indexes. Only Active content counts.
+//
+// Comments follow what they reply to. When a post or comment leaves Active, its Active replies are
+// hidden with it and remembered, and they come back when it is restored. Deleting content deletes
+// its replies.
+//
+// Cross-posts follow their original post: they take its status and are deleted with it. They do not
+// count on their own, since their likes and comments go to the original.
+//
+// Content on legal hold is frozen: its status does not change and it is not deleted, nor is anything
+// it replies to. The legal hold service changes its status around placing and releasing the hold.
+use candid::Principal;
+
+use crate::models::content::{ContentSchedule, ContentStatus, ContentType, ParentType};
+use crate::models::event::InteractionEventKind;
+use crate::models::storage::Storage;
+use crate::services::content::schedule::store_schedule;
+use crate::services::content::subscriptions::remove_thread_subscriptions;
+use crate::services::discovery::similarity::forget_liked_content;
+use crate::services::events::log_event;
+use crate::services::interaction::{has_liked, like_count};
+use crate::services::translation::remove_translations;
+use crate::services::user::quota::{comment_size, post_size, release_storage};
+use crate::storage::{Comment, Post};
+
+fn counted(status: &ContentStatus) -> bool {
+    *status == ContentStatus::Active
+}
+
+fn counted_post(post: &Post) -> bool {
+    counted(&post.status) && post.cross_post_of.is_none()
+}
+
+fn index(entries: &mut Vec<String>, id: &str) {
+    if !entries.iter().any(|entry| entry == id) {
+        entries.push(id.to_string());
+    }
+}
+
+// List new content under its author; called as it is stored
+pub(crate) fn index_post(store: &mut Storage, author: Principal, post_id: &str) {
+    index(store.user_posts.entry(author).or_default(), post_id);
+}
+
+pub(crate) fn index_comment(store: &mut Storage, comment: &Comment) {
+    index(store.user_comments.entry(comment.author).or_default(), &comment.id);
+    if comment.parent_type == ParentType::Post {
+        index(store.post_comments.entry(comment.parent_id.clone()).or_default(), &comment.id);
+    }
+}
+
+// Likes that count as received by the author, leaving out their own
+fn likes_received(store: &Storage, content_id: &str, author: &Principal) -> u64 {
+    like_count(store, content_id) - has_liked(store, content_id, author) as u64
+}
+
+fn post_left(store: &mut Storage, actor: Principal, post_id: &str, author: Principal, hashtags: Vec<String>) {
+    let likes = likes_received(store, post_id, &author);
+    store.trending_content.retain(|id| id != post_id);
+    log_event(store, actor, InteractionEventKind::PostDeleted { post_id: post_id.to_string(), author, hashtags, likes });
+}
+
+fn comment_left(store: &mut Storage, actor: Principal, comment_id: &str, author: Principal) {
+    let likes = likes_received(store, comment_id, &author);
+    log_event(store, actor, InteractionEventKind::CommentDeleted { comment_id: comment_id.to_string(), author, likes });
+}
+
+// Direct replies: the comments on a post, or a comment's child comments
+fn replies(store: &Storage, content_type: &ContentType, content_id: &str) -> Vec<String> {
+    match content_type {
+        ContentType::Post => store.post_comments.get(content_id).cloned().unwrap_or_default(),
+        ContentType::Comment => store.comments.get(content_id).map(|comment| comment.child_comments.clone()).unwrap_or_default(),
+    }
+}
+
+pub(crate) fn on_legal_hold(store: &Storage, content_id: &str) -> bool {
+    store.legal_holds.contains_key(content_id)
+}
+
+// Whether the content or any reply below it is on legal hold, so deleting it would lose held content
+pub(crate) fn thread_on_legal_hold(store: &Storage, content_type: &ContentType, content_id: &str) -> bool {
+    if store.legal_holds.is_empty() {
+        return false;
+    }
+    let mut pending = vec![content_id.to_string()];
+    let mut visited = std::collections::HashSet::new();
+    pending.extend(replies(store, content_type, content_id));
+    while let Some(id) = pending.pop() {
+        if !visited.insert(id.clone()) {
+            continue;
+        }
+        if on_legal_hold(store, &id) {
+            return true;
+        }
+        pending.extend(replies(store, &ContentType::Comment, &id));
+    }
+    false
+}
+
+// How a status change moved content in or out of what counts
+#[derive(PartialEq)]
+enum Counting {
+    Left,
+    Returned,
+    Unchanged,
+}
+
+// Hide the Active replies of content that just left Active, then theirs, and so on down the thread
+fn hide_replies(store: &mut Storage, actor: Principal, content_type: &ContentType, content_id: &str) {
+    let mut pending = vec![(content_type.clone(), content_id.to_string())];
+    while let Some((content_type, content_id)) = pending.pop() {
+        let mut hidden = Vec::new();
+        for reply in replies(store, &content_type, &content_id) {
+            if !store.comments.get(&reply).is_some_and(|comment| counted(&comment.status)) {
+                continue;
+            }
+            if change_comment_status(store, actor, &reply, ContentStatus::Hidden).is_some_and(|(_, change)| change == Counting::Left) {
+                pending.push((ContentType::Comment, reply.clone()));
+                hidden.push(reply);
+            }
+        }
+        if !hidden.is_empty() {
+            store.cascade_hidden_comments.insert(content_id, hidden);
+        }
+    }
+}
+
+// Bring back the replies hidden along with content now Active again, unless they were moderated
+// since, and the replies hidden along with those
+fn restore_replies(store: &mut Storage, actor: Principal, content_id: &str) {
+    let mut pending = vec![content_id.to_string()];
+    while let Some(content_id) = pending.pop() {
+        for reply in store.cascade_hidden_comments.remove(&content_id).unwrap_or_default() {
+            if !store.comments.get(&reply).is_some_and(|comment| comment.status == ContentStatus::Hidden) {
+                continue;
+            }
+            if change_comment_status(store, actor, &reply, ContentStatus::Active).is_some_and(|(_, change)| change == Counting::Returned) {
+                pending.push(reply);
+            }
+        }
+    }
+}
+
+// Change a post's status; returns its author, or None when there is no such post
+pub(crate) fn set_post_status(store: &mut Storage, actor: Principal, post_id: &str, status: ContentStatus) -> Option<Principal> {
+    if on_legal_hold(store, post_id) {
+        return store.posts.get(post_id).map(|post| post.author);
+    }
+    let (was_counted, is_counted, author, hashtags) = {
+        let mut post = store.posts.get_mut(post_id)?;
+        let was_counted = counted_post(&post);
+        post.status = status.clone();
+        (was_counted, counted_post(&post), post.author, post.hashtags.clone())
+    };
+    for cross_post in store.cross_posts.get(post_id).cloned().unwrap_or_default() {
+        set_post_status(store, actor, &cross_post, status.clone());
+    }
+
+    if was_counted && !is_counted {
+        post_left(store, actor, post_id, author, hashtags);
+        hide_replies(store, actor, &ContentType::Post, post_id);
+    } else if !was_counted && is_counted {
+        let likes = likes_received(store, post_id, &author);
+        log_event(store, actor, InteractionEventKind::PostRestored { post_id: post_id.to_string(), author, hashtags, likes });
+        restore_replies(store, actor, post_id);
+    }
+    Some(author)
+}
+
+// Change one comment's status, leaving its replies as they are
+fn change_comment_status(store: &mut Storage, actor: Principal, comment_id: &str, status: ContentStatus) -> Option<(Principal, Counting)> {
+    if on_legal_hold(store, comment_id) {
+        return store.comments.get(comment_id).map(|comment| (comment.author, Counting::Unchanged));
+    }
+    let (was_counted, author) = {
+        let mut comment = store.comments.get_mut(comment_id)?;
+        let was_counted = counted(&comment.status);
+        comment.status = status.clone();
+        (was_counted, comment.author)
+    };
+    let is_counted = counted(&status);
+
+    if was_counted && !is_counted {
+        comment_left(store, actor, comment_id, author);
+        Some((author, Counting::Left))
+    } else if !was_counted && is_counted {
+        let likes = likes_received(store, comment_id, &author);
+        log_event(store, actor, InteractionEventKind::CommentRestored { comment_id: comment_id.to_string(), author, likes });
+        Some((author, Counting::Returned))
+    } else {
+        Some((author, Counting::Unchanged))
+    }
+}
+
+pub(crate) fn set_comment_status(store: &mut Storage, actor: Principal, comment_id: &str, status: ContentStatus) -> Option<Principal> {
+    let (author, change) = change_comment_status(store, actor, comment_id, status)?;
+    match change {
+        Counting::Left => hide_replies(store, actor, &ContentType::Comment, comment_id),
+        Counting::Returned => restore_replies(store, actor, comment_id),
+        Counting::Unchanged => {}
+    }
+    Some(author)
+}
+
+pub(crate) fn set_content_status(
+    store: &mut Storage,
+    actor: Principal,
+    content_type: &ContentType,
+    content_id: &str,
+    status: ContentStatus,
+) -> Option<Principal> {
+    match content_type {
+        ContentType::Post => set_post_status(store, actor, content_id, status),
+        ContentType::Comment => set_comment_status(store, actor, content_id, status),
+    }
+}
+
+// Delete a post, its comments, its cross-posts and everything kept alongside them; returns the
+// deleted post. Nothing is deleted when the thread or one of its cross-posts holds content on legal hold.
+pub(crate) fn remove_post(store: &mut Storage, actor: Principal, post_id: &str) -> Option<Post> {
+    let cross_posts = store.cross_posts.get(post_id).cloned().unwrap_or_default();
+    let held = thread_on_legal_hold(store, &ContentType::Post, post_id)
+        || cross_posts.iter().any(|cross_post| thread_on_legal_hold(store, &ContentType::Post, cross_post));
+    if !store.posts.contains_key(post_id) || held {
+        return None;
+    }
+    // Each cross-post takes itself off the original's list as it is removed
+    for cross_post in cross_posts {
+        remove_post(store, actor, &cross_post);
+    }
+    // Ids left over point at posts that no longer exist
+    if let Some(remaining) = store.cross_posts.get_mut(post_id) {
+        remaining.retain(|id| store.posts.contains_key(id));
+        if remaining.is_empty() {
+            store.cross_posts.remove(post_id);
+        }
+    }
+    for reply in replies(store, &ContentType::Post, post_id) {
+        remove_comment(store, actor, &reply);
+    }
+    store.cascade_hidden_comments.remove(post_id);
+    store.post_comments.remove(post_id);
+    let post = store.posts.remove(post_id)?;
+    if counted_post(&post) {
+        post_left(store, actor, post_id, post.author, post.hashtags.clone());
+    }
+    if let Some(original) = &post.cross_post_of {
+        let cross_posts = store.cross_posts.entry(original.clone()).or_default();
+        cross_posts.retain(|id| id != post_id);
+        if cross_posts.is_empty() {
+            store.cross_posts.remove(original);
+        }
+    }
+    release_storage(&mut store.storage_usage, post.author, post_size(&post));
+    remove_translations(store, post_id);
+    store.article_summaries.remove(post_id);
+    store.article_outlines.remove(post_id);
+    store.rendered_content.remove(post_id);
+    store_schedule(store, post_id, ContentSchedule::default());
+    store.reactions.remove(post_id);
+    if let Some(likers) = store.like_sets.remove(post_id) {
+        forget_liked_content(post_id, &likers);
+    }
+    store.post_impression_counts.remove(post_id);
+    remove_thread_subscriptions(store, post_id);
+    if let Some(posts) = store.user_posts.get_mut(&post.author) {
+        posts.retain(|id| id != post_id);
+    }
+    Some(post)
+}
+
+// Delete a comment and every reply below it; returns the deleted comment. Nothing is deleted when
+// the thread below it holds content on legal hold.
+pub(crate) fn remove_comment(store: &mut Storage, actor: Principal, comment_id: &str) -> Option<Comment> {
+    if thread_on_legal_hold(store, &ContentType::Comment, comment_id) {
+        return None;
+    }
+    let comment = delete_comment(store, actor, comment_id)?;
+    if let Some(comments) = store.post_comments.get_mut(&comment.parent_id).filter(|_| comment.parent_type == ParentType::Post) {
+        comments.retain(|id| id != comment_id);
+    }
+    let mut pending = comment.child_comments.clone();
+    while let Some(reply) = pending.pop() {
+        if let Some(reply) = delete_comment(store, actor, &reply) {
+            pending.extend(reply.child_comments);
+        }
+    }
+    Some(comment)
+}
+
+// Delete one comment and what is kept alongside it. Each comment leaves storage before its replies
+// are visited, so a corrupted reply chain cannot lead back to it.
+fn delete_comment(store: &mut Storage, actor: Principal, comment_id: &str) -> Option<Comment> {
+    let comment = store.comments.remove(comment_id)?;
+    store.cascade_hidden_comments.remove(comment_id);
+    if counted(&comment.status) {
+        comment_left(store, actor, comment_id, comment.author);
+    }
+    release_storage(&mut store.storage_usage, comment.author, comment_size(&comment));
+    remove_translations(store, comment_id);
+    store.rendered_content.remove(comment_id);
+    store.reactions.remove(comment_id);
+    if let Some(likers) = store.like_sets.remove(comment_id) {
+        forget_liked_content(comment_id, &likers);
+    }
+    if let Some(comments) = store.user_comments.get_mut(&comment.author) {
+        comments.retain(|id| id != comment_id);
+    }
+    Some(comment)
+}
@@ -1,5 +1,7 @@
 use candid::Principal;
-use ic_cdk::api::{canister_balance, time};
+use ic_cdk::api::management_canister::main::{canister_status, CanisterIdRecord};
+use ic_cdk::api::{canister_balance, id, time};
+use num_traits::ToPrimitive;
 use std::cell::RefCell;
 use std::collections::VecDeque;
 
@@ -9,6 +11,7 @@ use crate::models::error::SquareResult;
 use crate::auth;
 use crate::utils::error_handler::*;
 use crate::storage::STORAGE;
+use crate::utils::time_utils::{day_start, MILLIS_PER_DAY, MILLIS_PER_HOUR};
 
 // Constants
 const WARNING_THRESHOLD: u64 = 100_000_000_000;  // 100 billion cycles (0.1 ICP)
@@ -18,6 +21,7 @@ const MAX_HISTORY_DAYS: usize = 30;  // Keep 30 days of history
 const ESTIMATED_DAILY_CONSUMPTION: u64 = 5_000_000_000;  // 5 billion cycles per day by default
 const SECONDS_IN_DAY: u64 = 24 * 60 * 60; // 24 hours in seconds
 const CYCLES_HISTORY_MAX_DAYS: usize = 30; // Keep 30 days of history
+const BURN_SMOOTHING: f64 = 0.3;  // Weight of each newer day in the burn forecast
 
 // Thread-local storage for cycles consumption history
 thread_local! {
@@ -61,16 +65,24 @@ pub fn record_cycles_consumption() {
             
             CYCLES_HISTORY.with(|history| {
                 let mut history_mut = history.borrow_mut();
+                let date = day_start(current_time);
                 
-                // Add new consumption record
-                let daily_consumption = DailyConsumption {
-                    date: current_time,
-                    consumption,
-                    operations: 1, // Increment for each recording period
-                };
+                // Fold recordings from the same UTC day into one record
+                match history_mut.back_mut() {
+                    Some(today) if today.date == date => {
+                        today.consumption += consumption;
+                        today.operations += 1;
+                    }
+                    _ => {
+                        history_mut.push_back(DailyConsumption {
+                            date,
+                            consumption,
+                            operations: 1, // Increment for each recording period
+                        });
+                    }
+                }
                 
-                // Add to history and maintain max size
-                history_mut.push_back(daily_consumption);
+                // Maintain max size
                 if history_mut.len() > MAX_HISTORY_DAYS {
                     history_mut.pop_front();
                 }
@@ -83,6 +95,8 @@ pub fn record_cycles_consumption() {
     
     // Check if balance is below threshold and notify if needed
     check_balance_threshold();
+    check_runway_forecast();
+    check_freeze_guard();
 }
 
 // Get current cycles balance
@@ -94,13 +108,10 @@ pub fn get_cycles_balance() -> SquareResult<CyclesBalanceResponse> {
     let current_balance = canister_balance();
     let balance_in_trillion = current_balance as f64 / TRILLION;
     
-    // Calculate estimated days remaining based on average consumption
-    let average_daily_consumption = get_average_daily_consumption();
-    let estimated_days = if average_daily_consumption > 0 {
-        current_balance / average_daily_consumption
-    } else {
-        current_balance / ESTIMATED_DAILY_CONSUMPTION
-    };
+    // Estimate days remaining from the burn forecast, or the default consumption without history
+    let estimated_days = forecast_runway(current_balance, time() / 1_000_000)
+        .runway_days
+        .unwrap_or(current_balance / ESTIMATED_DAILY_CONSUMPTION);
     
     // Check if balance is below warning threshold
     let threshold_warning = CYCLES_THRESHOLD_CONFIG.with(|config| {
@@ -161,6 +172,74 @@ pub fn get_cycles_consumption_history() -> SquareResult<CyclesConsumptionRespons
     })
 }
 
+// Get the burn forecast and days of runway
+pub fn get_cycles_runway_forecast() -> SquareResult<CyclesRunwayForecast> {
+    Ok(forecast_runway(canister_balance(), time() / 1_000_000))
+}
+
+// Get the freezing threshold guard and the settings behind it
+pub fn get_freeze_guard_status() -> SquareResult<FreezeGuardStatus> {
+    Ok(freeze_guard_status(canister_balance(), time() / 1_000_000))
+}
+
+// Re-read the freezing threshold now instead of waiting for the next full heartbeat run
+pub async fn refresh_freeze_guard() -> SquareResult<FreezeGuardStatus> {
+    const MODULE: &str = "services::cycles";
+    const FUNCTION: &str = "refresh_freeze_guard";
+    
+    if auth::is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "refresh_freeze_guard",
+            "Only admins can refresh the freeze guard",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    refresh_freezing_threshold().await;
+    check_freeze_guard();
+    get_freeze_guard_status()
+}
+
+// Read the freezing threshold and idle burn from the management canister. Only controllers may
+// call canister_status, so this fails unless the canister is one of its own controllers; the
+// guard then keeps the last reading it had.
+pub async fn refresh_freezing_threshold() {
+    let result = canister_status(CanisterIdRecord { canister_id: id() }).await;
+    STORAGE.with(|storage| {
+        let reading = &mut storage.borrow_mut().freeze_guard;
+        match result {
+            Ok((status,)) => {
+                reading.freezing_threshold_seconds = status.settings.freezing_threshold.0.to_u64();
+                reading.idle_cycles_burned_per_day = status.idle_cycles_burned_per_day.0.to_u64();
+                reading.checked_at = Some(time() / 1_000_000);
+                reading.last_error = None;
+            }
+            Err((code, message)) => {
+                ic_cdk::println!("Failed to read the freezing threshold: {:?} {}", code, message);
+                reading.last_error = Some(format!("canister_status failed with code {:?}: {}", code, message));
+            }
+        }
+    });
+}
+
+// Whether expensive background jobs and media uploads are paused to stay clear of freezing
+pub fn freeze_guard_active() -> bool {
+    STORAGE.with(|storage| storage.borrow().freeze_guard.level == FreezeGuardLevel::Guarded)
+}
+
+// Reject an upload of inline media while the freeze guard is active
+pub fn ensure_media_uploads_allowed(has_media: bool, operation: &str, module: &str, function: &str) -> SquareResult<()> {
+    if has_media && freeze_guard_active() {
+        return log_and_return(service_unavailable_error(
+            operation,
+            "Media uploads are paused while the cycles balance is close to the freezing threshold",
+            module,
+            function
+        ));
+    }
+    Ok(())
+}
+
 // Update cycles threshold configuration
 pub fn update_cycles_threshold(request: UpdateCyclesThresholdRequest, _caller: Principal) -> SquareResult<()> {
     const MODULE: &str = "services::cycles";
@@ -363,6 +442,18 @@ fn check_balance_threshold() {
 
 // Create a warning notification
 fn create_warning_notification(balance: u64, threshold: u64, severity: CyclesWarningSeverity, message: String) {
+    push_notification(balance, threshold, severity, message, None, CyclesAlertKind::Balance);
+}
+
+// Balance, runway and freeze alerts, and each runway tier, are deduplicated separately
+fn push_notification(
+    balance: u64,
+    threshold: u64,
+    severity: CyclesWarningSeverity,
+    message: String,
+    runway_days: Option<u64>,
+    kind: CyclesAlertKind,
+) {
     let notification = CyclesWarningNotification {
         timestamp: time() / 1_000_000,
         balance,
@@ -370,6 +461,8 @@ fn create_warning_notification(balance: u64, threshold: u64, severity: CyclesWar
         severity: severity.clone(),
         message,
         is_acknowledged: false,
+        runway_days,
+        kind,
     };
     
     CYCLES_NOTIFICATIONS.with(|notifications| {
@@ -377,7 +470,7 @@ fn create_warning_notification(balance: u64, threshold: u64, severity: CyclesWar
         
         // Check if we already have a similar unacknowledged notification
         let has_similar = notifications_mut.iter().any(|n| {
-            !n.is_acknowledged && n.severity == severity
+            !n.is_acknowledged && n.kind == kind && n.severity == severity && n.runway_days == runway_days
         });
         
         // Only add if we don't have a similar unacknowledged notification
@@ -621,22 +714,143 @@ fn emergency_cycles_conservation() {
     send_bark_message(&message);
 }
 
-// Get average daily consumption
-fn get_average_daily_consumption() -> u64 {
-    let mut total_consumption = 0u64;
-    let mut days = 0usize;
+// The reserve is what the canister must hold to stay above its freezing threshold: the idle burn
+// over the threshold period. The guard compares it with the balance projected at the forecast burn.
+fn freeze_guard_status(balance: u64, now: u64) -> FreezeGuardStatus {
+    let daily_burn = forecast_runway(balance, now).daily_burn;
+    let projected = |days: u64| balance.saturating_sub(daily_burn.saturating_mul(days));
+    STORAGE.with(|storage| {
+        let reading = &storage.borrow().freeze_guard;
+        let freeze_reserve = reading.freezing_threshold_seconds.zip(reading.idle_cycles_burned_per_day).map(|(seconds, idle_burn)| {
+            (idle_burn as u128 * seconds as u128 / SECONDS_IN_DAY as u128).min(u64::MAX as u128) as u64
+        });
+        let level = match freeze_reserve {
+            Some(reserve) if projected(FREEZE_GUARD_DAYS) <= reserve => FreezeGuardLevel::Guarded,
+            Some(reserve) if projected(FREEZE_WARNING_DAYS) <= reserve => FreezeGuardLevel::Warning,
+            _ => FreezeGuardLevel::Normal,
+        };
+        FreezeGuardStatus {
+            balance,
+            freezing_threshold_seconds: reading.freezing_threshold_seconds,
+            idle_cycles_burned_per_day: reading.idle_cycles_burned_per_day,
+            freeze_reserve,
+            projected_balance: projected(FREEZE_GUARD_DAYS),
+            level,
+            checked_at: reading.checked_at,
+            last_error: reading.last_error.clone(),
+        }
+    })
+}
+
+// Move the guard to the level of the current projection, alerting when it tightens
+fn check_freeze_guard() {
+    let status = freeze_guard_status(canister_balance(), time() / 1_000_000);
+    let previous = STORAGE.with(|storage| std::mem::replace(&mut storage.borrow_mut().freeze_guard.level, status.level));
+    if previous == status.level {
+        return;
+    }
+    ic_cdk::println!("Freeze guard moved from {:?} to {:?}", previous, status.level);
     
-    CYCLES_HISTORY.with(|history| {
+    let notifications_enabled = NOTIFICATION_SETTINGS.with(|settings| {
+        *settings.borrow()
+    });
+    let Some(reserve) = status.freeze_reserve else {
+        return;
+    };
+    // Only a tightening guard alerts; easing off from Guarded to Warning does not
+    let tightened = status.level != FreezeGuardLevel::Normal && previous != FreezeGuardLevel::Guarded;
+    if !notifications_enabled || !tightened {
+        return;
+    }
+    
+    let (severity, message) = if status.level == FreezeGuardLevel::Guarded {
+        (CyclesWarningSeverity::Critical, format!(
+            "CRITICAL: Cycles balance ({}) will reach the freezing threshold reserve ({}) within {} days. Expensive jobs and media uploads are paused until it is topped up.",
+            status.balance, reserve, FREEZE_GUARD_DAYS
+        ))
+    } else {
+        (CyclesWarningSeverity::Warning, format!(
+            "WARNING: Cycles balance ({}) will reach the freezing threshold reserve ({}) within {} days.",
+            status.balance, reserve, FREEZE_WARNING_DAYS
+        ))
+    };
+    push_notification(status.balance, reserve, severity, message.clone(), None, CyclesAlertKind::FreezeThreshold);
+    if status.level == FreezeGuardLevel::Guarded {
+        send_bark_message(&message);
+    }
+}
+
+// Forecast the daily burn and days of runway from the consumption history. Completed days are
+// smoothed exponentially, so a recent change in load moves the forecast within a few days; the
+// current day only counts, extrapolated to a full day, while there is nothing else to go on.
+fn forecast_runway(balance: u64, now: u64) -> CyclesRunwayForecast {
+    let today = day_start(now);
+    let (daily_burn, history_days) = CYCLES_HISTORY.with(|history| {
         let history_ref = history.borrow();
-        for day in history_ref.iter() {
-            total_consumption += day.consumption;
+        let mut smoothed: Option<f64> = None;
+        let mut days = 0u64;
+        for day in history_ref.iter().filter(|day| day.date < today) {
+            let consumption = day.consumption as f64;
+            smoothed = Some(match smoothed {
+                Some(previous) => BURN_SMOOTHING * consumption + (1.0 - BURN_SMOOTHING) * previous,
+                None => consumption,
+            });
             days += 1;
         }
+        match (smoothed, history_ref.back()) {
+            (Some(burn), _) => (burn as u64, days),
+            (None, Some(current)) if current.date == today => {
+                let elapsed = (now - today).max(MILLIS_PER_HOUR);
+                ((current.consumption as u128 * MILLIS_PER_DAY as u128 / elapsed as u128) as u64, 1)
+            }
+            _ => (0, 0),
+        }
+    });
+
+    let runway_days = (daily_burn > 0).then(|| balance / daily_burn);
+    let alert_tier = runway_days.and_then(|runway| {
+        RUNWAY_ALERT_DAYS.iter().rev().find(|tier| runway < **tier).copied()
+    });
+    CyclesRunwayForecast {
+        balance,
+        daily_burn,
+        runway_days,
+        history_days,
+        alert_tier,
+    }
+}
+
+// Raise a runway alert each time the forecast drops into a tighter tier. This is separate from
+// the balance thresholds: a busy canister can have a comfortable balance and little runway.
+fn check_runway_forecast() {
+    let notifications_enabled = NOTIFICATION_SETTINGS.with(|settings| {
+        *settings.borrow()
     });
     
-    if days > 0 {
-        total_consumption / days as u64
-    } else {
-        ESTIMATED_DAILY_CONSUMPTION
+    if !notifications_enabled {
+        return;
+    }
+    
+    let forecast = forecast_runway(canister_balance(), time() / 1_000_000);
+    let previous_tier = STORAGE.with(|storage| std::mem::replace(&mut storage.borrow_mut().freeze_guard.last_runway_tier, forecast.alert_tier));
+    let (Some(tier), Some(runway)) = (forecast.alert_tier, forecast.runway_days) else {
+        return;
+    };
+    if previous_tier.is_some_and(|previous| previous <= tier) {
+        return;
+    }
+    
+    let critical = tier == RUNWAY_ALERT_DAYS[RUNWAY_ALERT_DAYS.len() - 1];
+    let message = format!(
+        "{}: About {} days of cycles left at the current burn of {} per day. Top up within {} days.",
+        if critical { "CRITICAL" } else { "WARNING" },
+        runway,
+        forecast.daily_burn,
+        tier
+    );
+    let severity = if critical { CyclesWarningSeverity::Critical } else { CyclesWarningSeverity::Warning };
+    push_notification(forecast.balance, forecast.daily_burn.saturating_mul(tier), severity, message.clone(), Some(tier), CyclesAlertKind::Runway);
+    if critical {
+        send_bark_message(&message);
     }
 }
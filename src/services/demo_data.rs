@@ -0,0 +1,251 @@
+// Synthetic users, follows, posts, comments and likes for local and staging deployments, so
+// frontend and performance work have a realistic content graph to run against. Only built with the
+// `demo-data` feature.
+use candid::Principal;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+
+use crate::auth::is_admin;
+use crate::models::content::{Comment, ContentFormat, ContentStatus, ContentType, ContentVisibility, ParentType, Post};
+use crate::models::demo_data::*;
+use crate::models::error::SquareResult;
+use crate::models::event::InteractionEventKind;
+use crate::models::user::{FollowUserRequest, RegisterUserRequest};
+use crate::services::content::anchoring::stamp_content_hash;
+use crate::services::content::comments::excluded_from_engagement;
+use crate::services::content::transitions::{index_comment, index_post};
+use crate::services::events::log_event;
+use crate::services::query_cache::{invalidate, ENGAGEMENT_QUERIES, POST_QUERIES};
+use crate::services::user::quota::{charge_storage, comment_size, post_size};
+use crate::services::user::{follow_user, register_user};
+use crate::storage::STORAGE;
+use crate::utils::content_utils::normalize_hashtags;
+use crate::utils::error_handler::*;
+use crate::utils::logger;
+use crate::utils::time_utils::{now_millis, MILLIS_PER_DAY};
+
+const MODULE: &str = "services::demo_data";
+
+const FIRST_NAMES: [&str; 12] = ["Maya", "Leo", "Aiko", "Omar", "Sofia", "Kai", "Nina", "Ravi", "Elena", "Tom", "Zara", "Felix"];
+const TOPICS: [&str; 8] = ["bitcoin", "defi", "nft", "icp", "gaming", "ai", "layer2", "dao"];
+const POST_TEMPLATES: [&str; 6] = [
+    "Thoughts on where #{topic} goes this quarter: adoption looks stronger than the price suggests.",
+    "New to #{topic}? Start with the docs, then break something on a testnet.",
+    "Weekly #{topic} roundup: three launches, two audits and one very long governance thread.",
+    "Unpopular opinion: #{topic} needs better tooling more than it needs more tokens.",
+    "Just shipped a small side project around #{topic}. Feedback welcome!",
+    "What is everyone reading about #{topic} this week?",
+];
+const COMMENT_TEMPLATES: [&str; 6] = [
+    "Great point, thanks for sharing.",
+    "I see it differently, but this is well argued.",
+    "Do you have a source for this?",
+    "This aged well.",
+    "Bookmarking this for later.",
+    "Could you expand on the second part?",
+];
+
+// Skewed towards the front of the list, so a few accounts and posts draw most of the activity
+fn popular_index(rng: &mut SmallRng, len: usize) -> usize {
+    ((rng.gen_range(0.0..1.0f64).powi(2) * len as f64) as usize).min(len - 1)
+}
+
+fn demo_principal(seed: u64, index: u32) -> Principal {
+    let mut bytes = b"demo".to_vec();
+    bytes.extend_from_slice(&seed.to_be_bytes());
+    bytes.extend_from_slice(&index.to_be_bytes());
+    Principal::from_slice(&bytes)
+}
+
+// Users are created first, through registration. Posts and comments go to new users, or to existing
+// users when none are created; comments go to new posts, or to existing active posts.
+pub fn seed_demo_data(users: u32, posts: u32, comments: u32) -> SquareResult<DemoDataSummary> {
+    const FUNCTION: &str = "seed_demo_data";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "seed_demo_data",
+            "Only admins can seed demo data",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    for (field, value, max) in [("users", users, MAX_DEMO_USERS), ("posts", posts, MAX_DEMO_POSTS), ("comments", comments, MAX_DEMO_COMMENTS)] {
+        if value > max {
+            return log_and_return(field_validation_error(field, &format!("must be at most {}", max), MODULE, FUNCTION));
+        }
+    }
+
+    // Seeding runs in this one message without awaits, so trapping on a failure part way through
+    // rolls back everything written before it instead of leaving a partial seed
+    let summary = write_demo_data(users, posts, comments)
+        .unwrap_or_else(|error| ic_cdk::trap(&format!("Seeding demo data failed and was rolled back: {}", error)));
+
+    invalidate(POST_QUERIES);
+    invalidate(ENGAGEMENT_QUERIES);
+    logger::log(&format!(
+        "[{}] Seeded {} users, {} follows, {} posts, {} comments and {} likes",
+        MODULE, summary.users_created, summary.follows_created, summary.posts_created, summary.comments_created, summary.likes_created
+    ));
+    Ok(summary)
+}
+
+fn write_demo_data(users: u32, posts: u32, comments: u32) -> SquareResult<DemoDataSummary> {
+    const FUNCTION: &str = "write_demo_data";
+
+    let now = now_millis();
+    let mut rng = SmallRng::seed_from_u64(now);
+    let mut summary = DemoDataSummary::default();
+
+    let first_number = STORAGE.with(|storage| storage.borrow().users.len());
+    let mut authors = Vec::new();
+    for index in 0..users {
+        let principal = demo_principal(now, index);
+        let number = first_number + index as usize;
+        register_user(RegisterUserRequest {
+            username: format!("{} {}", FIRST_NAMES[number % FIRST_NAMES.len()], number),
+            handle: format!("demo_{}", number),
+            bio: format!("Demo account writing about {}", TOPICS[number % TOPICS.len()]),
+            avatar: String::new(),
+            social_links: None,
+            interests: Some(vec![TOPICS[number % TOPICS.len()].to_string(), TOPICS[(number + 3) % TOPICS.len()].to_string()]),
+            invite_code: None,
+        }, principal)?;
+        summary.users_created += 1;
+
+        let follows = rng.gen_range(0..=MAX_DEMO_FOLLOWS_PER_USER.min(authors.len()));
+        let followed: BTreeSet<Principal> = (0..follows).map(|_| authors[popular_index(&mut rng, authors.len())]).collect();
+        for user_to_follow in followed {
+            follow_user(FollowUserRequest { user_to_follow }, principal)?;
+            summary.follows_created += 1;
+        }
+        authors.push(principal);
+    }
+    if authors.is_empty() {
+        authors = STORAGE.with(|storage| storage.borrow().users.keys().copied().collect());
+        authors.sort();
+    }
+    if authors.is_empty() && (posts > 0 || comments > 0) {
+        return log_and_return(invalid_operation_error(
+            "seed_demo_data",
+            "There are no users to write the demo content",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let mut post_ids = Vec::new();
+    STORAGE.with(|storage| -> SquareResult<()> {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        for index in 0..posts {
+            let author = authors[popular_index(&mut rng, authors.len())];
+            let topic = TOPICS[rng.gen_range(0..TOPICS.len())];
+            let hashtags = normalize_hashtags(vec![topic.to_string()]);
+            let created_at = now.saturating_sub(rng.gen_range(0..DEMO_CONTENT_DAYS * MILLIS_PER_DAY));
+            let mut post = Post {
+                id: format!("demo_post_{}_{}", now, index),
+                author,
+                content: POST_TEMPLATES[rng.gen_range(0..POST_TEMPLATES.len())].replace("{topic}", topic),
+                media_urls: Vec::new(),
+                hashtags: hashtags.clone(),
+                token_mentions: Vec::new(),
+                tags: vec![topic.to_string()],
+                created_at,
+                updated_at: created_at,
+                status: ContentStatus::Active,
+                visibility: ContentVisibility::Public,
+                news_reference: None,
+                posted_by: None,
+                title: None,
+                is_premium: false,
+                license: None,
+                token_gate: None,
+                format: ContentFormat::Plain,
+                content_hash: None,
+                cross_post_of: None,
+                cover_image: None,
+                thumbnail: None,
+            };
+            charge_storage(&mut store.storage_usage, author, 0, post_size(&post), None)?;
+            stamp_content_hash(&mut post, &mut store.pending_content_hashes, now);
+            index_post(store, author, &post.id);
+            let post_id = post.id.clone();
+            store.posts.insert(post_id.clone(), post);
+            log_event(store, author, InteractionEventKind::PostCreated { post_id: post_id.clone(), author, hashtags });
+
+            let likes = rng.gen_range(0..=authors.len().min(20));
+            for _ in 0..likes {
+                let liker = authors[popular_index(&mut rng, authors.len())];
+                let liker_id = store.principal_ids.intern(liker);
+                if store.like_sets.entry(post_id.clone()).or_default().insert(liker_id) {
+                    log_event(store, liker, InteractionEventKind::Liked { content_id: post_id.clone(), author: Some(author) });
+                    summary.likes_created += 1;
+                }
+            }
+            post_ids.push(post_id);
+            summary.posts_created += 1;
+        }
+
+        if post_ids.is_empty() {
+            post_ids = store.posts
+                .values()
+                .filter(|post| post.status == ContentStatus::Active)
+                .map(|post| post.id.clone())
+                .collect();
+            post_ids.sort();
+        }
+        if post_ids.is_empty() && comments > 0 {
+            return log_and_return(invalid_operation_error(
+                "seed_demo_data",
+                "There are no posts to comment on",
+                MODULE,
+                FUNCTION
+            ));
+        }
+
+        // A quarter of the comments reply to an earlier demo comment
+        let mut comment_ids: Vec<String> = Vec::new();
+        for index in 0..comments {
+            let author = authors[rng.gen_range(0..authors.len())];
+            let (parent_id, parent_type) = if !comment_ids.is_empty() && rng.gen_bool(0.25) {
+                (comment_ids[rng.gen_range(0..comment_ids.len())].clone(), ParentType::Comment)
+            } else {
+                (post_ids[popular_index(&mut rng, post_ids.len())].clone(), ParentType::Post)
+            };
+            let id = format!("demo_comment_{}_{}", now, index);
+            let comment = Comment {
+                id: id.clone(),
+                author,
+                content: COMMENT_TEMPLATES[rng.gen_range(0..COMMENT_TEMPLATES.len())].to_string(),
+                parent_id: parent_id.clone(),
+                parent_type,
+                created_at: now,
+                updated_at: now,
+                status: ContentStatus::Active,
+                child_comments: Vec::new(),
+                likes_count: 0,
+                format: ContentFormat::Plain,
+                quoted_post_id: None,
+            };
+            if let (ParentType::Comment, Some(mut parent)) = (parent_type, store.comments.get_mut(&parent_id)) {
+                parent.child_comments.push(id.clone());
+            }
+            charge_storage(&mut store.storage_usage, author, 0, comment_size(&comment), None)?;
+            index_comment(store, &comment);
+            let parent_content_type = match parent_type {
+                ParentType::Post => ContentType::Post,
+                ParentType::Comment => ContentType::Comment,
+            };
+            let excluded = excluded_from_engagement(store, &comment, &parent_content_type);
+            store.comments.insert(id.clone(), comment);
+            log_event(store, author, InteractionEventKind::CommentCreated { comment_id: id.clone(), author, excluded });
+            comment_ids.push(id);
+            summary.comments_created += 1;
+        }
+        Ok(())
+    })?;
+
+    Ok(summary)
+}
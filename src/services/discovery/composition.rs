@@ -0,0 +1,204 @@
+use candid::Principal;
+use std::collections::HashSet;
+
+use crate::auth::is_admin;
+use crate::models::content::{ContentStatus, ContentVisibility, PaginationParams, Post};
+use crate::models::discovery::*;
+use crate::models::display::FeedResponse;
+use crate::models::error::SquareResult;
+use crate::models::storage::Storage;
+use crate::services::content::rendering::attach_rendered_posts;
+use crate::services::content::summary::attach_article_summaries;
+use crate::services::content::cross_posts::dedup_cross_posts;
+use crate::services::content::timeline::feed_item;
+use crate::services::emoji::attach_post_emojis;
+use crate::services::user::deactivated_users;
+use crate::services::user::social::attach_liked_by_followed;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::response_size::truncate_feed;
+use crate::utils::time_utils::{now_millis, MILLIS_PER_DAY};
+use crate::utils::validators::validate_feed_composition;
+use super::trending::ranked_trending_posts;
+
+const MODULE: &str = "services::discovery::composition";
+
+// Post ids from each source, best first, alongside the source's weight
+struct Sources {
+    pools: Vec<(u32, Vec<String>)>,
+    degraded: bool,
+}
+
+fn composition(store: &Storage) -> FeedComposition {
+    store.feed_composition.clone().unwrap_or_default()
+}
+
+fn newest_first<'a>(posts: impl Iterator<Item = &'a Post>, window: usize) -> Vec<String> {
+    let mut posts: Vec<&Post> = posts.collect();
+    posts.sort_by_key(|post| std::cmp::Reverse(post.created_at));
+    posts.into_iter().take(window).map(|post| post.id.clone()).collect()
+}
+
+// Gather every source for `caller`; without a caller there is no followed source
+fn gather_sources(caller: Option<Principal>, window: usize) -> Sources {
+    let now = now_millis();
+    let deactivated = deactivated_users();
+
+    let (weights, followed, fresh_creators, news, trending) = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let weights = composition(&store);
+        let mut eligible: Vec<&Post> = store.posts
+            .values()
+            .filter(|post| post.status == ContentStatus::Active && post.visibility == ContentVisibility::Public)
+            .filter(|post| !deactivated.contains(&post.author) && Some(post.author) != caller)
+            .collect();
+        dedup_cross_posts(&mut eligible);
+
+        let following: HashSet<Principal> = caller
+            .and_then(|caller| store.user_profiles.as_ref()?.get(&caller))
+            .map(|profile| profile.followed_users.clone())
+            .unwrap_or_default();
+        let followed = newest_first(eligible.iter().copied().filter(|post| following.contains(&post.author)), window);
+
+        let fresh_since = now.saturating_sub(FRESH_CREATOR_DAYS * MILLIS_PER_DAY);
+        let fresh_creators = newest_first(
+            eligible.iter().copied().filter(|post| store.users.get(&post.author).is_some_and(|user| user.registered_at >= fresh_since)),
+            window
+        );
+
+        let news = newest_first(eligible.iter().copied().filter(|post| post.news_reference.is_some()), window);
+
+        // Ranked from the heartbeat's trending scores
+        let trending_since = now.saturating_sub(COMPOSED_TRENDING_DAYS * MILLIS_PER_DAY);
+        let recent: HashSet<&str> = eligible
+            .iter()
+            .filter(|post| post.created_at >= trending_since)
+            .map(|post| post.id.as_str())
+            .collect();
+        let trending = ranked_trending_posts(window, |post_id| recent.contains(post_id));
+
+        (weights, followed, fresh_creators, news, trending)
+    });
+
+    // Until the heartbeat has scored posts there is no trending share
+    let degraded = trending.is_none();
+    let trending = trending.unwrap_or_default();
+
+    let followed_weight = if caller.is_some() { weights.followed } else { 0 };
+    Sources {
+        pools: vec![
+            (followed_weight, followed),
+            (weights.trending, trending),
+            (weights.fresh_creators, fresh_creators),
+            (weights.news, news),
+        ],
+        degraded,
+    }
+}
+
+// Smooth weighted round robin over the sources that still have posts. A post found in several
+// sources is shown once, in the first turn that reaches it.
+fn interleave(pools: Vec<(u32, Vec<String>)>, window: usize) -> Vec<String> {
+    let mut cursors = vec![0; pools.len()];
+    let mut credits = vec![0i64; pools.len()];
+    let mut seen = HashSet::new();
+    let mut feed = Vec::new();
+    while feed.len() < window {
+        let active: Vec<usize> = (0..pools.len())
+            .filter(|index| pools[*index].0 > 0 && cursors[*index] < pools[*index].1.len())
+            .collect();
+        let Some(first) = active.first().copied() else {
+            break;
+        };
+        let total: i64 = active.iter().map(|index| pools[*index].0 as i64).sum();
+        let mut turn = first;
+        for index in active {
+            credits[index] += pools[index].0 as i64;
+            if credits[index] > credits[turn] {
+                turn = index;
+            }
+        }
+        credits[turn] -= total;
+
+        let post_id = &pools[turn].1[cursors[turn]];
+        cursors[turn] += 1;
+        if seen.insert(post_id.clone()) {
+            feed.push(post_id.clone());
+        }
+    }
+    feed
+}
+
+fn composed_feed(caller: Option<Principal>, pagination: PaginationParams, function: &str) -> SquareResult<FeedResponse> {
+    let offset = pagination.offset.unwrap_or(0);
+    let limit = pagination.limit.unwrap_or(10);
+    if offset.saturating_add(limit) > MAX_COMPOSED_FEED_WINDOW {
+        return log_and_return(field_validation_error(
+            "pagination",
+            &format!("offset + limit must be at most {}", MAX_COMPOSED_FEED_WINDOW),
+            MODULE,
+            function
+        ));
+    }
+
+    let sources = gather_sources(caller, MAX_COMPOSED_FEED_WINDOW);
+    let feed = interleave(sources.pools, MAX_COMPOSED_FEED_WINDOW);
+    let total = feed.len();
+    let mut posts = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        feed.iter()
+            .skip(offset)
+            .take(limit)
+            .filter_map(|post_id| store.posts.get(post_id))
+            .map(feed_item)
+            .collect::<SquareResult<Vec<_>>>()
+    })?;
+
+    attach_liked_by_followed(&mut posts);
+    attach_article_summaries(&mut posts);
+    attach_rendered_posts(&mut posts);
+    attach_post_emojis(&mut posts);
+
+    let mut response = FeedResponse {
+        posts,
+        comments: vec![],
+        has_more: offset + limit < total,
+        next_offset: offset + limit,
+        total: total as u64,
+        degraded: sources.degraded,
+        truncated: false,
+    };
+    truncate_feed(&mut response, offset);
+    Ok(response)
+}
+
+// Followed accounts, trending posts, fresh creators and news, mixed by the feed composition
+pub fn get_home_feed(pagination: PaginationParams, caller: Principal) -> SquareResult<FeedResponse> {
+    composed_feed(Some(caller), pagination, "get_home_feed")
+}
+
+// The home feed's mix without the followed share, for signed-out visitors too
+pub fn get_explore_feed(pagination: PaginationParams) -> SquareResult<FeedResponse> {
+    composed_feed(None, pagination, "get_explore_feed")
+}
+
+pub fn get_feed_composition() -> SquareResult<FeedComposition> {
+    Ok(STORAGE.with(|storage| composition(&storage.borrow())))
+}
+
+// Takes effect on the next feed read
+pub fn update_feed_composition(composition: FeedComposition) -> SquareResult<FeedComposition> {
+    const FUNCTION: &str = "update_feed_composition";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_feed_composition",
+            "Only admins can update the feed composition",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    validate_feed_composition(&composition).finish(MODULE, FUNCTION)?;
+    STORAGE.with(|storage| storage.borrow_mut().feed_composition = Some(composition.clone()));
+    Ok(composition)
+}
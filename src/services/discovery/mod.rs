@@ -7,6 +7,10 @@ use crate::models::error::SquareResult;
 pub mod trending;
 pub mod search;
 pub mod recommendations;
+pub mod tags;
+pub mod similarity;
+pub mod composition;
+pub mod spotlight;
 
 // Re-export commonly used functions
 pub use trending::{
@@ -14,6 +18,10 @@ pub use trending::{
     get_trending_topics,
     get_hot_tags,
     update_trending_content,
+    decay_trending_topics,
+    get_trending_maintenance_stats,
+    merge_tags,
+    refresh_trending_scores,
 };
 
 pub use search::{
@@ -21,7 +29,38 @@ pub use search::{
     search_content,
 };
 
+pub use tags::{
+    get_tag,
+    update_tag,
+    add_tag_moderator,
+    remove_tag_moderator,
+    get_my_moderated_tags,
+    set_tag_posting_roles,
+    pin_topic_post,
+    unpin_topic_post,
+};
+
 pub use recommendations::{
     get_personalized_recommendations,
     get_collaborative_recommendations,
 };
+
+pub use similarity::{
+    schedule_similarity_refresh,
+    run_similarity_refresh,
+    get_similarity_refresh_status,
+    start_similarity_refresh,
+};
+
+pub use composition::{
+    get_home_feed,
+    get_explore_feed,
+    get_feed_composition,
+    update_feed_composition,
+};
+
+pub use spotlight::{
+    get_rising_creators,
+    get_newcomer_boost,
+    update_newcomer_boost,
+};
@@ -1,35 +1,52 @@
 use candid::Principal;
 use std::collections::{HashMap, HashSet};
 
-use crate::models::content::{ContentType, PostResponse};
+use crate::models::content::{ContentStatus, ContentType, PostResponse};
 use crate::models::discovery::*;
 use crate::models::display::FeedResponse;
 use crate::models::error::{SquareResult, SquareError};
 use crate::storage::{STORAGE, Post};
 use std::borrow::Borrow;
 use crate::utils::error_handler::*;
-use crate::services::user::social::get_user_social_info;
+use crate::services::user::social::{attach_liked_by_followed, get_user_social_info};
+use crate::services::content::summary::attach_article_summaries;
+use crate::services::content::rendering::attach_rendered_posts;
+use crate::services::emoji::attach_post_emojis;
+use crate::services::user::deactivated_users;
+use crate::services::impression::{click_weight, ranking_arm, recently_served};
+use super::spotlight::newcomer_multiplier;
+use crate::utils::instruction_budget::approaching_instruction_limit;
+use crate::utils::response_size::truncate_feed;
+use crate::utils::pagination::page_bounds;
+use crate::services::feature_flags::ensure_flag_disabled;
+use crate::models::feature_flag::FeatureFlag;
+use crate::services::token_gate::withhold_gated_content;
 
 
 pub fn get_personalized_recommendations(request: PersonalizedRecommendationsRequest) -> SquareResult<FeedResponse> {
     const MODULE: &str = "services::discovery::recommendations";
     const FUNCTION: &str = "get_personalized_recommendations";
     
+    ensure_flag_disabled(FeatureFlag::DisableRecommendations, "get_personalized_recommendations", MODULE, FUNCTION)?;
+    
     let limit = request.pagination.limit;
-    let offset = request.pagination.offset;
     let _content_type = request.content_types.unwrap_or_else(|| vec![ContentType::Post])[0].clone();
     let user = ic_cdk::caller();
+    let arm = ranking_arm(user);
     
     // Get user's interests and interactions
     let (user_likes, user_follows, user_interests) = STORAGE.with(|storage| {
         let store = storage.borrow();
         
         // Get user's likes
-        let likes: HashSet<String> = store.likes
-            .iter()
-            .filter(|(_, users)| users.contains(&user))
-            .map(|(content_id, _)| content_id.clone())
-            .collect();
+        let likes: HashSet<String> = match store.principal_ids.id(&user) {
+            Some(id) => store.like_sets
+                .iter()
+                .filter(|(_, likers)| likers.contains(id))
+                .map(|(content_id, _)| content_id.clone())
+                .collect(),
+            None => HashSet::new(),
+        };
         
         // Get user's follows
         let follows: HashSet<Principal> = store.user_profiles.as_ref().and_then(|profiles| profiles.get(&user)).map(|profile| profile.followed_users.iter().cloned().collect()).unwrap_or_default();
@@ -44,52 +61,71 @@ pub fn get_personalized_recommendations(request: PersonalizedRecommendationsRequ
         (likes, follows, interests)
     });
     
-    // Get collaborative recommendations
+    // Get collaborative recommendations, skipping them if the call is already near the instruction limit
     let limit_value = limit.unwrap_or(10);
-    let collaborative_recs = get_collaborative_recommendations(user, limit_value);
+    let mut degraded = approaching_instruction_limit();
+    let collaborative_recs = if degraded {
+        Vec::new()
+    } else {
+        get_collaborative_recommendations(user, limit_value)
+            .into_iter()
+            .map(|(id, content_type, score)| {
+                let weight = STORAGE.with(|storage| click_weight(&storage.borrow(), arm, &id));
+                (id, content_type, score * weight)
+            })
+            .collect()
+    };
+    degraded = degraded || approaching_instruction_limit();
     
-    // Get content-based recommendations
-    let content_based_recs = STORAGE.with(|storage| {
-        let store = storage.borrow();
-        let mut recommendations = Vec::new();
-        
-        for post in store.posts.values() {
-            // Skip if content type doesn't match
-            if !post.hashtags.iter().any(|tag| tag.starts_with("#")) {
-                continue;
-            }
+    // Get content-based recommendations, or the latest unliked posts when degraded
+    let content_based_recs = if degraded {
+        latest_recommendations(&user_likes)
+    } else {
+        STORAGE.with(|storage| {
+            let store = storage.borrow();
+            let mut recommendations = Vec::new();
             
-            // Skip if user has already liked this content
-            if user_likes.contains(&post.id) {
-                continue;
-            }
+            for post in store.posts.values() {
+                // Skip if content type doesn't match
+                if !post.hashtags.iter().any(|tag| tag.starts_with("#")) {
+                    continue;
+                }
+            
+                // Skip if user has already liked this content
+                if user_likes.contains(&post.id) {
+                    continue;
+                }
             
-            // Calculate content-based score
-            let mut score = 0.0;
+                // Calculate content-based score
+                let mut score = 0.0;
             
-            // Score based on author follows
-            if user_follows.contains(&post.author) {
-                score += 1.0;
-            }
+                // Score based on author follows
+                if user_follows.contains(&post.author) {
+                    score += 1.0;
+                }
             
-            // Score based on matching interests
-            let matching_interests = post.tags
-                .iter()
-                .filter(|tag| user_interests.contains(*tag))
-                .count();
-            score += matching_interests as f64 * 0.5;
+                // Score based on matching interests
+                let matching_interests = post.tags
+                    .iter()
+                    .filter(|tag| user_interests.contains(*tag))
+                    .count();
+                score += matching_interests as f64 * 0.5;
             
-            if score > 0.0 {
-                recommendations.push((post.id.clone(), ContentType::Post, score));
+                if score > 0.0 {
+                    let weight = newcomer_multiplier(post.author) * click_weight(&store, arm, &post.id);
+                    recommendations.push((post.id.clone(), ContentType::Post, score * weight));
+                }
             }
-        }
-        
-        recommendations
-    });
+            
+            recommendations
+        })
+    };
     
-    // Merge and sort recommendations
+    // Merge and sort recommendations; collaborative scores are not comparable with recency scores
     let mut all_recs: Vec<(String, ContentType, f64)> = Vec::new();
-    all_recs.extend(collaborative_recs);
+    if !degraded {
+        all_recs.extend(collaborative_recs);
+    }
     all_recs.extend(content_based_recs);
     
     // Sort by score
@@ -99,10 +135,26 @@ pub fn get_personalized_recommendations(request: PersonalizedRecommendationsRequ
     let mut seen = HashSet::new();
     all_recs.retain(|(id, _, _)| seen.insert(id.clone()));
     
+    // Skip what the caller was already served this session
+    if request.exclude_seen == Some(true) {
+        let seen = recently_served(user);
+        all_recs.retain(|(id, _, _)| !seen.contains(id));
+    }
+    
+    // Hide content from deactivated accounts
+    let deactivated = deactivated_users();
+    if !deactivated.is_empty() {
+        STORAGE.with(|storage| {
+            let store = storage.borrow();
+            all_recs.retain(|(id, _, _)| {
+                store.posts.get(id).is_none_or(|post| !deactivated.contains(&post.author))
+            });
+        });
+    }
+    
     // Apply pagination
     let total = all_recs.len() as u64;
-    let start = offset.unwrap_or(0);
-    let end = (start + limit_value).min(all_recs.len());
+    let (start, end) = page_bounds(all_recs.len(), &request.pagination, 10);
     let recommendations = all_recs[start..end].to_vec();
     
     // Convert to feed items
@@ -110,7 +162,8 @@ pub fn get_personalized_recommendations(request: PersonalizedRecommendationsRequ
         let store = storage.borrow();
         recommendations.into_iter()
             .filter_map(|(id, _, _)| store.posts.get(&id))
-            .map(|post| Ok::<_, SquareError>(PostResponse {
+            .filter(|post| post.status != ContentStatus::LegalHold)
+            .map(|post| Ok::<_, SquareError>(withhold_gated_content(PostResponse {
                 id: post.id.clone(),
                 author: post.author,
                 content: post.content.clone(),
@@ -126,74 +179,84 @@ pub fn get_personalized_recommendations(request: PersonalizedRecommendationsRequ
                 comments_count: 0, // TODO: Get from comments storage
                 author_info: get_user_social_info(post.author.to_string(), None)?,
                 news_reference: post.news_reference.clone(),
-            }))
+                posted_by: post.posted_by,
+                title: post.title.clone(),
+                is_premium: post.is_premium,
+                license: post.license,
+                token_gate: post.token_gate.clone(),
+                liked_by_followed: Vec::new(),
+                summary: None,
+                format: post.format,
+                source: None,
+                reactions: Vec::new(),
+                emojis: Vec::new(),
+                content_hash: post.content_hash.clone(),
+                cross_post_of: post.cross_post_of.clone(),
+                table_of_contents: Vec::new(),
+                cover_image: post.cover_image.clone(),
+                thumbnail: post.thumbnail.clone(),
+            })))
             .collect::<Vec<_>>()
     });
     
-    Ok(FeedResponse {
-        posts: feed_items.into_iter().collect::<Result<Vec<_>, _>>()?,
+    let mut posts = feed_items.into_iter().collect::<Result<Vec<_>, _>>()?;
+    attach_liked_by_followed(&mut posts);
+    attach_article_summaries(&mut posts);
+    attach_rendered_posts(&mut posts);
+    attach_post_emojis(&mut posts);
+    
+    let mut response = FeedResponse {
+        posts,
         comments: vec![],
-        has_more: (start as u64 + limit_value as u64) < total,
-        next_offset: start + limit_value,
+        has_more: (end as u64) < total,
+        next_offset: end,
         total,
+        degraded,
+        truncated: false,
+    };
+    truncate_feed(&mut response, start);
+    Ok(response)
+}
+
+// Cheap fallback ranking: newest posts the user has not liked, scored by creation time
+fn latest_recommendations(user_likes: &HashSet<String>) -> Vec<(String, ContentType, f64)> {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.posts
+            .values()
+            .filter(|post| post.hashtags.iter().any(|tag| tag.starts_with("#")))
+            .filter(|post| !user_likes.contains(&post.id) && post.status != ContentStatus::LegalHold)
+            .map(|post| (post.id.clone(), ContentType::Post, post.created_at as f64))
+            .collect()
     })
 }
 
+// Scores content liked by the user's precomputed similar users; users without a neighbour list get none
 pub fn get_collaborative_recommendations(user: Principal, limit: usize) -> Vec<(String, ContentType, f64)> {
-    // Get all users and their interactions
-    let (user_interactions, all_users) = STORAGE.with(|storage| {
+    let recommendations = STORAGE.with(|storage| {
         let store = storage.borrow();
+        let Some(similar_users) = store.similar_users.get(&user).filter(|similar_users| !similar_users.is_empty()) else {
+            return HashMap::new();
+        };
+        let user_id = store.principal_ids.id(&user);
+        let neighbours: Vec<(u32, f64)> = similar_users
+            .iter()
+            .filter_map(|similar| store.principal_ids.id(&similar.user).map(|id| (id, similar.similarity)))
+            .collect();
         
-        // Get all users who have interacted with content
-        let mut users = HashSet::new();
-        for likes in store.likes.values() {
-            users.extend(likes.iter());
-        }
-        
-        // Create user interaction map
-        let mut interactions = HashMap::new();
-        for (content_id, liking_users) in &store.likes {
-            for user in liking_users {
-                interactions
-                    .entry(*user)
-                    .or_insert_with(HashSet::new)
-                    .insert(content_id.clone());
+        // Get recommendations from similar users
+        let mut recommendations = HashMap::new();
+        for (content_id, likers) in &store.like_sets {
+            if user_id.is_some_and(|id| likers.contains(id)) {
+                continue;
             }
-        }
-        
-        (interactions, users)
-    });
-    
-    // Calculate user similarities
-    let mut user_similarities = Vec::new();
-    for other_user in all_users {
-        if other_user == user {
-            continue;
-        }
-        
-        let similarity = calculate_user_similarity(&user, &other_user);
-        if similarity > 0.0 {
-            user_similarities.push((other_user, similarity));
-        }
-    }
-    
-    // Sort by similarity
-    user_similarities.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-    
-    // Get recommendations from similar users
-    let mut recommendations = HashMap::new();
-    let user_likes = user_interactions.get(&user).cloned().unwrap_or_default();
-    
-    for (similar_user, similarity) in user_similarities {
-        if let Some(similar_user_likes) = user_interactions.get(&similar_user) {
-            for content_id in similar_user_likes {
-                if !user_likes.contains(content_id) {
-                    let score = recommendations.entry(content_id.clone()).or_insert(0.0);
-                    *score += similarity;
-                }
+            let score: f64 = neighbours.iter().filter(|(id, _)| likers.contains(*id)).map(|(_, similarity)| similarity).sum();
+            if score > 0.0 {
+                recommendations.insert(content_id.clone(), score);
             }
         }
-    }
+        recommendations
+    });
     
     // Convert to vector and sort by score
     let mut recommendations: Vec<_> = recommendations.into_iter().collect();
@@ -213,49 +276,3 @@ pub fn get_collaborative_recommendations(user: Principal, limit: usize) -> Vec<(
             .collect::<Vec<_>>()
     })
 }
-
-// Calculate similarity between two users based on their likes, follows, and content interactions
-fn calculate_user_similarity(user1: &Principal, user2: &Principal) -> f64 {
-    STORAGE.with(|storage| {
-        let store = storage.borrow();
-        
-        // Get likes intersection
-        let user1_likes = store.likes
-            .iter()
-            .filter(|(_, users)| users.contains(user1))
-            .map(|(content_id, _)| content_id)
-            .collect::<HashSet<_>>();
-        
-        let user2_likes = store.likes
-            .iter()
-            .filter(|(_, users)| users.contains(user2))
-            .map(|(content_id, _)| content_id)
-            .collect::<HashSet<_>>();
-        
-        let likes_intersection = user1_likes.intersection(&user2_likes).count();
-        let likes_union = user1_likes.union(&user2_likes).count();
-        
-        // Get follows intersection
-        let user1_follows: HashSet<Principal> = store.user_profiles.as_ref().and_then(|profiles| profiles.get(&user1)).map(|profile| profile.followed_users.iter().cloned().collect()).unwrap_or_default();
-        let user2_follows: HashSet<Principal> = store.user_profiles.as_ref().and_then(|profiles| profiles.get(&user2)).map(|profile| profile.followed_users.iter().cloned().collect()).unwrap_or_default();
-        
-        let follows_intersection = user1_follows.intersection(&user2_follows).count();
-        let follows_union = user1_follows.union(&user2_follows).count();
-        
-        // Calculate Jaccard similarity for likes and follows
-        let likes_similarity = if likes_union > 0 {
-            likes_intersection as f64 / likes_union as f64
-        } else {
-            0.0
-        };
-        
-        let follows_similarity = if follows_union > 0 {
-            follows_intersection as f64 / follows_union as f64
-        } else {
-            0.0
-        };
-        
-        // Weighted average of similarities
-        0.7 * likes_similarity + 0.3 * follows_similarity
-    })
-}
@@ -1,7 +1,7 @@
 use candid::Principal;
 use std::collections::HashSet;
 
-use crate::models::content::{ContentType, PostResponse};
+use crate::models::content::{ContentStatus, ContentType, PostResponse};
 use crate::models::discovery::*;
 use crate::models::display::FeedResponse;
 use crate::models::error::{SquareResult, SquareError};
@@ -9,27 +9,70 @@ use crate::storage::{STORAGE, Post};
 use std::borrow::Borrow;
 use crate::utils::error_handler::*;
 use crate::models::user::UserSocialResponse;
-use crate::services::user::social::get_user_social_info;
+use crate::services::user::social::{attach_liked_by_followed, get_user_social_info};
+use crate::services::content::summary::attach_article_summaries;
+use crate::services::content::rendering::attach_rendered_posts;
+use crate::services::emoji::attach_post_emojis;
+use crate::services::user::deactivated_users;
+use crate::services::impression::recently_served;
+use super::trending::cached_trending_scores;
+use crate::services::query_cache::{cached, sort_variant, CachedQuery};
+use crate::services::token_gate::withhold_gated_content;
+use crate::services::content::cross_posts::dedup_cross_posts;
+use crate::utils::response_size::truncate_feed;
+use crate::utils::pagination::page_bounds;
 
 pub fn discover_content(request: DiscoverContentRequest) -> SquareResult<FeedResponse> {
+    let offset = request.pagination.offset.unwrap_or(0);
+    // The unfiltered first page is the explore page
+    let is_explore_page = request.tags.as_ref().is_none_or(|tags| tags.is_empty())
+        && request.filter.is_none()
+        && request.content_types.as_ref().is_none_or(|types| types.iter().all(|content_type| *content_type == ContentType::Post))
+        && request.exclude_seen != Some(true)
+        && offset == 0;
+    let mut response = if !is_explore_page {
+        load_discover_content(request)?
+    } else {
+        let limit = request.pagination.limit.unwrap_or(10);
+        let variant = sort_variant(&request.sort_by);
+        cached(CachedQuery::Explore, limit, variant, || load_discover_content(request))?
+    };
+    attach_liked_by_followed(&mut response.posts);
+    attach_article_summaries(&mut response.posts);
+    attach_rendered_posts(&mut response.posts);
+    attach_post_emojis(&mut response.posts);
+    truncate_feed(&mut response, offset);
+    Ok(response)
+}
+
+fn load_discover_content(request: DiscoverContentRequest) -> SquareResult<FeedResponse> {
     const MODULE: &str = "services::discovery::search";
     const FUNCTION: &str = "discover_content";
     
-    let limit = request.pagination.limit;
-    let offset = request.pagination.offset;
     let _content_type = request.content_types.unwrap_or_else(|| vec![ContentType::Post])[0].clone();
     let tags = request.tags.unwrap_or_default();
     
     // Get all posts from storage
     let mut posts = STORAGE.with(|storage| {
         let store = storage.borrow();
-        store.posts.values().cloned().collect::<Vec<Post>>()
+        // Only published posts; embargoed and held ones are not Active yet
+        store.posts.values().filter(|post| post.status == ContentStatus::Active).cloned().collect::<Vec<Post>>()
     });
     
     // Filter by content type
     // Filter by content type
     posts.retain(|post| post.hashtags.iter().any(|tag| tag.starts_with("#")));
     
+    // Hide content from deactivated accounts
+    let deactivated = deactivated_users();
+    posts.retain(|post| !deactivated.contains(&post.author));
+    
+    // Skip what the caller was already served this session
+    if request.exclude_seen == Some(true) {
+        let seen = recently_served(ic_cdk::caller());
+        posts.retain(|post| !seen.contains(&post.id));
+    }
+    
     // Filter by tags if specified
     if !tags.is_empty() {
         posts.retain(|post| {
@@ -37,19 +80,27 @@ pub fn discover_content(request: DiscoverContentRequest) -> SquareResult<FeedRes
         });
     }
     
-    // Sort by creation time (newest first)
-    posts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    // A post cross-posted into several communities is listed once
+    dedup_cross_posts(&mut posts);
+    
+    // Trending order reads the scores the heartbeat computed, quality-weighted and with the newcomer
+    // boost; until it has computed any, posts are listed newest first
+    let trending = request.sort_by == Some(SortOption::Trending);
+    let scores = if trending { cached_trending_scores(posts.iter().map(|post| post.id.as_str())) } else { None };
+    let degraded = trending && scores.is_none();
+    match &scores {
+        Some(scores) => posts.sort_by(|a, b| scores[&b.id].total_cmp(&scores[&a.id]).then_with(|| b.created_at.cmp(&a.created_at))),
+        None => posts.sort_by(|a, b| b.created_at.cmp(&a.created_at)),
+    }
     
     // Apply pagination
     let total = posts.len() as u64;
-    let start = offset.unwrap_or(0);
-    let limit_value = limit.unwrap_or(10);
-    let end = (start + limit_value).min(posts.len());
+    let (start, end) = page_bounds(posts.len(), &request.pagination, 10);
     let posts = posts[start..end].to_vec();
     
     // Convert to response format
     let feed_items = posts.into_iter()
-        .map(|post| Ok::<_, SquareError>(PostResponse {
+        .map(|post| Ok::<_, SquareError>(withhold_gated_content(PostResponse {
             id: post.id.clone(),
             author: post.author,
             content: post.content.clone(),
@@ -73,18 +124,38 @@ pub fn discover_content(request: DiscoverContentRequest) -> SquareResult<FeedRes
                 following_count: 0,
                 is_following: false,
                 interests: vec![],
-                is_followed_by_caller: false
+                is_followed_by_caller: false,
+                has_linked_wallet: false,
+                followed_at: None,
             }),
             news_reference: post.news_reference.clone(),
-        }))
+            posted_by: post.posted_by,
+            title: post.title.clone(),
+            is_premium: post.is_premium,
+            license: post.license,
+            token_gate: post.token_gate.clone(),
+            liked_by_followed: Vec::new(),
+            summary: None,
+            format: post.format,
+            source: None,
+            reactions: Vec::new(),
+            emojis: Vec::new(),
+            content_hash: post.content_hash.clone(),
+            cross_post_of: post.cross_post_of.clone(),
+            table_of_contents: Vec::new(),
+            cover_image: post.cover_image.clone(),
+            thumbnail: post.thumbnail.clone(),
+        })))
         .collect::<Result<Vec<_>, _>>()?;
     
     Ok(FeedResponse {
         posts: feed_items,
         comments: vec![],
-        has_more: (start as u64 + limit_value as u64) < total,
-        next_offset: start + limit_value,
+        has_more: (end as u64) < total,
+        next_offset: end,
         total,
+        degraded,
+        truncated: false,
     })
 }
 
@@ -100,6 +171,8 @@ pub fn search_content(request: SearchRequest) -> SquareResult<Vec<SearchResultRe
     let _content_type = request.content_types.unwrap_or_else(|| vec![ContentType::Post])[0].clone();
     let tags: HashSet<String> = HashSet::new(); // We don't filter by tags in search
     
+    let deactivated = deactivated_users();
+    
     // Get all posts from storage
     let mut results = STORAGE.with(|storage| {
         let store = storage.borrow();
@@ -111,6 +184,17 @@ pub fn search_content(request: SearchRequest) -> SquareResult<Vec<SearchResultRe
                 continue;
             }
             
+            // Skip content from deactivated accounts and anything not published, such as embargoed,
+            // held, moderated or legally held posts
+            if deactivated.contains(&post.author) || post.status != ContentStatus::Active {
+                continue;
+            }
+            
+            // Cross-posts repeat their original, which is searched instead
+            if post.cross_post_of.is_some() {
+                continue;
+            }
+            
             // Skip if tags don't match (if tags are specified)
             if !tags.is_empty() && !post.tags.iter().any(|tag| tags.contains(tag)) {
                 continue;
@@ -121,7 +205,9 @@ pub fn search_content(request: SearchRequest) -> SquareResult<Vec<SearchResultRe
             let tag_match = post.tags.iter().any(|tag| tag.to_lowercase().contains(&query));
             
             if content_match || tag_match {
-                let snippet = if content_match {
+                let snippet = if post.token_gate.is_some() || post.is_premium {
+                    String::new()
+                } else if content_match {
                     create_snippet(&post.content, &query)
                 } else {
                     post.content[..100.min(post.content.len())].to_string()
@@ -143,7 +229,9 @@ pub fn search_content(request: SearchRequest) -> SquareResult<Vec<SearchResultRe
                         following_count: 0,
                         is_following: false,
                         interests: vec![],
-                        is_followed_by_caller: false
+                        is_followed_by_caller: false,
+                        has_linked_wallet: false,
+                        followed_at: None,
                     }),
                     relevance_score: calculate_relevance_score(
                         false,
@@ -151,6 +239,7 @@ pub fn search_content(request: SearchRequest) -> SquareResult<Vec<SearchResultRe
                         tag_match,
                         post.created_at,
                     ),
+                    thumbnail: post.thumbnail.clone().or_else(|| post.cover_image.clone()),
                 });
             }
         }
@@ -0,0 +1,243 @@
+use candid::Principal;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use crate::auth::is_admin;
+use crate::models::discovery::*;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::storage::Storage;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::id_set::IdSet;
+use crate::utils::instruction_budget::approaching_instruction_limit;
+use crate::utils::time_utils::{now_millis, MILLIS_PER_DAY};
+
+const MODULE: &str = "services::discovery::similarity";
+
+// Each liker's liked content, with content numbered as it is first seen. Built from the like index
+// when a refresh starts, and kept in step with likes and deletions until the next one, so refresh
+// batches do not rebuild it.
+#[derive(Default)]
+struct LikedContent {
+    content_ids: HashMap<String, u32>,
+    by_user: HashMap<u32, IdSet>,
+}
+
+impl LikedContent {
+    fn content_id(&mut self, content_id: &str) -> u32 {
+        let next = self.content_ids.len() as u32;
+        *self.content_ids.entry(content_id.to_string()).or_insert(next)
+    }
+}
+
+thread_local! {
+    // Heap only; None until the first refresh after an upgrade builds it
+    static LIKED_CONTENT: RefCell<Option<LikedContent>> = const { RefCell::new(None) };
+}
+
+fn build_liked_content(store: &Storage) {
+    let mut liked = LikedContent::default();
+    for (content_id, likers) in store.like_sets.iter() {
+        let ordinal = liked.content_id(content_id);
+        for liker in likers.iter() {
+            liked.by_user.entry(liker).or_default().insert(ordinal);
+        }
+    }
+    LIKED_CONTENT.with(|cache| *cache.borrow_mut() = Some(liked));
+}
+
+// Record a like or unlike by the liker's interned id, and count it toward the next refresh
+pub fn record_like_change(liker: u32, content_id: &str, liked: bool) {
+    LIKED_CONTENT.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let Some(cache) = cache.as_mut() else {
+            return;
+        };
+        let ordinal = cache.content_id(content_id);
+        let likes = cache.by_user.entry(liker).or_default();
+        if liked {
+            likes.insert(ordinal);
+        } else {
+            likes.remove(ordinal);
+            if likes.is_empty() {
+                cache.by_user.remove(&liker);
+            }
+        }
+    });
+    STORAGE.with(|storage| storage.borrow_mut().similarity_refresh.like_changes += 1);
+}
+
+// Take deleted content's likes out of its likers' sets
+pub(crate) fn forget_liked_content(content_id: &str, likers: &IdSet) {
+    LIKED_CONTENT.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let Some(cache) = cache.as_mut() else {
+            return;
+        };
+        let Some(ordinal) = cache.content_ids.remove(content_id) else {
+            return;
+        };
+        for liker in likers.iter() {
+            if let Some(likes) = cache.by_user.get_mut(&liker) {
+                likes.remove(ordinal);
+            }
+        }
+    });
+}
+
+// Calculate similarity between two users based on their likes and follows
+fn calculate_user_similarity(store: &Storage, user1: &Principal, user1_likes: &IdSet, user2: &Principal, user2_likes: &IdSet) -> f64 {
+    let likes_intersection = user1_likes.intersection_len(user2_likes);
+    let likes_union = user1_likes.union_len(user2_likes);
+    
+    // Get follows intersection
+    let empty = HashSet::new();
+    let follows_of = |user: &Principal| store.user_profiles.as_ref().and_then(|profiles| profiles.get(user)).map_or(&empty, |profile| &profile.followed_users);
+    let user1_follows = follows_of(user1);
+    let user2_follows = follows_of(user2);
+    
+    let follows_intersection = user1_follows.intersection(user2_follows).count();
+    let follows_union = user1_follows.len() + user2_follows.len() - follows_intersection;
+    
+    // Calculate Jaccard similarity for likes and follows
+    let likes_similarity = if likes_union > 0 {
+        likes_intersection as f64 / likes_union as f64
+    } else {
+        0.0
+    };
+    
+    let follows_similarity = if follows_union > 0 {
+        follows_intersection as f64 / follows_union as f64
+    } else {
+        0.0
+    };
+    
+    // Weighted average of similarities
+    0.7 * likes_similarity + 0.3 * follows_similarity
+}
+
+fn top_similar_users(store: &Storage, interactions: &HashMap<u32, IdSet>, user: Principal) -> Vec<SimilarUser> {
+    let Some((user_id, user_likes)) = store.principal_ids.id(&user).and_then(|id| interactions.get(&id).map(|likes| (id, likes))) else {
+        return Vec::new();
+    };
+    let mut similar: Vec<SimilarUser> = interactions
+        .iter()
+        .filter(|(other_id, _)| **other_id != user_id)
+        .filter_map(|(other_id, other_likes)| {
+            let other = store.principal_ids.principal(*other_id)?;
+            let similarity = calculate_user_similarity(store, &user, user_likes, &other, other_likes);
+            (similarity > 0.0).then_some(SimilarUser { user: other, similarity })
+        })
+        .collect();
+    similar.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    similar.truncate(SIMILAR_USERS_PER_USER);
+    similar
+}
+
+// Queue every recently active user who has liked something; users outside the new queue lose their list
+fn start_refresh(store: &mut Storage) {
+    let active_since = now_millis().saturating_sub(SIMILARITY_ACTIVE_DAYS * MILLIS_PER_DAY);
+    let pending: Vec<Principal> = store.users
+        .values()
+        .filter(|user| user.last_login >= active_since)
+        .map(|user| user.principal)
+        .filter(|principal| store.principal_ids.id(principal).is_some())
+        .collect();
+    let queued: HashSet<&Principal> = pending.iter().collect();
+    store.similar_users.retain(|user, _| queued.contains(user));
+
+    let refresh = &mut store.similarity_refresh;
+    refresh.pending = pending;
+    refresh.like_changes = 0;
+    build_liked_content(store);
+}
+
+// Called from the full heartbeat run; a refresh already in progress carries on
+pub fn schedule_similarity_refresh() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.similarity_refresh.pending.is_empty() {
+            start_refresh(&mut store);
+        }
+    });
+}
+
+fn refresh_batch(store: &mut Storage, interactions: &HashMap<u32, IdSet>) {
+    for _ in 0..SIMILARITY_REFRESH_BATCH_SIZE {
+        if approaching_instruction_limit() {
+            break;
+        }
+        let Some(user) = store.similarity_refresh.pending.pop() else {
+            break;
+        };
+        let similar = top_similar_users(store, interactions, user);
+        if similar.is_empty() {
+            store.similar_users.remove(&user);
+        } else {
+            store.similar_users.insert(user, similar);
+        }
+    }
+}
+
+// Process the next batch of a running refresh. Called on every heartbeat.
+pub fn run_similarity_refresh() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.similarity_refresh.pending.is_empty() {
+            if store.similarity_refresh.like_changes < SIMILARITY_REFRESH_LIKE_CHANGES {
+                return;
+            }
+            start_refresh(&mut store);
+        } else if LIKED_CONTENT.with(|cache| cache.borrow().is_none()) {
+            // A refresh carried over an upgrade
+            build_liked_content(&store);
+        }
+
+        LIKED_CONTENT.with(|cache| {
+            if let Some(liked) = cache.borrow().as_ref() {
+                refresh_batch(&mut store, &liked.by_user);
+            }
+        });
+        if store.similarity_refresh.pending.is_empty() {
+            store.similarity_refresh.last_completed_at = Some(now_millis());
+        }
+    });
+}
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage the similar-users refresh",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+pub fn get_similarity_refresh_status() -> SquareResult<SimilarityRefreshStatus> {
+    const FUNCTION: &str = "get_similarity_refresh_status";
+
+    require_admin("get_similarity_refresh_status", FUNCTION)?;
+    Ok(STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let refresh = &store.similarity_refresh;
+        SimilarityRefreshStatus {
+            running: !refresh.pending.is_empty(),
+            pending_users: refresh.pending.len() as u64,
+            cached_users: store.similar_users.len() as u64,
+            like_changes_since_refresh: refresh.like_changes,
+            last_completed_at: refresh.last_completed_at,
+        }
+    }))
+}
+
+// Restart the refresh from scratch; batches are processed on the following heartbeats
+pub fn start_similarity_refresh() -> SquareResult<SimilarityRefreshStatus> {
+    const FUNCTION: &str = "start_similarity_refresh";
+
+    require_admin("start_similarity_refresh", FUNCTION)?;
+    STORAGE.with(|storage| start_refresh(&mut storage.borrow_mut()));
+    get_similarity_refresh_status()
+}
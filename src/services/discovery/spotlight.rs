@@ -0,0 +1,137 @@
+use candid::Principal;
+use std::collections::HashMap;
+
+use crate::auth::is_admin;
+use crate::models::discovery::*;
+use crate::models::error::SquareResult;
+use crate::models::event::InteractionEventKind;
+use crate::models::storage::Storage;
+use crate::models::user::UserStatus;
+use crate::services::user::{deactivated_users, hidden_from_rankings};
+use crate::services::user::social::get_user_social_info;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::{now_millis, MILLIS_PER_DAY};
+
+const MODULE: &str = "services::discovery::spotlight";
+
+fn boost_settings(store: &Storage) -> NewcomerBoostSettings {
+    store.newcomer_boost.clone().unwrap_or_default()
+}
+
+// What a discovery ranking score is multiplied by for posts by `author`
+pub(crate) fn newcomer_multiplier(author: Principal) -> f64 {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let settings = boost_settings(&store);
+        let followers = store.user_profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(&author))
+            .map_or(0, |profile| profile.followers_count);
+        if settings.enabled && followers < settings.max_followers {
+            1.0 + settings.boost_percent as f64 / 100.0
+        } else {
+            1.0
+        }
+    })
+}
+
+// A like or follow one principal gave, so repeats and undos are counted once per pair
+#[derive(PartialEq, Eq, Hash)]
+enum Engagement {
+    Like(String),
+    Follow(Principal),
+}
+
+// Creators whose likes and new followers grew the most from the previous window to the latest one.
+// Counted from the retained event log, once per liker and post or follower: a like or follow undone
+// since does not count, and one given again counts in the window of its latest time. The creator's
+// own likes and follows do not count. Creators hidden from rankings are left out.
+pub fn get_rising_creators(limit: Option<usize>) -> SquareResult<Vec<RisingCreator>> {
+    let limit = limit.unwrap_or(10).min(MAX_RISING_CREATORS);
+    let now = now_millis();
+    let window = RISING_CREATORS_WINDOW_DAYS * MILLIS_PER_DAY;
+    let recent_since = now.saturating_sub(window);
+    let previous_since = now.saturating_sub(2 * window);
+    let deactivated = deactivated_users();
+
+    let mut ranked: Vec<(Principal, u64, u64)> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        // (actor, engagement) -> (creator, whether it falls in the recent window)
+        let mut standing: HashMap<(Principal, Engagement), (Principal, bool)> = HashMap::new();
+        for event in store.interaction_events.events.iter().filter(|event| event.timestamp >= previous_since) {
+            let recent = event.timestamp >= recent_since;
+            match &event.kind {
+                InteractionEventKind::Liked { content_id, author: Some(author) } if *author != event.actor => {
+                    standing.insert((event.actor, Engagement::Like(content_id.clone())), (*author, recent));
+                }
+                InteractionEventKind::Followed { followed } if *followed != event.actor => {
+                    standing.insert((event.actor, Engagement::Follow(*followed)), (*followed, recent));
+                }
+                InteractionEventKind::Unliked { content_id, .. } => {
+                    standing.remove(&(event.actor, Engagement::Like(content_id.clone())));
+                }
+                InteractionEventKind::Unfollowed { followed } => {
+                    standing.remove(&(event.actor, Engagement::Follow(*followed)));
+                }
+                _ => {}
+            }
+        }
+        let mut engagement: HashMap<Principal, (u64, u64)> = HashMap::new();
+        for (creator, recent) in standing.into_values() {
+            let counts = engagement.entry(creator).or_default();
+            if recent {
+                counts.0 += 1;
+            } else {
+                counts.1 += 1;
+            }
+        }
+        engagement
+            .into_iter()
+            .filter(|(creator, _)| store.users.get(creator).is_some_and(|user| user.status == UserStatus::Active))
+            .filter(|(creator, _)| !deactivated.contains(creator) && !hidden_from_rankings(&store, creator))
+            .filter(|(_, (recent, previous))| *recent >= MIN_RISING_ENGAGEMENT && recent > previous)
+            .map(|(creator, (recent, previous))| (creator, recent, previous))
+            .collect()
+    });
+    ranked.sort_by(|a, b| (b.1 - b.2).cmp(&(a.1 - a.2)).then_with(|| b.1.cmp(&a.1)).then_with(|| a.0.cmp(&b.0)));
+
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|(creator, recent, previous)| Ok(RisingCreator {
+            user: get_user_social_info(creator.to_string(), None)?,
+            recent_engagement: recent,
+            previous_engagement: previous,
+            growth: recent as i64 - previous as i64,
+        }))
+        .collect()
+}
+
+pub fn get_newcomer_boost() -> SquareResult<NewcomerBoostSettings> {
+    Ok(STORAGE.with(|storage| boost_settings(&storage.borrow())))
+}
+
+// Takes effect on the next ranking; cached explore pages keep their order until they expire
+pub fn update_newcomer_boost(settings: NewcomerBoostSettings) -> SquareResult<NewcomerBoostSettings> {
+    const FUNCTION: &str = "update_newcomer_boost";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_newcomer_boost",
+            "Only admins can update the newcomer boost",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if settings.boost_percent > MAX_NEWCOMER_BOOST_PERCENT {
+        return log_and_return(field_validation_error(
+            "boost_percent",
+            &format!("must be at most {}", MAX_NEWCOMER_BOOST_PERCENT),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    STORAGE.with(|storage| storage.borrow_mut().newcomer_boost = Some(settings.clone()));
+    Ok(settings)
+}
@@ -0,0 +1,431 @@
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::auth::{is_admin, is_manager_or_admin};
+use crate::models::content::{ContentStatus, ContentType, ParentType, Post};
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::storage::Storage;
+use crate::models::tag::*;
+use crate::models::user::UserRole;
+use crate::storage::STORAGE;
+use crate::services::content::get_post;
+use crate::utils::content_utils::normalize_hashtag;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::MILLIS_PER_HOUR;
+use crate::utils::validators::validate_update_tag;
+use crate::services::query_cache::{invalidate, TAG_QUERIES};
+
+const MODULE: &str = "services::discovery::tags";
+const MAX_COMMENT_PARENT_DEPTH: usize = 64;
+
+// Build the public view of a tag, falling back to empty metadata for tags without an entity
+pub fn tag_response(store: &Storage, name: &str, post_count: u64) -> TagResponse {
+    let mut moderators: Vec<Principal> = store.tag_moderators
+        .get(name)
+        .map(|moderators| moderators.iter().cloned().collect())
+        .unwrap_or_default();
+    moderators.sort();
+
+    match store.tags.get(name) {
+        Some(tag) => TagResponse {
+            name: tag.name.clone(),
+            tag_type: TagType::Topic,
+            description: tag.description.clone(),
+            cover_image: tag.cover_image.clone(),
+            rules: tag.rules.clone(),
+            moderators,
+            token_gate: tag.token_gate.clone(),
+            posting_roles: tag.posting_roles.clone(),
+            follower_count: tag.follower_count,
+            post_count,
+            created_at: Some(tag.created_at),
+        },
+        None => TagResponse {
+            name: name.to_string(),
+            tag_type: TagType::Topic,
+            description: String::new(),
+            cover_image: None,
+            rules: Vec::new(),
+            moderators,
+            token_gate: None,
+            posting_roles: Vec::new(),
+            follower_count: 0,
+            post_count,
+            created_at: None,
+        },
+    }
+}
+
+pub fn new_tag(name: &str, now: u64) -> Tag {
+    Tag {
+        name: name.to_string(),
+        description: String::new(),
+        cover_image: None,
+        rules: Vec::new(),
+        pinned: None,
+        token_gate: None,
+        posting_roles: Vec::new(),
+        follower_count: 0,
+        created_at: now,
+        updated_at: now,
+        updated_by: None,
+    }
+}
+
+// Adjust a tag's follower count, creating the tag entity on its first follower
+pub fn adjust_tag_followers(store: &mut Storage, name: &str, followed: bool, now: u64) {
+    let tag = store.tags.entry(name.to_string()).or_insert_with(|| new_tag(name, now));
+    if followed {
+        tag.follower_count += 1;
+    } else {
+        tag.follower_count = tag.follower_count.saturating_sub(1);
+    }
+}
+
+// Topic page
+pub fn get_tag(name: String) -> SquareResult<TopicPageResponse> {
+    const FUNCTION: &str = "get_tag";
+
+    let Some(name) = normalize_hashtag(&name) else {
+        return log_and_return(field_validation_error(
+            "name",
+            "must contain at least one letter or digit",
+            MODULE,
+            FUNCTION
+        ));
+    };
+
+    let now = time() / 1_000_000;
+    let (tag, pinned) = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let post_count = store.posts.values().filter(|post| post.hashtags.contains(&name)).count() as u64;
+        if post_count == 0 && !store.tags.contains_key(&name) {
+            return log_and_return(not_found_error("Tag", &name, MODULE, FUNCTION));
+        }
+        let pinned = store.tags.get(&name).and_then(|tag| active_pin(&store, tag, now));
+        Ok((tag_response(&store, &name, post_count), pinned))
+    })?;
+
+    let pinned_content = match pinned {
+        Some(pin) => Some(PinnedContentResponse {
+            post: get_post(pin.post_id)?,
+            pinned_by: pin.pinned_by,
+            pinned_at: pin.pinned_at,
+            expires_at: pin.expires_at,
+        }),
+        None => None,
+    };
+
+    Ok(TopicPageResponse { tag, pinned_content })
+}
+
+// A pin is shown until it expires or the post stops being active
+fn active_pin(store: &Storage, tag: &Tag, now: u64) -> Option<PinnedContent> {
+    tag.pinned
+        .clone()
+        .filter(|pin| pin.expires_at.is_none_or(|expires_at| expires_at > now))
+        .filter(|pin| store.posts.get(&pin.post_id).is_some_and(|post| post.status == ContentStatus::Active))
+}
+
+// Moderator-managed tag metadata
+pub fn update_tag(request: UpdateTagRequest, caller: Principal) -> SquareResult<TagResponse> {
+    const FUNCTION: &str = "update_tag";
+
+    invalidate(TAG_QUERIES);
+
+    validate_update_tag(&request).finish(MODULE, FUNCTION)?;
+    let Some(name) = normalize_hashtag(&request.name) else {
+        return log_and_return(field_validation_error(
+            "name",
+            "must contain at least one letter or digit",
+            MODULE,
+            FUNCTION
+        ));
+    };
+
+    require_tag_moderation(caller, &name, "update_tag", FUNCTION)?;
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+
+        let tag = store.tags.entry(name.clone()).or_insert_with(|| new_tag(&name, now));
+        if let Some(description) = request.description {
+            tag.description = description;
+        }
+        if let Some(cover_image) = request.cover_image {
+            tag.cover_image = Some(cover_image).filter(|url| !url.is_empty());
+        }
+        if let Some(rules) = request.rules {
+            tag.rules = rules;
+        }
+        tag.updated_at = now;
+        tag.updated_by = Some(caller);
+
+        let post_count = store.posts.values().filter(|post| post.hashtags.contains(&name)).count() as u64;
+        Ok(tag_response(&store, &name, post_count))
+    })
+}
+
+// Pin an explainer post to the top of a topic feed
+pub fn pin_topic_post(request: PinTopicPostRequest, caller: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "pin_topic_post";
+
+    let Some(name) = normalize_hashtag(&request.tag) else {
+        return log_and_return(field_validation_error(
+            "tag",
+            "must contain at least one letter or digit",
+            MODULE,
+            FUNCTION
+        ));
+    };
+    if request.expires_in_hours.is_some_and(|hours| hours == 0 || hours > MAX_TOPIC_PIN_HOURS) {
+        return log_and_return(field_validation_error(
+            "expires_in_hours",
+            &format!("must be between 1 and {}", MAX_TOPIC_PIN_HOURS),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    require_tag_moderation(caller, &name, "pin_topic_post", FUNCTION)?;
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+
+        let Some(post) = store.posts.get(&request.post_id) else {
+            return log_and_return(not_found_error("Post", &request.post_id, MODULE, FUNCTION));
+        };
+        if post.status != ContentStatus::Active || !post.hashtags.contains(&name) {
+            return log_and_return(invalid_operation_error(
+                "pin_topic_post",
+                "Only active posts carrying the tag can be pinned to its topic page",
+                MODULE,
+                FUNCTION
+            ));
+        }
+
+        let tag = store.tags.entry(name.clone()).or_insert_with(|| new_tag(&name, now));
+        tag.pinned = Some(PinnedContent {
+            post_id: request.post_id,
+            pinned_by: caller,
+            pinned_at: now,
+            expires_at: request.expires_in_hours.map(|hours| now + hours * MILLIS_PER_HOUR),
+        });
+        Ok(())
+    })
+}
+
+pub fn unpin_topic_post(tag: String, caller: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "unpin_topic_post";
+
+    let name = normalize_hashtag(&tag).unwrap_or(tag);
+    require_tag_moderation(caller, &name, "unpin_topic_post", FUNCTION)?;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let Some(tag) = store.tags.get_mut(&name).filter(|tag| tag.pinned.is_some()) else {
+            return log_and_return(not_found_error("PinnedContent", &name, MODULE, FUNCTION));
+        };
+        tag.pinned = None;
+        Ok(())
+    })
+}
+
+// Managers, admins and the tag's own moderators may manage a topic page
+fn require_tag_moderation(caller: Principal, tag: &str, operation: &str, function: &str) -> SquareResult<()> {
+    let is_tag_moderator = STORAGE.with(|storage| is_moderator_of_tag(&storage.borrow(), caller, tag));
+    if is_manager_or_admin().is_err() && !is_tag_moderator {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only moderators of this tag can manage its topic page",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+// Tag moderators
+pub fn is_moderator_of_tag(store: &Storage, principal: Principal, tag: &str) -> bool {
+    store.tag_moderators.get(tag).is_some_and(|moderators| moderators.contains(&principal))
+}
+
+// Post a piece of content belongs to; comments resolve to the post at the top of their thread
+pub fn root_post<'a>(store: &'a Storage, content_type: &ContentType, content_id: &str) -> Option<&'a Post> {
+    let mut current = match content_type {
+        ContentType::Post => return store.posts.get(content_id),
+        ContentType::Comment => store.comments.get(content_id),
+    };
+    // Walk up nested comments, bounded in case of a corrupted parent chain
+    for _ in 0..MAX_COMMENT_PARENT_DEPTH {
+        let comment = current?;
+        match comment.parent_type {
+            ParentType::Post => return store.posts.get(&comment.parent_id),
+            ParentType::Comment => current = store.comments.get(&comment.parent_id),
+        }
+    }
+    None
+}
+
+// Hashtags governing a piece of content; comments inherit the hashtags of the post they belong to
+pub fn content_hashtags(store: &Storage, content_type: &ContentType, content_id: &str) -> Vec<String> {
+    root_post(store, content_type, content_id)
+        .map(|post| post.hashtags.clone())
+        .unwrap_or_default()
+}
+
+// Tag moderators may act on content carrying at least one of their tags
+pub fn can_moderate_in_tags(store: &Storage, principal: Principal, content_type: &ContentType, content_id: &str) -> bool {
+    content_hashtags(store, content_type, content_id)
+        .iter()
+        .any(|tag| is_moderator_of_tag(store, principal, tag))
+}
+
+// Tags a principal moderates
+pub fn moderated_tags(store: &Storage, principal: Principal) -> Vec<String> {
+    let mut tags: Vec<String> = store.tag_moderators
+        .iter()
+        .filter(|(_, moderators)| moderators.contains(&principal))
+        .map(|(tag, _)| tag.clone())
+        .collect();
+    tags.sort();
+    tags
+}
+
+pub fn add_tag_moderator(tag: String, moderator: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "add_tag_moderator";
+
+    invalidate(TAG_QUERIES);
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "add_tag_moderator",
+            "Only admins can appoint tag moderators",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    let Some(tag) = normalize_hashtag(&tag) else {
+        return log_and_return(field_validation_error(
+            "tag",
+            "must contain at least one letter or digit",
+            MODULE,
+            FUNCTION
+        ));
+    };
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if !store.users.contains_key(&moderator) {
+            return log_and_return(not_found_error("User", &moderator.to_string(), MODULE, FUNCTION));
+        }
+        store.tag_moderators.entry(tag).or_default().insert(moderator);
+        Ok(())
+    })
+}
+
+pub fn remove_tag_moderator(tag: String, moderator: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "remove_tag_moderator";
+
+    invalidate(TAG_QUERIES);
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "remove_tag_moderator",
+            "Only admins can remove tag moderators",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    let tag = normalize_hashtag(&tag).unwrap_or(tag);
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let removed = store.tag_moderators.get_mut(&tag).is_some_and(|moderators| moderators.remove(&moderator));
+        if !removed {
+            return log_and_return(not_found_error("TagModerator", &moderator.to_string(), MODULE, FUNCTION));
+        }
+        if store.tag_moderators.get(&tag).is_some_and(|moderators| moderators.is_empty()) {
+            store.tag_moderators.remove(&tag);
+        }
+        Ok(())
+    })
+}
+
+// Restrict posting with a tag to some roles, such as #official to admins and moderators; an empty
+// list lifts the restriction
+pub fn set_tag_posting_roles(tag: String, roles: Vec<UserRole>, caller: Principal) -> SquareResult<TagResponse> {
+    const FUNCTION: &str = "set_tag_posting_roles";
+
+    invalidate(TAG_QUERIES);
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "set_tag_posting_roles",
+            "Only admins can restrict who posts with a tag",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    let Some(name) = normalize_hashtag(&tag) else {
+        return log_and_return(field_validation_error(
+            "tag",
+            "must contain at least one letter or digit",
+            MODULE,
+            FUNCTION
+        ));
+    };
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+
+        let tag = store.tags.entry(name.clone()).or_insert_with(|| new_tag(&name, now));
+        tag.posting_roles.clear();
+        for role in roles {
+            if !tag.posting_roles.contains(&role) {
+                tag.posting_roles.push(role);
+            }
+        }
+        tag.updated_at = now;
+        tag.updated_by = Some(caller);
+
+        let post_count = store.posts.values().filter(|post| post.hashtags.contains(&name)).count() as u64;
+        Ok(tag_response(&store, &name, post_count))
+    })
+}
+
+// Posting with a restricted tag requires one of its roles. The admin is exempt, and the tag's
+// moderators are too unless the tag is reserved for admins.
+pub fn check_tag_roles(author: Principal, hashtags: &[String], operation: &str) -> SquareResult<()> {
+    const FUNCTION: &str = "check_tag_roles";
+
+    if is_admin().is_ok() {
+        return Ok(());
+    }
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let role = store.users.get(&author).map(|user| user.role.clone()).unwrap_or_default();
+        for tag in hashtags {
+            let Some(roles) = store.tags.get(tag).map(|tag| &tag.posting_roles).filter(|roles| !roles.is_empty()) else {
+                continue;
+            };
+            let admin_only = roles.iter().all(|role| *role == UserRole::Admin);
+            if !roles.contains(&role) && (admin_only || !is_moderator_of_tag(&store, author, tag)) {
+                let allowed: Vec<String> = roles.iter().map(|role| format!("{:?}", role)).collect();
+                return log_and_return(permission_denied_error(
+                    operation,
+                    &format!("Posting with {} is restricted to these roles: {}", tag, allowed.join(", ")),
+                    MODULE,
+                    FUNCTION
+                ));
+            }
+        }
+        Ok(())
+    })
+}
+
+pub fn get_my_moderated_tags(caller: Principal) -> SquareResult<Vec<String>> {
+    Ok(STORAGE.with(|storage| moderated_tags(&storage.borrow(), caller)))
+}
@@ -1,17 +1,45 @@
 use ic_cdk::api::time;
 use candid::Principal;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet, BTreeMap};
 
 use crate::models::discovery::*;
+use crate::models::content::ContentStatus;
 use crate::models::error::SquareResult;
 use crate::models::tag::TagType;
 use crate::storage::STORAGE;
 use std::borrow::{Borrow, BorrowMut};
 use crate::utils::error_handler::*;
+use crate::utils::content_utils::{normalize_hashtag, normalize_hashtags};
+use crate::auth::{is_admin, is_manager_or_admin};
+use super::tags::{new_tag, tag_response};
+use crate::models::account_quality::engagement_weight;
+use crate::services::user::account_quality;
+use crate::services::query_cache::{cached, invalidate, CachedQuery, POST_QUERIES, TOPIC_QUERIES};
+use crate::services::interaction::likers;
+use crate::services::events::{log_event, record_event};
+use crate::models::event::InteractionEventKind;
+use crate::models::storage::Storage;
+use crate::utils::instruction_budget::approaching_instruction_limit;
+use super::spotlight::newcomer_multiplier;
 
 // Constants
 const ONE_DAY: u64 = 24 * 60 * 60 * 1000; // 24 hours in milliseconds
 
+// Trending scores of Active posts, with the newcomer boost, as of the last heartbeat that computed them
+#[derive(Default)]
+struct TrendingScores {
+    scores: HashMap<String, f64>,
+    // Post ids, highest score first
+    ranked: Vec<String>,
+    computed_at: Option<u64>,
+}
+
+thread_local! {
+    // Heap-only; empty until the first heartbeat after an upgrade
+    static TRENDING_SCORES: RefCell<TrendingScores> = RefCell::new(TrendingScores::default());
+}
+
 // Define TrendingTopics struct
 #[derive(candid::CandidType, candid::Deserialize, Clone, Default)]
 pub struct TrendingTopics {
@@ -37,40 +65,126 @@ pub fn init_trending_topics() {
 }
 
 // Helper function to calculate trending score for content
-fn calculate_trending_score(content_id: &str) -> f64 {
+pub(crate) fn calculate_trending_score(content_id: &str) -> f64 {
     // Calculate a trending score based on likes, comments, and recency
     let now = time() / 1_000_000; // Convert nanoseconds to milliseconds
     
-    // Get likes count from main storage
-    let likes_count = STORAGE.with(|storage| {
-        let store = storage.borrow();
-        store.likes.get(content_id).map_or(0, |likes| likes.len() as u64)
-    });
-    
-    // Get comments count from main storage
-    let comments_count = STORAGE.with(|storage| {
+    // Distinct likers and commenters, so repeated engagement from one principal counts once.
+    // The author's own engagement is ignored.
+    let (creation_time, likers, commenters) = STORAGE.with(|storage| {
         let store = storage.borrow();
-        store.comments.get(content_id).map_or(0, |comments| comments.child_comments.len() as u64)
+        let post = store.posts.get(content_id);
+        let author = post.map(|post| post.author);
+        let creation_time = post.map_or(now, |post| post.created_at);
+        
+        let likers: HashSet<Principal> = likers(&store, content_id)
+            .into_iter()
+            .filter(|liker| Some(*liker) != author)
+            .collect();
+        let commenters: HashSet<Principal> = store.post_comments
+            .get(content_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|comment_id| store.comments.get(comment_id))
+            .filter(|comment| comment.status == ContentStatus::Active && Some(comment.author) != author)
+            .map(|comment| comment.author)
+            .collect();
+        (creation_time, likers, commenters)
     });
     
-    // Get content creation time from main storage
-    let creation_time = STORAGE.with(|storage| {
-        let store = storage.borrow();
-        store.posts.get(content_id).map_or(now, |post| post.created_at)
-    });
+    // Weight each principal's engagement by their account quality, so low-quality accounts count for less
+    let mut weights: HashMap<Principal, f64> = HashMap::new();
+    let mut weight_of = |principal: Principal| {
+        *weights.entry(principal).or_insert_with(|| engagement_weight(account_quality(principal).tier))
+    };
+    let weighted_likes: f64 = likers.into_iter().map(&mut weight_of).sum();
+    let weighted_comments: f64 = commenters.into_iter().map(&mut weight_of).sum();
     
     // Calculate time decay factor (1 day half-life)
-    let time_diff = now - creation_time;
+    let time_diff = now.saturating_sub(creation_time);
     let time_decay = 1.0 / (1.0 + (time_diff as f64 / ONE_DAY as f64));
     
     // Calculate engagement score
-    let engagement_score = (likes_count * 2 + comments_count * 3) as f64;
+    let engagement_score = weighted_likes * 2.0 + weighted_comments * 3.0;
     
     // Final trending score
     engagement_score * time_decay
 }
 
+// Score Active posts for trending order once the last scores are older than TRENDING_SCORES_TTL_MS;
+// runs from the heartbeat so queries read the scores instead of computing them. Newest posts are
+// scored first, and posts the instruction budget does not reach keep their previous score.
+pub fn refresh_trending_scores() {
+    let now = time() / 1_000_000;
+    let fresh = TRENDING_SCORES.with(|cache| cache.borrow().computed_at.is_some_and(|at| at + TRENDING_SCORES_TTL_MS > now));
+    if fresh {
+        return;
+    }
+
+    let mut posts: Vec<(u64, String, Principal)> = STORAGE.with(|storage| {
+        storage.borrow()
+            .posts
+            .values()
+            .filter(|post| post.status == ContentStatus::Active)
+            .map(|post| (post.created_at, post.id.clone(), post.author))
+            .collect()
+    });
+    posts.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut scores = HashMap::with_capacity(posts.len());
+    for (_, post_id, author) in &posts {
+        if approaching_instruction_limit() {
+            break;
+        }
+        scores.insert(post_id.clone(), calculate_trending_score(post_id) * newcomer_multiplier(*author));
+    }
+
+    TRENDING_SCORES.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        for (_, post_id, _) in &posts {
+            if !scores.contains_key(post_id) {
+                let previous = cache.scores.get(post_id).copied().unwrap_or(0.0);
+                scores.insert(post_id.clone(), previous);
+            }
+        }
+        let mut ranked: Vec<(&String, &f64)> = scores.iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        cache.ranked = ranked.into_iter().map(|(post_id, _)| post_id.clone()).collect();
+        cache.scores = scores;
+        cache.computed_at = Some(now);
+    });
+}
+
+// The heartbeat's trending score of each listed post, 0 for posts it has not scored yet; None until
+// the heartbeat has computed any scores
+pub(crate) fn cached_trending_scores<'a>(post_ids: impl IntoIterator<Item = &'a str>) -> Option<HashMap<String, f64>> {
+    TRENDING_SCORES.with(|cache| {
+        let cache = cache.borrow();
+        cache.computed_at?;
+        Some(post_ids
+            .into_iter()
+            .map(|post_id| (post_id.to_string(), cache.scores.get(post_id).copied().unwrap_or(0.0)))
+            .collect())
+    })
+}
+
+// Post ids by the heartbeat's trending scores, highest first, that `keep` accepts, up to `limit`;
+// None until the heartbeat has computed any scores
+pub(crate) fn ranked_trending_posts(limit: usize, mut keep: impl FnMut(&str) -> bool) -> Option<Vec<String>> {
+    TRENDING_SCORES.with(|cache| {
+        let cache = cache.borrow();
+        cache.computed_at?;
+        Some(cache.ranked.iter().filter(|post_id| keep(post_id)).take(limit).cloned().collect())
+    })
+}
+
 pub fn get_trending_topics(request: GetTrendingTopicsRequest) -> SquareResult<Vec<TrendingTopicResponse>> {
+    let limit = request.limit.unwrap_or(10) as usize;
+    let variant = request.time_range_hours.unwrap_or(0) as u64;
+    cached(CachedQuery::TrendingTopics, limit, variant, || load_trending_topics(request))
+}
+
+fn load_trending_topics(request: GetTrendingTopicsRequest) -> SquareResult<Vec<TrendingTopicResponse>> {
     const MODULE: &str = "services::discovery::trending";
     const FUNCTION: &str = "get_trending_topics";
     
@@ -108,6 +222,14 @@ pub fn get_trending_topics(request: GetTrendingTopicsRequest) -> SquareResult<Ve
 }
 
 pub fn get_hot_tags(request: GetHotTagsRequest) -> SquareResult<HotTagsResponse> {
+    if request.tag_type.is_some() {
+        return load_hot_tags(request);
+    }
+    let limit = request.limit.unwrap_or(10) as usize;
+    cached(CachedQuery::HotTags, limit, 0, || load_hot_tags(request))
+}
+
+fn load_hot_tags(request: GetHotTagsRequest) -> SquareResult<HotTagsResponse> {
     const MODULE: &str = "services::discovery::trending";
     const FUNCTION: &str = "get_hot_tags";
     
@@ -123,7 +245,6 @@ pub fn get_hot_tags(request: GetHotTagsRequest) -> SquareResult<HotTagsResponse>
                 acc
             })
             .into_iter()
-            .map(|(tag, count)| (tag, count))
             .collect();
         
         // Sort by count in descending order
@@ -133,11 +254,7 @@ pub fn get_hot_tags(request: GetHotTagsRequest) -> SquareResult<HotTagsResponse>
         tags.truncate(limit as usize);
         
         tags.into_iter()
-            .map(|(tag, count)| HotTagInfo {
-                name: tag,
-                count: count as u64,
-                tag_type: TagType::Topic
-            })
+            .map(|(tag, count)| tag_response(&store, &tag, count))
             .collect()
     });
     
@@ -148,18 +265,188 @@ pub fn get_hot_tags(request: GetHotTagsRequest) -> SquareResult<HotTagsResponse>
 }
 
 pub fn update_trending_content(topics: Vec<String>) -> SquareResult<()> {
-    const MODULE: &str = "services::discovery::trending";
-    const FUNCTION: &str = "update_trending_content";
+    if !topics.is_empty() {
+        record_event(ic_cdk::id(), InteractionEventKind::TopicsMentioned { topics });
+    }
+    Ok(())
+}
+
+// Count one mention of each topic; shared by the live trending consumer and event replays
+pub(crate) fn count_topic_mentions(counts: &mut BTreeMap<String, u64>, topics: &[String]) {
+    for topic in normalize_hashtags(topics.to_vec()) {
+        *counts.entry(topic).or_insert(0) += 1;
+    }
+    bound_topic_counts(counts);
+}
+
+// Take back the mentions of content that no longer counts
+pub(crate) fn uncount_topic_mentions(counts: &mut BTreeMap<String, u64>, topics: &[String]) {
+    for topic in normalize_hashtags(topics.to_vec()) {
+        if let Some(count) = counts.get_mut(&topic) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                counts.remove(&topic);
+            }
+        }
+    }
+}
+
+// Keep only the highest-count topics once a map grows past its cap; returns how many were dropped
+fn bound_topic_counts(counts: &mut BTreeMap<String, u64>) -> u64 {
+    if counts.len() <= MAX_TRENDING_TOPICS {
+        return 0;
+    }
+    let mut ranked: Vec<(String, u64)> = std::mem::take(counts).into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let dropped = ranked.split_off(MAX_TRENDING_TOPICS).len() as u64;
+    counts.extend(ranked);
+    dropped
+}
+
+// Roll the current period into `previous`, decay current counts and drop topics below the floor;
+// returns how many topics were dropped
+pub(crate) fn decay_topic_counts(current: &mut BTreeMap<String, u64>, previous: &mut BTreeMap<String, u64>) -> u64 {
+    let mut pruned = 0;
     
+    *previous = current.clone();
+    previous.retain(|_, count| *count >= TRENDING_TOPIC_FLOOR_COUNT);
+    pruned += bound_topic_counts(previous);
+    
+    current.retain(|_, count| {
+        *count = *count * TRENDING_DECAY_PERCENT / 100;
+        let keep = *count >= TRENDING_TOPIC_FLOOR_COUNT;
+        if !keep {
+            pruned += 1;
+        }
+        keep
+    });
+    pruned += bound_topic_counts(current);
+    pruned
+}
+
+pub(crate) fn merge_topic_counts(counts: &mut BTreeMap<String, u64>, from: &str, into: &str) {
+    if let Some(count) = counts.remove(from) {
+        *counts.entry(into.to_string()).or_insert(0) += count;
+    }
+}
+
+// Called from the live trending consumer after a decay
+pub(crate) fn record_trending_maintenance(store: &mut Storage, pruned: u64, now: u64) {
+    let trending_topics_count = store.trending_topics.len() as u64;
+    let previous_trending_topics_count = store.previous_trending_topics.len() as u64;
+    let stats = store.trending_maintenance.get_or_insert_with(TrendingMaintenanceStats::default);
+    stats.last_run_at = now;
+    stats.last_pruned_count = pruned;
+    stats.total_pruned_count += pruned;
+    stats.runs += 1;
+    stats.trending_topics_count = trending_topics_count;
+    stats.previous_trending_topics_count = previous_trending_topics_count;
+}
+
+// Decays trending counts at most once a day, from the heartbeat
+pub fn decay_trending_topics() {
+    let now = time() / 1_000_000;
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
-        
-        // Update trending topics
-        for topic in topics {
-            let count = store.trending_topics.entry(topic).or_insert(0);
-            *count += 1;
+        let last_update = store.last_trending_update.unwrap_or(0);
+        if now.saturating_sub(last_update) < ONE_DAY {
+            return;
         }
+        store.last_trending_update = Some(now);
+        log_event(&mut store, ic_cdk::id(), InteractionEventKind::TrendingDecayed);
     });
+}
+
+pub fn get_trending_maintenance_stats() -> SquareResult<TrendingMaintenanceStats> {
+    const MODULE: &str = "services::discovery::trending";
+    const FUNCTION: &str = "get_trending_maintenance_stats";
     
-    Ok(())
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_trending_maintenance_stats",
+            "Only managers or admins can view trending maintenance stats",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    
+    Ok(STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let mut stats = store.trending_maintenance.clone().unwrap_or_default();
+        stats.trending_topics_count = store.trending_topics.len() as u64;
+        stats.previous_trending_topics_count = store.previous_trending_topics.len() as u64;
+        stats
+    }))
+}
+
+// Merge one hashtag into another across posts and trending counts; returns the number of posts updated
+pub fn merge_tags(from: String, to: String) -> SquareResult<u64> {
+    const MODULE: &str = "services::discovery::trending";
+    const FUNCTION: &str = "merge_tags";
+    
+    invalidate(POST_QUERIES);
+    invalidate(TOPIC_QUERIES);
+    
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "merge_tags",
+            "Only admins can merge tags",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    
+    let (Some(from), Some(to)) = (normalize_hashtag(&from), normalize_hashtag(&to)) else {
+        return log_and_return(validation_error(
+            "Both tags must contain at least one letter or digit",
+            MODULE,
+            FUNCTION
+        ));
+    };
+    if from == to {
+        return log_and_return(validation_error(
+            "Cannot merge a tag into itself",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        
+        let mut posts_updated = 0;
+        for mut post in store.posts.values_mut() {
+            if !post.hashtags.contains(&from) {
+                continue;
+            }
+            let hashtags: Vec<String> = post.hashtags
+                .iter()
+                .map(|tag| if *tag == from { to.clone() } else { tag.clone() })
+                .collect();
+            post.hashtags = normalize_hashtags(hashtags);
+            posts_updated += 1;
+        }
+        
+        let store = &mut *store;
+        log_event(store, ic_cdk::caller(), InteractionEventKind::TopicsMerged { from: from.clone(), into: to.clone() });
+        
+        // Move followers over, counting users who already followed both tags once
+        let mut new_followers = 0;
+        for mut profile in store.user_profiles.iter_mut().flat_map(|profiles| profiles.values_mut()) {
+            if profile.followed_topics.remove(&from) && profile.followed_topics.insert(to.clone()) {
+                new_followers += 1;
+            }
+        }
+        let from_tag = store.tags.remove(&from);
+        let tag = store.tags.entry(to.clone()).or_insert_with(|| {
+            // Keep the merged tag's metadata when the target has none yet
+            let mut tag = from_tag.unwrap_or_else(|| new_tag(&to, time() / 1_000_000));
+            tag.name = to.clone();
+            tag.follower_count = 0;
+            tag
+        });
+        tag.follower_count += new_followers;
+        
+        Ok(posts_updated)
+    })
 }
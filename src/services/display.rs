@@ -27,6 +27,7 @@ pub fn get_dashboard_feed(principal: Principal, pagination: crate::models::conte
         pagination: pagination.clone(),
         sort_by: Some(crate::models::discovery::SortOption::Trending),
         filter: None,
+        exclude_seen: None,
     }).map_err(|e| {
         e
     })?;
@@ -53,6 +54,7 @@ pub fn get_dashboard_feed(principal: Principal, pagination: crate::models::conte
                 created_before: None,
                 author: Some(followed_principals[0]), // Filter by the first followed user as an example
             }),
+            exclude_seen: None,
         }).map_err(|e| {
             e
         })?
@@ -63,6 +65,8 @@ pub fn get_dashboard_feed(principal: Principal, pagination: crate::models::conte
             has_more: false,
             next_offset: 0,
             total: 0,
+            degraded: false,
+            truncated: false,
         }
     };
     
@@ -76,7 +80,8 @@ pub fn get_dashboard_feed(principal: Principal, pagination: crate::models::conte
             include_trending: Some(true),
             include_similar_to_liked: Some(true),
             diversity_factor: Some(0.5),
-            recency_weight: Some(0.7)
+            recency_weight: Some(0.7),
+            exclude_seen: None,
         }
     )?;
     
@@ -0,0 +1,249 @@
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::auth::is_admin;
+use crate::models::account_quality::RateLimitedAction;
+use crate::models::content::{CommentResponse, ContentStatus, ContentType, PostResponse};
+use crate::models::emoji::*;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::interaction::InteractionResponse;
+use crate::models::storage::Storage;
+use crate::services::user::check_action_rate_limit;
+use crate::storage::STORAGE;
+use crate::utils::content_utils::extract_emoji_shortcodes;
+use crate::utils::error_handler::*;
+
+const MODULE: &str = "services::emoji";
+
+// Shortcodes are stored lowercase and without the surrounding colons
+fn normalize_shortcode(shortcode: &str) -> String {
+    shortcode.trim().trim_matches(':').to_lowercase()
+}
+
+fn standard_emoji(shortcode: &str) -> Option<&'static str> {
+    STANDARD_EMOJIS.iter().find(|(name, _)| *name == shortcode).map(|(_, emoji)| *emoji)
+}
+
+pub fn resolve_emoji(store: &Storage, shortcode: &str) -> Option<EmojiMetadata> {
+    if let Some(emoji) = standard_emoji(shortcode) {
+        return Some(EmojiMetadata { shortcode: shortcode.to_string(), unicode: Some(emoji.to_string()), image_url: None });
+    }
+    store.custom_emojis.get(shortcode).map(|custom| EmojiMetadata {
+        shortcode: shortcode.to_string(),
+        unicode: None,
+        image_url: Some(custom.image_url.clone()),
+    })
+}
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage custom emojis",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+pub fn list_custom_emojis() -> SquareResult<Vec<CustomEmoji>> {
+    Ok(STORAGE.with(|storage| storage.borrow().custom_emojis.values().cloned().collect()))
+}
+
+pub fn add_custom_emoji(request: AddCustomEmojiRequest, caller: Principal) -> SquareResult<CustomEmoji> {
+    const FUNCTION: &str = "add_custom_emoji";
+
+    require_admin("add_custom_emoji", FUNCTION)?;
+    let shortcode = normalize_shortcode(&request.shortcode);
+    if shortcode.is_empty()
+        || shortcode.len() > MAX_EMOJI_SHORTCODE_LENGTH
+        || !shortcode.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return log_and_return(field_validation_error(
+            "shortcode",
+            &format!("must be 1 to {} letters, digits, '_' or '-'", MAX_EMOJI_SHORTCODE_LENGTH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if standard_emoji(&shortcode).is_some() {
+        return log_and_return(already_exists_error("Emoji", &shortcode, MODULE, FUNCTION));
+    }
+    if !request.image_url.starts_with("https://") || request.image_url.len() > MAX_EMOJI_IMAGE_URL_LENGTH {
+        return log_and_return(field_validation_error(
+            "image_url",
+            &format!("must be an https:// URL of at most {} characters", MAX_EMOJI_IMAGE_URL_LENGTH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    let category = request.category.map(|category| category.trim().to_string()).filter(|category| !category.is_empty());
+    if category.as_ref().is_some_and(|category| category.chars().count() > MAX_EMOJI_CATEGORY_LENGTH) {
+        return log_and_return(field_validation_error(
+            "category",
+            &format!("must be at most {} characters", MAX_EMOJI_CATEGORY_LENGTH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        // Re-adding a shortcode replaces its image
+        if !store.custom_emojis.contains_key(&shortcode) && store.custom_emojis.len() >= MAX_CUSTOM_EMOJIS {
+            return log_and_return(quota_exceeded_error("custom_emojis", MAX_CUSTOM_EMOJIS as u64, MODULE, FUNCTION));
+        }
+        let emoji = CustomEmoji {
+            shortcode: shortcode.clone(),
+            image_url: request.image_url,
+            category,
+            added_by: caller,
+            added_at: time() / 1_000_000,
+        };
+        store.custom_emojis.insert(shortcode, emoji.clone());
+        Ok(emoji)
+    })
+}
+
+// Removing an emoji also drops the reactions made with it
+pub fn remove_custom_emoji(shortcode: String) -> SquareResult<()> {
+    const FUNCTION: &str = "remove_custom_emoji";
+
+    require_admin("remove_custom_emoji", FUNCTION)?;
+    let shortcode = normalize_shortcode(&shortcode);
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.custom_emojis.remove(&shortcode).is_none() {
+            return log_and_return(not_found_error("Emoji", &shortcode, MODULE, FUNCTION));
+        }
+        store.reactions.retain(|_, reactions| {
+            reactions.remove(&shortcode);
+            !reactions.is_empty()
+        });
+        Ok(())
+    })
+}
+
+// Toggle the caller's reaction with one emoji on a post or comment
+pub fn react_to_content(request: ReactToContentRequest, caller: Principal) -> SquareResult<InteractionResponse> {
+    const FUNCTION: &str = "react_to_content";
+
+    let shortcode = normalize_shortcode(&request.shortcode);
+
+    let already_reacted = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let status = match request.content_type {
+            ContentType::Post => store.posts.get(&request.content_id).map(|post| post.status.clone()),
+            ContentType::Comment => store.comments.get(&request.content_id).map(|comment| comment.status.clone()),
+        };
+        match status {
+            None => return log_and_return(not_found_error("Content", &request.content_id, MODULE, FUNCTION)),
+            Some(ContentStatus::Active) => {}
+            Some(_) => {
+                return log_and_return(invalid_operation_error(
+                    "react_to_content",
+                    "Cannot react to inactive content",
+                    MODULE,
+                    FUNCTION
+                ));
+            }
+        }
+        if resolve_emoji(&store, &shortcode).is_none() {
+            return log_and_return(not_found_error("Emoji", &shortcode, MODULE, FUNCTION));
+        }
+        Ok(store.reactions
+            .get(&request.content_id)
+            .and_then(|reactions| reactions.get(&shortcode))
+            .is_some_and(|users| users.contains(&caller)))
+    })?;
+
+    if !already_reacted {
+        check_action_rate_limit(caller, RateLimitedAction::Like)?;
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if already_reacted {
+            if let Some(reactions) = store.reactions.get_mut(&request.content_id) {
+                if let Some(users) = reactions.get_mut(&shortcode) {
+                    users.remove(&caller);
+                    if users.is_empty() {
+                        reactions.remove(&shortcode);
+                    }
+                }
+                if reactions.is_empty() {
+                    store.reactions.remove(&request.content_id);
+                }
+            }
+            return Ok(InteractionResponse {
+                success: true,
+                message: "Reaction removed".to_string(),
+            });
+        }
+
+        let reactions = store.reactions.entry(request.content_id.clone()).or_default();
+        if reactions.values().filter(|users| users.contains(&caller)).count() >= MAX_REACTIONS_PER_USER {
+            return log_and_return(quota_exceeded_error("reactions", MAX_REACTIONS_PER_USER as u64, MODULE, FUNCTION));
+        }
+        reactions.entry(shortcode).or_default().insert(caller);
+        Ok(InteractionResponse {
+            success: true,
+            message: "Reaction added".to_string(),
+        })
+    })
+}
+
+// Reaction counts for the caller, plus metadata for every shortcode in `content` or the reactions
+fn emoji_details(store: &Storage, content_id: &str, content: &str, caller: Principal) -> (Vec<ReactionCount>, Vec<EmojiMetadata>) {
+    let reactions: Vec<ReactionCount> = store.reactions
+        .get(content_id)
+        .map(|reactions| {
+            reactions
+                .iter()
+                .map(|(shortcode, users)| ReactionCount {
+                    shortcode: shortcode.clone(),
+                    count: users.len() as u64,
+                    reacted_by_caller: users.contains(&caller),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut shortcodes = extract_emoji_shortcodes(content);
+    for reaction in &reactions {
+        if !shortcodes.contains(&reaction.shortcode) {
+            shortcodes.push(reaction.shortcode.clone());
+        }
+    }
+    let emojis = shortcodes.iter().filter_map(|shortcode| resolve_emoji(store, shortcode)).collect();
+    (reactions, emojis)
+}
+
+// Withheld gated content is empty, so only its reactions are resolved
+pub fn attach_post_emojis(posts: &mut [PostResponse]) {
+    let caller = ic_cdk::caller();
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        for post in posts.iter_mut() {
+            (post.reactions, post.emojis) = emoji_details(&store, &post.id, &post.content, caller);
+        }
+    });
+}
+
+fn attach_emojis_to_comment(store: &Storage, comment: &mut CommentResponse, caller: Principal) {
+    (comment.reactions, comment.emojis) = emoji_details(store, &comment.id, &comment.content, caller);
+    for child in comment.child_comments.iter_mut() {
+        attach_emojis_to_comment(store, child, caller);
+    }
+}
+
+pub fn attach_comment_emojis(comments: &mut [CommentResponse]) {
+    let caller = ic_cdk::caller();
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        for comment in comments.iter_mut() {
+            attach_emojis_to_comment(&store, comment, caller);
+        }
+    });
+}
@@ -0,0 +1,326 @@
+use candid::Principal;
+use ic_cdk::api::time;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::auth::is_admin;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::event::*;
+use crate::models::storage::Storage;
+use crate::models::user::UserStats;
+use crate::services::discovery::trending::{
+    count_topic_mentions, decay_topic_counts, merge_topic_counts, record_trending_maintenance, uncount_topic_mentions,
+};
+use crate::services::query_cache::{invalidate, TOPIC_QUERIES};
+use crate::services::user::profile::record_activity;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::local_day_start;
+
+const MODULE: &str = "services::events";
+
+// Interactions append events here instead of updating counters in place. Each consumer keeps a
+// cursor into the log and folds new events into its derived state, so a consumer can be rebuilt
+// from the checkpoint and the retained events after a bug in its fold is fixed.
+
+pub(crate) fn stats_entry(stats: &mut HashMap<Principal, UserStats>, principal: Principal) -> &mut UserStats {
+    stats.entry(principal).or_insert_with(|| UserStats {
+        principal,
+        post_count: 0,
+        comment_count: 0,
+        like_count: 0,
+        points: 0,
+        reputation: 0,
+    })
+}
+
+// How an event moves the counters of the user it credits
+struct StatsDelta {
+    user: Principal,
+    posts: i64,
+    comments: i64,
+    likes: i64,
+    reputation: i64,
+}
+
+impl StatsDelta {
+    fn apply(&self, counters: &mut StatsCounters) {
+        counters.post_count = counters.post_count.saturating_add_signed(self.posts);
+        counters.comment_count = counters.comment_count.saturating_add_signed(self.comments);
+        counters.like_count = counters.like_count.saturating_add_signed(self.likes);
+        counters.reputation = counters.reputation.saturating_add_signed(self.reputation);
+    }
+}
+
+fn user_stats_delta(event: &InteractionEvent) -> Option<StatsDelta> {
+    let delta = |user, posts, comments, likes, reputation| Some(StatsDelta { user, posts, comments, likes, reputation });
+    match &event.kind {
+        InteractionEventKind::PostCreated { author, .. } => delta(*author, 1, 0, 0, 0),
+        InteractionEventKind::PostDeleted { author, likes, .. } => delta(*author, -1, 0, -(*likes as i64), 0),
+        InteractionEventKind::PostRestored { author, likes, .. } => delta(*author, 1, 0, *likes as i64, 0),
+        InteractionEventKind::CommentCreated { author, .. } => delta(*author, 0, 1, 0, 0),
+        InteractionEventKind::CommentDeleted { author, likes, .. } => delta(*author, 0, -1, -(*likes as i64), 0),
+        InteractionEventKind::CommentRestored { author, likes, .. } => delta(*author, 0, 1, *likes as i64, 0),
+        InteractionEventKind::Liked { author: Some(author), .. } if *author != event.actor => delta(*author, 0, 0, 1, 0),
+        InteractionEventKind::Unliked { author: Some(author), .. } if *author != event.actor => delta(*author, 0, 0, -1, 0),
+        InteractionEventKind::Viewed { author, .. } if *author != event.actor => delta(*author, 0, 0, 0, 1),
+        _ => None,
+    }
+}
+
+fn apply_user_stats(stats: &mut HashMap<Principal, UserStats>, event: &InteractionEvent) {
+    let Some(delta) = user_stats_delta(event) else {
+        return;
+    };
+    let entry = stats_entry(stats, delta.user);
+    let mut counters = StatsCounters::from(&*entry);
+    delta.apply(&mut counters);
+    counters.write_to(entry);
+}
+
+// Change a user's checkpoint counters, dropping them once they are all zero
+pub(crate) fn update_checkpoint_counters(checkpoint: &mut EventCheckpoint, user: Principal, change: impl FnOnce(&mut StatsCounters)) {
+    let counters = checkpoint.user_counters.entry(user).or_default();
+    change(counters);
+    if *counters == StatsCounters::default() {
+        checkpoint.user_counters.remove(&user);
+    }
+}
+
+// Returns None for events that do not touch trending, otherwise the number of topics pruned
+fn apply_trending(current: &mut BTreeMap<String, u64>, previous: &mut BTreeMap<String, u64>, event: &InteractionEvent) -> Option<u64> {
+    match &event.kind {
+        InteractionEventKind::PostCreated { hashtags, .. } | InteractionEventKind::PostRestored { hashtags, .. } => {
+            count_topic_mentions(current, hashtags)
+        }
+        InteractionEventKind::PostDeleted { hashtags, .. } => uncount_topic_mentions(current, hashtags),
+        InteractionEventKind::TopicsMentioned { topics } => count_topic_mentions(current, topics),
+        InteractionEventKind::TopicsMerged { from, into } => {
+            merge_topic_counts(current, from, into);
+            merge_topic_counts(previous, from, into);
+        }
+        InteractionEventKind::TrendingDecayed => return Some(decay_topic_counts(current, previous)),
+        _ => return None,
+    }
+    Some(0)
+}
+
+// Engagement with the user's own content, which earns nothing
+fn self_engagement(event: &InteractionEvent) -> bool {
+    match &event.kind {
+        InteractionEventKind::Liked { author, .. } | InteractionEventKind::Unliked { author, .. } => *author == Some(event.actor),
+        InteractionEventKind::Viewed { author, .. } => *author == event.actor,
+        InteractionEventKind::Followed { followed } | InteractionEventKind::Unfollowed { followed } => *followed == event.actor,
+        InteractionEventKind::CommentCreated { excluded, .. } => *excluded,
+        _ => false,
+    }
+}
+
+// Counts are kept for the actor's current local day and start over on the first event of a new day.
+// Undoing a like or follow takes it back off the day's count. Self-engagement is not counted.
+fn apply_task_progress(progress: &mut HashMap<Principal, DailyTaskProgress>, timezone_offset: i32, event: &InteractionEvent) {
+    if self_engagement(event) {
+        return;
+    }
+    let change: fn(&mut DailyTaskProgress) = match &event.kind {
+        InteractionEventKind::PostCreated { .. } => |progress| progress.posts += 1,
+        InteractionEventKind::CommentCreated { .. } => |progress| progress.comments += 1,
+        InteractionEventKind::Liked { .. } => |progress| progress.likes_given += 1,
+        InteractionEventKind::Unliked { .. } => |progress| progress.likes_given = progress.likes_given.saturating_sub(1),
+        InteractionEventKind::Followed { .. } => |progress| progress.follows += 1,
+        InteractionEventKind::Unfollowed { .. } => |progress| progress.follows = progress.follows.saturating_sub(1),
+        _ => return,
+    };
+    let day_start = local_day_start(event.timestamp, timezone_offset);
+    let entry = progress.entry(task_progress_user(event)).or_default();
+    if entry.day_start != day_start {
+        *entry = DailyTaskProgress { day_start, ..Default::default() };
+    }
+    change(entry);
+}
+
+fn task_progress_user(event: &InteractionEvent) -> Principal {
+    match &event.kind {
+        InteractionEventKind::PostCreated { author, .. } | InteractionEventKind::CommentCreated { author, .. } => *author,
+        _ => event.actor,
+    }
+}
+
+fn timezone_offset(store: &Storage, user: &Principal) -> i32 {
+    store.user_timezones.get(user).map(|timezone| timezone.offset_minutes).unwrap_or(0)
+}
+
+// The offset recorded with the event, so replays and trims count it toward the day it happened on.
+// Events logged before offsets were recorded fall back to the user's current one.
+fn event_timezone_offset(store: &Storage, event: &InteractionEvent) -> i32 {
+    event.timezone_offset.unwrap_or_else(|| timezone_offset(store, &task_progress_user(event)))
+}
+
+// Replays skip side effects that already happened, such as maintenance stats
+fn apply_live(store: &mut Storage, consumer: EventConsumer, event: &InteractionEvent, replay: bool) {
+    match consumer {
+        EventConsumer::UserStats => apply_user_stats(store.user_stats.get_or_insert_with(HashMap::new), event),
+        EventConsumer::Trending => {
+            let Some(pruned) = apply_trending(&mut store.trending_topics, &mut store.previous_trending_topics, event) else {
+                return;
+            };
+            if matches!(event.kind, InteractionEventKind::TrendingDecayed) && !replay {
+                record_trending_maintenance(store, pruned, event.timestamp);
+            }
+            invalidate(TOPIC_QUERIES);
+        }
+        EventConsumer::TaskProgress => {
+            let offset = event_timezone_offset(store, event);
+            apply_task_progress(&mut store.task_progress, offset, event);
+        }
+    }
+}
+
+fn apply_checkpoint(store: &mut Storage, event: &InteractionEvent) {
+    let offset = event_timezone_offset(store, event);
+    let checkpoint = &mut store.interaction_events.checkpoint;
+    if let Some(delta) = user_stats_delta(event) {
+        update_checkpoint_counters(checkpoint, delta.user, |counters| delta.apply(counters));
+    }
+    apply_trending(&mut checkpoint.trending_topics, &mut checkpoint.previous_trending_topics, event);
+    apply_task_progress(&mut checkpoint.task_progress, offset, event);
+    checkpoint.seq = event.seq + 1;
+}
+
+fn first_retained_seq(log: &InteractionEventLog) -> u64 {
+    log.events.first().map_or(log.next_seq, |event| event.seq)
+}
+
+// Feed every consumer the events past its cursor
+fn process_events(store: &mut Storage, replay: bool) {
+    for consumer in EVENT_CONSUMERS {
+        let log = &store.interaction_events;
+        let cursor = log.cursors.get(&consumer).copied().unwrap_or(log.checkpoint.seq);
+        let start = cursor.saturating_sub(first_retained_seq(log)) as usize;
+        let pending: Vec<InteractionEvent> = log.events.get(start..).map(<[InteractionEvent]>::to_vec).unwrap_or_default();
+        for event in &pending {
+            apply_live(store, consumer, event, replay);
+        }
+        let next_seq = store.interaction_events.next_seq;
+        store.interaction_events.cursors.insert(consumer, next_seq);
+    }
+}
+
+// Fold the oldest events into the checkpoint once the log is over its cap
+fn trim_events(store: &mut Storage) {
+    if store.interaction_events.events.len() <= MAX_RETAINED_EVENTS {
+        return;
+    }
+    let trimmed: Vec<InteractionEvent> = store.interaction_events.events.drain(..EVENT_TRIM_BATCH).collect();
+    for event in &trimmed {
+        apply_checkpoint(store, event);
+    }
+}
+
+// Append an event and let every consumer process it. For callers already holding the storage borrow.
+pub fn log_event(store: &mut Storage, actor: Principal, kind: InteractionEventKind) {
+    let mut event = InteractionEvent {
+        seq: store.interaction_events.next_seq,
+        actor,
+        timestamp: time() / 1_000_000,
+        kind,
+        timezone_offset: None,
+    };
+    event.timezone_offset = Some(timezone_offset(store, &task_progress_user(&event)));
+    let log = &mut store.interaction_events;
+    log.next_seq += 1;
+    let timestamp = event.timestamp;
+    log.events.push(event);
+    record_activity(store, actor, timestamp);
+    process_events(store, false);
+    trim_events(store);
+}
+
+pub fn record_event(actor: Principal, kind: InteractionEventKind) {
+    STORAGE.with(|storage| log_event(&mut storage.borrow_mut(), actor, kind));
+}
+
+fn event_log_status(store: &Storage) -> InteractionEventLogStatus {
+    let log = &store.interaction_events;
+    InteractionEventLogStatus {
+        next_seq: log.next_seq,
+        checkpoint_seq: log.checkpoint.seq,
+        retained_events: log.events.len() as u64,
+        consumers: EVENT_CONSUMERS
+            .iter()
+            .map(|consumer| {
+                let next_seq = log.cursors.get(consumer).copied().unwrap_or(log.checkpoint.seq);
+                EventConsumerStatus {
+                    consumer: *consumer,
+                    next_seq,
+                    pending_events: log.next_seq - next_seq,
+                }
+            })
+            .collect(),
+    }
+}
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage the interaction event log",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+pub fn get_event_log_status() -> SquareResult<InteractionEventLogStatus> {
+    const FUNCTION: &str = "get_event_log_status";
+
+    require_admin("get_event_log_status", FUNCTION)?;
+    Ok(STORAGE.with(|storage| event_log_status(&storage.borrow())))
+}
+
+// Reset a consumer's derived state to the checkpoint and replay the retained events through it
+pub(crate) fn replay_consumer(store: &mut Storage, consumer: EventConsumer) {
+    let checkpoint = &store.interaction_events.checkpoint;
+    match consumer {
+        EventConsumer::UserStats => {
+            // Points are not event-driven and are kept
+            for (principal, stats) in store.user_stats.iter_mut().flatten() {
+                checkpoint.user_counters.get(principal).cloned().unwrap_or_default().write_to(stats);
+            }
+        }
+        EventConsumer::Trending => {
+            store.trending_topics = checkpoint.trending_topics.clone();
+            store.previous_trending_topics = checkpoint.previous_trending_topics.clone();
+        }
+        EventConsumer::TaskProgress => store.task_progress = checkpoint.task_progress.clone(),
+    }
+    let checkpoint_seq = checkpoint.seq;
+    store.interaction_events.cursors.insert(consumer, checkpoint_seq);
+    process_events(store, true);
+    crate::utils::logger::log(&format!("Rebuilt {:?} from event {}", consumer, checkpoint_seq));
+}
+
+pub fn rebuild_derived_state(consumer: EventConsumer) -> SquareResult<InteractionEventLogStatus> {
+    const FUNCTION: &str = "rebuild_derived_state";
+
+    require_admin("rebuild_derived_state", FUNCTION)?;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        replay_consumer(&mut store, consumer);
+        Ok(event_log_status(&store))
+    })
+}
+
+// Today's progress for the caller; zero when they have done nothing yet today
+pub fn get_task_progress(caller: Principal) -> SquareResult<DailyTaskProgress> {
+    let now = time() / 1_000_000;
+    Ok(STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let day_start = local_day_start(now, timezone_offset(&store, &caller));
+        store.task_progress
+            .get(&caller)
+            .filter(|progress| progress.day_start == day_start)
+            .cloned()
+            .unwrap_or(DailyTaskProgress { day_start, ..Default::default() })
+    }))
+}
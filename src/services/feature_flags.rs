@@ -0,0 +1,74 @@
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::auth::is_admin;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::feature_flag::*;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+
+const MODULE: &str = "services::feature_flags";
+
+pub fn is_flag_enabled(flag: FeatureFlag) -> bool {
+    STORAGE.with(|storage| storage.borrow().feature_flags.get(&flag).is_some_and(|state| state.enabled))
+}
+
+// Reject an operation while the flag switching it off is enabled
+pub fn ensure_flag_disabled(flag: FeatureFlag, operation: &str, module: &str, function: &str) -> SquareResult<()> {
+    if is_flag_enabled(flag) {
+        return log_and_return(service_unavailable_error(
+            operation,
+            &format!("Temporarily disabled by operators ({:?})", flag),
+            module,
+            function
+        ));
+    }
+    Ok(())
+}
+
+pub fn get_feature_flags() -> SquareResult<Vec<FeatureFlagResponse>> {
+    Ok(STORAGE.with(|storage| {
+        let store = storage.borrow();
+        FeatureFlag::ALL
+            .iter()
+            .map(|flag| {
+                let state = store.feature_flags.get(flag);
+                FeatureFlagResponse {
+                    flag: *flag,
+                    enabled: state.is_some_and(|state| state.enabled),
+                    updated_at: state.map(|state| state.updated_at),
+                    updated_by: state.map(|state| state.updated_by),
+                }
+            })
+            .collect()
+    }))
+}
+
+pub fn set_feature_flag(request: SetFeatureFlagRequest, caller: Principal) -> SquareResult<FeatureFlagResponse> {
+    const FUNCTION: &str = "set_feature_flag";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "set_feature_flag",
+            "Only admins can change feature flags",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        storage.borrow_mut().feature_flags.insert(request.flag, FeatureFlagState {
+            enabled: request.enabled,
+            updated_at: now,
+            updated_by: caller,
+        });
+    });
+
+    Ok(FeatureFlagResponse {
+        flag: request.flag,
+        enabled: request.enabled,
+        updated_at: Some(now),
+        updated_by: Some(caller),
+    })
+}
@@ -0,0 +1,235 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use candid::Principal;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::models::content::{ContentStatus, ContentVisibility, Post};
+use crate::models::http::*;
+use crate::models::storage::Storage;
+use crate::services::access_policy::check_anonymous_read;
+use crate::services::content::outline::article_toc;
+use crate::services::content::rendering::rendered_html;
+use crate::services::content::sanitizer::allowed_html_tags;
+use crate::storage::STORAGE;
+use crate::utils::content_utils::{anchor_headings, escape_html, sanitize_html, strip_html_tags};
+
+// Pages may show images and media but run no scripts, in case markup slipped past the sanitizer
+const CONTENT_SECURITY_POLICY: &str = "default-src 'none'; img-src 'self' https: data:; media-src 'self' https: data:; style-src 'unsafe-inline'; frame-ancestors 'none'";
+
+lazy_static! {
+    static ref DATA_URL: Regex = Regex::new(r"data:((?:image|video)/[a-zA-Z0-9.+-]+);base64,([A-Za-z0-9+/=]+)").unwrap();
+}
+
+struct Asset {
+    content_type: String,
+    body: Vec<u8>,
+}
+
+// Only content anyone may read is served over HTTP
+fn publicly_readable(post: &Post) -> bool {
+    post.status == ContentStatus::Active
+        && post.visibility == ContentVisibility::Public
+        && !post.is_premium
+        && post.token_gate.is_none()
+}
+
+// Characters of the article text used as its link preview description when it has no summary
+const PREVIEW_DESCRIPTION_LENGTH: usize = 200;
+
+// OpenGraph tags for link previews; the image is the cover image, never guessed from the media
+fn open_graph_tags(store: &Storage, post: &Post, title: &str) -> String {
+    let description = store.article_summaries
+        .get(&post.id)
+        .map(|cached| cached.summary.clone())
+        .unwrap_or_else(|| strip_html_tags(&post.content).chars().take(PREVIEW_DESCRIPTION_LENGTH).collect());
+    let mut tags = format!(
+        "<meta property=\"og:type\" content=\"article\"><meta property=\"og:title\" content=\"{}\"><meta property=\"og:description\" content=\"{}\">",
+        title, escape_html(description.trim())
+    );
+    if let Some(cover_image) = &post.cover_image {
+        tags.push_str(&format!("<meta property=\"og:image\" content=\"{}\">", escape_html(cover_image)));
+    }
+    tags
+}
+
+fn article_page(store: &Storage, post_id: &str) -> Option<Asset> {
+    let post = store.posts.get(post_id).filter(|post| publicly_readable(post))?;
+    let title = escape_html(post.title.as_deref()?);
+    // Sanitized again on the way out, since HTML posts stored before the sanitizer are served as stored.
+    // Headings carry their anchors, so links to "/articles/<id>#<anchor>" open at the section.
+    let html = sanitize_html(&rendered_html(store, &post.id, post.format, &post.content), &allowed_html_tags(store));
+    let html = anchor_headings(&html);
+    let toc = article_toc(store, &post.id);
+    let nav = if toc.is_empty() {
+        String::new()
+    } else {
+        let items: String = toc
+            .iter()
+            .map(|entry| format!(
+                "<li class=\"toc-level-{}\"><a href=\"#{}\">{}</a></li>",
+                entry.level, escape_html(&entry.anchor), escape_html(&entry.text)
+            ))
+            .collect();
+        format!("<nav><ol>{}</ol></nav>", items)
+    };
+    let cover = post.cover_image
+        .as_ref()
+        .map(|url| format!("<img src=\"{}\" alt=\"\">", escape_html(url)))
+        .unwrap_or_default();
+    let page = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title>{}</head><body><article><h1>{}</h1>{}{}{}</article></body></html>",
+        title, open_graph_tags(store, post, &title), title, cover, nav, html
+    );
+    Some(Asset {
+        content_type: "text/html; charset=utf-8".to_string(),
+        body: page.into_bytes(),
+    })
+}
+
+// The n-th inline base64 image or video of a post, counting media URLs before the content
+fn media_asset(store: &Storage, post_id: &str, index: &str) -> Option<Asset> {
+    let post = store.posts.get(post_id).filter(|post| publicly_readable(post))?;
+    let index: usize = index.parse().ok()?;
+    let captures = post.media_urls
+        .iter()
+        .chain(std::iter::once(&post.content))
+        .flat_map(|text| DATA_URL.captures_iter(text))
+        .nth(index)?;
+    // SVG can carry scripts; the sanitizer drops it, but content stored before it may still hold some
+    if captures[1].eq_ignore_ascii_case("image/svg+xml") {
+        return None;
+    }
+    Some(Asset {
+        content_type: captures[1].to_string(),
+        body: STANDARD.decode(&captures[2]).ok()?,
+    })
+}
+
+// Every publicly readable post of a user, oldest first, read through the user's post index
+fn posts_export(store: &Storage, user: &str) -> Option<Asset> {
+    let user = Principal::from_text(user).ok()?;
+    let mut posts: Vec<&Post> = store.user_posts
+        .get(&user)
+        .into_iter()
+        .flatten()
+        .filter_map(|id| store.posts.get(id))
+        .filter(|post| post.author == user && publicly_readable(post))
+        .collect();
+    posts.sort_by_key(|post| post.created_at);
+    let export: Vec<serde_json::Value> = posts
+        .iter()
+        .map(|post| serde_json::json!({
+            "id": post.id,
+            "title": post.title,
+            "content": post.content,
+            "format": format!("{:?}", post.format),
+            "media_urls": post.media_urls,
+            "cover_image": post.cover_image,
+            "thumbnail": post.thumbnail,
+            "hashtags": post.hashtags,
+            "tags": post.tags,
+            "created_at": post.created_at,
+            "updated_at": post.updated_at,
+        }))
+        .collect();
+    Some(Asset {
+        content_type: "application/json".to_string(),
+        body: serde_json::to_vec(&export).ok()?,
+    })
+}
+
+fn resolve(path: &str) -> Option<Asset> {
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        match segments.as_slice() {
+            ["articles", post_id] => article_page(&store, post_id),
+            ["media", post_id, index] => media_asset(&store, post_id, index),
+            ["exports", user, "posts.json"] => posts_export(&store, user),
+            _ => None,
+        }
+    })
+}
+
+// Bodies are rebuilt for every chunk, so each token carries a hash of the body it started on
+fn body_version(body: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn chunk(body: &[u8], index: u64) -> &[u8] {
+    let start = (index as usize).saturating_mul(HTTP_CHUNK_SIZE).min(body.len());
+    let end = start.saturating_add(HTTP_CHUNK_SIZE).min(body.len());
+    &body[start..end]
+}
+
+fn next_token(path: &str, body: &[u8], index: u64) -> Option<StreamingCallbackToken> {
+    let next = index + 1;
+    ((next as usize).saturating_mul(HTTP_CHUNK_SIZE) < body.len()).then(|| StreamingCallbackToken {
+        path: path.to_string(),
+        chunk_index: next,
+        version: body_version(body),
+    })
+}
+
+fn status_response(status_code: u16, message: &str) -> HttpGatewayResponse {
+    HttpGatewayResponse {
+        status_code,
+        headers: vec![
+            ("Content-Type".to_string(), "text/plain; charset=utf-8".to_string()),
+            ("X-Content-Type-Options".to_string(), "nosniff".to_string()),
+        ],
+        body: message.as_bytes().to_vec(),
+        streaming_strategy: None,
+    }
+}
+
+// Serves article pages, inline media and post exports. Bodies over HTTP_CHUNK_SIZE are sent as
+// their first chunk plus a callback strategy for the gateway to fetch the rest.
+pub fn http_request(request: HttpGatewayRequest) -> HttpGatewayResponse {
+    if !request.method.eq_ignore_ascii_case("GET") {
+        return status_response(405, "Method not allowed");
+    }
+    // Gateway requests arrive as anonymous calls
+    if check_anonymous_read("http_request").is_err() {
+        return status_response(403, "Forbidden");
+    }
+    let path = request.url.split(['?', '#']).next().unwrap_or_default();
+    let Some(asset) = resolve(path) else {
+        return status_response(404, "Not found");
+    };
+
+    let streaming_strategy = next_token(path, &asset.body, 0).map(|token| StreamingStrategy::Callback {
+        callback: StreamingCallback::new(ic_cdk::id(), "http_request_streaming_callback".to_string()),
+        token,
+    });
+    HttpGatewayResponse {
+        status_code: 200,
+        headers: vec![
+            ("Content-Type".to_string(), asset.content_type),
+            ("Content-Length".to_string(), asset.body.len().to_string()),
+            ("Content-Security-Policy".to_string(), CONTENT_SECURITY_POLICY.to_string()),
+            ("X-Content-Type-Options".to_string(), "nosniff".to_string()),
+        ],
+        body: chunk(&asset.body, 0).to_vec(),
+        streaming_strategy,
+    }
+}
+
+// An empty final chunk ends the stream when the resource is gone or changed since streaming began
+pub fn http_request_streaming_callback(token: StreamingCallbackToken) -> StreamingCallbackHttpResponse {
+    let allowed = check_anonymous_read("http_request").is_ok();
+    match resolve(&token.path).filter(|asset| allowed && body_version(&asset.body) == token.version) {
+        Some(asset) => StreamingCallbackHttpResponse {
+            body: chunk(&asset.body, token.chunk_index).to_vec(),
+            token: next_token(&token.path, &asset.body, token.chunk_index),
+        },
+        None => StreamingCallbackHttpResponse {
+            body: Vec::new(),
+            token: None,
+        },
+    }
+}
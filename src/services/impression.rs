@@ -0,0 +1,275 @@
+use candid::Principal;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+use crate::auth::{is_admin, is_manager_or_admin};
+use crate::models::error::SquareResult;
+use crate::models::impression::*;
+use crate::models::storage::Storage;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::{now_millis, TimestampMillis};
+
+const MODULE: &str = "services::impression";
+
+fn settings(store: &Storage) -> ImpressionSettings {
+    store.impression_settings.clone().unwrap_or_default()
+}
+
+// Hash of the principal from SHA-256, so sampling and arms stay the same across upgrades and toolchains
+fn principal_hash(principal: Principal) -> u64 {
+    let digest = Sha256::digest(principal.as_slice());
+    u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 digests are 32 bytes"))
+}
+
+// The same users stay in the sample for as long as the percentage does not change
+fn sampled(principal: Principal, settings: &ImpressionSettings) -> bool {
+    settings.enabled && principal_hash(principal) % 100 < settings.sample_percent
+}
+
+// Each user stays in the same experiment arm, independently of whether they are sampled
+pub fn ranking_arm(principal: Principal) -> RankingArm {
+    if (principal_hash(principal) / 100).is_multiple_of(2) {
+        RankingArm::Baseline
+    } else {
+        RankingArm::ClickWeighted
+    }
+}
+
+fn click_through_rate(counts: &ImpressionCounts) -> f64 {
+    if counts.impressions == 0 { 0.0 } else { counts.clicks as f64 / counts.impressions as f64 }
+}
+
+// Multiplier on a post's recommendation score for a user in the given arm: the post's click-through
+// relative to that of all posts, within CLICK_WEIGHT_RANGE. Posts with few impressions keep 1.
+pub(crate) fn click_weight(store: &Storage, arm: RankingArm, post_id: &str) -> f64 {
+    if arm != RankingArm::ClickWeighted {
+        return 1.0;
+    }
+    let Some(counts) = store.post_impression_counts.get(post_id).filter(|counts| counts.impressions >= MIN_IMPRESSIONS_FOR_CLICK_WEIGHT) else {
+        return 1.0;
+    };
+    let overall = store.impression_counts.values().fold(ImpressionCounts::default(), |total, counts| ImpressionCounts {
+        impressions: total.impressions + counts.impressions,
+        clicks: total.clicks + counts.clicks,
+    });
+    let overall_rate = click_through_rate(&overall);
+    if overall_rate == 0.0 {
+        return 1.0;
+    }
+    (click_through_rate(counts) / overall_rate).clamp(1.0 / CLICK_WEIGHT_RANGE, CLICK_WEIGHT_RANGE)
+}
+
+// Make room for a new user's log by dropping the one whose latest impression is oldest
+fn evict_idle_user(store: &mut Storage) {
+    let idle = store.impressions
+        .iter()
+        .min_by_key(|(_, impressions)| impressions.last().map_or(0, |impression| impression.served_at))
+        .map(|(principal, _)| *principal);
+    if let Some(principal) = idle {
+        store.impressions.remove(&principal);
+    }
+}
+
+// Log the posts a feed served to the caller. Clients call this with the ids they displayed, since
+// feeds are queries and cannot record anything. Every caller's ids go into their recently served
+// buffer; impressions are only logged for sampled callers. Returns how many impressions were logged,
+// which is 0 for callers outside the sample and for posts already shown on the same surface within
+// the click window.
+pub fn record_impressions(request: RecordImpressionsRequest, caller: Principal) -> SquareResult<u64> {
+    const FUNCTION: &str = "record_impressions";
+
+    if request.content_ids.len() > MAX_IMPRESSIONS_PER_CALL {
+        return log_and_return(field_validation_error(
+            "content_ids",
+            &format!("at most {} ids per call", MAX_IMPRESSIONS_PER_CALL),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let now = now_millis();
+    Ok(STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let content_ids: Vec<String> = request.content_ids
+            .into_iter()
+            .filter(|content_id| store.posts.contains_key(content_id))
+            .collect();
+        mark_served(store, caller, &content_ids, now);
+        if !sampled(caller, &settings(store)) {
+            return 0;
+        }
+        if !store.impressions.contains_key(&caller) && store.impressions.len() >= MAX_IMPRESSION_USERS {
+            evict_idle_user(store);
+        }
+
+        let log = store.impressions.entry(caller).or_default();
+        let mut recorded = 0;
+        let mut logged_posts = Vec::new();
+        for content_id in content_ids {
+            let repeated = log.iter().any(|impression| {
+                impression.content_id == content_id
+                    && impression.surface == request.surface
+                    && now.saturating_sub(impression.served_at) < IMPRESSION_CLICK_WINDOW
+            });
+            if repeated {
+                continue;
+            }
+            logged_posts.push(content_id.clone());
+            log.push(Impression {
+                content_id,
+                surface: request.surface,
+                served_at: now,
+                clicked_at: None,
+            });
+            recorded += 1;
+        }
+        if log.len() > MAX_IMPRESSIONS_PER_USER {
+            let excess = log.len() - MAX_IMPRESSIONS_PER_USER;
+            log.drain(..excess);
+        }
+        store.impression_counts.entry(request.surface).or_default().impressions += recorded;
+        store.arm_impression_counts.entry(ranking_arm(caller)).or_default().impressions += recorded;
+        for content_id in logged_posts {
+            store.post_impression_counts.entry(content_id).or_default().impressions += 1;
+        }
+        recorded
+    }))
+}
+
+// Add posts to a user's recently served buffer, moving ones already there to the end
+fn mark_served(store: &mut Storage, user: Principal, content_ids: &[String], now: TimestampMillis) {
+    if content_ids.is_empty() {
+        return;
+    }
+    let served = store.recently_served.entry(user).or_default();
+    served.retain(|post| now.saturating_sub(post.served_at) < RECENTLY_SERVED_TTL && !content_ids.contains(&post.content_id));
+    served.extend(content_ids.iter().map(|content_id| ServedPost {
+        content_id: content_id.clone(),
+        served_at: now,
+    }));
+    if served.len() > MAX_RECENTLY_SERVED {
+        let excess = served.len() - MAX_RECENTLY_SERVED;
+        served.drain(..excess);
+    }
+}
+
+// Posts served to a user within the session window, for feeds that skip them
+pub fn recently_served(user: Principal) -> HashSet<String> {
+    let now = now_millis();
+    STORAGE.with(|storage| {
+        storage.borrow()
+            .recently_served
+            .get(&user)
+            .into_iter()
+            .flatten()
+            .filter(|post| now.saturating_sub(post.served_at) < RECENTLY_SERVED_TTL)
+            .map(|post| post.content_id.clone())
+            .collect()
+    })
+}
+
+// Drop buffers whose session has ended; run on full heartbeat runs
+pub fn prune_recently_served() {
+    let now = now_millis();
+    STORAGE.with(|storage| {
+        storage.borrow_mut().recently_served.retain(|_, served| {
+            served.last().is_some_and(|post| now.saturating_sub(post.served_at) < RECENTLY_SERVED_TTL)
+        });
+    });
+}
+
+// Count a view as a click on the latest unclicked impression of the post within the click window
+pub fn record_click(store: &mut Storage, viewer: Principal, content_id: &str, now: TimestampMillis) {
+    let Some(log) = store.impressions.get_mut(&viewer) else {
+        return;
+    };
+    let impression = log.iter_mut().rev().find(|impression| {
+        impression.content_id == content_id
+            && impression.clicked_at.is_none()
+            && now.saturating_sub(impression.served_at) < IMPRESSION_CLICK_WINDOW
+    });
+    if let Some(impression) = impression {
+        impression.clicked_at = Some(now);
+        store.impression_counts.entry(impression.surface).or_default().clicks += 1;
+        store.arm_impression_counts.entry(ranking_arm(viewer)).or_default().clicks += 1;
+        if let Some(counts) = store.post_impression_counts.get_mut(content_id) {
+            counts.clicks += 1;
+        }
+    }
+}
+
+// Click-through per surface and per ranking arm since impressions were first logged
+pub fn get_impression_stats() -> SquareResult<ImpressionStatsResponse> {
+    const FUNCTION: &str = "get_impression_stats";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_impression_stats",
+            "Only managers or admins can view impression stats",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let mut surfaces: Vec<SurfaceImpressionStats> = store.impression_counts
+            .iter()
+            .map(|(surface, counts)| SurfaceImpressionStats {
+                surface: *surface,
+                impressions: counts.impressions,
+                clicks: counts.clicks,
+                click_through_rate: click_through_rate(counts),
+            })
+            .collect();
+        surfaces.sort_by_key(|stats| std::cmp::Reverse(stats.impressions));
+        let arms = [RankingArm::Baseline, RankingArm::ClickWeighted]
+            .into_iter()
+            .map(|arm| {
+                let counts = store.arm_impression_counts.get(&arm).cloned().unwrap_or_default();
+                ArmImpressionStats {
+                    arm,
+                    impressions: counts.impressions,
+                    clicks: counts.clicks,
+                    click_through_rate: click_through_rate(&counts),
+                }
+            })
+            .collect();
+        Ok(ImpressionStatsResponse {
+            settings: settings(&store),
+            sampled_users: store.impressions.len() as u64,
+            surfaces,
+            arms,
+        })
+    })
+}
+
+pub fn get_impression_settings() -> SquareResult<ImpressionSettings> {
+    Ok(STORAGE.with(|storage| settings(&storage.borrow())))
+}
+
+// Changing the percentage changes which users are sampled; logs already kept stay until evicted
+pub fn update_impression_settings(settings: ImpressionSettings) -> SquareResult<ImpressionSettings> {
+    const FUNCTION: &str = "update_impression_settings";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_impression_settings",
+            "Only admins can update impression settings",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if settings.sample_percent > 100 {
+        return log_and_return(field_validation_error(
+            "sample_percent",
+            "must be between 0 and 100",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    STORAGE.with(|storage| storage.borrow_mut().impression_settings = Some(settings.clone()));
+    Ok(settings)
+}
@@ -0,0 +1,400 @@
+use candid::Principal;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Bound;
+
+use crate::auth::is_admin;
+use crate::models::content::{ContentStatus, ParentType};
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::event::EventConsumer;
+use crate::models::index_rebuild::*;
+use crate::models::storage::Storage;
+use crate::services::events::{replay_consumer, stats_entry, update_checkpoint_counters};
+use crate::services::interaction::{has_liked, like_count};
+use crate::services::query_cache::{invalidate, TOPIC_QUERIES};
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::now_millis;
+
+const MODULE: &str = "services::index_rebuild";
+
+// Passes walk their records in key order from a cursor, so records written between calls are
+// either ahead of the cursor or already maintained by the write path.
+
+fn report(state: &mut IndexRebuildState, pass: IndexRebuildPass, key: &str, detail: String) {
+    state.discrepancies_found += 1;
+    if state.discrepancies.len() < MAX_REPORTED_DISCREPANCIES {
+        state.discrepancies.push(IndexDiscrepancy {
+            pass,
+            key: key.to_string(),
+            detail,
+        });
+    }
+}
+
+// The first `limit` keys of an ordered index after `after`; stable collections have `keys_after`
+pub(crate) fn next_keys<K: Ord + Clone, V>(index: &BTreeMap<K, V>, after: Option<&K>, limit: usize) -> Vec<K> {
+    let keys = match after {
+        Some(after) => index.range((Bound::Excluded(after), Bound::Unbounded)),
+        None => index.range(..),
+    };
+    keys.take(limit).map(|(key, _)| key.clone()).collect()
+}
+
+pub(crate) fn principal_cursor(cursor: Option<&str>) -> Option<Principal> {
+    cursor.and_then(|cursor| Principal::from_text(cursor).ok())
+}
+
+fn check_post_authors(store: &mut Storage, state: &mut IndexRebuildState, limit: usize) -> usize {
+    let ids = store.posts.keys_after(state.cursor.as_ref(), limit);
+    for id in &ids {
+        let Some(author) = store.posts.get(id).map(|post| post.author) else {
+            continue;
+        };
+        let listed = store.user_posts.entry(author).or_default();
+        if !listed.contains(id) {
+            listed.push(id.clone());
+            report(state, IndexRebuildPass::PostAuthors, id, format!("Missing from user_posts of {}", author));
+        }
+    }
+    if let Some(last) = ids.last() {
+        state.cursor = Some(last.clone());
+    }
+    ids.len()
+}
+
+// Drop entries that are not the user's own content, and duplicates
+fn prune_user_entries(
+    index: &mut BTreeMap<Principal, Vec<String>>,
+    users: &[Principal],
+    is_own: impl Fn(&Principal, &str) -> bool,
+    pass: IndexRebuildPass,
+    state: &mut IndexRebuildState,
+) {
+    for user in users {
+        let Some(listed) = index.get_mut(user) else {
+            continue;
+        };
+        let mut seen = HashSet::new();
+        listed.retain(|id| {
+            if !is_own(user, id) {
+                report(state, pass, &user.to_string(), format!("Lists {}, which is not theirs", id));
+                false
+            } else if !seen.insert(id.clone()) {
+                report(state, pass, &user.to_string(), format!("Lists {} more than once", id));
+                false
+            } else {
+                true
+            }
+        });
+        if listed.is_empty() {
+            index.remove(user);
+        }
+    }
+}
+
+fn check_user_post_entries(store: &mut Storage, state: &mut IndexRebuildState, limit: usize) -> usize {
+    let users = next_keys(&store.user_posts, principal_cursor(state.cursor.as_deref()).as_ref(), limit);
+    let posts = &store.posts;
+    prune_user_entries(
+        &mut store.user_posts,
+        &users,
+        |user, id| posts.get(id).is_some_and(|post| post.author == *user),
+        IndexRebuildPass::UserPostEntries,
+        state,
+    );
+    if let Some(last) = users.last() {
+        state.cursor = Some(last.to_string());
+    }
+    users.len()
+}
+
+fn check_comment_links(store: &mut Storage, state: &mut IndexRebuildState, limit: usize) -> usize {
+    const PASS: IndexRebuildPass = IndexRebuildPass::CommentLinks;
+
+    let ids = store.comments.keys_after(state.cursor.as_ref(), limit);
+    for id in &ids {
+        let Some(comment) = store.comments.get(id) else {
+            continue;
+        };
+        let (author, parent_id, parent_type) = (comment.author, comment.parent_id.clone(), comment.parent_type);
+
+        let listed = store.user_comments.entry(author).or_default();
+        if !listed.contains(id) {
+            listed.push(id.clone());
+            report(state, PASS, id, format!("Missing from user_comments of {}", author));
+        }
+
+        if parent_type == ParentType::Post && store.posts.contains_key(&parent_id) {
+            let listed = store.post_comments.entry(parent_id.clone()).or_default();
+            if !listed.contains(id) {
+                listed.push(id.clone());
+                report(state, PASS, id, format!("Missing from the comments of post {}", parent_id));
+            }
+        }
+
+        if parent_type == ParentType::Comment {
+            match store.comments.get_mut(&parent_id) {
+                Some(mut parent) if !parent.child_comments.contains(id) => {
+                    parent.child_comments.push(id.clone());
+                    report(state, PASS, id, format!("Missing from the replies of {}", parent_id));
+                }
+                Some(_) => {}
+                // Nothing to link to; left for moderation rather than deleted here
+                None => report(state, PASS, id, format!("Parent comment {} does not exist", parent_id)),
+            }
+        }
+
+        let children = store.comments.get(id).map(|comment| comment.child_comments.clone()).unwrap_or_default();
+        let mut seen = HashSet::new();
+        let own: Vec<String> = children
+            .iter()
+            .filter(|child| {
+                store.comments.get(*child).is_some_and(|reply| reply.parent_type == ParentType::Comment && reply.parent_id == *id)
+                    && seen.insert(child.as_str())
+            })
+            .cloned()
+            .collect();
+        if own.len() != children.len() {
+            report(state, PASS, id, format!("Listed {} replies that are missing, duplicated or not its own", children.len() - own.len()));
+            if let Some(mut comment) = store.comments.get_mut(id) {
+                comment.child_comments = own;
+            }
+        }
+    }
+    if let Some(last) = ids.last() {
+        state.cursor = Some(last.clone());
+    }
+    ids.len()
+}
+
+fn check_user_comment_entries(store: &mut Storage, state: &mut IndexRebuildState, limit: usize) -> usize {
+    let users = next_keys(&store.user_comments, principal_cursor(state.cursor.as_deref()).as_ref(), limit);
+    let comments = &store.comments;
+    prune_user_entries(
+        &mut store.user_comments,
+        &users,
+        |user, id| comments.get(id).is_some_and(|comment| comment.author == *user),
+        IndexRebuildPass::UserCommentEntries,
+        state,
+    );
+    if let Some(last) = users.last() {
+        state.cursor = Some(last.to_string());
+    }
+    users.len()
+}
+
+fn check_comment_like_counts(store: &mut Storage, state: &mut IndexRebuildState, limit: usize) -> usize {
+    let ids = store.comments.keys_after(state.cursor.as_ref(), limit);
+    for id in &ids {
+        let likes = like_count(store, id);
+        let Some(mut comment) = store.comments.get_mut(id).filter(|comment| comment.likes_count != likes) else {
+            continue;
+        };
+        report(state, IndexRebuildPass::CommentLikeCounts, id, format!("likes_count was {}, it has {} likes", comment.likes_count, likes));
+        comment.likes_count = likes;
+    }
+    if let Some(last) = ids.last() {
+        state.cursor = Some(last.clone());
+    }
+    ids.len()
+}
+
+// Post, comment and like counts as the indexed posts and comments give them; only Active content counts
+pub(crate) fn expected_user_stats(store: &Storage, user: &Principal) -> (u64, u64, u64) {
+    let posts: Vec<&String> = store.user_posts
+        .get(user)
+        .into_iter()
+        .flatten()
+        .filter(|id| store.posts.get(*id).is_some_and(|post| post.status == ContentStatus::Active))
+        .collect();
+    let comments: Vec<&String> = store.user_comments
+        .get(user)
+        .into_iter()
+        .flatten()
+        .filter(|id| store.comments.get(*id).is_some_and(|comment| comment.status == ContentStatus::Active))
+        .collect();
+    // Users liking their own content do not add to their likes received
+    let likes: u64 = posts
+        .iter()
+        .chain(&comments)
+        .map(|id| like_count(store, id) - has_liked(store, id, user) as u64)
+        .sum();
+    (posts.len() as u64, comments.len() as u64, likes)
+}
+
+pub(crate) fn current_user_stats(store: &Storage, user: &Principal) -> (u64, u64, u64) {
+    store.user_stats
+        .as_ref()
+        .and_then(|stats| stats.get(user))
+        .map_or((0, 0, 0), |stats| (stats.post_count, stats.comment_count, stats.like_count))
+}
+
+// Move a checkpoint counter by the same amount as the live counter it seeds
+fn shift(base: u64, from: u64, to: u64) -> u64 {
+    (base + to).saturating_sub(from)
+}
+
+fn check_user_stats(store: &mut Storage, state: &mut IndexRebuildState, limit: usize) -> usize {
+    let users = store.users.keys_after(principal_cursor(state.cursor.as_deref()).as_ref(), limit);
+    for user in &users {
+        let expected = expected_user_stats(store, user);
+        let current = current_user_stats(store, user);
+        if current == expected {
+            continue;
+        }
+        report(
+            state,
+            IndexRebuildPass::UserStats,
+            &user.to_string(),
+            format!("Post, comment and like counts were {:?}, the records give {:?}", current, expected),
+        );
+
+        let stats = stats_entry(store.user_stats.get_or_insert_with(HashMap::new), *user);
+        (stats.post_count, stats.comment_count, stats.like_count) = expected;
+        // Keep the repair when the UserStats consumer is later replayed from the checkpoint
+        update_checkpoint_counters(&mut store.interaction_events.checkpoint, *user, |base| {
+            base.post_count = shift(base.post_count, current.0, expected.0);
+            base.comment_count = shift(base.comment_count, current.1, expected.1);
+            base.like_count = shift(base.like_count, current.2, expected.2);
+        });
+    }
+    if let Some(last) = users.last() {
+        state.cursor = Some(last.to_string());
+    }
+    users.len()
+}
+
+// Runs in one call: the replay is bounded by the retained event log
+fn check_trending(store: &mut Storage, state: &mut IndexRebuildState) -> usize {
+    const PASS: IndexRebuildPass = IndexRebuildPass::Trending;
+
+    let before = store.trending_topics.clone();
+    replay_consumer(store, EventConsumer::Trending);
+    let topics: BTreeSet<&String> = before.keys().chain(store.trending_topics.keys()).collect();
+    for topic in &topics {
+        let was = before.get(*topic).copied().unwrap_or(0);
+        let is = store.trending_topics.get(*topic).copied().unwrap_or(0);
+        if was != is {
+            report(state, PASS, topic, format!("Count was {}, the event log gives {}", was, is));
+        }
+    }
+    let checked = topics.len();
+    invalidate(TOPIC_QUERIES);
+
+    let trending_content = std::mem::take(&mut store.trending_content);
+    let content_checked = trending_content.len();
+    for id in trending_content {
+        if store.posts.get(&id).is_some_and(|post| post.status == ContentStatus::Active) {
+            store.trending_content.push(id);
+        } else {
+            report(state, PASS, &id, "Trending content is missing or not active".to_string());
+        }
+    }
+    checked + content_checked
+}
+
+// Check up to INDEX_REBUILD_BATCH_SIZE records, moving on to the next pass as each one finishes
+fn run_batch(store: &mut Storage, state: &mut IndexRebuildState) {
+    let mut budget = INDEX_REBUILD_BATCH_SIZE;
+    while let Some(pass) = state.passes.first().copied() {
+        if budget == 0 {
+            break;
+        }
+        // A pass that returns fewer records than it was given has reached the end
+        let (checked, done) = match pass {
+            IndexRebuildPass::PostAuthors => {
+                let checked = check_post_authors(store, state, budget);
+                (checked, checked < budget)
+            }
+            IndexRebuildPass::UserPostEntries => {
+                let checked = check_user_post_entries(store, state, budget);
+                (checked, checked < budget)
+            }
+            IndexRebuildPass::CommentLinks => {
+                let checked = check_comment_links(store, state, budget);
+                (checked, checked < budget)
+            }
+            IndexRebuildPass::UserCommentEntries => {
+                let checked = check_user_comment_entries(store, state, budget);
+                (checked, checked < budget)
+            }
+            IndexRebuildPass::CommentLikeCounts => {
+                let checked = check_comment_like_counts(store, state, budget);
+                (checked, checked < budget)
+            }
+            IndexRebuildPass::UserStats => {
+                let checked = check_user_stats(store, state, budget);
+                (checked, checked < budget)
+            }
+            IndexRebuildPass::Trending => (check_trending(store, state), true),
+        };
+        state.records_checked += checked as u64;
+        budget = budget.saturating_sub(checked);
+        if done {
+            state.passes.remove(0);
+            state.cursor = None;
+        }
+    }
+}
+
+// Recompute indexes and counters from the primary records, one batch per call. Call again with the
+// same scope until the progress reports `complete`; a different scope is refused until then.
+pub fn rebuild_indexes(scope: IndexScope) -> SquareResult<IndexRebuildProgress> {
+    const FUNCTION: &str = "rebuild_indexes";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "rebuild_indexes",
+            "Only admins can rebuild indexes",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let mut state = match store.index_rebuild.take() {
+            Some(state) if state.scope != scope => {
+                let running = state.scope;
+                store.index_rebuild = Some(state);
+                return log_and_return(invalid_operation_error(
+                    "rebuild_indexes",
+                    &format!("A {:?} rebuild is in progress; call rebuild_indexes with {:?} until it completes", running, running),
+                    MODULE,
+                    FUNCTION
+                ));
+            }
+            Some(state) => state,
+            None => IndexRebuildState {
+                scope,
+                passes: scope.passes(),
+                cursor: None,
+                records_checked: 0,
+                discrepancies_found: 0,
+                discrepancies: Vec::new(),
+                started_at: now_millis(),
+            },
+        };
+
+        run_batch(&mut store, &mut state);
+        let complete = state.passes.is_empty();
+        let progress = IndexRebuildProgress {
+            scope: state.scope,
+            next_pass: state.passes.first().copied(),
+            records_checked: state.records_checked,
+            discrepancies_found: state.discrepancies_found,
+            discrepancies: state.discrepancies.clone(),
+            started_at: state.started_at,
+            complete,
+        };
+        if complete {
+            crate::utils::logger::log(&format!(
+                "{:?} index rebuild checked {} records and found {} discrepancies",
+                state.scope, state.records_checked, state.discrepancies_found
+            ));
+        } else {
+            store.index_rebuild = Some(state);
+        }
+        Ok(progress)
+    })
+}
@@ -0,0 +1,196 @@
+use crate::auth::is_admin;
+use crate::models::content::ParentType;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::integrity::*;
+use crate::models::storage::Storage;
+use crate::services::index_rebuild::{current_user_stats, expected_user_stats, next_keys, principal_cursor};
+use crate::services::interaction::like_count;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::instruction_budget::approaching_instruction_limit;
+use crate::utils::time_utils::now_millis;
+
+const MODULE: &str = "services::integrity";
+
+fn summary(report: &mut IntegrityReport, check: IntegrityCheck) -> &mut IntegrityCheckSummary {
+    let index = match report.checks.iter().position(|summary| summary.check == check) {
+        Some(index) => index,
+        None => {
+            report.checks.push(IntegrityCheckSummary {
+                check,
+                records_checked: 0,
+                findings: 0,
+            });
+            report.checks.len() - 1
+        }
+    };
+    &mut report.checks[index]
+}
+
+fn record(report: &mut IntegrityReport, check: IntegrityCheck, key: &str, detail: String) {
+    summary(report, check).findings += 1;
+    if report.findings.len() < MAX_INTEGRITY_FINDINGS {
+        report.findings.push(IntegrityFinding {
+            check,
+            key: key.to_string(),
+            detail,
+        });
+    }
+}
+
+// Check up to `limit` records past the cursor; returns how many were checked and the last key
+fn check_batch(store: &Storage, run: &mut IntegrityAuditRun, check: IntegrityCheck, limit: usize) -> (usize, Option<String>) {
+    let report = &mut run.report;
+    match check {
+        IntegrityCheck::OrphanedComments => {
+            let ids = store.comments.keys_after(run.cursor.as_ref(), limit);
+            for comment in ids.iter().filter_map(|id| store.comments.get(id)) {
+                let parent_exists = match comment.parent_type {
+                    ParentType::Post => store.posts.contains_key(&comment.parent_id),
+                    ParentType::Comment => store.comments.contains_key(&comment.parent_id),
+                };
+                if !parent_exists {
+                    record(report, check, &comment.id, format!("Parent {:?} {} does not exist", comment.parent_type, comment.parent_id));
+                }
+            }
+            (ids.len(), ids.last().cloned())
+        }
+        IntegrityCheck::DanglingLikes => {
+            let ids = store.like_sets.keys_after(run.cursor.as_ref(), limit);
+            for id in &ids {
+                if !store.posts.contains_key(id) && !store.comments.contains_key(id) {
+                    record(report, check, id, format!("{} likes on content that does not exist", like_count(store, id)));
+                }
+            }
+            (ids.len(), ids.last().cloned())
+        }
+        IntegrityCheck::StatsDrift => {
+            let users = store.users.keys_after(principal_cursor(run.cursor.as_deref()).as_ref(), limit);
+            for user in &users {
+                let current = current_user_stats(store, user);
+                let expected = expected_user_stats(store, user);
+                if current != expected {
+                    record(
+                        report,
+                        check,
+                        &user.to_string(),
+                        format!("Post, comment and like counts are {:?}, the records give {:?}", current, expected),
+                    );
+                }
+            }
+            (users.len(), users.last().map(ToString::to_string))
+        }
+        IntegrityCheck::DanglingUserPosts => {
+            let users = next_keys(&store.user_posts, principal_cursor(run.cursor.as_deref()).as_ref(), limit);
+            for user in &users {
+                for id in store.user_posts.get(user).into_iter().flatten() {
+                    match store.posts.get(id) {
+                        None => record(report, check, &user.to_string(), format!("Lists {}, which does not exist", id)),
+                        Some(post) if post.author != *user => {
+                            record(report, check, &user.to_string(), format!("Lists {}, which is by {}", id, post.author));
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+            (users.len(), users.last().map(ToString::to_string))
+        }
+    }
+}
+
+fn start_audit(store: &mut Storage) {
+    store.integrity_audit.running = Some(IntegrityAuditRun {
+        remaining: INTEGRITY_CHECKS.to_vec(),
+        cursor: None,
+        report: IntegrityReport {
+            started_at: now_millis(),
+            ..Default::default()
+        },
+    });
+}
+
+// Called from the full heartbeat run; starts an audit once a day
+pub fn schedule_integrity_audit() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let audit = &store.integrity_audit;
+        let last_started = audit.last_report.as_ref().map_or(0, |report| report.started_at);
+        if audit.running.is_none() && now_millis().saturating_sub(last_started) >= INTEGRITY_AUDIT_INTERVAL {
+            start_audit(&mut store);
+        }
+    });
+}
+
+// Check the next batch of a running audit. Called on every heartbeat.
+pub fn run_integrity_audit() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let Some(mut run) = store.integrity_audit.running.take() else {
+            return;
+        };
+
+        let mut budget = INTEGRITY_AUDIT_BATCH_SIZE;
+        while let Some(check) = run.remaining.first().copied() {
+            if budget == 0 || approaching_instruction_limit() {
+                break;
+            }
+            let (checked, last) = check_batch(&store, &mut run, check, budget);
+            summary(&mut run.report, check).records_checked += checked as u64;
+            budget -= checked;
+            run.cursor = last;
+            // Fewer records than asked for means the check reached the end
+            if budget > 0 {
+                run.remaining.remove(0);
+                run.cursor = None;
+            }
+        }
+
+        if run.remaining.is_empty() {
+            let mut report = run.report;
+            report.completed_at = Some(now_millis());
+            let findings: u64 = report.checks.iter().map(|summary| summary.findings).sum();
+            if findings > 0 {
+                crate::utils::logger::log(&format!("Integrity audit found {} problems", findings));
+            }
+            store.integrity_audit.last_report = Some(report);
+        } else {
+            store.integrity_audit.running = Some(run);
+        }
+    });
+}
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage integrity audits",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+pub fn get_integrity_report() -> SquareResult<IntegrityAuditStatus> {
+    const FUNCTION: &str = "get_integrity_report";
+
+    require_admin("get_integrity_report", FUNCTION)?;
+    Ok(STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let audit = &store.integrity_audit;
+        IntegrityAuditStatus {
+            running: audit.running.as_ref().map(|run| run.report.clone()),
+            next_check: audit.running.as_ref().and_then(|run| run.remaining.first().copied()),
+            last_report: audit.last_report.clone(),
+        }
+    }))
+}
+
+// Restart the audit from scratch; batches are checked on the following heartbeats
+pub fn start_integrity_audit() -> SquareResult<IntegrityAuditStatus> {
+    const FUNCTION: &str = "start_integrity_audit";
+
+    require_admin("start_integrity_audit", FUNCTION)?;
+    STORAGE.with(|storage| start_audit(&mut storage.borrow_mut()));
+    get_integrity_report()
+}
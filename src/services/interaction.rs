@@ -6,44 +6,68 @@ use crate::storage::UserStats;
 
 use crate::auth::is_manager_or_admin;
 use crate::models::interaction::*;
-use crate::models::content::ContentType;
+use crate::models::content::{ContentType, ContentVisibility};
 use crate::models::error::{SquareError, SquareResult};
 use crate::storage::{ContentStatus, ParentType, STORAGE};
 use crate::utils::error_handler::*;
+use crate::utils::content_utils::normalize_hashtag;
+use crate::models::content::PaginationParams;
+use crate::models::display::PaginatedResponse;
+use crate::services::discovery::tags::{can_moderate_in_tags, content_hashtags, moderated_tags};
+use crate::services::user::creator::paginate;
+use crate::services::anomaly::{check_engagement_throttle, record_engagement};
+use crate::models::anomaly::EngagementKind;
+use crate::models::account_quality::RateLimitedAction;
+use crate::services::user::check_action_rate_limit;
+use crate::services::query_cache::{invalidate, ENGAGEMENT_QUERIES};
+use crate::models::storage::Storage;
+use crate::services::discovery::similarity::record_like_change;
+use crate::services::events::{log_event, record_event};
+use crate::services::content::transitions::set_content_status;
+use crate::services::content::cross_posts::canonical_post_id;
+use crate::models::event::InteractionEventKind;
+use crate::services::impression::record_click;
+use crate::utils::time_utils::day_start;
 
-// Like functionality
-pub fn like_content(content_id: String, content_type: ContentType, caller: Principal) -> SquareResult<()> {
-    const MODULE: &str = "services::interaction";
-    const FUNCTION: &str = "like_content";
-    
-    
-    let request = LikeContentRequest {
-        content_id,
-        content_type,
-    };
-    
-    like_content_request(request, caller)
-        .map(|_| ())
-        .map_err(|e| {
-            e
-        })
+// Like sets hold interned principal ids; these read them in terms of principals
+pub fn like_count(store: &Storage, content_id: &str) -> u64 {
+    store.like_sets.get(content_id).map_or(0, |likers| likers.len() as u64)
 }
 
-pub fn unlike_content(content_id: String, content_type: ContentType, caller: Principal) -> SquareResult<()> {
-    const MODULE: &str = "services::interaction";
-    const FUNCTION: &str = "unlike_content";
-    
-    
-    // We can reuse the like_content function since it toggles the like status
-    like_content(content_id, content_type, caller).map_err(|e| {
-        e
-    })
+pub fn has_liked(store: &Storage, content_id: &str, principal: &Principal) -> bool {
+    store.principal_ids.id(principal).is_some_and(|id| store.like_sets.get(content_id).is_some_and(|likers| likers.contains(id)))
+}
+
+pub fn likers(store: &Storage, content_id: &str) -> Vec<Principal> {
+    store.like_sets
+        .get(content_id)
+        .map(|likers| likers.iter().filter_map(|id| store.principal_ids.principal(id)).collect())
+        .unwrap_or_default()
+}
+
+// Like functionality. Liking liked content or unliking content that is not liked changes nothing
+// and returns the current state, so clients can safely retry.
+pub fn like_content(content_id: String, content_type: ContentType, caller: Principal) -> SquareResult<LikeState> {
+    set_like(LikeContentRequest { content_id, content_type }, caller, Some(true))
+}
+
+pub fn unlike_content(content_id: String, content_type: ContentType, caller: Principal) -> SquareResult<LikeState> {
+    set_like(LikeContentRequest { content_id, content_type }, caller, Some(false))
+}
+
+pub fn toggle_like(request: LikeContentRequest, caller: Principal) -> SquareResult<LikeState> {
+    set_like(request, caller, None)
 }
 
-pub fn get_likes(content_id: String, content_type: ContentType) -> SquareResult<LikesResponse> {
+pub fn get_likes(mut content_id: String, content_type: ContentType) -> SquareResult<LikesResponse> {
     const MODULE: &str = "services::interaction";
     const FUNCTION: &str = "get_likes";
     
+    // A cross-post shares the likes of its original
+    if content_type == ContentType::Post {
+        content_id = STORAGE.with(|storage| canonical_post_id(&storage.borrow(), &content_id));
+    }
+    
     // Verify content exists in main storage
     match content_type {
         ContentType::Post => {
@@ -82,7 +106,8 @@ pub fn get_likes(content_id: String, content_type: ContentType) -> SquareResult<
     let likes = STORAGE.with(|storage| {
         let store = storage.borrow();
         
-        if let Some(likes_set) = store.likes.get(&content_id) {
+        let likes_set = likers(&store, &content_id);
+        if !likes_set.is_empty() {
             // Convert likes to UserLikeInfo objects
             likes_set.iter().map(|principal| {
                 // Get user profile if available
@@ -118,10 +143,15 @@ pub fn get_likes(content_id: String, content_type: ContentType) -> SquareResult<
     })
 }
 
-// Internal function to handle like requests
-pub fn like_content_request(request: LikeContentRequest, caller: Principal) -> SquareResult<InteractionResponse> {
+// Bring the caller's like on a piece of content to `target`, or flip it when `target` is None
+fn set_like(mut request: LikeContentRequest, caller: Principal, target: Option<bool>) -> SquareResult<LikeState> {
     const MODULE: &str = "services::interaction";
-    const FUNCTION: &str = "like_content_request";
+    const FUNCTION: &str = "set_like";
+    
+    // Liking a cross-post likes its original
+    if request.content_type == ContentType::Post {
+        request.content_id = STORAGE.with(|storage| canonical_post_id(&storage.borrow(), &request.content_id));
+    }
     
     // Verify content exists and is active in main storage
     let content_author = match request.content_type {
@@ -173,76 +203,72 @@ pub fn like_content_request(request: LikeContentRequest, caller: Principal) -> S
         },
     };
     
-    // Check if already liked using main storage
-    let already_liked = STORAGE.with(|storage| {
-        let store = storage.borrow();
-        store.likes.get(&request.content_id)
-            .map_or(false, |principals| principals.contains(&caller))
-    });
+    let already_liked = STORAGE.with(|storage| has_liked(&storage.borrow(), &request.content_id, &caller));
+    let like = target.unwrap_or(!already_liked);
+    if like == already_liked {
+        return Ok(like_state(&request.content_id, like));
+    }
     
-    if already_liked {
-        // Unlike if already liked
-        STORAGE.with(|storage| {
-            let mut store = storage.borrow_mut();
-            // Remove like from main storage
-            if let Some(principals) = store.likes.get_mut(&request.content_id) {
-                principals.remove(&caller);
-                
-                if principals.is_empty() {
-                    store.likes.remove(&request.content_id);
+    // New accounts cannot like content flagged for coordinated engagement
+    if like {
+        check_engagement_throttle(&request.content_id, caller)?;
+        check_action_rate_limit(caller, RateLimitedAction::Like)?;
+    }
+    
+    invalidate(ENGAGEMENT_QUERIES);
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if like {
+            let id = store.principal_ids.intern(caller);
+            store.like_sets.entry(request.content_id.clone())
+                .or_default()
+                .insert(id);
+            log_event(&mut store, caller, InteractionEventKind::Liked { content_id: request.content_id.clone(), author: content_author });
+        } else {
+            let id = store.principal_ids.id(&caller);
+            let emptied = match (id, store.like_sets.get_mut(&request.content_id)) {
+                (Some(id), Some(mut likers)) => {
+                    likers.remove(id);
+                    likers.is_empty()
                 }
+                _ => false,
+            };
+            if emptied {
+                store.like_sets.remove(&request.content_id);
             }
-        });
-        
-        // Update author stats
-        if let Some(author) = content_author {
-            STORAGE.with(|storage| {
-                let mut store = storage.borrow_mut();
-                if let Some(stats) = store.user_stats.as_mut().and_then(|stats| stats.get_mut(&author)) {
-                    if stats.like_count > 0 {
-                        stats.like_count -= 1;
-                    }
-                }
-            });
+            log_event(&mut store, caller, InteractionEventKind::Unliked { content_id: request.content_id.clone(), author: content_author });
         }
-        
-        return Ok(InteractionResponse {
-            success: true,
-            message: "Content unliked successfully".to_string(),
-        });
-    } else {
-        // Add like using main storage
-        STORAGE.with(|storage| {
-            let mut store = storage.borrow_mut();
-            // Add like to main storage
-            // likes 是 HashMap<String, HashSet<Principal>>
-            store.likes.entry(request.content_id.clone())
-                .or_insert_with(HashSet::new)
-                .insert(caller);
-        });
-        
-        // Update author stats
-        if let Some(author) = content_author {
-            STORAGE.with(|storage| {
-                let mut store = storage.borrow_mut();
-                if let Some(stats) = store.user_stats.as_mut().and_then(|stats| stats.get_mut(&author)) {
-                    stats.like_count += 1;
-                }
-            });
-        }
-        
-        return Ok(InteractionResponse {
-            success: true,
-            message: "Content liked successfully".to_string(),
-        })
+    });
+    if let Some(id) = STORAGE.with(|storage| storage.borrow().principal_ids.id(&caller)) {
+        record_like_change(id, &request.content_id, like);
+    }
+    if like {
+        record_engagement(&request.content_id, request.content_type.clone(), caller, EngagementKind::Like);
+    }
+    
+    Ok(like_state(&request.content_id, like))
+}
+
+fn like_state(content_id: &str, liked: bool) -> LikeState {
+    LikeState {
+        liked,
+        like_count: STORAGE.with(|storage| like_count(&storage.borrow(), content_id)),
+    }
+}
+
+fn content_status(store: &Storage, content_type: &ContentType, content_id: &str) -> Option<ContentStatus> {
+    match content_type {
+        ContentType::Post => store.posts.get(content_id).map(|post| post.status.clone()),
+        ContentType::Comment => store.comments.get(content_id).map(|comment| comment.status.clone()),
     }
 }
 
 // Report functionality
-pub fn report_content(request: ReportContentRequest, caller: Principal) -> SquareResult<()> {
+pub fn report_content(request: ReportContentRequest, caller: Principal) -> SquareResult<ReportResult> {
     const MODULE: &str = "services::interaction";
     const FUNCTION: &str = "report_content";
     
+    
     // Verify content exists in main storage
     match request.content_type {
         ContentType::Post => {
@@ -277,70 +303,87 @@ pub fn report_content(request: ReportContentRequest, caller: Principal) -> Squar
         },
     }
     
-    // Generate report ID based on timestamp
-    let current_time = time() / 1_000_000;
-    let report_id = format!("report_{}", current_time / 1_000_000);
-    
-    // Clone content_type to avoid move
-    let content_type = request.content_type.clone();
+    // Reporting the same content again while the report is open changes nothing
+    let reported = STORAGE.with(|storage| {
+        open_report(&storage.borrow(), &request.content_id)
+            .filter(|report| report.reporters.contains(&caller))
+            .cloned()
+    });
+    if let Some(report) = reported {
+        return Ok(ReportResult {
+            report_id: report.id,
+            already_reported: true,
+            report_count: report.report_count,
+        });
+    }
     
-    // Create report
-    let report = ContentReport {
-        id: report_id.clone(),
-        content_id: request.content_id.clone(),
-        content_type,
-        reporter: caller,
-        reason: request.reason,
-        description: request.description,
-        status: ReportStatus::Pending,
-        created_at: current_time,
-        resolved_at: None,
-        resolver: None,
-        resolution_notes: None,
-    };
+    check_engagement_throttle(&request.content_id, caller)?;
+    check_action_rate_limit(caller, RateLimitedAction::Report)?;
     
-    // Store in main storage
-    STORAGE.with(|storage| {
+    let current_time = time() / 1_000_000;
+    let result = STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
+        let open_id = open_report(&store, &request.content_id).map(|report| report.id.clone());
         
-        // Add report to storage
-        store.reports.insert(report_id.clone(), report.clone());
+        // Later reporters join the open report
+        if let Some(report) = open_id.and_then(|id| store.reports.get_mut(&id)) {
+            if report.reporters.len() < MAX_LISTED_REPORTERS {
+                report.reporters.push(caller);
+            }
+            report.report_count += 1;
+            return ReportResult {
+                report_id: report.id.clone(),
+                already_reported: false,
+                report_count: report.report_count,
+            };
+        }
+        
+        let report_id = format!("report_{}_{}", request.content_id, current_time);
+        store.open_reports.insert(request.content_id.clone(), report_id.clone());
+        store.reports.insert(report_id.clone(), ContentReport {
+            id: report_id.clone(),
+            content_id: request.content_id.clone(),
+            content_type: request.content_type.clone(),
+            reporter: caller,
+            reporters: vec![caller],
+            report_count: 1,
+            reason: request.reason,
+            description: request.description,
+            status: ReportStatus::Pending,
+            created_at: current_time,
+            resolved_at: None,
+            resolver: None,
+            resolution_notes: None,
+        });
+        ReportResult {
+            report_id,
+            already_reported: false,
+            report_count: 1,
+        }
     });
+    record_engagement(&request.content_id, request.content_type.clone(), caller, EngagementKind::Report);
     
-    // For now, just set content under review if it's the first report
-    match request.content_type {
-        ContentType::Post => {
-            STORAGE.with(|storage| {
-                let mut store = storage.borrow_mut();
-                if let Some(mut post) = store.posts.get(&request.content_id).cloned() {
-                    if post.status == ContentStatus::Active {
-                        post.status = ContentStatus::UnderReview;
-                        store.posts.insert(request.content_id.clone(), post);
-                    }
-                }
-            });
-        },
-        ContentType::Comment => {
-            STORAGE.with(|storage| {
-                let mut store = storage.borrow_mut();
-                if let Some(mut comment) = store.comments.get(&request.content_id).cloned() {
-                    if comment.status == ContentStatus::Active {
-                        comment.status = ContentStatus::UnderReview;
-                        store.comments.insert(request.content_id.clone(), comment);
-                    }
-                }
-            });
-        },
-    }
+    // Content goes under review on its first report
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if content_status(&store, &request.content_type, &request.content_id) == Some(ContentStatus::Active) {
+            set_content_status(&mut store, caller, &request.content_type, &request.content_id, ContentStatus::UnderReview);
+        }
+    });
     
-    Ok(())
+    Ok(result)
+}
+
+// The pending report on a piece of content, if any
+fn open_report<'a>(store: &'a Storage, content_id: &str) -> Option<&'a ContentReport> {
+    store.open_reports
+        .get(content_id)
+        .and_then(|report_id| store.reports.get(report_id))
+        .filter(|report| report.status == ReportStatus::Pending)
 }
 
 // Resolve report (admin function)
 pub fn resolve_report(request: ResolveReportRequest, caller: Principal) -> SquareResult<InteractionResponse> {
-    // Check if caller is admin or manager
-    is_manager_or_admin()?;
-    
     // Get report from main storage
     let report = STORAGE.with(|storage| {
         let store = storage.borrow();
@@ -351,6 +394,14 @@ pub fn resolve_report(request: ResolveReportRequest, caller: Principal) -> Squar
     let report_info = report
         .ok_or_else(|| SquareError::NotFound(format!("Report not found: {}", request.report_id)))?;
     
+    // Admins and managers resolve any report; tag moderators only reports within their tags
+    let is_tag_moderator = STORAGE.with(|storage| {
+        can_moderate_in_tags(&storage.borrow(), caller, &report_info.content_type, &report_info.content_id)
+    });
+    if !is_tag_moderator {
+        is_manager_or_admin()?;
+    }
+    
     // Check if report is already resolved
     if report_info.status != ReportStatus::Pending {
         return Err(SquareError::InvalidOperation("Report is already resolved or rejected".to_string()));
@@ -360,55 +411,18 @@ pub fn resolve_report(request: ResolveReportRequest, caller: Principal) -> Squar
     let content_type = report_info.content_type.clone();
     
     // Update content based on request status
-    if request.status == ReportStatus::Resolved {
-        // Content violates rules, remove it
-        match content_type {
-            ContentType::Post => {
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    if let Some(mut post) = store.posts.get(&content_id).cloned() {
-                        post.status = ContentStatus::Removed;
-                        store.posts.insert(content_id.clone(), post);
-                    }
-                });
-            },
-            ContentType::Comment => {
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    if let Some(mut comment) = store.comments.get(&content_id).cloned() {
-                        comment.status = ContentStatus::Removed;
-                        store.comments.insert(content_id.clone(), comment);
-                    }
-                });
-            },
-        }
-    } else if request.status == ReportStatus::Rejected {
-        // Report rejected, content remains active
-        match content_type {
-            ContentType::Post => {
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    if let Some(mut post) = store.posts.get(&content_id).cloned() {
-                        if post.status == ContentStatus::UnderReview {
-                            post.status = ContentStatus::Active;
-                            store.posts.insert(content_id.clone(), post);
-                        }
-                    }
-                });
-            },
-            ContentType::Comment => {
-                STORAGE.with(|storage| {
-                    let mut store = storage.borrow_mut();
-                    if let Some(mut comment) = store.comments.get(&content_id).cloned() {
-                        if comment.status == ContentStatus::UnderReview {
-                            comment.status = ContentStatus::Active;
-                            store.comments.insert(content_id.clone(), comment);
-                        }
-                    }
-                });
-            },
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if request.status == ReportStatus::Resolved {
+            // Content violates rules, remove it
+            set_content_status(&mut store, caller, &content_type, &content_id, ContentStatus::Removed);
+        } else if request.status == ReportStatus::Rejected
+            && content_status(&store, &content_type, &content_id) == Some(ContentStatus::UnderReview)
+        {
+            // Report rejected, content remains active
+            set_content_status(&mut store, caller, &content_type, &content_id, ContentStatus::Active);
         }
-    }
+    });
     
     // Update report in main storage
     let current_time = time() / 1_000_000;
@@ -421,6 +435,9 @@ pub fn resolve_report(request: ResolveReportRequest, caller: Principal) -> Squar
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         // 更新报告
+        if store.open_reports.get(&content_id) == Some(&request.report_id) {
+            store.open_reports.remove(&content_id);
+        }
         store.reports.insert(request.report_id.clone(), updated_report);
     });
     
@@ -431,24 +448,58 @@ pub fn resolve_report(request: ResolveReportRequest, caller: Principal) -> Squar
     })
 }
 
+// Pending reports queue, oldest first. Admins and managers see every report;
+// tag moderators only see reports on content within the tags they moderate.
+pub fn get_reports_queue(tag: Option<String>, pagination: PaginationParams, caller: Principal) -> SquareResult<PaginatedResponse<ContentReport>> {
+    const MODULE: &str = "services::interaction";
+    const FUNCTION: &str = "get_reports_queue";
+    
+    let tag = tag.and_then(|tag| normalize_hashtag(&tag));
+    let is_global_moderator = is_manager_or_admin().is_ok();
+    
+    let allowed_tags = STORAGE.with(|storage| moderated_tags(&storage.borrow(), caller));
+    if !is_global_moderator && allowed_tags.is_empty() {
+        return log_and_return(permission_denied_error(
+            "get_reports_queue",
+            "Only moderators can view the reports queue",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    
+    let mut reports: Vec<ContentReport> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.reports
+            .values()
+            .filter(|report| report.status == ReportStatus::Pending)
+            .filter(|report| {
+                let hashtags = content_hashtags(&store, &report.content_type, &report.content_id);
+                let in_requested_tag = tag.as_ref().is_none_or(|tag| hashtags.contains(tag));
+                let in_scope = is_global_moderator || hashtags.iter().any(|tag| allowed_tags.contains(tag));
+                in_requested_tag && in_scope
+            })
+            .cloned()
+            .collect()
+    });
+    
+    reports.sort_by_key(|report| report.created_at);
+    Ok(paginate(reports, pagination))
+}
+
 // Get interaction counts
 #[allow(dead_code)]
 pub fn get_interaction_counts(content_id: String, caller: Option<Principal>) -> SquareResult<InteractionCountsResponse> {
     // Get likes count from main storage
     let likes_count = STORAGE.with(|storage| {
         let store = storage.borrow();
-        store.likes.iter()
-            .filter(|(content_id_ref, _)| **content_id_ref == content_id)
-            .count() as u64
+        like_count(&store, &content_id)
     });
     
     // Check if caller liked the content using main storage
     let is_liked_by_caller = match caller {
         Some(principal) => STORAGE.with(|storage| {
             let store = storage.borrow();
-            store.likes.iter().any(|(content_id_ref, principal_set)| {
-                **content_id_ref == content_id && principal_set.contains(&principal)
-            })
+            has_liked(&store, &content_id, &principal)
         }),
         None => false,
     };
@@ -472,50 +523,44 @@ pub fn get_interaction_counts(content_id: String, caller: Option<Principal>) ->
     })
 }
 
-// View tracking
-#[allow(dead_code)]
-pub fn view_content(request: ViewContentRequest, _caller: Principal) -> SquareResult<()> {
-    // Get content author from main storage
-    let content_author = match request.content_type {
-        ContentType::Post => {
-            // Get post from main storage
-            STORAGE.with(|storage| {
-                let store = storage.borrow();
-                store.posts.get(&request.content_id).map(|post| post.author)
-            })
-        },
-        ContentType::Comment => return Ok(()), // Don't track views for comments
-    };
-    
-    // Update author stats in main storage
-    if let Some(author) = content_author {
-        STORAGE.with(|storage| {
-            let mut store = storage.borrow_mut();
-            
-            if store.user_stats.is_none() {
-                store.user_stats = Some(HashMap::new());
-            }
-            
-            let author_principal = author;
-            
-            if let Some(stats_map) = &mut store.user_stats {
-                let mut user_stats = stats_map.get(&author_principal).cloned().unwrap_or_else(|| {
-                    UserStats {
-                        principal: author_principal,
-                        post_count: 0,
-                        comment_count: 0,
-                        like_count: 0,
-                        points: 0,
-                        reputation: 0,
-                    }
-                });
-                
-                user_stats.reputation += 1;
-                
-                stats_map.insert(author_principal, user_stats);
-            }
-        });
+// Whether this is the viewer's first view of the post today; the set of today's views starts over
+// on the first view of a new UTC day
+fn first_view_today(store: &mut Storage, viewer: Principal, content_id: &str, now: u64) -> bool {
+    let today = day_start(now);
+    if store.daily_views_day != today {
+        store.daily_views.clear();
+        store.daily_views_day = today;
     }
-    
+    !store.daily_views.get(&viewer).is_some_and(|viewed| viewed.contains(content_id))
+}
+
+// View tracking. Only Active public posts are tracked, and each viewer's view of a post counts once
+// per day, within the hourly view limit. A counted view of a post the caller was recently served
+// counts as a click on that impression.
+pub fn view_content(request: ViewContentRequest, caller: Principal) -> SquareResult<()> {
+    if request.content_type == ContentType::Comment {
+        return Ok(()); // Don't track views for comments
+    }
+    let now = time() / 1_000_000;
+    let author = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let author = store.posts
+            .get(&request.content_id)
+            .filter(|post| post.status == ContentStatus::Active && post.visibility == ContentVisibility::Public)
+            .map(|post| post.author)?;
+        first_view_today(&mut store, caller, &request.content_id, now).then_some(author)
+    });
+    let Some(author) = author else {
+        return Ok(());
+    };
+
+    check_action_rate_limit(caller, RateLimitedAction::View)?;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        store.daily_views.entry(caller).or_default().insert(request.content_id.clone());
+        record_click(&mut store, caller, &request.content_id, now);
+    });
+    record_event(caller, InteractionEventKind::Viewed { content_id: request.content_id, author });
+
     Ok(())
 }
@@ -0,0 +1,85 @@
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::auth::is_admin;
+use crate::models::error::{ErrorCode, ErrorSeverity, SquareError, SquareResult};
+use crate::models::feature_flag::FeatureFlag;
+use crate::models::maintenance::*;
+use crate::services::feature_flags::ensure_flag_disabled;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+
+const MODULE: &str = "services::maintenance";
+
+fn active_maintenance() -> Option<MaintenanceMode> {
+    STORAGE.with(|storage| storage.borrow().maintenance_mode.clone().filter(|mode| mode.enabled))
+}
+
+// Reject update calls from anyone but the admin while maintenance mode or the ReadOnlyMode flag is on.
+// Every update endpoint runs this before its first await.
+pub fn check_maintenance_mode() -> SquareResult<()> {
+    const FUNCTION: &str = "check_maintenance_mode";
+
+    if is_admin().is_ok() {
+        return Ok(());
+    }
+    let Some(mode) = active_maintenance() else {
+        return ensure_flag_disabled(FeatureFlag::ReadOnlyMode, "update", MODULE, FUNCTION);
+    };
+    log_and_return(SquareError::new(
+        ErrorCode::ServiceUnavailable,
+        format!("Maintenance in progress: {}", mode.message),
+        MODULE,
+        FUNCTION,
+        ErrorSeverity::Info
+    )
+    .with_retryable(true)
+    .recoverable("Try again once maintenance has finished"))
+}
+
+pub fn set_maintenance_mode(request: SetMaintenanceModeRequest, caller: Principal) -> SquareResult<Option<SystemBanner>> {
+    const FUNCTION: &str = "set_maintenance_mode";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "set_maintenance_mode",
+            "Only admins can toggle maintenance mode",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if request.message.as_ref().is_some_and(|message| message.chars().count() > MAX_MAINTENANCE_MESSAGE_LENGTH) {
+        return log_and_return(field_validation_error(
+            "message",
+            &format!("must be at most {} characters", MAX_MAINTENANCE_MESSAGE_LENGTH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        store.maintenance_mode = request.enabled.then(|| MaintenanceMode {
+            enabled: true,
+            message: request.message
+                .filter(|message| !message.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_MAINTENANCE_MESSAGE.to_string()),
+            started_at: now,
+            started_by: caller,
+            expected_end: request.expected_end,
+        });
+    });
+
+    get_system_banner()
+}
+
+// Banner clients show while maintenance is announced
+pub fn get_system_banner() -> SquareResult<Option<SystemBanner>> {
+    Ok(active_maintenance().map(|mode| SystemBanner {
+        kind: BannerKind::Maintenance,
+        message: mode.message,
+        since: mode.started_at,
+        expected_end: mode.expected_end,
+    }))
+}
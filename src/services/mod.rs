@@ -6,3 +6,29 @@ pub mod discovery;
 pub mod display;
 pub mod cycles;
 pub mod admin;
+pub mod task_registry;
+pub mod quiz;
+pub mod anomaly;
+pub mod feature_flags;
+pub mod maintenance;
+pub mod query_cache;
+pub mod shards;
+pub mod classifier;
+pub mod syndication;
+pub mod openchat;
+pub mod token_gate;
+pub mod translation;
+pub mod emoji;
+pub mod events;
+pub mod index_rebuild;
+pub mod integrity;
+pub mod task_template;
+pub mod task_webhook;
+pub mod points_expiry;
+pub mod treasury;
+pub mod http;
+pub mod impression;
+pub mod access_policy;
+pub mod api_version;
+#[cfg(feature = "demo-data")]
+pub mod demo_data;
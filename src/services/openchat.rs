@@ -0,0 +1,333 @@
+use candid::Principal;
+use ic_cdk::api::{call, time};
+use sha2::{Digest, Sha256};
+use std::cell::Cell;
+
+use crate::auth::{is_admin, is_manager_or_admin};
+use crate::models::content::{ContentStatus, ContentVisibility, PaginationParams, Post};
+use crate::models::display::PaginatedResponse;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::openchat::*;
+use crate::services::discovery::trending::calculate_trending_score;
+use crate::services::user::creator::paginate;
+use crate::storage::STORAGE;
+use crate::utils::content_utils::{normalize_hashtag, strip_html_tags};
+use crate::utils::error_handler::*;
+use crate::utils::logger;
+use crate::utils::time_utils::MILLIS_PER_HOUR;
+
+const MODULE: &str = "services::openchat";
+
+thread_local! {
+    // Guards against overlapping runs sending the same post twice while calls are in flight
+    static MIRROR_RUNNING: Cell<bool> = const { Cell::new(false) };
+}
+
+// Marks a mirror run as in progress; dropping it, even when a call traps after an await, ends the run
+struct MirrorRun;
+
+impl MirrorRun {
+    fn start() -> Option<Self> {
+        (!MIRROR_RUNNING.with(|running| running.replace(true))).then_some(MirrorRun)
+    }
+}
+
+impl Drop for MirrorRun {
+    fn drop(&mut self) {
+        MIRROR_RUNNING.with(|running| running.set(false));
+    }
+}
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can configure the OpenChat bridge",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+fn require_manager(operation: &str, function: &str) -> SquareResult<()> {
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only managers or admins can view the OpenChat bridge",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+pub fn get_openchat_settings() -> SquareResult<OpenChatBridgeSettings> {
+    const FUNCTION: &str = "get_openchat_settings";
+
+    require_manager("get_openchat_settings", FUNCTION)?;
+    Ok(STORAGE.with(|storage| storage.borrow().openchat_settings.clone().unwrap_or_default()))
+}
+
+pub fn update_openchat_settings(request: UpdateOpenChatSettingsRequest) -> SquareResult<OpenChatBridgeSettings> {
+    const FUNCTION: &str = "update_openchat_settings";
+
+    require_admin("update_openchat_settings", FUNCTION)?;
+    if request.min_trending_score.is_some_and(|score| !score.is_finite() || score < 0.0) {
+        return log_and_return(field_validation_error(
+            "min_trending_score",
+            "must be a non-negative number",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if request.max_posts_per_topic == Some(0) {
+        return log_and_return(field_validation_error(
+            "max_posts_per_topic",
+            "must be greater than 0",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if request.lookback_hours == Some(0) {
+        return log_and_return(field_validation_error(
+            "lookback_hours",
+            "must be greater than 0",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let mut settings = store.openchat_settings.clone().unwrap_or_default();
+
+        if let Some(enabled) = request.enabled {
+            settings.enabled = enabled;
+        }
+        if let Some(community_canister_id) = request.community_canister_id {
+            settings.community_canister_id = Some(community_canister_id);
+        }
+        if let Some(min_trending_score) = request.min_trending_score {
+            settings.min_trending_score = min_trending_score;
+        }
+        if let Some(max_posts_per_topic) = request.max_posts_per_topic {
+            settings.max_posts_per_topic = max_posts_per_topic;
+        }
+        if let Some(lookback_hours) = request.lookback_hours {
+            settings.lookback_hours = lookback_hours;
+        }
+
+        if settings.enabled && settings.community_canister_id.is_none() {
+            return log_and_return(field_validation_error(
+                "community_canister_id",
+                "is required to enable the bridge",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        store.openchat_settings = Some(settings.clone());
+        Ok(settings)
+    })
+}
+
+pub fn set_openchat_topic_route(request: SetOpenChatTopicRouteRequest, caller: Principal) -> SquareResult<OpenChatTopicRoute> {
+    const FUNCTION: &str = "set_openchat_topic_route";
+
+    require_admin("set_openchat_topic_route", FUNCTION)?;
+    let Some(tag) = normalize_hashtag(&request.tag) else {
+        return log_and_return(field_validation_error("tag", "must contain letters or digits", MODULE, FUNCTION));
+    };
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if !store.openchat_routes.contains_key(&tag) && store.openchat_routes.len() >= MAX_OPENCHAT_TOPIC_ROUTES {
+            return log_and_return(quota_exceeded_error("openchat_routes", MAX_OPENCHAT_TOPIC_ROUTES as u64, MODULE, FUNCTION));
+        }
+
+        let route = OpenChatTopicRoute {
+            tag: tag.clone(),
+            channel_id: request.channel_id,
+            added_by: caller,
+            added_at: time() / 1_000_000,
+        };
+        store.openchat_routes.insert(tag, route.clone());
+        Ok(route)
+    })
+}
+
+pub fn remove_openchat_topic_route(tag: String) -> SquareResult<()> {
+    const FUNCTION: &str = "remove_openchat_topic_route";
+
+    require_admin("remove_openchat_topic_route", FUNCTION)?;
+    let tag = normalize_hashtag(&tag).unwrap_or(tag);
+
+    STORAGE.with(|storage| {
+        if storage.borrow_mut().openchat_routes.remove(&tag).is_none() {
+            return log_and_return(not_found_error("OpenChatTopicRoute", &tag, MODULE, FUNCTION));
+        }
+        Ok(())
+    })
+}
+
+pub fn get_openchat_topic_routes() -> SquareResult<Vec<OpenChatTopicRoute>> {
+    const FUNCTION: &str = "get_openchat_topic_routes";
+
+    require_manager("get_openchat_topic_routes", FUNCTION)?;
+
+    STORAGE.with(|storage| {
+        let mut routes: Vec<OpenChatTopicRoute> = storage.borrow().openchat_routes.values().cloned().collect();
+        routes.sort_by(|a, b| a.tag.cmp(&b.tag));
+        Ok(routes)
+    })
+}
+
+pub fn get_mirrored_messages(pagination: PaginationParams) -> SquareResult<PaginatedResponse<MirroredMessage>> {
+    const FUNCTION: &str = "get_mirrored_messages";
+
+    require_manager("get_mirrored_messages", FUNCTION)?;
+
+    let mut messages: Vec<MirroredMessage> = STORAGE.with(|storage| {
+        storage.borrow().openchat_mirrored.values().cloned().collect()
+    });
+    // Newest first
+    messages.sort_by_key(|message| std::cmp::Reverse(message.mirrored_at));
+    Ok(paginate(messages, pagination))
+}
+
+// Stable per post and channel, so OpenChat can drop a resend of the same message
+fn message_id(post_id: &str, channel_id: u128) -> u128 {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}:{}", post_id, channel_id).as_bytes());
+    let digest = hasher.finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    u128::from_be_bytes(bytes)
+}
+
+fn message_text(post: &Post) -> String {
+    let body: String = strip_html_tags(&post.content).chars().take(MAX_OPENCHAT_MESSAGE_CHARS).collect();
+    let mut text = match &post.title {
+        Some(title) => format!("**{}**\n\n{}", title, body),
+        None => body,
+    };
+    if !post.hashtags.is_empty() {
+        text.push_str("\n\n");
+        text.push_str(&post.hashtags.join(" "));
+    }
+    text
+}
+
+// Unmirrored public posts for each routed topic that clear the trending threshold
+fn select_posts_to_mirror(settings: &OpenChatBridgeSettings, now: u64) -> Vec<(MirroredMessage, String)> {
+    let window_start = now.saturating_sub(settings.lookback_hours * MILLIS_PER_HOUR);
+    let candidates: Vec<(OpenChatTopicRoute, Vec<Post>)> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let mut routes: Vec<&OpenChatTopicRoute> = store.openchat_routes.values().collect();
+        routes.sort_by(|a, b| a.tag.cmp(&b.tag));
+        routes
+            .into_iter()
+            .map(|route| {
+                let posts = store.posts
+                    .values()
+                    .filter(|post| post.status == ContentStatus::Active
+                        && post.visibility == ContentVisibility::Public
+                        && !post.is_premium
+                        && post.token_gate.is_none()
+                        && post.created_at >= window_start
+                        && post.hashtags.contains(&route.tag)
+                        && !store.openchat_mirrored.contains_key(&post.id))
+                    .cloned()
+                    .collect();
+                (route.clone(), posts)
+            })
+            .collect()
+    });
+
+    let mut selected: Vec<(MirroredMessage, String)> = Vec::new();
+    for (route, posts) in candidates {
+        let mut scored: Vec<(f64, Post)> = posts
+            .into_iter()
+            .map(|post| (calculate_trending_score(&post.id), post))
+            .filter(|(score, _)| *score >= settings.min_trending_score)
+            .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        for (_, post) in scored.into_iter().take(settings.max_posts_per_topic as usize) {
+            // A post carrying several routed tags goes to the first topic only
+            if selected.iter().any(|(message, _)| message.post_id == post.id) {
+                continue;
+            }
+            let message = MirroredMessage {
+                post_id: post.id.clone(),
+                tag: route.tag.clone(),
+                channel_id: route.channel_id,
+                message_id: message_id(&post.id, route.channel_id),
+                mirrored_at: now,
+            };
+            selected.push((message, message_text(&post)));
+        }
+    }
+    selected
+}
+
+async fn send_to_openchat(community: Principal, message: &MirroredMessage, text: String) -> Result<(), String> {
+    let args = OpenChatSendMessageArgs {
+        channel_id: message.channel_id,
+        message_id: message.message_id,
+        text,
+        block_level_markdown: true,
+    };
+    let response: Result<(OpenChatSendMessageResponse,), _> =
+        call::call(community, OPENCHAT_SEND_MESSAGE_METHOD, (args,)).await;
+    match response {
+        Ok((OpenChatSendMessageResponse::Success,)) => Ok(()),
+        Ok((other,)) => Err(format!("OpenChat rejected the message: {:?}", other)),
+        Err((code, message)) => Err(format!("Call failed with code {:?}: {}", code, message)),
+    }
+}
+
+// Mirror newly trending posts into their topic channels; run from the heartbeat
+pub async fn mirror_trending_posts() -> Vec<MirroredMessage> {
+    let settings = STORAGE.with(|storage| storage.borrow().openchat_settings.clone().unwrap_or_default());
+    let Some(community) = settings.community_canister_id.filter(|_| settings.enabled) else {
+        return Vec::new();
+    };
+    let Some(_run) = MirrorRun::start() else {
+        return Vec::new();
+    };
+
+    let now = time() / 1_000_000;
+    let mut mirrored = Vec::new();
+    for (message, text) in select_posts_to_mirror(&settings, now) {
+        match send_to_openchat(community, &message, text).await {
+            Ok(()) => {
+                STORAGE.with(|storage| {
+                    storage.borrow_mut().openchat_mirrored.insert(message.post_id.clone(), message.clone());
+                });
+                mirrored.push(message);
+            }
+            Err(error) => {
+                logger::log(&format!("[{}] Mirroring {} to OpenChat failed: {}", MODULE, message.post_id, error));
+            }
+        }
+    }
+
+    mirrored
+}
+
+pub async fn run_openchat_mirror() -> SquareResult<Vec<MirroredMessage>> {
+    const FUNCTION: &str = "run_openchat_mirror";
+
+    require_admin("run_openchat_mirror", FUNCTION)?;
+    let enabled = STORAGE.with(|storage| storage.borrow().openchat_settings.as_ref().is_some_and(|settings| settings.enabled));
+    if !enabled {
+        return log_and_return(invalid_operation_error(
+            "run_openchat_mirror",
+            "The OpenChat bridge is disabled",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    Ok(mirror_trending_posts().await)
+}
@@ -0,0 +1,167 @@
+use candid::Principal;
+
+use crate::auth::is_admin;
+use crate::models::error::SquareResult;
+use crate::models::reward::*;
+use crate::models::notification::NotificationType;
+use crate::services::treasury::burn_points;
+use crate::services::user::notification::create_notification;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::instruction_budget::approaching_instruction_limit;
+use crate::utils::logger;
+use crate::utils::time_utils::{now_millis, TimestampMillis, MILLIS_PER_DAY};
+use crate::utils::validators::validate_points_expiry_settings;
+
+const MODULE: &str = "services::points_expiry";
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage points expiry",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+// A user's points that are due to expire now and within the warning window, from one pass over
+// their history. Debits use up the oldest points first, so everything taken out so far counts
+// against the earliest credits.
+struct ExpiringPoints {
+    expired: u64,
+    expiring_soon: u64,
+}
+
+fn expiring_points(rewards: &UserRewards, settings: &PointsExpirySettings, now: TimestampMillis) -> ExpiringPoints {
+    let expired_by = now.saturating_sub(settings.expiry_days.saturating_mul(MILLIS_PER_DAY));
+    let expiring_by = expired_by.saturating_add(settings.warning_days.saturating_mul(MILLIS_PER_DAY));
+    let (mut earned_by_expiry, mut earned_by_warning, mut debited) = (0u64, 0u64, 0u64);
+    for transaction in &rewards.points_history {
+        if transaction.amount < 0 {
+            debited = debited.saturating_add(transaction.amount.unsigned_abs());
+            continue;
+        }
+        let amount = transaction.amount as u64;
+        if transaction.timestamp <= expired_by {
+            earned_by_expiry = earned_by_expiry.saturating_add(amount);
+        }
+        if transaction.timestamp <= expiring_by {
+            earned_by_warning = earned_by_warning.saturating_add(amount);
+        }
+    }
+    let expired = earned_by_expiry.saturating_sub(debited).min(rewards.points);
+    // What expires now is burned first, and counts as taken out
+    let expiring_soon = earned_by_warning
+        .saturating_sub(debited.saturating_add(expired))
+        .min(rewards.points - expired);
+    ExpiringPoints { expired, expiring_soon }
+}
+
+// Called from the full heartbeat run; starts a sweep once a day while expiry is enabled
+pub fn schedule_points_expiry() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let enabled = store.points_expiry_settings.as_ref().is_some_and(|settings| settings.enabled);
+        let now = now_millis();
+        let state = &mut store.points_expiry;
+        if enabled && !state.running && now.saturating_sub(state.last_started_at) >= MILLIS_PER_DAY {
+            *state = PointsExpiryState {
+                running: true,
+                last_started_at: now,
+                ..Default::default()
+            };
+        }
+    });
+}
+
+// Sweep the next batch of users of a running pass. Called on every heartbeat.
+pub fn run_points_expiry() {
+    let now = now_millis();
+    let (warnings, settings) = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let settings = store.points_expiry_settings.clone().unwrap_or_default();
+        if !store.points_expiry.running {
+            return (Vec::new(), settings);
+        }
+        if !settings.enabled {
+            store.points_expiry.running = false;
+            store.points_expiry.cursor = None;
+            return (Vec::new(), settings);
+        }
+
+        let users = store.user_rewards.keys_after(store.points_expiry.cursor.as_ref(), POINTS_EXPIRY_BATCH_SIZE);
+        let mut warnings: Vec<(Principal, u64)> = Vec::new();
+        let mut swept = 0;
+        for user in &users {
+            if approaching_instruction_limit() {
+                break;
+            }
+            swept += 1;
+            let Some(ExpiringPoints { expired, expiring_soon: expiring }) = store.user_rewards
+                .get(user)
+                .map(|rewards| expiring_points(rewards, &settings, now))
+            else {
+                continue;
+            };
+            if expired > 0 {
+                burn_points(store, *user, expired, POINTS_EXPIRY_REASON.to_string(), None, None, now);
+                store.points_expiry.points_expired += expired;
+            }
+
+            let recently_warned = store.points_expiry_warnings
+                .get(user)
+                .is_some_and(|warned_at| now.saturating_sub(*warned_at) < settings.warning_days.saturating_mul(MILLIS_PER_DAY));
+            if expiring > 0 && !recently_warned {
+                store.points_expiry_warnings.insert(*user, now);
+                store.points_expiry.users_warned += 1;
+                warnings.push((*user, expiring));
+            }
+        }
+
+        if swept > 0 {
+            store.points_expiry.cursor = Some(users[swept - 1]);
+        }
+        // A short batch means the sweep reached the last user
+        if swept == users.len() && users.len() < POINTS_EXPIRY_BATCH_SIZE {
+            store.points_expiry.running = false;
+            store.points_expiry.cursor = None;
+            if store.points_expiry.points_expired > 0 {
+                logger::log(&format!("[{}] Expired {} points", MODULE, store.points_expiry.points_expired));
+            }
+        }
+        (warnings, settings)
+    });
+
+    for (user, points) in warnings {
+        let content = format!("{} of your points will expire within {} days. Use them before they are gone.", points, settings.warning_days);
+        let _ = create_notification(user, NotificationType::System, content, None, None);
+    }
+}
+
+pub fn get_points_expiry_settings() -> SquareResult<PointsExpirySettings> {
+    const FUNCTION: &str = "get_points_expiry_settings";
+
+    require_admin("get_points_expiry_settings", FUNCTION)?;
+    Ok(STORAGE.with(|storage| storage.borrow().points_expiry_settings.clone().unwrap_or_default()))
+}
+
+// Takes effect from the next daily sweep; turning expiry off stops a sweep in progress
+pub fn update_points_expiry_settings(settings: PointsExpirySettings) -> SquareResult<PointsExpirySettings> {
+    const FUNCTION: &str = "update_points_expiry_settings";
+
+    require_admin("update_points_expiry_settings", FUNCTION)?;
+    validate_points_expiry_settings(&settings).finish(MODULE, FUNCTION)?;
+    STORAGE.with(|storage| storage.borrow_mut().points_expiry_settings = Some(settings.clone()));
+    Ok(settings)
+}
+
+pub fn get_points_expiry_status() -> SquareResult<PointsExpiryState> {
+    const FUNCTION: &str = "get_points_expiry_status";
+
+    require_admin("get_points_expiry_status", FUNCTION)?;
+    Ok(STORAGE.with(|storage| storage.borrow().points_expiry.clone()))
+}
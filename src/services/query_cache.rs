@@ -0,0 +1,112 @@
+use ic_cdk::api::time;
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::models::content::PaginationParams;
+use crate::models::discovery::{DiscoverContentRequest, GetHotTagsRequest, GetTrendingTopicsRequest, SortOption};
+use crate::models::error::SquareResult;
+use crate::utils::time_utils::MILLIS_PER_SECOND;
+
+// Short-lived cache for hot, parameter-identical queries.
+// State written during a query call is discarded, so entries only persist when computed in an
+// update context: the heartbeat warms the default pages and writes invalidate what they affect.
+pub const QUERY_CACHE_TTL_MS: u64 = 30 * MILLIS_PER_SECOND;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CachedQuery {
+    TrendingTopics,
+    HotTags,
+    LatestPosts,
+    Explore,
+}
+
+// Queries affected by each kind of write
+pub const POST_QUERIES: &[CachedQuery] = &[CachedQuery::LatestPosts, CachedQuery::Explore, CachedQuery::HotTags];
+pub const ENGAGEMENT_QUERIES: &[CachedQuery] = &[CachedQuery::Explore];
+pub const TAG_QUERIES: &[CachedQuery] = &[CachedQuery::HotTags];
+pub const TOPIC_QUERIES: &[CachedQuery] = &[CachedQuery::TrendingTopics];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct CacheKey {
+    query: CachedQuery,
+    limit: usize,
+    // Query-specific parameter, e.g. the trending time range or explore sort order
+    variant: u64,
+}
+
+struct CacheEntry {
+    value: Box<dyn Any>,
+    cached_at: u64,
+}
+
+thread_local! {
+    // Heap-only; starts empty after every upgrade
+    static QUERY_CACHE: RefCell<HashMap<CacheKey, CacheEntry>> = RefCell::new(HashMap::new());
+}
+
+// Return the cached result for the query, computing and caching it when missing or stale
+pub fn cached<T: Clone + 'static>(
+    query: CachedQuery,
+    limit: usize,
+    variant: u64,
+    compute: impl FnOnce() -> SquareResult<T>
+) -> SquareResult<T> {
+    let now = time() / 1_000_000;
+    let key = CacheKey { query, limit, variant };
+
+    let hit = QUERY_CACHE.with(|cache| {
+        cache.borrow()
+            .get(&key)
+            .filter(|entry| entry.cached_at + QUERY_CACHE_TTL_MS > now)
+            .and_then(|entry| entry.value.downcast_ref::<T>().cloned())
+    });
+    if let Some(value) = hit {
+        return Ok(value);
+    }
+
+    let value = compute()?;
+    QUERY_CACHE.with(|cache| {
+        cache.borrow_mut().insert(key, CacheEntry { value: Box::new(value.clone()), cached_at: now });
+    });
+    Ok(value)
+}
+
+// Drop every cached page of the given queries; called by writes that change their results
+pub fn invalidate(queries: &[CachedQuery]) {
+    QUERY_CACHE.with(|cache| cache.borrow_mut().retain(|key, _| !queries.contains(&key.query)));
+}
+
+// Sort order encoded as the explore cache variant
+pub fn sort_variant(sort_by: &Option<SortOption>) -> u64 {
+    match sort_by {
+        None => 0,
+        Some(SortOption::MostCommented) => 1,
+        Some(SortOption::Trending) => 2,
+        Some(SortOption::MostLiked) => 3,
+        Some(SortOption::Latest) => 4,
+    }
+}
+
+// Recompute the default pages of each cached query once they expire; run from the heartbeat
+pub fn warm_query_cache() {
+    let default_page = || PaginationParams { offset: None, limit: None };
+
+    let _ = crate::services::discovery::get_trending_topics(GetTrendingTopicsRequest {
+        limit: None,
+        time_range_hours: None,
+    });
+    let _ = crate::services::discovery::get_hot_tags(GetHotTagsRequest {
+        tag_type: None,
+        limit: None,
+    });
+    let _ = crate::services::content::get_posts(default_page());
+    let _ = crate::services::discovery::discover_content(DiscoverContentRequest {
+        content_types: None,
+        tags: None,
+        pagination: default_page(),
+        sort_by: Some(SortOption::Trending),
+        filter: None,
+        exclude_seen: None,
+    });
+}
@@ -0,0 +1,240 @@
+use candid::Principal;
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::auth::is_manager_or_admin;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::quiz::*;
+use crate::models::reward::UserTasks;
+use crate::services::reward::credit_points;
+use crate::services::user::user_timezone_offset;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::{local_day_start, MILLIS_PER_DAY};
+use crate::utils::validators::validate_create_quiz;
+
+const MODULE: &str = "services::quiz";
+
+// Salted hash of a question's correct option, so stored answers can't be read back
+fn hash_option(salt: &str, quiz_id: &str, question_index: usize, option_index: u32) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}:{}:{}:{}", salt, quiz_id, question_index, option_index).as_bytes());
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn create_quiz(request: CreateQuizRequest, caller: Principal) -> SquareResult<String> {
+    const FUNCTION: &str = "create_quiz";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "create_quiz",
+            "Only managers and admins can create quizzes",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    validate_create_quiz(&request).finish(MODULE, FUNCTION)?;
+
+    let now = time() / 1_000_000;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+
+        let quiz_id = format!("quiz_{}_{}", now, store.quizzes.len() + 1);
+        // Per-quiz salt derived from the creator, quiz and creation time
+        let salt = hash_option(&caller.to_text(), &quiz_id, store.quizzes.len(), (time() % u32::MAX as u64) as u32);
+
+        let questions = request.questions
+            .into_iter()
+            .enumerate()
+            .map(|(index, input)| QuizQuestion {
+                correct_option_hash: hash_option(&salt, &quiz_id, index, input.correct_option),
+                question: input.question,
+                options: input.options,
+            })
+            .collect();
+
+        store.quizzes.insert(quiz_id.clone(), Quiz {
+            id: quiz_id.clone(),
+            title: request.title,
+            questions,
+            points_per_correct_answer: request.points_per_correct_answer,
+            min_correct_to_pass: request.min_correct_to_pass,
+            salt,
+            is_active: true,
+            created_by: caller,
+            created_at: now,
+        });
+
+        Ok(quiz_id)
+    })
+}
+
+pub fn set_quiz_active(quiz_id: String, is_active: bool) -> SquareResult<()> {
+    const FUNCTION: &str = "set_quiz_active";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "set_quiz_active",
+            "Only managers and admins can manage quizzes",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let Some(quiz) = store.quizzes.get_mut(&quiz_id) else {
+            return log_and_return(not_found_error("Quiz", &quiz_id, MODULE, FUNCTION));
+        };
+        quiz.is_active = is_active;
+        Ok(())
+    })
+}
+
+// Today's quiz for a user: active quizzes rotate daily by the user's local day number
+fn daily_quiz_for(user: &Principal, now: u64) -> Option<(Quiz, u64)> {
+    let day_start = local_day_start(now, user_timezone_offset(user));
+
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let mut active: Vec<&Quiz> = store.quizzes.values().filter(|quiz| quiz.is_active).collect();
+        if active.is_empty() {
+            return None;
+        }
+        active.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let day_number = day_start / MILLIS_PER_DAY;
+        let quiz = active[(day_number % active.len() as u64) as usize].clone();
+        Some((quiz, day_start))
+    })
+}
+
+fn has_submitted(user: &Principal, day_start: u64) -> bool {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.quiz_submissions
+            .get(user)
+            .is_some_and(|submissions| submissions.iter().any(|submission| submission.day_start == day_start))
+    })
+}
+
+pub fn get_daily_quiz(caller: Principal) -> SquareResult<DailyQuizResponse> {
+    const FUNCTION: &str = "get_daily_quiz";
+
+    let now = time() / 1_000_000;
+    let Some((quiz, day_start)) = daily_quiz_for(&caller, now) else {
+        return log_and_return(not_found_error("Quiz", "daily", MODULE, FUNCTION));
+    };
+
+    Ok(DailyQuizResponse {
+        quiz_id: quiz.id,
+        title: quiz.title,
+        questions: quiz.questions
+            .into_iter()
+            .map(|question| QuizQuestionResponse {
+                question: question.question,
+                options: question.options,
+            })
+            .collect(),
+        points_per_correct_answer: quiz.points_per_correct_answer,
+        min_correct_to_pass: quiz.min_correct_to_pass,
+        day_start,
+        already_submitted: has_submitted(&caller, day_start),
+    })
+}
+
+pub fn submit_quiz_answers(request: SubmitQuizAnswersRequest, caller: Principal) -> SquareResult<QuizResultResponse> {
+    const FUNCTION: &str = "submit_quiz_answers";
+
+    let now = time() / 1_000_000;
+    let Some((quiz, day_start)) = daily_quiz_for(&caller, now) else {
+        return log_and_return(not_found_error("Quiz", "daily", MODULE, FUNCTION));
+    };
+
+    if quiz.id != request.quiz_id {
+        return log_and_return(invalid_operation_error(
+            "submit_quiz_answers",
+            "Only today's quiz can be submitted",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if request.answers.len() != quiz.questions.len() {
+        return log_and_return(field_validation_error(
+            "answers",
+            &format!("must contain exactly {} answers", quiz.questions.len()),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if has_submitted(&caller, day_start) {
+        return log_and_return(invalid_operation_error(
+            "submit_quiz_answers",
+            "Today's quiz has already been submitted",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let correct_count = quiz.questions
+        .iter()
+        .zip(&request.answers)
+        .enumerate()
+        .filter(|(index, (question, answer))| hash_option(&quiz.salt, &quiz.id, *index, **answer) == question.correct_option_hash)
+        .count() as u32;
+    let passed = correct_count >= quiz.min_correct_to_pass;
+    let points_earned = if passed { correct_count as u64 * quiz.points_per_correct_answer } else { 0 };
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+
+        let submissions = store.quiz_submissions.entry(caller).or_default();
+        submissions.push(QuizSubmission {
+            quiz_id: quiz.id.clone(),
+            day_start,
+            answers: request.answers,
+            correct_count,
+            points_earned,
+            submitted_at: now,
+        });
+        if submissions.len() > MAX_QUIZ_SUBMISSIONS_PER_USER {
+            let excess = submissions.len() - MAX_QUIZ_SUBMISSIONS_PER_USER;
+            submissions.drain(..excess);
+        }
+
+        let user_tasks = store.user_tasks.entry(caller).or_insert_with(|| UserTasks {
+            principal: caller,
+            completed_tasks: Default::default(),
+            daily_tasks_reset: now,
+            last_check_in: None,
+            last_updated: now,
+        });
+        user_tasks.completed_tasks.insert(DAILY_QUIZ_TASK_ID.to_string(), now);
+        user_tasks.last_updated = now;
+    });
+
+    let total_points = if points_earned > 0 {
+        credit_points(caller, points_earned, format!("Daily quiz: {}", quiz.title), Some(quiz.id.clone()))
+    } else {
+        STORAGE.with(|storage| storage.borrow().user_rewards.get(&caller).map(|rewards| rewards.points).unwrap_or(0))
+    };
+
+    Ok(QuizResultResponse {
+        quiz_id: quiz.id,
+        correct_count,
+        total_questions: quiz.questions.len() as u32,
+        passed,
+        points_earned,
+        total_points,
+    })
+}
+
+pub fn get_my_quiz_submissions(caller: Principal) -> SquareResult<Vec<QuizSubmission>> {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let mut submissions = store.quiz_submissions.get(&caller).cloned().unwrap_or_default();
+        submissions.reverse();
+        Ok(submissions)
+    })
+}
@@ -8,88 +8,61 @@ use crate::models::reward::*;
 use crate::models::error::{SquareError, SquareResult};
 use crate::storage::STORAGE;
 use crate::utils::error_handler::*;
+use crate::utils::time_utils::{local_day_start, MILLIS_PER_DAY, MILLIS_PER_WEEK};
+use crate::services::user::user_timezone_offset;
+use crate::services::user::profile::last_seen;
+use crate::services::task_registry::{external_task_definitions, find_task_definition, task_canister_of, verify_external_task};
+use crate::services::events::get_task_progress;
+use crate::services::task_template::{in_local_period, resolve_task_id, template_proof_required};
+use crate::services::task_webhook::notify_task_completion;
+use crate::services::treasury::mint_points;
+use crate::models::storage::Storage;
+use crate::models::user::UserRole;
+use crate::utils::content_utils::normalize_hashtag;
+use crate::utils::time_utils::TimestampMillis;
 
-// Initialize default tasks with configurable active state
-pub fn init_default_tasks(enable_daily_post: bool, enable_social_engagement: bool) {
-    const MODULE: &str = "services::reward";
-    const FUNCTION: &str = "init_default_tasks";
-    let now = time() / 1_000_000;
-    // Check if tasks already exist in main storage
-    let tasks_exist = STORAGE.with(|storage| {
-        let store = storage.borrow();
-        store.tasks.as_ref().map_or(false, |tasks| !tasks.is_empty())
-    });
-    
-    // Only initialize if no tasks exist yet
-    if !tasks_exist {
-        // Daily post task
-        let daily_post = TaskDefinition {
-            id: "daily_post".to_string(),
-            title: "Daily Post".to_string(),
-            description: "Create a post daily to earn points".to_string(),
-            points: 50,
-            task_type: TaskType::Daily,
-            completion_criteria: "Create at least one post".to_string(),
-            expiration_time: None,
-            created_at: now,
-            updated_at: now,
-            is_active: enable_daily_post,
-            requirements: None,
-            canister_id: ic_cdk::id(),
-        };
-        
-        // Social engagement task
-        let social_engagement = TaskDefinition {
-            id: "social_engagement".to_string(),
-            title: "Social Engagement".to_string(),
-            description: "Engage with other users to earn points".to_string(),
-            points: 100,
-            task_type: TaskType::Daily,
-            completion_criteria: "Like or comment on at least 3 posts".to_string(),
-            expiration_time: None,
-            created_at: now,
-            updated_at: now,
-            is_active: enable_social_engagement,
-            requirements: None,
-            canister_id: ic_cdk::id(),
-        };
-        
-        // Add tasks to main storage
-        STORAGE.with(|storage| {
-            let mut store = storage.borrow_mut();
-            if store.tasks.is_none() {
-                store.tasks = Some(HashMap::new());
-            }
-            
-            if let Some(tasks) = &mut store.tasks {
-                tasks.insert(daily_post.id.clone(), daily_post);
-                tasks.insert(social_engagement.id.clone(), social_engagement);
-            }
-        });
-        
-        ic_cdk::println!("Default tasks initialized in main storage");
+// Whether `user` belongs to one of the task's segments; untargeted tasks are for everyone
+fn in_task_audience(store: &Storage, task: &TaskDefinition, user: Principal, now: TimestampMillis) -> bool {
+    if task.audience.is_empty() {
+        return true;
     }
+    let account = store.users.get(&user);
+    let inactive_after = INACTIVE_USER_DAYS * MILLIS_PER_DAY;
+    task.audience.iter().any(|segment| match segment {
+        TaskAudience::NewUsers => account.is_some_and(|account| now.saturating_sub(account.registered_at) < NEW_USER_TASK_DAYS * MILLIS_PER_DAY),
+        TaskAudience::Creators => account.is_some_and(|account| account.role == UserRole::Creator),
+        TaskAudience::InactiveUsers => {
+            last_seen(store, user).is_some_and(|seen| now.saturating_sub(seen) >= inactive_after)
+                || store.returning_users.get(&user).is_some_and(|returned| now.saturating_sub(*returned) < inactive_after)
+        }
+        TaskAudience::TagFollowers(tag) => store.user_profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(&user))
+            .is_some_and(|profile| profile.followed_topics.contains(tag)),
+    })
 }
 
-// Default initialization with all tasks enabled
-pub fn init_default_tasks_all_enabled() {
-    init_default_tasks(true, true);
-}
-
-// Initialize default tasks only if no tasks exist
-pub fn init_default_tasks_if_empty() {
-    // Check if tasks exist in main storage
-    let tasks_exist = STORAGE.with(|storage| {
-        let store = storage.borrow();
-        store.tasks.as_ref().map_or(false, |tasks| !tasks.is_empty())
-    });
-    
-    if !tasks_exist {
-        ic_cdk::println!("Initializing default tasks");
-        init_default_tasks_all_enabled();
-    } else {
-        ic_cdk::println!("Tasks already exist, skipping initialization");
+// Tag segments are stored normalized, the way followed topics are
+fn normalize_audience(audience: Vec<TaskAudience>, module: &str, function: &str) -> SquareResult<Vec<TaskAudience>> {
+    let mut normalized = Vec::new();
+    for segment in audience {
+        let segment = match segment {
+            TaskAudience::TagFollowers(tag) => match normalize_hashtag(&tag) {
+                Some(tag) => TaskAudience::TagFollowers(tag),
+                None => return log_and_return(field_validation_error(
+                    "audience",
+                    "tags must contain at least one letter or digit",
+                    module,
+                    function
+                )),
+            },
+            segment => segment,
+        };
+        if !normalized.contains(&segment) {
+            normalized.push(segment);
+        }
     }
+    Ok(normalized)
 }
 
 // Toggle task active status
@@ -130,10 +103,25 @@ pub fn toggle_task_status(task_id: &str, active: bool) -> SquareResult<()> {
 // See: canisters/daily_checkin_task/src/lib.rs
 // Users should call that canister directly for daily check-ins
 
-// Task completion
-pub fn complete_task(request: CompleteTaskRequest, caller: Principal) -> SquareResult<TaskCompletionResponse> {
-    const MODULE: &str = "services::reward";
-    const FUNCTION: &str = "complete_task";
+// Task completion. Tasks synced from a task canister are credited only once that canister's
+// `verify_task` confirms the completion. A template id completes the template's instance for the
+// caller's local date.
+pub async fn complete_task(request: CompleteTaskRequest, caller: Principal) -> SquareResult<TaskCompletionResponse> {
+    let now = time() / 1_000_000;
+    let task_id = STORAGE.with(|storage| resolve_task_id(&storage.borrow(), &request.task_id, user_timezone_offset(&caller), now));
+    let request = CompleteTaskRequest { task_id, ..request };
+    let task = check_task_completion(&request, caller)?;
+    let task_canister = STORAGE.with(|storage| task_canister_of(&storage.borrow(), &task.id));
+    if let Some(canister_id) = task_canister {
+        verify_external_task(canister_id, &task.id, caller, request.proof.clone()).await?;
+        // The task may have been completed, or may have expired, while the call was in flight
+        check_task_completion(&request, caller)?;
+    }
+    Ok(credit_task_completion(&task, caller))
+}
+
+// Whether the caller may complete the task now; returns its definition
+fn check_task_completion(request: &CompleteTaskRequest, caller: Principal) -> SquareResult<TaskDefinition> {
     let now = time() / 1_000_000;
     // Get task info from main storage
     let task_info = STORAGE.with(|storage| {
@@ -154,6 +142,9 @@ pub fn complete_task(request: CompleteTaskRequest, caller: Principal) -> SquareR
     };
     
     let _proof = request.task_id.clone();
+    // Daily tasks reset at the start of the user's local day
+    let offset_minutes = user_timezone_offset(&caller);
+    let today_start = local_day_start(now, offset_minutes);
     
     // Check if the task has an expiration time and if it has expired in main storage
     // This check needs to happen for ALL tasks, including custom tasks
@@ -163,7 +154,6 @@ pub fn complete_task(request: CompleteTaskRequest, caller: Principal) -> SquareR
         if let Some(user_tasks) = store.user_tasks.get(&caller) {
             if request.task_id.starts_with("daily_") {
                 // For daily tasks, check if completed today
-                let today_start = (now / SECONDS_IN_DAY) * SECONDS_IN_DAY;
                 if let Some(completion_time) = user_tasks.completed_tasks.get(&request.task_id) {
                     return *completion_time >= today_start;
                 }
@@ -184,28 +174,19 @@ pub fn complete_task(request: CompleteTaskRequest, caller: Principal) -> SquareR
     // Check task expiration
     let task_expired = STORAGE.with(|storage| {
         let store = storage.borrow();
-        if let Some(tasks) = &store.tasks {
-            if let Some(task) = tasks.get(&request.task_id) {
-                if let Some(expiry) = task.expiration_time {
-                    // 确保时间单位一致，统一使用毫秒
-                    let expiry_ms = expiry / 1_000_000;
-                    if expiry_ms < now {
-                        ic_cdk::println!("Task {} has expired: expiry={}, now={}", request.task_id, expiry_ms, now);
-                        return true;
-                    }
+        if let Some(task) = find_task_definition(&store, &request.task_id) {
+            if let Some(expiry) = task.expiration_time {
+                // expiration_time is stored in milliseconds, like `now`
+                if expiry < now {
+                    ic_cdk::println!("Task {} has expired: expiry={}, now={}", request.task_id, expiry, now);
+                    return true;
                 }
-                return false;
             }
+            return false;
         }
         
-        // If task not found in store, check if it's a default task
-        match request.task_id.as_str() {
-            "daily_post" | "social_engagement" => false,
-            _ => {
-                ic_cdk::println!("Task {} not found and not a default task", request.task_id);
-                true // Unknown task is considered expired
-            }
-        }
+        ic_cdk::println!("Task {} not found", request.task_id);
+        true // Unknown task is considered expired
     });
 
     if task_expired {
@@ -215,28 +196,15 @@ pub fn complete_task(request: CompleteTaskRequest, caller: Principal) -> SquareR
     }
         
     // Now get the task details from main storage
-    let (task_reward, task_type, _expiration_time) = STORAGE.with(|storage| {
-        let store = storage.borrow();
-        if let Some(tasks) = &store.tasks {
-            if let Some(task) = tasks.get(&request.task_id) {
-                Ok((task.points, task.task_type.clone(), task.expiration_time))
-            } else {
-                // Check for hardcoded tasks from get_available_tasks
-                match request.task_id.as_str() {
-                    "daily_post" => Ok((50, TaskType::Daily, None)),
-                    "social_engagement" => Ok((100, TaskType::Daily, None)),
-                    _ => Err(SquareError::NotFound(format!("Task with ID {} not found", request.task_id)))
-                }
-            }
-        } else {
-            // Check for hardcoded tasks from get_available_tasks
-            match request.task_id.as_str() {
-                "daily_post" => Ok((50, TaskType::Daily, None)),
-                "social_engagement" => Ok((100, TaskType::Daily, None)),
-                _ => Err(SquareError::NotFound(format!("Task with ID {} not found", request.task_id)))
-            }
-        }
-    })?;
+    let task = STORAGE.with(|storage| find_task_definition(&storage.borrow(), &request.task_id))
+        .ok_or_else(|| SquareError::NotFound(format!("Task with ID {} not found", request.task_id)))?;
+    let task_type = task.task_type.clone();
+    if !STORAGE.with(|storage| in_task_audience(&storage.borrow(), &task, caller, now)) {
+        return Err(SquareError::InvalidOperation(format!("Task {} is not offered to you", request.task_id)));
+    }
+    if !in_local_period(&task, offset_minutes, now) {
+        return Err(SquareError::InvalidOperation(format!("Task {} is not open on your local date", request.task_id)));
+    }
     
     // Get user tasks
     let user_tasks_exists = STORAGE.with(|storage| {
@@ -274,16 +242,10 @@ pub fn complete_task(request: CompleteTaskRequest, caller: Principal) -> SquareR
                 match task_type {
                     TaskType::Daily => {
                         // For daily tasks, check if THIS SPECIFIC daily task was completed today
-                        // Using day_id approach to prevent multiple completions of the same daily task in the same day
-                        let current_day_id = time() / 1_000_000_000 / 86400;
-                        
-                        // Check if this specific task was completed today
+                        // Compare against the start of the user's local day to prevent multiple completions of the same daily task in the same day
                         if let Some(completion_time) = user_tasks.completed_tasks.get(&request.task_id) {
-                            // Convert completion time to day_id
-                            let completion_day_id = completion_time / 1_000_000_000 / 86400;
-                            
-                            // If the completion day_id matches current day_id, task was already completed today
-                            if completion_day_id == current_day_id {
+                            // If the completion falls within the user's current local day, task was already completed today
+                            if *completion_time >= today_start {
                                 return true; // Already completed this specific daily task today
                             }
                         }
@@ -292,12 +254,12 @@ pub fn complete_task(request: CompleteTaskRequest, caller: Principal) -> SquareR
                     TaskType::Weekly => {
                         // For weekly tasks, check if ANY weekly task was completed this week
                         let now = time() / 1_000_000;
-                        let week_start = now - (now % (SECONDS_IN_DAY * 7));
+                        let week_start = now - (now % MILLIS_PER_WEEK);
                         
                         // Check if this specific task was completed this week
                         if user_tasks.completed_tasks.contains_key(&request.task_id) {
                             let completion_time = *user_tasks.completed_tasks.get(&request.task_id).unwrap();
-                            let completion_week = completion_time / 1_000_000 - (completion_time / 1_000_000 % (SECONDS_IN_DAY * 7));
+                            let completion_week = completion_time - (completion_time % MILLIS_PER_WEEK);
                             if completion_week == week_start {
                                 return true;
                             }
@@ -317,25 +279,15 @@ pub fn complete_task(request: CompleteTaskRequest, caller: Principal) -> SquareR
     
     // Validate the proof based on task type
     if let Some(proof_str) = &request.proof {
-        // For tasks that require proof, validate it
-        match request.task_id.as_str() {
-            "daily_post" => {
-                if proof_str.is_empty() {
-                    return Err(SquareError::ValidationFailed("Invalid proof for daily_post task".to_string()));
-                }
-            },
-            _ => {
-                // For other tasks, just ensure proof is not empty when provided
-                if proof_str.is_empty() {
-                    return Err(SquareError::ValidationFailed(format!("Invalid proof for task {}", request.task_id)));
-                }
-            }
+        // Ensure proof is not empty when provided
+        if proof_str.is_empty() {
+            return Err(SquareError::ValidationFailed(format!("Invalid proof for task {}", request.task_id)));
         }
     } else {
         // Some tasks might not require proof
         match request.task_id.as_str() {
-            "social_engagement" => {}, // No proof needed
             "daily_checkin" => {}, // No proof needed
+            _ if template_proof_required(&task) == Some(false) => {}, // The task's template waives it
             _ => {
                 // For other tasks, proof is required
                 return Err(SquareError::ValidationFailed(format!("Proof is required for task {}", request.task_id)));
@@ -343,6 +295,32 @@ pub fn complete_task(request: CompleteTaskRequest, caller: Principal) -> SquareR
         }
     }
     
+    // Social interaction requirements are checked against what the caller did today
+    let social_requirement = STORAGE.with(|storage| {
+        find_task_definition(&storage.borrow(), &request.task_id)
+            .and_then(|task| task.requirements)
+            .and_then(|requirements| requirements.social_interaction)
+    });
+    if let Some(requirement) = social_requirement {
+        let progress = get_task_progress(caller)?;
+        let likes_required = requirement.like_count.unwrap_or(0);
+        let follows_required = requirement.follow_count.unwrap_or(0);
+        if progress.likes_given < likes_required || progress.follows < follows_required {
+            return Err(SquareError::ValidationFailed(format!(
+                "Task {} requires {} likes and {} follows today; you have {} and {}",
+                request.task_id, likes_required, follows_required, progress.likes_given, progress.follows
+            )));
+        }
+    }
+    
+    Ok(task)
+}
+
+// Record the completion and award the task's points
+fn credit_task_completion(task: &TaskDefinition, caller: Principal) -> TaskCompletionResponse {
+    let now = time() / 1_000_000;
+    let task_reward = task.points;
+    
     // Mark task as completed
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
@@ -365,63 +343,29 @@ pub fn complete_task(request: CompleteTaskRequest, caller: Principal) -> SquareR
             };
             
             let mut updated_tasks = user_tasks.clone();
-            updated_tasks.completed_tasks.insert(request.task_id.clone(), now);
+            updated_tasks.completed_tasks.insert(task.id.clone(), now);
             updated_tasks.last_updated = now;
             
             store.user_tasks.insert(caller, updated_tasks);
     });
     
     // Award points
-    let points = STORAGE.with(|storage| {
-        let mut store = storage.borrow_mut();
-        
-        // Ensure user_rewards exists
-        if store.user_rewards.is_empty() {
-            store.user_rewards = HashMap::new();
-        }
-        
-        // Get user rewards
-        let user_rewards = match store.user_rewards.get(&caller) {
-            Some(rewards) => rewards.clone(),
-            None => UserRewards {
-                principal: caller,
-                points: 0,
-                points_history: Vec::new(),
-                last_claim_date: None,
-                // consecutive_daily_logins field has been moved to daily_checkin_task canister
-                transactions: Vec::new(),
-                last_updated: now,
-            }
-        };
-        
-        let mut updated_rewards = user_rewards.clone();
-        
-        // Add points
-        updated_rewards.points += task_reward;
-        
-        // Record transaction
-        updated_rewards.points_history.push(PointsTransaction {
-            amount: task_reward as i64,
-            reason: format!("Completed task: {}", request.task_id),
-            timestamp: now,
-            reference_id: Some(request.task_id.clone()),
-            points: task_reward,
-        });
-        
-        updated_rewards.last_updated = now;
-        
-        // Save updated rewards and return points
-        store.user_rewards.insert(caller, updated_rewards.clone());
-        updated_rewards.points
-    });
+    let points = credit_points(
+        caller,
+        task_reward,
+        format!("Completed task: {}", task.id),
+        Some(task.id.clone())
+    );
+    // Let a partner's canister know, so it can grant its own reward
+    notify_task_completion(task, caller, task_reward, now);
         
     // Return the response
-    Ok(TaskCompletionResponse {
+    TaskCompletionResponse {
         success: true,
         points_earned: task_reward,
         total_points: points,
-        message: format!("You earned {} points for completing {}", task_reward, request.task_id),
-    })
+        message: format!("You earned {} points for completing {}", task_reward, task.id),
+    }
 }
 
 // Get user rewards
@@ -586,11 +530,22 @@ pub fn get_available_tasks(caller: Principal) -> SquareResult<Vec<TaskResponse>>
     
     let mut tasks = Vec::new();
     let now = time() / 1_000_000;
+    let offset_minutes = user_timezone_offset(&caller);
+    let today_start = local_day_start(now, offset_minutes);
     
-    // Get all tasks from main storage
+    // Get all tasks from main storage, plus tasks synced from registered task canisters
     let task_definitions = STORAGE.with(|storage| {
         let store = storage.borrow();
-        store.tasks.clone().unwrap_or_default()
+        let mut definitions: Vec<(String, TaskDefinition)> = store.tasks.clone().unwrap_or_default().into_iter().collect();
+        for task in external_task_definitions(&store) {
+            // Built-in tasks take precedence over external tasks with the same ID
+            if !definitions.iter().any(|(task_id, _)| task_id == &task.id) {
+                definitions.push((task.id.clone(), task));
+            }
+        }
+        // Targeted tasks are only listed for their segments, and template instances on the dates of their period
+        definitions.retain(|(_, task)| in_task_audience(&store, task, caller, now) && in_local_period(task, offset_minutes, now));
+        definitions
     });
     
     // Iterate through all task definitions
@@ -611,19 +566,18 @@ pub fn get_available_tasks(caller: Principal) -> SquareResult<Vec<TaskResponse>>
         let is_completed = if let Some(ut) = &user_tasks {
             if task_def.task_type == TaskType::Daily {
                 // For daily tasks, check if completed today
-                let today_start = now - (now % SECONDS_IN_DAY);
                 ut.completed_tasks.get(&task_id)
                     .map(|completion_time| *completion_time >= today_start)
                     .unwrap_or(false)
             } else if task_def.task_type == TaskType::Weekly {
                 // For weekly tasks, check if completed this week
-                let week_start = now - (now % (SECONDS_IN_DAY * 7));
+                let week_start = now - (now % MILLIS_PER_WEEK);
                 ut.completed_tasks.get(&task_id)
                     .map(|completion_time| *completion_time >= week_start)
                     .unwrap_or(false)
             } else if task_def.task_type == TaskType::Monthly {
                 // For monthly tasks, check if completed this month (approximate)
-                let month_start = now - (now % (SECONDS_IN_DAY * 30));
+                let month_start = now - (now % (MILLIS_PER_DAY * 30));
                 ut.completed_tasks.get(&task_id)
                     .map(|completion_time| *completion_time >= month_start)
                     .unwrap_or(false)
@@ -646,65 +600,19 @@ pub fn get_available_tasks(caller: Principal) -> SquareResult<Vec<TaskResponse>>
             completion_criteria: task_def.completion_criteria.clone(),
             expiration_time: task_def.expiration_time,
             created_at: task_def.created_at,
+            canister_id: task_def.canister_id,
         });
     }
     
-    // If no tasks found, add default tasks
-    if tasks.is_empty() {
-        // Initialize tasks if they don't exist yet
-        init_default_tasks_all_enabled();
-        
-        // Try again with default tasks
-        return get_available_tasks(caller);
-    }
-    
     Ok(tasks)
 }
 
 // Admin functions
-pub fn award_points(request: AwardPointsRequest) -> SquareResult<()> {
-    // Check if caller is admin or manager
-    is_manager_or_admin()?;
+// Credit points to a user and record the transaction; returns the new balance.
+// Shared by task completion, quizzes and manual awards. The points are minted in the ledger.
+pub fn credit_points(principal: Principal, points: u64, reason: String, reference_id: Option<String>) -> u64 {
     let now = time() / 1_000_000;
-    let _points = STORAGE.with(|storage| {
-        let mut store = storage.borrow_mut();
-        let user_rewards = match store.user_rewards.get(&request.principal) {
-            Some(rewards) => rewards.clone(),
-            None => UserRewards {
-                principal: request.principal,
-                points: 0,
-                points_history: Vec::new(),
-                last_claim_date: None,
-            // consecutive_daily_logins field has been moved to daily_checkin_task canister
-            transactions: Vec::new(),
-            last_updated: now,
-        }
-        };
-        
-        let mut updated_rewards = user_rewards.clone();
-        
-        // Add points
-        updated_rewards.points += request.points;
-        
-
-        
-        // Record transaction
-        updated_rewards.points_history.push(PointsTransaction {
-            amount: request.points as i64,
-            reason: request.reason.clone(),
-            timestamp: now,
-            reference_id: request.reference_id.clone(),
-            points: request.points,
-        });
-        
-        updated_rewards.last_updated = now;
-        
-        store.user_rewards.insert(request.principal, updated_rewards.clone());
-        
-        updated_rewards.points
-    });
-    
-    Ok(())
+    STORAGE.with(|storage| mint_points(&mut storage.borrow_mut(), principal, points, reason, reference_id, None, now))
 }
 
 // Task management (admin functions)
@@ -728,6 +636,7 @@ pub fn create_task(request: CreateTaskRequest) -> SquareResult<String> {
     // Use points_reward field for task points
     let points = request.points_reward;
     
+    let audience = normalize_audience(request.audience.unwrap_or_default(), MODULE, FUNCTION)?;
     let task = TaskDefinition {
         id: task_id.clone(),
         title: request.title,
@@ -741,6 +650,10 @@ pub fn create_task(request: CreateTaskRequest) -> SquareResult<String> {
         is_active: true,
         requirements: request.requirements,
         canister_id: request.canister_id,
+        template_id: None,
+        period_start: None,
+        period_end: None,
+        audience,
     };
     
     // Store the task in main storage
@@ -782,6 +695,10 @@ pub fn update_task(request: UpdateTaskRequest) -> SquareResult<()> {
         e
     })?;
     
+    let audience = request.audience
+        .map(|audience| normalize_audience(audience, MODULE, FUNCTION))
+        .transpose()?;
+    
     // Update task in main storage
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
@@ -808,6 +725,9 @@ pub fn update_task(request: UpdateTaskRequest) -> SquareResult<()> {
             task.expiration_time = request.end_time;
             task.updated_at = now;
             task.requirements = request.requirements;
+            if let Some(audience) = audience {
+                task.audience = audience;
+            }
             
             // Save updated task
             tasks.insert(request.id.clone(), task);
@@ -0,0 +1,208 @@
+use candid::Principal;
+use ic_cdk::api::{call, time};
+
+use crate::auth::is_admin;
+use crate::models::content::{PaginationParams, PostResponse, PostsResponse};
+use crate::models::discovery::{SearchRequest, SearchResultResponse};
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::shard::*;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::middleware::ApiResponse;
+use crate::utils::validators::validate_register_content_shard;
+
+const MODULE: &str = "services::shards";
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage content shards",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+pub fn register_content_shard(request: RegisterContentShardRequest, caller: Principal) -> SquareResult<ContentShard> {
+    const FUNCTION: &str = "register_content_shard";
+
+    require_admin("register_content_shard", FUNCTION)?;
+    validate_register_content_shard(&request).finish(MODULE, FUNCTION)?;
+    if request.canister_id == ic_cdk::id() {
+        return log_and_return(invalid_operation_error(
+            "register_content_shard",
+            "This canister is always included in fan-out reads",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+
+        if store.content_shards.contains_key(&request.canister_id) {
+            return log_and_return(already_exists_error(
+                "ContentShard",
+                &request.canister_id.to_string(),
+                MODULE,
+                FUNCTION
+            ));
+        }
+        if store.content_shards.len() >= MAX_CONTENT_SHARDS {
+            return log_and_return(quota_exceeded_error("content_shards", MAX_CONTENT_SHARDS as u64, MODULE, FUNCTION));
+        }
+
+        let shard = ContentShard {
+            canister_id: request.canister_id,
+            name: request.name,
+            enabled: true,
+            registered_by: caller,
+            registered_at: time() / 1_000_000,
+        };
+        store.content_shards.insert(shard.canister_id, shard.clone());
+        Ok(shard)
+    })
+}
+
+pub fn set_content_shard_enabled(canister_id: Principal, enabled: bool) -> SquareResult<ContentShard> {
+    const FUNCTION: &str = "set_content_shard_enabled";
+
+    require_admin("set_content_shard_enabled", FUNCTION)?;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let Some(shard) = store.content_shards.get_mut(&canister_id) else {
+            return log_and_return(not_found_error("ContentShard", &canister_id.to_string(), MODULE, FUNCTION));
+        };
+        shard.enabled = enabled;
+        Ok(shard.clone())
+    })
+}
+
+pub fn unregister_content_shard(canister_id: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "unregister_content_shard";
+
+    require_admin("unregister_content_shard", FUNCTION)?;
+
+    STORAGE.with(|storage| {
+        if storage.borrow_mut().content_shards.remove(&canister_id).is_none() {
+            return log_and_return(not_found_error("ContentShard", &canister_id.to_string(), MODULE, FUNCTION));
+        }
+        Ok(())
+    })
+}
+
+pub fn get_content_shards() -> SquareResult<Vec<ContentShard>> {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let mut shards: Vec<ContentShard> = store.content_shards.values().cloned().collect();
+        shards.sort_by_key(|shard| shard.registered_at);
+        Ok(shards)
+    })
+}
+
+fn enabled_shards() -> Vec<Principal> {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let mut shards: Vec<Principal> = store.content_shards
+            .values()
+            .filter(|shard| shard.enabled)
+            .map(|shard| shard.canister_id)
+            .collect();
+        shards.sort();
+        shards
+    })
+}
+
+// Each shard is asked for its first offset + limit items so the global page can be cut after merging
+fn read_window(pagination: &PaginationParams, function: &str) -> SquareResult<(usize, usize, PaginationParams)> {
+    let offset = pagination.offset.unwrap_or(0);
+    let limit = pagination.limit.unwrap_or(10);
+    let window = offset.saturating_add(limit);
+    if window > MAX_SHARDED_READ_WINDOW {
+        return log_and_return(field_validation_error(
+            "pagination",
+            &format!("offset + limit must be at most {} for cross-shard reads", MAX_SHARDED_READ_WINDOW),
+            MODULE,
+            function
+        ));
+    }
+    Ok((offset, limit, PaginationParams { offset: Some(0), limit: Some(window) }))
+}
+
+// Query one shard, returning None when the call fails or the shard reports an error
+async fn query_shard<A, T>(canister_id: Principal, method: &str, args: A) -> Option<T>
+where
+    A: candid::utils::ArgumentEncoder,
+    T: candid::CandidType + for<'de> candid::Deserialize<'de>,
+{
+    let response: Result<(ApiResponse<T>,), _> = call::call(canister_id, method, args).await;
+    match response {
+        Ok((ApiResponse { success: true, data: Some(data), .. },)) => Some(data),
+        _ => None,
+    }
+}
+
+// Latest posts across this canister and every enabled shard, newest first
+pub async fn get_posts_across_shards(pagination: PaginationParams) -> SquareResult<ShardedPostsResponse> {
+    const FUNCTION: &str = "get_posts_across_shards";
+
+    let (offset, limit, window) = read_window(&pagination, FUNCTION)?;
+
+    let local = crate::services::content::get_posts(window.clone())?;
+    let mut total = local.total;
+    let mut posts: Vec<PostResponse> = local.posts;
+    let mut unavailable_shards = Vec::new();
+
+    for shard in enabled_shards() {
+        match query_shard::<_, PostsResponse>(shard, "get_posts_v2", (window.clone(),)).await {
+            Some(response) => {
+                total += response.total;
+                posts.extend(response.posts);
+            }
+            None => unavailable_shards.push(shard),
+        }
+    }
+
+    posts.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+    let posts: Vec<PostResponse> = posts.into_iter().skip(offset).take(limit).collect();
+
+    Ok(ShardedPostsResponse {
+        next_offset: offset + posts.len(),
+        posts,
+        total,
+        unavailable_shards,
+    })
+}
+
+// Search across this canister and every enabled shard, most relevant first
+pub async fn search_content_across_shards(request: SearchRequest) -> SquareResult<ShardedSearchResponse> {
+    const FUNCTION: &str = "search_content_across_shards";
+
+    let (offset, limit, window) = read_window(&request.pagination, FUNCTION)?;
+    let shard_request = SearchRequest { pagination: window, ..request };
+
+    let mut results: Vec<SearchResultResponse> = crate::services::discovery::search_content(shard_request.clone())?;
+    let mut unavailable_shards = Vec::new();
+
+    for shard in enabled_shards() {
+        match query_shard::<_, Vec<SearchResultResponse>>(shard, "search_content_v2", (shard_request.clone(),)).await {
+            Some(shard_results) => results.extend(shard_results),
+            None => unavailable_shards.push(shard),
+        }
+    }
+
+    results.sort_by(|a, b| {
+        b.relevance_score
+            .total_cmp(&a.relevance_score)
+            .then_with(|| b.created_at.cmp(&a.created_at))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    Ok(ShardedSearchResponse {
+        results: results.into_iter().skip(offset).take(limit).collect(),
+        unavailable_shards,
+    })
+}
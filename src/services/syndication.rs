@@ -0,0 +1,252 @@
+use candid::Principal;
+use ic_cdk::api::management_canister::main::raw_rand;
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::auth::is_admin;
+use crate::models::content::{ContentStatus, ContentVisibility};
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::syndication::*;
+use crate::services::content::rendering::rendered_html;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::MILLIS_PER_HOUR;
+use crate::utils::validators::validate_register_syndication_partner;
+
+const MODULE: &str = "services::syndication";
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage syndication partners",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+fn hash_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Partner tokens come from the management canister's randomness so they cannot be guessed
+pub async fn register_syndication_partner(request: RegisterSyndicationPartnerRequest, caller: Principal) -> SquareResult<SyndicationPartnerCredentials> {
+    const FUNCTION: &str = "register_syndication_partner";
+
+    require_admin("register_syndication_partner", FUNCTION)?;
+    validate_register_syndication_partner(&request).finish(MODULE, FUNCTION)?;
+    if STORAGE.with(|storage| storage.borrow().syndication_partners.len()) >= MAX_SYNDICATION_PARTNERS {
+        return log_and_return(quota_exceeded_error("syndication_partners", MAX_SYNDICATION_PARTNERS as u64, MODULE, FUNCTION));
+    }
+
+    let (random_bytes,) = match raw_rand().await {
+        Ok(bytes) => bytes,
+        Err((_, message)) => {
+            return log_and_return(service_unavailable_error(
+                "raw_rand",
+                &message,
+                MODULE,
+                FUNCTION
+            ));
+        }
+    };
+    let token: String = random_bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let partner = SyndicationPartner {
+            id: format!("partner_{}_{}", now, store.syndication_partners.len() + 1),
+            name: request.name,
+            canister_id: request.canister_id,
+            token_hash: hash_token(&token),
+            attribution_text: request.attribution_text,
+            requests_per_hour: request.requests_per_hour.unwrap_or(DEFAULT_SYNDICATION_REQUESTS_PER_HOUR),
+            enabled: true,
+            created_by: caller,
+            created_at: now,
+            last_request_at: None,
+        };
+        store.syndication_partners.insert(partner.id.clone(), partner.clone());
+        Ok(SyndicationPartnerCredentials { partner, token })
+    })
+}
+
+pub fn update_syndication_partner(request: UpdateSyndicationPartnerRequest) -> SquareResult<SyndicationPartner> {
+    const FUNCTION: &str = "update_syndication_partner";
+
+    require_admin("update_syndication_partner", FUNCTION)?;
+    if request.attribution_text.as_ref().is_some_and(|text| text.is_empty() || text.chars().count() > MAX_ATTRIBUTION_TEXT_LENGTH) {
+        return log_and_return(field_validation_error(
+            "attribution_text",
+            &format!("must be between 1 and {} characters", MAX_ATTRIBUTION_TEXT_LENGTH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if request.requests_per_hour == Some(0) {
+        return log_and_return(field_validation_error(
+            "requests_per_hour",
+            "must be greater than 0",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let Some(partner) = store.syndication_partners.get_mut(&request.id) else {
+            return log_and_return(not_found_error("SyndicationPartner", &request.id, MODULE, FUNCTION));
+        };
+
+        if let Some(enabled) = request.enabled {
+            partner.enabled = enabled;
+        }
+        if let Some(attribution_text) = request.attribution_text {
+            partner.attribution_text = attribution_text;
+        }
+        if let Some(requests_per_hour) = request.requests_per_hour {
+            partner.requests_per_hour = requests_per_hour;
+        }
+        Ok(partner.clone())
+    })
+}
+
+pub fn remove_syndication_partner(id: String) -> SquareResult<()> {
+    const FUNCTION: &str = "remove_syndication_partner";
+
+    require_admin("remove_syndication_partner", FUNCTION)?;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.syndication_partners.remove(&id).is_none() {
+            return log_and_return(not_found_error("SyndicationPartner", &id, MODULE, FUNCTION));
+        }
+        store.syndication_requests.remove(&id);
+        Ok(())
+    })
+}
+
+pub fn get_syndication_partners() -> SquareResult<Vec<SyndicationPartner>> {
+    const FUNCTION: &str = "get_syndication_partners";
+
+    require_admin("get_syndication_partners", FUNCTION)?;
+
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let mut partners: Vec<SyndicationPartner> = store.syndication_partners.values().cloned().collect();
+        partners.sort_by_key(|partner| partner.created_at);
+        Ok(partners)
+    })
+}
+
+// Authenticate the partner token and count the request against its hourly limit
+fn authorize_partner(partner_token: &str, caller: Principal, now: u64) -> SquareResult<(SyndicationPartner, u32)> {
+    const FUNCTION: &str = "authorize_partner";
+
+    let token_hash = hash_token(partner_token);
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let Some(partner) = store.syndication_partners
+            .values_mut()
+            .find(|partner| partner.token_hash == token_hash && partner.enabled)
+        else {
+            return log_and_return(permission_denied_error(
+                "get_syndication_feed",
+                "Invalid or disabled partner token",
+                MODULE,
+                FUNCTION
+            ));
+        };
+        if partner.canister_id.is_some_and(|canister_id| canister_id != caller) {
+            return log_and_return(permission_denied_error(
+                "get_syndication_feed",
+                "This partner token is bound to a different canister",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        partner.last_request_at = Some(now);
+        let partner = partner.clone();
+
+        let requests = store.syndication_requests.entry(partner.id.clone()).or_default();
+        requests.retain(|timestamp| timestamp + MILLIS_PER_HOUR > now);
+        if requests.len() >= partner.requests_per_hour as usize {
+            return log_and_return(rate_limit_error("get_syndication_feed", partner.requests_per_hour as u64, MODULE, FUNCTION));
+        }
+        requests.push(now);
+        let remaining = partner.requests_per_hour - requests.len() as u32;
+        Ok((partner, remaining))
+    })
+}
+
+// Public, active, non-premium posts after the `(since, since_id)` cursor in (updated_at, id) order,
+// oldest first so partners can sync incrementally. The id breaks ties between posts updated in the
+// same millisecond, which would otherwise be skipped when a page ends among them.
+pub fn get_syndication_feed(
+    partner_token: String,
+    since: Option<u64>,
+    since_id: Option<String>,
+    limit: Option<usize>,
+    caller: Principal
+) -> SquareResult<SyndicationFeedResponse> {
+    let now = time() / 1_000_000;
+    let (partner, requests_remaining) = authorize_partner(&partner_token, caller, now)?;
+    let limit = limit.unwrap_or(20).clamp(1, MAX_SYNDICATION_FEED_LIMIT);
+
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let mut posts: Vec<_> = store.posts
+            .values()
+            .filter(|post| post.status == ContentStatus::Active
+                && post.visibility == ContentVisibility::Public
+                && !post.is_premium
+                && post.token_gate.is_none()
+                && since.is_none_or(|since| match &since_id {
+                    Some(since_id) => (post.updated_at, &post.id) > (since, since_id),
+                    None => post.updated_at > since,
+                }))
+            .collect();
+        posts.sort_by(|a, b| a.updated_at.cmp(&b.updated_at).then_with(|| a.id.cmp(&b.id)));
+        let has_more = posts.len() > limit;
+        posts.truncate(limit);
+
+        let items: Vec<SyndicatedContent> = posts
+            .into_iter()
+            .map(|post| {
+                let profile = store.user_profiles.as_ref().and_then(|profiles| profiles.get(&post.author));
+                let author_name = profile.map_or_else(|| post.author.to_string(), |profile| profile.username.clone());
+                SyndicatedContent {
+                    id: post.id.clone(),
+                    title: post.title.clone(),
+                    body: rendered_html(&store, &post.id, post.format, &post.content),
+                    media_urls: post.media_urls.clone(),
+                    hashtags: post.hashtags.clone(),
+                    author: post.author,
+                    author_handle: profile.map(|profile| profile.handle.clone()).unwrap_or_default(),
+                    attribution: format!("By {} via {}", author_name, partner.attribution_text),
+                    author_name,
+                    license: post.license,
+                    published_at: post.created_at,
+                    updated_at: post.updated_at,
+                }
+            })
+            .collect();
+
+        let (next_since, next_since_id) = match items.last() {
+            Some(item) => (Some(item.updated_at), Some(item.id.clone())),
+            None => (since, since_id.clone()),
+        };
+        Ok(SyndicationFeedResponse {
+            next_since,
+            next_since_id,
+            items,
+            has_more,
+            requests_remaining,
+        })
+    })
+}
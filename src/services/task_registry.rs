@@ -0,0 +1,295 @@
+use candid::Principal;
+use ic_cdk::api::{call, time};
+
+use crate::auth::is_admin;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::reward::{TaskDefinition, TaskVerificationRequest, TaskVerificationResponse};
+use crate::models::storage::Storage;
+use crate::models::task_registry::*;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::middleware::handle_canister_error;
+use crate::utils::validators::{validate_register_task_canister, validate_update_task_canister};
+
+const MODULE: &str = "services::task_registry";
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage task canisters",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+pub fn register_task_canister(request: RegisterTaskCanisterRequest, caller: Principal) -> SquareResult<TaskCanisterRegistration> {
+    const FUNCTION: &str = "register_task_canister";
+
+    require_admin("register_task_canister", FUNCTION)?;
+    validate_register_task_canister(&request).finish(MODULE, FUNCTION)?;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+
+        if store.task_canisters.contains_key(&request.canister_id) {
+            return log_and_return(already_exists_error(
+                "TaskCanister",
+                &request.canister_id.to_string(),
+                MODULE,
+                FUNCTION
+            ));
+        }
+
+        let registration = TaskCanisterRegistration {
+            canister_id: request.canister_id,
+            name: request.name,
+            description: request.description,
+            kind: request.kind,
+            min_points: request.min_points,
+            max_points: request.max_points,
+            enabled: true,
+            registered_by: caller,
+            registered_at: time() / 1_000_000,
+            last_synced_at: None,
+            last_sync_error: None,
+            webhook_enabled: false,
+        };
+        store.task_canisters.insert(registration.canister_id, registration.clone());
+        Ok(registration)
+    })
+}
+
+pub fn update_task_canister(request: UpdateTaskCanisterRequest) -> SquareResult<TaskCanisterRegistration> {
+    const FUNCTION: &str = "update_task_canister";
+
+    require_admin("update_task_canister", FUNCTION)?;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+
+        let Some(mut registration) = store.task_canisters.get(&request.canister_id).cloned() else {
+            return log_and_return(not_found_error(
+                "TaskCanister",
+                &request.canister_id.to_string(),
+                MODULE,
+                FUNCTION
+            ));
+        };
+
+        validate_update_task_canister(&request, &registration).finish(MODULE, FUNCTION)?;
+
+        if let Some(name) = request.name {
+            registration.name = name;
+        }
+        if let Some(description) = request.description {
+            registration.description = description;
+        }
+        if let Some(min_points) = request.min_points {
+            registration.min_points = min_points;
+        }
+        if let Some(max_points) = request.max_points {
+            registration.max_points = max_points;
+        }
+        if let Some(enabled) = request.enabled {
+            registration.enabled = enabled;
+        }
+
+        // Drop cached tasks that no longer fit the allowed points range
+        if let Some(tasks) = store.external_tasks.get_mut(&registration.canister_id) {
+            tasks.retain(|task| (registration.min_points..=registration.max_points).contains(&task.points));
+        }
+
+        store.task_canisters.insert(registration.canister_id, registration.clone());
+        Ok(registration)
+    })
+}
+
+pub fn unregister_task_canister(canister_id: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "unregister_task_canister";
+
+    require_admin("unregister_task_canister", FUNCTION)?;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.task_canisters.remove(&canister_id).is_none() {
+            return log_and_return(not_found_error(
+                "TaskCanister",
+                &canister_id.to_string(),
+                MODULE,
+                FUNCTION
+            ));
+        }
+        store.external_tasks.remove(&canister_id);
+        store.task_webhook_secrets.remove(&canister_id);
+        store.task_webhook_deliveries.retain(|delivery| delivery.canister_id != canister_id);
+        Ok(())
+    })
+}
+
+pub fn get_task_canisters() -> SquareResult<Vec<TaskCanisterRegistration>> {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let mut canisters: Vec<TaskCanisterRegistration> = store.task_canisters.values().cloned().collect();
+        canisters.sort_by_key(|registration| registration.registered_at);
+        Ok(canisters)
+    })
+}
+
+// Tasks cached from enabled task canisters
+pub fn external_task_definitions(store: &Storage) -> Vec<TaskDefinition> {
+    store.external_tasks
+        .iter()
+        .filter(|(canister_id, _)| store.task_canisters.get(canister_id).is_some_and(|registration| registration.enabled))
+        .flat_map(|(_, tasks)| tasks.iter().cloned())
+        .collect()
+}
+
+// Look up a task among local definitions first, then tasks cached from task canisters
+pub fn find_task_definition(store: &Storage, task_id: &str) -> Option<TaskDefinition> {
+    store.tasks
+        .as_ref()
+        .and_then(|tasks| tasks.get(task_id).cloned())
+        .or_else(|| external_task_definitions(store).into_iter().find(|task| task.id == task_id))
+}
+
+// The registered canister a task was synced from; None for the canister's own tasks
+pub fn task_canister_of(store: &Storage, task_id: &str) -> Option<Principal> {
+    if store.tasks.as_ref().is_some_and(|tasks| tasks.contains_key(task_id)) {
+        return None;
+    }
+    store.external_tasks
+        .iter()
+        .find(|(_, tasks)| tasks.iter().any(|task| task.id == task_id))
+        .map(|(canister_id, _)| *canister_id)
+}
+
+// Ask the task canister whether `user` completed one of its tasks, through its
+// `verify_task(TaskVerificationRequest) -> Result<TaskVerificationResponse, text>`
+pub async fn verify_external_task(canister_id: Principal, task_id: &str, user: Principal, proof: Option<String>) -> SquareResult<()> {
+    const FUNCTION: &str = "verify_external_task";
+
+    let request = TaskVerificationRequest {
+        user,
+        task_id: task_id.to_string(),
+        timestamp: time() / 1_000_000,
+        proof,
+    };
+    let response: Result<(Result<TaskVerificationResponse, String>,), _> =
+        call::call(canister_id, "verify_task", (request,)).await;
+    let rejection = match handle_canister_error(response, MODULE, FUNCTION)? {
+        (Ok(verification),) if verification.success
+            && verification.verification_data.as_ref().is_none_or(|data| data.task_id == task_id) => return Ok(()),
+        (Ok(verification),) => verification.message,
+        (Err(message),) => message,
+    };
+    log_and_return(field_validation_error(
+        "proof",
+        &format!("The task canister did not verify task {}: {}", task_id, rejection),
+        MODULE,
+        FUNCTION
+    ))
+}
+
+// Admin-triggered sync of every enabled task canister
+pub async fn sync_task_canisters() -> SquareResult<Vec<TaskCanisterSyncResult>> {
+    const FUNCTION: &str = "sync_task_canisters";
+
+    require_admin("sync_task_canisters", FUNCTION)?;
+    Ok(sync_all_task_canisters().await)
+}
+
+pub async fn sync_all_task_canisters() -> Vec<TaskCanisterSyncResult> {
+    let canister_ids: Vec<Principal> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.task_canisters
+            .values()
+            .filter(|registration| registration.enabled)
+            .map(|registration| registration.canister_id)
+            .collect()
+    });
+
+    let mut results = Vec::with_capacity(canister_ids.len());
+    for canister_id in canister_ids {
+        results.push(sync_task_canister(canister_id).await);
+    }
+    results
+}
+
+// Fetch a canister's task definitions and cache the ones within its allowed points range
+async fn sync_task_canister(canister_id: Principal) -> TaskCanisterSyncResult {
+    let response: Result<(Vec<ExternalTaskDefinition>,), _> =
+        call::call(canister_id, "get_task_definitions", ()).await;
+    let now = time() / 1_000_000;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+
+        // The canister may have been removed while the call was in flight
+        let Some(registration) = store.task_canisters.get(&canister_id).cloned() else {
+            return TaskCanisterSyncResult {
+                canister_id,
+                tasks_synced: 0,
+                tasks_rejected: 0,
+                error: Some("Task canister is no longer registered".to_string()),
+            };
+        };
+
+        let definitions = match response {
+            Ok((definitions,)) => definitions,
+            Err((code, message)) => {
+                let error = format!("get_task_definitions failed: {:?} {}", code, message);
+                if let Some(registration) = store.task_canisters.get_mut(&canister_id) {
+                    registration.last_sync_error = Some(error.clone());
+                }
+                return TaskCanisterSyncResult {
+                    canister_id,
+                    tasks_synced: 0,
+                    tasks_rejected: 0,
+                    error: Some(error),
+                };
+            }
+        };
+
+        let total = definitions.len();
+        let tasks: Vec<TaskDefinition> = definitions
+            .into_iter()
+            .filter(|definition| (registration.min_points..=registration.max_points).contains(&definition.points))
+            .take(MAX_TASKS_PER_CANISTER)
+            .map(|definition| TaskDefinition {
+                id: definition.id,
+                title: definition.title,
+                description: definition.description,
+                points: definition.points,
+                task_type: definition.task_type,
+                completion_criteria: definition.completion_criteria,
+                expiration_time: definition.expiration_time,
+                created_at: now,
+                updated_at: now,
+                is_active: true,
+                requirements: None,
+                canister_id,
+                template_id: None,
+                period_start: None,
+                period_end: None,
+                audience: Vec::new(),
+            })
+            .collect();
+
+        let tasks_synced = tasks.len() as u64;
+        store.external_tasks.insert(canister_id, tasks);
+        if let Some(registration) = store.task_canisters.get_mut(&canister_id) {
+            registration.last_synced_at = Some(now);
+            registration.last_sync_error = None;
+        }
+
+        TaskCanisterSyncResult {
+            canister_id,
+            tasks_synced,
+            tasks_rejected: total as u64 - tasks_synced,
+            error: None,
+        }
+    })
+}
@@ -0,0 +1,334 @@
+use candid::Principal;
+use std::collections::HashMap;
+
+use crate::auth::is_manager_or_admin;
+use crate::models::error::SquareResult;
+use crate::models::reward::TaskDefinition;
+use crate::models::storage::Storage;
+use crate::models::task_template::*;
+use crate::models::user::{MAX_TIMEZONE_OFFSET_MINUTES, MIN_TIMEZONE_OFFSET_MINUTES};
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::{civil_date, day_start, local_date, now_millis, weekday, TimestampMillis, MILLIS_PER_DAY, MILLIS_PER_MINUTE};
+use crate::utils::validators::{validate_create_task_template, validate_update_task_template};
+
+const MODULE: &str = "services::task_template";
+
+fn require_manager(operation: &str, function: &str) -> SquareResult<()> {
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only managers or admins can manage task templates",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+fn matches_day(recurrence: &TaskRecurrence, day: TimestampMillis) -> bool {
+    match recurrence {
+        TaskRecurrence::Daily => true,
+        TaskRecurrence::Weekly { weekday: scheduled } => weekday(day) == *scheduled,
+        TaskRecurrence::Cron { weekdays, month_days } => {
+            let weekday_matches = weekdays.contains(&weekday(day));
+            let month_day_matches = month_days.contains(&(civil_date(day).2 as u8));
+            match (weekdays.is_empty(), month_days.is_empty()) {
+                (true, true) => true,
+                (false, true) => weekday_matches,
+                (true, false) => month_day_matches,
+                (false, false) => weekday_matches || month_day_matches,
+            }
+        }
+    }
+}
+
+// Start of the period containing `now`
+fn period_start(recurrence: &TaskRecurrence, now: TimestampMillis) -> Option<TimestampMillis> {
+    let today = day_start(now);
+    (0..RECURRENCE_SEARCH_DAYS)
+        .map(|days_back| today.saturating_sub(days_back * MILLIS_PER_DAY))
+        .find(|day| matches_day(recurrence, *day))
+}
+
+fn next_period_start(recurrence: &TaskRecurrence, now: TimestampMillis) -> Option<TimestampMillis> {
+    let today = day_start(now);
+    (1..=RECURRENCE_SEARCH_DAYS)
+        .map(|days_ahead| today + days_ahead * MILLIS_PER_DAY)
+        .find(|day| matches_day(recurrence, *day))
+}
+
+// Instances are named after their template and the date their period starts on, e.g. daily_post_20260105
+fn instance_id(template_id: &str, period_start: TimestampMillis) -> String {
+    let (year, month, day) = civil_date(period_start);
+    format!("{}_{:04}{:02}{:02}", template_id, year, month, day)
+}
+
+fn instantiate(template: &TaskTemplate, period_start: TimestampMillis, period_end: Option<TimestampMillis>, now: TimestampMillis) -> TaskDefinition {
+    TaskDefinition {
+        id: instance_id(&template.id, period_start),
+        title: template.title.clone(),
+        description: template.description.clone(),
+        points: template.points,
+        task_type: template.recurrence.task_type(),
+        completion_criteria: template.completion_criteria.clone(),
+        // The period is over once it has ended in the timezone furthest behind UTC
+        expiration_time: period_end.map(|end| end + MIN_TIMEZONE_OFFSET_MINUTES.unsigned_abs() as u64 * MILLIS_PER_MINUTE),
+        created_at: now,
+        updated_at: now,
+        is_active: true,
+        requirements: template.requirements.clone(),
+        canister_id: ic_cdk::id(),
+        template_id: Some(template.id.clone()),
+        period_start: Some(period_start),
+        period_end,
+        audience: Vec::new(),
+    }
+}
+
+// Start the instance of every enabled template whose next period has begun somewhere. A period
+// begins in the timezone furthest ahead of UTC first, so the previous instance stays open until the
+// period has ended in every timezone too. Runs every heartbeat and only looks at the calendar for
+// templates that are due.
+pub fn generate_task_instances() {
+    let now = now_millis();
+    let furthest_ahead = now + MAX_TIMEZONE_OFFSET_MINUTES as u64 * MILLIS_PER_MINUTE;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let tasks = store.tasks.get_or_insert_with(HashMap::new);
+        for template in store.task_templates.values_mut() {
+            template.earlier_instances.retain(|instance| {
+                let open = tasks.get(instance).is_some_and(|task| task.expiration_time.is_none_or(|expiry| now <= expiry));
+                if !open {
+                    tasks.remove(instance);
+                }
+                open
+            });
+            if !template.enabled || template.next_period_start.is_some_and(|next| furthest_ahead < next) {
+                continue;
+            }
+            let Some(start) = period_start(&template.recurrence, furthest_ahead) else {
+                continue;
+            };
+            let end = next_period_start(&template.recurrence, furthest_ahead);
+            if let Some(previous) = template.current_instance.take() {
+                template.earlier_instances.push(previous);
+            }
+            let task = instantiate(template, start, end, now);
+            template.current_instance = Some(task.id.clone());
+            template.current_period_start = Some(start);
+            template.next_period_start = end;
+            tasks.insert(task.id.clone(), task);
+        }
+    });
+}
+
+// Whether the user's local date is in the period of a template instance; other tasks have no period
+pub(crate) fn in_local_period(task: &TaskDefinition, offset_minutes: i32, now: TimestampMillis) -> bool {
+    let Some(start) = task.period_start else {
+        return true;
+    };
+    let today = local_date(now, offset_minutes);
+    start <= today && task.period_end.is_none_or(|end| today < end)
+}
+
+// A template id, such as `daily_post`, names the template's instance for the user's local date.
+// Any other id is returned as is.
+pub(crate) fn resolve_task_id(store: &Storage, task_id: &str, offset_minutes: i32, now: TimestampMillis) -> String {
+    let (Some(template), Some(tasks)) = (store.task_templates.get(task_id), store.tasks.as_ref()) else {
+        return task_id.to_string();
+    };
+    if tasks.contains_key(task_id) {
+        return task_id.to_string();
+    }
+    template.current_instance
+        .iter()
+        .chain(template.earlier_instances.iter().rev())
+        .find(|instance| tasks.get(*instance).is_some_and(|task| in_local_period(task, offset_minutes, now)))
+        .cloned()
+        .unwrap_or_else(|| task_id.to_string())
+}
+
+// The built-in tasks, as templates. `init` seeds them on a fresh canister and a migration converts
+// the fixed task definitions of earlier releases.
+pub(crate) fn default_task_templates(created_by: Principal, now: TimestampMillis) -> Vec<TaskTemplate> {
+    let template = |id: &str, title: &str, description: &str, points: u64, completion_criteria: &str, proof_required: bool| TaskTemplate {
+        id: id.to_string(),
+        title: title.to_string(),
+        description: description.to_string(),
+        points,
+        completion_criteria: completion_criteria.to_string(),
+        requirements: None,
+        recurrence: TaskRecurrence::Daily,
+        proof_required,
+        enabled: true,
+        created_by,
+        created_at: now,
+        updated_at: now,
+        current_instance: None,
+        current_period_start: None,
+        next_period_start: None,
+        earlier_instances: Vec::new(),
+    };
+    vec![
+        template("daily_post", "Daily Post", "Create a post daily to earn points", 50, "Create at least one post", true),
+        template("social_engagement", "Social Engagement", "Engage with other users to earn points", 100, "Like or comment on at least 3 posts", false),
+    ]
+}
+
+pub fn init_default_task_templates() {
+    let now = now_millis();
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.task_templates.is_empty() {
+            store.task_templates = default_task_templates(ic_cdk::id(), now)
+                .into_iter()
+                .map(|template| (template.id.clone(), template))
+                .collect();
+        }
+    });
+    generate_task_instances();
+}
+
+// Whether completing the task needs a proof, for tasks generated from a template
+pub fn template_proof_required(task: &TaskDefinition) -> Option<bool> {
+    let template_id = task.template_id.as_ref()?;
+    STORAGE.with(|storage| storage.borrow().task_templates.get(template_id).map(|template| template.proof_required))
+}
+
+pub fn get_task_templates() -> SquareResult<Vec<TaskTemplate>> {
+    const FUNCTION: &str = "get_task_templates";
+
+    require_manager("get_task_templates", FUNCTION)?;
+    let mut templates: Vec<TaskTemplate> = STORAGE.with(|storage| storage.borrow().task_templates.values().cloned().collect());
+    templates.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(templates)
+}
+
+pub fn create_task_template(request: CreateTaskTemplateRequest, caller: Principal) -> SquareResult<TaskTemplate> {
+    const FUNCTION: &str = "create_task_template";
+
+    require_manager("create_task_template", FUNCTION)?;
+    validate_create_task_template(&request).finish(MODULE, FUNCTION)?;
+
+    let now = now_millis();
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.task_templates.contains_key(&request.id) {
+            return log_and_return(already_exists_error("TaskTemplate", &request.id, MODULE, FUNCTION));
+        }
+        if store.task_templates.len() >= MAX_TASK_TEMPLATES {
+            return log_and_return(quota_exceeded_error("task_templates", MAX_TASK_TEMPLATES as u64, MODULE, FUNCTION));
+        }
+
+        store.task_templates.insert(request.id.clone(), TaskTemplate {
+            id: request.id.clone(),
+            title: request.title,
+            description: request.description,
+            points: request.points,
+            completion_criteria: request.completion_criteria,
+            requirements: request.requirements,
+            recurrence: request.recurrence,
+            proof_required: request.proof_required,
+            enabled: true,
+            created_by: caller,
+            created_at: now,
+            updated_at: now,
+            current_instance: None,
+            current_period_start: None,
+            next_period_start: None,
+            earlier_instances: Vec::new(),
+        });
+        Ok(())
+    })?;
+
+    generate_task_instances();
+    template_by_id(&request.id, FUNCTION)
+}
+
+fn template_by_id(template_id: &str, function: &str) -> SquareResult<TaskTemplate> {
+    match STORAGE.with(|storage| storage.borrow().task_templates.get(template_id).cloned()) {
+        Some(template) => Ok(template),
+        None => log_and_return(not_found_error("TaskTemplate", template_id, MODULE, function)),
+    }
+}
+
+// Take a template's instances off the task list, so the next run starts a fresh period
+fn retire_instances(tasks: &mut Option<HashMap<String, TaskDefinition>>, template: &mut TaskTemplate) {
+    let instances = template.current_instance.take().into_iter().chain(template.earlier_instances.drain(..));
+    if let Some(tasks) = tasks.as_mut() {
+        for instance in instances {
+            tasks.remove(&instance);
+        }
+    }
+    template.current_period_start = None;
+    template.next_period_start = None;
+}
+
+pub fn update_task_template(request: UpdateTaskTemplateRequest) -> SquareResult<TaskTemplate> {
+    const FUNCTION: &str = "update_task_template";
+
+    require_manager("update_task_template", FUNCTION)?;
+    validate_update_task_template(&request).finish(MODULE, FUNCTION)?;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let Some(template) = store.task_templates.get_mut(&request.id) else {
+            return log_and_return(not_found_error("TaskTemplate", &request.id, MODULE, FUNCTION));
+        };
+
+        if let Some(title) = request.title {
+            template.title = title;
+        }
+        if let Some(description) = request.description {
+            template.description = description;
+        }
+        if let Some(points) = request.points {
+            template.points = points;
+        }
+        if let Some(completion_criteria) = request.completion_criteria {
+            template.completion_criteria = completion_criteria;
+        }
+        if let Some(requirements) = request.requirements {
+            template.requirements = Some(requirements);
+        }
+        if let Some(proof_required) = request.proof_required {
+            template.proof_required = proof_required;
+        }
+        if let Some(recurrence) = request.recurrence.filter(|recurrence| *recurrence != template.recurrence) {
+            template.recurrence = recurrence;
+            // Periods of the old schedule end now, and the next run starts one under the new schedule
+            retire_instances(&mut store.tasks, template);
+        }
+        if let Some(enabled) = request.enabled {
+            if !enabled && template.enabled {
+                retire_instances(&mut store.tasks, template);
+            }
+            template.enabled = enabled;
+        }
+        template.updated_at = now_millis();
+        Ok(())
+    })?;
+
+    generate_task_instances();
+    template_by_id(&request.id, FUNCTION)
+}
+
+// Deleting a template also removes its instances; completions already recorded are kept
+pub fn delete_task_template(template_id: String) -> SquareResult<()> {
+    const FUNCTION: &str = "delete_task_template";
+
+    require_manager("delete_task_template", FUNCTION)?;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let Some(mut template) = store.task_templates.remove(&template_id) else {
+            return log_and_return(not_found_error("TaskTemplate", &template_id, MODULE, FUNCTION));
+        };
+        retire_instances(&mut store.tasks, &mut template);
+        Ok(())
+    })
+}
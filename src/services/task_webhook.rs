@@ -0,0 +1,231 @@
+use candid::Principal;
+use ic_cdk::api::management_canister::main::raw_rand;
+use ic_cdk::api::{call, time};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::auth::is_admin;
+use crate::models::error::SquareResult;
+use crate::models::reward::TaskDefinition;
+use crate::models::task_registry::*;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::hmac::hmac_sha256;
+use crate::utils::logger;
+
+const MODULE: &str = "services::task_webhook";
+
+thread_local! {
+    // Deliveries awaiting an answer, per task canister; heap-only, since an upgrade waits for them
+    static IN_FLIGHT: RefCell<HashMap<Principal, usize>> = RefCell::new(HashMap::new());
+}
+
+// Holds one of a canister's in-flight slots until dropped, including when the callback traps
+struct InFlight(Principal);
+
+impl InFlight {
+    fn acquire(canister_id: Principal) -> Option<Self> {
+        IN_FLIGHT.with(|in_flight| {
+            let mut in_flight = in_flight.borrow_mut();
+            let count = in_flight.entry(canister_id).or_default();
+            if *count >= MAX_TASK_WEBHOOKS_IN_FLIGHT {
+                return None;
+            }
+            *count += 1;
+            Some(InFlight(canister_id))
+        })
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        IN_FLIGHT.with(|in_flight| {
+            let mut in_flight = in_flight.borrow_mut();
+            if let Some(count) = in_flight.get_mut(&self.0) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    in_flight.remove(&self.0);
+                }
+            }
+        });
+    }
+}
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage task canister webhooks",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn sign_receipt(receipt: &TaskCompletionReceipt, secret: &str) -> String {
+    let message = format!(
+        "{}|{}|{}|{}|{}|{}",
+        receipt.receipt_id, receipt.task_id, receipt.user, receipt.points_awarded, receipt.completed_at, receipt.issuer
+    );
+    to_hex(&hmac_sha256(secret.as_bytes(), message.as_bytes()))
+}
+
+// Send a receipt to the canister behind a completed task, if it is a registered task canister with
+// its webhook enabled. Never blocks the completion; failed deliveries are retried later.
+pub fn notify_task_completion(task: &TaskDefinition, user: Principal, points_awarded: u64, completed_at: u64) {
+    let delivery = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let registration = store.task_canisters.get(&task.canister_id).filter(|registration| registration.enabled && registration.webhook_enabled)?;
+        // Signed as it is sent, so retries after a secret rotation carry the new secret's signature
+        let receipt = TaskCompletionReceipt {
+            receipt_id: format!("{}_{}_{}", task.id, user, completed_at),
+            task_id: task.id.clone(),
+            user,
+            points_awarded,
+            completed_at,
+            issuer: ic_cdk::id(),
+            signature: String::new(),
+        };
+        Some(TaskWebhookDelivery {
+            canister_id: registration.canister_id,
+            receipt,
+            attempts: 0,
+            last_error: None,
+            last_attempt_at: completed_at,
+        })
+    });
+
+    if let Some(delivery) = delivery {
+        ic_cdk::spawn(deliver(delivery));
+    }
+}
+
+// Sign the receipt with the canister's current secret and send it. A canister that already has
+// MAX_TASK_WEBHOOKS_IN_FLIGHT receipts open gets this one on a later retry.
+async fn deliver(mut delivery: TaskWebhookDelivery) {
+    let Some(secret) = STORAGE.with(|storage| storage.borrow().task_webhook_secrets.get(&delivery.canister_id).cloned()) else {
+        // The webhook was disabled since the receipt was queued
+        return;
+    };
+    delivery.receipt.signature = sign_receipt(&delivery.receipt, &secret);
+
+    let Some(in_flight) = InFlight::acquire(delivery.canister_id) else {
+        delivery.last_error = Some("Waiting for earlier receipts to be answered".to_string());
+        requeue(delivery);
+        return;
+    };
+    let result: Result<(), _> = call::call(delivery.canister_id, "on_task_completed", (delivery.receipt.clone(),)).await;
+    drop(in_flight);
+    let Err((code, message)) = result else {
+        return;
+    };
+
+    delivery.attempts += 1;
+    delivery.last_attempt_at = time() / 1_000_000;
+    delivery.last_error = Some(format!("on_task_completed failed: {:?} {}", code, message));
+    if delivery.attempts >= MAX_TASK_WEBHOOK_ATTEMPTS {
+        logger::log(&format!(
+            "[{}] Gave up delivering receipt {} to {}",
+            MODULE, delivery.receipt.receipt_id, delivery.canister_id
+        ));
+    }
+    requeue(delivery);
+}
+
+// Keep an undelivered receipt for a retry, or for admins to inspect once it is out of attempts
+fn requeue(delivery: TaskWebhookDelivery) {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        // The webhook may have been disabled while the call was in flight
+        if !store.task_webhook_secrets.contains_key(&delivery.canister_id) {
+            return;
+        }
+        let deliveries = &mut store.task_webhook_deliveries;
+        if deliveries.len() >= MAX_TASK_WEBHOOK_DELIVERIES {
+            deliveries.remove(0);
+        }
+        deliveries.push(delivery);
+    });
+}
+
+// Retry every failed delivery that has attempts left; run on full heartbeat runs
+pub fn retry_task_webhooks() {
+    let due: Vec<TaskWebhookDelivery> = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let (due, exhausted) = std::mem::take(&mut store.task_webhook_deliveries)
+            .into_iter()
+            .partition(|delivery| delivery.attempts < MAX_TASK_WEBHOOK_ATTEMPTS);
+        store.task_webhook_deliveries = exhausted;
+        due
+    });
+    for delivery in due {
+        ic_cdk::spawn(deliver(delivery));
+    }
+}
+
+// Turn on receipts for a task canister with a fresh secret, or replace the secret if they are on.
+// The secret is only returned here; give it to the canister's operator so it can check signatures.
+pub async fn enable_task_webhook(canister_id: Principal) -> SquareResult<String> {
+    const FUNCTION: &str = "enable_task_webhook";
+
+    require_admin("enable_task_webhook", FUNCTION)?;
+    if STORAGE.with(|storage| !storage.borrow().task_canisters.contains_key(&canister_id)) {
+        return log_and_return(not_found_error("TaskCanister", &canister_id.to_string(), MODULE, FUNCTION));
+    }
+
+    let (random_bytes,) = match raw_rand().await {
+        Ok(bytes) => bytes,
+        Err((_, message)) => {
+            return log_and_return(service_unavailable_error(
+                "raw_rand",
+                &message,
+                MODULE,
+                FUNCTION
+            ));
+        }
+    };
+    let secret = to_hex(&random_bytes);
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        // The canister may have been removed while randomness was being fetched
+        let Some(registration) = store.task_canisters.get_mut(&canister_id) else {
+            return log_and_return(not_found_error("TaskCanister", &canister_id.to_string(), MODULE, FUNCTION));
+        };
+        registration.webhook_enabled = true;
+        store.task_webhook_secrets.insert(canister_id, secret.clone());
+        Ok(secret)
+    })
+}
+
+// Stops receipts and drops the ones still waiting to be delivered
+pub fn disable_task_webhook(canister_id: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "disable_task_webhook";
+
+    require_admin("disable_task_webhook", FUNCTION)?;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let Some(registration) = store.task_canisters.get_mut(&canister_id) else {
+            return log_and_return(not_found_error("TaskCanister", &canister_id.to_string(), MODULE, FUNCTION));
+        };
+        registration.webhook_enabled = false;
+        store.task_webhook_secrets.remove(&canister_id);
+        store.task_webhook_deliveries.retain(|delivery| delivery.canister_id != canister_id);
+        Ok(())
+    })
+}
+
+// Receipts awaiting a retry, and those that ran out of attempts
+pub fn get_task_webhook_deliveries() -> SquareResult<Vec<TaskWebhookDelivery>> {
+    const FUNCTION: &str = "get_task_webhook_deliveries";
+
+    require_admin("get_task_webhook_deliveries", FUNCTION)?;
+    Ok(STORAGE.with(|storage| storage.borrow().task_webhook_deliveries.clone()))
+}
@@ -0,0 +1,243 @@
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::auth::is_manager_or_admin;
+use crate::models::content::{ContentFormat, ContentType, PostResponse};
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::storage::Storage;
+use crate::models::token_gate::*;
+use crate::services::content::get_post;
+use crate::services::content::rendering::rendered_html;
+use crate::services::emoji::attach_post_emojis;
+use crate::services::discovery::tags::{is_moderator_of_tag, new_tag, root_post};
+use crate::services::query_cache::{invalidate, POST_QUERIES, TAG_QUERIES};
+use crate::services::user::token_holdings;
+use crate::storage::STORAGE;
+use crate::utils::content_utils::normalize_hashtag;
+use crate::utils::error_handler::*;
+
+const MODULE: &str = "services::token_gate";
+
+// Caller's standing against a gate, based on the last verified balance
+pub fn gate_status(store: &Storage, user: Principal, gate: &TokenGate, now: u64) -> (TokenGateStatus, Option<CachedTokenBalance>) {
+    let cached = store.token_balances
+        .get(&user)
+        .and_then(|balances| balances.iter().find(|cached| cached.ledger_canister_id == gate.ledger_canister_id))
+        .filter(|cached| cached.verified_at + TOKEN_GATE_REVERIFY_MS > now)
+        .cloned();
+    let status = match &cached {
+        None => TokenGateStatus::Unverified,
+        Some(cached) if cached.balance >= gate.min_balance => TokenGateStatus::Passed,
+        Some(_) => TokenGateStatus::Failed,
+    };
+    (status, cached)
+}
+
+fn ensure_gate_passed(store: &Storage, user: Principal, gate: &TokenGate, label: &str, operation: &str, function: &str) -> SquareResult<()> {
+    let now = time() / 1_000_000;
+    match gate_status(store, user, gate, now).0 {
+        TokenGateStatus::Passed => Ok(()),
+        status => log_and_return(permission_denied_error(
+            operation,
+            &format!(
+                "{} requires holding at least {} of token {} ({:?}); call verify_token_gate to refresh your balance",
+                label, gate.min_balance, gate.ledger_canister_id, status
+            ),
+            MODULE,
+            function
+        )),
+    }
+}
+
+// Posting into gated tag communities requires verified holdings; managers and tag moderators are exempt
+pub fn check_tag_gates(user: Principal, hashtags: &[String]) -> SquareResult<()> {
+    const FUNCTION: &str = "check_tag_gates";
+
+    if is_manager_or_admin().is_ok() {
+        return Ok(());
+    }
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        for tag in hashtags {
+            let Some(gate) = store.tags.get(tag).and_then(|tag| tag.token_gate.as_ref()) else {
+                continue;
+            };
+            if !is_moderator_of_tag(&store, user, tag) {
+                ensure_gate_passed(&store, user, gate, tag, "create_post", FUNCTION)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+// Commenting inherits the gates of the thread's post and of its tags
+pub fn check_content_gates(user: Principal, content_type: &ContentType, content_id: &str) -> SquareResult<()> {
+    const FUNCTION: &str = "check_content_gates";
+
+    if is_manager_or_admin().is_ok() {
+        return Ok(());
+    }
+    let hashtags: Vec<String> = STORAGE.with(|storage| -> SquareResult<Vec<String>> {
+        let store = storage.borrow();
+        let Some(post) = root_post(&store, content_type, content_id) else {
+            return Ok(Vec::new());
+        };
+        if let Some(gate) = post.token_gate.as_ref().filter(|_| post.author != user) {
+            ensure_gate_passed(&store, user, gate, "This post", "create_comment", FUNCTION)?;
+        }
+        Ok(post.hashtags.clone())
+    })?;
+    check_tag_gates(user, &hashtags)
+}
+
+// List and detail views never carry gated content; holders read it through `get_gated_post`
+pub fn withhold_gated_content(mut response: PostResponse) -> PostResponse {
+    if response.token_gate.is_some() {
+        response.content = String::new();
+        response.media_urls.clear();
+    }
+    response
+}
+
+pub fn validate_token_gate(gate: &Option<TokenGate>, function: &str) -> SquareResult<()> {
+    if gate.as_ref().is_some_and(|gate| gate.min_balance == 0u64) {
+        return log_and_return(field_validation_error(
+            "token_gate.min_balance",
+            "must be greater than 0",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+fn target_gate(store: &Storage, target: &GateTarget, function: &str) -> SquareResult<TokenGate> {
+    let gate = match target {
+        GateTarget::Tag(tag) => {
+            let tag = normalize_hashtag(tag).unwrap_or_else(|| tag.clone());
+            store.tags.get(&tag).and_then(|tag| tag.token_gate.clone())
+        }
+        GateTarget::Post(post_id) => store.posts.get(post_id).and_then(|post| post.token_gate.clone()),
+    };
+    match gate {
+        Some(gate) => Ok(gate),
+        None => log_and_return(not_found_error("TokenGate", &format!("{:?}", target), MODULE, function)),
+    }
+}
+
+// Post authors gate their own posts; tag moderators and managers gate tag communities
+pub fn set_token_gate(request: SetTokenGateRequest, caller: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "set_token_gate";
+
+    validate_token_gate(&request.gate, FUNCTION)?;
+    let is_manager = is_manager_or_admin().is_ok();
+    let now = time() / 1_000_000;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        match request.target {
+            GateTarget::Post(post_id) => {
+                let Some(mut post) = store.posts.get_mut(&post_id) else {
+                    return log_and_return(not_found_error("Post", &post_id, MODULE, FUNCTION));
+                };
+                if post.author != caller && !is_manager {
+                    return log_and_return(permission_denied_error(
+                        "set_token_gate",
+                        "Only the author can gate this post",
+                        MODULE,
+                        FUNCTION
+                    ));
+                }
+                post.token_gate = request.gate;
+                post.updated_at = now;
+                invalidate(POST_QUERIES);
+            }
+            GateTarget::Tag(tag) => {
+                let Some(name) = normalize_hashtag(&tag) else {
+                    return log_and_return(field_validation_error("tag", "must contain letters or digits", MODULE, FUNCTION));
+                };
+                if !is_manager && !is_moderator_of_tag(&store, caller, &name) {
+                    return log_and_return(permission_denied_error(
+                        "set_token_gate",
+                        "Only moderators of this tag can gate its community",
+                        MODULE,
+                        FUNCTION
+                    ));
+                }
+                let entry = store.tags.entry(name.clone()).or_insert_with(|| new_tag(&name, now));
+                entry.token_gate = request.gate;
+                entry.updated_at = now;
+                entry.updated_by = Some(caller);
+                invalidate(TAG_QUERIES);
+            }
+        }
+        Ok(())
+    })
+}
+
+fn access_response(gate: TokenGate, status: TokenGateStatus, cached: Option<CachedTokenBalance>) -> TokenGateAccessResponse {
+    TokenGateAccessResponse {
+        gate,
+        status,
+        balance: cached.as_ref().map(|cached| cached.balance.clone()),
+        verified_at: cached.as_ref().map(|cached| cached.verified_at),
+        expires_at: cached.as_ref().map(|cached| cached.verified_at + TOKEN_GATE_REVERIFY_MS),
+    }
+}
+
+pub fn get_token_gate_status(target: GateTarget, caller: Principal) -> SquareResult<TokenGateAccessResponse> {
+    const FUNCTION: &str = "get_token_gate_status";
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let gate = target_gate(&store, &target, FUNCTION)?;
+        let (status, cached) = gate_status(&store, caller, &gate, now);
+        Ok(access_response(gate, status, cached))
+    })
+}
+
+// Re-check the caller's holdings with the token ledger and cache the balance
+pub async fn verify_token_gate(target: GateTarget, caller: Principal) -> SquareResult<TokenGateAccessResponse> {
+    const FUNCTION: &str = "verify_token_gate";
+
+    let gate = STORAGE.with(|storage| target_gate(&storage.borrow(), &target, FUNCTION))?;
+    let holdings = token_holdings(caller, gate.ledger_canister_id).await?;
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let balances = store.token_balances.entry(caller).or_default();
+        balances.retain(|cached| cached.ledger_canister_id != gate.ledger_canister_id);
+        balances.push(CachedTokenBalance {
+            ledger_canister_id: gate.ledger_canister_id,
+            balance: holdings.total,
+            verified_at: now,
+        });
+        let (status, cached) = gate_status(&store, caller, &gate, now);
+        Ok(access_response(gate, status, cached))
+    })
+}
+
+// Full content of a gated post for its author, managers and verified holders
+pub fn get_gated_post(post_id: String, caller: Principal) -> SquareResult<PostResponse> {
+    const FUNCTION: &str = "get_gated_post";
+
+    let mut response = get_post(post_id.clone())?;
+    let is_manager = is_manager_or_admin().is_ok();
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let Some(post) = store.posts.get(&post_id) else {
+            return log_and_return(not_found_error("Post", &post_id, MODULE, FUNCTION));
+        };
+        if let Some(gate) = post.token_gate.as_ref().filter(|_| post.author != caller && !is_manager) {
+            ensure_gate_passed(&store, caller, gate, "This post", "get_gated_post", FUNCTION)?;
+        }
+        response.content = rendered_html(&store, &post.id, post.format, &post.content);
+        response.source = Some(post.content.clone()).filter(|_| post.format != ContentFormat::Html);
+        response.media_urls = post.media_urls.clone();
+        Ok(())
+    })?;
+    attach_post_emojis(std::slice::from_mut(&mut response));
+    Ok(response)
+}
@@ -0,0 +1,270 @@
+use candid::Principal;
+use ic_cdk::api::time;
+use serde::Deserialize;
+
+use crate::auth::is_admin;
+use crate::models::account_quality::RateLimitedAction;
+use crate::models::content::{ContentStatus, ContentType, ContentVisibility};
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::storage::Storage;
+use crate::models::translation::*;
+use crate::services::classifier::{content_hash, post_json, truncate_to_bytes};
+use crate::services::content::posts::can_view_post;
+use crate::services::discovery::tags::root_post;
+use crate::services::user::check_action_rate_limit;
+use crate::services::user::creator::can_read_premium;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+
+const MODULE: &str = "services::translation";
+
+#[derive(Deserialize)]
+struct TranslationResult {
+    title: Option<String>,
+    text: String,
+}
+
+// Original content a reader may have translated
+struct TranslationSource {
+    content_type: ContentType,
+    title: Option<String>,
+    text: String,
+}
+
+fn normalize_language(lang: &str) -> String {
+    lang.trim().to_lowercase()
+}
+
+fn translation_key(content_id: &str, lang: &str) -> String {
+    format!("{}:{}", content_id, lang)
+}
+
+// Active content the caller can already read; gated posts and their comments are never sent out,
+// and neither is anything under a post that is not public
+fn translation_source(content_id: &str, caller: Principal, function: &str) -> SquareResult<TranslationSource> {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let (content_type, author, status, title, text) = if let Some(post) = store.posts.get(content_id) {
+            (ContentType::Post, post.author, &post.status, post.title.clone(), post.content.clone())
+        } else if let Some(comment) = store.comments.get(content_id) {
+            (ContentType::Comment, comment.author, &comment.status, None, comment.content.clone())
+        } else {
+            return log_and_return(not_found_error("Content", content_id, MODULE, function));
+        };
+
+        if (*status != ContentStatus::Active && author != caller) || *status == ContentStatus::LegalHold {
+            return log_and_return(not_found_error("Content", content_id, MODULE, function));
+        }
+        let Some(root) = root_post(&store, &content_type, content_id) else {
+            return log_and_return(not_found_error("Content", content_id, MODULE, function));
+        };
+        if !can_view_post(&store, root, caller) {
+            return log_and_return(not_found_error("Content", content_id, MODULE, function));
+        }
+        if root.visibility != ContentVisibility::Public && root.author != caller {
+            return log_and_return(permission_denied_error(
+                "translate_content",
+                "Only public posts and their comments can be translated",
+                MODULE,
+                function
+            ));
+        }
+        if root.token_gate.is_some() {
+            return log_and_return(permission_denied_error(
+                "translate_content",
+                "Token-gated content cannot be translated",
+                MODULE,
+                function
+            ));
+        }
+        if content_type == ContentType::Post && root.is_premium && !can_read_premium(&store, &root.author, caller) {
+            return log_and_return(permission_denied_error(
+                "translate_content",
+                "Only the author's followers can translate a premium post",
+                MODULE,
+                function
+            ));
+        }
+        Ok(TranslationSource { content_type, title, text })
+    })
+}
+
+async fn request_translation(
+    settings: &TranslationSettings,
+    content_id: &str,
+    target_lang: &str,
+    source: &TranslationSource
+) -> Result<TranslationResult, String> {
+    let body = serde_json::json!({
+        "content_id": content_id,
+        "target_lang": target_lang,
+        "title": source.title,
+        "text": truncate_to_bytes(&source.text, MAX_TRANSLATION_TEXT_BYTES),
+    });
+    post_json(
+        &settings.endpoint_url,
+        settings.auth_token.as_ref(),
+        body,
+        TRANSLATION_MAX_RESPONSE_BYTES,
+        settings.cycles_per_call,
+        "Translation API"
+    ).await
+}
+
+// Translate a post or comment, reusing the cached translation while the original is unchanged
+pub async fn translate_content(content_id: String, target_lang: String, caller: Principal) -> SquareResult<TranslationResponse> {
+    const FUNCTION: &str = "translate_content";
+
+    let settings = STORAGE.with(|storage| storage.borrow().translation_settings.clone().unwrap_or_default());
+    if !settings.enabled || settings.endpoint_url.is_empty() {
+        return log_and_return(service_unavailable_error("translation", "Translation is not enabled", MODULE, FUNCTION));
+    }
+    let target_lang = normalize_language(&target_lang);
+    if !settings.supported_languages.contains(&target_lang) {
+        return log_and_return(field_validation_error(
+            "target_lang",
+            &format!("must be one of: {}", settings.supported_languages.join(", ")),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let source = translation_source(&content_id, caller, FUNCTION)?;
+    let source_hash = content_hash(&format!("{}\n{}", source.title.as_deref().unwrap_or_default(), source.text));
+    let key = translation_key(&content_id, &target_lang);
+
+    let cached = STORAGE.with(|storage| {
+        storage.borrow().translations.get(&key).filter(|cached| cached.source_hash == source_hash).cloned()
+    });
+    let (cached, translation) = match cached {
+        Some(translation) => (true, translation),
+        None => {
+            // Only new outcalls count against the caller's limit
+            check_action_rate_limit(caller, RateLimitedAction::Translate)?;
+            let result = match request_translation(&settings, &content_id, &target_lang, &source).await {
+                Ok(result) => result,
+                Err(message) => return log_and_return(service_unavailable_error("translation", &message, MODULE, FUNCTION)),
+            };
+            let translation = CachedTranslation {
+                content_id: content_id.clone(),
+                content_type: source.content_type.clone(),
+                target_lang: target_lang.clone(),
+                source_hash,
+                title: result.title.filter(|_| source.title.is_some()),
+                text: result.text,
+                translated_at: time() / 1_000_000,
+            };
+            STORAGE.with(|storage| storage.borrow_mut().translations.insert(key, translation.clone()));
+            (false, translation)
+        }
+    };
+
+    Ok(TranslationResponse {
+        content_id,
+        content_type: source.content_type,
+        target_lang,
+        original_title: source.title,
+        original_text: source.text,
+        translated_title: translation.title,
+        translated_text: translation.text,
+        cached,
+        translated_at: translation.translated_at,
+    })
+}
+
+// Drop cached translations of content that no longer exists
+pub fn remove_translations(store: &mut Storage, content_id: &str) {
+    let prefix = format!("{}:", content_id);
+    store.translations.retain(|key, _| !key.starts_with(&prefix));
+}
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage translation settings",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+fn settings_response(settings: TranslationSettings) -> TranslationSettingsResponse {
+    TranslationSettingsResponse {
+        enabled: settings.enabled,
+        endpoint_url: settings.endpoint_url,
+        has_auth_token: settings.auth_token.is_some(),
+        supported_languages: settings.supported_languages,
+        cycles_per_call: settings.cycles_per_call,
+    }
+}
+
+pub fn get_translation_settings() -> SquareResult<TranslationSettingsResponse> {
+    const FUNCTION: &str = "get_translation_settings";
+
+    require_admin("get_translation_settings", FUNCTION)?;
+    Ok(settings_response(STORAGE.with(|storage| storage.borrow().translation_settings.clone().unwrap_or_default())))
+}
+
+pub fn update_translation_settings(request: UpdateTranslationSettingsRequest) -> SquareResult<TranslationSettingsResponse> {
+    const FUNCTION: &str = "update_translation_settings";
+
+    require_admin("update_translation_settings", FUNCTION)?;
+    if request.endpoint_url.as_ref().is_some_and(|url| !url.starts_with("https://") || url.len() > MAX_TRANSLATION_URL_LENGTH) {
+        return log_and_return(field_validation_error(
+            "endpoint_url",
+            &format!("must be an https:// URL of at most {} characters", MAX_TRANSLATION_URL_LENGTH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    let supported_languages: Option<Vec<String>> = request.supported_languages
+        .map(|languages| languages.iter().map(|lang| normalize_language(lang)).collect());
+    if supported_languages.as_ref().is_some_and(|languages| {
+        languages.is_empty()
+            || languages.len() > MAX_SUPPORTED_LANGUAGES
+            || languages.iter().any(|lang| lang.is_empty() || lang.len() > 10 || !lang.chars().all(|c| c.is_ascii_alphabetic() || c == '-'))
+    }) {
+        return log_and_return(field_validation_error(
+            "supported_languages",
+            &format!("must list 1 to {} language codes such as \"en\" or \"zh-tw\"", MAX_SUPPORTED_LANGUAGES),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let mut settings = store.translation_settings.clone().unwrap_or_default();
+
+        if let Some(endpoint_url) = request.endpoint_url {
+            settings.endpoint_url = endpoint_url;
+        }
+        if let Some(auth_token) = request.auth_token {
+            settings.auth_token = Some(auth_token).filter(|token| !token.is_empty());
+        }
+        if let Some(mut supported_languages) = supported_languages {
+            supported_languages.sort();
+            supported_languages.dedup();
+            settings.supported_languages = supported_languages;
+        }
+        if let Some(cycles_per_call) = request.cycles_per_call {
+            settings.cycles_per_call = cycles_per_call;
+        }
+        if let Some(enabled) = request.enabled {
+            settings.enabled = enabled;
+        }
+        if settings.enabled && settings.endpoint_url.is_empty() {
+            return log_and_return(field_validation_error(
+                "endpoint_url",
+                "must be set before enabling translation",
+                MODULE,
+                FUNCTION
+            ));
+        }
+
+        store.translation_settings = Some(settings.clone());
+        Ok(settings_response(settings))
+    })
+}
@@ -0,0 +1,294 @@
+use candid::Principal;
+use std::collections::hash_map::Entry;
+
+use crate::auth::{is_admin, is_manager_or_admin};
+use crate::models::content::PaginationParams;
+use crate::models::error::SquareResult;
+use crate::models::reward::{AwardPointsRequest, PointsTransaction, UserRewards};
+use crate::models::storage::Storage;
+use crate::models::treasury::*;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::stable_map::{DefaultMemory, ValueMut};
+use crate::utils::time_utils::{day_start, now_millis, TimestampMillis, MILLIS_PER_DAY};
+use crate::utils::validators::{validate_adjust_points, validate_set_mint_budget};
+
+const MODULE: &str = "services::treasury";
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage the points treasury",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+fn require_manager(operation: &str, function: &str) -> SquareResult<()> {
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only managers or admins can award or adjust points",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+// Record an entry, numbering it and updating the mint's totals
+fn record_entry(treasury: &mut PointsTreasury, mut entry: LedgerEntry) {
+    if entry.debit == LedgerAccount::Mint {
+        treasury.minted += entry.amount;
+    }
+    if entry.credit == LedgerAccount::Mint {
+        treasury.burned += entry.amount;
+    }
+    if treasury.entries.len() >= MAX_LEDGER_ENTRIES {
+        treasury.entries.pop_front();
+    }
+    entry.id = treasury.next_entry_id;
+    treasury.next_entry_id += 1;
+    treasury.entries.push_back(entry);
+}
+
+// Record points a user already holds as minted to them, without touching their balance
+pub(crate) fn record_opening_balance(treasury: &mut PointsTreasury, principal: Principal, points: u64, now: TimestampMillis) {
+    record_entry(treasury, LedgerEntry {
+        id: 0,
+        debit: LedgerAccount::Mint,
+        credit: LedgerAccount::User(principal),
+        amount: points,
+        reason: OPENING_BALANCE_REASON.to_string(),
+        reference_id: None,
+        authorized_by: None,
+        timestamp: now,
+    });
+}
+
+fn user_rewards(store: &mut Storage, principal: Principal, now: TimestampMillis) -> ValueMut<'_, Principal, UserRewards, DefaultMemory> {
+    store.user_rewards.entry(principal).or_insert_with(|| UserRewards {
+        principal,
+        points: 0,
+        points_history: Vec::new(),
+        last_claim_date: None,
+        transactions: Vec::new(),
+        last_updated: now,
+    })
+}
+
+// Issue points from the mint to a user, in both the ledger and the user's points history.
+// Returns the new balance.
+pub(crate) fn mint_points(
+    store: &mut Storage,
+    principal: Principal,
+    points: u64,
+    reason: String,
+    reference_id: Option<String>,
+    authorized_by: Option<Principal>,
+    now: TimestampMillis,
+) -> u64 {
+    record_entry(&mut store.points_treasury, LedgerEntry {
+        id: 0,
+        debit: LedgerAccount::Mint,
+        credit: LedgerAccount::User(principal),
+        amount: points,
+        reason: reason.clone(),
+        reference_id: reference_id.clone(),
+        authorized_by,
+        timestamp: now,
+    });
+    let mut rewards = user_rewards(store, principal, now);
+    rewards.points += points;
+    rewards.points_history.push(PointsTransaction {
+        amount: points as i64,
+        reason,
+        timestamp: now,
+        reference_id,
+        points,
+    });
+    rewards.last_updated = now;
+    rewards.points
+}
+
+// Return points from a user to the mint; callers make sure the balance covers them.
+// Returns the new balance.
+pub(crate) fn burn_points(
+    store: &mut Storage,
+    principal: Principal,
+    points: u64,
+    reason: String,
+    reference_id: Option<String>,
+    authorized_by: Option<Principal>,
+    now: TimestampMillis,
+) -> u64 {
+    record_entry(&mut store.points_treasury, LedgerEntry {
+        id: 0,
+        debit: LedgerAccount::User(principal),
+        credit: LedgerAccount::Mint,
+        amount: points,
+        reason: reason.clone(),
+        reference_id: reference_id.clone(),
+        authorized_by,
+        timestamp: now,
+    });
+    let mut rewards = user_rewards(store, principal, now);
+    rewards.points -= points;
+    rewards.points_history.push(PointsTransaction {
+        amount: -(points as i64),
+        reason,
+        timestamp: now,
+        reference_id,
+        points,
+    });
+    rewards.last_updated = now;
+    rewards.points
+}
+
+// Take `points` from the manager's budget for the current period, starting a new period when the
+// last one has run out
+fn spend_mint_budget(store: &mut Storage, manager: Principal, points: u64, now: TimestampMillis) -> SquareResult<()> {
+    const FUNCTION: &str = "award_points";
+
+    let budget = match store.mint_budgets.entry(manager) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(MintBudget {
+            limit: DEFAULT_MINT_BUDGET,
+            period_days: DEFAULT_MINT_BUDGET_PERIOD_DAYS,
+            period_start: day_start(now),
+            minted_in_period: 0,
+        }),
+    };
+    if now >= budget.period_start.saturating_add(budget.period_days.saturating_mul(MILLIS_PER_DAY)) {
+        budget.period_start = day_start(now);
+        budget.minted_in_period = 0;
+    }
+    if budget.minted_in_period.saturating_add(points) > budget.limit {
+        return log_and_return(quota_exceeded_error("mint_budget", budget.limit, MODULE, FUNCTION));
+    }
+    budget.minted_in_period += points;
+    Ok(())
+}
+
+// Managers award from their mint budget; admins are not limited
+pub fn award_points(request: AwardPointsRequest, caller: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "award_points";
+
+    require_manager("award_points", FUNCTION)?;
+    let unlimited = is_admin().is_ok();
+    let now = now_millis();
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if !unlimited {
+            spend_mint_budget(&mut store, caller, request.points, now)?;
+        }
+        mint_points(&mut store, request.principal, request.points, request.reason, request.reference_id, Some(caller), now);
+        Ok(())
+    })
+}
+
+// Take points back from a user. Fails rather than going below zero.
+pub fn adjust_points(request: AdjustPointsRequest, caller: Principal) -> SquareResult<u64> {
+    const FUNCTION: &str = "adjust_points";
+
+    require_manager("adjust_points", FUNCTION)?;
+    validate_adjust_points(&request).finish(MODULE, FUNCTION)?;
+    let now = now_millis();
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let balance = store.user_rewards.get(&request.principal).map_or(0, |rewards| rewards.points);
+        if balance < request.points {
+            return log_and_return(validation_error(
+                &format!("Cannot take {} points from a balance of {}", request.points, balance),
+                MODULE,
+                FUNCTION
+            ));
+        }
+        Ok(burn_points(&mut store, request.principal, request.points, request.reason, request.reference_id, Some(caller), now))
+    })
+}
+
+pub fn set_mint_budget(request: SetMintBudgetRequest) -> SquareResult<MintBudget> {
+    const FUNCTION: &str = "set_mint_budget";
+
+    require_admin("set_mint_budget", FUNCTION)?;
+    validate_set_mint_budget(&request).finish(MODULE, FUNCTION)?;
+    let now = now_millis();
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let budget = store.mint_budgets.entry(request.manager).or_insert(MintBudget {
+            limit: request.limit,
+            period_days: request.period_days,
+            period_start: day_start(now),
+            minted_in_period: 0,
+        });
+        // What was awarded so far this period still counts against the new limit
+        budget.limit = request.limit;
+        budget.period_days = request.period_days;
+        Ok(budget.clone())
+    })
+}
+
+pub fn get_mint_budgets() -> SquareResult<Vec<MintBudgetResponse>> {
+    const FUNCTION: &str = "get_mint_budgets";
+
+    require_admin("get_mint_budgets", FUNCTION)?;
+    let mut budgets: Vec<MintBudgetResponse> = STORAGE.with(|storage| {
+        storage.borrow().mint_budgets
+            .iter()
+            .map(|(manager, budget)| MintBudgetResponse {
+                manager: *manager,
+                budget: budget.clone(),
+            })
+            .collect()
+    });
+    budgets.sort_by_key(|response| response.manager);
+    Ok(budgets)
+}
+
+// Ledger entries, newest first
+pub fn get_points_ledger(pagination: PaginationParams) -> SquareResult<Vec<LedgerEntry>> {
+    const FUNCTION: &str = "get_points_ledger";
+
+    require_admin("get_points_ledger", FUNCTION)?;
+    let offset = pagination.offset.unwrap_or(0);
+    let limit = pagination.limit.unwrap_or(50).min(500);
+    Ok(STORAGE.with(|storage| {
+        storage.borrow().points_treasury.entries.iter().rev().skip(offset).take(limit).cloned().collect()
+    }))
+}
+
+// Check user balances against the mint's totals and against each user's points history
+pub fn get_points_reconciliation() -> SquareResult<PointsReconciliationReport> {
+    const FUNCTION: &str = "get_points_reconciliation";
+
+    require_admin("get_points_reconciliation", FUNCTION)?;
+    Ok(STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let treasury = &store.points_treasury;
+        let expected_circulation = treasury.minted.saturating_sub(treasury.burned);
+        let mut total_user_balances: u64 = 0;
+        let mut mismatched_users = Vec::new();
+        for (principal, rewards) in &store.user_rewards {
+            total_user_balances += rewards.points;
+            let history_total: i64 = rewards.points_history.iter().map(|transaction| transaction.amount).sum();
+            if history_total != rewards.points as i64 && mismatched_users.len() < MAX_RECONCILIATION_MISMATCHES {
+                mismatched_users.push(*principal);
+            }
+        }
+        mismatched_users.sort();
+        PointsReconciliationReport {
+            generated_at: now_millis(),
+            minted: treasury.minted,
+            burned: treasury.burned,
+            expected_circulation,
+            total_user_balances,
+            discrepancy: total_user_balances as i64 - expected_circulation as i64,
+            users_checked: store.user_rewards.len() as u64,
+            mismatched_users,
+        }
+    }))
+}
@@ -0,0 +1,111 @@
+// Contribution calendar: what a user posted and commented on each day of a year, counted in their
+// own timezone, with their posting streaks
+use candid::Principal;
+use std::collections::BTreeMap;
+
+use crate::models::activity::*;
+use crate::models::content::ContentStatus;
+use crate::models::error::SquareResult;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::{civil_date, now_millis, TimestampMillis, MILLIS_PER_DAY, MILLIS_PER_MINUTE};
+
+const MODULE: &str = "services::user::activity";
+
+// Days since the epoch in the timezone `offset_minutes` east of UTC
+fn local_day(timestamp: TimestampMillis, offset_minutes: i32) -> i64 {
+    (timestamp as i64 + offset_minutes as i64 * MILLIS_PER_MINUTE as i64).div_euclid(MILLIS_PER_DAY as i64)
+}
+
+fn day_date(day: i64) -> (u64, u32, u32) {
+    civil_date(day.max(0) as u64 * MILLIS_PER_DAY)
+}
+
+// Consecutive days with a post, counting back from `last`
+fn posting_run_ending_at(days: &BTreeMap<i64, (u64, u64)>, last: i64) -> u64 {
+    let mut day = last;
+    while days.get(&day).is_some_and(|(posts, _)| *posts > 0) {
+        day -= 1;
+    }
+    (last - day) as u64
+}
+
+pub fn get_my_activity_calendar(year: u64, caller: Principal) -> SquareResult<ActivityCalendarResponse> {
+    const FUNCTION: &str = "get_my_activity_calendar";
+
+    let now = now_millis();
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        if !store.users.contains_key(&caller) {
+            return log_and_return(not_found_error("User", &caller.to_string(), MODULE, FUNCTION));
+        }
+        let offset = store.user_timezones.get(&caller).map(|timezone| timezone.offset_minutes).unwrap_or(0);
+        let today = local_day(now, offset);
+        let current_year = day_date(today).0;
+        if !(MIN_ACTIVITY_YEAR..=current_year).contains(&year) {
+            return log_and_return(field_validation_error(
+                "year",
+                &format!("must be between {} and {}", MIN_ACTIVITY_YEAR, current_year),
+                MODULE,
+                FUNCTION
+            ));
+        }
+
+        // Active posts and comments by local day; cross-posts repeat a post and are left out
+        let mut days: BTreeMap<i64, (u64, u64)> = BTreeMap::new();
+        let posts = store.user_posts
+            .get(&caller)
+            .into_iter()
+            .flatten()
+            .filter_map(|post_id| store.posts.get(post_id))
+            .filter(|post| post.status == ContentStatus::Active && post.cross_post_of.is_none());
+        for post in posts {
+            days.entry(local_day(post.created_at, offset)).or_default().0 += 1;
+        }
+        let comments = store.user_comments
+            .get(&caller)
+            .into_iter()
+            .flatten()
+            .filter_map(|comment_id| store.comments.get(comment_id))
+            .filter(|comment| comment.status == ContentStatus::Active);
+        for comment in comments {
+            days.entry(local_day(comment.created_at, offset)).or_default().1 += 1;
+        }
+
+        let current_streak = match posting_run_ending_at(&days, today) {
+            0 => posting_run_ending_at(&days, today - 1),
+            streak => streak,
+        };
+
+        let mut response = ActivityCalendarResponse {
+            year,
+            days: Vec::new(),
+            total_posts: 0,
+            total_comments: 0,
+            longest_streak: 0,
+            current_streak,
+            timezone_offset_minutes: offset,
+        };
+        let mut streak = 0;
+        let mut previous_post_day = None;
+        for (day, (posts, comments)) in &days {
+            let (day_year, month, day_of_month) = day_date(*day);
+            if day_year != year {
+                continue;
+            }
+            response.days.push(ActivityDay {
+                date: format!("{:04}-{:02}-{:02}", day_year, month, day_of_month),
+                posts: *posts,
+                comments: *comments,
+            });
+            response.total_posts += posts;
+            response.total_comments += comments;
+            if *posts > 0 {
+                streak = if previous_post_day == Some(day - 1) { streak + 1 } else { 1 };
+                previous_post_day = Some(*day);
+                response.longest_streak = response.longest_streak.max(streak);
+            }
+        }
+        Ok(response)
+    })
+}
@@ -1,12 +1,19 @@
 use candid::Principal;
+use ic_cdk::api::time;
 use std::collections::{HashSet, HashMap};
 use std::borrow::{Borrow, BorrowMut};
 
 use crate::auth::{is_admin, is_manager_or_admin};
 use crate::models::user::*;
+use crate::models::content::ContentStatus;
+use crate::models::display::PaginatedResponse;
+use crate::models::interaction::ContentReport;
+use crate::models::storage::Storage;
 use crate::models::error::{SquareError, SquareResult};
 use crate::storage::{STORAGE, UserStatus as StorageUserStatus, UserRole as StorageUserRole};
 use crate::utils::error_handler::*;
+use crate::utils::pagination::page_bounds;
+use crate::utils::time_utils::MILLIS_PER_HOUR;
 use super::utils::{map_storage_status_to_model, map_storage_role_to_model};
 
 // User management (admin functions)
@@ -36,15 +43,24 @@ pub fn update_user_status(request: UserStatusUpdateRequest) -> SquareResult<()>
     };
     
     // Update user status in main storage
-    STORAGE.with(|storage| {
+    let updated = STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         
         if let Some(mut user) = store.users.get(&principal).cloned() {
-            user.status = request.status;
+            user.status = request.status.clone();
             store.users.insert(principal, user);
+            // An admin status change overrides any self-deactivation
+            store.account_deactivations.remove(&principal);
+            true
+        } else {
+            false
         }
     });
     
+    if updated {
+        record_user_moderation(principal, UserModerationAction::StatusChanged(request.status), None, None);
+    }
+    
     Ok(())
 }
 
@@ -74,15 +90,22 @@ pub fn update_user_role(request: UserRoleUpdateRequest) -> SquareResult<()> {
     };
     
     // Update user role in main storage
-    STORAGE.with(|storage| {
+    let updated = STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         
         if let Some(mut user) = store.users.get(&principal).cloned() {
-            user.role = request.role;
+            user.role = request.role.clone();
             store.users.insert(principal, user);
+            true
+        } else {
+            false
         }
     });
     
+    if updated {
+        record_user_moderation(principal, UserModerationAction::RoleChanged(request.role), None, None);
+    }
+    
     Ok(())
 }
 
@@ -114,47 +137,310 @@ pub fn verify_user(principal: Principal) -> SquareResult<()> {
     Ok(())
 }
 
-// Debug function to list all users
-pub fn debug_list_all_users() -> SquareResult<Vec<(String, String)>> {
+// Debug endpoints are admin only and count against one hourly budget shared by all admins
+pub(crate) fn require_debug_access(module: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            function,
+            "Only admins can call debug endpoints",
+            module,
+            function
+        ));
+    }
+    
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        store.debug_calls.retain(|called_at| called_at + MILLIS_PER_HOUR > now);
+        if store.debug_calls.len() >= MAX_DEBUG_CALLS_PER_HOUR {
+            return log_and_return(rate_limit_error("debug", MAX_DEBUG_CALLS_PER_HOUR as u64, module, function));
+        }
+        store.debug_calls.push(now);
+        Ok(())
+    })
+}
+
+// Debug function to list users with their usernames, a page or an even sample at a time
+pub fn debug_list_all_users(request: DebugUserListRequest) -> SquareResult<PaginatedResponse<(String, String)>> {
     const MODULE: &str = "services::user::admin";
     const FUNCTION: &str = "debug_list_all_users";
     
-    // Check if caller is admin
-    if is_admin().is_err() {
+    let limit = request.pagination.limit.unwrap_or(100);
+    if limit > MAX_DEBUG_PAGE_SIZE || request.sample_size.is_some_and(|size| size == 0 || size > MAX_DEBUG_PAGE_SIZE) {
+        return log_and_return(field_validation_error(
+            "pagination",
+            &format!("limit and sample_size must be between 1 and {}", MAX_DEBUG_PAGE_SIZE),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    require_debug_access(MODULE, FUNCTION)?;
+    
+    let mut users: Vec<(String, String)> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.users
+            .keys()
+            .map(|principal| {
+                let username = store.user_profiles
+                    .as_ref()
+                    .and_then(|profiles| profiles.get(principal))
+                    .map_or_else(|| "<no profile>".to_string(), |profile| profile.username.clone());
+                (principal.to_string(), username)
+            })
+            .collect()
+    });
+    
+    // Sort users by username
+    users.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    let total = users.len();
+    
+    if let Some(sample_size) = request.sample_size {
+        let stride = total.div_ceil(sample_size).max(1);
+        return Ok(PaginatedResponse {
+            items: users.into_iter().step_by(stride).collect(),
+            total,
+            has_more: false,
+            next_offset: total,
+        });
+    }
+    
+    let (start, end) = page_bounds(total, &request.pagination, 100);
+    Ok(PaginatedResponse {
+        items: users.drain(start..end).collect(),
+        total,
+        has_more: end < total,
+        next_offset: end,
+    })
+}
+
+// Admin user-management console
+pub fn list_users_admin(request: AdminUserListRequest) -> SquareResult<PaginatedResponse<AdminUserSummary>> {
+    const MODULE: &str = "services::user::admin";
+    const FUNCTION: &str = "list_users_admin";
+    
+    if is_manager_or_admin().is_err() {
         return log_and_return(permission_denied_error(
-            "Only admins can list all users", 
-            MODULE, 
-            FUNCTION,
-            "list_all_users"
+            "list_users_admin",
+            "Only managers or admins can list users",
+            MODULE,
+            FUNCTION
         ));
     }
     
-    let mut users = Vec::new();
+    let mut summaries: Vec<AdminUserSummary> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        
+        store.users
+            .values()
+            .filter(|user| request.status.as_ref().is_none_or(|status| user.status == *status))
+            .filter(|user| request.role.as_ref().is_none_or(|role| user.role == *role))
+            .filter(|user| request.registered_after.is_none_or(|after| user.registered_at >= after))
+            .filter(|user| request.registered_before.is_none_or(|before| user.registered_at <= before))
+            .map(|user| {
+                let profile = store.user_profiles.as_ref().and_then(|profiles| profiles.get(&user.principal));
+                AdminUserSummary {
+                    principal: user.principal,
+                    username: profile.map(|p| p.username.clone()).unwrap_or_default(),
+                    handle: profile.map(|p| p.handle.clone()).unwrap_or_default(),
+                    status: user.status.clone(),
+                    role: user.role.clone(),
+                    registered_at: user.registered_at,
+                    last_login: user.last_login,
+                    content_counts: count_user_content(&store, user.principal),
+                }
+            })
+            .collect()
+    });
+    
+    // Newest registrations first
+    summaries.sort_by_key(|summary| std::cmp::Reverse(summary.registered_at));
+    
+    let total = summaries.len();
+    let (start, end) = page_bounds(total, &request.pagination, 20);
+    
+    Ok(PaginatedResponse {
+        items: summaries[start..end].to_vec(),
+        total,
+        has_more: end < total,
+        next_offset: end,
+    })
+}
+
+pub fn get_user_moderation_history(principal: Principal) -> SquareResult<UserModerationHistoryResponse> {
+    const MODULE: &str = "services::user::admin";
+    const FUNCTION: &str = "get_user_moderation_history";
+    
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_user_moderation_history",
+            "Only managers or admins can view moderation history",
+            MODULE,
+            FUNCTION
+        ));
+    }
     
-    // Get all users from main storage
     STORAGE.with(|storage| {
         let store = storage.borrow();
         
-        for (principal, _) in &store.users {
-            let principal_str = principal.to_string();
-            
-            // Get username from profile
-            let username = if let Some(profiles) = &store.user_profiles {
-                if let Some(profile) = profiles.get(principal) {
-                    profile.username.clone()
-                } else {
-                    "<no profile>".to_string()
+        let user = store.users.get(&principal).ok_or_else(|| {
+            not_found_error("User", &principal.to_string(), MODULE, FUNCTION)
+        })?;
+        
+        // Newest first
+        let records = store.user_moderation_log
+            .get(&principal)
+            .map(|records| records.iter().rev().cloned().collect())
+            .unwrap_or_default();
+        
+        let owned_content = user_content_ids(&store, principal);
+        let mut reports_against: Vec<ContentReport> = store.reports
+            .values()
+            .filter(|report| owned_content.contains(&report.content_id))
+            .cloned()
+            .collect();
+        reports_against.sort_by_key(|report| std::cmp::Reverse(report.created_at));
+        
+        Ok(UserModerationHistoryResponse {
+            principal,
+            status: user.status.clone(),
+            records,
+            reports_against,
+        })
+    })
+}
+
+pub fn bulk_update_user_status(request: BulkUserStatusUpdateRequest) -> SquareResult<BulkUserStatusUpdateResponse> {
+    const MODULE: &str = "services::user::admin";
+    const FUNCTION: &str = "bulk_update_user_status";
+    
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "bulk_update_user_status",
+            "Only admins can update user status",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    
+    if request.principals.len() > MAX_BULK_STATUS_UPDATES {
+        return log_and_return(validation_error(
+            &format!("Too many users. Maximum allowed per request is {}", MAX_BULK_STATUS_UPDATES),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    
+    let mut updated = Vec::new();
+    let mut not_found = Vec::new();
+    
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        
+        for principal in request.principals.iter().collect::<HashSet<_>>() {
+            match store.users.get_mut(principal) {
+                Some(mut user) => {
+                    user.status = request.status.clone();
+                    updated.push(*principal);
+                    store.account_deactivations.remove(principal);
                 }
-            } else {
-                "<no profile>".to_string()
-            };
-            
-            users.push((principal_str, username));
+                None => not_found.push(*principal),
+            }
         }
     });
     
-    // Sort users by username
-    users.sort_by(|a, b| a.1.cmp(&b.1));
+    for principal in &updated {
+        record_user_moderation(
+            *principal,
+            UserModerationAction::StatusChanged(request.status.clone()),
+            request.reason.clone(),
+            None
+        );
+    }
+    
+    Ok(BulkUserStatusUpdateResponse { updated, not_found })
+}
+
+pub fn get_user_content_counts(principal: Principal) -> SquareResult<UserContentCounts> {
+    const MODULE: &str = "services::user::admin";
+    const FUNCTION: &str = "get_user_content_counts";
     
-    Ok(users)
-}
\ No newline at end of file
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_user_content_counts",
+            "Only managers or admins can view content counts",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        
+        if !store.users.contains_key(&principal) {
+            return log_and_return(not_found_error("User", &principal.to_string(), MODULE, FUNCTION));
+        }
+        
+        Ok(count_user_content(&store, principal))
+    })
+}
+
+// Append an entry to a user's moderation history, keeping only the most recent records
+pub fn record_user_moderation(user: Principal, action: UserModerationAction, reason: Option<String>, content_id: Option<String>) {
+    let record = UserModerationRecord {
+        action,
+        moderator: ic_cdk::caller(),
+        reason,
+        content_id,
+        timestamp: time() / 1_000_000,
+    };
+    
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let records = store.user_moderation_log.entry(user).or_default();
+        records.push(record);
+        
+        if records.len() > MAX_MODERATION_RECORDS_PER_USER {
+            let excess = records.len() - MAX_MODERATION_RECORDS_PER_USER;
+            records.drain(0..excess);
+        }
+    });
+}
+
+fn user_content_ids(store: &Storage, principal: Principal) -> HashSet<String> {
+    store.user_posts
+        .get(&principal)
+        .into_iter()
+        .chain(store.user_comments.get(&principal))
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+fn count_user_content(store: &Storage, principal: Principal) -> UserContentCounts {
+    let mut counts = UserContentCounts::default();
+    
+    // The user_posts and user_comments indexes list everything stored, whatever its status
+    for post in store.user_posts.get(&principal).into_iter().flatten().filter_map(|id| store.posts.get(id)) {
+        counts.posts += 1;
+        if matches!(post.status, ContentStatus::Removed | ContentStatus::Hidden) {
+            counts.removed_or_hidden += 1;
+        }
+    }
+    
+    for comment in store.user_comments.get(&principal).into_iter().flatten().filter_map(|id| store.comments.get(id)) {
+        counts.comments += 1;
+        if matches!(comment.status, ContentStatus::Removed | ContentStatus::Hidden) {
+            counts.removed_or_hidden += 1;
+        }
+    }
+    
+    let owned_content = user_content_ids(store, principal);
+    counts.reports_received = store.reports
+        .values()
+        .filter(|report| owned_content.contains(&report.content_id))
+        .map(|report| report.report_count)
+        .sum();
+    
+    counts
+}
@@ -0,0 +1,42 @@
+use candid::Principal;
+
+use crate::models::storage::Storage;
+use crate::models::user::UserCard;
+
+fn card_from_profile(store: &Storage, principal: &Principal) -> Option<UserCard> {
+    let profile = store.user_profiles.as_ref()?.get(principal)?;
+    Some(UserCard {
+        username: profile.username.clone(),
+        handle: profile.handle.clone(),
+        avatar: profile.avatar.clone(),
+        bio: profile.bio.clone(),
+        interests: profile.interests.clone(),
+        followers_count: profile.followers_count,
+        following_count: profile.following_count,
+    })
+}
+
+// Card for display, built from the profile when the index has no entry
+pub fn user_card(store: &Storage, principal: &Principal) -> Option<UserCard> {
+    store.user_cards.get(principal).cloned().or_else(|| card_from_profile(store, principal))
+}
+
+// Call after any write to a profile's display fields or follow counts
+pub fn refresh_user_card(store: &mut Storage, principal: &Principal) {
+    match card_from_profile(store, principal) {
+        Some(card) => {
+            store.user_cards.insert(*principal, card);
+        }
+        None => {
+            store.user_cards.remove(principal);
+        }
+    }
+}
+
+pub fn rebuild_user_cards(store: &mut Storage) {
+    let principals: Vec<Principal> = store.user_profiles.iter().flat_map(|profiles| profiles.keys().copied()).collect();
+    store.user_cards.clear();
+    for principal in principals {
+        refresh_user_card(store, &principal);
+    }
+}
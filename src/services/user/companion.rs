@@ -0,0 +1,154 @@
+use candid::Principal;
+use ic_cdk::api::{call, time};
+
+use crate::auth::is_admin;
+use crate::models::companion::*;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::user::{RegisterUserRequest, MAX_BIO_LENGTH, MAX_USERNAME_LENGTH};
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use super::profile::register_user;
+
+const MODULE: &str = "services::user::companion";
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage companion canisters",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+pub fn register_companion_canister(request: RegisterCompanionCanisterRequest, caller: Principal) -> SquareResult<CompanionCanister> {
+    const FUNCTION: &str = "register_companion_canister";
+
+    require_admin("register_companion_canister", FUNCTION)?;
+    let name = request.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_COMPANION_NAME_LENGTH {
+        return log_and_return(field_validation_error(
+            "name",
+            &format!("must be between 1 and {} characters", MAX_COMPANION_NAME_LENGTH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    let profile_method = request.profile_method.unwrap_or_else(|| DEFAULT_PROFILE_IMPORT_METHOD.to_string());
+    if profile_method.is_empty() || !profile_method.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return log_and_return(field_validation_error(
+            "profile_method",
+            "must be a method name made of letters, digits and underscores",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if request.canister_id == ic_cdk::id() {
+        return log_and_return(invalid_operation_error(
+            "register_companion_canister",
+            "This canister cannot import profiles from itself",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        // Registering again updates the name and method
+        if !store.companion_canisters.contains_key(&request.canister_id) && store.companion_canisters.len() >= MAX_COMPANION_CANISTERS {
+            return log_and_return(quota_exceeded_error("companion_canisters", MAX_COMPANION_CANISTERS as u64, MODULE, FUNCTION));
+        }
+        let companion = CompanionCanister {
+            canister_id: request.canister_id,
+            name,
+            profile_method,
+            registered_by: caller,
+            registered_at: time() / 1_000_000,
+        };
+        store.companion_canisters.insert(companion.canister_id, companion.clone());
+        Ok(companion)
+    })
+}
+
+pub fn remove_companion_canister(canister_id: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "remove_companion_canister";
+
+    require_admin("remove_companion_canister", FUNCTION)?;
+    STORAGE.with(|storage| {
+        if storage.borrow_mut().companion_canisters.remove(&canister_id).is_none() {
+            return log_and_return(not_found_error("CompanionCanister", &canister_id.to_string(), MODULE, FUNCTION));
+        }
+        Ok(())
+    })
+}
+
+// Public so sign-up screens can offer the available imports
+pub fn get_companion_canisters() -> SquareResult<Vec<CompanionCanister>> {
+    STORAGE.with(|storage| {
+        let mut companions: Vec<CompanionCanister> = storage.borrow().companion_canisters.values().cloned().collect();
+        companions.sort_by_key(|companion| companion.registered_at);
+        Ok(companions)
+    })
+}
+
+// Register the caller with the username, avatar and bio they already have in a companion product.
+// The imported profile goes through the same validation, name policy and invite checks as `register_user`.
+pub async fn register_from(request: RegisterFromRequest, caller: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "register_from";
+
+    let companion = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        if store.users.contains_key(&caller) {
+            return log_and_return(already_exists_error("User", &caller.to_string(), MODULE, FUNCTION).with_details("User already registered"));
+        }
+        match store.companion_canisters.get(&request.canister_id) {
+            Some(companion) => Ok(companion.clone()),
+            None => log_and_return(permission_denied_error(
+                "register_from",
+                "Profiles can only be imported from registered companion canisters",
+                MODULE,
+                FUNCTION
+            )),
+        }
+    })?;
+
+    let response: Result<(Option<ImportedProfile>,), _> =
+        call::call(companion.canister_id, &companion.profile_method, (caller,)).await;
+    let imported = match response {
+        Ok((Some(profile),)) => profile,
+        Ok((None,)) => {
+            return log_and_return(not_found_error("Profile", &caller.to_string(), MODULE, FUNCTION)
+                .with_details(format!("No profile found in {}", companion.name)));
+        }
+        Err((code, message)) => {
+            return log_and_return(service_unavailable_error(
+                &companion.name,
+                &format!("{} failed with code {:?}: {}", companion.profile_method, code, message),
+                MODULE,
+                FUNCTION
+            ));
+        }
+    };
+
+    let Some(handle) = request.handle.or(imported.handle) else {
+        return log_and_return(field_validation_error(
+            "handle",
+            &format!("is required because {} has no handle for this user", companion.name),
+            MODULE,
+            FUNCTION
+        ));
+    };
+    // Other products allow longer names and bios; cut them to fit rather than fail the import
+    let register_request = RegisterUserRequest {
+        username: imported.username.trim().chars().take(MAX_USERNAME_LENGTH).collect(),
+        handle,
+        bio: imported.bio.unwrap_or_default().chars().take(MAX_BIO_LENGTH).collect(),
+        avatar: imported.avatar.unwrap_or_default(),
+        social_links: None,
+        interests: None,
+        invite_code: request.invite_code,
+    };
+    register_user(register_request, caller)
+}
@@ -0,0 +1,473 @@
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::auth::is_manager_or_admin;
+use crate::models::content::{PaginationParams, MAX_TITLE_LENGTH};
+use crate::models::creator::*;
+use crate::models::display::PaginatedResponse;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::notification::NotificationType;
+use crate::models::storage::Storage;
+use crate::models::user::{UserRole, UserSocialResponse, UserStatus};
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::pagination::page_bounds;
+use super::identity::has_linked_wallet;
+use crate::services::interaction::like_count;
+use crate::services::user::utils::hidden_from_rankings;
+
+// Creators and admins may publish articles and premium posts
+pub fn is_creator(principal: Principal) -> bool {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.users.get(&principal).is_some_and(|user| {
+            user.status == UserStatus::Active
+                && (user.role == UserRole::Creator || user.role == UserRole::Admin)
+        })
+    })
+}
+
+// Premium posts are for the author's followers; everyone else gets them without their content
+pub fn can_read_premium(store: &Storage, author: &Principal, reader: Principal) -> bool {
+    reader == *author
+        || store.user_profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(author))
+            .is_some_and(|profile| profile.followers.contains(&reader))
+}
+
+// Validate creator-only post attributes for the given author
+pub fn validate_creator_post(author: Principal, title: &Option<String>, is_premium: bool) -> SquareResult<()> {
+    const MODULE: &str = "services::user::creator";
+    const FUNCTION: &str = "validate_creator_post";
+
+    if title.is_none() && !is_premium {
+        return Ok(());
+    }
+
+    if !is_creator(author) {
+        return log_and_return(permission_denied_error(
+            "create_post",
+            "Articles and premium posts are only available to creators",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    if title.as_ref().is_some_and(|title| title.trim().is_empty() || title.chars().count() > MAX_TITLE_LENGTH) {
+        return log_and_return(validation_error(
+            &format!("Article title must be between 1 and {} characters", MAX_TITLE_LENGTH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    Ok(())
+}
+
+// Creator application flow
+pub fn apply_for_creator(request: CreatorApplicationRequest, caller: Principal) -> SquareResult<()> {
+    const MODULE: &str = "services::user::creator";
+    const FUNCTION: &str = "apply_for_creator";
+
+    let user = STORAGE.with(|storage| storage.borrow().users.get(&caller).cloned());
+    let user = match user {
+        Some(user) => user,
+        None => return log_and_return(not_found_error(
+            "User",
+            &caller.to_string(),
+            MODULE,
+            FUNCTION
+        ).with_details("User must be registered to apply")),
+    };
+
+    if user.role == UserRole::Creator {
+        return log_and_return(invalid_operation_error(
+            "apply_for_creator",
+            "User is already a creator",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    if user.status != UserStatus::Active {
+        return log_and_return(invalid_operation_error(
+            "apply_for_creator",
+            "Only active users can apply",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    if request.pitch.trim().is_empty() || request.pitch.len() > MAX_CREATOR_PITCH_LENGTH {
+        return log_and_return(validation_error(
+            &format!("Pitch must be between 1 and {} characters", MAX_CREATOR_PITCH_LENGTH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let portfolio_links = request.portfolio_links.unwrap_or_default();
+    if portfolio_links.len() > MAX_PORTFOLIO_LINKS {
+        return log_and_return(validation_error(
+            &format!("Too many portfolio links. Maximum allowed is {}", MAX_PORTFOLIO_LINKS),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let has_pending = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.creator_applications
+            .get(&caller)
+            .is_some_and(|application| application.status == CreatorApplicationStatus::Pending)
+    });
+
+    if has_pending {
+        return log_and_return(already_exists_error(
+            "CreatorApplication",
+            &caller.to_string(),
+            MODULE,
+            FUNCTION
+        ).with_details("A pending application already exists"));
+    }
+
+    let application = CreatorApplication {
+        applicant: caller,
+        pitch: request.pitch,
+        portfolio_links,
+        status: CreatorApplicationStatus::Pending,
+        submitted_at: time() / 1_000_000,
+        reviewed_at: None,
+        reviewer: None,
+        review_notes: None,
+    };
+
+    STORAGE.with(|storage| {
+        storage.borrow_mut().creator_applications.insert(caller, application);
+    });
+
+    Ok(())
+}
+
+pub fn get_creator_application(principal: Principal) -> SquareResult<CreatorApplication> {
+    const MODULE: &str = "services::user::creator";
+    const FUNCTION: &str = "get_creator_application";
+
+    STORAGE.with(|storage| storage.borrow().creator_applications.get(&principal).cloned())
+        .ok_or_else(|| not_found_error("CreatorApplication", &principal.to_string(), MODULE, FUNCTION))
+}
+
+pub fn review_creator_application(request: ReviewCreatorApplicationRequest, caller: Principal) -> SquareResult<()> {
+    const MODULE: &str = "services::user::creator";
+    const FUNCTION: &str = "review_creator_application";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "review_creator_application",
+            "Only managers or admins can review creator applications",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let mut application = get_creator_application(request.applicant)?;
+    if application.status != CreatorApplicationStatus::Pending {
+        return log_and_return(invalid_operation_error(
+            "review_creator_application",
+            "Application has already been reviewed",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    application.status = if request.approve {
+        CreatorApplicationStatus::Approved
+    } else {
+        CreatorApplicationStatus::Rejected
+    };
+    application.reviewed_at = Some(time() / 1_000_000);
+    application.reviewer = Some(caller);
+    application.review_notes = request.notes;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        // Approval only upgrades regular users, so admins and moderators keep their role
+        if let Some(mut user) = store.users
+            .get_mut(&request.applicant)
+            .filter(|user| request.approve && user.role == UserRole::User)
+        {
+            user.role = UserRole::Creator;
+        }
+        store.creator_applications.insert(request.applicant, application);
+    });
+
+    let message = if request.approve {
+        "Your creator application has been approved"
+    } else {
+        "Your creator application has been rejected"
+    };
+    super::notification::create_notification(
+        request.applicant,
+        NotificationType::System,
+        message.to_string(),
+        None,
+        Some(caller)
+    )?;
+
+    Ok(())
+}
+
+pub fn get_creator_applications(status: Option<CreatorApplicationStatus>, pagination: PaginationParams) -> SquareResult<PaginatedResponse<CreatorApplication>> {
+    const MODULE: &str = "services::user::creator";
+    const FUNCTION: &str = "get_creator_applications";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_creator_applications",
+            "Only managers or admins can list creator applications",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let mut applications: Vec<CreatorApplication> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.creator_applications
+            .values()
+            .filter(|application| status.as_ref().is_none_or(|s| application.status == *s))
+            .cloned()
+            .collect()
+    });
+
+    // Oldest first so reviewers work through the queue in order
+    applications.sort_by_key(|application| application.submitted_at);
+
+    Ok(paginate(applications, pagination))
+}
+
+// Creators directory, most followed first. Creators hidden from rankings are left out.
+pub fn get_creators(pagination: PaginationParams) -> SquareResult<PaginatedResponse<UserSocialResponse>> {
+    let mut creators: Vec<UserSocialResponse> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let profiles = match &store.user_profiles {
+            Some(profiles) => profiles,
+            None => return Vec::new(),
+        };
+
+        store.users
+            .values()
+            .filter(|user| user.role == UserRole::Creator && user.status == UserStatus::Active)
+            .filter(|user| !hidden_from_rankings(&store, &user.principal))
+            .filter_map(|user| profiles.get(&user.principal))
+            .map(|profile| UserSocialResponse {
+                principal: profile.principal,
+                username: profile.username.clone(),
+                handle: profile.handle.clone(),
+                avatar: profile.avatar.clone(),
+                bio: profile.bio.clone(),
+                interests: profile.interests.clone(),
+                followers_count: profile.followers_count,
+                following_count: profile.following_count,
+                is_following: false,
+                is_followed_by_caller: false,
+                has_linked_wallet: has_linked_wallet(&store, &profile.principal),
+                followed_at: None,
+            })
+            .collect()
+    });
+
+    creators.sort_by_key(|creator| std::cmp::Reverse(creator.followers_count));
+
+    Ok(paginate(creators, pagination))
+}
+
+// Creator analytics for the caller's own content
+pub fn get_creator_analytics(caller: Principal) -> SquareResult<CreatorAnalyticsResponse> {
+    const MODULE: &str = "services::user::creator";
+    const FUNCTION: &str = "get_creator_analytics";
+
+    if !is_creator(caller) {
+        return log_and_return(permission_denied_error(
+            "get_creator_analytics",
+            "Analytics are only available to creators",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let analytics = STORAGE.with(|storage| {
+        let store = storage.borrow();
+
+        let posts: Vec<_> = store.user_posts
+            .get(&caller)
+            .into_iter()
+            .flatten()
+            .filter_map(|post_id| store.posts.get(post_id))
+            .filter(|post| post.author == caller)
+            .collect();
+
+        let mut top_posts: Vec<(String, u64)> = posts
+            .iter()
+            .map(|post| (post.id.clone(), like_count(&store, &post.id)))
+            .collect();
+        let total_likes_received: u64 = top_posts.iter().map(|(_, likes)| likes).sum();
+
+        let total_comments_received: u64 = posts
+            .iter()
+            .map(|post| store.post_comments.get(&post.id).map_or(0, |comments| comments.len() as u64))
+            .sum();
+
+        let followers_count = store.user_profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(&caller))
+            .map_or(0, |profile| profile.followers_count);
+
+        let total_posts = posts.len() as u64;
+        let engagement_rate = if total_posts > 0 {
+            (total_likes_received + total_comments_received) as f64 / total_posts as f64
+        } else {
+            0.0
+        };
+
+        top_posts.sort_by_key(|(_, likes)| std::cmp::Reverse(*likes));
+        top_posts.truncate(5);
+
+        CreatorAnalyticsResponse {
+            total_posts,
+            total_articles: posts.iter().filter(|post| post.title.is_some()).count() as u64,
+            total_premium_posts: posts.iter().filter(|post| post.is_premium).count() as u64,
+            total_comments_received,
+            total_likes_received,
+            followers_count,
+            engagement_rate,
+            top_posts,
+        }
+    });
+
+    Ok(analytics)
+}
+
+// Auto-promotion thresholds (admin)
+pub fn get_creator_thresholds() -> SquareResult<CreatorPromotionThresholds> {
+    Ok(STORAGE.with(|storage| storage.borrow().creator_promotion_thresholds.clone().unwrap_or_default()))
+}
+
+pub fn update_creator_thresholds(request: UpdateCreatorThresholdsRequest) -> SquareResult<CreatorPromotionThresholds> {
+    const MODULE: &str = "services::user::creator";
+    const FUNCTION: &str = "update_creator_thresholds";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_creator_thresholds",
+            "Only managers or admins can update creator thresholds",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let mut thresholds = store.creator_promotion_thresholds.clone().unwrap_or_default();
+
+        if let Some(enabled) = request.enabled {
+            thresholds.enabled = enabled;
+        }
+        if let Some(min_followers) = request.min_followers {
+            thresholds.min_followers = min_followers;
+        }
+        if let Some(min_posts) = request.min_posts {
+            thresholds.min_posts = min_posts;
+        }
+        if let Some(min_likes_received) = request.min_likes_received {
+            thresholds.min_likes_received = min_likes_received;
+        }
+
+        store.creator_promotion_thresholds = Some(thresholds.clone());
+        Ok(thresholds)
+    })
+}
+
+// Promote active users who meet every engagement threshold; returns the promoted principals.
+// Runs from the heartbeat over a batch of users at a time, reading the post and like counts kept in
+// user stats.
+pub fn run_creator_auto_promotion() -> Vec<Principal> {
+    let thresholds = STORAGE.with(|storage| storage.borrow().creator_promotion_thresholds.clone().unwrap_or_default());
+    if !thresholds.enabled {
+        return Vec::new();
+    }
+
+    let now = time() / 1_000_000;
+    let promoted: Vec<Principal> = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+
+        let batch = store.users.keys_after(store.creator_promotion_cursor.as_ref(), CREATOR_PROMOTION_BATCH);
+        // Start over from the first user once the last batch is done
+        store.creator_promotion_cursor = if batch.len() < CREATOR_PROMOTION_BATCH { None } else { batch.last().copied() };
+
+        let candidates: Vec<Principal> = batch
+            .into_iter()
+            .filter(|principal| {
+                store.users
+                    .get(principal)
+                    .is_some_and(|user| user.role == UserRole::User && user.status == UserStatus::Active)
+            })
+            .filter(|principal| {
+                let followers = store.user_profiles
+                    .as_ref()
+                    .and_then(|profiles| profiles.get(principal))
+                    .map_or(0, |profile| profile.followers_count);
+                let (posts, likes_received) = store.user_stats
+                    .as_ref()
+                    .and_then(|stats| stats.get(principal))
+                    .map_or((0, 0), |stats| (stats.post_count, stats.like_count));
+                followers >= thresholds.min_followers
+                    && posts >= thresholds.min_posts
+                    && likes_received >= thresholds.min_likes_received
+            })
+            .collect();
+
+        for principal in &candidates {
+            if let Some(mut user) = store.users.get_mut(principal) {
+                user.role = UserRole::Creator;
+            }
+            store.creator_applications.insert(*principal, CreatorApplication {
+                applicant: *principal,
+                pitch: String::new(),
+                portfolio_links: Vec::new(),
+                status: CreatorApplicationStatus::AutoPromoted,
+                submitted_at: now,
+                reviewed_at: Some(now),
+                reviewer: None,
+                review_notes: Some("Met engagement thresholds".to_string()),
+            });
+        }
+
+        candidates
+    });
+
+    for principal in &promoted {
+        let _ = super::notification::create_notification(
+            *principal,
+            NotificationType::Achievement,
+            "Congratulations! You have been promoted to Creator".to_string(),
+            None,
+            None
+        );
+    }
+
+    promoted
+}
+
+pub(crate) fn paginate<T: Clone>(items: Vec<T>, pagination: PaginationParams) -> PaginatedResponse<T> {
+    let total = items.len();
+    let (start, end) = page_bounds(total, &pagination, 20);
+
+    PaginatedResponse {
+        items: items[start..end].to_vec(),
+        total,
+        has_more: end < total,
+        next_offset: end,
+    }
+}
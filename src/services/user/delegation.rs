@@ -0,0 +1,187 @@
+use candid::Principal;
+use ic_cdk::api::time;
+use std::collections::HashMap;
+
+use crate::auth::is_manager_or_admin;
+use crate::models::content::PaginationParams;
+use crate::models::delegation::*;
+use crate::models::error::{SquareError, SquareResult};
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::pagination::page_bounds;
+
+// Delegated posting: an organization account authorizes other principals to post on its behalf
+pub fn grant_posting_delegate(delegate: Principal, caller: Principal) -> SquareResult<()> {
+    const MODULE: &str = "services::user::delegation";
+    const FUNCTION: &str = "grant_posting_delegate";
+
+    if delegate == caller {
+        return log_and_return(validation_error(
+            "Cannot grant posting rights to yourself",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    // Both the organization and the delegate must be registered users
+    let (org_exists, delegate_exists) = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        (store.users.contains_key(&caller), store.users.contains_key(&delegate))
+    });
+
+    if !org_exists {
+        return log_and_return(not_found_error(
+            "User",
+            &caller.to_string(),
+            MODULE,
+            FUNCTION
+        ).with_details("Organization account is not registered"));
+    }
+
+    if !delegate_exists {
+        return log_and_return(not_found_error(
+            "User",
+            &delegate.to_string(),
+            MODULE,
+            FUNCTION
+        ).with_details("Delegate is not a registered user"));
+    }
+
+    let already_granted = is_posting_delegate(caller, delegate);
+    if already_granted {
+        return log_and_return(already_exists_error(
+            "PostingDelegation",
+            &delegate.to_string(),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        store.posting_delegations
+            .entry(caller)
+            .or_insert_with(HashMap::new)
+            .insert(delegate, PostingDelegation {
+                org: caller,
+                delegate,
+                granted_at: now,
+            });
+    });
+
+    record_delegated_action(caller, delegate, DelegatedAction::Granted, None);
+
+    Ok(())
+}
+
+pub fn revoke_posting_delegate(delegate: Principal, caller: Principal) -> SquareResult<()> {
+    const MODULE: &str = "services::user::delegation";
+    const FUNCTION: &str = "revoke_posting_delegate";
+
+    let removed = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let removed = store.posting_delegations
+            .get_mut(&caller)
+            .and_then(|delegates| delegates.remove(&delegate))
+            .is_some();
+
+        if store.posting_delegations.get(&caller).is_some_and(|delegates| delegates.is_empty()) {
+            store.posting_delegations.remove(&caller);
+        }
+
+        removed
+    });
+
+    if !removed {
+        return log_and_return(not_found_error(
+            "PostingDelegation",
+            &delegate.to_string(),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    record_delegated_action(caller, delegate, DelegatedAction::Revoked, None);
+
+    Ok(())
+}
+
+pub fn get_posting_delegates(org: Principal) -> SquareResult<Vec<PostingDelegation>> {
+    let mut delegates = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.posting_delegations
+            .get(&org)
+            .map(|delegates| delegates.values().cloned().collect::<Vec<_>>())
+            .unwrap_or_default()
+    });
+
+    delegates.sort_by_key(|delegation| std::cmp::Reverse(delegation.granted_at));
+
+    Ok(delegates)
+}
+
+// Check whether `delegate` may post on behalf of `org`
+pub fn is_posting_delegate(org: Principal, delegate: Principal) -> bool {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.posting_delegations
+            .get(&org)
+            .is_some_and(|delegates| delegates.contains_key(&delegate))
+    })
+}
+
+// Append an entry to the organization's audit trail, keeping only the most recent records
+pub fn record_delegated_action(org: Principal, delegate: Principal, action: DelegatedAction, content_id: Option<String>) {
+    let record = DelegatedActionRecord {
+        org,
+        delegate,
+        action,
+        content_id,
+        timestamp: time() / 1_000_000,
+    };
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let records = store.delegation_audit_log.entry(org).or_insert_with(Vec::new);
+        records.push(record);
+
+        if records.len() > MAX_DELEGATION_AUDIT_RECORDS {
+            let excess = records.len() - MAX_DELEGATION_AUDIT_RECORDS;
+            records.drain(0..excess);
+        }
+    });
+}
+
+// Audit trail is visible to the organization itself and to managers/admin
+pub fn get_delegation_audit_log(org: Principal, caller: Principal, pagination: PaginationParams) -> SquareResult<DelegationAuditResponse> {
+    const MODULE: &str = "services::user::delegation";
+    const FUNCTION: &str = "get_delegation_audit_log";
+
+    if org != caller && is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_delegation_audit_log",
+            "Only the organization account or managers can view its audit trail",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let records = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.delegation_audit_log.get(&org).cloned().unwrap_or_default()
+    });
+
+    // Newest first
+    let records: Vec<DelegatedActionRecord> = records.into_iter().rev().collect();
+
+    let total = records.len();
+    let (start, end) = page_bounds(total, &pagination, 20);
+
+    Ok(DelegationAuditResponse {
+        records: records[start..end].to_vec(),
+        total: total as u64,
+        has_more: end < total,
+        next_offset: end,
+    })
+}
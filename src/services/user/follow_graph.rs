@@ -0,0 +1,115 @@
+use candid::Principal;
+
+use crate::auth::is_admin;
+use crate::models::error::SquareResult;
+use crate::models::follow_graph::*;
+use crate::models::storage::Storage;
+use crate::models::user::UserSocialResponse;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::TimestampMillis;
+
+const MODULE: &str = "services::user::follow_graph";
+
+// The last exported edge, as `follower:followee`
+fn parse_cursor(cursor: &str) -> Option<(Principal, Principal)> {
+    let (follower, followee) = cursor.split_once(':')?;
+    Some((Principal::from_text(follower).ok()?, Principal::from_text(followee).ok()?))
+}
+
+// When `follower` followed `followee`, if it was recorded
+pub fn follow_time(store: &Storage, follower: &Principal, followee: &Principal) -> Option<TimestampMillis> {
+    store.follow_times.get(follower).and_then(|times| times.get(followee)).copied()
+}
+
+// Most recent follows first; follows without a recorded time last
+pub fn sort_by_follow_time(users: &mut [UserSocialResponse]) {
+    users.sort_by(|a, b| b.followed_at.cmp(&a.followed_at).then_with(|| a.principal.cmp(&b.principal)));
+}
+
+// Date a new follow; follows made again after an unfollow get the new time
+pub fn record_follow_time(store: &mut Storage, follower: Principal, followee: Principal, now: TimestampMillis) {
+    store.follow_times.entry(follower).or_default().insert(followee, now);
+}
+
+pub fn remove_follow_time(store: &mut Storage, follower: Principal, followee: Principal) {
+    if let Some(times) = store.follow_times.get_mut(&follower) {
+        times.remove(&followee);
+        if times.is_empty() {
+            store.follow_times.remove(&follower);
+        }
+    }
+}
+
+// Every follow edge, FOLLOW_GRAPH_PAGE_SIZE at a time, ordered by follower in the profile map's key
+// order and then by followee.
+// Follows made or removed between pages show up or drop out when they sort after the cursor.
+pub fn export_follow_graph(cursor: Option<String>) -> SquareResult<FollowGraphPage> {
+    const FUNCTION: &str = "export_follow_graph";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "export_follow_graph",
+            "Only admins can export the follow graph",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    let after = match cursor.as_deref().map(parse_cursor) {
+        Some(None) => return log_and_return(field_validation_error("cursor", "is not a cursor returned by export_follow_graph", MODULE, FUNCTION)),
+        Some(after) => after,
+        None => None,
+    };
+
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let Some(profiles) = &store.user_profiles else {
+            return Ok(FollowGraphPage { edges: Vec::new(), next_cursor: None });
+        };
+
+        // One edge past the page tells whether there is a next page
+        let mut edges = Vec::new();
+        let add_edges = |edges: &mut Vec<FollowEdge>, follower: &Principal, after_followee: Option<&Principal>| {
+            let Some(profile) = profiles.get(follower) else {
+                return;
+            };
+            let mut followees: Vec<&Principal> = profile.followed_users
+                .iter()
+                .filter(|followee| after_followee.is_none_or(|after| *followee > after))
+                .collect();
+            followees.sort_unstable();
+            edges.extend(followees.into_iter().take(FOLLOW_GRAPH_PAGE_SIZE + 1 - edges.len()).map(|followee| FollowEdge {
+                follower: *follower,
+                followee: *followee,
+                followed_at: follow_time(&store, follower, followee),
+            }));
+        };
+
+        // The rest of the cursor's follower, then followers in key order from there
+        if let Some((follower, followee)) = &after {
+            add_edges(&mut edges, follower, Some(followee));
+        }
+        let mut cursor = after.map(|(follower, _)| follower);
+        while edges.len() <= FOLLOW_GRAPH_PAGE_SIZE {
+            let followers = profiles.keys_after(cursor.as_ref(), FOLLOW_GRAPH_PAGE_SIZE);
+            let Some(last) = followers.last().copied() else {
+                break;
+            };
+            for follower in &followers {
+                add_edges(&mut edges, follower, None);
+                if edges.len() > FOLLOW_GRAPH_PAGE_SIZE {
+                    break;
+                }
+            }
+            cursor = Some(last);
+        }
+
+        let next_cursor = if edges.len() > FOLLOW_GRAPH_PAGE_SIZE {
+            edges.truncate(FOLLOW_GRAPH_PAGE_SIZE);
+            edges.last().map(|edge| format!("{}:{}", edge.follower, edge.followee))
+        } else {
+            None
+        };
+        Ok(FollowGraphPage { edges, next_cursor })
+    })
+}
@@ -0,0 +1,191 @@
+use candid::{Nat, Principal};
+use ic_cdk::api::{call, time};
+use sha2::{Digest, Sha256};
+
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::identity::*;
+use crate::models::storage::Storage;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+
+const MODULE: &str = "services::user::identity";
+
+// Verified accounts linked to a user, oldest first
+pub fn linked_accounts(store: &Storage, principal: &Principal) -> Vec<LinkedAccount> {
+    store.linked_accounts.get(principal).cloned().unwrap_or_default()
+}
+
+pub fn has_linked_wallet(store: &Storage, principal: &Principal) -> bool {
+    store.linked_accounts.get(principal).is_some_and(|accounts| !accounts.is_empty())
+}
+
+fn is_linked_elsewhere(store: &Storage, account: &Principal) -> bool {
+    store.linked_account_owners.contains_key(account)
+}
+
+fn generate_challenge(user: Principal, account: Principal, nonce: usize) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}:{}:{}:{}", user, account, time(), nonce).as_bytes());
+    hasher.finalize().iter().take(16).map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Step one: the user names the account to link and receives a short-lived challenge
+pub fn request_account_link(request: RequestAccountLinkRequest, caller: Principal) -> SquareResult<AccountLinkChallenge> {
+    const FUNCTION: &str = "request_account_link";
+
+    if request.account == caller || request.account == Principal::anonymous() {
+        return log_and_return(field_validation_error(
+            "account",
+            "must be a different, non-anonymous principal",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if !store.users.contains_key(&caller) {
+            return log_and_return(not_found_error("User", &caller.to_string(), MODULE, FUNCTION));
+        }
+        if is_linked_elsewhere(&store, &request.account) {
+            return log_and_return(already_exists_error("LinkedAccount", &request.account.to_string(), MODULE, FUNCTION));
+        }
+        if store.linked_accounts.get(&caller).map_or(0, |accounts| accounts.len()) >= MAX_LINKED_ACCOUNTS {
+            return log_and_return(quota_exceeded_error("linked_accounts", MAX_LINKED_ACCOUNTS as u64, MODULE, FUNCTION));
+        }
+
+        // Drop expired challenges and the caller's earlier challenge for this account, which the new one replaces
+        store.pending_account_links.retain(|_, pending| {
+            pending.expires_at > now && !(pending.user == caller && pending.account == request.account)
+        });
+        let pending_count = store.pending_account_links.values().filter(|pending| pending.user == caller).count();
+        if pending_count >= MAX_PENDING_ACCOUNT_LINKS {
+            return log_and_return(quota_exceeded_error("pending_account_links", MAX_PENDING_ACCOUNT_LINKS as u64, MODULE, FUNCTION));
+        }
+
+        let challenge = generate_challenge(caller, request.account, store.pending_account_links.len());
+        let expires_at = now + ACCOUNT_LINK_CHALLENGE_TTL_MS;
+        store.pending_account_links.insert(challenge.clone(), PendingAccountLink {
+            user: caller,
+            account: request.account,
+            kind: request.kind,
+            expires_at,
+        });
+
+        Ok(AccountLinkChallenge {
+            challenge,
+            account: request.account,
+            kind: request.kind,
+            expires_at,
+        })
+    })
+}
+
+// Step two: the linked account signs the challenge by submitting it in a call made with its own identity
+pub fn confirm_account_link(challenge: String, caller: Principal) -> SquareResult<LinkedAccount> {
+    const FUNCTION: &str = "confirm_account_link";
+
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let Some(pending) = store.pending_account_links.get(&challenge).cloned() else {
+            return log_and_return(not_found_error("AccountLinkChallenge", &challenge, MODULE, FUNCTION));
+        };
+        if pending.expires_at <= now {
+            store.pending_account_links.remove(&challenge);
+            return log_and_return(invalid_operation_error(
+                "confirm_account_link",
+                "Challenge has expired; request a new one",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        if pending.account != caller {
+            return log_and_return(permission_denied_error(
+                "confirm_account_link",
+                "The challenge must be signed by the account being linked",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        if is_linked_elsewhere(&store, &caller) {
+            store.pending_account_links.remove(&challenge);
+            return log_and_return(already_exists_error("LinkedAccount", &caller.to_string(), MODULE, FUNCTION));
+        }
+
+        store.pending_account_links.remove(&challenge);
+        let linked = LinkedAccount {
+            principal: caller,
+            kind: pending.kind,
+            verified_at: now,
+        };
+        store.linked_accounts.entry(pending.user).or_default().push(linked.clone());
+        store.linked_account_owners.insert(caller, pending.user);
+        Ok(linked)
+    })
+}
+
+pub fn unlink_account(account: Principal, caller: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "unlink_account";
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let accounts = store.linked_accounts.entry(caller).or_default();
+        let before = accounts.len();
+        accounts.retain(|linked| linked.principal != account);
+        let removed = accounts.len() < before;
+        if accounts.is_empty() {
+            store.linked_accounts.remove(&caller);
+        }
+
+        if !removed {
+            return log_and_return(not_found_error("LinkedAccount", &account.to_string(), MODULE, FUNCTION));
+        }
+        store.linked_account_owners.remove(&account);
+        Ok(())
+    })
+}
+
+pub fn get_my_linked_accounts(caller: Principal) -> SquareResult<Vec<LinkedAccount>> {
+    Ok(STORAGE.with(|storage| linked_accounts(&storage.borrow(), &caller)))
+}
+
+// ICRC-1 balance of one principal's default account
+pub async fn icrc1_balance(ledger: Principal, owner: Principal) -> Result<Nat, String> {
+    let account = Icrc1Account { owner, subaccount: None };
+    let response: Result<(Nat,), _> = call::call(ledger, "icrc1_balance_of", (account,)).await;
+    response
+        .map(|(balance,)| balance)
+        .map_err(|(code, message)| format!("icrc1_balance_of failed with code {:?}: {}", code, message))
+}
+
+// Sum a token's balance over the user's principal and every linked account
+pub async fn token_holdings(user: Principal, ledger: Principal) -> SquareResult<TokenHoldings> {
+    const FUNCTION: &str = "token_holdings";
+
+    let mut owners = vec![user];
+    owners.extend(STORAGE.with(|storage| {
+        linked_accounts(&storage.borrow(), &user).into_iter().map(|linked| linked.principal)
+            .collect::<Vec<_>>()
+    }));
+
+    let mut total = Nat::from(0u64);
+    let mut balances = Vec::with_capacity(owners.len());
+    for owner in owners {
+        let balance = match icrc1_balance(ledger, owner).await {
+            Ok(balance) => balance,
+            Err(message) => {
+                return log_and_return(service_unavailable_error(&ledger.to_string(), &message, MODULE, FUNCTION));
+            }
+        };
+        total += balance.clone();
+        balances.push((owner, balance));
+    }
+
+    Ok(TokenHoldings {
+        ledger_canister_id: ledger,
+        total,
+        balances,
+    })
+}
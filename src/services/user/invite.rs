@@ -0,0 +1,286 @@
+use candid::Principal;
+use ic_cdk::api::management_canister::main::raw_rand;
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::auth::is_admin;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::invite::*;
+use crate::models::storage::Storage;
+use crate::models::user::UserStatus;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::{MILLIS_PER_DAY, MILLIS_PER_WEEK};
+
+// Unambiguous characters only (no 0/O, 1/I/L)
+const INVITE_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+// Randomness from the management canister; codes derived from it cannot be predicted by callers
+async fn invite_seed(module: &str, function: &str) -> SquareResult<Vec<u8>> {
+    match raw_rand().await {
+        Ok((bytes,)) => Ok(bytes),
+        Err((_, message)) => log_and_return(service_unavailable_error("raw_rand", &message, module, function)),
+    }
+}
+
+// Invite-gated registration for controlled launches. Each code hashes the random seed with a
+// counter, so one seed serves a whole batch.
+fn generate_invite_code(store: &Storage, seed: &[u8], nonce: &mut u64) -> String {
+    loop {
+        let mut hasher = Sha256::new();
+        hasher.update(seed);
+        hasher.update(nonce.to_le_bytes());
+        *nonce += 1;
+        let code: String = hasher
+            .finalize()
+            .iter()
+            .take(INVITE_CODE_LENGTH)
+            .map(|byte| INVITE_CODE_ALPHABET[*byte as usize % INVITE_CODE_ALPHABET.len()] as char)
+            .collect();
+        if !store.invite_codes.contains_key(&code) {
+            return code;
+        }
+    }
+}
+
+fn invite_status(invite: &InviteCode, now: u64) -> InviteStatus {
+    if invite.used_by.is_some() {
+        InviteStatus::Used
+    } else if invite.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        InviteStatus::Expired
+    } else {
+        InviteStatus::Available
+    }
+}
+
+fn to_invite_response(invite: &InviteCode, now: u64) -> InviteCodeResponse {
+    InviteCodeResponse {
+        code: invite.code.clone(),
+        status: invite_status(invite, now),
+        created_at: invite.created_at,
+        expires_at: invite.expires_at,
+        used_by: invite.used_by,
+        used_at: invite.used_at,
+    }
+}
+
+// Creation times of the codes a user generated within the last rolling week, oldest first
+fn codes_generated_this_week(store: &Storage, user: Principal, now: u64) -> Vec<u64> {
+    let mut created: Vec<u64> = store.invite_codes
+        .values()
+        .filter(|invite| invite.created_by == user && invite.source == InviteSource::User)
+        .filter(|invite| invite.created_at + MILLIS_PER_WEEK > now)
+        .map(|invite| invite.created_at)
+        .collect();
+    created.sort_unstable();
+    created
+}
+
+// Called from registration while invite-only mode is enabled; marks the code as used
+pub fn redeem_invite_code(store: &mut Storage, code: Option<&str>, user: Principal, now: u64) -> SquareResult<()> {
+    const MODULE: &str = "services::user::invite";
+    const FUNCTION: &str = "redeem_invite_code";
+
+    if !store.invite_settings.clone().unwrap_or_default().invite_only {
+        return Ok(());
+    }
+
+    let Some(code) = code.map(|code| code.trim().to_uppercase()).filter(|code| !code.is_empty()) else {
+        return log_and_return(field_validation_error(
+            "invite_code",
+            "is required while registration is invite-only",
+            MODULE,
+            FUNCTION
+        ));
+    };
+
+    let Some(invite) = store.invite_codes.get_mut(&code) else {
+        return log_and_return(not_found_error("InviteCode", &code, MODULE, FUNCTION));
+    };
+
+    match invite_status(invite, now) {
+        InviteStatus::Available => {
+            invite.used_by = Some(user);
+            invite.used_at = Some(now);
+            Ok(())
+        }
+        InviteStatus::Used => log_and_return(invalid_operation_error(
+            "register_user",
+            "Invite code has already been used",
+            MODULE,
+            FUNCTION
+        )),
+        InviteStatus::Expired => log_and_return(invalid_operation_error(
+            "register_user",
+            "Invite code has expired",
+            MODULE,
+            FUNCTION
+        )),
+    }
+}
+
+// Active users within their weekly allowance may create a code
+fn check_can_create_invite(store: &Storage, caller: Principal, now: u64) -> SquareResult<()> {
+    const MODULE: &str = "services::user::invite";
+    const FUNCTION: &str = "create_invite_code";
+
+    if !store.users.get(&caller).is_some_and(|user| user.status == UserStatus::Active) {
+        return log_and_return(permission_denied_error(
+            "create_invite_code",
+            "Only active registered users can create invite codes",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let settings = store.invite_settings.clone().unwrap_or_default();
+    if codes_generated_this_week(store, caller, now).len() >= settings.invites_per_week as usize {
+        return log_and_return(quota_exceeded_error(
+            "weekly_invite_codes",
+            settings.invites_per_week as u64,
+            MODULE,
+            FUNCTION
+        ));
+    }
+    Ok(())
+}
+
+pub async fn create_invite_code(caller: Principal) -> SquareResult<InviteCodeResponse> {
+    const MODULE: &str = "services::user::invite";
+    const FUNCTION: &str = "create_invite_code";
+
+    STORAGE.with(|storage| check_can_create_invite(&storage.borrow(), caller, time() / 1_000_000))?;
+    let seed = invite_seed(MODULE, FUNCTION).await?;
+    let now = time() / 1_000_000;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        // Checked again, as other calls may have used up the allowance during the await
+        check_can_create_invite(&store, caller, now)?;
+
+        let invite = InviteCode {
+            code: generate_invite_code(&store, &seed, &mut 0),
+            created_by: caller,
+            source: InviteSource::User,
+            created_at: now,
+            expires_at: None,
+            used_by: None,
+            used_at: None,
+        };
+        let response = to_invite_response(&invite, now);
+        store.invite_codes.insert(invite.code.clone(), invite);
+        Ok(response)
+    })
+}
+
+pub fn get_my_invites(caller: Principal) -> SquareResult<MyInvitesResponse> {
+    let now = time() / 1_000_000;
+
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let settings = store.invite_settings.clone().unwrap_or_default();
+
+        let mut invites: Vec<&InviteCode> = store.invite_codes
+            .values()
+            .filter(|invite| invite.created_by == caller && invite.source == InviteSource::User)
+            .collect();
+        invites.sort_by_key(|invite| std::cmp::Reverse(invite.created_at));
+
+        let generated = codes_generated_this_week(&store, caller, now);
+        let remaining_this_week = settings.invites_per_week.saturating_sub(generated.len() as u32);
+        let next_available_at = if remaining_this_week == 0 {
+            // The oldest code in the window frees up a slot once it leaves the week
+            let index = generated.len().saturating_sub(settings.invites_per_week as usize);
+            generated.get(index).map(|created_at| created_at + MILLIS_PER_WEEK)
+        } else {
+            None
+        };
+
+        Ok(MyInvitesResponse {
+            invites: invites.into_iter().map(|invite| to_invite_response(invite, now)).collect(),
+            remaining_this_week,
+            next_available_at,
+        })
+    })
+}
+
+// Admin functions
+pub async fn mint_invite_codes(request: MintInviteCodesRequest, caller: Principal) -> SquareResult<Vec<String>> {
+    const MODULE: &str = "services::user::invite";
+    const FUNCTION: &str = "mint_invite_codes";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "mint_invite_codes",
+            "Only admins can mint invite codes",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    if request.count == 0 || request.count > MAX_INVITE_MINT_BATCH {
+        return log_and_return(field_validation_error(
+            "count",
+            &format!("must be between 1 and {}", MAX_INVITE_MINT_BATCH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let seed = invite_seed(MODULE, FUNCTION).await?;
+    let now = time() / 1_000_000;
+    let expires_at = request.expires_in_days.map(|days| now + days * MILLIS_PER_DAY);
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let mut codes = Vec::with_capacity(request.count as usize);
+        let mut nonce = 0;
+        for _ in 0..request.count {
+            let code = generate_invite_code(&store, &seed, &mut nonce);
+            store.invite_codes.insert(code.clone(), InviteCode {
+                code: code.clone(),
+                created_by: caller,
+                source: InviteSource::Admin,
+                created_at: now,
+                expires_at,
+                used_by: None,
+                used_at: None,
+            });
+            codes.push(code);
+        }
+        Ok(codes)
+    })
+}
+
+pub fn get_invite_settings() -> SquareResult<InviteSettings> {
+    Ok(STORAGE.with(|storage| storage.borrow().invite_settings.clone().unwrap_or_default()))
+}
+
+pub fn update_invite_settings(request: UpdateInviteSettingsRequest) -> SquareResult<InviteSettings> {
+    const MODULE: &str = "services::user::invite";
+    const FUNCTION: &str = "update_invite_settings";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_invite_settings",
+            "Only admins can update invite settings",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let mut settings = store.invite_settings.clone().unwrap_or_default();
+
+        if let Some(invite_only) = request.invite_only {
+            settings.invite_only = invite_only;
+        }
+        if let Some(invites_per_week) = request.invites_per_week {
+            settings.invites_per_week = invites_per_week;
+        }
+
+        store.invite_settings = Some(settings.clone());
+        Ok(settings)
+    })
+}
@@ -9,12 +9,41 @@ pub mod notification;
 pub mod privacy;
 pub mod sync;
 pub mod utils;
+pub mod delegation;
+pub mod creator;
+pub mod invite;
+pub mod quality;
+pub mod trust;
+pub mod identity;
+pub mod showcase;
+pub mod visitors;
+pub mod name_policy;
+pub mod companion;
+pub mod cards;
+pub mod quota;
+pub mod post_alerts;
+pub mod activity;
+pub mod follow_graph;
 
 // Re-export commonly used functions and types for convenience
-pub use profile::{register_user, update_user_profile, get_user_profile, get_user_full_profile, debug_fix_user_profile};
-pub use social::{follow_user, unfollow_user, follow_topic, unfollow_topic, get_followers, get_following, get_user_social_info};
-pub use admin::{update_user_status, update_user_role, verify_user, debug_list_all_users};
-pub use notification::{create_notification, get_user_notifications, mark_notification_as_read, mark_all_notifications_as_read};
+pub use profile::{register_user, update_user_profile, get_user_profile, get_user_full_profile, debug_fix_user_profile, deactivate_account, reactivate_account, record_login, set_timezone_offset};
+pub use social::{repair_follow_counters, run_follow_counter_repair, follow_user, unfollow_user, follow_topic, unfollow_topic, get_followers, get_following, get_user_social_info};
+pub use admin::{update_user_status, update_user_role, verify_user, debug_list_all_users, list_users_admin, get_user_moderation_history, bulk_update_user_status, get_user_content_counts};
+pub use post_alerts::{set_post_alerts, get_post_alerts, run_post_alert_fanout};
+pub use notification::{create_notification, get_notifications_since, get_user_notifications, mark_notification_as_read, mark_all_notifications_as_read, clear_notifications, prune_notifications, get_notification_retention, update_notification_retention};
 pub use privacy::{update_privacy_settings, get_privacy_settings};
-pub use sync::{sync_user_data, synchronize_all_user_data, debug_fix_user_data};
-pub use utils::{find_user_by_handle, get_user_leaderboard};
\ No newline at end of file
+pub use sync::{sync_user_data, schedule_user_data_sync, run_user_data_sync, debug_fix_user_data};
+pub use utils::{find_user_by_handle, resolve_user_identifier, get_user_leaderboard, get_my_leaderboard_rank, hidden_from_rankings, deactivated_users, user_timezone_offset};
+pub use delegation::{grant_posting_delegate, revoke_posting_delegate, get_posting_delegates, get_delegation_audit_log};
+pub use creator::{apply_for_creator, get_creator_application, review_creator_application, get_creator_applications, get_creators, get_creator_analytics, get_creator_thresholds, update_creator_thresholds};
+pub use invite::{create_invite_code, get_my_invites, mint_invite_codes, get_invite_settings, update_invite_settings};
+pub use quality::{account_quality, check_action_rate_limit, get_account_quality, get_my_account_quality};
+pub use trust::{check_content_privileges, check_posting_privileges, get_my_trust_level, get_trust_settings, update_trust_settings};
+pub use quota::{get_my_storage_usage, get_storage_quota_settings, update_storage_quota_settings, set_storage_quota_override};
+pub use showcase::{add_showcase_nft, remove_showcase_nft};
+pub use identity::{request_account_link, confirm_account_link, unlink_account, get_my_linked_accounts, token_holdings};
+pub use visitors::{record_profile_visit, get_profile_visitors};
+pub use activity::get_my_activity_calendar;
+pub use follow_graph::export_follow_graph;
+pub use name_policy::{check_name_policy, get_name_policy, update_name_policy};
+pub use companion::{register_from, register_companion_canister, remove_companion_canister, get_companion_canisters};
@@ -0,0 +1,123 @@
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::auth::{is_admin, is_manager_or_admin};
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::name_policy::*;
+use crate::storage::STORAGE;
+use crate::utils::content_utils::name_skeleton;
+use crate::utils::error_handler::*;
+
+const MODULE: &str = "services::user::name_policy";
+
+fn denied_term(settings: &NamePolicySettings, name: &str) -> Option<String> {
+    let skeleton = name_skeleton(name);
+    settings.denied_terms
+        .iter()
+        .find(|term| !term.is_empty() && skeleton.contains(&name_skeleton(term)))
+        .cloned()
+}
+
+fn is_reserved_handle(settings: &NamePolicySettings, handle: &str) -> bool {
+    let skeleton = name_skeleton(handle);
+    SYSTEM_RESERVED_HANDLES
+        .iter()
+        .copied()
+        .chain(settings.reserved_handles.iter().map(String::as_str))
+        .any(|reserved| name_skeleton(reserved) == skeleton)
+}
+
+// Reject usernames and handles that contain denied terms or imitate a reserved handle.
+// Managers and admins may take reserved handles for official accounts.
+pub fn check_name_policy(username: Option<&str>, handle: Option<&str>, function: &str) -> SquareResult<()> {
+    let settings = STORAGE.with(|storage| storage.borrow().name_policy.clone().unwrap_or_default());
+
+    for (field, name) in [("username", username), ("handle", handle)] {
+        let Some(name) = name else {
+            continue;
+        };
+        if denied_term(&settings, name).is_some() {
+            return log_and_return(field_validation_error(field, "contains a word that is not allowed", MODULE, function));
+        }
+    }
+    let reserved = handle.filter(|handle| is_reserved_handle(&settings, handle) && is_manager_or_admin().is_err());
+    if let Some(handle) = reserved {
+        return log_and_return(field_validation_error(
+            "handle",
+            &format!("'{}' is reserved or too similar to a reserved handle", handle),
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage the name policy",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+pub fn get_name_policy() -> SquareResult<NamePolicySettings> {
+    const FUNCTION: &str = "get_name_policy";
+
+    require_admin("get_name_policy", FUNCTION)?;
+    Ok(STORAGE.with(|storage| storage.borrow().name_policy.clone().unwrap_or_default()))
+}
+
+fn normalize_terms(terms: Vec<String>, field: &str, max: usize, function: &str) -> SquareResult<Vec<String>> {
+    let mut terms: Vec<String> = terms
+        .iter()
+        .map(|term| term.trim().to_lowercase())
+        .filter(|term| !term.is_empty())
+        .collect();
+    terms.sort();
+    terms.dedup();
+    if terms.len() > max {
+        return log_and_return(field_validation_error(field, &format!("can list at most {} entries", max), MODULE, function));
+    }
+    // Terms that fold to nothing would match every name
+    if let Some(term) = terms.iter().find(|term| term.chars().count() > MAX_NAME_TERM_LENGTH || name_skeleton(term).is_empty()) {
+        return log_and_return(field_validation_error(
+            field,
+            &format!("\"{}\" must contain letters or digits and be at most {} characters", term, MAX_NAME_TERM_LENGTH),
+            MODULE,
+            function
+        ));
+    }
+    Ok(terms)
+}
+
+// Existing names are left alone; the policy applies to registrations and profile edits from now on
+pub fn update_name_policy(request: UpdateNamePolicyRequest, caller: Principal) -> SquareResult<NamePolicySettings> {
+    const FUNCTION: &str = "update_name_policy";
+
+    require_admin("update_name_policy", FUNCTION)?;
+    let denied_terms = request.denied_terms
+        .map(|terms| normalize_terms(terms, "denied_terms", MAX_DENIED_NAME_TERMS, FUNCTION))
+        .transpose()?;
+    let reserved_handles = request.reserved_handles
+        .map(|handles| normalize_terms(handles, "reserved_handles", MAX_RESERVED_HANDLES, FUNCTION))
+        .transpose()?;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let mut settings = store.name_policy.clone().unwrap_or_default();
+        if let Some(denied_terms) = denied_terms {
+            settings.denied_terms = denied_terms;
+        }
+        if let Some(reserved_handles) = reserved_handles {
+            settings.reserved_handles = reserved_handles;
+        }
+        settings.updated_by = Some(caller);
+        settings.updated_at = time() / 1_000_000;
+        store.name_policy = Some(settings.clone());
+        Ok(settings)
+    })
+}
@@ -1,13 +1,63 @@
 use candid::Principal;
 use ic_cdk::api::time;
-use std::collections::HashMap;
-use std::borrow::{Borrow, BorrowMut};
+use std::collections::VecDeque;
 
+use crate::auth::is_admin;
 use crate::models::notification::*;
-use crate::models::error::{SquareError, SquareResult};
+use crate::models::error::SquareResult;
 use crate::models::content::PaginationParams;
-use crate::storage::{STORAGE};
+use crate::models::storage::Storage;
+use crate::storage::STORAGE;
 use crate::utils::error_handler::*;
+use crate::utils::logger;
+use crate::utils::pagination::page_bounds;
+use crate::utils::time_utils::{TimestampMillis, MILLIS_PER_DAY};
+use crate::utils::validators::validate_notification_retention;
+
+const MODULE: &str = "services::user::notification";
+
+fn next_seq(sync: &mut NotificationSyncState) -> u64 {
+    sync.last_seq += 1;
+    sync.last_seq
+}
+
+fn to_response(notification: &UserNotification) -> NotificationResponse {
+    NotificationResponse {
+        id: notification.id.clone(),
+        notification_type: notification.notification_type.clone(),
+        content: notification.content.clone(),
+        related_user: notification.related_user,
+        related_content_id: notification.related_content_id.clone(),
+        created_at: notification.created_at,
+        read: notification.read,
+        seq: notification.seq,
+    }
+}
+
+fn retention_settings(store: &Storage) -> NotificationRetentionSettings {
+    store.notification_retention.clone().unwrap_or_default()
+}
+
+// Drop notifications past the retention count or age from the front of a user's ring buffer,
+// remembering how far pruning reached so devices behind it resync. Returns how many were dropped.
+fn apply_retention(
+    notifications: &mut VecDeque<UserNotification>,
+    sync: &mut NotificationSyncState,
+    settings: &NotificationRetentionSettings,
+    now: TimestampMillis,
+) -> usize {
+    let cutoff = settings.max_age_days.map_or(0, |days| now.saturating_sub(days.saturating_mul(MILLIS_PER_DAY)));
+    let mut pruned = 0;
+    while let Some(oldest) = notifications.front() {
+        if notifications.len() as u64 <= settings.max_per_user && oldest.created_at >= cutoff {
+            break;
+        }
+        sync.pruned_through = sync.pruned_through.max(oldest.seq);
+        notifications.pop_front();
+        pruned += 1;
+    }
+    pruned
+}
 
 // Notification functions
 pub fn create_notification(
@@ -17,14 +67,33 @@ pub fn create_notification(
     related_entity_id: Option<String>,
     related_user: Option<Principal>
 ) -> SquareResult<()> {
-    const MODULE: &str = "services::user::notification";
-    const FUNCTION: &str = "create_notification";
-    
     let now = time() / 1_000_000;
-    let notification_id = format!("{}-{}", now, user_principal);
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        push_notification(&mut store, user_principal, notification_type, content, related_entity_id, related_user, now);
+    });
     
-    let notification = UserNotification {
-        id: notification_id,
+    Ok(())
+}
+
+// Store a notification at the back of the user's ring buffer, for callers already holding storage
+pub(crate) fn push_notification(
+    store: &mut Storage,
+    user_principal: Principal,
+    notification_type: NotificationType,
+    content: String,
+    related_entity_id: Option<String>,
+    related_user: Option<Principal>,
+    now: TimestampMillis,
+) {
+    let settings = retention_settings(store);
+    let sync = store.notification_sync.entry(user_principal).or_default();
+    let user_notifications = store.user_notifications.entry(user_principal).or_default();
+    
+    // The sequence number keeps ids unique when a user gets several notifications in one call
+    let seq = next_seq(sync);
+    user_notifications.push_back(UserNotification {
+        id: format!("{}-{}-{}", now, user_principal, seq),
         user_id: user_principal,
         notification_type,
         content,
@@ -32,103 +101,47 @@ pub fn create_notification(
         related_user,
         read: false,
         created_at: now,
-    };
-    
-    // Store notification in main storage
-    STORAGE.with(|storage| {
-        let mut store = storage.borrow_mut();
-        
-        // Get user notifications
-        if store.user_notifications.contains_key(&user_principal) {
-            // User already has notifications
-        } else {
-            // Create empty notifications list for user
-            store.user_notifications.insert(user_principal, Vec::new());
-        }
-        
-        // Get user notifications
-        if let Some(user_notifications) = store.user_notifications.get_mut(&user_principal) {
-            // Add notification to user's notifications list
-            
-            // Add notification
-            user_notifications.push(notification);
-            
-            // Sort notifications by creation time (newest first)
-            user_notifications.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-            
-            // Limit to max notifications per user
-            const MAX_NOTIFICATIONS: usize = 100;
-            if user_notifications.len() > MAX_NOTIFICATIONS {
-                *user_notifications = user_notifications[0..MAX_NOTIFICATIONS].to_vec();
-            }
-        }
+        seq,
     });
-    
-    Ok(())
+    apply_retention(user_notifications, sync, &settings, now);
 }
 
+// Newest first
 pub fn get_user_notifications(principal: Principal, pagination: PaginationParams) -> SquareResult<NotificationsResponse> {
-    const MODULE: &str = "services::user::notification";
-    const FUNCTION: &str = "get_user_notifications";
-    
-    // Get notifications from main storage
-    let notifications_result = STORAGE.with(|storage| {
+    STORAGE.with(|storage| {
         let store = storage.borrow();
-        store.user_notifications.get(&principal).cloned()
-    });
-    
-    // Get notifications
-    let notifications = notifications_result.unwrap_or_default();
-    
-    // Apply pagination
-    let total = notifications.len() as u64;
-    let start = pagination.offset.unwrap_or(0);
-    let limit = pagination.limit.unwrap_or(10);
-    let end = std::cmp::min(start + limit, notifications.len());
-    
-    // Get paginated notifications
-    let paginated_notifications = if start < notifications.len() {
-        notifications[start..end].to_vec()
-    } else {
-        Vec::new()
-    };
-    
-    // Count unread notifications
-    let unread_count = notifications.iter().filter(|n| !n.read).count() as u64;
-    
-    Ok(NotificationsResponse {
-        notifications: paginated_notifications.into_iter().map(|n| NotificationResponse {
-            id: n.id,
-            notification_type: n.notification_type,
-            content: n.content,
-            related_user: n.related_user,
-            related_content_id: n.related_content_id,
-            created_at: n.created_at,
-            read: n.read,
-        }).collect(),
-        total,
-        unread_count,
-        has_more: (start + limit) < notifications.len(),
-        next_cursor: Some(if (start + limit) < notifications.len() {
-            (start + limit).to_string()
-        } else {
-            start.to_string()
-        }),
+        let empty = VecDeque::new();
+        let notifications = store.user_notifications.get(&principal).unwrap_or(&empty);
+        
+        let total = notifications.len();
+        let (start, end) = page_bounds(total, &pagination, 10);
+        let unread_count = notifications.iter().filter(|n| !n.read).count() as u64;
+        
+        Ok(NotificationsResponse {
+            notifications: notifications.iter().rev().skip(start).take(end - start).map(to_response).collect(),
+            total: total as u64,
+            unread_count,
+            has_more: end < total,
+            next_cursor: Some(end.to_string()),
+        })
     })
 }
 
 pub fn mark_notification_as_read(principal: Principal, notification_id: String) -> SquareResult<()> {
-    const MODULE: &str = "services::user::notification";
-    const FUNCTION: &str = "mark_notification_as_read";
-    
     // Update notification in main storage
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         
+        let store = &mut *store;
+        let sync = store.notification_sync.entry(principal).or_default();
         if let Some(user_notifications) = store.user_notifications.get_mut(&principal) {
             for notification in user_notifications {
                 if notification.id == notification_id {
-                    notification.read = true;
+                    // Other devices pick the read state up from the new sequence number
+                    if !notification.read {
+                        notification.read = true;
+                        notification.seq = next_seq(sync);
+                    }
                     break;
                 }
             }
@@ -138,18 +151,126 @@ pub fn mark_notification_as_read(principal: Principal, notification_id: String)
 }
 
 pub fn mark_all_notifications_as_read(principal: Principal) -> SquareResult<()> {
-    const MODULE: &str = "services::user::notification";
-    const FUNCTION: &str = "mark_all_notifications_as_read";
-    
     // Update notifications in main storage
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         
+        let store = &mut *store;
+        let sync = store.notification_sync.entry(principal).or_default();
         if let Some(user_notifications) = store.user_notifications.get_mut(&principal) {
-            for notification in user_notifications {
+            // Oldest first, so the new sequence numbers keep the creation order
+            for notification in user_notifications.iter_mut().filter(|notification| !notification.read) {
                 notification.read = true;
+                notification.seq = next_seq(sync);
             }
         }
         Ok(())
     })
-}
\ No newline at end of file
+}
+
+// Notifications created or marked read after `since`, for devices that keep their own copy of the
+// list. A device starts from 0 and passes `latest_seq` back on the next call.
+pub fn get_notifications_since(principal: Principal, since: u64, limit: Option<u64>) -> SquareResult<NotificationsSinceResponse> {
+    let limit = limit.map_or(MAX_NOTIFICATIONS_SINCE, |limit| (limit as usize).clamp(1, MAX_NOTIFICATIONS_SINCE));
+    
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let sync = store.notification_sync.get(&principal).cloned().unwrap_or_default();
+        let empty = VecDeque::new();
+        let notifications = store.user_notifications.get(&principal).unwrap_or(&empty);
+        
+        // A device behind a pruned notification, or ahead of the sequence, cannot catch up incrementally
+        let resync_required = since > sync.last_seq || (since > 0 && since < sync.pruned_through);
+        let since = if resync_required { 0 } else { since };
+        
+        let mut changed: Vec<&UserNotification> = notifications.iter().filter(|notification| notification.seq > since).collect();
+        changed.sort_by_key(|notification| notification.seq);
+        let has_more = changed.len() > limit;
+        changed.truncate(limit);
+        
+        Ok(NotificationsSinceResponse {
+            latest_seq: if has_more { changed.last().map_or(since, |notification| notification.seq) } else { sync.last_seq },
+            notifications: changed.into_iter().map(to_response).collect(),
+            has_more,
+            resync_required,
+            unread_count: notifications.iter().filter(|notification| !notification.read).count() as u64,
+        })
+    })
+}
+
+// Remove the caller's notifications created before `older_than`, or all of them. Every other
+// device of the user resyncs on its next get_notifications_since call.
+pub fn clear_notifications(principal: Principal, older_than: Option<TimestampMillis>) -> SquareResult<u64> {
+    Ok(STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let Some(user_notifications) = store.user_notifications.get_mut(&principal) else {
+            return 0;
+        };
+        let before = user_notifications.len();
+        match older_than {
+            Some(cutoff) => user_notifications.retain(|notification| notification.created_at >= cutoff),
+            None => user_notifications.clear(),
+        }
+        let cleared = before - user_notifications.len();
+        if cleared > 0 {
+            let sync = store.notification_sync.entry(principal).or_default();
+            sync.pruned_through = next_seq(sync);
+        }
+        if user_notifications.is_empty() {
+            store.user_notifications.remove(&principal);
+        }
+        cleared as u64
+    }))
+}
+
+// Drop notifications past the retention age from every user; run on each full heartbeat
+pub fn prune_notifications() -> u64 {
+    let now = time() / 1_000_000;
+    let pruned = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let settings = retention_settings(store);
+        // Counts are enforced as notifications arrive, so only age is left to prune
+        let Some(max_age_days) = settings.max_age_days else {
+            return 0;
+        };
+        let cutoff = now.saturating_sub(max_age_days.saturating_mul(MILLIS_PER_DAY));
+        let mut pruned = 0;
+        for (user, notifications) in store.user_notifications.iter_mut() {
+            if notifications.front().is_none_or(|oldest| oldest.created_at >= cutoff) {
+                continue;
+            }
+            let sync = store.notification_sync.entry(*user).or_default();
+            pruned += apply_retention(notifications, sync, &settings, now);
+        }
+        store.user_notifications.retain(|_, notifications| !notifications.is_empty());
+        pruned as u64
+    });
+    if pruned > 0 {
+        logger::log(&format!("[{}] Pruned {} notifications past retention", MODULE, pruned));
+    }
+    pruned
+}
+
+pub fn get_notification_retention() -> SquareResult<NotificationRetentionSettings> {
+    Ok(STORAGE.with(|storage| retention_settings(&storage.borrow())))
+}
+
+// Applied to every user straight away, and to each new notification after that
+pub fn update_notification_retention(settings: NotificationRetentionSettings) -> SquareResult<NotificationRetentionSettings> {
+    const FUNCTION: &str = "update_notification_retention";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_notification_retention",
+            "Only admins can change notification retention",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    validate_notification_retention(&settings).finish(MODULE, FUNCTION)?;
+    STORAGE.with(|storage| storage.borrow_mut().notification_retention = Some(settings.clone()));
+    prune_notifications();
+    Ok(settings)
+}
@@ -0,0 +1,142 @@
+use candid::Principal;
+use std::ops::Bound;
+
+use crate::models::content::{ContentStatus, ContentVisibility};
+use crate::models::error::SquareResult;
+use crate::models::notification::*;
+use crate::models::storage::Storage;
+use crate::services::content::summary::post_excerpt;
+use crate::services::user::notification::push_notification;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::instruction_budget::approaching_instruction_limit;
+use crate::utils::time_utils::now_millis;
+
+const MODULE: &str = "services::user::post_alerts";
+
+fn follows(store: &Storage, follower: &Principal, author: &Principal) -> bool {
+    store.user_profiles
+        .as_ref()
+        .and_then(|profiles| profiles.get(follower))
+        .is_some_and(|profile| profile.followed_users.contains(author))
+}
+
+// Turn the caller's "notify me when they post" bell for a followed user on or off
+pub fn set_post_alerts(author: Principal, enabled: bool, caller: Principal) -> SquareResult<PostAlertState> {
+    const FUNCTION: &str = "set_post_alerts";
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if enabled {
+            if author == caller || !follows(&store, &caller, &author) {
+                return log_and_return(invalid_operation_error(
+                    "set_post_alerts",
+                    "Post alerts can only be turned on for users you follow",
+                    MODULE,
+                    FUNCTION
+                ));
+            }
+            store.post_alert_subscribers.entry(author).or_default().insert(caller);
+        } else {
+            remove_post_alert(&mut store, caller, author);
+        }
+        Ok(PostAlertState { author, enabled })
+    })
+}
+
+// The followed users the caller gets new-post alerts for
+pub fn get_post_alerts(caller: Principal) -> SquareResult<Vec<Principal>> {
+    Ok(STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let Some(profile) = store.user_profiles.as_ref().and_then(|profiles| profiles.get(&caller)) else {
+            return Vec::new();
+        };
+        let mut authors: Vec<Principal> = profile.followed_users
+            .iter()
+            .filter(|author| store.post_alert_subscribers.get(*author).is_some_and(|subscribers| subscribers.contains(&caller)))
+            .copied()
+            .collect();
+        authors.sort();
+        authors
+    }))
+}
+
+// Unfollowing turns the bell off too
+pub fn remove_post_alert(store: &mut Storage, follower: Principal, author: Principal) {
+    if let Some(subscribers) = store.post_alert_subscribers.get_mut(&author) {
+        subscribers.remove(&follower);
+        if subscribers.is_empty() {
+            store.post_alert_subscribers.remove(&author);
+        }
+    }
+}
+
+// Queue alerts for a new post; they are sent in batches by the heartbeat
+pub fn enqueue_post_alerts(store: &mut Storage, post_id: &str) {
+    let Some(post) = store.posts.get(post_id).filter(|post| post.visibility != ContentVisibility::Private) else {
+        return;
+    };
+    if !store.post_alert_subscribers.contains_key(&post.author) {
+        return;
+    }
+    store.post_alert_queue.push_back(PostAlertJob {
+        post_id: post_id.to_string(),
+        author: post.author,
+        resume_after: None,
+    });
+}
+
+fn alert_content(store: &Storage, post_id: &str, author: &Principal) -> Option<String> {
+    let post = store.posts.get(post_id).filter(|post| post.status == ContentStatus::Active)?;
+    let name = store.user_cards.get(author).map_or_else(|| author.to_text(), |card| card.username.clone());
+    Some(match post_excerpt(post, POST_ALERT_EXCERPT_LENGTH) {
+        Some(excerpt) => format!("{} posted: {}", name, excerpt),
+        None => format!("{} published a members-only post", name),
+    })
+}
+
+// Send up to POST_ALERT_BATCH_SIZE queued alerts, oldest post first. A post with more subscribers
+// than that is picked up again on the next heartbeat where it left off; posts taken down before
+// their turn are dropped from the queue.
+pub fn run_post_alert_fanout() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let now = now_millis();
+        let mut sent = 0;
+
+        while sent < POST_ALERT_BATCH_SIZE && !approaching_instruction_limit() {
+            let Some(job) = store.post_alert_queue.front().cloned() else {
+                break;
+            };
+            let Some(content) = alert_content(store, &job.post_id, &job.author) else {
+                store.post_alert_queue.pop_front();
+                continue;
+            };
+            let lower = job.resume_after.map_or(Bound::Unbounded, Bound::Excluded);
+            let batch: Vec<Principal> = store.post_alert_subscribers
+                .get(&job.author)
+                .map(|subscribers| subscribers.range((lower, Bound::Unbounded)).take(POST_ALERT_BATCH_SIZE - sent).copied().collect())
+                .unwrap_or_default();
+
+            for subscriber in &batch {
+                push_notification(
+                    store,
+                    *subscriber,
+                    NotificationType::NewPost,
+                    content.clone(),
+                    Some(job.post_id.clone()),
+                    Some(job.author),
+                    now
+                );
+            }
+            sent += batch.len();
+
+            if sent < POST_ALERT_BATCH_SIZE {
+                store.post_alert_queue.pop_front();
+            } else if let Some(front) = store.post_alert_queue.front_mut() {
+                front.resume_after = batch.last().copied();
+            }
+        }
+    });
+}
@@ -16,7 +16,7 @@ pub fn update_privacy_settings(principal: Principal, privacy_settings: UserPriva
         let mut store = storage.borrow_mut();
         
         if let Some(profiles) = &mut store.user_profiles {
-            if let Some(profile) = profiles.get_mut(&principal) {
+            if let Some(mut profile) = profiles.get_mut(&principal) {
                 profile.privacy_settings = Some(privacy_settings);
                 Ok(())
             } else {
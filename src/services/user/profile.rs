@@ -6,41 +6,32 @@ use std::borrow::{Borrow, BorrowMut};
 use crate::models::user::*;
 use crate::models::error::{SquareError, SquareResult};
 use crate::storage::{STORAGE};
+use crate::utils::stable_map::StableMap;
 use crate::utils::error_handler::*;
-use super::utils::{map_storage_status_to_model, map_storage_role_to_model};
+use crate::utils::validators::{validate_register_user, validate_update_profile};
+use super::utils::{map_storage_status_to_model, map_storage_role_to_model, is_handle_taken};
+use crate::services::feature_flags::ensure_flag_disabled;
+use crate::models::feature_flag::FeatureFlag;
+use crate::services::query_cache::{invalidate, POST_QUERIES};
+use super::identity::linked_accounts;
+use super::showcase::showcase;
+use super::name_policy::check_name_policy;
+use super::cards::refresh_user_card;
+use super::admin::require_debug_access;
+use crate::models::reward::INACTIVE_USER_DAYS;
+use crate::utils::time_utils::{TimestampMillis, MILLIS_PER_DAY};
+use crate::models::storage::Storage;
 
 // User registration and profile management
 pub fn register_user(request: RegisterUserRequest, caller: Principal) -> SquareResult<()> {
     const MODULE: &str = "services::user::profile";
     const FUNCTION: &str = "register_user";
     
-    // Validate username length
-    if request.username.len() < MIN_USERNAME_LENGTH || request.username.len() > MAX_USERNAME_LENGTH {
-        return log_and_return(validation_error(
-            &format!("Username must be between {} and {} characters", MIN_USERNAME_LENGTH, MAX_USERNAME_LENGTH),
-            MODULE,
-            FUNCTION
-        ));
-    }
+    ensure_flag_disabled(FeatureFlag::PauseRegistrations, "register_user", MODULE, FUNCTION)?;
     
-    // Validate handle format
-    let handle_regex = regex::Regex::new(HANDLE_PATTERN).unwrap();
-    if !handle_regex.is_match(&request.handle) {
-        return log_and_return(validation_error(
-            "Handle must start with a letter and can only contain letters, numbers, and underscores",
-            MODULE,
-            FUNCTION
-        ));
-    }
-    
-    // Validate bio length
-    if request.bio.len() > MAX_BIO_LENGTH {
-        return log_and_return(validation_error(
-            &format!("Bio exceeds maximum length of {} characters", MAX_BIO_LENGTH),
-            MODULE,
-            FUNCTION
-        ));
-    }
+    // Validate username, handle and bio, reporting every violation
+    validate_register_user(&request).finish(MODULE, FUNCTION)?;
+    check_name_policy(Some(&request.username), Some(&request.handle), FUNCTION)?;
     
     // Check if user already exists in main storage
     let user_exists = STORAGE.with(|storage| {
@@ -76,14 +67,7 @@ pub fn register_user(request: RegisterUserRequest, caller: Principal) -> SquareR
         ).with_details(format!("Username '{}' is already taken", request.username)));
     }
     
-    let handle_taken = STORAGE.with(|storage| {
-        let store = storage.borrow();
-        if let Some(profiles) = &store.user_profiles {
-            profiles.values().any(|profile| profile.handle == request.handle)
-        } else {
-            false
-        }
-    });
+    let handle_taken = STORAGE.with(|storage| is_handle_taken(&storage.borrow(), &request.handle, None));
         
     if handle_taken {
         return log_and_return(already_exists_error(
@@ -96,6 +80,7 @@ pub fn register_user(request: RegisterUserRequest, caller: Principal) -> SquareR
     
     // Create user
     let now = time() / 1_000_000;
+    let invite_code = request.invite_code.clone();
     let user = User {
         principal: caller,
         registered_at: now,
@@ -138,16 +123,20 @@ pub fn register_user(request: RegisterUserRequest, caller: Principal) -> SquareR
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         
+        // Consume the invite code when registration is invite-only
+        super::invite::redeem_invite_code(&mut store, invite_code.as_deref(), caller, now)?;
+        
         // Store user
         store.users.insert(caller, user);
         
         // Store user profile
         if store.user_profiles.is_none() {
-            store.user_profiles = Some(HashMap::new());
+            store.user_profiles = Some(StableMap::default());
         }
         if let Some(profiles) = &mut store.user_profiles {
             profiles.insert(caller, profile);
         }
+        refresh_user_card(&mut store, &caller);
         
         // Store user stats
         if store.user_stats.is_none() {
@@ -156,9 +145,9 @@ pub fn register_user(request: RegisterUserRequest, caller: Principal) -> SquareR
         if let Some(user_stats) = &mut store.user_stats {
             user_stats.insert(caller, stats);
         }
-    });
-    
-    Ok(())
+        
+        Ok(())
+    })
 }
 
 pub fn update_user_profile(request: UpdateProfileRequest, caller: Principal) -> SquareResult<String> {
@@ -187,16 +176,14 @@ pub fn update_user_profile(request: UpdateProfileRequest, caller: Principal) ->
         ).with_details("User profile not found")),
     };
     
-    // Validate username length
-    if let Some(username) = &request.username {
-        if username.len() < MIN_USERNAME_LENGTH || username.len() > MAX_USERNAME_LENGTH {
-            return log_and_return(validation_error(
-                &format!("Username must be between {} and {} characters", MIN_USERNAME_LENGTH, MAX_USERNAME_LENGTH),
-                MODULE,
-                FUNCTION
-            ));
-        }
-    }
+    // Validate username, handle and bio, reporting every violation
+    validate_update_profile(&request).finish(MODULE, FUNCTION)?;
+    // Names the user already holds are not re-checked
+    check_name_policy(
+        request.username.as_deref().filter(|username| *username != profile.username),
+        request.handle.as_deref().filter(|handle| *handle != profile.handle),
+        FUNCTION
+    )?;
     
     // Check if new username is already taken (if different from current)
     if let Some(username) = &request.username {
@@ -225,40 +212,10 @@ pub fn update_user_profile(request: UpdateProfileRequest, caller: Principal) ->
         }
     }
     
-    // Validate bio length
-    if let Some(bio) = &request.bio {
-        if bio.len() > MAX_BIO_LENGTH {
-            return log_and_return(validation_error(
-                &format!("Bio exceeds maximum length of {} characters", MAX_BIO_LENGTH),
-                MODULE,
-                FUNCTION
-            ));
-        }
-    }
-    
     // Update handle if provided
     if let Some(ref handle) = request.handle {
-        // Validate handle format
-        let handle_regex = regex::Regex::new(HANDLE_PATTERN).unwrap();
-        if !handle_regex.is_match(handle) {
-            return log_and_return(validation_error(
-                "Handle must start with a letter and can only contain letters, numbers, and underscores",
-                MODULE,
-                FUNCTION
-            ));
-        }
-        
         // Check if handle is already taken in main storage
-        let handle_taken = STORAGE.with(|storage| {
-            let store = storage.borrow();
-            if let Some(profiles) = &store.user_profiles {
-                profiles.iter().any(|(principal, p)| {
-                    *principal != caller && p.handle == *handle
-                })
-            } else {
-                false
-            }
-        });
+        let handle_taken = STORAGE.with(|storage| is_handle_taken(&storage.borrow(), handle, Some(caller)));
         
         if handle_taken {
             return log_and_return(already_exists_error(
@@ -306,6 +263,7 @@ pub fn update_user_profile(request: UpdateProfileRequest, caller: Principal) ->
         if let Some(profiles) = &mut store.user_profiles {
             profiles.insert(caller, updated_profile);
         }
+        refresh_user_card(&mut store, &caller);
         
         // Update last login time in user record
         if let Some(mut user) = store.users.get(&caller).cloned() {
@@ -323,14 +281,7 @@ pub fn get_user_profile(user_identifier: String) -> SquareResult<UserProfileResp
     
     ic_cdk::println!("[{}::{}] Getting profile for user: {}", MODULE, FUNCTION, user_identifier);
     
-    let principal = if let Ok(principal) = Principal::from_text(&user_identifier) {
-        // It's a valid Principal
-        principal
-    } else {
-        // Try to find user by handle
-        let principal = super::utils::find_user_by_handle(&user_identifier)?;
-        principal
-    };
+    let principal = super::utils::resolve_user_identifier(&user_identifier)?;
     
     // Get user from main storage
     let user_result = STORAGE.with(|storage| {
@@ -408,6 +359,8 @@ pub fn get_user_profile(user_identifier: String) -> SquareResult<UserProfileResp
         created_at: profile.created_at,
         updated_at: profile.updated_at,
         privacy_settings: profile.privacy_settings,
+        linked_accounts: STORAGE.with(|storage| linked_accounts(&storage.borrow(), &principal)),
+        showcase: STORAGE.with(|storage| showcase(&storage.borrow(), &principal)),
     })
 }
 
@@ -415,14 +368,8 @@ pub fn get_user_full_profile(user_identifier: String) -> SquareResult<UserRespon
     const MODULE: &str = "services::user::profile";
     const FUNCTION: &str = "get_user_full_profile";
     
-    // Get user profile
-    let profile = get_user_profile(user_identifier.clone())?;
-    
-    // Get followed users
-    let _followed_users = super::social::get_following(user_identifier.clone(), None)?;
-    
-    // Get followers
-    let _followers = super::social::get_followers(user_identifier, None)?;
+    // Get user profile; follow counts come from its counters
+    let profile = get_user_profile(user_identifier)?;
     
     Ok(UserResponse {
         principal: profile.principal,
@@ -453,6 +400,8 @@ pub fn debug_fix_user_profile(user_identifier: String) -> SquareResult<String> {
     const MODULE: &str = "services::user::profile";
     const FUNCTION: &str = "debug_fix_user_profile";
     
+    require_debug_access(MODULE, FUNCTION)?;
+    
     // Parse user identifier
     let principal = if let Ok(principal) = Principal::from_text(&user_identifier) {
         principal
@@ -516,13 +465,14 @@ pub fn debug_fix_user_profile(user_identifier: String) -> SquareResult<String> {
             
             // Create profiles map if it doesn't exist
             if store.user_profiles.is_none() {
-                store.user_profiles = Some(HashMap::new());
+                store.user_profiles = Some(StableMap::default());
             }
             
             // Store profile
             if let Some(profiles) = &mut store.user_profiles {
                 profiles.insert(principal, profile);
             }
+            refresh_user_card(&mut store, &principal);
         });
         
         return Ok(format!("Created default profile for user {}", principal));
@@ -572,7 +522,7 @@ pub fn debug_fix_user_profile(user_identifier: String) -> SquareResult<String> {
         let mut store = storage.borrow_mut();
         
         if let Some(profiles) = &mut store.user_profiles {
-            if let Some(profile) = profiles.get_mut(&principal) {
+            if let Some(mut profile) = profiles.get_mut(&principal) {
                 // Update followers count
                 profile.followers_count = profile.followers.len() as u64;
                 
@@ -580,7 +530,181 @@ pub fn debug_fix_user_profile(user_identifier: String) -> SquareResult<String> {
                 profile.following_count = profile.followed_users.len() as u64;
             }
         }
+        refresh_user_card(&mut store, &principal);
     });
     
     Ok(format!("Fixed profile for user {}", principal))
 }
+
+// Self-service account deactivation, distinct from admin suspensions and bans
+pub fn deactivate_account(request: DeactivateAccountRequest, caller: Principal) -> SquareResult<()> {
+    const MODULE: &str = "services::user::profile";
+    const FUNCTION: &str = "deactivate_account";
+    
+    invalidate(POST_QUERIES);
+    
+    let status = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.users.get(&caller).map(|user| user.status.clone())
+    });
+    
+    match status {
+        None => return log_and_return(not_found_error(
+            "User",
+            &caller.to_string(),
+            MODULE,
+            FUNCTION
+        )),
+        Some(UserStatus::Active) => {},
+        Some(UserStatus::Deactivated) => return log_and_return(invalid_operation_error(
+            "deactivate_account",
+            "Account is already deactivated",
+            MODULE,
+            FUNCTION
+        )),
+        Some(_) => return log_and_return(invalid_operation_error(
+            "deactivate_account",
+            "Accounts under moderation cannot be deactivated",
+            MODULE,
+            FUNCTION
+        )),
+    }
+    
+    let deactivation = AccountDeactivation {
+        deactivated_at: time() / 1_000_000,
+        reactivate_on_login: request.reactivate_on_login.unwrap_or(false),
+    };
+    
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if let Some(mut user) = store.users.get_mut(&caller) {
+            user.status = UserStatus::Deactivated;
+        }
+        store.account_deactivations.insert(caller, deactivation);
+    });
+    
+    Ok(())
+}
+
+pub fn reactivate_account(caller: Principal) -> SquareResult<()> {
+    const MODULE: &str = "services::user::profile";
+    const FUNCTION: &str = "reactivate_account";
+    
+    invalidate(POST_QUERIES);
+    
+    let reactivated = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.account_deactivations.remove(&caller).is_none() {
+            return false;
+        }
+        if let Some(mut user) = store.users.get_mut(&caller).filter(|user| user.status == UserStatus::Deactivated) {
+            user.status = UserStatus::Active;
+        }
+        true
+    });
+    
+    if !reactivated {
+        return log_and_return(invalid_operation_error(
+            "reactivate_account",
+            "Account is not deactivated",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    
+    Ok(())
+}
+
+// Record an authenticated login; reactivates the account if the user opted in
+pub fn record_login(caller: Principal) -> SquareResult<UserStatus> {
+    const MODULE: &str = "services::user::profile";
+    const FUNCTION: &str = "record_login";
+    
+    let auto_reactivate = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.account_deactivations
+            .get(&caller)
+            .is_some_and(|deactivation| deactivation.reactivate_on_login)
+    });
+    
+    if auto_reactivate {
+        reactivate_account(caller)?;
+    }
+    
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let status = store.users.get(&caller).map(|user| user.status.clone()).ok_or_else(|| {
+            not_found_error("User", &caller.to_string(), MODULE, FUNCTION)
+        })?;
+        let now = time() / 1_000_000;
+        record_activity(store, caller, now);
+        if let Some(mut user) = store.users.get_mut(&caller) {
+            user.last_login = now;
+        }
+        Ok(status)
+    })
+}
+
+// When the user last logged in or did anything logged as an interaction event
+pub(crate) fn last_seen(store: &Storage, user: Principal) -> Option<TimestampMillis> {
+    let last_login = store.users.get(&user)?.last_login;
+    Some(store.last_active.get(&user).map_or(last_login, |active| last_login.max(*active)))
+}
+
+// Note that a registered user is active now. Inactive-user tasks stay visible for a while after
+// the user returns.
+pub(crate) fn record_activity(store: &mut Storage, user: Principal, now: TimestampMillis) {
+    let Some(seen) = last_seen(store, user) else {
+        return;
+    };
+    let inactive_after = INACTIVE_USER_DAYS * MILLIS_PER_DAY;
+    if now.saturating_sub(seen) >= inactive_after {
+        store.returning_users.insert(user, now);
+    } else if store.returning_users.get(&user).is_some_and(|returned| now.saturating_sub(*returned) >= inactive_after) {
+        store.returning_users.remove(&user);
+    }
+    store.last_active.insert(user, now);
+}
+
+// Set the caller's timezone offset from UTC in minutes (e.g. 480 for UTC+8)
+pub fn set_timezone_offset(offset_minutes: i32, caller: Principal) -> SquareResult<()> {
+    const MODULE: &str = "services::user::profile";
+    const FUNCTION: &str = "set_timezone_offset";
+    
+    if !(MIN_TIMEZONE_OFFSET_MINUTES..=MAX_TIMEZONE_OFFSET_MINUTES).contains(&offset_minutes) {
+        return log_and_return(field_validation_error(
+            "offset_minutes",
+            &format!("must be between {} and {}", MIN_TIMEZONE_OFFSET_MINUTES, MAX_TIMEZONE_OFFSET_MINUTES),
+            MODULE,
+            FUNCTION
+        ));
+    }
+    
+    let now = time() / 1_000_000;
+    
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        
+        if !store.users.contains_key(&caller) {
+            return log_and_return(not_found_error("User", &caller.to_string(), MODULE, FUNCTION));
+        }
+        
+        if let Some(timezone) = store.user_timezones.get(&caller) {
+            if timezone.offset_minutes == offset_minutes {
+                return Ok(());
+            }
+            if now < timezone.updated_at + TIMEZONE_CHANGE_COOLDOWN_MS {
+                return log_and_return(invalid_operation_error(
+                    "set_timezone_offset",
+                    "Timezone can only be changed once every 7 days",
+                    MODULE,
+                    FUNCTION
+                ));
+            }
+        }
+        
+        store.user_timezones.insert(caller, UserTimezone { offset_minutes, updated_at: now });
+        Ok(())
+    })
+}
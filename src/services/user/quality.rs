@@ -0,0 +1,214 @@
+use candid::Principal;
+use ic_cdk::api::time;
+use std::collections::HashSet;
+
+use crate::auth::is_manager_or_admin;
+use crate::models::account_quality::*;
+use crate::models::content::ContentStatus;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::storage::Storage;
+use crate::models::user::UserModerationAction;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::instruction_budget::approaching_instruction_limit;
+use crate::utils::time_utils::{MILLIS_PER_DAY, MILLIS_PER_HOUR};
+
+const MODULE: &str = "services::user::quality";
+
+const SCORING_WINDOW_DAYS: u64 = 30;
+const ESTABLISHED_ACCOUNT_AGE_DAYS: u64 = 30;
+const MODERATION_PENALTY_POINTS: u32 = 5;
+
+fn tier_for_score(score: u32) -> AccountQualityTier {
+    if score < LOW_QUALITY_SCORE_THRESHOLD {
+        AccountQualityTier::Low
+    } else if score < HIGH_QUALITY_SCORE_THRESHOLD {
+        AccountQualityTier::Medium
+    } else {
+        AccountQualityTier::High
+    }
+}
+
+// Score an account from age, recent activity days, content history and follower graph
+pub fn compute_account_quality(store: &Storage, principal: Principal, now: u64) -> AccountQuality {
+    let account_age_days = |user: &Principal| {
+        store.users
+            .get(user)
+            .map_or(0, |user| now.saturating_sub(user.registered_at) / MILLIS_PER_DAY)
+    };
+
+    let age_points = (account_age_days(&principal).min(SCORING_WINDOW_DAYS) * 30 / SCORING_WINDOW_DAYS) as u32;
+
+    // Distinct days with check-ins, task completions or other point credits
+    let window_start = now.saturating_sub(SCORING_WINDOW_DAYS * MILLIS_PER_DAY);
+    let mut active_days = HashSet::new();
+    if let Some(rewards) = store.user_rewards.get(&principal) {
+        active_days.extend(rewards.points_history.iter().map(|transaction| transaction.timestamp));
+    }
+    if let Some(tasks) = store.user_tasks.get(&principal) {
+        active_days.extend(tasks.completed_tasks.values().copied());
+        active_days.extend(tasks.last_check_in);
+    }
+    let active_days: HashSet<u64> = active_days
+        .into_iter()
+        .filter(|timestamp| *timestamp >= window_start)
+        .map(|timestamp| timestamp / MILLIS_PER_DAY)
+        .collect();
+    let activity_points = (active_days.len() as u32).min(10) * 2;
+
+    let active_posts = store.user_posts
+        .get(&principal)
+        .into_iter()
+        .flatten()
+        .filter(|post_id| store.posts.get(*post_id).is_some_and(|post| post.status == ContentStatus::Active))
+        .count();
+    let active_comments = store.user_comments
+        .get(&principal)
+        .into_iter()
+        .flatten()
+        .filter(|comment_id| store.comments.get(*comment_id).is_some_and(|comment| comment.status == ContentStatus::Active))
+        .count();
+    let active_content = active_posts + active_comments;
+    let moderation_penalty = store.user_moderation_log
+        .get(&principal)
+        .map_or(0, |records| {
+            records
+                .iter()
+                .filter(|record| matches!(record.action, UserModerationAction::ContentModerated(ContentStatus::Removed)))
+                .count() as u32
+        }) * MODERATION_PENALTY_POINTS;
+    let content_points = ((active_content as u32).min(10) * 2).saturating_sub(moderation_penalty);
+
+    let established_followers = store.user_profiles
+        .as_ref()
+        .and_then(|profiles| profiles.get(&principal))
+        .map_or(0, |profile| {
+            profile.followers
+                .iter()
+                .filter(|follower| account_age_days(follower) >= ESTABLISHED_ACCOUNT_AGE_DAYS)
+                .count() as u32
+        });
+    let follower_points = established_followers.min(10) * 3;
+
+    let score = (age_points + activity_points + content_points + follower_points).min(MAX_ACCOUNT_QUALITY_SCORE);
+    AccountQuality {
+        score,
+        tier: tier_for_score(score),
+        age_points,
+        activity_points,
+        content_points,
+        follower_points,
+        computed_at: now,
+    }
+}
+
+fn fresh(quality: &AccountQuality, now: u64) -> bool {
+    quality.computed_at + ACCOUNT_QUALITY_CACHE_MS > now
+}
+
+// The cached account quality while fresh, otherwise computed on the spot. Nothing is stored here,
+// since writes from queries are discarded: updates cache the scores they use, and the heartbeat
+// keeps cached scores fresh.
+pub fn account_quality(principal: Principal) -> AccountQuality {
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        match store.account_quality_scores.get(&principal).filter(|quality| fresh(quality, now)) {
+            Some(quality) => quality.clone(),
+            None => compute_account_quality(&store, principal, now),
+        }
+    })
+}
+
+// The account quality, recomputed and cached once the cached score is stale; for update calls
+fn cached_account_quality(principal: Principal) -> AccountQuality {
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if let Some(quality) = store.account_quality_scores.get(&principal).filter(|quality| fresh(quality, now)) {
+            return quality.clone();
+        }
+        let quality = compute_account_quality(&store, principal, now);
+        store.account_quality_scores.insert(principal, quality.clone());
+        quality
+    })
+}
+
+// Recompute stale cached scores, a batch per heartbeat; scores of deleted accounts are dropped
+pub fn refresh_account_quality() {
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let stale: Vec<Principal> = store.account_quality_scores
+            .iter()
+            .filter(|(_, quality)| !fresh(quality, now))
+            .map(|(principal, _)| *principal)
+            .take(ACCOUNT_QUALITY_REFRESH_BATCH)
+            .collect();
+        for principal in stale {
+            if approaching_instruction_limit() {
+                break;
+            }
+            if !store.users.contains_key(&principal) {
+                store.account_quality_scores.remove(&principal);
+                continue;
+            }
+            let quality = compute_account_quality(&store, principal, now);
+            store.account_quality_scores.insert(principal, quality);
+        }
+    });
+}
+
+// Enforce per-tier hourly limits; low-quality accounts get stricter ones
+pub fn check_action_rate_limit(caller: Principal, action: RateLimitedAction) -> SquareResult<()> {
+    const FUNCTION: &str = "check_action_rate_limit";
+
+    let tier = cached_account_quality(caller).tier;
+    let limit = hourly_action_limit(tier, action);
+    let now = time() / 1_000_000;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let actions = store.recent_actions.entry(caller).or_default();
+        actions.retain(|recent| recent.timestamp + MILLIS_PER_HOUR > now);
+
+        if actions.iter().filter(|recent| recent.action == action).count() >= limit {
+            return log_and_return(rate_limit_error(&format!("{:?}", action), limit as u64, MODULE, FUNCTION));
+        }
+        actions.push(RecentAction { action, timestamp: now });
+        Ok(())
+    })
+}
+
+// Drop rate-limit history older than an hour; run from the heartbeat
+pub fn prune_recent_actions() {
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        store.recent_actions.retain(|_, actions| {
+            actions.retain(|recent| recent.timestamp + MILLIS_PER_HOUR > now);
+            !actions.is_empty()
+        });
+    });
+}
+
+pub fn get_account_quality(principal: Principal) -> SquareResult<AccountQuality> {
+    const FUNCTION: &str = "get_account_quality";
+
+    if is_manager_or_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "get_account_quality",
+            "Only managers or admins can view other accounts' quality scores",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    if !STORAGE.with(|storage| storage.borrow().users.contains_key(&principal)) {
+        return log_and_return(not_found_error("User", &principal.to_string(), MODULE, FUNCTION));
+    }
+    Ok(account_quality(principal))
+}
+
+pub fn get_my_account_quality(caller: Principal) -> SquareResult<AccountQuality> {
+    Ok(account_quality(caller))
+}
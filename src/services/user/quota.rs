@@ -0,0 +1,146 @@
+use candid::Principal;
+use ic_cdk::api::time;
+use std::collections::HashMap;
+
+use crate::auth::{is_admin, is_manager_or_admin};
+use crate::models::content::{Comment, Post};
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::quota::*;
+use crate::models::storage::Storage;
+use crate::services::user::trust::compute_trust_level;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+
+const MODULE: &str = "services::user::quota";
+
+fn strings_size(values: &[String]) -> u64 {
+    values.iter().map(|value| value.len() as u64).sum()
+}
+
+// Approximate stored size of a post; inline base64 media is part of its content
+pub fn post_size(post: &Post) -> u64 {
+    STORED_ITEM_OVERHEAD_BYTES
+        + post.content.len() as u64
+        + post.title.as_ref().map_or(0, |title| title.len() as u64)
+        + post.cover_image.as_ref().map_or(0, |url| url.len() as u64)
+        + post.thumbnail.as_ref().map_or(0, |url| url.len() as u64)
+        + strings_size(&post.media_urls)
+        + strings_size(&post.hashtags)
+        + strings_size(&post.tags)
+        + strings_size(&post.token_mentions)
+}
+
+pub fn comment_size(comment: &Comment) -> u64 {
+    STORED_ITEM_OVERHEAD_BYTES + comment.content.len() as u64
+}
+
+// The user's quota and whether it comes from an admin override
+fn quota_for(store: &Storage, user: Principal) -> (u64, bool) {
+    match store.storage_quota_overrides.get(&user) {
+        Some(quota) => (*quota, true),
+        None => {
+            let level = compute_trust_level(store, user, time() / 1_000_000).level;
+            (store.storage_quota_settings.clone().unwrap_or_default().quota(level), false)
+        }
+    }
+}
+
+// The quota that applies when the caller stores content for `author`; None when the caller is exempt.
+// Look it up before borrowing storage mutably and pass it to `charge_storage`.
+pub fn storage_quota(author: Principal) -> Option<u64> {
+    if is_manager_or_admin().is_ok() {
+        return None;
+    }
+    Some(STORAGE.with(|storage| quota_for(&storage.borrow(), author).0))
+}
+
+// Record that an item of `author` went from `old_size` to `new_size` bytes. Growth past the quota
+// is refused and leaves usage unchanged; shrinking is always allowed.
+pub fn charge_storage(
+    usage: &mut HashMap<Principal, u64>,
+    author: Principal,
+    old_size: u64,
+    new_size: u64,
+    quota: Option<u64>,
+) -> SquareResult<()> {
+    const FUNCTION: &str = "charge_storage";
+
+    let used = usage.get(&author).copied().unwrap_or(0);
+    let updated = (used + new_size).saturating_sub(old_size);
+    if let Some(quota) = quota.filter(|quota| new_size > old_size && updated > *quota) {
+        return log_and_return(quota_exceeded_error("storage_bytes", quota, MODULE, FUNCTION)
+            .with_details(format!("{} of {} bytes are in use and this needs {} more", used, quota, new_size - old_size)));
+    }
+    if updated == 0 {
+        usage.remove(&author);
+    } else {
+        usage.insert(author, updated);
+    }
+    Ok(())
+}
+
+pub fn release_storage(usage: &mut HashMap<Principal, u64>, author: Principal, size: u64) {
+    // Shrinking never fails
+    let _ = charge_storage(usage, author, size, 0, None);
+}
+
+pub fn get_my_storage_usage(caller: Principal) -> SquareResult<StorageUsageResponse> {
+    Ok(STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let (quota_bytes, overridden) = quota_for(&store, caller);
+        StorageUsageResponse {
+            bytes_used: store.storage_usage.get(&caller).copied().unwrap_or(0),
+            quota_bytes,
+            trust_level: compute_trust_level(&store, caller, time() / 1_000_000).level,
+            overridden,
+        }
+    }))
+}
+
+fn require_admin(operation: &str, function: &str) -> SquareResult<()> {
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            operation,
+            "Only admins can manage storage quotas",
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+pub fn get_storage_quota_settings() -> SquareResult<StorageQuotaSettings> {
+    Ok(STORAGE.with(|storage| storage.borrow().storage_quota_settings.clone().unwrap_or_default()))
+}
+
+pub fn update_storage_quota_settings(settings: StorageQuotaSettings) -> SquareResult<StorageQuotaSettings> {
+    const FUNCTION: &str = "update_storage_quota_settings";
+
+    require_admin("update_storage_quota_settings", FUNCTION)?;
+    let quotas = [settings.new, settings.basic, settings.member, settings.regular];
+    if !quotas.windows(2).all(|pair| pair[0] <= pair[1]) {
+        return log_and_return(validation_error(
+            "Each trust level's storage quota must be at least the quota of the level below it",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| storage.borrow_mut().storage_quota_settings = Some(settings.clone()));
+    Ok(settings)
+}
+
+// Give one user a quota that replaces their trust level's; None goes back to the trust level quota
+pub fn set_storage_quota_override(user: Principal, quota_bytes: Option<u64>) -> SquareResult<StorageUsageResponse> {
+    const FUNCTION: &str = "set_storage_quota_override";
+
+    require_admin("set_storage_quota_override", FUNCTION)?;
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        match quota_bytes {
+            Some(quota) => store.storage_quota_overrides.insert(user, quota),
+            None => store.storage_quota_overrides.remove(&user),
+        };
+    });
+    get_my_storage_usage(user)
+}
@@ -0,0 +1,219 @@
+use candid::{Nat, Principal};
+use ic_cdk::api::{call, time};
+use sha2::{Digest, Sha224};
+
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::showcase::*;
+use crate::models::storage::Storage;
+use crate::services::user::identity::linked_accounts;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::logger;
+
+const MODULE: &str = "services::user::showcase";
+
+pub fn showcase(store: &Storage, principal: &Principal) -> Vec<ShowcaseNft> {
+    store.nft_showcases.get(principal).cloned().unwrap_or_default()
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+// Hex ledger account identifier of a principal's default subaccount, as EXT `bearer` reports owners
+fn account_identifier(principal: &Principal) -> String {
+    let mut hasher = Sha224::new();
+    hasher.update(b"\x0Aaccount-id");
+    hasher.update(principal.as_slice());
+    hasher.update([0u8; 32]);
+    let hash = hasher.finalize();
+
+    let mut bytes = crc32(&hash).to_be_bytes().to_vec();
+    bytes.extend_from_slice(&hash);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Ask the collection canister who holds the token
+async fn token_owner(request: &AddShowcaseNftRequest, candidates: &[Principal]) -> Result<Option<Principal>, String> {
+    match request.standard {
+        NftStandard::Ext => {
+            let response: Result<(ExtBearerResponse,), _> =
+                call::call(request.canister_id, "bearer", (request.token_id.clone(),)).await;
+            match response {
+                Ok((ExtBearerResponse::Ok(account),)) => Ok(candidates
+                    .iter()
+                    .find(|candidate| account_identifier(candidate).eq_ignore_ascii_case(&account))
+                    .copied()),
+                Ok((ExtBearerResponse::Err(error),)) => Err(format!("Collection rejected the token: {:?}", error)),
+                Err((code, message)) => Err(format!("bearer failed with code {:?}: {}", code, message)),
+            }
+        }
+        NftStandard::Icrc7 => {
+            let token_id: Nat = request.token_id.parse().map_err(|_| "ICRC-7 token IDs must be numeric".to_string())?;
+            let response: Result<(Icrc7OwnerOfResponse,), _> =
+                call::call(request.canister_id, "icrc7_owner_of", (vec![token_id],)).await;
+            match response {
+                Ok((owners,)) => Ok(owners
+                    .into_iter()
+                    .next()
+                    .flatten()
+                    .filter(|account| account.subaccount.as_ref().is_none_or(|subaccount| subaccount.iter().all(|byte| *byte == 0)))
+                    .map(|account| account.owner)
+                    .filter(|owner| candidates.contains(owner))),
+                Err((code, message)) => Err(format!("icrc7_owner_of failed with code {:?}: {}", code, message)),
+            }
+        }
+    }
+}
+
+// Add an NFT to the caller's showcase once the collection confirms they or a linked account hold it
+pub async fn add_showcase_nft(request: AddShowcaseNftRequest, caller: Principal) -> SquareResult<ShowcaseNft> {
+    const FUNCTION: &str = "add_showcase_nft";
+
+    if request.token_id.is_empty() || request.token_id.chars().count() > MAX_NFT_TOKEN_ID_LENGTH {
+        return log_and_return(field_validation_error(
+            "token_id",
+            &format!("must be between 1 and {} characters", MAX_NFT_TOKEN_ID_LENGTH),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let candidates = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        if !store.users.contains_key(&caller) {
+            return log_and_return(not_found_error("User", &caller.to_string(), MODULE, FUNCTION));
+        }
+        let items = store.nft_showcases.get(&caller);
+        if items.is_some_and(|items| items.iter().any(|item| item.canister_id == request.canister_id && item.token_id == request.token_id)) {
+            return log_and_return(already_exists_error("ShowcaseNft", &request.token_id, MODULE, FUNCTION));
+        }
+        if items.map_or(0, |items| items.len()) >= MAX_SHOWCASE_NFTS {
+            return log_and_return(quota_exceeded_error("showcase", MAX_SHOWCASE_NFTS as u64, MODULE, FUNCTION));
+        }
+
+        let mut candidates = vec![caller];
+        candidates.extend(linked_accounts(&store, &caller).into_iter().map(|linked| linked.principal));
+        Ok(candidates)
+    })?;
+
+    let owner = match token_owner(&request, &candidates).await {
+        Ok(Some(owner)) => owner,
+        Ok(None) => {
+            return log_and_return(permission_denied_error(
+                "add_showcase_nft",
+                "The NFT is not held by you or one of your linked accounts",
+                MODULE,
+                FUNCTION
+            ));
+        }
+        Err(message) => {
+            return log_and_return(service_unavailable_error(&request.canister_id.to_string(), &message, MODULE, FUNCTION));
+        }
+    };
+
+    let item = ShowcaseNft {
+        canister_id: request.canister_id,
+        standard: request.standard,
+        token_id: request.token_id,
+        owner,
+        verified_at: time() / 1_000_000,
+    };
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let items = store.nft_showcases.entry(caller).or_default();
+        // Other calls may have added the same token, or filled the showcase, while this one was verifying
+        items.retain(|existing| existing.canister_id != item.canister_id || existing.token_id != item.token_id);
+        if items.len() >= MAX_SHOWCASE_NFTS {
+            return log_and_return(quota_exceeded_error("showcase", MAX_SHOWCASE_NFTS as u64, MODULE, FUNCTION));
+        }
+        items.push(item.clone());
+        Ok(())
+    })?;
+    Ok(item)
+}
+
+// Check again that showcased NFTs are still held, oldest verification first; run from the heartbeat.
+// Items whose token moved to someone else are dropped; items the collection could not answer for
+// are kept and tried on a later run.
+pub async fn reverify_showcases() {
+    let now = time() / 1_000_000;
+    let mut due: Vec<(Principal, ShowcaseNft)> = STORAGE.with(|storage| {
+        storage.borrow().nft_showcases
+            .iter()
+            .flat_map(|(principal, items)| items.iter().map(move |item| (*principal, item.clone())))
+            .filter(|(_, item)| item.verified_at + SHOWCASE_REVERIFY_AFTER <= now)
+            .collect()
+    });
+    due.sort_by_key(|(_, item)| item.verified_at);
+    due.truncate(SHOWCASE_REVERIFY_BATCH);
+
+    for (principal, item) in due {
+        let candidates = STORAGE.with(|storage| {
+            let store = storage.borrow();
+            let mut candidates = vec![principal];
+            candidates.extend(linked_accounts(&store, &principal).into_iter().map(|linked| linked.principal));
+            candidates
+        });
+        let request = AddShowcaseNftRequest {
+            canister_id: item.canister_id,
+            standard: item.standard,
+            token_id: item.token_id.clone(),
+        };
+        let owner = match token_owner(&request, &candidates).await {
+            Ok(owner) => owner,
+            Err(error) => {
+                logger::log(&format!("[{}] Re-verifying {} {} failed: {}", MODULE, item.canister_id, item.token_id, error));
+                continue;
+            }
+        };
+
+        let verified_at = time() / 1_000_000;
+        STORAGE.with(|storage| {
+            let mut store = storage.borrow_mut();
+            let Some(items) = store.nft_showcases.get_mut(&principal) else {
+                return;
+            };
+            let is_item = |existing: &ShowcaseNft| existing.canister_id == item.canister_id && existing.token_id == item.token_id;
+            match owner {
+                Some(owner) => {
+                    if let Some(existing) = items.iter_mut().find(|existing| is_item(existing)) {
+                        existing.owner = owner;
+                        existing.verified_at = verified_at;
+                    }
+                }
+                None => items.retain(|existing| !is_item(existing)),
+            }
+            if items.is_empty() {
+                store.nft_showcases.remove(&principal);
+            }
+        });
+    }
+}
+
+pub fn remove_showcase_nft(canister_id: Principal, token_id: String, caller: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "remove_showcase_nft";
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let items = store.nft_showcases.entry(caller).or_default();
+        let before = items.len();
+        items.retain(|item| item.canister_id != canister_id || item.token_id != token_id);
+        let removed = items.len() < before;
+        if items.is_empty() {
+            store.nft_showcases.remove(&caller);
+        }
+
+        if !removed {
+            return log_and_return(not_found_error("ShowcaseNft", &token_id, MODULE, FUNCTION));
+        }
+        Ok(())
+    })
+}
@@ -1,15 +1,28 @@
 use candid::Principal;
+use ic_cdk::api::time;
 use std::collections::{HashSet, HashMap};
 use std::borrow::{Borrow, BorrowMut};
 
 use crate::models::user::*;
+use crate::models::content::PostResponse;
 use crate::models::error::{SquareError, SquareResult};
 use crate::models::notification::NotificationType;
 use crate::storage::{STORAGE};
 use crate::utils::error_handler::*;
+use crate::utils::content_utils::normalize_hashtag;
+use crate::services::discovery::tags::adjust_tag_followers;
+use crate::services::query_cache::{invalidate, TAG_QUERIES};
+use crate::auth::is_admin;
+use super::identity::has_linked_wallet;
+use super::cards::{refresh_user_card, user_card};
+use crate::services::content::timeline::backfill_timeline;
+use crate::services::user::post_alerts::remove_post_alert;
+use crate::services::user::follow_graph::{follow_time, record_follow_time, remove_follow_time, sort_by_follow_time};
+use crate::services::events::log_event;
+use crate::models::event::InteractionEventKind;
 
 // Social interactions
-pub fn follow_user(request: FollowUserRequest, caller: Principal) -> SquareResult<()> {
+pub fn follow_user(request: FollowUserRequest, caller: Principal) -> SquareResult<FollowState> {
     const MODULE: &str = "services::user::social";
     const FUNCTION: &str = "follow_user";
     
@@ -25,10 +38,10 @@ pub fn follow_user(request: FollowUserRequest, caller: Principal) -> SquareResul
         ));
     }
     
-    // Check if target user exists
+    // Check if target user exists; deactivated accounts cannot be followed
     let target_exists = STORAGE.with(|storage| {
         let store = storage.borrow();
-        store.users.contains_key(&target_principal)
+        store.users.contains_key(&target_principal) && !store.account_deactivations.contains_key(&target_principal)
     });
     
     if !target_exists {
@@ -43,20 +56,28 @@ pub fn follow_user(request: FollowUserRequest, caller: Principal) -> SquareResul
     // Update follower's profile
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
+        let mut newly_followed = false;
         
         if let Some(profiles) = &mut store.user_profiles {
-            // Update follower's profile
-            if let Some(follower_profile) = profiles.get_mut(&caller) {
-                follower_profile.followed_users.insert(target_principal);
-                follower_profile.following_count = follower_profile.followed_users.len() as u64;
+            // Update follower's profile; counters only move when the set changes
+            if let Some(mut follower_profile) = profiles.get_mut(&caller) {
+                newly_followed = follower_profile.followed_users.insert(target_principal);
+                follower_profile.following_count += newly_followed as u64;
             }
             
             // Update target's profile
-            if let Some(target_profile) = profiles.get_mut(&target_principal) {
-                target_profile.followers.insert(caller);
-                target_profile.followers_count = target_profile.followers.len() as u64;
+            if let Some(mut target_profile) = profiles.get_mut(&target_principal) {
+                let added = target_profile.followers.insert(caller);
+                target_profile.followers_count += added as u64;
             }
         }
+        refresh_user_card(&mut store, &caller);
+        refresh_user_card(&mut store, &target_principal);
+        backfill_timeline(&mut store, caller, target_principal);
+        if newly_followed {
+            record_follow_time(&mut store, caller, target_principal, time() / 1_000_000);
+            log_event(&mut store, caller, InteractionEventKind::Followed { followed: target_principal });
+        }
     });
     
     // Create notification for target user
@@ -82,10 +103,10 @@ pub fn follow_user(request: FollowUserRequest, caller: Principal) -> SquareResul
         Some(caller)
     )?;
     
-    Ok(())
+    Ok(follow_state(caller, target_principal))
 }
 
-pub fn unfollow_user(request: FollowUserRequest, caller: Principal) -> SquareResult<()> {
+pub fn unfollow_user(request: FollowUserRequest, caller: Principal) -> SquareResult<FollowState> {
     const MODULE: &str = "services::user::social";
     const FUNCTION: &str = "unfollow_user";
     
@@ -95,59 +116,102 @@ pub fn unfollow_user(request: FollowUserRequest, caller: Principal) -> SquareRes
     // Update follower's profile
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
+        let mut unfollowed = false;
         
         if let Some(profiles) = &mut store.user_profiles {
-            // Update follower's profile
-            if let Some(follower_profile) = profiles.get_mut(&caller) {
-                follower_profile.followed_users.remove(&target_principal);
-                follower_profile.following_count = follower_profile.followed_users.len() as u64;
+            // Update follower's profile; counters only move when the set changes
+            if let Some(mut follower_profile) = profiles.get_mut(&caller) {
+                unfollowed = follower_profile.followed_users.remove(&target_principal);
+                follower_profile.following_count = follower_profile.following_count.saturating_sub(unfollowed as u64);
             }
             
             // Update target's profile
-            if let Some(target_profile) = profiles.get_mut(&target_principal) {
-                target_profile.followers.remove(&caller);
-                target_profile.followers_count = target_profile.followers.len() as u64;
+            if let Some(mut target_profile) = profiles.get_mut(&target_principal) {
+                let removed = target_profile.followers.remove(&caller);
+                target_profile.followers_count = target_profile.followers_count.saturating_sub(removed as u64);
             }
         }
+        refresh_user_card(&mut store, &caller);
+        refresh_user_card(&mut store, &target_principal);
+        if unfollowed {
+            remove_post_alert(&mut store, caller, target_principal);
+            remove_follow_time(&mut store, caller, target_principal);
+            log_event(&mut store, caller, InteractionEventKind::Unfollowed { followed: target_principal });
+        }
     });
     
-    Ok(())
+    Ok(follow_state(caller, target_principal))
+}
+
+fn follow_state(caller: Principal, target: Principal) -> FollowState {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let profiles = store.user_profiles.as_ref();
+        let follower = profiles.and_then(|profiles| profiles.get(&caller));
+        FollowState {
+            following: follower.is_some_and(|profile| profile.followed_users.contains(&target)),
+            followers_count: profiles.and_then(|profiles| profiles.get(&target)).map_or(0, |profile| profile.followers_count),
+            following_count: follower.map_or(0, |profile| profile.following_count),
+            followed_at: follow_time(&store, &caller, &target),
+        }
+    })
 }
 
 pub fn follow_topic(request: FollowTopicRequest, caller: Principal) -> SquareResult<()> {
     const MODULE: &str = "services::user::social";
     const FUNCTION: &str = "follow_topic";
     
-    // Update user profile
+    invalidate(TAG_QUERIES);
+    
+    let Some(topic) = normalize_hashtag(&request.topic) else {
+        return log_and_return(field_validation_error(
+            "topic",
+            "must contain at least one letter or digit",
+            MODULE,
+            FUNCTION
+        ));
+    };
+    let now = time() / 1_000_000;
+    
+    // Update user profile and the tag's follower count
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         
-        if let Some(profiles) = &mut store.user_profiles {
-            if let Some(profile) = profiles.get_mut(&caller) {
-                profile.followed_topics.insert(request.topic.clone());
-            }
+        let Some(mut profile) = store.user_profiles.as_mut().and_then(|profiles| profiles.get_mut(&caller)) else {
+            return log_and_return(not_found_error("UserProfile", &caller.to_string(), MODULE, FUNCTION));
+        };
+        let changed = profile.followed_topics.insert(topic.clone());
+        drop(profile);
+        if changed {
+            adjust_tag_followers(&mut store, &topic, true, now);
         }
-    });
-    
-    Ok(())
+        Ok(())
+    })
 }
 
 pub fn unfollow_topic(request: FollowTopicRequest, caller: Principal) -> SquareResult<()> {
     const MODULE: &str = "services::user::social";
     const FUNCTION: &str = "unfollow_topic";
     
-    // Update user profile
+    invalidate(TAG_QUERIES);
+    
+    let topic = normalize_hashtag(&request.topic).unwrap_or(request.topic);
+    let now = time() / 1_000_000;
+    
+    // Update user profile and the tag's follower count
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         
-        if let Some(profiles) = &mut store.user_profiles {
-            if let Some(profile) = profiles.get_mut(&caller) {
-                profile.followed_topics.remove(&request.topic);
-            }
+        let Some(mut profile) = store.user_profiles.as_mut().and_then(|profiles| profiles.get_mut(&caller)) else {
+            return log_and_return(not_found_error("UserProfile", &caller.to_string(), MODULE, FUNCTION));
+        };
+        let changed = profile.followed_topics.remove(&topic);
+        drop(profile);
+        if changed {
+            adjust_tag_followers(&mut store, &topic, false, now);
         }
-    });
-    
-    Ok(())
+        Ok(())
+    })
 }
 
 // Follower management functions
@@ -155,13 +219,8 @@ pub fn get_followers(user_identifier: String, caller: Option<Principal>) -> Squa
     const MODULE: &str = "services::user::social";
     const FUNCTION: &str = "get_followers";
     
-    // Parse user identifier
-    let principal = if let Ok(principal) = Principal::from_text(&user_identifier) {
-        principal
-    } else {
-        // Try to find user by handle
-        super::utils::find_user_by_handle(&user_identifier)?
-    };
+    // Parse user identifier (principal or handle)
+    let principal = super::utils::resolve_user_identifier(&user_identifier)?;
     
     // Get user profile
     let profile_result = STORAGE.with(|storage| {
@@ -183,13 +242,16 @@ pub fn get_followers(user_identifier: String, caller: Option<Principal>) -> Squa
         ).with_details("User profile not found")),
     };
     
-    // Get followers
+    // Get followers, skipping deactivated accounts; most recent followers first
     let mut followers = Vec::new();
+    let deactivated = super::utils::deactivated_users();
     
-    for follower_principal in &profile.followers {
-        let social_info = get_user_social_info(follower_principal.to_string(), caller)?;
+    for follower_principal in profile.followers.iter().filter(|p| !deactivated.contains(p)) {
+        let mut social_info = get_user_social_info(follower_principal.to_string(), caller)?;
+        social_info.followed_at = STORAGE.with(|storage| follow_time(&storage.borrow(), follower_principal, &principal));
         followers.push(social_info);
     }
+    sort_by_follow_time(&mut followers);
     
     Ok(followers)
 }
@@ -198,13 +260,8 @@ pub fn get_following(user_identifier: String, caller: Option<Principal>) -> Squa
     const MODULE: &str = "services::user::social";
     const FUNCTION: &str = "get_following";
     
-    // Parse user identifier
-    let principal = if let Ok(principal) = Principal::from_text(&user_identifier) {
-        principal
-    } else {
-        // Try to find user by handle
-        super::utils::find_user_by_handle(&user_identifier)?
-    };
+    // Parse user identifier (principal or handle)
+    let principal = super::utils::resolve_user_identifier(&user_identifier)?;
     
     // Get user profile
     let profile_result = STORAGE.with(|storage| {
@@ -226,13 +283,16 @@ pub fn get_following(user_identifier: String, caller: Option<Principal>) -> Squa
         ).with_details("User profile not found")),
     };
     
-    // Get followed users
+    // Get followed users, skipping deactivated accounts; most recently followed first
     let mut following = Vec::new();
+    let deactivated = super::utils::deactivated_users();
     
-    for followed_principal in &profile.followed_users {
-        let social_info = get_user_social_info(followed_principal.to_string(), caller)?;
+    for followed_principal in profile.followed_users.iter().filter(|p| !deactivated.contains(p)) {
+        let mut social_info = get_user_social_info(followed_principal.to_string(), caller)?;
+        social_info.followed_at = STORAGE.with(|storage| follow_time(&storage.borrow(), &principal, followed_principal));
         following.push(social_info);
     }
+    sort_by_follow_time(&mut following);
     
     Ok(following)
 }
@@ -241,62 +301,134 @@ pub fn get_user_social_info(user_identifier: String, caller: Option<Principal>)
     const MODULE: &str = "services::user::social";
     const FUNCTION: &str = "get_user_social_info";
     
-    // Parse user identifier
-    let principal = if let Ok(principal) = Principal::from_text(&user_identifier) {
-        principal
-    } else {
-        // Try to find user by handle
-        super::utils::find_user_by_handle(&user_identifier)?
-    };
+    // Parse user identifier (principal or handle)
+    let principal = super::utils::resolve_user_identifier(&user_identifier)?;
     
-    // Get user profile
-    let profile_result = STORAGE.with(|storage| {
+    // Feeds call this for every item, so read the card index rather than the full profile
+    let response = STORAGE.with(|storage| {
         let store = storage.borrow();
-        if let Some(profiles) = &store.user_profiles {
-            profiles.get(&principal).cloned()
-        } else {
-            None
-        }
+        let card = user_card(&store, &principal)?;
+        let is_following = caller
+            .and_then(|caller| store.user_profiles.as_ref()?.get(&caller))
+            .is_some_and(|caller_profile| caller_profile.followed_users.contains(&principal));
+        Some(UserSocialResponse {
+            principal,
+            username: card.username,
+            handle: card.handle,
+            avatar: card.avatar,
+            bio: card.bio,
+            interests: card.interests,
+            followers_count: card.followers_count,
+            following_count: card.following_count,
+            is_following,
+            is_followed_by_caller: is_following,  // They are the same in this context
+            has_linked_wallet: has_linked_wallet(&store, &principal),
+            followed_at: None,
+        })
     });
     
-    let profile = match profile_result {
-        Some(profile) => profile,
-        None => return log_and_return(not_found_error(
+    match response {
+        Some(response) => Ok(response),
+        None => log_and_return(not_found_error(
             "UserProfile", 
             &principal.to_string(), 
             MODULE, 
             FUNCTION
         ).with_details("User profile not found")),
-    };
-    
-    // Check if caller is following this user
-    let is_following = if let Some(caller_principal) = caller {
-        STORAGE.with(|storage| {
-            let store = storage.borrow();
-            if let Some(profiles) = &store.user_profiles {
-                if let Some(caller_profile) = profiles.get(&caller_principal) {
-                    caller_profile.followed_users.contains(&principal)
-                } else {
-                    false
-                }
-            } else {
-                false
+    }
+}
+// Follow counts are maintained on follow and unfollow; reset any that disagree with the follow sets.
+// Each run checks the next FOLLOW_REPAIR_BATCH profiles, resuming after `follow_repair_cursor`.
+pub fn repair_follow_counters() -> FollowCounterRepairResponse {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        let Some(profiles) = store.user_profiles.as_mut() else {
+            return FollowCounterRepairResponse { profiles_checked: 0, profiles_repaired: 0 };
+        };
+
+        let batch = profiles.keys_after(store.follow_repair_cursor.as_ref(), FOLLOW_REPAIR_BATCH);
+        // Start over from the first profile once the last batch is done
+        store.follow_repair_cursor = if batch.len() < FOLLOW_REPAIR_BATCH { None } else { batch.last().copied() };
+
+        let mut repaired = Vec::new();
+        for principal in &batch {
+            let drifted = profiles.get(principal).is_some_and(|profile| {
+                profile.followers_count != profile.followers.len() as u64
+                    || profile.following_count != profile.followed_users.len() as u64
+            });
+            // Only drifted profiles go through the write-back guard
+            if drifted && let Some(mut profile) = profiles.get_mut(principal) {
+                profile.followers_count = profile.followers.len() as u64;
+                profile.following_count = profile.followed_users.len() as u64;
+                repaired.push(*principal);
             }
-        })
-    } else {
-        false
-    };
-    
-    Ok(UserSocialResponse {
-        interests: profile.interests,
-        is_followed_by_caller: is_following,  // They are the same in this context
-        principal,
-        username: profile.username,
-        handle: profile.handle,
-        avatar: profile.avatar,
-        bio: profile.bio,
-        followers_count: profile.followers_count,
-        following_count: profile.following_count,
-        is_following,
+        }
+        for principal in &repaired {
+            refresh_user_card(store, principal);
+        }
+        if !repaired.is_empty() {
+            crate::utils::logger::log(&format!("Repaired follow counters of {} profiles", repaired.len()));
+        }
+        FollowCounterRepairResponse {
+            profiles_checked: batch.len() as u64,
+            profiles_repaired: repaired.len() as u64,
+        }
     })
-}
\ No newline at end of file
+}
+
+pub fn run_follow_counter_repair() -> SquareResult<FollowCounterRepairResponse> {
+    const MODULE: &str = "services::user::social";
+    const FUNCTION: &str = "run_follow_counter_repair";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "run_follow_counter_repair",
+            "Only admins can repair follow counters",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    Ok(repair_follow_counters())
+}
+
+// Fill the "liked by people you follow" preview for the calling principal.
+// Intersects the per-content likes index with the caller's follow set, iterating the smaller of the two.
+pub fn attach_liked_by_followed(posts: &mut [PostResponse]) {
+    const PREVIEW_SIZE: usize = 3;
+
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() || posts.is_empty() {
+        return;
+    }
+
+    let previews: Vec<Vec<Principal>> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let Some(followed) = store.user_profiles.as_ref().and_then(|profiles| profiles.get(&caller)).map(|profile| &profile.followed_users) else {
+            return Vec::new();
+        };
+        posts
+            .iter()
+            .map(|post| {
+                let Some(likers) = store.like_sets.get(&post.id) else {
+                    return Vec::new();
+                };
+                let mut matches: Vec<Principal> = if likers.len() <= followed.len() {
+                    likers.iter().filter_map(|id| store.principal_ids.principal(id)).filter(|principal| followed.contains(principal)).collect()
+                } else {
+                    followed.iter().filter(|principal| store.principal_ids.id(principal).is_some_and(|id| likers.contains(id))).cloned().collect()
+                };
+                matches.sort();
+                matches.truncate(PREVIEW_SIZE);
+                matches
+            })
+            .collect()
+    });
+
+    for (post, preview) in posts.iter_mut().zip(previews) {
+        post.liked_by_followed = preview
+            .into_iter()
+            .filter_map(|principal| get_user_social_info(principal.to_string(), Some(caller)).ok())
+            .collect();
+    }
+}
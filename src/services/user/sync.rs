@@ -6,7 +6,10 @@ use ic_cdk::api::time;
 use crate::models::user::*;
 use crate::models::error::{SquareError, SquareResult};
 use crate::storage::{STORAGE};
+use crate::utils::stable_map::StableMap;
 use crate::utils::error_handler::*;
+use super::cards::refresh_user_card;
+use super::admin::require_debug_access;
 
 // Data synchronization functions
 
@@ -16,31 +19,48 @@ pub fn sync_user_data(principal: Principal) -> SquareResult<()> {
     synchronize_specific_user_data(principal)
 }
 
-// This function synchronizes all user data across all storage locations
-// It ensures that all necessary data structures exist for each user
-// Optimized for large-scale systems with batch processing
-pub fn synchronize_all_user_data() -> SquareResult<()> {
+// Users checked per heartbeat by the user data sync
+const USER_SYNC_BATCH: usize = 500;
+
+// Queue a pass of synchronize_specific_user_data over every user. Run on upgrade; the heartbeat
+// works through it, so the upgrade itself does not visit every user.
+pub fn schedule_user_data_sync() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        store.user_sync_pending = true;
+        store.user_sync_cursor = None;
+    });
+}
+
+// Continue the queued user data sync, a batch per heartbeat
+pub fn run_user_data_sync() {
     const MODULE: &str = "services::user::sync";
-    const FUNCTION: &str = "synchronize_all_user_data";
+    const FUNCTION: &str = "run_user_data_sync";
     
-    // Get all user principals from main storage
-    let principals = STORAGE.with(|storage| {
+    let batch = STORAGE.with(|storage| {
         let store = storage.borrow();
-        store.users.keys().cloned().collect::<Vec<Principal>>()
+        store.user_sync_pending.then(|| store.users.keys_after(store.user_sync_cursor.as_ref(), USER_SYNC_BATCH))
     });
+    let Some(batch) = batch else {
+        return;
+    };
     
-    // Synchronize data for each user
-    for principal in principals {
-        match synchronize_specific_user_data(principal) {
-            Ok(_) => (),
-            Err(e) => {
-                ic_cdk::println!("[{}::{}] Error synchronizing data for user {}: {}", 
-                    MODULE, FUNCTION, principal, e);
-            }
+    for principal in &batch {
+        if let Err(e) = synchronize_specific_user_data(*principal) {
+            ic_cdk::println!("[{}::{}] Error synchronizing data for user {}: {}", 
+                MODULE, FUNCTION, principal, e);
         }
     }
     
-    Ok(())
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        store.user_sync_cursor = batch.last().copied();
+        if batch.len() < USER_SYNC_BATCH {
+            store.user_sync_pending = false;
+            store.user_sync_cursor = None;
+            crate::utils::logger::log("User data synchronization completed");
+        }
+    });
 }
 
 // Helper function to synchronize data for a specific user
@@ -101,13 +121,14 @@ pub fn synchronize_specific_user_data(principal: Principal) -> SquareResult<()>
             
             // Create profiles map if it doesn't exist
             if store.user_profiles.is_none() {
-                store.user_profiles = Some(HashMap::new());
+                store.user_profiles = Some(StableMap::default());
             }
             
             // Store profile
             if let Some(profiles) = &mut store.user_profiles {
                 profiles.insert(principal, profile);
             }
+            refresh_user_card(&mut store, &principal);
         });
         
         ic_cdk::println!("[{}::{}] Created default profile for user {}", 
@@ -154,19 +175,25 @@ pub fn synchronize_specific_user_data(principal: Principal) -> SquareResult<()>
             MODULE, FUNCTION, principal);
     }
     
-    // Fix followers and following counts
+    // Fix followers and following counts; profiles whose counts match are not written back
     STORAGE.with(|storage| {
         let mut store = storage.borrow_mut();
         
-        if let Some(profiles) = &mut store.user_profiles {
-            if let Some(profile) = profiles.get_mut(&principal) {
-                // Update followers count
-                profile.followers_count = profile.followers.len() as u64;
-                
-                // Update following count
-                profile.following_count = profile.followed_users.len() as u64;
-            }
+        let Some(profiles) = &mut store.user_profiles else {
+            return;
+        };
+        let drifted = profiles.get(&principal).is_some_and(|profile| {
+            profile.followers_count != profile.followers.len() as u64
+                || profile.following_count != profile.followed_users.len() as u64
+        });
+        if !drifted {
+            return;
         }
+        if let Some(mut profile) = profiles.get_mut(&principal) {
+            profile.followers_count = profile.followers.len() as u64;
+            profile.following_count = profile.followed_users.len() as u64;
+        }
+        refresh_user_card(&mut store, &principal);
     });
     
     Ok(())
@@ -177,6 +204,8 @@ pub fn debug_fix_user_data(principal_str: String) -> SquareResult<bool> {
     const MODULE: &str = "services::user::sync";
     const FUNCTION: &str = "debug_fix_user_data";
     
+    require_debug_access(MODULE, FUNCTION)?;
+    
     // Parse principal
     let principal = match Principal::from_text(&principal_str) {
         Ok(p) => p,
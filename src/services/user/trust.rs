@@ -0,0 +1,190 @@
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::auth::{is_admin, is_manager_or_admin};
+use crate::models::content::{ContentStatus, MAX_MEDIA_URLS};
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::storage::Storage;
+use crate::models::trust::*;
+use crate::models::user::{UserModerationAction, UserStatus};
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::{MILLIS_PER_DAY, MILLIS_PER_HOUR};
+
+const MODULE: &str = "services::user::trust";
+
+// The author's Active posts, read through the user_posts index
+pub(crate) fn active_posts(store: &Storage, author: &Principal) -> u64 {
+    store.user_posts
+        .get(author)
+        .map_or(0, |post_ids| post_ids
+            .iter()
+            .filter(|post_id| store.posts.get(*post_id).is_some_and(|post| post.status == ContentStatus::Active))
+            .count() as u64)
+}
+
+// Derive an account's trust level from its age, active posts and recent moderation history
+pub fn compute_trust_level(store: &Storage, principal: Principal, now: u64) -> TrustLevelResponse {
+    let settings = store.trust_settings.clone().unwrap_or_default();
+
+    let account_age_days = store.users
+        .get(&principal)
+        .map_or(0, |user| now.saturating_sub(user.registered_at) / MILLIS_PER_DAY);
+    let active_posts = active_posts(store, &principal);
+
+    let history_start = now.saturating_sub(settings.clean_history_days * MILLIS_PER_DAY);
+    let clean_history = !store.user_moderation_log.get(&principal).is_some_and(|records| {
+        records.iter().any(|record| {
+            record.timestamp >= history_start
+                && matches!(
+                    record.action,
+                    UserModerationAction::ContentModerated(ContentStatus::Removed)
+                        | UserModerationAction::StatusChanged(UserStatus::Suspended)
+                        | UserModerationAction::StatusChanged(UserStatus::Banned)
+                )
+        })
+    });
+
+    let meets = |config: &TrustLevelConfig| {
+        clean_history && account_age_days >= config.min_account_age_days && active_posts >= config.min_active_posts
+    };
+    let level = if meets(&settings.regular) {
+        TrustLevel::Regular
+    } else if meets(&settings.member) {
+        TrustLevel::Member
+    } else if meets(&settings.basic) {
+        TrustLevel::Basic
+    } else {
+        TrustLevel::New
+    };
+    let next_level = match level {
+        TrustLevel::New => Some(TrustLevel::Basic),
+        TrustLevel::Basic => Some(TrustLevel::Member),
+        TrustLevel::Member => Some(TrustLevel::Regular),
+        TrustLevel::Regular => None,
+    };
+
+    TrustLevelResponse {
+        level,
+        privileges: settings.privileges(level).clone(),
+        account_age_days,
+        active_posts,
+        clean_history,
+        next_level,
+    }
+}
+
+fn contains_link(text: &str) -> bool {
+    let text = text.to_lowercase();
+    text.contains("http://") || text.contains("https://") || text.contains("www.")
+}
+
+// Enforce the trust-level limits on what a post, edit or comment contains; admins and managers are exempt
+pub fn check_content_privileges(author: Principal, content: &str, media_count: usize) -> SquareResult<()> {
+    const FUNCTION: &str = "check_content_privileges";
+
+    if is_manager_or_admin().is_ok() {
+        return Ok(());
+    }
+
+    let now = time() / 1_000_000;
+    let trust = STORAGE.with(|storage| compute_trust_level(&storage.borrow(), author, now));
+    check_privileges(&trust, content, media_count, FUNCTION)
+}
+
+fn check_privileges(trust: &TrustLevelResponse, content: &str, media_count: usize, function: &str) -> SquareResult<()> {
+    let privileges = &trust.privileges;
+
+    if !privileges.can_post_links && contains_link(content) {
+        return log_and_return(permission_denied_error(
+            "post_links",
+            &format!("Accounts at trust level {:?} cannot post links yet", trust.level),
+            MODULE,
+            function
+        ));
+    }
+    if media_count > privileges.max_media_urls as usize {
+        return log_and_return(field_validation_error(
+            "media_urls",
+            &format!("trust level {:?} allows at most {} media items per post", trust.level, privileges.max_media_urls),
+            MODULE,
+            function
+        ));
+    }
+    Ok(())
+}
+
+// Enforce the author's trust-level posting privileges for a new post, including its hourly post limit;
+// admins and managers are exempt
+pub fn check_posting_privileges(author: Principal, content: &str, media_count: usize) -> SquareResult<()> {
+    const FUNCTION: &str = "check_posting_privileges";
+
+    if is_manager_or_admin().is_ok() {
+        return Ok(());
+    }
+
+    let now = time() / 1_000_000;
+    let (trust, recent_posts) = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let recent_posts = store.user_posts
+            .get(&author)
+            .into_iter()
+            .flatten()
+            .filter(|post_id| store.posts.get(*post_id).is_some_and(|post| post.created_at + MILLIS_PER_HOUR > now))
+            .count();
+        (compute_trust_level(&store, author, now), recent_posts)
+    });
+    check_privileges(&trust, content, media_count, FUNCTION)?;
+    let privileges = &trust.privileges;
+    if recent_posts >= privileges.max_posts_per_hour as usize {
+        return log_and_return(rate_limit_error("create_post", privileges.max_posts_per_hour as u64, MODULE, FUNCTION)
+            .with_details(format!("Trust level {:?} allows {} posts per hour", trust.level, privileges.max_posts_per_hour)));
+    }
+    Ok(())
+}
+
+pub fn get_my_trust_level(caller: Principal) -> SquareResult<TrustLevelResponse> {
+    let now = time() / 1_000_000;
+    Ok(STORAGE.with(|storage| compute_trust_level(&storage.borrow(), caller, now)))
+}
+
+pub fn get_trust_settings() -> SquareResult<TrustSettings> {
+    Ok(STORAGE.with(|storage| storage.borrow().trust_settings.clone().unwrap_or_default()))
+}
+
+pub fn update_trust_settings(settings: TrustSettings) -> SquareResult<TrustSettings> {
+    const FUNCTION: &str = "update_trust_settings";
+
+    if is_admin().is_err() {
+        return log_and_return(permission_denied_error(
+            "update_trust_settings",
+            "Only admins can update trust level settings",
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    let levels = [&settings.basic, &settings.member, &settings.regular];
+    let ascending = levels.windows(2).all(|pair| {
+        pair[0].min_account_age_days <= pair[1].min_account_age_days && pair[0].min_active_posts <= pair[1].min_active_posts
+    });
+    if !ascending {
+        return log_and_return(validation_error(
+            "Each trust level must require at least as much as the level below it",
+            MODULE,
+            FUNCTION
+        ));
+    }
+    let all_privileges = [&settings.new, &settings.basic.privileges, &settings.member.privileges, &settings.regular.privileges];
+    if all_privileges.iter().any(|privileges| privileges.max_media_urls as usize > MAX_MEDIA_URLS) {
+        return log_and_return(field_validation_error(
+            "max_media_urls",
+            &format!("must be at most {}", MAX_MEDIA_URLS),
+            MODULE,
+            FUNCTION
+        ));
+    }
+
+    STORAGE.with(|storage| storage.borrow_mut().trust_settings = Some(settings.clone()));
+    Ok(settings)
+}
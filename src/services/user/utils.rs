@@ -1,10 +1,11 @@
 use candid::Principal;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::borrow::Borrow;
 
 use crate::models::user::*;
 use crate::models::error::{SquareError, SquareResult};
 use crate::models::content::PaginationParams;
+use crate::models::storage::Storage;
 use crate::storage::{STORAGE, UserStatus as StorageUserStatus, UserRole as StorageUserRole};
 use crate::utils::error_handler::*;
 
@@ -15,6 +16,7 @@ pub fn map_storage_status_to_model(status: StorageUserStatus) -> UserStatus {
         StorageUserStatus::Suspended => UserStatus::Suspended,
         StorageUserStatus::Banned => UserStatus::Banned,
         StorageUserStatus::Restricted => UserStatus::Restricted,
+        StorageUserStatus::Deactivated => UserStatus::Deactivated,
     }
 }
 
@@ -28,6 +30,38 @@ pub fn map_storage_role_to_model(role: StorageUserRole) -> UserRole {
     }
 }
 
+// Principals of self-deactivated accounts, hidden from search, follows and feeds
+pub fn deactivated_users() -> HashSet<Principal> {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.account_deactivations.keys().cloned().collect()
+    })
+}
+
+// User's timezone offset in minutes, 0 (UTC) when unset
+pub fn user_timezone_offset(user: &Principal) -> i32 {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        store.user_timezones.get(user).map(|timezone| timezone.offset_minutes).unwrap_or(0)
+    })
+}
+
+// Handles are unique regardless of case; the stored casing is kept for display
+pub fn is_handle_taken(store: &Storage, handle: &str, except: Option<Principal>) -> bool {
+    store.user_profiles.as_ref().is_some_and(|profiles| {
+        profiles.iter().any(|(principal, profile)| Some(*principal) != except && profile.handle.eq_ignore_ascii_case(handle))
+    })
+}
+
+// Resolve a user identifier given as principal text or handle (with or without a leading '@')
+pub fn resolve_user_identifier(identifier: &str) -> SquareResult<Principal> {
+    let identifier = identifier.trim();
+    match Principal::from_text(identifier) {
+        Ok(principal) => Ok(principal),
+        Err(_) => find_user_by_handle(identifier.strip_prefix('@').unwrap_or(identifier)),
+    }
+}
+
 // Find user principal by handle
 pub fn find_user_by_handle(handle: &str) -> SquareResult<Principal> {
     const MODULE: &str = "services::user::utils";
@@ -36,14 +70,12 @@ pub fn find_user_by_handle(handle: &str) -> SquareResult<Principal> {
     // Search in main storage
     let principal_result = STORAGE.with(|storage| {
         let store = storage.borrow();
-        if let Some(profiles) = &store.user_profiles {
-            for (principal, profile) in profiles {
-                if profile.handle == handle {
-                    return Some(*principal);
-                }
-            }
-        }
-        None
+        let profiles = store.user_profiles.as_ref()?;
+        // An exact match wins while case-only duplicates from before the migration remain
+        profiles.iter()
+            .find(|(_, profile)| profile.handle == handle)
+            .or_else(|| profiles.iter().find(|(_, profile)| profile.handle.eq_ignore_ascii_case(handle)))
+            .map(|(principal, _)| *principal)
     });
     
     match principal_result {
@@ -57,51 +89,64 @@ pub fn find_user_by_handle(handle: &str) -> SquareResult<Principal> {
     }
 }
 
-// Get user leaderboard
-pub fn get_user_leaderboard(pagination: PaginationParams) -> SquareResult<UserLeaderboardResponse> {
-    const MODULE: &str = "services::user::utils";
-    const FUNCTION: &str = "get_user_leaderboard";
-    
+// Users who turned on `hide_from_rankings` in their interaction preferences
+pub fn hidden_from_rankings(store: &Storage, principal: &Principal) -> bool {
+    store.user_profiles
+        .as_ref()
+        .and_then(|profiles| profiles.get(principal))
+        .and_then(|profile| profile.privacy_settings.as_ref())
+        .and_then(|settings| settings.interaction_preferences.hide_from_rankings)
+        .unwrap_or(false)
+}
+
+// Every user with stats, by reputation (descending) and not yet ranked
+fn leaderboard_entries(store: &Storage) -> Vec<UserLeaderboardItem> {
     let mut users: Vec<UserLeaderboardItem> = Vec::new();
-    
-    // Get user stats from main storage
-    STORAGE.with(|storage| {
-        let store = storage.borrow();
-        
-        if let Some(stats) = &store.user_stats {
-            // Convert stats to leaderboard entries
-            for (principal, stat) in stats {
-                // Get user profile
-                let profile = if let Some(profiles) = &store.user_profiles {
-                    profiles.get(principal).cloned()
-                } else {
-                    None
-                };
-                
-                if let Some(profile) = profile {
-                    let post_count = store.posts.values().filter(|post| post.author == *principal).count() as u64;
-                    users.push(UserLeaderboardItem {
-                        principal: *principal,
-                        username: profile.username,
-                        handle: profile.handle,
-                        avatar: profile.avatar,
-                        rank: 0, // Will be set after sorting
-                        post_count: post_count,
-                        last_claim_date: 0, // TODO: Get from daily check-in
-                        consecutive_daily_logins: 0, // TODO: Get from daily check-in
-                        followers_count: profile.followers_count,
-                        comment_count: stat.comment_count,
-                        like_count: stat.like_count,
-                        reputation: stat.reputation,
-                    });
-                }
+
+    if let Some(stats) = &store.user_stats {
+        // Convert stats to leaderboard entries
+        for (principal, stat) in stats {
+            // Get user profile
+            let profile = if let Some(profiles) = &store.user_profiles {
+                profiles.get(principal).cloned()
+            } else {
+                None
+            };
+
+            if let Some(profile) = profile {
+                users.push(UserLeaderboardItem {
+                    principal: *principal,
+                    username: profile.username,
+                    handle: profile.handle,
+                    avatar: profile.avatar,
+                    rank: 0, // Will be set after sorting
+                    post_count: stat.post_count,
+                    last_claim_date: 0, // TODO: Get from daily check-in
+                    consecutive_daily_logins: 0, // TODO: Get from daily check-in
+                    followers_count: profile.followers_count,
+                    comment_count: stat.comment_count,
+                    like_count: stat.like_count,
+                    reputation: stat.reputation,
+                    points: store.user_rewards.get(principal).map_or(0, |rewards| rewards.points),
+                });
             }
         }
+    }
+
+    // Sort users by reputation (descending); ties by principal so ranks do not change between calls
+    users.sort_by(|a, b| b.reputation.cmp(&a.reputation).then_with(|| a.principal.cmp(&b.principal)));
+    users
+}
+
+// Get user leaderboard. Users hidden from rankings are left out and do not take up a rank.
+pub fn get_user_leaderboard(pagination: PaginationParams) -> SquareResult<UserLeaderboardResponse> {
+    let mut users: Vec<UserLeaderboardItem> = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let mut users = leaderboard_entries(&store);
+        users.retain(|user| !hidden_from_rankings(&store, &user.principal));
+        users
     });
     
-    // Sort users by reputation (descending)
-    users.sort_by(|a, b| b.reputation.cmp(&a.reputation));
-    
     // Set ranks based on reputation
     for (i, user) in users.iter_mut().enumerate() {
         user.rank = (i + 1) as u64;
@@ -130,4 +175,20 @@ pub fn get_user_leaderboard(pagination: PaginationParams) -> SquareResult<UserLe
             start as u64
         },
     })
-}
\ No newline at end of file
+}
+
+// The caller's own leaderboard entry, ranked among all users even when they are hidden from rankings
+pub fn get_my_leaderboard_rank(caller: Principal) -> SquareResult<UserLeaderboardItem> {
+    const MODULE: &str = "services::user::utils";
+    const FUNCTION: &str = "get_my_leaderboard_rank";
+
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        leaderboard_entries(&store)
+            .into_iter()
+            .enumerate()
+            .find(|(_, user)| user.principal == caller)
+            .map(|(i, user)| UserLeaderboardItem { rank: (i + 1) as u64, ..user })
+            .ok_or_else(|| not_found_error("User", &caller.to_string(), MODULE, FUNCTION))
+    })
+}
@@ -0,0 +1,134 @@
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::models::account_quality::RateLimitedAction;
+use crate::models::content::PaginationParams;
+use crate::models::display::PaginatedResponse;
+use crate::models::error::{SquareError, SquareResult};
+use crate::models::profile_visit::*;
+use crate::models::storage::Storage;
+use crate::services::user::check_action_rate_limit;
+use crate::services::user::creator::paginate;
+use crate::services::user::social::get_user_social_info;
+use crate::storage::STORAGE;
+use crate::utils::error_handler::*;
+use crate::utils::time_utils::MILLIS_PER_DAY;
+
+const MODULE: &str = "services::user::visitors";
+
+// Profile visit sharing is opt-in
+pub fn shares_profile_visits(store: &Storage, principal: &Principal) -> bool {
+    store.user_profiles
+        .as_ref()
+        .and_then(|profiles| profiles.get(principal))
+        .and_then(|profile| profile.privacy_settings.as_ref())
+        .and_then(|settings| settings.interaction_preferences.share_profile_visits)
+        .unwrap_or(false)
+}
+
+// Count a view of `profile_owner`'s profile; the visitor is only named when both users share profile visits.
+// Each visitor counts once a day per profile, and within their hourly profile visit limit.
+pub fn record_profile_visit(profile_owner: Principal, caller: Principal) -> SquareResult<()> {
+    const FUNCTION: &str = "record_profile_visit";
+
+    if profile_owner == caller {
+        return Ok(());
+    }
+    let now = time() / 1_000_000;
+
+    let counted_recently = STORAGE.with(|storage| {
+        storage.borrow().recent_profile_visits
+            .get(&caller)
+            .and_then(|visited| visited.get(&profile_owner))
+            .is_some_and(|visited_at| visited_at + PROFILE_VISIT_DEDUP_MS > now)
+    });
+    if counted_recently {
+        return Ok(());
+    }
+    check_action_rate_limit(caller, RateLimitedAction::ProfileVisit)?;
+
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if !store.users.contains_key(&profile_owner) {
+            return log_and_return(not_found_error("User", &profile_owner.to_string(), MODULE, FUNCTION));
+        }
+        if !store.users.contains_key(&caller) {
+            return log_and_return(not_found_error("User", &caller.to_string(), MODULE, FUNCTION));
+        }
+        let visited = store.recent_profile_visits.entry(caller).or_default();
+        visited.retain(|_, visited_at| *visited_at + PROFILE_VISIT_DEDUP_MS > now);
+        visited.insert(profile_owner, now);
+
+        let visitor = Some(caller).filter(|caller| shares_profile_visits(&store, caller) && shares_profile_visits(&store, &profile_owner));
+        let visits = store.profile_visits.entry(profile_owner).or_default();
+        visits.retain(|visit| visit.visited_at + PROFILE_VISIT_WINDOW_MS > now);
+        // A named visitor is listed once, at their latest visit
+        if visitor.is_some() {
+            visits.retain(|visit| visit.visitor != visitor);
+        }
+        visits.push(ProfileVisit { visitor, visited_at: now });
+        if visits.len() > MAX_PROFILE_VISITS_PER_USER {
+            let excess = visits.len() - MAX_PROFILE_VISITS_PER_USER;
+            visits.drain(..excess);
+        }
+        Ok(())
+    })
+}
+
+// Drop the repeat-visit records older than a day; run from the heartbeat
+pub fn prune_recent_profile_visits() {
+    let now = time() / 1_000_000;
+    STORAGE.with(|storage| {
+        storage.borrow_mut().recent_profile_visits.retain(|_, visited| {
+            visited.retain(|_, visited_at| *visited_at + PROFILE_VISIT_DEDUP_MS > now);
+            !visited.is_empty()
+        });
+    });
+}
+
+// Visitors who still share their visits, newest first; counts only unless the caller shares theirs too
+pub fn get_profile_visitors(pagination: PaginationParams, caller: Principal) -> SquareResult<ProfileVisitorsResponse> {
+    let now = time() / 1_000_000;
+
+    let (sharing_enabled, total_visits, named) = STORAGE.with(|storage| {
+        let store = storage.borrow();
+        let sharing_enabled = shares_profile_visits(&store, &caller);
+        let visits: Vec<&ProfileVisit> = store.profile_visits
+            .get(&caller)
+            .map(|visits| visits.iter().filter(|visit| visit.visited_at + PROFILE_VISIT_WINDOW_MS > now).collect())
+            .unwrap_or_default();
+
+        let mut named: Vec<ProfileVisit> = visits
+            .iter()
+            .filter(|visit| sharing_enabled && visit.visitor.is_some_and(|visitor| shares_profile_visits(&store, &visitor)))
+            .map(|visit| (*visit).clone())
+            .collect();
+        named.sort_by_key(|visit| std::cmp::Reverse(visit.visited_at));
+        (sharing_enabled, visits.len() as u64, named)
+    });
+
+    let anonymous_visits = total_visits - named.len() as u64;
+    let page = paginate(named, pagination);
+    let items = page.items
+        .into_iter()
+        .filter_map(|visit| {
+            let visitor = visit.visitor?;
+            get_user_social_info(visitor.to_string(), Some(caller))
+                .ok()
+                .map(|user| ProfileVisitor { user, visited_at: visit.visited_at })
+        })
+        .collect();
+
+    Ok(ProfileVisitorsResponse {
+        sharing_enabled,
+        total_visits,
+        anonymous_visits,
+        window_days: PROFILE_VISIT_WINDOW_MS / MILLIS_PER_DAY,
+        visitors: PaginatedResponse {
+            items,
+            total: page.total,
+            has_more: page.has_more,
+            next_offset: page.next_offset,
+        },
+    })
+}
@@ -0,0 +1,71 @@
+// Stable memory layout. A MemoryManager splits stable memory into virtual memories: one
+// StableBTreeMap each for posts, comments, users, profiles, likes, rewards and both directions of
+// the principal id table, which are written as they change and so survive upgrades on their own,
+// and a cell holding the rest of Storage, which is still saved in pre_upgrade.
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager};
+use ic_stable_structures::{DefaultMemoryImpl, Memory, StableCell};
+use std::cell::RefCell;
+
+use crate::models::storage::Storage;
+use crate::utils::stable_map::DefaultMemory;
+
+pub const POSTS_MEMORY: MemoryId = MemoryId::new(0);
+pub const COMMENTS_MEMORY: MemoryId = MemoryId::new(1);
+pub const USERS_MEMORY: MemoryId = MemoryId::new(2);
+pub const LIKES_MEMORY: MemoryId = MemoryId::new(3);
+pub const REWARDS_MEMORY: MemoryId = MemoryId::new(4);
+pub const STATE_MEMORY: MemoryId = MemoryId::new(5);
+pub const PROFILES_MEMORY: MemoryId = MemoryId::new(6);
+pub const PRINCIPAL_BY_ID_MEMORY: MemoryId = MemoryId::new(7);
+pub const PRINCIPAL_IDS_MEMORY: MemoryId = MemoryId::new(8);
+
+// Written by the memory manager at the start of stable memory
+const MEMORY_MANAGER_MAGIC: &[u8; 3] = b"MGR";
+
+thread_local! {
+    // Created on first use, so that post_upgrade can read a legacy layout before the manager
+    // claims stable memory
+    static MEMORY_MANAGER: RefCell<Option<MemoryManager<DefaultMemoryImpl>>> = const { RefCell::new(None) };
+}
+
+pub fn memory(id: MemoryId) -> DefaultMemory {
+    MEMORY_MANAGER.with(|manager| {
+        manager
+            .borrow_mut()
+            .get_or_insert_with(|| MemoryManager::init(DefaultMemoryImpl::default()))
+            .get(id)
+    })
+}
+
+// Whether stable memory is laid out by the memory manager, as opposed to empty or holding the raw
+// backup regions written by releases before it
+pub fn uses_memory_manager() -> bool {
+    let stable = DefaultMemoryImpl::default();
+    if stable.size() == 0 {
+        return false;
+    }
+    let mut magic = [0u8; 3];
+    stable.read(0, &mut magic);
+    &magic == MEMORY_MANAGER_MAGIC
+}
+
+fn state_cell() -> StableCell<Vec<u8>, DefaultMemory> {
+    StableCell::init(memory(STATE_MEMORY), Vec::new()).expect("the state cell initializes")
+}
+
+// The collections backed by their own memory serialize as empty, so this stays small as they grow
+pub fn save_state(store: &Storage) -> Result<usize, String> {
+    let bytes = candid::encode_one(store).map_err(|e| format!("Failed to encode storage: {:?}", e))?;
+    let size = bytes.len();
+    state_cell().set(bytes).map_err(|e| format!("Failed to write the state cell: {:?}", e))?;
+    Ok(size)
+}
+
+// None when pre_upgrade never saved it, which happens if it was skipped before the first save
+pub fn load_state() -> Result<Option<Storage>, String> {
+    let cell = state_cell();
+    if cell.get().is_empty() {
+        return Ok(None);
+    }
+    candid::decode_one(cell.get()).map(Some).map_err(|e| format!("Failed to decode storage: {:?}", e))
+}
@@ -1,4 +1,21 @@
 use crate::storage::STORAGE;
+use crate::services::content::summary::refresh_article_summary;
+use crate::services::content::outline::refresh_article_outline;
+use crate::utils::time_utils::normalize_to_millis;
+use crate::utils::content_utils::{normalize_hashtag, normalize_hashtags};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use candid::Principal;
+use crate::models::notification::NotificationType;
+use crate::services::user::create_notification;
+use crate::services::user::cards::{rebuild_user_cards, refresh_user_card};
+use crate::models::event::{EventCheckpoint, InteractionEventKind, StatsCounters};
+use crate::models::interaction::ReportStatus;
+use crate::models::content::{ContentStatus, ParentType};
+use crate::services::user::quota::{comment_size, post_size};
+use crate::services::task_template::default_task_templates;
+use crate::services::treasury::record_opening_balance;
+use crate::services::discovery::tags::new_tag;
+use crate::utils::time_utils::TimestampMillis;
 use ic_cdk::api::time;
 use super::types::*;
 
@@ -34,6 +51,579 @@ pub fn migrate_discovery() {
     ic_cdk::println!("Using unified storage, no discovery data migration needed");
 }
 
+fn normalize(timestamp: &mut u64) {
+    *timestamp = normalize_to_millis(*timestamp);
+}
+
+fn normalize_opt(timestamp: &mut Option<u64>) {
+    if let Some(value) = timestamp {
+        normalize(value);
+    }
+}
+
+// Older releases stored a mix of seconds, milliseconds and nanoseconds.
+// Convert every stored timestamp to milliseconds once; returns whether anything was migrated.
+pub fn migrate_timestamps_to_millis() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.timestamps_in_millis {
+            return false;
+        }
+
+        for mut user in store.users.values_mut() {
+            normalize(&mut user.registered_at);
+            normalize(&mut user.last_login);
+        }
+        if let Some(profiles) = store.user_profiles.as_mut() {
+            for mut profile in profiles.values_mut() {
+                normalize(&mut profile.created_at);
+                normalize(&mut profile.updated_at);
+            }
+        }
+        for mut post in store.posts.values_mut() {
+            normalize(&mut post.created_at);
+            normalize(&mut post.updated_at);
+        }
+        for mut comment in store.comments.values_mut() {
+            normalize(&mut comment.created_at);
+            normalize(&mut comment.updated_at);
+        }
+        for report in store.reports.values_mut() {
+            normalize(&mut report.created_at);
+            normalize_opt(&mut report.resolved_at);
+        }
+        normalize_opt(&mut store.last_trending_update);
+
+        for mut rewards in store.user_rewards.values_mut() {
+            normalize_opt(&mut rewards.last_claim_date);
+            normalize(&mut rewards.last_updated);
+            let rewards = &mut *rewards;
+            for transaction in rewards.points_history.iter_mut().chain(rewards.transactions.iter_mut()) {
+                normalize(&mut transaction.timestamp);
+            }
+        }
+        for tasks in store.user_tasks.values_mut() {
+            tasks.completed_tasks.values_mut().for_each(normalize);
+            normalize(&mut tasks.daily_tasks_reset);
+            normalize_opt(&mut tasks.last_check_in);
+            normalize(&mut tasks.last_updated);
+        }
+        if let Some(tasks) = store.tasks.as_mut() {
+            for task in tasks.values_mut() {
+                normalize_opt(&mut task.expiration_time);
+                normalize(&mut task.created_at);
+                normalize(&mut task.updated_at);
+            }
+        }
+
+        for notification in store.user_notifications.values_mut().flatten() {
+            normalize(&mut notification.created_at);
+        }
+        for delegation in store.posting_delegations.values_mut().flat_map(|grants| grants.values_mut()) {
+            normalize(&mut delegation.granted_at);
+        }
+        for record in store.delegation_audit_log.values_mut().flatten() {
+            normalize(&mut record.timestamp);
+        }
+        for application in store.creator_applications.values_mut() {
+            normalize(&mut application.submitted_at);
+            normalize_opt(&mut application.reviewed_at);
+        }
+        for record in store.user_moderation_log.values_mut().flatten() {
+            normalize(&mut record.timestamp);
+        }
+        for deactivation in store.account_deactivations.values_mut() {
+            normalize(&mut deactivation.deactivated_at);
+        }
+
+        store.timestamps_in_millis = true;
+        true
+    })
+}
+
+// Older releases stored hashtags as typed, so #ICP, #icp and #Icp were counted separately.
+// Normalize post hashtags and fold trending counts together once; returns whether anything was migrated.
+pub fn migrate_hashtags_to_normalized() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.hashtags_normalized {
+            return false;
+        }
+
+        for mut post in store.posts.values_mut() {
+            post.hashtags = normalize_hashtags(std::mem::take(&mut post.hashtags));
+        }
+        let store = &mut *store;
+        for counts in [&mut store.trending_topics, &mut store.previous_trending_topics] {
+            let mut merged = BTreeMap::new();
+            for (tag, count) in std::mem::take(counts) {
+                if let Some(tag) = normalize_hashtag(&tag) {
+                    *merged.entry(tag).or_insert(0) += count;
+                }
+            }
+            *counts = merged;
+        }
+
+        store.hashtags_normalized = true;
+        true
+    })
+}
+
+// Articles written before summaries existed have none cached; build them once
+pub fn migrate_article_summaries() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.article_summaries_built {
+            return false;
+        }
+
+        let post_ids: Vec<String> = store.posts.keys().cloned().collect();
+        for post_id in post_ids {
+            refresh_article_summary(&mut store, &post_id);
+        }
+
+        store.article_summaries_built = true;
+        true
+    })
+}
+
+// Articles written before tables of contents existed have none cached; build them once
+pub fn migrate_article_outlines() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.article_outlines_built {
+            return false;
+        }
+
+        let post_ids: Vec<String> = store.posts.keys().cloned().collect();
+        for post_id in post_ids {
+            refresh_article_outline(&mut store, &post_id);
+        }
+
+        store.article_outlines_built = true;
+        true
+    })
+}
+
+// Handles used to be unique only in their exact casing, so "Alice" and "alice" could both exist.
+// The oldest account keeps its handle and the others get a numbered one, and are told so; returns whether anything was migrated.
+pub fn migrate_handles_case_insensitive() -> bool {
+    let renamed = STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.handles_case_insensitive {
+            return None;
+        }
+
+        let mut renamed = Vec::new();
+        if let Some(profiles) = store.user_profiles.as_mut() {
+            let mut in_use: HashSet<String> = profiles.values().map(|profile| profile.handle.to_ascii_lowercase()).collect();
+            let mut kept = HashSet::new();
+            let mut by_age: Vec<(TimestampMillis, Principal)> = profiles.iter().map(|(principal, profile)| (profile.created_at, *principal)).collect();
+            by_age.sort();
+
+            for (_, principal) in by_age {
+                let Some(mut profile) = profiles.get_mut(&principal) else {
+                    continue;
+                };
+                if kept.insert(profile.handle.to_ascii_lowercase()) {
+                    continue;
+                }
+                let handle = (2..)
+                    .map(|n| {
+                        let suffix = format!("_{}", n);
+                        let base: String = profile.handle.chars().take(30 - suffix.len()).collect();
+                        format!("{}{}", base, suffix)
+                    })
+                    .find(|candidate| !in_use.contains(&candidate.to_ascii_lowercase()))
+                    .unwrap();
+                in_use.insert(handle.to_ascii_lowercase());
+                kept.insert(handle.to_ascii_lowercase());
+                renamed.push((principal, std::mem::replace(&mut profile.handle, handle.clone()), handle));
+            }
+        }
+        for (principal, _, _) in &renamed {
+            refresh_user_card(&mut store, principal);
+        }
+
+        store.handles_case_insensitive = true;
+        Some(renamed)
+    });
+
+    let Some(renamed) = renamed else {
+        return false;
+    };
+    for (principal, old_handle, new_handle) in renamed {
+        let _ = create_notification(
+            principal,
+            NotificationType::System,
+            format!(
+                "Handles are now unique regardless of case. @{} matched an older account's handle, so yours is now @{}. You can choose another one in your profile.",
+                old_handle, new_handle
+            ),
+            None,
+            None
+        );
+    }
+    true
+}
+
+// Profiles created before the user card index have no card; build the index once
+pub fn migrate_user_cards() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.user_cards_built {
+            return false;
+        }
+
+        rebuild_user_cards(&mut store);
+        store.user_cards_built = true;
+        true
+    })
+}
+
+// Likes used to be stored as plain principal sets; intern the principals and compress each set once
+pub fn migrate_like_sets() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.like_sets_built {
+            return false;
+        }
+
+        let store = &mut *store;
+        for (content_id, likers) in std::mem::take(&mut store.legacy_likes) {
+            let mut like_set = store.like_sets.entry(content_id).or_default();
+            for liker in likers {
+                like_set.insert(store.principal_ids.intern(liker));
+            }
+        }
+        store.like_sets.retain(|_, likers| !likers.is_empty());
+        store.like_sets_built = true;
+        true
+    })
+}
+
+// Post and comment counts in user stats were never maintained before the event log; count them
+// from existing content and checkpoint the derived state the log starts from
+pub fn migrate_event_log() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.event_log_initialized {
+            return false;
+        }
+
+        let store = &mut *store;
+        let mut post_counts: HashMap<Principal, u64> = HashMap::new();
+        for post in store.posts.values() {
+            *post_counts.entry(post.author).or_insert(0) += 1;
+        }
+        let mut comment_counts: HashMap<Principal, u64> = HashMap::new();
+        for comment in store.comments.values() {
+            *comment_counts.entry(comment.author).or_insert(0) += 1;
+        }
+        for (principal, stats) in store.user_stats.iter_mut().flatten() {
+            stats.post_count = post_counts.get(principal).copied().unwrap_or(0);
+            stats.comment_count = comment_counts.get(principal).copied().unwrap_or(0);
+        }
+
+        let log = &mut store.interaction_events;
+        log.checkpoint = EventCheckpoint {
+            seq: log.next_seq,
+            user_counters: store.user_stats
+                .iter()
+                .flatten()
+                .map(|(principal, stats)| (*principal, StatsCounters::from(stats)))
+                .filter(|(_, counters)| *counters != StatsCounters::default())
+                .collect(),
+            user_stats: HashMap::new(),
+            trending_topics: store.trending_topics.clone(),
+            previous_trending_topics: store.previous_trending_topics.clone(),
+            task_progress: store.task_progress.clone(),
+        };
+        store.event_log_initialized = true;
+        true
+    })
+}
+
+// Count the storage used by content stored before quotas existed
+pub fn migrate_storage_usage() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.storage_usage_built {
+            return false;
+        }
+
+        let mut usage: HashMap<Principal, u64> = HashMap::new();
+        for post in store.posts.values() {
+            *usage.entry(post.author).or_insert(0) += post_size(post);
+        }
+        for comment in store.comments.values() {
+            *usage.entry(comment.author).or_insert(0) += comment_size(comment);
+        }
+        store.storage_usage = usage;
+        store.storage_usage_built = true;
+        true
+    })
+}
+
+// Earlier releases kept "daily_post" and "social_engagement" as fixed task definitions. They become
+// templates, keeping whether each was active; a fresh set is seeded only when there were no tasks at all.
+pub fn migrate_task_templates() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.task_templates_migrated {
+            return false;
+        }
+
+        let store = &mut *store;
+        let tasks = store.tasks.get_or_insert_with(HashMap::new);
+        let seed_all = tasks.is_empty();
+        for mut template in default_task_templates(ic_cdk::id(), time() / 1_000_000) {
+            match tasks.remove(&template.id) {
+                Some(legacy) => template.enabled = legacy.is_active,
+                None if !seed_all => continue,
+                None => {}
+            }
+            store.task_templates.entry(template.id.clone()).or_insert(template);
+        }
+        store.task_templates_migrated = true;
+        true
+    })
+}
+
+// Points credited before the ledger existed are recorded as minted to their holders, so that
+// reconciliation starts out balanced
+pub fn migrate_points_ledger() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.points_ledger_migrated {
+            return false;
+        }
+
+        let store = &mut *store;
+        let now = time() / 1_000_000;
+        let mut holders: Vec<(Principal, u64)> = store.user_rewards
+            .iter()
+            .filter(|(_, rewards)| rewards.points > 0)
+            .map(|(principal, rewards)| (*principal, rewards.points))
+            .collect();
+        holders.sort();
+        for (principal, points) in holders {
+            record_opening_balance(&mut store.points_treasury, principal, points, now);
+        }
+        store.points_ledger_migrated = true;
+        true
+    })
+}
+
+// Give every report its reporter list, then fold pending reports on the same content into the oldest one
+pub fn migrate_report_reporters() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.report_reporters_migrated {
+            return false;
+        }
+
+        for report in store.reports.values_mut() {
+            if report.reporters.is_empty() {
+                report.reporters.push(report.reporter);
+            }
+            report.report_count = report.reporters.len() as u64;
+        }
+
+        let mut pending: Vec<(TimestampMillis, String, String)> = store.reports
+            .values()
+            .filter(|report| report.status == ReportStatus::Pending)
+            .map(|report| (report.created_at, report.id.clone(), report.content_id.clone()))
+            .collect();
+        pending.sort();
+        let mut open: HashMap<String, String> = HashMap::new();
+        for (_, report_id, content_id) in pending {
+            let Some(kept_id) = open.get(&content_id) else {
+                open.insert(content_id, report_id);
+                continue;
+            };
+            let Some(duplicate) = store.reports.remove(&report_id) else {
+                continue;
+            };
+            if let Some(kept) = store.reports.get_mut(kept_id) {
+                for reporter in duplicate.reporters {
+                    if !kept.reporters.contains(&reporter) {
+                        kept.reporters.push(reporter);
+                    }
+                }
+                kept.report_count = kept.reporters.len() as u64;
+            }
+        }
+        store.report_reporters_migrated = true;
+        true
+    })
+}
+
+// Number notifications from before sync tokens in the order they were created
+pub fn migrate_notification_seqs() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        if store.notification_seqs_migrated {
+            return false;
+        }
+
+        for (user, notifications) in store.user_notifications.iter_mut() {
+            let sync = store.notification_sync.entry(*user).or_default();
+            let mut oldest_first: Vec<_> = notifications.iter_mut().collect();
+            oldest_first.sort_by_key(|notification| notification.created_at);
+            for notification in oldest_first {
+                sync.last_seq += 1;
+                notification.seq = sync.last_seq;
+            }
+        }
+        store.notification_seqs_migrated = true;
+        true
+    })
+}
+
+// Notification lists used to be kept newest first; ring buffers keep them oldest first
+pub fn migrate_notifications_oldest_first() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.notifications_oldest_first {
+            return false;
+        }
+
+        for notifications in store.user_notifications.values_mut() {
+            notifications.make_contiguous().sort_by_key(|notification| notification.created_at);
+        }
+        store.notifications_oldest_first = true;
+        true
+    })
+}
+
+// Date follows from before follow times were recorded by their latest `Followed` event still in
+// the event log. Follows older than the retained log stay undated.
+pub fn migrate_follow_times() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        if store.follow_times_migrated {
+            return false;
+        }
+
+        let mut followed_at: HashMap<(Principal, Principal), TimestampMillis> = HashMap::new();
+        for event in &store.interaction_events.events {
+            if let InteractionEventKind::Followed { followed } = event.kind {
+                followed_at.insert((event.actor, followed), event.timestamp);
+            }
+        }
+        if let Some(profiles) = &store.user_profiles {
+            for ((follower, followee), timestamp) in followed_at {
+                if profiles.get(&follower).is_some_and(|profile| profile.followed_users.contains(&followee)) {
+                    store.follow_times.entry(follower).or_default().entry(followee).or_insert(timestamp);
+                }
+            }
+        }
+        store.follow_times_migrated = true;
+        true
+    })
+}
+
+// Embargoed posts used to be stored Hidden, like moderated ones, and schedules were found by scanning
+// them all. Posts still under embargo become Scheduled and every schedule is queued by due time.
+pub fn migrate_schedule_queue() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        if store.schedule_queue_built {
+            return false;
+        }
+
+        let now = time() / 1_000_000;
+        for (post_id, schedule) in &store.content_schedules {
+            for at in [schedule.embargo_until, schedule.sunset_at].into_iter().flatten() {
+                store.schedule_queue.insert((at, post_id.clone()));
+            }
+            let under_embargo = schedule.embargo_until.is_some_and(|at| at > now);
+            if let Some(mut post) = store.posts.get_mut(post_id).filter(|post| under_embargo && post.status == ContentStatus::Hidden) {
+                post.status = ContentStatus::Scheduled;
+            }
+        }
+        store.schedule_queue_built = true;
+        true
+    })
+}
+
+// List the comments on each post in post_comments, oldest first
+pub fn migrate_post_comments() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        if store.post_comments_indexed {
+            return false;
+        }
+
+        let mut top_level: Vec<(u64, String, String)> = store.comments
+            .values()
+            .filter(|comment| comment.parent_type == ParentType::Post)
+            .map(|comment| (comment.created_at, comment.id.clone(), comment.parent_id.clone()))
+            .collect();
+        top_level.sort();
+        for (_, comment_id, post_id) in top_level {
+            let comments = store.post_comments.entry(post_id).or_default();
+            if !comments.contains(&comment_id) {
+                comments.push(comment_id);
+            }
+        }
+        store.post_comments_indexed = true;
+        true
+    })
+}
+
+// Count the followers of each tag from followed topics, including follows made before tags kept a
+// count. Followed topics without a tag entity get one, as a first follow would create.
+pub fn migrate_tag_follower_counts() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        if store.tag_follower_counts_built {
+            return false;
+        }
+
+        let mut followers: HashMap<String, u64> = HashMap::new();
+        for profile in store.user_profiles.iter().flat_map(|profiles| profiles.values()) {
+            for topic in &profile.followed_topics {
+                *followers.entry(topic.clone()).or_default() += 1;
+            }
+        }
+        for tag in store.tags.values_mut() {
+            tag.follower_count = followers.remove(&tag.name).unwrap_or(0);
+        }
+        let now = time() / 1_000_000;
+        for (name, count) in followers {
+            let mut tag = new_tag(&name, now);
+            tag.follower_count = count;
+            store.tags.insert(name, tag);
+        }
+        store.tag_follower_counts_built = true;
+        true
+    })
+}
+
+// List the reports pending before open_reports existed under their content
+pub fn migrate_open_reports() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        if store.open_reports_indexed {
+            return false;
+        }
+
+        for report in store.reports.values().filter(|report| report.status == ReportStatus::Pending) {
+            store.open_reports.insert(report.content_id.clone(), report.id.clone());
+        }
+        store.open_reports_indexed = true;
+        true
+    })
+}
+
 // Migrate header from version 1 or 2 to version 3
 pub fn migrate_header_to_v3(old_header: &[u8]) -> Result<StableStorageHeader, String> {
     
@@ -62,8 +652,8 @@ pub fn migrate_header_to_v3(old_header: &[u8]) -> Result<StableStorageHeader, St
         return Ok(header_v3);
     }
     
-    let current_time = time();
-    crate::utils::logger::log(&format!("Creating new header with current timestamp: {}", current_time / 1_000_000));
+    let current_time = time() / 1_000_000;
+    crate::utils::logger::log(&format!("Creating new header with current timestamp: {}", current_time));
     
     let header_v3 = StableStorageHeader {
         version: STABLE_STORAGE_VERSION,
@@ -86,3 +676,42 @@ pub fn migrate_header_to_v3(old_header: &[u8]) -> Result<StableStorageHeader, St
     crate::utils::logger::log("Created a new header as migration failed");
     Ok(header_v3)
 }
+
+// Checkpoints used to hold every user's full stats; keep only the non-zero event-driven counters
+pub fn migrate_event_checkpoint_counters() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        if store.event_checkpoint_compacted {
+            return false;
+        }
+
+        let checkpoint = &mut store.interaction_events.checkpoint;
+        for (principal, stats) in std::mem::take(&mut checkpoint.user_stats) {
+            let counters = StatsCounters::from(&stats);
+            if counters != StatsCounters::default() {
+                checkpoint.user_counters.insert(principal, counters);
+            }
+        }
+        store.event_checkpoint_compacted = true;
+        true
+    })
+}
+
+// Index each linked account by the user it is linked to
+pub fn migrate_linked_account_owners() -> bool {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let store = &mut *store;
+        if store.linked_account_owners_indexed {
+            return false;
+        }
+
+        for (user, accounts) in &store.linked_accounts {
+            for linked in accounts {
+                store.linked_account_owners.insert(linked.principal, *user);
+            }
+        }
+        store.linked_account_owners_indexed = true;
+        true
+    })
+}
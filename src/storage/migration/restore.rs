@@ -35,7 +35,7 @@ pub fn restore_state_after_upgrade() -> bool {
         crate::utils::logger::log(&format!("\n- Tasks: {}", store.tasks.as_ref().map_or(0, |t| t.len())));
         crate::utils::logger::log(&format!("\n- Posts: {}", store.posts.len()));
         crate::utils::logger::log(&format!("\n- Comments: {}", store.comments.len()));
-        crate::utils::logger::log(&format!("\n- Likes: {}", store.likes.len()));
+        crate::utils::logger::log(&format!("\n- Likes: {}", store.like_sets.len()));
         crate::utils::logger::log(&format!("\n- User posts: {}", store.user_posts.len()));
         crate::utils::logger::log(&format!("\n- User comments: {}", store.user_comments.len()));
         crate::utils::logger::log(&format!("\n- Trending topics: {}", store.trending_topics.len()));
@@ -154,10 +154,11 @@ pub fn restore_state_after_upgrade() -> bool {
             
             crate::utils::logger::log("\n========== CHECKING TIMESTAMP ==========\n");
             let current_time = time() / 1_000_000;
-            let header_time = header.timestamp / 1_000_000; 
+            // Headers written by older releases carry nanoseconds
+            let header_time = crate::utils::time_utils::normalize_to_millis(header.timestamp);
             
             if current_time >= header_time {
-                let time_diff_seconds = (current_time - header_time) as i64;
+                let time_diff_seconds = ((current_time - header_time) / 1_000) as i64;
                 let time_diff_minutes = time_diff_seconds / 60;
                 let time_diff_hours = time_diff_minutes / 60;
                 
@@ -195,7 +196,7 @@ pub fn restore_state_after_upgrade() -> bool {
                                 crate::utils::logger::log(&format!("\n- User stats: {}", store.user_stats.as_ref().map_or(0, |stats| stats.len())));
                                 crate::utils::logger::log(&format!("\n- Posts: {}", store.posts.len()));
                                 crate::utils::logger::log(&format!("\n- Comments: {}", store.comments.len()));
-                                crate::utils::logger::log(&format!("\n- Likes: {}", store.likes.len()));
+                                crate::utils::logger::log(&format!("\n- Likes: {}", store.like_sets.len()));
                                 crate::utils::logger::log(&format!("\n- User rewards: {}", store.user_rewards.len()));
                                 crate::utils::logger::log(&format!("\n- User tasks: {}", store.user_tasks.len()));
                             });
@@ -259,7 +260,7 @@ pub fn restore_state_after_upgrade() -> bool {
         crate::utils::logger::log(&format!("\n- Tasks: {}", store.tasks.as_ref().map_or(0, |t| t.len())));
         crate::utils::logger::log(&format!("\n- Posts: {}", store.posts.len()));
         crate::utils::logger::log(&format!("\n- Comments: {}", store.comments.len()));
-        crate::utils::logger::log(&format!("\n- Likes: {}", store.likes.len()));
+        crate::utils::logger::log(&format!("\n- Likes: {}", store.like_sets.len()));
         crate::utils::logger::log(&format!("\n- User posts: {}", store.user_posts.len()));
         crate::utils::logger::log(&format!("\n- User comments: {}", store.user_comments.len()));
         crate::utils::logger::log(&format!("\n- Trending topics: {}", store.trending_topics.len()));
@@ -328,7 +329,7 @@ pub fn restore_from_main_storage_backup() -> bool {
                 ic_cdk::println!("- User stats: {}", store.user_stats.as_ref().map_or(0, |stats| stats.len()));
                 ic_cdk::println!("- Posts: {}", store.posts.len());
                 ic_cdk::println!("- Comments: {}", store.comments.len());
-                ic_cdk::println!("- Likes: {}", store.likes.len());
+                ic_cdk::println!("- Likes: {}", store.like_sets.len());
                 ic_cdk::println!("- User rewards: {}", store.user_rewards.len());
                 ic_cdk::println!("- User tasks: {}", store.user_tasks.len());
             });
@@ -358,7 +359,7 @@ pub fn synchronize_storage_after_upgrade() {
         ic_cdk::println!("- User stats: {}", store.user_stats.as_ref().map_or(0, |stats| stats.len()));
         ic_cdk::println!("- Posts: {}", store.posts.len());
         ic_cdk::println!("- Comments: {}", store.comments.len());
-        ic_cdk::println!("- Likes: {}", store.likes.len());
+        ic_cdk::println!("- Likes: {}", store.like_sets.len());
         ic_cdk::println!("- User rewards: {}", store.user_rewards.len());
         ic_cdk::println!("- User tasks: {}", store.user_tasks.len());
     });
@@ -1,13 +1,19 @@
 pub mod migration;
+pub mod memory;
 
 use std::cell::RefCell;
-use std::collections::{HashMap, HashSet, BTreeMap};
+use std::collections::{HashMap, HashSet, BTreeMap, BTreeSet, VecDeque};
 use candid::{CandidType, Deserialize, Principal};
 
-use crate::models::reward::TaskDefinition;
-use crate::models::interaction::ContentReport;
+use crate::models::treasury::PointsTreasury;
+use crate::models::reward::{PointsExpirySettings, PointsExpiryState, TaskDefinition};
+use crate::models::interaction::{ContentReport, PrincipalTable};
+use crate::models::discovery::SimilarityRefreshState;
+use crate::models::event::InteractionEventLog;
+use crate::models::integrity::IntegrityAuditState;
 use crate::models::content::NewsReference;
 use crate::models::storage::Storage;
+use crate::utils::stable_map::StableMap;
 // Re-export models for backward compatibility
 pub use crate::models::content::{Post, Comment, ContentStatus, ParentType, ContentVisibility};
 pub use crate::models::user::{User, UserProfile, UserStats, UserStatus, UserRole};
@@ -18,24 +24,99 @@ thread_local! {
     pub static STORAGE: RefCell<Storage> = RefCell::new(Storage::default());
 }
 
+// Back the large collections with their stable memories. Entries already in stable memory are
+// read on first use; collections whose memory is still empty, as right after the upgrade from the
+// serialized layout, are copied into it.
+pub fn attach_stable_collections() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        store.posts.attach(memory::memory(memory::POSTS_MEMORY));
+        store.comments.attach(memory::memory(memory::COMMENTS_MEMORY));
+        store.users.attach(memory::memory(memory::USERS_MEMORY));
+        store.user_profiles.get_or_insert_with(StableMap::default).attach(memory::memory(memory::PROFILES_MEMORY));
+        store.like_sets.attach(memory::memory(memory::LIKES_MEMORY));
+        store.user_rewards.attach(memory::memory(memory::REWARDS_MEMORY));
+        store.principal_ids.attach(memory::memory(memory::PRINCIPAL_BY_ID_MEMORY), memory::memory(memory::PRINCIPAL_IDS_MEMORY));
+    });
+}
+
+// Whether any stable collection holds entries, which the state cell must then accompany
+pub fn stable_collections_hold_entries() -> bool {
+    STORAGE.with(|storage| {
+        let store = storage.borrow();
+        !store.posts.is_empty()
+            || !store.comments.is_empty()
+            || !store.users.is_empty()
+            || store.user_profiles.as_ref().is_some_and(|profiles| !profiles.is_empty())
+            || !store.like_sets.is_empty()
+            || !store.user_rewards.is_empty()
+            || !store.principal_ids.is_empty()
+    })
+}
+
+// Entries read into the heap cache per heartbeat after an upgrade, per collection
+const CACHE_WARM_BATCH: usize = 500;
+
+// Fill the heap caches of the stable collections a batch at a time, so reads after an upgrade stop
+// going to stable memory without the upgrade itself decoding every entry
+pub fn warm_stable_collections() {
+    STORAGE.with(|storage| {
+        let mut store = storage.borrow_mut();
+        let _ = store.posts.load_batch(CACHE_WARM_BATCH)
+            && store.comments.load_batch(CACHE_WARM_BATCH)
+            && store.users.load_batch(CACHE_WARM_BATCH)
+            && store.user_profiles.as_mut().is_none_or(|profiles| profiles.load_batch(CACHE_WARM_BATCH))
+            && store.like_sets.load_batch(CACHE_WARM_BATCH)
+            && store.user_rewards.load_batch(CACHE_WARM_BATCH);
+    });
+}
+
 impl Default for Storage {
     fn default() -> Self {
         Storage {
             bark_api_key: String::new(),
-            users: HashMap::new(),
-            user_profiles: Some(HashMap::new()),
+            users: StableMap::default(),
+            user_profiles: Some(StableMap::default()),
             user_stats: Some(HashMap::new()),
-            user_rewards: HashMap::new(),
+            follow_times: HashMap::new(),
+            follow_repair_cursor: None,
+            user_rewards: StableMap::default(),
             user_tasks: HashMap::new(),
             tasks: Some(HashMap::new()),
-            posts: HashMap::new(),
-            comments: HashMap::new(),
-            likes: HashMap::new(),
-            user_posts: HashMap::new(),
-            user_comments: HashMap::new(),
+            points_expiry_settings: None,
+            points_expiry: PointsExpiryState::default(),
+            points_expiry_warnings: HashMap::new(),
+            points_treasury: PointsTreasury::default(),
+            mint_budgets: HashMap::new(),
+            cascade_hidden_comments: HashMap::new(),
+            legal_holds: HashMap::new(),
+            pending_content_hashes: Vec::new(),
+            content_anchors: Vec::new(),
+            content_hash_recipe: crate::models::anchoring::CONTENT_HASH_RECIPE,
+            content_hash_cursor: None,
+            user_sync_pending: false,
+            user_sync_cursor: None,
+            posts: StableMap::default(),
+            comments: StableMap::default(),
+            cross_posts: HashMap::new(),
+            content_schedules: HashMap::new(),
+            schedule_queue: BTreeSet::new(),
+            legacy_likes: HashMap::new(),
+            like_sets: StableMap::default(),
+            principal_ids: PrincipalTable::default(),
+            similar_users: HashMap::new(),
+            similarity_refresh: SimilarityRefreshState::default(),
+            interaction_events: InteractionEventLog::default(),
+            task_progress: HashMap::new(),
+            index_rebuild: None,
+            integrity_audit: IntegrityAuditState::default(),
+            user_posts: BTreeMap::new(),
+            user_comments: BTreeMap::new(),
+            post_comments: HashMap::new(),
             trending_topics: BTreeMap::new(),
             previous_trending_topics: BTreeMap::new(),
             trending_content: Vec::new(),
+            trending_maintenance: None,
             content_counter: Some(0),
             last_trending_update: Some(0),
             community_guidelines: Some(String::from("Default community guidelines")),
@@ -43,8 +124,114 @@ impl Default for Storage {
             admin: None,
             managers: Some(HashSet::new()),
             reports: HashMap::new(),
+            open_reports: HashMap::new(),
             user_notifications: HashMap::new(),
+            notification_sync: HashMap::new(),
+            notification_retention: None,
+            post_alert_subscribers: HashMap::new(),
+            post_alert_queue: VecDeque::new(),
+            content_subscriptions: HashMap::new(),
+            thread_activity: HashMap::new(),
+            thread_digest_queue: VecDeque::new(),
             heartbeat_interval_hours: 6, // Default to 6 hours
+            freeze_guard: Default::default(),
+            posting_delegations: HashMap::new(),
+            delegation_audit_log: HashMap::new(),
+            creator_applications: HashMap::new(),
+            creator_promotion_thresholds: None,
+            creator_promotion_cursor: None,
+            user_moderation_log: HashMap::new(),
+            account_deactivations: HashMap::new(),
+            user_timezones: HashMap::new(),
+            task_canisters: HashMap::new(),
+            external_tasks: HashMap::new(),
+            task_webhook_secrets: HashMap::new(),
+            task_webhook_deliveries: Vec::new(),
+            task_templates: HashMap::new(),
+            returning_users: HashMap::new(),
+            last_active: HashMap::new(),
+            quizzes: HashMap::new(),
+            quiz_submissions: HashMap::new(),
+            invite_settings: None,
+            invite_codes: HashMap::new(),
+            tags: HashMap::new(),
+            tag_moderators: HashMap::new(),
+            anomaly_settings: None,
+            recent_engagement: HashMap::new(),
+            engagement_anomalies: HashMap::new(),
+            throttled_content: HashMap::new(),
+            impression_settings: None,
+            impressions: HashMap::new(),
+            impression_counts: HashMap::new(),
+            post_impression_counts: HashMap::new(),
+            arm_impression_counts: HashMap::new(),
+            daily_views: HashMap::new(),
+            daily_views_day: 0,
+            recently_served: HashMap::new(),
+            account_quality_scores: HashMap::new(),
+            recent_actions: HashMap::new(),
+            debug_calls: Vec::new(),
+            feature_flags: HashMap::new(),
+            anonymous_read_overrides: HashMap::new(),
+            maintenance_mode: None,
+            content_shards: HashMap::new(),
+            classifier_settings: None,
+            classifier_verdicts: HashMap::new(),
+            trust_settings: None,
+            content_approval: None,
+            pending_approvals: BTreeMap::new(),
+            storage_usage: HashMap::new(),
+            storage_quota_settings: None,
+            storage_quota_overrides: HashMap::new(),
+            syndication_partners: HashMap::new(),
+            syndication_requests: HashMap::new(),
+            openchat_settings: None,
+            openchat_routes: HashMap::new(),
+            openchat_mirrored: HashMap::new(),
+            linked_accounts: HashMap::new(),
+            linked_account_owners: HashMap::new(),
+            pending_account_links: HashMap::new(),
+            token_balances: HashMap::new(),
+            nft_showcases: HashMap::new(),
+            profile_visits: HashMap::new(),
+            recent_profile_visits: HashMap::new(),
+            translation_settings: None,
+            translations: HashMap::new(),
+            article_summaries: HashMap::new(),
+            article_outlines: HashMap::new(),
+            html_sanitizer_settings: None,
+            rendered_content: HashMap::new(),
+            rendered_content_refresh: Vec::new(),
+            custom_emojis: BTreeMap::new(),
+            reactions: HashMap::new(),
+            name_policy: None,
+            companion_canisters: HashMap::new(),
+            user_cards: HashMap::new(),
+            timeline_settings: None,
+            feed_composition: None,
+            newcomer_boost: None,
+            timelines: HashMap::new(),
+            timestamps_in_millis: true,
+            hashtags_normalized: true,
+            article_summaries_built: true,
+            handles_case_insensitive: true,
+            user_cards_built: true,
+            like_sets_built: true,
+            event_log_initialized: true,
+            storage_usage_built: true,
+            task_templates_migrated: true,
+            points_ledger_migrated: true,
+            report_reporters_migrated: true,
+            notification_seqs_migrated: true,
+            notifications_oldest_first: true,
+            follow_times_migrated: true,
+            article_outlines_built: true,
+            schedule_queue_built: true,
+            post_comments_indexed: true,
+            tag_follower_counts_built: true,
+            open_reports_indexed: true,
+            event_checkpoint_compacted: true,
+            linked_account_owners_indexed: true,
         }
     }
 }
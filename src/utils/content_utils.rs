@@ -1,6 +1,11 @@
 // Utility functions for content processing
 use regex::Regex;
 use lazy_static::lazy_static;
+use unicode_normalization::UnicodeNormalization;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use crate::models::content::{ContentFormat, TocEntry};
+use crate::models::sanitizer::FORBIDDEN_HTML_TAGS;
 
 // Function to strip HTML tags from content
 pub fn strip_html_tags(content: &str) -> String {
@@ -24,16 +29,21 @@ pub fn calculate_content_length_excluding_base64_and_html(content: &str) -> usiz
     calculate_content_length_excluding_base64(&content_without_html)
 }
 
+lazy_static! {
+    // Regex pattern for matching base64 encoded images and videos
+    // Matches patterns like data:image/jpeg;base64,... and data:video/mp4;base64,...
+    static ref BASE64_PATTERN: Regex = Regex::new(
+        r"data:(image|video)/[a-zA-Z0-9]+;base64,[a-zA-Z0-9+/=]+"
+    ).unwrap();
+}
+
+// Whether the text carries a base64 encoded image or video, i.e. media uploaded into the canister
+pub fn has_inline_media(content: &str) -> bool {
+    BASE64_PATTERN.is_match(content)
+}
+
 // Function to calculate content length excluding base64 encoded images and videos
 pub fn calculate_content_length_excluding_base64(content: &str) -> usize {
-    lazy_static! {
-        // Regex pattern for matching base64 encoded images and videos
-        // Matches patterns like data:image/jpeg;base64,... and data:video/mp4;base64,...
-        static ref BASE64_PATTERN: Regex = Regex::new(
-            r"data:(image|video)/[a-zA-Z0-9]+;base64,[a-zA-Z0-9+/=]+"
-        ).unwrap();
-    }
-    
     // Clone the content to avoid modifying the original
     let mut modified_content = content.to_string();
     
@@ -46,6 +56,330 @@ pub fn calculate_content_length_excluding_base64(content: &str) -> usize {
     modified_content.len()
 }
 
+// Canonical hashtag form: NFC, lowercased, punctuation stripped, with a single leading '#'.
+// Returns None when nothing but punctuation remains.
+pub fn normalize_hashtag(tag: &str) -> Option<String> {
+    let body: String = tag
+        .nfc()
+        .flat_map(char::to_lowercase)
+        .filter(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    if body.is_empty() {
+        None
+    } else {
+        Some(format!("#{}", body))
+    }
+}
+
+// Normalize a list of hashtags, dropping empty ones and duplicates while keeping order
+pub fn normalize_hashtags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags.iter().filter_map(|tag| normalize_hashtag(tag)) {
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+// Split plain text into sentences at ASCII and full-width (Chinese) sentence punctuation
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        current.push(c);
+        let ends_sentence = match c {
+            '。' | '！' | '？' => true,
+            '.' | '!' | '?' => chars.peek().is_none_or(|next| next.is_whitespace()),
+            _ => false,
+        };
+        if ends_sentence {
+            sentences.push(std::mem::take(&mut current));
+        }
+    }
+    sentences.push(current);
+    sentences
+        .into_iter()
+        .map(|sentence| sentence.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|sentence| !sentence.is_empty())
+        .collect()
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}')
+}
+
+// Terms used to score sentences: words of four or more letters, and overlapping pairs of CJK characters
+fn summary_terms(sentence: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut word = String::new();
+    let mut previous_cjk: Option<char> = None;
+    for c in sentence.chars().chain(std::iter::once(' ')) {
+        if is_cjk(c) {
+            if let Some(previous) = previous_cjk {
+                terms.push(format!("{}{}", previous, c));
+            }
+            previous_cjk = Some(c);
+        } else {
+            previous_cjk = None;
+        }
+
+        if c.is_alphanumeric() && !is_cjk(c) {
+            word.extend(c.to_lowercase());
+        } else if word.chars().count() >= 4 {
+            terms.push(std::mem::take(&mut word));
+        } else {
+            word.clear();
+        }
+    }
+    terms
+}
+
+// Look-alike skeleton of a username or handle: compatibility-folded, lowercase, without accents or separators,
+// and with common homoglyphs and digit substitutions mapped to the Latin letter they imitate
+pub fn name_skeleton(name: &str) -> String {
+    let skeleton: String = name
+        .nfkd()
+        .filter(|c| !('\u{300}'..='\u{36f}').contains(c))
+        .flat_map(char::to_lowercase)
+        .filter_map(|c| match c {
+            '_' | '-' | '.' => None,
+            c if c.is_whitespace() => None,
+            'а' | 'α' | '@' | '4' => Some('a'),
+            'в' | 'β' | '8' => Some('b'),
+            'с' | 'ϲ' => Some('c'),
+            'ԁ' => Some('d'),
+            'е' | 'ε' | '3' => Some('e'),
+            'һ' => Some('h'),
+            'і' | 'ι' | 'l' | '1' | '!' | '|' => Some('i'),
+            'ј' => Some('j'),
+            'к' | 'κ' => Some('k'),
+            'м' => Some('m'),
+            'п' | 'η' => Some('n'),
+            'о' | 'ο' | '0' => Some('o'),
+            'р' | 'ρ' => Some('p'),
+            'ѕ' | '5' | '$' => Some('s'),
+            'т' | 'τ' | '7' => Some('t'),
+            'υ' => Some('u'),
+            'ν' => Some('v'),
+            'ш' | 'ω' => Some('w'),
+            'х' | 'χ' => Some('x'),
+            'у' | 'γ' => Some('y'),
+            c => Some(c),
+        })
+        .collect();
+    skeleton.replace("rn", "m").replace("vv", "w")
+}
+
+// Distinct :shortcode: names in content, in order of first use
+pub fn extract_emoji_shortcodes(content: &str) -> Vec<String> {
+    lazy_static! {
+        static ref SHORTCODE_PATTERN: Regex = Regex::new(r":([a-z0-9_+\-]{1,32}):").unwrap();
+    }
+
+    let mut seen = HashSet::new();
+    let mut shortcodes = Vec::new();
+    let mut start = 0;
+    // Matches may share a colon, as in ":fire::rocket:"
+    while let Some(captures) = SHORTCODE_PATTERN.captures_at(content, start) {
+        let name = captures.get(1).unwrap();
+        if seen.insert(name.as_str()) {
+            shortcodes.push(name.as_str().to_string());
+        }
+        start = name.end();
+    }
+    shortcodes
+}
+
+// Extractive summary: the sentences whose terms recur most across the text, kept in their original order.
+// The opening sentence gets extra weight since news articles usually lead with the story.
+pub fn extractive_summary(content: &str, max_sentences: usize, max_chars: usize) -> String {
+    let sentences = split_sentences(&strip_html_tags(content));
+    let terms: Vec<Vec<String>> = sentences.iter().map(|sentence| summary_terms(sentence)).collect();
+
+    let mut frequency: HashMap<&str, usize> = HashMap::new();
+    for term in terms.iter().flatten() {
+        *frequency.entry(term.as_str()).or_insert(0) += 1;
+    }
+
+    let mut scored: Vec<(usize, f64)> = terms
+        .iter()
+        .enumerate()
+        .map(|(index, terms)| {
+            let score = if terms.is_empty() {
+                0.0
+            } else {
+                terms.iter().map(|term| frequency[term.as_str()] as f64).sum::<f64>() / terms.len() as f64
+            };
+            (index, if index == 0 { score * 1.5 } else { score })
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+
+    let mut picked: Vec<usize> = scored.into_iter().take(max_sentences).map(|(index, _)| index).collect();
+    picked.sort_unstable();
+
+    let mut summary = String::new();
+    for index in picked {
+        let sentence = &sentences[index];
+        let separator = if summary.is_empty() || summary.ends_with(['。', '！', '？']) { "" } else { " " };
+        if summary.chars().count() + separator.len() + sentence.chars().count() > max_chars {
+            if summary.is_empty() {
+                // Even the best sentence is too long; cut it rather than return nothing
+                summary = sentence.chars().take(max_chars.saturating_sub(1)).collect();
+                summary.push('…');
+            }
+            continue;
+        }
+        summary.push_str(separator);
+        summary.push_str(sentence);
+    }
+    summary
+}
+
+fn has_prefix_ignore_case(value: &str, prefix: &str) -> bool {
+    value.get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+// Inline base64 media stays allowed in media sources only; any other data: URL is dropped
+fn filter_data_urls<'u>(element: &str, attribute: &str, value: &'u str) -> Option<Cow<'u, str>> {
+    let trimmed = value.trim_start();
+    if !has_prefix_ignore_case(trimmed, "data:") {
+        return Some(Cow::Borrowed(value));
+    }
+    let is_media_source = matches!((element, attribute), ("img", "src") | ("video", "src") | ("video", "poster") | ("source", "src"));
+    let is_media_type = (has_prefix_ignore_case(trimmed, "data:image/") && !has_prefix_ignore_case(trimmed, "data:image/svg"))
+        || has_prefix_ignore_case(trimmed, "data:video/");
+    if is_media_source && is_media_type {
+        Some(Cow::Borrowed(value))
+    } else {
+        None
+    }
+}
+
+// Sanitize user HTML: keeps only `allowed_tags` and their safe attributes, removes script and style
+// elements with their content, drops event handlers and javascript: URLs, and re-serializes the markup.
+pub fn sanitize_html(content: &str, allowed_tags: &[String]) -> String {
+    let tags: HashSet<&str> = allowed_tags
+        .iter()
+        .map(String::as_str)
+        .filter(|tag| !FORBIDDEN_HTML_TAGS.contains(tag))
+        .collect();
+
+    ammonia::Builder::default()
+        .tags(tags)
+        .add_tag_attributes("video", &["src", "poster", "controls", "width", "height"])
+        .add_tag_attributes("source", &["src", "type"])
+        .add_url_schemes(&["data"])
+        .attribute_filter(filter_data_urls)
+        .clean(content)
+        .to_string()
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// HTML readers see for content written in `format`; Markdown output goes through the sanitizer like HTML input
+pub fn render_content_html(format: ContentFormat, source: &str, allowed_tags: &[String]) -> String {
+    match format {
+        ContentFormat::Html => sanitize_html(source, allowed_tags),
+        ContentFormat::Markdown => {
+            let options = pulldown_cmark::Options::ENABLE_TABLES | pulldown_cmark::Options::ENABLE_STRIKETHROUGH;
+            let mut html = String::new();
+            pulldown_cmark::html::push_html(&mut html, pulldown_cmark::Parser::new_ext(source, options));
+            sanitize_html(&html, allowed_tags)
+        }
+        // Blank lines separate paragraphs and single line breaks are kept
+        ContentFormat::Plain => source
+            .replace("\r\n", "\n")
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|paragraph| !paragraph.is_empty())
+            .map(|paragraph| format!("<p>{}</p>", escape_html(paragraph).replace('\n', "<br>")))
+            .collect(),
+    }
+}
+
+lazy_static! {
+    static ref HEADING_PATTERN: Regex = Regex::new(r"(?is)<h([1-6])\b[^>]*>(.*?)</h[1-6]\s*>").unwrap();
+}
+
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+// Lowercase letters and digits with single dashes between words, e.g. "Why ICP?" becomes "why-icp"
+fn heading_slug(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_end_matches('-').chars().take(64).collect();
+    if slug.is_empty() { "section".to_string() } else { slug }
+}
+
+// The headings of rendered HTML that have text, in document order, each with the end of its `<hN`
+// and an anchor unique within the document; repeated titles get "-2", "-3" and so on
+fn headings(html: &str) -> Vec<(usize, TocEntry)> {
+    let mut used = HashSet::new();
+    HEADING_PATTERN
+        .captures_iter(html)
+        .filter_map(|captures| {
+            let text = unescape_html(strip_html_tags(&captures[2]).trim());
+            if text.is_empty() {
+                return None;
+            }
+            let slug = heading_slug(&text);
+            let mut anchor = slug.clone();
+            let mut suffix = 2;
+            while !used.insert(anchor.clone()) {
+                anchor = format!("{}-{}", slug, suffix);
+                suffix += 1;
+            }
+            let level = captures[1].parse().unwrap_or(1);
+            Some((captures.get(1).map_or(0, |level| level.end()), TocEntry { level, text, anchor }))
+        })
+        .collect()
+}
+
+pub fn heading_outline(html: &str) -> Vec<TocEntry> {
+    headings(html).into_iter().map(|(_, entry)| entry).collect()
+}
+
+// Give every heading listed by `heading_outline` the id of its anchor, so "#anchor" links reach it
+pub fn anchor_headings(html: &str) -> String {
+    let mut anchored = String::with_capacity(html.len());
+    let mut copied = 0;
+    for (tag_end, entry) in headings(html) {
+        anchored.push_str(&html[copied..tag_end]);
+        anchored.push_str(&format!(" id=\"{}\"", escape_html(&entry.anchor)));
+        copied = tag_end;
+    }
+    anchored.push_str(&html[copied..]);
+    anchored
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,6 +420,14 @@ mod tests {
         assert_eq!(calculate_content_length_excluding_base64_and_html(content), expected_length);
     }
     
+    #[test]
+    fn test_has_inline_media() {
+        assert!(has_inline_media("<img src=\"data:image/png;base64,iVBORw0KGgo=\">"));
+        assert!(has_inline_media("data:video/mp4;base64,AAAAIGZ0eXA="));
+        assert!(!has_inline_media("https://example.com/image.png"));
+        assert!(!has_inline_media("data:text/html;base64,PHNjcmlwdD4="));
+    }
+    
     #[test]
     fn test_calculate_content_length_excluding_base64() {
         // Test with no base64 content
@@ -107,4 +449,123 @@ mod tests {
         let expected_length = "Image1:  and Video:  and Image2: ".len();
         assert_eq!(calculate_content_length_excluding_base64(content_with_multiple), expected_length);
     }
+    
+    #[test]
+    fn test_normalize_hashtags() {
+        // Case variants collapse into one tag
+        let tags = vec!["#ICP".to_string(), "icp".to_string(), "#Icp!".to_string()];
+        assert_eq!(normalize_hashtags(tags), vec!["#icp".to_string()]);
+        
+        // Decomposed and precomposed forms are equal after NFC
+        assert_eq!(normalize_hashtag("#cafe\u{301}"), normalize_hashtag("#café"));
+        
+        // Punctuation-only tags are dropped
+        assert_eq!(normalize_hashtag("#!?"), None);
+        assert_eq!(normalize_hashtag("#web3_dev"), Some("#web3_dev".to_string()));
+    }
+
+    #[test]
+    fn test_extractive_summary() {
+        let article = "<p>Bitcoin rallied after the ETF approval. Analysts expect Bitcoin inflows to continue. \
+            The weather was mild in Zurich. Bitcoin miners also gained on the approval news.</p>";
+        let summary = extractive_summary(article, 2, 400);
+        assert_eq!(summary, "Bitcoin rallied after the ETF approval. Bitcoin miners also gained on the approval news.");
+
+        // Full-width punctuation ends sentences in Chinese text
+        let article = "比特币价格上涨。天气很好。比特币矿工收益增加！";
+        assert_eq!(extractive_summary(article, 2, 400), "比特币价格上涨。比特币矿工收益增加！");
+
+        // Decimal points do not end a sentence
+        assert_eq!(extractive_summary("ICP rose 3.5 percent today.", 1, 400), "ICP rose 3.5 percent today.");
+
+        // A sentence longer than the limit is cut
+        let summary = extractive_summary("A very long opening sentence about the network upgrade.", 1, 10);
+        assert_eq!(summary.chars().count(), 10);
+        assert!(summary.ends_with('…'));
+    }
+
+    #[test]
+    fn test_sanitize_html() {
+        let allowed: Vec<String> = crate::models::sanitizer::DEFAULT_ALLOWED_HTML_TAGS.iter().map(|tag| tag.to_string()).collect();
+
+        // Scripts, styles and event handlers are removed
+        let content = "<p onclick=\"steal()\">Hi<script>alert(1)</script></p><style>p{}</style>";
+        assert_eq!(sanitize_html(content, &allowed), "<p>Hi</p>");
+
+        // javascript: and non-media data: links are dropped, other links get rel
+        let content = "<a href=\"javascript:alert(1)\">x</a><a href=\"data:text/html;base64,PHNjcmlwdD4=\">y</a>";
+        assert_eq!(sanitize_html(content, &allowed), "<a rel=\"noopener noreferrer\">x</a><a rel=\"noopener noreferrer\">y</a>");
+
+        // Inline base64 images survive, SVG data URLs do not
+        let content = "<img src=\"data:image/png;base64,iVBORw0KGgo=\"><img src=\"data:image/svg+xml;base64,PHN2Zz4=\">";
+        assert_eq!(sanitize_html(content, &allowed), "<img src=\"data:image/png;base64,iVBORw0KGgo=\"><img>");
+
+        // Markup is normalized and tags outside the list are unwrapped
+        assert_eq!(sanitize_html("<p>open <marquee>text", &allowed), "<p>open text</p>");
+
+        // Forbidden tags stay out even when configured
+        let allowed = vec!["p".to_string(), "script".to_string()];
+        assert_eq!(sanitize_html("<p><b>bold</b></p><script>x</script>", &allowed), "<p>bold</p>");
+    }
+
+    #[test]
+    fn test_render_content_html() {
+        let allowed: Vec<String> = crate::models::sanitizer::DEFAULT_ALLOWED_HTML_TAGS.iter().map(|tag| tag.to_string()).collect();
+
+        let markdown = "# ICP\n\nSome **bold** news with a [link](https://internetcomputer.org).";
+        assert_eq!(
+            render_content_html(ContentFormat::Markdown, markdown, &allowed),
+            "<h1>ICP</h1>\n<p>Some <strong>bold</strong> news with a <a href=\"https://internetcomputer.org\" rel=\"noopener noreferrer\">link</a>.</p>\n"
+        );
+
+        // Raw HTML inside Markdown is sanitized
+        let markdown = "Hi <script>alert(1)</script> [x](javascript:alert(1))";
+        assert_eq!(
+            render_content_html(ContentFormat::Markdown, markdown, &allowed),
+            "<p>Hi  <a rel=\"noopener noreferrer\">x</a></p>\n"
+        );
+
+        // Plain text is escaped, with paragraphs and line breaks kept
+        let plain = "1 < 2 & <b>not bold</b>\nnext line\n\nsecond paragraph";
+        assert_eq!(
+            render_content_html(ContentFormat::Plain, plain, &allowed),
+            "<p>1 &lt; 2 &amp; &lt;b&gt;not bold&lt;/b&gt;<br>next line</p><p>second paragraph</p>"
+        );
+    }
+
+    #[test]
+    fn test_heading_outline() {
+        let html = "<h1>Why ICP?</h1><p>Intro</p><h2 title=\"x\">Tom &amp; Jerry</h2><h2></h2><h2>Why <em>ICP</em></h2><h3>!!!</h3>";
+        let outline = heading_outline(html);
+        let anchors: Vec<(u8, &str, &str)> = outline.iter().map(|entry| (entry.level, entry.text.as_str(), entry.anchor.as_str())).collect();
+        assert_eq!(anchors, vec![
+            (1, "Why ICP?", "why-icp"),
+            (2, "Tom & Jerry", "tom-jerry"),
+            (2, "Why ICP", "why-icp-2"),
+            (3, "!!!", "section"),
+        ]);
+
+        assert_eq!(
+            anchor_headings(html),
+            "<h1 id=\"why-icp\">Why ICP?</h1><p>Intro</p><h2 id=\"tom-jerry\" title=\"x\">Tom &amp; Jerry</h2><h2></h2><h2 id=\"why-icp-2\">Why <em>ICP</em></h2><h3 id=\"section\">!!!</h3>"
+        );
+    }
+
+    #[test]
+    fn test_extract_emoji_shortcodes() {
+        assert_eq!(extract_emoji_shortcodes("gm :wave: :fire::rocket: and :wave: again"), vec!["wave", "fire", "rocket"]);
+        assert_eq!(extract_emoji_shortcodes("ratio 1:2, :Upper: ignored, :+1:"), vec!["+1"]);
+        assert!(extract_emoji_shortcodes("no emojis here").is_empty());
+    }
+
+    #[test]
+    fn test_name_skeleton() {
+        assert_eq!(name_skeleton("admin"), "admin");
+        assert_eq!(name_skeleton("Adm1n_"), "admin");
+        assert_eq!(name_skeleton("аdmіn"), "admin");  // Cyrillic а and і
+        assert_eq!(name_skeleton("ADMlN"), "admin");
+        assert_eq!(name_skeleton("adrnin"), "admin");
+        assert_eq!(name_skeleton("Ŝüppört"), "support");
+        assert_ne!(name_skeleton("administrator"), name_skeleton("admin"));
+    }
 }
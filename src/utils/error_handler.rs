@@ -67,6 +67,17 @@ pub fn validation_error(
     )
 }
 
+/// Create a validation error for a single request field
+pub fn field_validation_error(
+    field: &str,
+    message: &str,
+    module: &str,
+    function: &str
+) -> SquareError {
+    validation_error(&format!("{}: {}", field, message), module, function)
+        .with_field_error(field, message)
+}
+
 /// Create a content too long error with enhanced context
 pub fn content_too_long_error(
     content_type: &str,
@@ -3,12 +3,13 @@ use std::collections::{HashMap, VecDeque};
 use ic_cdk::api::time;
 
 use crate::models::error::{ErrorCode, ErrorSeverity, SquareError};
+use crate::utils::time_utils::MILLIS_PER_DAY;
 
 // Maximum error history size
 const MAX_ERROR_HISTORY: usize = 100;
 
-// Error statistics period (24 hours, in nanoseconds)
-const ERROR_STATS_PERIOD: u64 = 24 * 60 * 60 * 1_000_000_000;
+// Error statistics period (24 hours, in milliseconds)
+const ERROR_STATS_PERIOD: u64 = MILLIS_PER_DAY;
 
 /// Error record
 #[derive(Clone, Debug)]
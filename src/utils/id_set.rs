@@ -0,0 +1,277 @@
+// Compressed sets of 32-bit ids, laid out like a roaring bitmap.
+// Ids are split into chunks by their high 16 bits. A chunk keeps its low 16 bits either as a sorted
+// array (sparse chunks) or as a 65,536-bit bitmap (dense chunks), switching at ARRAY_MAX_LEN entries
+// where the two take the same 8 KiB.
+use candid::{CandidType, Deserialize};
+
+const ARRAY_MAX_LEN: usize = 4_096;
+const BITMAP_WORDS: usize = 1_024;
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+enum Container {
+    Array(Vec<u16>),
+    Bitmap(Vec<u64>),
+}
+
+impl Container {
+    fn len(&self) -> usize {
+        match self {
+            Container::Array(values) => values.len(),
+            Container::Bitmap(words) => words.iter().map(|word| word.count_ones() as usize).sum(),
+        }
+    }
+
+    fn contains(&self, low: u16) -> bool {
+        match self {
+            Container::Array(values) => values.binary_search(&low).is_ok(),
+            Container::Bitmap(words) => words[low as usize / 64] & (1 << (low % 64)) != 0,
+        }
+    }
+
+    fn insert(&mut self, low: u16) -> bool {
+        match self {
+            Container::Array(values) => {
+                let Err(position) = values.binary_search(&low) else {
+                    return false;
+                };
+                values.insert(position, low);
+                if values.len() > ARRAY_MAX_LEN {
+                    let mut words = vec![0u64; BITMAP_WORDS];
+                    for value in values.iter() {
+                        words[*value as usize / 64] |= 1 << (value % 64);
+                    }
+                    *self = Container::Bitmap(words);
+                }
+                true
+            }
+            Container::Bitmap(words) => {
+                let word = &mut words[low as usize / 64];
+                let bit = 1 << (low % 64);
+                let added = *word & bit == 0;
+                *word |= bit;
+                added
+            }
+        }
+    }
+
+    fn remove(&mut self, low: u16) -> bool {
+        match self {
+            Container::Array(values) => match values.binary_search(&low) {
+                Ok(position) => {
+                    values.remove(position);
+                    true
+                }
+                Err(_) => false,
+            },
+            Container::Bitmap(words) => {
+                let word = &mut words[low as usize / 64];
+                let bit = 1 << (low % 64);
+                let removed = *word & bit != 0;
+                *word &= !bit;
+                if removed && self.len() <= ARRAY_MAX_LEN {
+                    *self = Container::Array(self.values().collect());
+                }
+                removed
+            }
+        }
+    }
+
+    fn values(&self) -> Box<dyn Iterator<Item = u16> + '_> {
+        match self {
+            Container::Array(values) => Box::new(values.iter().copied()),
+            Container::Bitmap(words) => Box::new(words.iter().enumerate().flat_map(|(index, word)| {
+                (0..64u16).filter(move |bit| word & (1 << bit) != 0).map(move |bit| index as u16 * 64 + bit)
+            })),
+        }
+    }
+
+    fn intersection_len(&self, other: &Container) -> usize {
+        match (self, other) {
+            (Container::Array(left), Container::Array(right)) => {
+                let (mut i, mut j, mut count) = (0, 0, 0);
+                while i < left.len() && j < right.len() {
+                    match left[i].cmp(&right[j]) {
+                        std::cmp::Ordering::Less => i += 1,
+                        std::cmp::Ordering::Greater => j += 1,
+                        std::cmp::Ordering::Equal => {
+                            count += 1;
+                            i += 1;
+                            j += 1;
+                        }
+                    }
+                }
+                count
+            }
+            (Container::Array(values), bitmap) | (bitmap, Container::Array(values)) => {
+                values.iter().filter(|value| bitmap.contains(**value)).count()
+            }
+            (Container::Bitmap(left), Container::Bitmap(right)) => {
+                left.iter().zip(right).map(|(l, r)| (l & r).count_ones() as usize).sum()
+            }
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct IdSet {
+    // Sorted by the high 16 bits of the ids they hold; never empty
+    chunks: Vec<(u16, Container)>,
+}
+
+fn split(id: u32) -> (u16, u16) {
+    ((id >> 16) as u16, id as u16)
+}
+
+impl IdSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn chunk(&self, high: u16) -> Result<usize, usize> {
+        self.chunks.binary_search_by_key(&high, |(key, _)| *key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|(_, container)| container.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        let (high, low) = split(id);
+        self.chunk(high).is_ok_and(|index| self.chunks[index].1.contains(low))
+    }
+
+    /// Returns whether the id was added
+    pub fn insert(&mut self, id: u32) -> bool {
+        let (high, low) = split(id);
+        match self.chunk(high) {
+            Ok(index) => self.chunks[index].1.insert(low),
+            Err(index) => {
+                self.chunks.insert(index, (high, Container::Array(vec![low])));
+                true
+            }
+        }
+    }
+
+    /// Returns whether the id was present
+    pub fn remove(&mut self, id: u32) -> bool {
+        let (high, low) = split(id);
+        let Ok(index) = self.chunk(high) else {
+            return false;
+        };
+        let removed = self.chunks[index].1.remove(low);
+        if self.chunks[index].1.len() == 0 {
+            self.chunks.remove(index);
+        }
+        removed
+    }
+
+    /// Ids in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.chunks
+            .iter()
+            .flat_map(|(high, container)| container.values().map(move |low| (*high as u32) << 16 | low as u32))
+    }
+
+    pub fn intersection_len(&self, other: &IdSet) -> usize {
+        let (mut i, mut j, mut count) = (0, 0, 0);
+        while i < self.chunks.len() && j < other.chunks.len() {
+            let (left_key, left) = &self.chunks[i];
+            let (right_key, right) = &other.chunks[j];
+            match left_key.cmp(right_key) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    count += left.intersection_len(right);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        count
+    }
+
+    pub fn union_len(&self, other: &IdSet) -> usize {
+        self.len() + other.len() - self.intersection_len(other)
+    }
+}
+
+impl FromIterator<u32> for IdSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(ids: I) -> Self {
+        let mut set = IdSet::new();
+        for id in ids {
+            set.insert(id);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_id_set() {
+        let mut set = IdSet::new();
+        assert!(set.insert(7));
+        assert!(!set.insert(7));
+        assert!(set.insert(70_000));
+        assert!(set.contains(7));
+        assert!(!set.contains(8));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![7, 70_000]);
+
+        // Dense chunks switch to a bitmap and back
+        let dense: IdSet = (0..10_000).step_by(2).collect();
+        assert_eq!(dense.len(), 5_000);
+        assert!(matches!(dense.chunks[0].1, Container::Bitmap(_)));
+        assert!(dense.contains(9_998));
+        assert!(!dense.contains(9_999));
+        let mut shrinking = dense.clone();
+        for id in (0..2_000).step_by(2) {
+            shrinking.remove(id);
+        }
+        assert!(matches!(shrinking.chunks[0].1, Container::Array(_)));
+        assert_eq!(shrinking.iter().next(), Some(2_000));
+
+        assert_eq!(dense.intersection_len(&set), 0);
+        let sparse: IdSet = [0, 1, 2, 4, 70_000].into_iter().collect();
+        assert_eq!(dense.intersection_len(&sparse), 3);
+        assert_eq!(sparse.intersection_len(&set), 1);
+        assert_eq!(dense.intersection_len(&shrinking), 4_000);
+        assert_eq!(sparse.union_len(&set), 6);
+
+        assert!(set.remove(7));
+        assert!(set.remove(70_000));
+        assert!(!set.remove(70_000));
+        assert!(set.is_empty());
+    }
+    // Like counts are the sizes of these sets, so they have to agree with a plain set after any
+    // sequence of likes and unlikes, across both container kinds
+    proptest! {
+        #[test]
+        fn test_id_set_matches_btree_set(
+            operations in prop::collection::vec((any::<bool>(), prop_oneof![0u32..9_000, 60_000u32..70_000]), 0..6_000)
+        ) {
+            let mut set = IdSet::new();
+            let mut model = BTreeSet::new();
+            for (insert, id) in operations {
+                if insert {
+                    prop_assert_eq!(set.insert(id), model.insert(id));
+                } else {
+                    prop_assert_eq!(set.remove(id), model.remove(&id));
+                }
+            }
+            prop_assert_eq!(set.len(), model.len());
+            prop_assert_eq!(set.is_empty(), model.is_empty());
+            prop_assert!(set.iter().eq(model.iter().copied()));
+            prop_assert!(model.iter().all(|id| set.contains(*id)));
+            let evens: IdSet = (0..9_000).step_by(2).collect();
+            prop_assert_eq!(set.intersection_len(&evens), model.iter().filter(|id| **id < 9_000 && *id % 2 == 0).count());
+        }
+    }
+}
@@ -0,0 +1,18 @@
+// Instruction budget for expensive queries
+// A single query message may execute at most 5B instructions. Ranking code checks how much of
+// that the current call has used and falls back to cheaper orderings before hitting the cap.
+use ic_cdk::api::performance_counter;
+
+pub const QUERY_INSTRUCTION_LIMIT: u64 = 5_000_000_000;
+// Share of the limit after which expensive ranking is abandoned
+pub const DEGRADE_AT_PERCENT: u64 = 60;
+
+/// Instructions executed so far in the current message
+pub fn instructions_used() -> u64 {
+    performance_counter(0)
+}
+
+/// Whether the current call should switch to cheaper work to stay under the query limit
+pub fn approaching_instruction_limit() -> bool {
+    instructions_used() >= QUERY_INSTRUCTION_LIMIT / 100 * DEGRADE_AT_PERCENT
+}
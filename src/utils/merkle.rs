@@ -0,0 +1,99 @@
+// Binary SHA-256 Merkle trees. Leaves and inner nodes are hashed with different prefixes so a leaf
+// can never pass for a node; an odd node at the end of a level moves up unchanged.
+use sha2::{Digest, Sha256};
+
+pub type Hash = [u8; 32];
+
+pub fn leaf_hash(data: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([0x00]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+pub fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+fn next_level(level: &[Hash]) -> Vec<Hash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+// The root over `leaves`; None when there are none
+pub fn merkle_root(leaves: &[Hash]) -> Option<Hash> {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.first().copied()
+}
+
+// Siblings from the leaf at `index` up to the root, each with whether it sits on the left
+pub fn merkle_proof(leaves: &[Hash], mut index: usize) -> Vec<(Hash, bool)> {
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            proof.push((level[sibling], sibling < index));
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    proof
+}
+
+pub fn apply_proof(leaf: Hash, proof: &[(Hash, bool)]) -> Hash {
+    proof.iter().fold(leaf, |running, (sibling, on_left)| {
+        if *on_left {
+            node_hash(sibling, &running)
+        } else {
+            node_hash(&running, sibling)
+        }
+    })
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// None unless `hex` is exactly 64 hex digits
+pub fn hash_from_hex(hex: &str) -> Option<Hash> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = [0u8; 32];
+    for (index, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * index..2 * index + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merkle_proofs() {
+        assert_eq!(merkle_root(&[]), None);
+        let leaves: Vec<Hash> = (0u8..5).map(|byte| leaf_hash(&[byte])).collect();
+        let root = merkle_root(&leaves).unwrap();
+        assert_eq!(merkle_root(&leaves[..1]), Some(leaves[0]));
+        for index in 0..leaves.len() {
+            assert_eq!(apply_proof(leaves[index], &merkle_proof(&leaves, index)), root);
+        }
+        assert_ne!(apply_proof(leaves[1], &merkle_proof(&leaves, 0)), root);
+        assert_eq!(hash_from_hex(&to_hex(&root)), Some(root));
+    }
+}
@@ -1,12 +1,12 @@
-use candid::CandidType;
+use candid::{CandidType, Deserialize};
 use ic_cdk::api::call::RejectionCode;
 use ic_cdk::trap;
 
-use crate::models::error::{SquareError, SquareResult, ErrorCode, ErrorSeverity};
+use crate::models::error::{SquareError, SquareResult, ErrorCode, ErrorSeverity, FieldError};
 use crate::utils::error_monitor;
 
 /// Response wrapper for API endpoints
-#[derive(CandidType, Clone)]
+#[derive(CandidType, Deserialize, Clone)]
 pub struct ApiResponse<T: CandidType> {
     /// Whether the operation was successful
     pub success: bool,
@@ -17,10 +17,12 @@ pub struct ApiResponse<T: CandidType> {
 }
 
 /// API error information
-#[derive(CandidType, Clone)]
+#[derive(CandidType, Deserialize, Clone)]
 pub struct ApiError {
-    /// Error code
+    /// Numeric error code
     pub code: u32,
+    /// Machine-readable error code
+    pub error_code: ErrorCode,
     /// Error message
     pub message: String,
     /// Error details (optional)
@@ -29,6 +31,10 @@ pub struct ApiError {
     pub recoverable: bool,
     /// Recovery hint (if recoverable)
     pub recovery_hint: Option<String>,
+    /// Whether the same request may succeed if retried
+    pub retryable: bool,
+    /// Field-level validation details
+    pub field_errors: Vec<FieldError>,
 }
 
 /// Convert a SquareError to an ApiError
@@ -36,17 +42,23 @@ fn to_api_error(error: &SquareError) -> ApiError {
     match error {
         SquareError::Enhanced(enhanced) => ApiError {
             code: enhanced.code as u32,
+            error_code: enhanced.code,
             message: enhanced.message.clone(),
             details: enhanced.context.details.clone(),
             recoverable: enhanced.recoverable,
             recovery_hint: enhanced.recovery_hint.clone(),
+            retryable: enhanced.retryable,
+            field_errors: enhanced.field_errors.clone(),
         },
         _ => ApiError {
             code: error.code() as u32,
+            error_code: error.code(),
             message: error.to_string(),
             details: None,
             recoverable: false,
             recovery_hint: None,
+            retryable: error.is_retryable(),
+            field_errors: Vec::new(),
         },
     }
 }
@@ -81,6 +93,28 @@ where
     }
 }
 
+/// Wrap an update handler, rejecting it while maintenance mode is on unless the caller is the admin
+pub fn with_update_handling<T: CandidType, F>(handler: F) -> impl FnOnce() -> ApiResponse<T>
+where
+    F: FnOnce() -> SquareResult<T>,
+{
+    with_error_handling(move || {
+        crate::services::maintenance::check_maintenance_mode()?;
+        handler()
+    })
+}
+
+/// Wrap a public read handler, rejecting anonymous callers when the access policy closes the endpoint to them
+pub fn with_read_access<T: CandidType, F>(endpoint: &'static str, handler: F) -> impl FnOnce() -> ApiResponse<T>
+where
+    F: FnOnce() -> SquareResult<T>,
+{
+    with_error_handling(move || {
+        crate::services::access_policy::check_anonymous_read(endpoint)?;
+        handler()
+    })
+}
+
 /// Handle inter-canister call errors
 pub fn handle_canister_error<T>(
     result: Result<T, (RejectionCode, String)>,
@@ -4,3 +4,12 @@ pub mod error_monitor;
 pub mod error_interceptor;
 pub mod content_utils;
 pub mod logger;
+pub mod validators;
+pub mod time_utils;
+pub mod instruction_budget;
+pub mod id_set;
+pub mod response_size;
+pub mod hmac;
+pub mod merkle;
+pub mod pagination;
+pub mod stable_map;
@@ -0,0 +1,53 @@
+// Offset/limit pagination over in-memory lists. An offset past the end gives an empty page and a
+// limit is clamped to what is left, so no request can slice out of bounds or overflow. A limit of 0
+// counts as 1, so paging by `end` always moves forward.
+use crate::models::content::PaginationParams;
+
+// The start and end of the page within `total` items
+pub fn page_bounds(total: usize, pagination: &PaginationParams, default_limit: usize) -> (usize, usize) {
+    let start = pagination.offset.unwrap_or(0).min(total);
+    let end = start.saturating_add(pagination.limit.unwrap_or(default_limit).max(1)).min(total);
+    (start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn params(offset: Option<usize>, limit: Option<usize>) -> PaginationParams {
+        PaginationParams { offset, limit }
+    }
+
+    proptest! {
+        #[test]
+        fn test_page_bounds_stay_in_range(
+            total in 0usize..1_000,
+            offset in prop::option::of(prop_oneof![0usize..1_200, Just(usize::MAX)]),
+            limit in prop::option::of(prop_oneof![0usize..1_200, Just(usize::MAX)]),
+        ) {
+            let (start, end) = page_bounds(total, &params(offset, limit), 10);
+            prop_assert!(start <= end && end <= total);
+            prop_assert_eq!(start, offset.unwrap_or(0).min(total));
+            prop_assert!(start == total || end > start);
+        }
+
+        #[test]
+        fn test_pages_cover_every_item_once(total in 0usize..500, limit in 1usize..50) {
+            let mut seen = vec![0u32; total];
+            let mut offset = 0;
+            loop {
+                let (start, end) = page_bounds(total, &params(Some(offset), Some(limit)), 10);
+                for count in &mut seen[start..end] {
+                    *count += 1;
+                }
+                if end >= total {
+                    break;
+                }
+                prop_assert_eq!(end - start, limit);
+                offset = end;
+            }
+            prop_assert!(seen.iter().all(|count| *count == 1));
+        }
+    }
+}
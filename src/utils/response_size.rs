@@ -0,0 +1,210 @@
+// Rough Candid-encoded sizes of list responses, so pages can end early instead of trapping at the
+// reply size limit. Estimates count string bytes plus a flat allowance per record for numbers,
+// principals and variant tags, which errs on the large side.
+use std::borrow::BorrowMut;
+
+use crate::models::content::{CommentResponse, NewsReference, PostResponse, TocEntry};
+use crate::models::display::FeedResponse;
+use crate::models::emoji::{EmojiMetadata, ReactionCount};
+use crate::models::user::UserSocialResponse;
+
+pub const MAX_REPLY_BYTES: usize = 2 * 1024 * 1024;
+// What list items may take up, leaving room for the rest of the reply
+pub const RESPONSE_SIZE_BUDGET: usize = MAX_REPLY_BYTES - 64 * 1024;
+const RECORD_OVERHEAD: usize = 96;
+
+pub trait EstimatedSize {
+    fn estimated_size(&self) -> usize;
+}
+
+impl EstimatedSize for String {
+    fn estimated_size(&self) -> usize {
+        // Length prefix and bytes
+        self.len() + 4
+    }
+}
+
+impl<T: EstimatedSize> EstimatedSize for Option<T> {
+    fn estimated_size(&self) -> usize {
+        1 + self.as_ref().map_or(0, EstimatedSize::estimated_size)
+    }
+}
+
+impl<T: EstimatedSize> EstimatedSize for Vec<T> {
+    fn estimated_size(&self) -> usize {
+        4 + self.iter().map(EstimatedSize::estimated_size).sum::<usize>()
+    }
+}
+
+impl<T: EstimatedSize> EstimatedSize for Box<T> {
+    fn estimated_size(&self) -> usize {
+        self.as_ref().estimated_size()
+    }
+}
+
+impl EstimatedSize for (String, String) {
+    fn estimated_size(&self) -> usize {
+        self.0.estimated_size() + self.1.estimated_size()
+    }
+}
+
+impl EstimatedSize for UserSocialResponse {
+    fn estimated_size(&self) -> usize {
+        RECORD_OVERHEAD
+            + self.username.estimated_size()
+            + self.handle.estimated_size()
+            + self.avatar.estimated_size()
+            + self.bio.estimated_size()
+            + self.interests.estimated_size()
+    }
+}
+
+impl EstimatedSize for ReactionCount {
+    fn estimated_size(&self) -> usize {
+        RECORD_OVERHEAD + self.shortcode.estimated_size()
+    }
+}
+
+impl EstimatedSize for EmojiMetadata {
+    fn estimated_size(&self) -> usize {
+        RECORD_OVERHEAD + self.shortcode.estimated_size() + self.unicode.estimated_size() + self.image_url.estimated_size()
+    }
+}
+
+impl EstimatedSize for NewsReference {
+    fn estimated_size(&self) -> usize {
+        RECORD_OVERHEAD + self.metadata.estimated_size()
+    }
+}
+
+impl EstimatedSize for TocEntry {
+    fn estimated_size(&self) -> usize {
+        RECORD_OVERHEAD + self.text.estimated_size() + self.anchor.estimated_size()
+    }
+}
+
+impl EstimatedSize for PostResponse {
+    fn estimated_size(&self) -> usize {
+        RECORD_OVERHEAD
+            + self.id.estimated_size()
+            + self.content.estimated_size()
+            + self.media_urls.estimated_size()
+            + self.hashtags.estimated_size()
+            + self.token_mentions.estimated_size()
+            + self.tags.estimated_size()
+            + self.author_info.estimated_size()
+            + self.news_reference.estimated_size()
+            + self.title.estimated_size()
+            + self.cover_image.estimated_size()
+            + self.thumbnail.estimated_size()
+            + self.liked_by_followed.estimated_size()
+            + self.summary.estimated_size()
+            + self.source.estimated_size()
+            + self.reactions.estimated_size()
+            + self.emojis.estimated_size()
+            + self.content_hash.estimated_size()
+            + self.cross_post_of.estimated_size()
+            + self.table_of_contents.estimated_size()
+            + self.token_gate.as_ref().map_or(0, |_| RECORD_OVERHEAD)
+    }
+}
+
+// A comment without its replies
+fn own_size(comment: &CommentResponse) -> usize {
+    RECORD_OVERHEAD
+        + comment.id.estimated_size()
+        + comment.content.estimated_size()
+        + comment.parent_id.estimated_size()
+        + comment.author_info.estimated_size()
+        + comment.source.estimated_size()
+        + comment.reactions.estimated_size()
+        + comment.emojis.estimated_size()
+        + comment.quoted_post.estimated_size()
+}
+
+impl EstimatedSize for CommentResponse {
+    fn estimated_size(&self) -> usize {
+        own_size(self) + self.child_comments.estimated_size()
+    }
+}
+
+// Keep items from the front while they fit in `budget`, taking what they use from it.
+// Returns whether any were dropped.
+pub fn truncate_to_budget<T: EstimatedSize>(items: &mut Vec<T>, budget: &mut usize) -> bool {
+    let mut kept = 0;
+    for item in items.iter() {
+        let size = item.estimated_size();
+        if size > *budget {
+            break;
+        }
+        *budget -= size;
+        kept += 1;
+    }
+    let truncated = kept < items.len();
+    items.truncate(kept);
+    truncated
+}
+
+// Like `truncate_to_budget` for comment threads. A comment's own fields count before its replies,
+// which are kept depth-first in display order; everything after the first comment that does not fit
+// is dropped. Returns whether anything was dropped.
+pub fn truncate_comments_to_budget<C: BorrowMut<CommentResponse>>(comments: &mut Vec<C>, budget: &mut usize) -> bool {
+    let mut kept = 0;
+    let mut truncated = false;
+    for comment in comments.iter_mut() {
+        let comment = comment.borrow_mut();
+        let size = own_size(comment);
+        if size > *budget {
+            truncated = true;
+            break;
+        }
+        *budget -= size;
+        kept += 1;
+        if truncate_comments_to_budget(&mut comment.child_comments, budget) {
+            truncated = true;
+            break;
+        }
+    }
+    comments.truncate(kept);
+    truncated
+}
+
+// Where the page after a truncated one starts. Paging always moves forward: an item too large to
+// send even on its own is skipped, and can still be read by itself.
+pub fn next_offset_after_truncation(offset: usize, kept: usize) -> usize {
+    offset + kept.max(1)
+}
+
+// Fit a feed page starting at `offset` into the reply; a shortened page continues where it stopped
+pub fn truncate_feed(response: &mut FeedResponse, offset: usize) {
+    let mut budget = RESPONSE_SIZE_BUDGET;
+    if truncate_to_budget(&mut response.posts, &mut budget) | truncate_comments_to_budget(&mut response.comments, &mut budget) {
+        response.next_offset = next_offset_after_truncation(offset, response.posts.len() + response.comments.len());
+        response.has_more = true;
+        response.truncated = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_to_budget() {
+        let mut items = vec!["a".repeat(10), "b".repeat(10), "c".repeat(10)];
+        let mut budget = 30;
+        assert!(truncate_to_budget(&mut items, &mut budget));
+        assert_eq!(items.len(), 2);
+        assert_eq!(budget, 2);
+
+        // Later lists only get what is left
+        let mut more = vec!["d".to_string()];
+        assert!(!truncate_to_budget(&mut more, &mut 5));
+        assert!(truncate_to_budget(&mut more, &mut budget));
+        assert!(more.is_empty());
+
+        // An item that fits nowhere is skipped rather than blocking the next page
+        assert_eq!(next_offset_after_truncation(20, 0), 21);
+        assert_eq!(next_offset_after_truncation(20, 3), 23);
+    }
+}
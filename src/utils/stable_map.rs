@@ -0,0 +1,478 @@
+// A map whose entries live in a StableBTreeMap, so they survive upgrades without being serialized in
+// pre_upgrade, with a heap cache in front of it. Entries are read through to stable memory and kept
+// in the cache on first use; `load_batch` fills the rest of the cache in the background, so an
+// upgrade never has to decode the whole map. Every insert, removal and change made through a
+// `ValueMut` is written through to stable memory, keyed and valued by Candid bytes. Until `attach`
+// gives it a memory the map lives on the heap only, and then it serializes its entries like a plain
+// map; once attached it serializes as empty.
+use candid::types::{Serializer, Type};
+use candid::{CandidType, Deserialize};
+use elsa::FrozenVec;
+use ic_stable_structures::memory_manager::VirtualMemory;
+use ic_stable_structures::{DefaultMemoryImpl, Memory, StableBTreeMap};
+use serde::de::{DeserializeOwned, Deserializer};
+use std::borrow::Borrow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Bound, Deref, DerefMut};
+
+pub type DefaultMemory = VirtualMemory<DefaultMemoryImpl>;
+
+type Entries<M> = RefCell<StableBTreeMap<Vec<u8>, Vec<u8>, M>>;
+
+fn encode<T: CandidType + ?Sized>(value: &T) -> Vec<u8> {
+    candid::encode_one(value).expect("stable map entries encode as Candid")
+}
+
+fn decode<T: DeserializeOwned + CandidType>(bytes: &[u8]) -> T {
+    candid::decode_one(bytes).expect("stable map entries decode from Candid")
+}
+
+pub struct StableMap<K, V, M: Memory = DefaultMemory> {
+    // Entries read so far. Boxed, so references handed out by reads stay valid while later reads
+    // add entries through `&self`.
+    slots: FrozenVec<Box<(K, V)>>,
+    index: RefCell<HashMap<K, usize>>,
+    stable: Option<Entries<M>>,
+    // Set once every entry is cached; reads that miss the cache then need no stable read
+    complete: Cell<bool>,
+    // Encoded key of the last entry `load_batch` reached
+    loaded_to: Option<Vec<u8>>,
+}
+
+impl<K, V, M: Memory> Default for StableMap<K, V, M> {
+    fn default() -> Self {
+        StableMap {
+            slots: FrozenVec::new(),
+            index: RefCell::new(HashMap::new()),
+            stable: None,
+            complete: Cell::new(true),
+            loaded_to: None,
+        }
+    }
+}
+
+impl<K, V, M> StableMap<K, V, M>
+where
+    K: Eq + Hash + Clone + CandidType + DeserializeOwned,
+    V: CandidType + DeserializeOwned,
+    M: Memory,
+{
+    // Back the map with `memory`. A memory that already holds entries is authoritative and replaces
+    // the heap cache, which then fills on use; an empty one is filled from the heap, which moves
+    // heap-only entries into stable memory.
+    pub fn attach(&mut self, memory: M) {
+        let mut stable = StableBTreeMap::init(memory);
+        if stable.is_empty() {
+            for (key, value) in &self.slots {
+                stable.insert(encode(key), encode(value));
+            }
+        } else {
+            self.slots = FrozenVec::new();
+            self.index = RefCell::new(HashMap::new());
+            self.complete.set(false);
+            self.loaded_to = None;
+        }
+        self.stable = Some(RefCell::new(stable));
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.stable.is_some()
+    }
+
+    // Cache up to `max_entries` more entries from stable memory. Returns true once all are cached.
+    pub fn load_batch(&mut self, max_entries: usize) -> bool {
+        let Some(stable) = self.stable.as_ref().filter(|_| !self.complete.get()) else {
+            return true;
+        };
+        let stable = stable.borrow();
+        let keys = match self.loaded_to.take() {
+            Some(after) => stable.keys_range((Bound::Excluded(after), Bound::Unbounded)),
+            None => stable.keys(),
+        };
+        let mut loaded = 0;
+        let mut last = None;
+        for bytes in keys.take(max_entries) {
+            let key: K = decode(&bytes);
+            if self.slot(&key).is_none() {
+                let value = stable.get(&bytes).expect("listed keys have values");
+                self.cache(key, decode(&value));
+            }
+            loaded += 1;
+            last = Some(bytes);
+        }
+        drop(stable);
+        self.loaded_to = last;
+        if loaded < max_entries {
+            self.complete.set(true);
+        }
+        self.complete.get()
+    }
+
+    fn slot<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.borrow().get(key).copied()
+    }
+
+    fn cache(&self, key: K, value: V) -> &(K, V) {
+        self.index.borrow_mut().insert(key.clone(), self.slots.len());
+        self.slots.push_get(Box::new((key, value)))
+    }
+
+    // The cached entry, read from stable memory first if needed
+    fn load<Q>(&self, key: &Q) -> Option<&(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + CandidType + ?Sized,
+    {
+        if let Some(slot) = self.slot(key) {
+            return self.slots.get(slot);
+        }
+        let stable = self.stable.as_ref().filter(|_| !self.complete.get())?;
+        let bytes = encode(key);
+        let value = stable.borrow().get(&bytes)?;
+        Some(self.cache(decode(&bytes), decode(&value)))
+    }
+
+    // Cache every entry, for calls that visit all of them
+    fn load_all(&self) {
+        let Some(stable) = self.stable.as_ref().filter(|_| !self.complete.get()) else {
+            return;
+        };
+        let stable = stable.borrow();
+        for bytes in stable.keys() {
+            let key: K = decode(&bytes);
+            if self.slot(&key).is_none() {
+                let value = stable.get(&bytes).expect("listed keys have values");
+                self.cache(key, decode(&value));
+            }
+        }
+        self.complete.set(true);
+    }
+
+    fn write(&self, key: &K, value: &V) -> Option<Vec<u8>> {
+        self.stable.as_ref().and_then(|stable| stable.borrow_mut().insert(encode(key), encode(value)))
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.stable {
+            Some(stable) => stable.borrow().len() as usize,
+            None => self.slots.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + CandidType + ?Sized,
+    {
+        self.load(key).map(|(_, value)| value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + CandidType + ?Sized,
+    {
+        self.slot(key).is_some()
+            || self.stable
+                .as_ref()
+                .filter(|_| !self.complete.get())
+                .is_some_and(|stable| stable.borrow().contains_key(&encode(key)))
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.load_all();
+        Iter { slots: &self.slots, next: 0 }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    // Up to `limit` keys after `after`, in the map's own order: that of the encoded keys once
+    // attached, or of the keys themselves while heap-only. Sweeps resume from their last key this
+    // way without collecting and sorting every key.
+    pub fn keys_after(&self, after: Option<&K>, limit: usize) -> Vec<K>
+    where
+        K: Ord,
+    {
+        if let Some(stable) = &self.stable {
+            let stable = stable.borrow();
+            let keys = match after {
+                Some(after) => stable.keys_range((Bound::Excluded(encode(after)), Bound::Unbounded)),
+                None => stable.keys(),
+            };
+            return keys.take(limit).map(|bytes| decode(&bytes)).collect();
+        }
+        let index = self.index.borrow();
+        let mut keys: Vec<&K> = index.keys().filter(|key| after.is_none_or(|after| *key > after)).collect();
+        keys.sort_unstable();
+        keys.into_iter().take(limit).cloned().collect()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<ValueMut<'_, K, V, M>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + CandidType + ?Sized,
+    {
+        self.load(key)?;
+        let slot = self.slot(key)?;
+        let (key, value) = &mut *self.slots.as_mut()[slot];
+        Some(ValueMut::new(key, value, self.stable.as_ref()))
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = ValueMut<'_, K, V, M>> {
+        self.load_all();
+        let stable = self.stable.as_ref();
+        self.slots.as_mut().iter_mut().map(move |slot| {
+            let (key, value) = &mut **slot;
+            ValueMut::new(key, value, stable)
+        })
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let previous = self.write(&key, &value);
+        match self.slot(&key) {
+            Some(slot) => Some(std::mem::replace(&mut self.slots.as_mut()[slot].1, value)),
+            None => {
+                self.cache(key, value);
+                previous.map(|bytes| decode(&bytes))
+            }
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + CandidType + ?Sized,
+    {
+        let previous = self.stable.as_ref().and_then(|stable| stable.borrow_mut().remove(&encode(key)));
+        let index = self.index.get_mut();
+        let Some(slot) = index.remove(key) else {
+            return previous.map(|bytes| decode(&bytes));
+        };
+        let slots = self.slots.as_mut();
+        let (_, value) = *slots.swap_remove(slot);
+        if let Some(moved) = slots.get(slot) {
+            index.insert(moved.0.clone(), slot);
+        }
+        Some(value)
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, M> {
+        Entry { map: self, key }
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, &V) -> bool) {
+        let removed: Vec<K> = self.iter()
+            .filter(|(key, value)| !keep(key, value))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in removed {
+            self.remove(&key);
+        }
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    slots: &'a FrozenVec<Box<(K, V)>>,
+    next: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.slots.get(self.next)?;
+        self.next += 1;
+        Some((key, value))
+    }
+}
+
+impl<'a, K, V, M> IntoIterator for &'a StableMap<K, V, M>
+where
+    K: Eq + Hash + Clone + CandidType + DeserializeOwned,
+    V: CandidType + DeserializeOwned,
+    M: Memory,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V, M> CandidType for StableMap<K, V, M>
+where
+    K: Eq + Hash + CandidType,
+    V: CandidType,
+    M: Memory,
+{
+    fn _ty() -> Type {
+        HashMap::<K, V>::ty()
+    }
+
+    // Encoded like a map, as a vector of key-value records
+    fn idl_serialize<S: Serializer>(&self, serializer: S) -> Result<(), S::Error> {
+        let entries: Vec<(&K, &V)> = if self.stable.is_some() {
+            Vec::new()
+        } else {
+            self.slots.iter().map(|(key, value)| (key, value)).collect()
+        };
+        entries.idl_serialize(serializer)
+    }
+}
+
+impl<'de, K, V, M> Deserialize<'de> for StableMap<K, V, M>
+where
+    K: Eq + Hash + Clone + Deserialize<'de>,
+    V: Deserialize<'de>,
+    M: Memory,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map = StableMap::default();
+        for (key, value) in HashMap::<K, V>::deserialize(deserializer)? {
+            map.index.borrow_mut().insert(key.clone(), map.slots.len());
+            map.slots.push(Box::new((key, value)));
+        }
+        Ok(map)
+    }
+}
+
+// Mutable access to a value; a change is written to stable memory when the guard is dropped
+pub struct ValueMut<'a, K, V, M>
+where
+    K: CandidType,
+    V: CandidType,
+    M: Memory,
+{
+    key: &'a K,
+    value: &'a mut V,
+    stable: Option<&'a Entries<M>>,
+    changed: bool,
+}
+
+impl<'a, K: CandidType, V: CandidType, M: Memory> ValueMut<'a, K, V, M> {
+    fn new(key: &'a K, value: &'a mut V, stable: Option<&'a Entries<M>>) -> Self {
+        ValueMut { key, value, stable, changed: false }
+    }
+}
+
+impl<K: CandidType, V: CandidType, M: Memory> Deref for ValueMut<'_, K, V, M> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.value
+    }
+}
+
+impl<K: CandidType, V: CandidType, M: Memory> DerefMut for ValueMut<'_, K, V, M> {
+    fn deref_mut(&mut self) -> &mut V {
+        self.changed = true;
+        self.value
+    }
+}
+
+impl<K: CandidType, V: CandidType, M: Memory> Drop for ValueMut<'_, K, V, M> {
+    fn drop(&mut self) {
+        if let Some(stable) = self.stable.filter(|_| self.changed) {
+            stable.borrow_mut().insert(encode(self.key), encode(&*self.value));
+        }
+    }
+}
+
+pub struct Entry<'a, K, V, M: Memory> {
+    map: &'a mut StableMap<K, V, M>,
+    key: K,
+}
+
+impl<'a, K, V, M> Entry<'a, K, V, M>
+where
+    K: Eq + Hash + Clone + CandidType + DeserializeOwned,
+    V: CandidType + DeserializeOwned,
+    M: Memory,
+{
+    pub fn or_insert_with(self, default: impl FnOnce() -> V) -> ValueMut<'a, K, V, M> {
+        let Entry { map, key } = self;
+        if !map.contains_key(&key) {
+            map.insert(key.clone(), default());
+        }
+        map.get_mut(&key).expect("entry was just inserted")
+    }
+
+    pub fn or_default(self) -> ValueMut<'a, K, V, M>
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_stable_structures::VectorMemory;
+
+    fn sorted(map: &StableMap<String, Vec<u32>, VectorMemory>) -> Vec<(String, Vec<u32>)> {
+        let mut entries: Vec<(String, Vec<u32>)> = map.iter().map(|(key, value)| (key.clone(), value.clone())).collect();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn test_stable_map() {
+        let memory = VectorMemory::default();
+        let mut map: StableMap<String, Vec<u32>, VectorMemory> = StableMap::default();
+        map.insert("heap".to_string(), vec![1]);
+
+        // Attaching an empty memory moves the heap entries into it
+        map.attach(memory.clone());
+        map.insert("a".to_string(), vec![2]);
+        map.get_mut("a").unwrap().push(3);
+        map.entry("b".to_string()).or_default().push(4);
+        map.values_mut().filter(|value| value.contains(&1)).for_each(|mut value| value.push(5));
+        map.remove("b");
+        // Reading through a guard writes nothing back
+        assert_eq!(map.get_mut("a").map(|value| value.len()), Some(2));
+
+        // A map attached to the same memory reads entries through on use
+        let mut restored: StableMap<String, Vec<u32>, VectorMemory> = StableMap::default();
+        restored.attach(memory.clone());
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored.get("a"), Some(&vec![2, 3]));
+        assert!(restored.contains_key("heap") && !restored.contains_key("b"));
+        // Key batches resume after the last key without having cached anything
+        let first = restored.keys_after(None, 1);
+        let rest = restored.keys_after(first.last(), 10);
+        assert_eq!(first.len() + rest.len(), 2);
+        assert!(!rest.contains(&first[0]));
+        restored.get_mut("heap").unwrap().push(6);
+        assert_eq!(restored.remove("a"), Some(vec![2, 3]));
+
+        // Batches fill the rest of the cache
+        let mut warmed: StableMap<String, Vec<u32>, VectorMemory> = StableMap::default();
+        warmed.attach(memory);
+        assert!(!warmed.load_batch(1));
+        assert!(warmed.load_batch(1));
+        assert_eq!(sorted(&warmed), vec![("heap".to_string(), vec![1, 5, 6])]);
+
+        // Attached maps serialize as empty, heap-only maps with their entries
+        assert!(candid::decode_one::<HashMap<String, Vec<u32>>>(&candid::encode_one(&warmed).unwrap()).unwrap().is_empty());
+        let heap_only: StableMap<String, Vec<u32>, VectorMemory> = candid::decode_one(&candid::encode_one(HashMap::from([("c".to_string(), vec![7u32])])).unwrap()).unwrap();
+        assert_eq!(candid::decode_one::<HashMap<String, Vec<u32>>>(&candid::encode_one(&heap_only).unwrap()).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,130 @@
+// Timestamp conventions
+// Every timestamp stored or returned by the canister is milliseconds since the Unix epoch.
+// `ic_cdk::api::time()` returns nanoseconds, so always go through `now_millis()`.
+use ic_cdk::api::time;
+
+/// Milliseconds since the Unix epoch
+pub type TimestampMillis = u64;
+
+pub const MILLIS_PER_SECOND: u64 = 1_000;
+pub const MILLIS_PER_HOUR: u64 = 60 * 60 * MILLIS_PER_SECOND;
+pub const MILLIS_PER_DAY: u64 = 24 * MILLIS_PER_HOUR;
+pub const MILLIS_PER_WEEK: u64 = 7 * MILLIS_PER_DAY;
+pub const MILLIS_PER_MINUTE: u64 = 60 * MILLIS_PER_SECOND;
+
+// Values above this are nanoseconds (ms timestamps stay below it until the year 2286)
+const NANOS_THRESHOLD: u64 = 10_000_000_000_000;
+// Values below this are seconds (ms timestamps passed it in 1973)
+const SECONDS_THRESHOLD: u64 = 100_000_000_000;
+
+/// Current canister time in milliseconds
+pub fn now_millis() -> TimestampMillis {
+    time() / 1_000_000
+}
+
+/// Start of the UTC day containing `timestamp`
+pub fn day_start(timestamp: TimestampMillis) -> TimestampMillis {
+    timestamp - (timestamp % MILLIS_PER_DAY)
+}
+
+/// Start of the local day containing `timestamp` for a fixed UTC offset in minutes,
+/// expressed in UTC milliseconds
+pub fn local_day_start(timestamp: TimestampMillis, offset_minutes: i32) -> TimestampMillis {
+    let offset = offset_minutes as i64 * MILLIS_PER_MINUTE as i64;
+    let local = timestamp as i64 + offset;
+    (local - local.rem_euclid(MILLIS_PER_DAY as i64) - offset).max(0) as TimestampMillis
+}
+
+/// Local calendar date of `timestamp` for a fixed UTC offset in minutes, as the UTC midnight
+/// starting that date
+pub fn local_date(timestamp: TimestampMillis, offset_minutes: i32) -> TimestampMillis {
+    let local = timestamp as i64 + offset_minutes as i64 * MILLIS_PER_MINUTE as i64;
+    day_start(local.max(0) as TimestampMillis)
+}
+
+/// UTC calendar date (year, month 1-12, day 1-31) of `timestamp`
+pub fn civil_date(timestamp: TimestampMillis) -> (u64, u32, u32) {
+    // Days-to-civil conversion over 400-year eras, with years starting in March
+    let days = timestamp / MILLIS_PER_DAY + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// UTC day of the week of `timestamp`, 0 for Monday through 6 for Sunday
+pub fn weekday(timestamp: TimestampMillis) -> u8 {
+    // The epoch was a Thursday
+    ((timestamp / MILLIS_PER_DAY + 3) % 7) as u8
+}
+
+/// Convert a legacy timestamp of unknown unit (seconds, milliseconds or nanoseconds) to milliseconds.
+/// Zero is kept as-is since it is used as "never".
+pub fn normalize_to_millis(timestamp: u64) -> TimestampMillis {
+    if timestamp == 0 {
+        0
+    } else if timestamp >= NANOS_THRESHOLD {
+        timestamp / 1_000_000
+    } else if timestamp < SECONDS_THRESHOLD {
+        timestamp * MILLIS_PER_SECOND
+    } else {
+        timestamp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_to_millis() {
+        let millis = 1_700_000_000_000;
+        assert_eq!(normalize_to_millis(millis), millis);
+        assert_eq!(normalize_to_millis(millis * 1_000_000), millis);
+        assert_eq!(normalize_to_millis(millis / 1_000), millis);
+        assert_eq!(normalize_to_millis(0), 0);
+    }
+
+    #[test]
+    fn test_day_start() {
+        let timestamp = 3 * MILLIS_PER_DAY + 5 * MILLIS_PER_HOUR;
+        assert_eq!(day_start(timestamp), 3 * MILLIS_PER_DAY);
+    }
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(weekday(0), 3);
+        // 2024-02-29, a Thursday
+        let leap_day = 19_782 * MILLIS_PER_DAY + 5 * MILLIS_PER_HOUR;
+        assert_eq!(civil_date(leap_day), (2024, 2, 29));
+        assert_eq!(civil_date(leap_day + MILLIS_PER_DAY), (2024, 3, 1));
+        assert_eq!(weekday(leap_day), 3);
+        assert_eq!(civil_date(20_088 * MILLIS_PER_DAY), (2024, 12, 31));
+    }
+
+    #[test]
+    fn test_local_day_start() {
+        // 23:00 UTC on day 3 is already day 4 in UTC+8
+        let timestamp = 3 * MILLIS_PER_DAY + 23 * MILLIS_PER_HOUR;
+        assert_eq!(local_day_start(timestamp, 0), 3 * MILLIS_PER_DAY);
+        assert_eq!(local_day_start(timestamp, 8 * 60), 4 * MILLIS_PER_DAY - 8 * MILLIS_PER_HOUR);
+        // 02:00 UTC on day 3 is still day 2 in UTC-5
+        let timestamp = 3 * MILLIS_PER_DAY + 2 * MILLIS_PER_HOUR;
+        assert_eq!(local_day_start(timestamp, -5 * 60), 2 * MILLIS_PER_DAY + 5 * MILLIS_PER_HOUR);
+    }
+
+    #[test]
+    fn test_local_date() {
+        let timestamp = 3 * MILLIS_PER_DAY + 23 * MILLIS_PER_HOUR;
+        assert_eq!(local_date(timestamp, 0), 3 * MILLIS_PER_DAY);
+        assert_eq!(local_date(timestamp, 8 * 60), 4 * MILLIS_PER_DAY);
+        let timestamp = 3 * MILLIS_PER_DAY + 2 * MILLIS_PER_HOUR;
+        assert_eq!(local_date(timestamp, -5 * 60), 2 * MILLIS_PER_DAY);
+    }
+}
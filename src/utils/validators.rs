@@ -0,0 +1,442 @@
+// Request validation
+// Collects every field-level violation so callers can report them all at once
+use regex::Regex;
+use lazy_static::lazy_static;
+
+use crate::models::content::*;
+use crate::models::creator::MAX_ARTICLE_LENGTH;
+use crate::models::discovery::{FeedComposition, MAX_FEED_COMPOSITION_WEIGHT};
+use crate::models::error::{FieldError, SquareResult};
+use crate::models::legal_hold::*;
+use crate::models::notification::{NotificationRetentionSettings, MAX_NOTIFICATIONS_PER_USER_LIMIT};
+use crate::models::quiz::*;
+use crate::models::reward::{PointsExpirySettings, MAX_POINTS_EXPIRY_DAYS};
+use crate::models::tag::*;
+use crate::models::shard::*;
+use crate::models::syndication::*;
+use crate::models::task_registry::*;
+use crate::models::task_template::*;
+use crate::models::treasury::*;
+use crate::models::user::*;
+use crate::utils::content_utils::calculate_content_length_excluding_base64_and_html;
+use crate::utils::error_handler::*;
+
+lazy_static! {
+    static ref HANDLE_REGEX: Regex = Regex::new(HANDLE_PATTERN).unwrap();
+    static ref TASK_TEMPLATE_ID_REGEX: Regex = Regex::new(r"^[a-z0-9_]+$").unwrap();
+}
+
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Record a violation when `ok` is false
+    pub fn check(&mut self, field: &str, ok: bool, message: impl Into<String>) -> &mut Self {
+        if !ok {
+            self.errors.push(FieldError {
+                field: field.to_string(),
+                message: message.into(),
+            });
+        }
+        self
+    }
+
+    pub fn length_between(&mut self, field: &str, value: &str, min: usize, max: usize) -> &mut Self {
+        let len = value.chars().count();
+        self.check(
+            field,
+            len >= min && len <= max,
+            format!("must be between {} and {} characters", min, max)
+        )
+    }
+
+    pub fn max_length(&mut self, field: &str, length: usize, max: usize) -> &mut Self {
+        self.check(
+            field,
+            length <= max,
+            format!("exceeds maximum length of {} characters ({} given)", max, length)
+        )
+    }
+
+    pub fn max_count(&mut self, field: &str, count: usize, max: usize) -> &mut Self {
+        self.check(
+            field,
+            count <= max,
+            format!("too many entries. Maximum allowed is {}", max)
+        )
+    }
+
+    pub fn matches(&mut self, field: &str, value: &str, regex: &Regex, message: &str) -> &mut Self {
+        self.check(field, regex.is_match(value), message)
+    }
+
+    pub fn errors(&self) -> &[FieldError] {
+        &self.errors
+    }
+
+    // Turn collected violations into a single validation error carrying every field error
+    pub fn finish(&self, module: &str, function: &str) -> SquareResult<()> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+
+        let summary = self.errors
+            .iter()
+            .map(|error| format!("{} {}", error.field, error.message))
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        let error = self.errors.iter().fold(
+            validation_error(&summary, module, function),
+            |error, field_error| error.with_field_error(field_error.field.clone(), field_error.message.clone())
+        );
+
+        log_and_return(error)
+    }
+}
+
+const HANDLE_FORMAT_MESSAGE: &str = "must be 3-30 characters and can only contain letters, numbers, and underscores";
+
+pub fn validate_register_user(request: &RegisterUserRequest) -> Validator {
+    let mut validator = Validator::new();
+    validator
+        .length_between("username", &request.username, MIN_USERNAME_LENGTH, MAX_USERNAME_LENGTH)
+        .matches("handle", &request.handle, &HANDLE_REGEX, HANDLE_FORMAT_MESSAGE)
+        .max_length("bio", request.bio.chars().count(), MAX_BIO_LENGTH);
+    validator
+}
+
+pub fn validate_update_profile(request: &UpdateProfileRequest) -> Validator {
+    let mut validator = Validator::new();
+    if let Some(username) = &request.username {
+        validator.length_between("username", username, MIN_USERNAME_LENGTH, MAX_USERNAME_LENGTH);
+    }
+    if let Some(handle) = &request.handle {
+        validator.matches("handle", handle, &HANDLE_REGEX, HANDLE_FORMAT_MESSAGE);
+    }
+    if let Some(bio) = &request.bio {
+        validator.max_length("bio", bio.chars().count(), MAX_BIO_LENGTH);
+    }
+    validator
+}
+
+// `is_article` raises the content limit for creator articles
+fn validate_post_fields(
+    validator: &mut Validator,
+    content: &str,
+    is_article: bool,
+    hashtags: Option<&Vec<String>>,
+    token_mentions: Option<&Vec<String>>,
+    media_urls: Option<&Vec<String>>
+) {
+    let max_length = if is_article { MAX_ARTICLE_LENGTH } else { MAX_POST_LENGTH };
+    validator.max_length("content", calculate_content_length_excluding_base64_and_html(content), max_length);
+    if let Some(hashtags) = hashtags {
+        validator.max_count("hashtags", hashtags.len(), MAX_HASHTAGS);
+    }
+    if let Some(token_mentions) = token_mentions {
+        validator.max_count("token_mentions", token_mentions.len(), MAX_TOKEN_MENTIONS);
+    }
+    if let Some(media_urls) = media_urls {
+        validator.max_count("media_urls", media_urls.len(), MAX_MEDIA_URLS);
+    }
+}
+
+// Cover images and thumbnails are linked, not uploaded, so feeds stay small
+fn validate_post_images(validator: &mut Validator, cover_image: Option<&str>, thumbnail: Option<&str>) {
+    for (field, url) in [("cover_image", cover_image), ("thumbnail", thumbnail)] {
+        let Some(url) = url else {
+            continue;
+        };
+        validator.check(
+            field,
+            url.starts_with("https://") && url.len() <= MAX_IMAGE_URL_LENGTH,
+            format!("must be an https:// URL of at most {} characters", MAX_IMAGE_URL_LENGTH)
+        );
+    }
+}
+
+pub fn validate_create_post(request: &CreatePostRequest) -> Validator {
+    let mut validator = Validator::new();
+    validate_post_fields(
+        &mut validator,
+        &request.content,
+        request.title.is_some(),
+        Some(&request.hashtags),
+        request.token_mentions.as_ref(),
+        Some(&request.media_urls)
+    );
+    validate_post_images(&mut validator, request.cover_image.as_deref(), request.thumbnail.as_deref());
+    validator
+}
+
+pub fn validate_update_post(request: &UpdatePostRequest, is_article: bool) -> Validator {
+    let mut validator = Validator::new();
+    validate_post_fields(
+        &mut validator,
+        &request.content,
+        is_article || request.title.is_some(),
+        request.hashtags.as_ref(),
+        request.token_mentions.as_ref(),
+        request.media_urls.as_ref()
+    );
+    // An empty string removes the image
+    validate_post_images(
+        &mut validator,
+        request.cover_image.as_deref().filter(|url| !url.is_empty()),
+        request.thumbnail.as_deref().filter(|url| !url.is_empty())
+    );
+    validator
+}
+
+pub fn validate_comment_content(content: &str) -> Validator {
+    let mut validator = Validator::new();
+    validator.max_length("content", calculate_content_length_excluding_base64_and_html(content), MAX_COMMENT_LENGTH);
+    validator
+}
+
+pub fn validate_register_task_canister(request: &RegisterTaskCanisterRequest) -> Validator {
+    let mut validator = Validator::new();
+    validator
+        .length_between("name", &request.name, 1, MAX_TASK_CANISTER_NAME_LENGTH)
+        .max_length("description", request.description.chars().count(), MAX_TASK_CANISTER_DESCRIPTION_LENGTH)
+        .check("max_points", request.min_points <= request.max_points, "must be greater than or equal to min_points");
+    validator
+}
+
+pub fn validate_update_task_canister(request: &UpdateTaskCanisterRequest, current: &TaskCanisterRegistration) -> Validator {
+    let mut validator = Validator::new();
+    if let Some(name) = &request.name {
+        validator.length_between("name", name, 1, MAX_TASK_CANISTER_NAME_LENGTH);
+    }
+    if let Some(description) = &request.description {
+        validator.max_length("description", description.chars().count(), MAX_TASK_CANISTER_DESCRIPTION_LENGTH);
+    }
+    let min_points = request.min_points.unwrap_or(current.min_points);
+    let max_points = request.max_points.unwrap_or(current.max_points);
+    validator.check("max_points", min_points <= max_points, "must be greater than or equal to min_points");
+    validator
+}
+
+pub fn validate_register_content_shard(request: &RegisterContentShardRequest) -> Validator {
+    let mut validator = Validator::new();
+    validator.length_between("name", &request.name, 1, MAX_CONTENT_SHARD_NAME_LENGTH);
+    validator
+}
+
+pub fn validate_register_syndication_partner(request: &RegisterSyndicationPartnerRequest) -> Validator {
+    let mut validator = Validator::new();
+    validator
+        .length_between("name", &request.name, 1, MAX_SYNDICATION_PARTNER_NAME_LENGTH)
+        .length_between("attribution_text", &request.attribution_text, 1, MAX_ATTRIBUTION_TEXT_LENGTH)
+        .check("requests_per_hour", request.requests_per_hour != Some(0), "must be greater than 0");
+    validator
+}
+
+pub fn validate_create_quiz(request: &CreateQuizRequest) -> Validator {
+    let mut validator = Validator::new();
+    validator
+        .length_between("title", &request.title, 1, MAX_QUIZ_TEXT_LENGTH)
+        .check("questions", !request.questions.is_empty(), "must contain at least one question")
+        .max_count("questions", request.questions.len(), MAX_QUIZ_QUESTIONS)
+        .check(
+            "points_per_correct_answer",
+            (1..=MAX_QUIZ_POINTS_PER_ANSWER).contains(&request.points_per_correct_answer),
+            format!("must be between 1 and {}", MAX_QUIZ_POINTS_PER_ANSWER),
+        )
+        .check(
+            "min_correct_to_pass",
+            request.min_correct_to_pass as usize <= request.questions.len(),
+            "must not exceed the number of questions",
+        );
+    for (index, question) in request.questions.iter().enumerate() {
+        let field = format!("questions[{}]", index);
+        validator
+            .length_between(&format!("{}.question", field), &question.question, 1, MAX_QUIZ_TEXT_LENGTH)
+            .check(
+                &format!("{}.options", field),
+                (MIN_QUIZ_OPTIONS..=MAX_QUIZ_OPTIONS).contains(&question.options.len()),
+                format!("must contain between {} and {} options", MIN_QUIZ_OPTIONS, MAX_QUIZ_OPTIONS),
+            )
+            .check(
+                &format!("{}.options", field),
+                question.options.iter().all(|option| (1..=MAX_QUIZ_TEXT_LENGTH).contains(&option.chars().count())),
+                format!("each option must be between 1 and {} characters", MAX_QUIZ_TEXT_LENGTH),
+            )
+            .check(
+                &format!("{}.correct_option", field),
+                (question.correct_option as usize) < question.options.len(),
+                "must reference one of the options",
+            );
+    }
+    validator
+}
+
+pub fn validate_update_tag(request: &UpdateTagRequest) -> Validator {
+    let mut validator = Validator::new();
+    if let Some(description) = &request.description {
+        validator.max_length("description", description.chars().count(), MAX_TAG_DESCRIPTION_LENGTH);
+    }
+    if let Some(cover_image) = &request.cover_image {
+        validator.max_length("cover_image", cover_image.chars().count(), MAX_TAG_COVER_IMAGE_LENGTH);
+    }
+    if let Some(rules) = &request.rules {
+        validator
+            .max_count("rules", rules.len(), MAX_TAG_RULES)
+            .check(
+                "rules",
+                rules.iter().all(|rule| (1..=MAX_TAG_RULE_LENGTH).contains(&rule.chars().count())),
+                format!("each rule must be between 1 and {} characters", MAX_TAG_RULE_LENGTH),
+            );
+    }
+    validator
+}
+
+fn validate_recurrence(validator: &mut Validator, recurrence: &TaskRecurrence) {
+    match recurrence {
+        TaskRecurrence::Daily => {}
+        TaskRecurrence::Weekly { weekday } => {
+            validator.check("recurrence.weekday", *weekday <= 6, "must be between 0 (Monday) and 6 (Sunday)");
+        }
+        TaskRecurrence::Cron { weekdays, month_days } => {
+            validator
+                .check("recurrence.weekdays", weekdays.iter().all(|day| *day <= 6), "must be between 0 (Monday) and 6 (Sunday)")
+                .check("recurrence.month_days", month_days.iter().all(|day| (1..=31).contains(day)), "must be between 1 and 31");
+        }
+    }
+}
+
+pub fn validate_create_task_template(request: &CreateTaskTemplateRequest) -> Validator {
+    let mut validator = Validator::new();
+    validator
+        .length_between("id", &request.id, 1, MAX_TASK_TEMPLATE_ID_LENGTH)
+        .matches("id", &request.id, &TASK_TEMPLATE_ID_REGEX, "may only contain lowercase letters, digits and underscores")
+        .length_between("title", &request.title, 1, MAX_TASK_TEMPLATE_TITLE_LENGTH)
+        .max_length("description", request.description.chars().count(), MAX_TASK_TEMPLATE_DESCRIPTION_LENGTH)
+        .check("points", request.points > 0, "must be greater than 0");
+    validate_recurrence(&mut validator, &request.recurrence);
+    validator
+}
+
+pub fn validate_update_task_template(request: &UpdateTaskTemplateRequest) -> Validator {
+    let mut validator = Validator::new();
+    if let Some(title) = &request.title {
+        validator.length_between("title", title, 1, MAX_TASK_TEMPLATE_TITLE_LENGTH);
+    }
+    if let Some(description) = &request.description {
+        validator.max_length("description", description.chars().count(), MAX_TASK_TEMPLATE_DESCRIPTION_LENGTH);
+    }
+    if let Some(points) = request.points {
+        validator.check("points", points > 0, "must be greater than 0");
+    }
+    if let Some(recurrence) = &request.recurrence {
+        validate_recurrence(&mut validator, recurrence);
+    }
+    validator
+}
+
+pub fn validate_points_expiry_settings(settings: &PointsExpirySettings) -> Validator {
+    let mut validator = Validator::new();
+    validator
+        .check(
+            "expiry_days",
+            (1..=MAX_POINTS_EXPIRY_DAYS).contains(&settings.expiry_days),
+            format!("must be between 1 and {}", MAX_POINTS_EXPIRY_DAYS)
+        )
+        .check("warning_days", settings.warning_days < settings.expiry_days, "must be less than expiry_days");
+    validator
+}
+
+pub fn validate_notification_retention(settings: &NotificationRetentionSettings) -> Validator {
+    let mut validator = Validator::new();
+    validator
+        .check(
+            "max_per_user",
+            (1..=MAX_NOTIFICATIONS_PER_USER_LIMIT).contains(&settings.max_per_user),
+            format!("must be between 1 and {}", MAX_NOTIFICATIONS_PER_USER_LIMIT)
+        )
+        .check("max_age_days", settings.max_age_days != Some(0), "must be greater than 0");
+    validator
+}
+
+pub fn validate_feed_composition(composition: &FeedComposition) -> Validator {
+    let weights = [
+        ("followed", composition.followed),
+        ("trending", composition.trending),
+        ("fresh_creators", composition.fresh_creators),
+        ("news", composition.news),
+    ];
+    let mut validator = Validator::new();
+    for (field, weight) in weights {
+        validator.check(field, weight <= MAX_FEED_COMPOSITION_WEIGHT, format!("must be at most {}", MAX_FEED_COMPOSITION_WEIGHT));
+    }
+    validator.check(
+        "composition",
+        composition.trending + composition.fresh_creators + composition.news > 0,
+        "trending, fresh_creators or news must have a weight"
+    );
+    validator
+}
+
+pub fn validate_place_legal_hold(request: &PlaceLegalHoldRequest) -> Validator {
+    let mut validator = Validator::new();
+    validator
+        .max_length("reference", request.reference.as_deref().unwrap_or_default().chars().count(), MAX_LEGAL_HOLD_REFERENCE_LENGTH)
+        .max_length("notes", request.notes.as_deref().unwrap_or_default().chars().count(), MAX_LEGAL_HOLD_NOTES_LENGTH);
+    validator
+}
+
+pub fn validate_set_mint_budget(request: &SetMintBudgetRequest) -> Validator {
+    let mut validator = Validator::new();
+    validator.check(
+        "period_days",
+        (1..=MAX_MINT_BUDGET_PERIOD_DAYS).contains(&request.period_days),
+        format!("must be between 1 and {}", MAX_MINT_BUDGET_PERIOD_DAYS)
+    );
+    validator
+}
+
+pub fn validate_adjust_points(request: &AdjustPointsRequest) -> Validator {
+    let mut validator = Validator::new();
+    validator
+        .check("points", request.points > 0, "must be greater than 0")
+        .length_between("reason", request.reason.trim(), 1, MAX_ADJUSTMENT_REASON_LENGTH);
+    validator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn register_request(username: &str, handle: &str, bio: &str) -> RegisterUserRequest {
+        RegisterUserRequest {
+            username: username.to_string(),
+            handle: handle.to_string(),
+            bio: bio.to_string(),
+            avatar: String::new(),
+            social_links: None,
+            interests: None,
+            invite_code: None,
+        }
+    }
+
+    #[test]
+    fn test_register_user_reports_all_violations() {
+        let long_bio = "a".repeat(MAX_BIO_LENGTH + 1);
+        let validator = validate_register_user(&register_request("ab", "bad handle!", &long_bio));
+        let fields: Vec<&str> = validator.errors().iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, vec!["username", "handle", "bio"]);
+    }
+
+    #[test]
+    fn test_register_user_valid() {
+        let validator = validate_register_user(&register_request("alice", "alice_01", "hello"));
+        assert!(validator.errors().is_empty());
+    }
+}
@@ -464,7 +464,7 @@ test_task_reset() {
     
     local complete_task_request="(
         record {
-            task_id = \"daily_post\";
+            task_id = \"$DAILY_POST_TASK\";
             proof = opt \"$post_id\";
         }
     )"
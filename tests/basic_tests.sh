@@ -19,7 +19,7 @@ test_complete_daily_post_task() {
     local simple_proof="post_simple_$(date +%s)"
     local complete_task_request="(
         record {
-            task_id = \"daily_post\";
+            task_id = \"$DAILY_POST_TASK\";
             proof = opt \"$simple_proof\";
         }
     )"
@@ -56,7 +56,7 @@ test_complete_social_engagement_task() {
     # For social_engagement tasks, we don't need proof as per the reward.rs implementation
     local complete_task_request="(
         record {
-            task_id = \"social_engagement\";
+            task_id = \"$SOCIAL_ENGAGEMENT_TASK\";
             proof = null;
         }
     )"
@@ -88,7 +88,7 @@ test_task_repetition() {
     local simple_proof="post_simple_$(date +%s)"
     local complete_task_request="(
         record {
-            task_id = \"daily_post\";
+            task_id = \"$DAILY_POST_TASK\";
             proof = opt \"$simple_proof\";
         }
     )"
@@ -247,7 +247,7 @@ test_error_handling() {
     
     local complete_task_request="(
         record {
-            task_id = \"daily_post\";
+            task_id = \"$DAILY_POST_TASK\";
             proof = opt \"\";
         }
     )"
@@ -328,7 +328,7 @@ test_multi_user_scenario() {
     # Complete daily_post task
     local complete_task_request="(
         record {
-            task_id = \"daily_post\";
+            task_id = \"$DAILY_POST_TASK\";
             proof = opt \"$post_id\";
         }
     )"
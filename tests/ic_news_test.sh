@@ -156,6 +156,9 @@ ADMIN_IDENTITY="ic-news" # Admin identity (using default user for testing)
 
 # Constants for testing
 SECONDS_IN_DAY=86400 # Seconds in a day for testing task expiration
+# Today's instances of the built-in daily task templates
+DAILY_POST_TASK="daily_post_$(date -u +%Y%m%d)"
+SOCIAL_ENGAGEMENT_TASK="social_engagement_$(date -u +%Y%m%d)"
 
 # Source common functions
 source_dir=$(dirname "$0")
@@ -0,0 +1,143 @@
+// PocketIC harness for the square canister and the daily check-in task canister. Both wasm files
+// have to be built first (see docs/TEST_DOCUMENTATION.md); IC_NEWS_SQUARE_WASM and
+// DAILY_CHECKIN_TASK_WASM override where they are read from.
+use std::path::PathBuf;
+
+use candid::utils::ArgumentEncoder;
+use candid::{decode_one, encode_args, CandidType, Principal};
+use pocket_ic::{PocketIc, PocketIcBuilder};
+use serde::de::DeserializeOwned;
+
+pub mod types;
+
+use types::ApiResponse;
+
+const CANISTER_CYCLES: u128 = 100_000_000_000_000;
+
+fn wasm_path(env_var: &str, default: &str) -> PathBuf {
+    std::env::var_os(env_var)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(default))
+}
+
+fn read_wasm(env_var: &str, default: &str) -> Vec<u8> {
+    let path = wasm_path(env_var, default);
+    std::fs::read(&path).unwrap_or_else(|error| {
+        panic!("Cannot read {} ({}); build the canisters first or set {}", path.display(), error, env_var)
+    })
+}
+
+pub fn square_wasm() -> Vec<u8> {
+    read_wasm("IC_NEWS_SQUARE_WASM", "../../target/wasm32-unknown-unknown/release/ic_news_square.wasm")
+}
+
+pub fn checkin_wasm() -> Vec<u8> {
+    read_wasm(
+        "DAILY_CHECKIN_TASK_WASM",
+        "../../canisters/daily_checkin_task/target/wasm32-unknown-unknown/release/daily_checkin_task.wasm",
+    )
+}
+
+// A test principal distinct for each `id`
+pub fn user(id: u8) -> Principal {
+    Principal::from_slice(&[id, 0xAB, 0xCD, 0x01])
+}
+
+pub struct TestEnv {
+    pub pic: PocketIc,
+    pub admin: Principal,
+    pub square: Principal,
+    pub checkin: Principal,
+}
+
+impl TestEnv {
+    // Both canisters installed by `admin`, which makes it their admin
+    pub fn new() -> Self {
+        let pic = PocketIcBuilder::new().with_application_subnet().build();
+        let admin = user(0);
+        let square = Self::install(&pic, admin, square_wasm());
+        let checkin = Self::install(&pic, admin, checkin_wasm());
+        Self { pic, admin, square, checkin }
+    }
+
+    fn install(pic: &PocketIc, admin: Principal, wasm: Vec<u8>) -> Principal {
+        let canister = pic.create_canister_with_settings(Some(admin), None);
+        pic.add_cycles(canister, CANISTER_CYCLES);
+        pic.install_canister(canister, wasm, encode_args(()).unwrap(), Some(admin));
+        canister
+    }
+
+    pub fn upgrade_square(&self) {
+        self.pic
+            .upgrade_canister(self.square, square_wasm(), encode_args(()).unwrap(), Some(self.admin))
+            .expect("square upgrade failed");
+    }
+
+    pub fn upgrade_checkin(&self) {
+        self.pic
+            .upgrade_canister(self.checkin, checkin_wasm(), encode_args(()).unwrap(), Some(self.admin))
+            .expect("daily check-in upgrade failed");
+    }
+
+    pub fn update<T: CandidType + DeserializeOwned>(
+        &self,
+        canister: Principal,
+        sender: Principal,
+        method: &str,
+        args: impl ArgumentEncoder,
+    ) -> T {
+        let reply = self.pic
+            .update_call(canister, sender, method, encode_args(args).unwrap())
+            .unwrap_or_else(|reject| panic!("{} rejected: {:?}", method, reject));
+        decode_one(&reply).unwrap_or_else(|error| panic!("Cannot decode {} reply: {}", method, error))
+    }
+
+    pub fn query<T: CandidType + DeserializeOwned>(
+        &self,
+        canister: Principal,
+        sender: Principal,
+        method: &str,
+        args: impl ArgumentEncoder,
+    ) -> T {
+        let reply = self.pic
+            .query_call(canister, sender, method, encode_args(args).unwrap())
+            .unwrap_or_else(|reject| panic!("{} rejected: {:?}", method, reject));
+        decode_one(&reply).unwrap_or_else(|error| panic!("Cannot decode {} reply: {}", method, error))
+    }
+
+    // Square calls answer with an `ApiResponse`; these unwrap it and fail the test on an error
+    pub fn square_update<T: CandidType + DeserializeOwned>(&self, sender: Principal, method: &str, args: impl ArgumentEncoder) -> T {
+        self.update::<ApiResponse<T>>(self.square, sender, method, args).into_data(method)
+    }
+
+    pub fn square_query<T: CandidType + DeserializeOwned>(&self, sender: Principal, method: &str, args: impl ArgumentEncoder) -> T {
+        self.query::<ApiResponse<T>>(self.square, sender, method, args).into_data(method)
+    }
+
+    // For calls expected to fail; returns the error message
+    pub fn square_update_error(&self, sender: Principal, method: &str, args: impl ArgumentEncoder) -> String {
+        let response = self.update::<ApiResponse<candid::Reserved>>(self.square, sender, method, args);
+        assert!(!response.success, "{} unexpectedly succeeded", method);
+        response.error.map(|error| error.message).unwrap_or_default()
+    }
+
+    pub fn register_user(&self, sender: Principal, username: &str) {
+        let request = types::RegisterUserRequest {
+            username: username.to_string(),
+            handle: username.to_lowercase(),
+            bio: format!("{} writes here", username),
+            avatar: String::new(),
+            social_links: None,
+            interests: Some(vec!["defi".to_string()]),
+            invite_code: None,
+        };
+        let response = self.update::<ApiResponse<()>>(self.square, sender, "register_user", (request,));
+        response.into_data("register_user");
+    }
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
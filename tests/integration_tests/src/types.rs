@@ -0,0 +1,255 @@
+// Candid types mirrored from the canister interfaces, keeping only the fields the tests read.
+// Requests carry every field the canister expects.
+use candid::{CandidType, Deserialize, Principal};
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct ApiError {
+    pub message: String,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub error: Option<ApiError>,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn into_data(self, method: &str) -> T {
+        if !self.success {
+            panic!("{} failed: {:?}", method, self.error.map(|error| error.message));
+        }
+        self.data.unwrap_or_else(|| panic!("{} returned no data", method))
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ParentType {
+    Post,
+    Comment,
+}
+
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ContentFormat {
+    Plain,
+    Markdown,
+    Html,
+}
+
+#[derive(CandidType, Deserialize)]
+pub struct PaginationParams {
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+// Square requests
+#[derive(CandidType, Deserialize)]
+pub struct RegisterUserRequest {
+    pub username: String,
+    pub handle: String,
+    pub bio: String,
+    pub avatar: String,
+    pub social_links: Option<Vec<(String, String)>>,
+    pub interests: Option<Vec<String>>,
+    pub invite_code: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Default)]
+pub struct CreatePostRequest {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub content: String,
+    pub hashtags: Vec<String>,
+    pub media_urls: Vec<String>,
+    pub tags: Option<Vec<String>>,
+    pub token_mentions: Option<Vec<String>>,
+    pub mentions: Option<Vec<String>>,
+    pub is_nsfw: Option<bool>,
+    pub is_premium: Option<bool>,
+    pub format: Option<ContentFormat>,
+}
+
+#[derive(CandidType, Deserialize)]
+pub struct CreateCommentRequest {
+    pub id: Option<String>,
+    pub content: String,
+    pub parent_id: String,
+    pub parent_type: ParentType,
+    pub format: Option<ContentFormat>,
+}
+
+#[derive(CandidType, Deserialize)]
+pub struct LikeContentRequest {
+    pub content_id: String,
+    pub content_type: ParentType,
+}
+
+#[derive(CandidType, Deserialize)]
+pub struct CompleteTaskRequest {
+    pub task_id: String,
+    pub proof: Option<String>,
+}
+
+#[derive(CandidType, Deserialize)]
+pub enum TaskType {
+    Daily,
+    Weekly,
+    Monthly,
+    OneTime,
+    Special,
+}
+
+#[derive(CandidType, Deserialize)]
+pub enum TaskAudience {
+    NewUsers,
+    Creators,
+    InactiveUsers,
+    TagFollowers(String),
+}
+
+#[derive(CandidType, Deserialize)]
+pub struct CreateTaskRequest {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub points_reward: u64,
+    pub task_type: TaskType,
+    pub canister_id: Principal,
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+    pub completion_criteria: String,
+    pub requirements: Option<candid::Reserved>,
+    pub audience: Option<Vec<TaskAudience>>,
+}
+
+#[derive(CandidType, Deserialize)]
+pub struct FollowTopicRequest {
+    pub topic: String,
+}
+
+#[derive(CandidType, Deserialize)]
+pub enum TaskCanisterKind {
+    CheckIn,
+    Quiz,
+    Referral,
+    Other(String),
+}
+
+#[derive(CandidType, Deserialize)]
+pub struct RegisterTaskCanisterRequest {
+    pub canister_id: Principal,
+    pub name: String,
+    pub description: String,
+    pub kind: TaskCanisterKind,
+    pub min_points: u64,
+    pub max_points: u64,
+}
+
+// Square responses
+#[derive(CandidType, Deserialize, Debug)]
+pub struct UserProfileResponse {
+    pub principal: Principal,
+    pub username: String,
+    pub handle: String,
+    pub bio: String,
+    pub interests: Vec<String>,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct PostResponse {
+    pub id: String,
+    pub author: Principal,
+    pub content: String,
+    pub hashtags: Vec<String>,
+    pub content_hash: Option<String>,
+    pub likes_count: u64,
+    pub comments_count: u64,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct CommentResponse {
+    pub id: String,
+    pub author: Principal,
+    pub content: String,
+    pub parent_id: String,
+    pub parent_type: ParentType,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct CommentsResponse {
+    pub comments: Vec<CommentResponse>,
+    pub total: u64,
+    pub has_more: bool,
+    pub next_offset: u64,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct LikeState {
+    pub liked: bool,
+    pub like_count: u64,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct LikesResponse {
+    pub content_id: String,
+    pub total: u64,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct TaskResponse {
+    pub id: String,
+    pub title: String,
+    pub canister_id: Principal,
+    pub points: u64,
+    pub is_completed: bool,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct TaskCompletionResponse {
+    pub success: bool,
+    pub points_earned: u64,
+    pub total_points: u64,
+    pub message: String,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct TaskCanisterSyncResult {
+    pub canister_id: Principal,
+    pub tasks_synced: u64,
+    pub tasks_rejected: u64,
+    pub error: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Debug, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Nat(u64),
+    Float(f64),
+    Text(String),
+    Bool(bool),
+    Blob(Vec<u8>),
+    Array(Vec<Value>),
+    Map(Vec<(String, Value)>),
+    Principal(Principal),
+    Null,
+}
+
+// Daily check-in canister
+#[derive(CandidType, Deserialize)]
+pub struct TaskConfig {
+    pub title: String,
+    pub description: String,
+    pub base_points: u64,
+    pub max_consecutive_bonus_days: u64,
+    pub consecutive_days_bonus_multiplier: u64,
+    pub enabled: bool,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct DailyCheckInResponse {
+    pub success: bool,
+    pub points_earned: u64,
+    pub consecutive_days: u64,
+    pub bonus_points: u64,
+    pub total_points: u64,
+}
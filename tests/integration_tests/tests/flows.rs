@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use candid::Principal;
+use integration_tests::types::*;
+use integration_tests::{user, TestEnv};
+
+fn create_post(env: &TestEnv, author: Principal, content: &str) -> PostResponse {
+    let request = CreatePostRequest {
+        content: content.to_string(),
+        hashtags: vec!["icp".to_string()],
+        ..Default::default()
+    };
+    env.square_update(author, "create_post", (request,))
+}
+
+fn reward_points(env: &TestEnv, principal: Principal) -> u64 {
+    let rewards: HashMap<String, Value> = env.square_query(principal, "get_user_rewards_v2", ());
+    match rewards.get("points") {
+        Some(Value::Nat(points)) => *points,
+        other => panic!("Unexpected points value: {:?}", other),
+    }
+}
+
+#[test]
+fn register_post_comment_like_survive_upgrade() {
+    let env = TestEnv::new();
+    let alice = user(1);
+    let bob = user(2);
+    env.register_user(alice, "Alice");
+    env.register_user(bob, "Bob");
+
+    let post = create_post(&env, alice, "Hello from the square");
+    assert_eq!(post.author, alice);
+    assert!(post.content_hash.is_some());
+
+    let comment: CommentResponse = env.square_update(bob, "create_comment_v2", (CreateCommentRequest {
+        id: None,
+        content: "Welcome!".to_string(),
+        parent_id: post.id.clone(),
+        parent_type: ParentType::Post,
+        format: None,
+    },));
+    assert_eq!(comment.parent_id, post.id);
+
+    let like: LikeState = env.square_update(bob, "like_content_v2", (LikeContentRequest {
+        content_id: post.id.clone(),
+        content_type: ParentType::Post,
+    },));
+    assert!(like.liked);
+    assert_eq!(like.like_count, 1);
+
+    // Registering the same principal twice is rejected
+    let duplicate = RegisterUserRequest {
+        username: "Alice".to_string(),
+        handle: "alice2".to_string(),
+        bio: String::new(),
+        avatar: String::new(),
+        social_links: None,
+        interests: None,
+        invite_code: None,
+    };
+    assert!(!env.square_update_error(alice, "register_user", (duplicate,)).is_empty());
+
+    env.upgrade_square();
+
+    let profile: UserProfileResponse = env.square_query(alice, "get_user_profile", (None::<String>,));
+    assert_eq!(profile.principal, alice);
+    assert_eq!(profile.username, "Alice");
+    assert_eq!(profile.interests, vec!["defi".to_string()]);
+
+    let stored: PostResponse = env.square_query(alice, "get_post_v2", (post.id.clone(),));
+    assert_eq!(stored.content, "Hello from the square");
+    assert_eq!(stored.content_hash, post.content_hash);
+    assert_eq!(stored.likes_count, 1);
+    assert_eq!(stored.comments_count, 1);
+
+    let comments: CommentsResponse = env.square_query(alice, "get_comments_v2", (
+        post.id.clone(),
+        "post".to_string(),
+        PaginationParams { offset: None, limit: None },
+    ));
+    assert_eq!(comments.total, 1);
+    assert_eq!(comments.comments[0].id, comment.id);
+    assert_eq!(comments.comments[0].author, bob);
+
+    let likes: LikesResponse = env.square_query(alice, "get_likes_v2", (post.id.clone(), ParentType::Post));
+    assert_eq!(likes.total, 1);
+}
+
+#[test]
+fn task_completion_and_task_canister_survive_upgrade() {
+    let env = TestEnv::new();
+    let alice = user(1);
+    env.register_user(alice, "Alice");
+    let post = create_post(&env, alice, "My daily post");
+
+    let tasks: Vec<TaskResponse> = env.square_query(alice, "get_available_tasks_v2", ());
+    let daily_post = tasks
+        .iter()
+        .find(|task| task.title == "Daily Post")
+        .expect("the Daily Post task is seeded on install");
+    let completion: TaskCompletionResponse = env.square_update(alice, "complete_task_v2", (CompleteTaskRequest {
+        task_id: daily_post.id.clone(),
+        proof: Some(post.id.clone()),
+    },));
+    assert!(completion.success);
+    assert_eq!(completion.points_earned, daily_post.points);
+    let points = reward_points(&env, alice);
+    assert!(points >= daily_post.points);
+
+    // The check-in canister only offers its task once enabled
+    env.update::<()>(env.checkin, env.admin, "update_task_config", (TaskConfig {
+        title: "Daily Check-in".to_string(),
+        description: "Check in every day".to_string(),
+        base_points: 10,
+        max_consecutive_bonus_days: 7,
+        consecutive_days_bonus_multiplier: 2,
+        enabled: true,
+    },));
+    env.square_update::<candid::Reserved>(env.admin, "register_task_canister", (RegisterTaskCanisterRequest {
+        canister_id: env.checkin,
+        name: "Daily check-in".to_string(),
+        description: "Daily check-in streaks".to_string(),
+        kind: TaskCanisterKind::CheckIn,
+        min_points: 1,
+        max_points: 100,
+    },));
+    let synced: Vec<TaskCanisterSyncResult> = env.square_update(env.admin, "sync_task_canisters", ());
+    let result = synced.iter().find(|result| result.canister_id == env.checkin).unwrap();
+    assert_eq!(result.error, None);
+    assert_eq!(result.tasks_synced, 1);
+
+    let tasks: Vec<TaskResponse> = env.square_query(alice, "get_available_tasks_v2", ());
+    assert!(tasks.iter().any(|task| task.canister_id == env.checkin));
+
+    let check_in: Result<DailyCheckInResponse, String> = env.update(env.checkin, alice, "claim_daily_check_in", ());
+    let check_in = check_in.unwrap();
+    assert!(check_in.success);
+    assert_eq!(check_in.consecutive_days, 1);
+
+    env.upgrade_square();
+    env.upgrade_checkin();
+
+    assert_eq!(reward_points(&env, alice), points);
+    let tasks: Vec<TaskResponse> = env.square_query(alice, "get_available_tasks_v2", ());
+    assert!(tasks.iter().any(|task| task.id == daily_post.id && task.is_completed));
+    assert!(tasks.iter().any(|task| task.canister_id == env.checkin));
+
+    // The check-in made before the upgrade still counts for today
+    let again: Result<DailyCheckInResponse, String> = env.update(env.checkin, alice, "claim_daily_check_in", ());
+    assert!(again.unwrap_err().contains("Already claimed"));
+}
+
+fn create_targeted_task(env: &TestEnv, id: &str, audience: TaskAudience) {
+    env.square_update::<String>(env.admin, "create_task_v2", (CreateTaskRequest {
+        id: id.to_string(),
+        title: id.to_string(),
+        description: "A task for one audience".to_string(),
+        points_reward: 5,
+        task_type: TaskType::OneTime,
+        canister_id: env.square,
+        start_time: None,
+        end_time: None,
+        completion_criteria: String::new(),
+        requirements: None,
+        audience: Some(vec![audience]),
+    },));
+}
+
+fn sees_task(env: &TestEnv, principal: Principal, task_id: &str) -> bool {
+    let tasks: Vec<TaskResponse> = env.square_query(principal, "get_available_tasks_v2", ());
+    tasks.iter().any(|task| task.id == task_id)
+}
+
+#[test]
+fn targeted_tasks_reach_only_their_audience() {
+    let env = TestEnv::new();
+    let alice = user(1);
+    let bob = user(2);
+    env.register_user(alice, "Alice");
+    env.register_user(bob, "Bob");
+
+    // Tag segments match followed topics whether or not the tag is given with '#'
+    create_targeted_task(&env, "defi_readers", TaskAudience::TagFollowers("DeFi".to_string()));
+    create_targeted_task(&env, "welcome_back", TaskAudience::InactiveUsers);
+    env.square_update::<()>(alice, "follow_topic", (FollowTopicRequest { topic: "#defi".to_string() },));
+    assert!(sees_task(&env, alice, "defi_readers"));
+    assert!(!sees_task(&env, bob, "defi_readers"));
+    assert!(!sees_task(&env, alice, "welcome_back"));
+
+    // Posting counts as activity even without a login call
+    env.pic.advance_time(Duration::from_secs(5 * 24 * 60 * 60));
+    create_post(&env, alice, "Still here");
+    env.pic.advance_time(Duration::from_secs(3 * 24 * 60 * 60));
+    env.pic.tick();
+    assert!(!sees_task(&env, alice, "welcome_back"));
+    assert!(sees_task(&env, bob, "welcome_back"));
+
+    // A returning user keeps seeing the task for a while after coming back
+    create_post(&env, bob, "Back again");
+    assert!(sees_task(&env, bob, "welcome_back"));
+}
@@ -0,0 +1,175 @@
+// Random sequences of post, comment and like operations, checked against a model of what should
+// remain: counts match the comment and like sets, and comment pages list every comment once.
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+use candid::{Principal, Reserved};
+use integration_tests::types::*;
+use integration_tests::{user, TestEnv};
+use proptest::prelude::*;
+
+const USERS: u8 = 3;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Post(u8),
+    Comment(u8, usize),
+    Like(u8, usize),
+    Unlike(u8, usize),
+    DeletePost(usize),
+    DeleteComment(usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let user = 1..=USERS;
+    prop_oneof![
+        user.clone().prop_map(Op::Post),
+        (user.clone(), any::<usize>()).prop_map(|(user, post)| Op::Comment(user, post)),
+        (user.clone(), any::<usize>()).prop_map(|(user, post)| Op::Like(user, post)),
+        (user, any::<usize>()).prop_map(|(user, post)| Op::Unlike(user, post)),
+        any::<usize>().prop_map(Op::DeletePost),
+        any::<usize>().prop_map(Op::DeleteComment),
+    ]
+}
+
+struct ModelPost {
+    id: String,
+    author: Principal,
+    likers: BTreeSet<Principal>,
+    // Comment id -> author
+    comments: HashMap<String, Principal>,
+}
+
+fn like_request(post_id: &str) -> LikeContentRequest {
+    LikeContentRequest {
+        content_id: post_id.to_string(),
+        content_type: ParentType::Post,
+    }
+}
+
+fn apply(env: &TestEnv, posts: &mut Vec<ModelPost>, step: usize, op: Op) {
+    match op {
+        Op::Post(author) => {
+            let request = CreatePostRequest {
+                content: format!("Post number {} of the sequence", step),
+                hashtags: vec!["icp".to_string()],
+                ..Default::default()
+            };
+            let post: PostResponse = env.square_update(user(author), "create_post", (request,));
+            posts.push(ModelPost { id: post.id, author: user(author), likers: BTreeSet::new(), comments: HashMap::new() });
+        }
+        Op::Comment(author, index) if !posts.is_empty() => {
+            let count = posts.len();
+            let post = &mut posts[index % count];
+            let comment: CommentResponse = env.square_update(user(author), "create_comment_v2", (CreateCommentRequest {
+                id: None,
+                content: format!("Comment number {} of the sequence", step),
+                parent_id: post.id.clone(),
+                parent_type: ParentType::Post,
+                format: None,
+            },));
+            post.comments.insert(comment.id, user(author));
+        }
+        Op::Like(liker, index) if !posts.is_empty() => {
+            let count = posts.len();
+            let post = &mut posts[index % count];
+            let state: LikeState = env.square_update(user(liker), "like_content_v2", (like_request(&post.id),));
+            post.likers.insert(user(liker));
+            assert!(state.liked);
+            assert_eq!(state.like_count, post.likers.len() as u64);
+        }
+        Op::Unlike(liker, index) if !posts.is_empty() => {
+            let count = posts.len();
+            let post = &mut posts[index % count];
+            if post.likers.remove(&user(liker)) {
+                let state: LikeState = env.square_update(user(liker), "unlike_content_v2", (like_request(&post.id),));
+                assert!(!state.liked);
+                assert_eq!(state.like_count, post.likers.len() as u64);
+            }
+        }
+        Op::DeletePost(index) if !posts.is_empty() => {
+            let post = posts.remove(index % posts.len());
+            let () = env.square_update(post.author, "delete_post_v2", (post.id.clone(),));
+            let response = env.query::<ApiResponse<Reserved>>(env.square, post.author, "get_post_v2", (post.id,));
+            assert!(!response.success, "a deleted post is still readable");
+        }
+        Op::DeleteComment(index) => {
+            let mut comments: Vec<(usize, String, Principal)> = posts
+                .iter()
+                .enumerate()
+                .flat_map(|(post, model)| model.comments.iter().map(move |(id, author)| (post, id.clone(), *author)))
+                .collect();
+            if comments.is_empty() {
+                return;
+            }
+            comments.sort();
+            let (post, comment_id, author) = comments.swap_remove(index % comments.len());
+            let () = env.square_update(author, "delete_comment_v2", (comment_id.clone(),));
+            posts[post].comments.remove(&comment_id);
+        }
+        // Nothing to act on yet
+        _ => {}
+    }
+}
+
+// Every comment of the post, read a page of `limit` at a time
+fn comment_pages(env: &TestEnv, post_id: &str, limit: u64) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page: CommentsResponse = env.square_query(env.admin, "get_comments_v2", (
+            post_id.to_string(),
+            "post".to_string(),
+            PaginationParams { offset: Some(offset), limit: Some(limit) },
+        ));
+        ids.extend(page.comments.into_iter().map(|comment| comment.id));
+        if !page.has_more {
+            return ids;
+        }
+        assert!(page.next_offset > offset, "paging did not move forward");
+        offset = page.next_offset;
+    }
+}
+
+fn check(env: &TestEnv, posts: &[ModelPost], limit: u64) {
+    for post in posts {
+        let stored: PostResponse = env.square_query(env.admin, "get_post_v2", (post.id.clone(),));
+        assert_eq!(stored.likes_count, post.likers.len() as u64, "likes_count of {}", post.id);
+        assert_eq!(stored.comments_count, post.comments.len() as u64, "comments_count of {}", post.id);
+
+        let likes: LikesResponse = env.square_query(env.admin, "get_likes_v2", (post.id.clone(), ParentType::Post));
+        assert_eq!(likes.total, post.likers.len() as u64, "likes of {}", post.id);
+
+        let mut listed = comment_pages(env, &post.id, limit);
+        let count = listed.len();
+        listed.sort();
+        listed.dedup();
+        assert_eq!(listed.len(), count, "a comment of {} was listed twice", post.id);
+        let mut expected: Vec<String> = post.comments.keys().cloned().collect();
+        expected.sort();
+        assert_eq!(listed, expected, "comments of {}", post.id);
+    }
+}
+
+proptest! {
+    // Every case installs a fresh canister, so keep the count low
+    #![proptest_config(ProptestConfig::with_cases(8))]
+
+    #[test]
+    fn counts_and_pages_follow_the_operations(ops in prop::collection::vec(op(), 1..16), limit in 0u64..4) {
+        let env = TestEnv::new();
+        for id in 1..=USERS {
+            env.register_user(user(id), &format!("User{}", id));
+        }
+        let mut posts = Vec::new();
+        for (step, op) in ops.into_iter().enumerate() {
+            apply(&env, &mut posts, step, op);
+            // Keeps every action inside the hourly limits of new accounts
+            env.pic.advance_time(Duration::from_secs(60 * 60));
+            env.pic.tick();
+        }
+        check(&env, &posts, limit);
+        env.upgrade_square();
+        check(&env, &posts, limit);
+    }
+}